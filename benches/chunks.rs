@@ -0,0 +1,65 @@
+//! 解析、序列化和 CRC 在数 MB PNG 上的吞吐量。
+//!
+//! ```text
+//! cargo bench --bench chunks
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pngme::prelude::*;
+
+/// 每个 IDAT 的数据长度，与常见编码器的分块大小相当
+const IDAT_LENGTH: usize = 256 * 1024;
+
+/// 构造由 IHDR、若干 IDAT 和 IEND 组成、总大小约为 `megabytes` MB 的 PNG
+fn sample_png(megabytes: usize) -> Png {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&1024u32.to_be_bytes());
+    ihdr.extend_from_slice(&1024u32.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut chunks = vec![Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr).unwrap()];
+    // 伪随机字节，避免全零数据让 CRC 的某些实现走捷径
+    let mut state = 0x9e37_79b9_u32;
+    for _ in 0..megabytes * 1024 * 1024 / IDAT_LENGTH {
+        let data = (0..IDAT_LENGTH)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        chunks.push(Chunk::new(ChunkType::from_str("IDAT").unwrap(), data).unwrap());
+    }
+    chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()).unwrap());
+    Png::from_chunks(chunks)
+}
+
+fn bench_chunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("png");
+    for megabytes in [4, 16] {
+        let png = sample_png(megabytes);
+        let bytes = png.as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", megabytes), &bytes, |b, bytes| {
+            b.iter(|| Png::from_bytes_with_limits(black_box(bytes), &Limits::permissive()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", megabytes), &png, |b, png| {
+            b.iter(|| black_box(png).as_bytes())
+        });
+        group.bench_with_input(BenchmarkId::new("crc", megabytes), &png, |b, png| {
+            b.iter(|| {
+                black_box(png)
+                    .chunks()
+                    .iter()
+                    .map(|chunk| Chunk::calculate_crc(chunk.chunk_type(), chunk.data()))
+                    .fold(0, |acc, crc| acc ^ crc)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_chunks);
+criterion_main!(benches);
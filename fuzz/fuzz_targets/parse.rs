@@ -0,0 +1,22 @@
+//! 把任意字节交给 chunk 和 PNG 的解析入口，它们只能返回错误，不能 panic。
+//!
+//! ```text
+//! cargo +nightly fuzz run parse
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pngme::chunk::Chunk;
+use pngme::parse::ParseOptions;
+use pngme::png::Png;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Chunk::try_from(data);
+    let _ = Chunk::from_bytes_unchecked(data);
+    let _ = Png::try_from(data);
+    let _ = Png::from_bytes_unchecked(data);
+    let _ = Png::from_reader(data);
+    let _ = Png::from_bytes_with(data, &ParseOptions::lenient());
+    let _ = pngme::scan::scan_bytes(data);
+});
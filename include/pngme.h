@@ -0,0 +1,93 @@
+#ifndef PNGME_H
+#define PNGME_H
+
+/* 该文件由 cbindgen 生成，不要手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ 函数的返回值
+ */
+typedef enum PngmeStatus {
+  /*
+   成功
+   */
+  PNGME_STATUS_OK = 0,
+  /*
+   参数为空指针或者字符串不是 UTF-8
+   */
+  PNGME_STATUS_INVALID_ARGUMENT = 1,
+  /*
+   pngme 返回了错误，例如文件不是 PNG 或找不到 chunk
+   */
+  PNGME_STATUS_FAILED = 2,
+  /*
+   内部错误（panic）
+   */
+  PNGME_STATUS_PANIC = 3,
+} PngmeStatus;
+
+/*
+ pngme 分配的字节缓冲区，用 `pngme_free` 释放
+ */
+typedef struct PngmeBuffer {
+  uint8_t *data;
+  size_t len;
+} PngmeBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ 在 `png` 中写入 `message`，新的 PNG 文件写入 `out`
+
+ # Safety
+
+ `png` 和 `message` 必须分别指向 `png_len`、`message_len` 个可读字节（长度为 0 时可以为空），
+ `chunk_type` 必须是以 0 结尾的字符串，`out` 必须指向可写的 `PngmeBuffer`。
+ */
+enum PngmeStatus pngme_encode(const uint8_t *png,
+                              size_t png_len,
+                              const char *chunk_type,
+                              const uint8_t *message,
+                              size_t message_len,
+                              struct PngmeBuffer *out);
+
+/*
+ 读取 `png` 中 `chunk_type` 类型的信息，写入 `out`
+
+ # Safety
+
+ `png` 必须指向 `png_len` 个可读字节，`chunk_type` 必须是以 0 结尾的字符串，
+ `out` 必须指向可写的 `PngmeBuffer`。
+ */
+enum PngmeStatus pngme_decode(const uint8_t *png,
+                              size_t png_len,
+                              const char *chunk_type,
+                              struct PngmeBuffer *out);
+
+/*
+ 释放 pngme 返回的缓冲区，`data` 为空时什么也不做
+
+ # Safety
+
+ `buffer` 必须是 `pngme_encode` 或 `pngme_decode` 写入的缓冲区，并且只能释放一次。
+ */
+void pngme_free(struct PngmeBuffer buffer);
+
+/*
+ 当前线程最近一次错误的描述，没有错误时返回空指针。
+ 返回的字符串在当前线程下一次调用 pngme 函数之前有效
+ */
+const char *pngme_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PNGME_H */
@@ -0,0 +1,305 @@
+//! # Apng
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 APNG（动画 PNG）的三种控制 chunk：
+//!
+//! ```text
+//! acTL: | 帧数 (u32) | 播放次数 (u32) |
+//! fcTL: | 序号 (u32) | 宽 | 高 | x 偏移 | y 偏移 (u32) | 延迟分子 | 延迟分母 (u16) | 处置方式 | 混合方式 |
+//! fdAT: | 序号 (u32) | 帧数据 |
+//! ```
+//!
+//! fcTL 和 fdAT 共用一个从 0 开始连续递增的序号。
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::{Error, PngmeError, Result};
+
+/// 动画控制（acTL）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationControl {
+    pub num_frames: u32,
+    /// 播放次数，0 表示无限循环
+    pub num_plays: u32,
+}
+
+/// 渲染下一帧前如何处理当前帧的区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisposeOp {
+    None = 0,
+    Background = 1,
+    Previous = 2,
+}
+
+/// 帧如何与输出缓冲区混合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendOp {
+    Source = 0,
+    Over = 1,
+}
+
+/// 帧控制（fcTL）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameControl {
+    pub sequence_number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub delay_num: u16,
+    /// 延迟的分母，0 按规范视为 100
+    pub delay_den: u16,
+    pub dispose_op: DisposeOp,
+    pub blend_op: BlendOp,
+}
+
+/// 帧数据（fdAT）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameData {
+    pub sequence_number: u32,
+    pub data: Vec<u8>,
+}
+
+impl AnimationControl {
+    pub const CHUNK_TYPE: &'static str = "acTL";
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        let mut data = self.num_frames.to_be_bytes().to_vec();
+        data.extend_from_slice(&self.num_plays.to_be_bytes());
+        new_chunk(Self::CHUNK_TYPE, data).expect("acTL data is 8 bytes")
+    }
+}
+
+impl TryFrom<&Chunk> for AnimationControl {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 8)?;
+        let control = AnimationControl {
+            num_frames: be_u32(data, 0),
+            num_plays: be_u32(data, 4),
+        };
+        if control.num_frames == 0 {
+            return Err(invalid("acTL must declare at least one frame".to_string()));
+        }
+        Ok(control)
+    }
+}
+
+impl FrameControl {
+    pub const CHUNK_TYPE: &'static str = "fcTL";
+
+    /// 帧的显示时长
+    pub fn delay(&self) -> Duration {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        Duration::from_secs_f64(self.delay_num as f64 / den as f64)
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        let mut data = Vec::with_capacity(26);
+        for value in [
+            self.sequence_number,
+            self.width,
+            self.height,
+            self.x_offset,
+            self.y_offset,
+        ] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        data.extend_from_slice(&self.delay_num.to_be_bytes());
+        data.extend_from_slice(&self.delay_den.to_be_bytes());
+        data.push(self.dispose_op as u8);
+        data.push(self.blend_op as u8);
+        new_chunk(Self::CHUNK_TYPE, data).expect("fcTL data is 26 bytes")
+    }
+}
+
+impl TryFrom<&Chunk> for FrameControl {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 26)?;
+        let dispose_op = match data[24] {
+            0 => DisposeOp::None,
+            1 => DisposeOp::Background,
+            2 => DisposeOp::Previous,
+            op => return Err(invalid(format!("unknown dispose op {}", op))),
+        };
+        let blend_op = match data[25] {
+            0 => BlendOp::Source,
+            1 => BlendOp::Over,
+            op => return Err(invalid(format!("unknown blend op {}", op))),
+        };
+        let control = FrameControl {
+            sequence_number: be_u32(data, 0),
+            width: be_u32(data, 4),
+            height: be_u32(data, 8),
+            x_offset: be_u32(data, 12),
+            y_offset: be_u32(data, 16),
+            delay_num: u16::from_be_bytes([data[20], data[21]]),
+            delay_den: u16::from_be_bytes([data[22], data[23]]),
+            dispose_op,
+            blend_op,
+        };
+        if control.width == 0 || control.height == 0 {
+            return Err(invalid(
+                "frame width and height must be non-zero".to_string(),
+            ));
+        }
+        Ok(control)
+    }
+}
+
+impl FrameData {
+    pub const CHUNK_TYPE: &'static str = "fdAT";
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = self.sequence_number.to_be_bytes().to_vec();
+        data.extend_from_slice(&self.data);
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for FrameData {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Self::CHUNK_TYPE)?;
+        if chunk.data().len() < 4 {
+            return Err(invalid("fdAT is missing its sequence number".to_string()));
+        }
+        Ok(FrameData {
+            sequence_number: be_u32(chunk.data(), 0),
+            data: chunk.data()[4..].to_vec(),
+        })
+    }
+}
+
+fn check_type(chunk: &Chunk, chunk_type: &str) -> Result<()> {
+    if chunk.chunk_type().to_string() != chunk_type {
+        return Err(invalid(format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        )));
+    }
+    Ok(())
+}
+
+fn fixed_data<'a>(chunk: &'a Chunk, chunk_type: &str, length: usize) -> Result<&'a [u8]> {
+    check_type(chunk, chunk_type)?;
+    if chunk.data().len() != length {
+        return Err(invalid(format!(
+            "{} must be {} bytes, found {}",
+            chunk_type,
+            length,
+            chunk.data().len()
+        )));
+    }
+    Ok(chunk.data())
+}
+
+fn be_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Result<Chunk> {
+    let chunk_type = ChunkType::from_str(chunk_type).expect("APNG chunk types are valid");
+    Chunk::new(chunk_type, data)
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidAnimation(message)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn frame_control(sequence_number: u32, delay_num: u16) -> FrameControl {
+        FrameControl {
+            sequence_number,
+            width: 2,
+            height: 2,
+            x_offset: 0,
+            y_offset: 0,
+            delay_num,
+            delay_den: 0,
+            dispose_op: DisposeOp::Background,
+            blend_op: BlendOp::Over,
+        }
+    }
+
+    #[test]
+    fn test_animation_control_round_trip() {
+        let control = AnimationControl {
+            num_frames: 3,
+            num_plays: 0,
+        };
+        let chunk = control.to_chunk();
+        assert_eq!(chunk.data(), &[0, 0, 0, 3, 0, 0, 0, 0]);
+        assert_eq!(AnimationControl::try_from(&chunk).unwrap(), control);
+    }
+
+    #[test]
+    fn test_frame_control_round_trip() {
+        let control = frame_control(4, 25);
+        let chunk = control.to_chunk();
+        assert_eq!(chunk.length(), 26);
+        assert_eq!(FrameControl::try_from(&chunk).unwrap(), control);
+    }
+
+    #[test]
+    fn test_frame_delay() {
+        assert_eq!(frame_control(0, 25).delay(), Duration::from_millis(250));
+        let mut control = frame_control(0, 1);
+        control.delay_den = 30;
+        assert_eq!(control.delay().as_millis(), 33);
+    }
+
+    #[test]
+    fn test_frame_data_round_trip() {
+        let data = FrameData {
+            sequence_number: 7,
+            data: vec![1, 2, 3],
+        };
+        let chunk = data.to_chunk().unwrap();
+        assert_eq!(chunk.data(), &[0, 0, 0, 7, 1, 2, 3]);
+        assert_eq!(FrameData::try_from(&chunk).unwrap(), data);
+    }
+
+    #[test]
+    fn test_invalid_chunks() {
+        let mut data = frame_control(0, 1).to_chunk().data().to_vec();
+        data[24] = 3;
+        let chunk = new_chunk("fcTL", data).unwrap();
+        assert!(matches!(
+            FrameControl::try_from(&chunk),
+            Err(PngmeError::InvalidAnimation(_))
+        ));
+
+        let short = new_chunk("acTL", vec![0, 0, 0, 1]).unwrap();
+        assert!(AnimationControl::try_from(&short).is_err());
+        let no_frames = new_chunk("acTL", vec![0; 8]).unwrap();
+        assert!(AnimationControl::try_from(&no_frames).is_err());
+        let no_sequence = new_chunk("fdAT", vec![0, 0]).unwrap();
+        assert!(FrameData::try_from(&no_sequence).is_err());
+        assert!(FrameData::try_from(&short).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! # Archive
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块支持读取和改写 `.zip`（包括 `.apk`、`.aab`、`.ipa`）、`.tar`、`.tar.gz`（`.tgz`）
+//! 压缩包中的 PNG 文件。
+//! 压缩包以流的方式逐个成员读取，不会解压到磁盘；只有文件名以 `.png` 结尾的成员会被解析。
+
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 支持的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// 根据扩展名判断压缩包格式，不是压缩包时返回 `None`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        // APK、AAB 和 IPA 安装包也是 zip 格式
+        if [".zip", ".apk", ".aab", ".ipa"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+        {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// 依次解析压缩包中的每个 PNG，把成员名和解析结果交给 `f`。
+/// 单个 PNG 损坏不会中断遍历，压缩包本身损坏时返回错误
+pub fn for_each_png<R: Read>(
+    reader: R,
+    kind: ArchiveKind,
+    mut f: impl FnMut(&str, Result<Png>) -> Result<()>,
+) -> Result<()> {
+    for_each_member(reader, kind, |name, _, member| {
+        if is_png_name(name) {
+            f(name, Png::from_reader(member))?;
+        }
+        Ok(())
+    })
+}
+
+/// 依次读取压缩包中每个 PNG 的原始数据，用于需要自行解析的场景（例如不校验 CRC）
+pub fn for_each_png_bytes<R: Read>(
+    reader: R,
+    kind: ArchiveKind,
+    mut f: impl FnMut(&str, Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    for_each_member(reader, kind, |name, _, member| {
+        if is_png_name(name) {
+            f(name, read_member(member)?)?;
+        }
+        Ok(())
+    })
+}
+
+/// 改写压缩包中的 PNG：`f` 返回 `true` 表示修改了 PNG，需要写回。
+/// 返回新的压缩包内容和被修改的 PNG 数量，其他成员原样保留
+pub fn rewrite_pngs<R: Read>(
+    reader: R,
+    kind: ArchiveKind,
+    mut f: impl FnMut(&str, &mut Png) -> Result<bool>,
+) -> Result<(Vec<u8>, usize)> {
+    let mut modified = 0;
+    let mut edit = |name: &str, data: Vec<u8>| -> Result<Vec<u8>> {
+        if !is_png_name(name) {
+            return Ok(data);
+        }
+        let mut png = Png::from_reader(data.as_slice())?;
+        if f(name, &mut png)? {
+            modified += 1;
+            return Ok(png.as_bytes());
+        }
+        Ok(data)
+    };
+
+    let bytes = match kind {
+        ArchiveKind::Zip => {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            for_each_member(reader, kind, |name, info, member| {
+                let mut options = SimpleFileOptions::default()
+                    .compression_method(info.compression)
+                    .unix_permissions(info.mode);
+                if let Some(modified) = info.modified {
+                    options = options.last_modified_time(modified);
+                }
+                if info.is_dir {
+                    writer.add_directory(name, options).map_err(zip_error)?;
+                    return Ok(());
+                }
+                let data = read_member(member)?;
+                writer.start_file(name, options).map_err(zip_error)?;
+                writer.write_all(&edit(name, data)?)?;
+                Ok(())
+            })?;
+            writer.finish().map_err(zip_error)?.into_inner()
+        }
+        ArchiveKind::Tar => rewrite_tar(reader, Vec::new(), &mut edit)?,
+        ArchiveKind::TarGz => {
+            let encoder = GzEncoder::new(Vec::new(), Compression::default());
+            rewrite_tar(GzDecoder::new(reader), encoder, &mut edit)?.finish()?
+        }
+    };
+    Ok((bytes, modified))
+}
+
+/// 遍历成员时需要保留的信息
+struct MemberInfo {
+    is_dir: bool,
+    mode: u32,
+    compression: CompressionMethod,
+    modified: Option<zip::DateTime>,
+}
+
+/// 依次读取压缩包中的每个成员
+fn for_each_member<R: Read>(
+    mut reader: R,
+    kind: ArchiveKind,
+    mut f: impl FnMut(&str, &MemberInfo, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    match kind {
+        ArchiveKind::Zip => {
+            while let Some(mut file) =
+                zip::read::read_zipfile_from_stream(&mut reader).map_err(zip_error)?
+            {
+                let info = MemberInfo {
+                    is_dir: file.is_dir(),
+                    mode: file.unix_mode().unwrap_or(0o644),
+                    compression: match file.compression() {
+                        CompressionMethod::Stored => CompressionMethod::Stored,
+                        _ => CompressionMethod::Deflated,
+                    },
+                    modified: file.last_modified(),
+                };
+                let name = file.name().to_string();
+                f(&name, &info, &mut file)?;
+                // 回调可能没有读完成员，剩余数据必须跳过才能读到下一个成员
+                std::io::copy(&mut file, &mut std::io::sink())?;
+            }
+            Ok(())
+        }
+        ArchiveKind::Tar => for_each_tar_entry(reader, f),
+        ArchiveKind::TarGz => for_each_tar_entry(GzDecoder::new(reader), f),
+    }
+}
+
+fn for_each_tar_entry<R: Read>(
+    reader: R,
+    mut f: impl FnMut(&str, &MemberInfo, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let info = MemberInfo {
+            is_dir: entry.header().entry_type().is_dir(),
+            mode: entry.header().mode().unwrap_or(0o644),
+            compression: CompressionMethod::Stored,
+            modified: None,
+        };
+        f(&name, &info, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// 复制 tar 中的所有成员，普通文件的内容经过 `edit` 处理
+fn rewrite_tar<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    edit: &mut impl FnMut(&str, Vec<u8>) -> Result<Vec<u8>>,
+) -> Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let mut header = entry.header().clone();
+        let path = entry.path()?.into_owned();
+        if header.entry_type().is_file() {
+            let data = read_member(&mut entry)?;
+            let data = edit(&path.to_string_lossy(), data)?;
+            header.set_size(data.len() as u64);
+            builder.append_data(&mut header, &path, data.as_slice())?;
+        } else {
+            builder.append_data(&mut header, &path, &mut entry)?;
+        }
+    }
+    Ok(builder.into_inner()?)
+}
+
+fn read_member(member: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    member.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn is_png_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".png")
+}
+
+fn zip_error(e: zip::result::ZipError) -> PngmeError {
+    PngmeError::Archive(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+
+    fn testing_png(message: &str) -> Vec<u8> {
+        let chunk = Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            message.as_bytes().to_vec(),
+        )
+        .unwrap();
+        Png::from_chunks(vec![chunk]).as_bytes()
+    }
+
+    fn testing_zip() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("icons/", options).unwrap();
+        writer.start_file("icons/a.png", options).unwrap();
+        writer.write_all(&testing_png("a")).unwrap();
+        writer.start_file("README.txt", options).unwrap();
+        writer.write_all(b"not a png").unwrap();
+        writer.start_file("b.PNG", options).unwrap();
+        writer.write_all(&testing_png("b")).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn testing_tar() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in [
+            ("icons/a.png", testing_png("a")),
+            ("README.txt", b"not a png".to_vec()),
+            ("b.PNG", testing_png("b")),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, name, data.as_slice())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn testing_tar_gz() -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&testing_tar()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn messages(bytes: &[u8], kind: ArchiveKind) -> Vec<(String, String)> {
+        let mut found = Vec::new();
+        for_each_png(bytes, kind, |name, png| {
+            let png = png?;
+            let message = png.chunk_by_type("ruSt").unwrap().data_as_string()?;
+            found.push((name.to_string(), message));
+            Ok(())
+        })
+        .unwrap();
+        found
+    }
+
+    fn all_archives() -> Vec<(Vec<u8>, ArchiveKind)> {
+        vec![
+            (testing_zip(), ArchiveKind::Zip),
+            (testing_tar(), ArchiveKind::Tar),
+            (testing_tar_gz(), ArchiveKind::TarGz),
+        ]
+    }
+
+    #[test]
+    fn test_kind_from_path() {
+        let kind = |path: &str| ArchiveKind::from_path(Path::new(path));
+        assert_eq!(kind("assets.zip"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("app.apk"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("App.ipa"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("assets.TAR"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("assets.tar.gz"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("assets.tgz"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("image.png"), None);
+    }
+
+    #[test]
+    fn test_for_each_png() {
+        for (bytes, kind) in all_archives() {
+            assert_eq!(
+                messages(&bytes, kind),
+                [
+                    ("icons/a.png".to_string(), "a".to_string()),
+                    ("b.PNG".to_string(), "b".to_string())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_member_is_reported() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        builder
+            .append_data(&mut header, "broken.png", &b"bad"[..])
+            .unwrap();
+        let bytes = builder.into_inner().unwrap();
+
+        let mut results = Vec::new();
+        for_each_png(bytes.as_slice(), ArchiveKind::Tar, |name, png| {
+            results.push((name.to_string(), png.is_err()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(results, [("broken.png".to_string(), true)]);
+    }
+
+    #[test]
+    fn test_rewrite_pngs() {
+        for (bytes, kind) in all_archives() {
+            let (rewritten, modified) = rewrite_pngs(bytes.as_slice(), kind, |name, png| {
+                if name != "b.PNG" {
+                    return Ok(false);
+                }
+                png.remove_chunk("ruSt")?;
+                png.append_chunk(
+                    Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"changed".to_vec()).unwrap(),
+                );
+                Ok(true)
+            })
+            .unwrap();
+            assert_eq!(modified, 1);
+            assert_eq!(
+                messages(&rewritten, kind),
+                [
+                    ("icons/a.png".to_string(), "a".to_string()),
+                    ("b.PNG".to_string(), "changed".to_string())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_keeps_other_members() {
+        let (rewritten, _) =
+            rewrite_pngs(testing_tar().as_slice(), ArchiveKind::Tar, |_, _| Ok(false)).unwrap();
+        let mut names = Vec::new();
+        for_each_member(rewritten.as_slice(), ArchiveKind::Tar, |name, _, member| {
+            names.push((name.to_string(), read_member(member)?.len()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(names[1], ("README.txt".to_string(), 9));
+        assert_eq!(names.len(), 3);
+    }
+
+    #[test]
+    fn test_corrupt_archive() {
+        let result = for_each_png(&b"PK\x03\x04garbage"[..], ArchiveKind::Zip, |_, _| Ok(()));
+        assert!(result.is_err());
+    }
+}
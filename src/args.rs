@@ -0,0 +1,1018 @@
+//! # Args
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块定义了命令行参数。
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+pub use crate::encoding::Encoding;
+pub use crate::lsb::{Channels, LsbAlgorithm, Region};
+pub use crate::qr::Corner;
+pub use crate::trailer::Method;
+pub use crate::vfs::Preserve;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "pngme",
+    version,
+    about = "在 PNG 文件中隐藏信息",
+    disable_version_flag = true,
+    arg_required_else_help = true
+)]
+pub struct Cli {
+    /// 打印版本
+    #[arg(short = 'V', long)]
+    pub version: bool,
+    /// 在 stderr 输出调试日志（读写的文件、批处理和编辑步骤）；与 --version 一起使用时列出这个构建支持的功能
+    #[arg(short = 'v', long, global = true)]
+    pub verbose: bool,
+    /// 在内存中完整执行修改，列出将要写入或删除的文件（大小变化，新增、删除和修改的 chunk 及其 CRC），不写入任何文件
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// 以 JSON 对象输出结果：成功时为 {"ok":true,...}，出错时为 {"ok":false,...}，提示改为输出到 stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Option<PngMeArgs>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PngMeArgs {
+    /// 将信息编码到指定类型的 chunk 中
+    Encode(EncodeArgs),
+    /// 解码指定类型 chunk 中的信息
+    Decode(DecodeArgs),
+    /// 删除指定类型的 chunk
+    Remove(RemoveArgs),
+    /// 列出 encode --label 保存的所有标签
+    ListLabels(ListLabelsArgs),
+    /// 打印 PNG 文件中的所有 chunk
+    Print(PrintArgs),
+    /// 列出所有 chunk 的偏移、长度、CRC 状态和属性位
+    Scan(ScanArgs),
+    /// 在 chunk 数据中搜索字符串或正则表达式，检查图片是否泄露敏感信息
+    Grep(GrepArgs),
+    /// 比较两个 PNG 文件，列出新增、删除和修改的 chunk
+    Diff(DiffArgs),
+    /// 估算可以隐藏的数据量
+    Capacity(CapacityArgs),
+    /// 检查可能隐藏的数据（私有 chunk、高熵文本、IEND 之后的数据、LSB 异常），生成风险报告
+    Detect(DetectArgs),
+    /// 审查 APK/IPA 安装包中 PNG 的元数据
+    Audit(AuditArgs),
+    /// 重新计算所有 chunk 的 CRC，修复损坏的文件
+    Repair(RepairArgs),
+    /// 删除所有辅助 chunk，只保留图像必需的数据
+    Strip(StripArgs),
+    /// 把另一个 PNG 中的辅助 chunk（元数据、隐藏的信息）复制到目标文件中
+    Merge(MergeArgs),
+    /// 以更高的压缩级别重新压缩图像数据并删除多余的 chunk，减小文件
+    Optimize(OptimizeArgs),
+    /// 改写为确定的规范形式，使相同的图片在版本控制中得到相同的字节
+    Canonicalize(CanonicalizeArgs),
+    /// 把每个 chunk 的数据导出为单独的文件，并生成清单
+    ExtractAll(ExtractAllArgs),
+    /// 按 extract-all 生成的清单重新组装 PNG
+    Assemble(AssembleArgs),
+    /// 按日志完成或回滚批处理中被中断的原地修改
+    Recover(RecoverArgs),
+    /// 统计目录中 PNG 的 chunk 类型、长度和顺序，生成语料画像
+    Profile(ProfileArgs),
+    /// 用 Ed25519 私钥对图像签名
+    Sign(SignArgs),
+    /// 用 Ed25519 公钥校验图像的签名
+    Verify(VerifyArgs),
+    /// 为一组 PNG 生成校验清单（chunk 清单和关键 chunk 的 SHA-256 摘要），或按清单检查文件是否被修改
+    #[command(subcommand)]
+    Manifest(ManifestCommand),
+    /// 按模板批量写入 tEXt/iTXt 文本元数据
+    Stamp(StampArgs),
+    /// 按 CSV 映射表（文件名,字段,值）批量编辑目录中 PNG 的文本、DPI 和修改时间
+    Metadata(MetadataArgs),
+    /// 读取、写入或删除文本元数据（tEXt/zTXt/iTXt 中的关键字和文本）
+    #[command(subcommand)]
+    Meta(MetaCommand),
+    /// 写入或更新最后修改时间（tIME）
+    Touch(TouchArgs),
+    /// 写入或更新物理像素密度（pHYs）
+    SetDpi(SetDpiArgs),
+    /// 写入或更新 gamma（gAMA）
+    SetGamma(SetGammaArgs),
+    /// 读取、写入或删除 EXIF 元数据（eXIf）
+    #[command(subcommand)]
+    Exif(ExifCommand),
+    /// 嵌入或导出 ICC 配置（iCCP）
+    #[command(subcommand)]
+    Icc(IccCommand),
+    /// 写入或检测鲁棒水印：把短标识冗余地分散写入像素的亮度，用于追查泄露的副本
+    #[command(subcommand)]
+    Watermark(WatermarkCommand),
+    /// 找出泄露的图片来自收件人列表中的哪一份副本（见 watermark batch）
+    Trace(TraceArgs),
+    /// 监视目录，自动处理新增或修改的 PNG（写入水印或删除元数据）
+    Watch(WatchArgs),
+    /// 以 daemon 方式运行，通过 Unix socket 接收 JSON 命令
+    #[cfg(unix)]
+    Daemon(DaemonArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct EncodeArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// chunk 类型，例如 ruSt；@passphrase 表示从 --passphrase 派生类型，@default 表示配置文件中的类型。
+    /// 使用 --label 时省略
+    #[arg(
+        default_value = "",
+        hide_default_value = true,
+        required_unless_present = "label"
+    )]
+    pub chunk_type: String,
+    /// 要隐藏的信息
+    #[arg(required_unless_present_any = ["file", "label"], conflicts_with = "file")]
+    pub message: Option<String>,
+    /// 输出文件路径，默认覆盖输入文件
+    pub output: Option<PathBuf>,
+    /// 输出文件路径，与位置参数相同，便于和 --file 一起使用
+    #[arg(short = 'o', long = "out", conflicts_with = "output")]
+    pub out: Option<PathBuf>,
+    /// 隐藏一个文件（保留文件名），而不是文本信息
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    /// 把信息保存在该标签下（写入 pmLb chunk，替换同一标签原有的信息），不同标签的信息互不影响；
+    /// 此时省略 chunk 类型，位置参数依次为信息和输出文件路径
+    #[arg(long, conflicts_with_all = ["frame", "spread", "profile", "convert"])]
+    pub label: Option<String>,
+    /// 信息（或 --file 的内容）的编码：utf8 原样写入，base64 和 hex 先解码为二进制数据，解码时忽略空白
+    #[arg(long, value_enum, value_name = "ENCODING", default_value_t = Encoding::Utf8)]
+    pub input_encoding: Encoding,
+    /// 压缩信息后再写入
+    #[arg(long)]
+    pub compress: bool,
+    /// 不压缩，覆盖配置文件中的 compress = true
+    #[arg(long, conflicts_with = "compress")]
+    pub no_compress: bool,
+    /// 单个 chunk 的最大数据长度，超出时拆分到多个 chunk 中
+    #[arg(long, default_value_t = crate::payload::DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: usize,
+    /// 使用口令加密信息
+    #[arg(long, requires = "passphrase")]
+    pub encrypt: bool,
+    /// 不加密，覆盖配置文件中的 encrypt = true
+    #[arg(long, conflicts_with = "encrypt")]
+    pub no_encrypt: bool,
+    /// 加密/解密使用的口令
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+    /// 用该密钥计算 HMAC-SHA256 完整性标签附加到信息末尾，解码时可以发现信息被篡改
+    #[arg(long, env = "PNGME_MAC_KEY", hide_env_values = true)]
+    pub mac_key: Option<String>,
+    /// 输入是 JPEG、GIF 或 BMP 时先转换为 PNG（需要 convert feature），结果写入扩展名为 .png 的文件；
+    /// 处理目录且没有给出 --glob 时同时处理这些格式的文件
+    #[arg(long, alias = "force-convert")]
+    pub convert: bool,
+    /// 把信息写在 APNG 第 N 帧（从 0 开始）的数据之后
+    #[arg(long, value_name = "N")]
+    pub frame: Option<u32>,
+    /// 把信息拆分后分散到 APNG 的每一帧中
+    #[arg(long, conflicts_with = "frame")]
+    pub spread: bool,
+    /// 按 pngme profile 生成的语料画像选择写入位置，使文件更接近语料中的典型文件
+    #[arg(long, value_name = "PROFILE", conflicts_with_all = ["frame", "spread"])]
+    pub profile: Option<PathBuf>,
+    /// 写入方式：chunk 写入 IEND 之前的辅助 chunk，trailer 写在 IEND 之后（大多数查看器会忽略），
+    /// qr 把信息渲染为 QR 码画在图片的一角（修改像素，截图后仍能读出，忽略 chunk 类型），
+    /// fdat 拆分后藏在 APNG 各帧的 fdAT 数据之后并重新编号（忽略 chunk 类型），
+    /// lsb 写入像素样本的最低位（修改像素，肉眼不可见，忽略 chunk 类型）
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Method::Chunk,
+        conflicts_with_all = ["frame", "spread", "profile", "convert"]
+    )]
+    pub method: Method,
+    /// --method qr 时 QR 码所在的角
+    #[arg(long, value_enum, default_value_t = Corner::BottomRight)]
+    pub qr_corner: Corner,
+    /// --method qr 时每个模块的边长（像素），QR 码连同四周的留白占 (宽度 + 8) × PIXELS 像素见方
+    #[arg(long, value_name = "PIXELS", default_value_t = crate::qr::DEFAULT_SCALE)]
+    pub qr_scale: u32,
+    /// --method lsb 时只写入该矩形内的像素，格式为 X,Y,WIDTH,HEIGHT，矩形外的像素保持不变
+    #[arg(long, value_name = "X,Y,WIDTH,HEIGHT")]
+    pub region: Option<Region>,
+    /// --method lsb 时按由 --passphrase 派生的伪随机顺序把各位分散到整个载体中，而不是按顺序写入；
+    /// 解码时给出同一个口令即可，不需要该选项
+    #[arg(long, requires = "passphrase")]
+    pub scatter: bool,
+    /// --method lsb 时最低位与要写入的位不同时如何修改样本：match（默认）随机加 1 或减 1，保持直方图的形状，
+    /// 不易被卡方检验发现；replace 直接翻转最低位。使用的算法记录在头部中，解码时不需要指定
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    pub lsb_algorithm: Option<LsbAlgorithm>,
+    /// --method lsb 时只在这些通道中写入，由 r、g、b、a（alpha）和 y（灰度）组成，例如 rg、b、a；
+    /// 默认使用所有通道。选用的通道记录在头部中，解码时不需要指定
+    #[arg(long, value_name = "CHANNELS")]
+    pub channels: Option<Channels>,
+    /// --method lsb 时每个样本写入的低位数（1 到 8，默认 1），位数越多容量越大，也越容易被发现。
+    /// 记录在头部中，解码时不需要指定
+    #[arg(long, value_name = "BITS", value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub bits_per_channel: Option<u8>,
+    /// 用内置的点阵字体把这行文字（可打印的 ASCII 字符，小写画成大写）画在图片的一角，作为可见的标记（修改像素）。
+    /// 与任何写入方式一起使用，先画文字再写入信息
+    #[arg(long, value_name = "TEXT")]
+    pub visible_label: Option<String>,
+    /// --visible-label 所在的角
+    #[arg(long, value_enum, default_value_t = Corner::TopLeft)]
+    pub visible_label_corner: Corner,
+    /// --visible-label 每个点的边长（像素），每个字符占 6 × 7 个点，四周留 2 个点的底色
+    #[arg(long, value_name = "PIXELS", default_value_t = crate::caption::DEFAULT_SCALE)]
+    pub visible_label_scale: u32,
+    /// 只在内存中模拟这次写入，报告它留下的统计痕迹（大小、熵和最低位卡方检验的变化以及 detect 的风险等级），
+    /// 不修改任何文件
+    #[arg(long, conflicts_with = "convert")]
+    pub assess: bool,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DecodeArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// chunk 类型；@passphrase 表示从 --passphrase 派生类型，@default 表示配置文件中的类型。
+    /// 使用 --label 时省略
+    #[arg(
+        default_value = "",
+        hide_default_value = true,
+        required_unless_present = "label",
+        conflicts_with = "label"
+    )]
+    pub chunk_type: String,
+    /// 读取该标签下的信息（见 encode --label）
+    #[arg(long, conflicts_with_all = ["frame"])]
+    pub label: Option<String>,
+    /// 把解码出的数据写入文件，用于提取通过 --file 隐藏的文件
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// 把隐藏的文件以其原始文件名（经过清理）提取到该目录中
+    #[arg(long, value_name = "DIR", conflicts_with = "out")]
+    pub out_dir: Option<PathBuf>,
+    /// 输出信息的编码：utf8 要求信息是合法的 UTF-8，base64 和 hex 可以输出任意二进制数据（包括隐藏的文件）
+    #[arg(long, value_enum, value_name = "ENCODING", default_value_t = Encoding::Utf8)]
+    pub output_encoding: Encoding,
+    /// 使用口令解密信息
+    #[arg(long, requires = "passphrase")]
+    pub decrypt: bool,
+    /// 不解密，覆盖配置文件中的 encrypt = true
+    #[arg(long, conflicts_with = "decrypt")]
+    pub no_decrypt: bool,
+    /// 加密/解密使用的口令
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+    /// 用该密钥校验信息的完整性标签，没有标签或标签不匹配时报错
+    #[arg(long, env = "PNGME_MAC_KEY", hide_env_values = true)]
+    pub mac_key: Option<String>,
+    /// 只读取 APNG 第 N 帧（从 0 开始）中的信息
+    #[arg(long, value_name = "N")]
+    pub frame: Option<u32>,
+    /// 宽松解析：跳过 CRC 错误、无效类型码和长度字段损坏的 chunk，尽量读出其余的 chunk
+    #[arg(long)]
+    pub lenient: bool,
+    /// 读取方式：chunk 从辅助 chunk 中读取，trailer 从 IEND 之后读取，qr 从像素中识读 QR 码（例如截图），
+    /// fdat 从 APNG 各帧的 fdAT 中读取，lsb 从像素样本的最低位读取
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Method::Chunk,
+        conflicts_with_all = ["frame", "lenient"]
+    )]
+    pub method: Method,
+    /// --method lsb 时从该矩形内的像素读取，与写入时的 --region 相同
+    #[arg(long, value_name = "X,Y,WIDTH,HEIGHT")]
+    pub region: Option<Region>,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RemoveArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// chunk 类型；@default 表示配置文件中的类型
+    pub chunk_type: String,
+    /// 删除所有该类型的 chunk，而不只是第一条信息
+    #[arg(long)]
+    pub all: bool,
+    /// 只删除第 N 个（从 0 开始）该类型的 chunk
+    #[arg(long, value_name = "N", conflicts_with = "all")]
+    pub index: Option<usize>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ListLabelsArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PrintArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 宽松解析：跳过 CRC 错误、无效类型码和长度字段损坏的 chunk，尽量读出其余的 chunk
+    #[arg(long)]
+    pub lenient: bool,
+    /// 以十六进制视图显示该类型 chunk 的数据
+    #[arg(long, value_name = "TYPE")]
+    pub hex: Option<String>,
+    /// 十六进制视图中每个 chunk 最多显示的字节数，0 表示全部
+    #[arg(long, default_value_t = 256, value_name = "N")]
+    pub max_bytes: usize,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ScanArgs {
+    /// PNG 文件或压缩包路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+    /// 以十六进制视图显示该类型 chunk 的数据
+    #[arg(long, value_name = "TYPE", conflicts_with = "json")]
+    pub hex: Option<String>,
+    /// 十六进制视图中每个 chunk 最多显示的字节数，0 表示全部
+    #[arg(long, default_value_t = 256, value_name = "N")]
+    pub max_bytes: usize,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct GrepArgs {
+    /// 要搜索的字符串，指定 --regex 时为正则表达式
+    pub pattern: String,
+    /// PNG 文件或压缩包路径，是目录时搜索其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 把模式作为正则表达式
+    #[arg(short = 'E', long)]
+    pub regex: bool,
+    /// 忽略大小写
+    #[arg(short = 'i', long)]
+    pub ignore_case: bool,
+    /// 搜索 zTXt 和压缩的 iTXt 解压后的文本
+    #[arg(long)]
+    pub decompress: bool,
+    /// 片段中匹配前后各显示的字节数
+    #[arg(long, default_value_t = 16, value_name = "N")]
+    pub context: usize,
+    /// 只搜索这些类型的 chunk，例如 tEXt,iTXt
+    #[arg(long = "type", value_delimiter = ',', value_name = "TYPE")]
+    pub chunk_types: Vec<String>,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DiffArgs {
+    /// 修改前的 PNG 文件
+    pub before: PathBuf,
+    /// 修改后的 PNG 文件
+    pub after: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CapacityArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DetectArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct AuditArgs {
+    /// 安装包路径（.apk、.aab 或 .ipa）
+    pub file_path: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RepairArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StripArgs {
+    /// PNG 文件或压缩包路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    /// 要保留的辅助 chunk 类型，例如 tRNS,gAMA
+    #[arg(long, value_delimiter = ',')]
+    pub keep: Vec<String>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MergeArgs {
+    /// 复制 chunk 的来源 PNG 文件
+    pub source: PathBuf,
+    /// 目标 PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，默认覆盖目标文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    /// 要复制的辅助 chunk 类型，例如 tEXt,ruSt；默认复制所有可以安全复制的辅助 chunk
+    #[arg(long, value_delimiter = ',')]
+    pub types: Vec<String>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct OptimizeArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    /// zlib 压缩级别（0-9）
+    #[arg(long, default_value_t = 9, value_parser = clap::value_parser!(u32).range(0..=9))]
+    pub level: u32,
+    /// 保留重复的 chunk
+    #[arg(long)]
+    pub keep_redundant: bool,
+    /// 把 16 位图片转换为 8 位（有损）
+    #[arg(long)]
+    pub to_8bit: bool,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CanonicalizeArgs {
+    /// PNG 文件路径，`-` 表示标准输入；是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，`-` 表示标准输出，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    /// 只检查文件是否已经是规范形式，不写入；不是时返回错误
+    #[arg(long, conflicts_with = "out")]
+    pub check: bool,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExtractAllArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 输出目录，默认为输入文件所在的目录
+    #[arg(short = 'o', long = "out-dir")]
+    pub out_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AssembleArgs {
+    /// extract-all 生成的清单
+    pub manifest: PathBuf,
+    /// 输出的 PNG 文件路径
+    pub output: PathBuf,
+    /// 重新计算 CRC，而不是写回清单中存储的值
+    #[arg(long)]
+    pub recompute_crc: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RecoverArgs {
+    /// 被中断的批处理所在的目录
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ProfileArgs {
+    /// 要统计的目录
+    pub dir: PathBuf,
+    /// 把画像写入该 JSON 文件，默认打印到标准输出
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SignArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// PKCS#8 PEM 格式的 Ed25519 私钥
+    #[arg(long)]
+    pub key: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct VerifyArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// PEM 格式的 Ed25519 公钥
+    #[arg(long)]
+    pub pubkey: PathBuf,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+/// `manifest` 的子命令
+#[derive(Debug, Clone, Subcommand)]
+pub enum ManifestCommand {
+    /// 为目录中的 PNG 生成校验清单
+    Create(ManifestCreateArgs),
+    /// 按校验清单检查文件，列出被修改、删除或新增的文件
+    Verify(ManifestVerifyArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ManifestCreateArgs {
+    /// 要记录的目录
+    pub dir: PathBuf,
+    /// 把清单写入该 JSON 文件，默认打印到标准输出
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ManifestVerifyArgs {
+    /// manifest create 生成的清单
+    pub manifest: PathBuf,
+    /// 要检查的目录，默认为生成清单时的目录
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StampArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 要写入的文本，格式为 关键字=模板，模板中的 {{name}} 会被替换为变量的值
+    #[arg(long = "set", value_name = "KEYWORD=TEMPLATE", required = true)]
+    pub entries: Vec<String>,
+    /// 定义变量，格式为 name=value，优先于 --vars 和环境变量
+    #[arg(long = "var", value_name = "NAME=VALUE")]
+    pub vars: Vec<String>,
+    /// 从 JSON 对象文件读取变量，优先于环境变量
+    #[arg(long = "vars", value_name = "FILE")]
+    pub vars_file: Option<PathBuf>,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetadataArgs {
+    /// CSV 映射表，每行为 文件名,字段,值；字段 dpi 写入 pHYs，time 写入 tIME，其他作为文本关键字
+    pub csv: PathBuf,
+    /// 要编辑的 PNG 所在的目录
+    pub dir: PathBuf,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MetaCommand {
+    /// 显示关键字对应的文本
+    Get(MetaGetArgs),
+    /// 写入一条文本，替换同一关键字已有的文本
+    Set(MetaSetArgs),
+    /// 按关键字排序列出所有文本
+    List(MetaListArgs),
+    /// 删除关键字对应的所有文本
+    Delete(MetaDeleteArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetaGetArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 关键字，例如 Author
+    pub keyword: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetaSetArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 关键字，例如 Author
+    pub keyword: String,
+    /// 文本，能用 Latin-1 表示时写入 tEXt，否则写入 iTXt
+    pub value: String,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetaListArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetaDeleteArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 关键字，例如 Author
+    pub keyword: String,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TouchArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 写入的 UTC 时间，例如 2026-10-16T08:30:00，默认为当前时间
+    #[arg(long)]
+    pub time: Option<String>,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SetDpiArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 每英寸像素数，水平和垂直方向相同
+    pub dpi: f64,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SetGammaArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 显示时的解码指数，例如 2.2 写入 gamma 0.45455
+    pub gamma: f64,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+/// 修改文件的命令共用的选项
+#[derive(Debug, Clone, Default, Args)]
+pub struct EditArgs {
+    /// 写入前确认不认识的 chunk 被逐字节保留且相对 IDAT 的位置不变，否则拒绝写入
+    #[arg(long)]
+    pub strict_preserve: bool,
+    /// 在 pmPv chunk 中登记新增或修改的 chunk，以及 pngme 版本和操作 id
+    #[arg(long)]
+    pub provenance: bool,
+    /// 写入前重新解析输出并再次序列化，确认逐字节一致且所有信息都能提取，否则拒绝写入
+    #[arg(long)]
+    pub verify_roundtrip: bool,
+    /// 写入后恢复原文件的属性：times 为修改和访问时间，perms 为权限位、所有者和扩展属性，all 为全部
+    #[arg(long, value_enum, value_name = "WHAT")]
+    pub preserve: Option<Preserve>,
+    /// 不覆盖输入文件，把结果写入输入文件旁边按模板命名的文件，变量同 --name-template，例如 "{stem}_tagged.{ext}"
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["suffix", "output_dir"])]
+    pub output_template: Option<String>,
+    /// 不覆盖输入文件，把结果写入在扩展名前加上该后缀的文件，例如 _tagged 写入 a_tagged.png
+    #[arg(long, conflicts_with = "output_dir")]
+    pub suffix: Option<String>,
+}
+
+/// 处理目录时的选项
+#[derive(Debug, Clone, Args)]
+pub struct BatchArgs {
+    /// 递归处理子目录
+    #[arg(short = 'r', long)]
+    pub recursive: bool,
+    /// 只处理文件名匹配该模式的文件，支持 * 和 ?，多个模式用 | 分隔
+    #[arg(long, default_value = crate::batch::DEFAULT_GLOB)]
+    pub glob: String,
+    /// 并行处理文件的线程数
+    #[arg(short = 'j', long, default_value_t = 1)]
+    pub jobs: usize,
+    /// 把结果写入该目录（保留子目录结构），而不是原地修改
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+    /// 输出文件名模板，可用变量 {stem}、{ext}、{hash}（内容 SHA-256 的前 16 位）和 {date}
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = crate::batch::DEFAULT_NAME_TEMPLATE,
+        requires = "output_dir"
+    )]
+    pub name_template: String,
+    /// 跟随符号链接进入目录和处理链接到的文件；默认跳过符号链接
+    #[arg(long)]
+    pub follow_symlinks: bool,
+    /// 原地修改有多个硬链接的文件时保留硬链接，每组硬链接只处理一次
+    #[arg(long, conflicts_with = "output_dir")]
+    pub preserve_hardlinks: bool,
+    /// 原地修改时不记录日志（见 pngme recover）
+    #[arg(long)]
+    pub no_journal: bool,
+}
+
+impl Default for BatchArgs {
+    fn default() -> Self {
+        BatchArgs {
+            recursive: false,
+            glob: crate::batch::DEFAULT_GLOB.to_string(),
+            jobs: 1,
+            output_dir: None,
+            name_template: crate::batch::DEFAULT_NAME_TEMPLATE.to_string(),
+            follow_symlinks: false,
+            preserve_hardlinks: false,
+            no_journal: false,
+        }
+    }
+}
+
+/// `exif` 的子命令
+#[derive(Debug, Clone, Subcommand)]
+pub enum ExifCommand {
+    /// 显示 eXIf 的字节序、方向和时间，或导出原始数据
+    Get(ExifGetArgs),
+    /// 写入 eXIf，替换已有的
+    Set(ExifSetArgs),
+    /// 删除 eXIf
+    Remove(ExifRemoveArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExifGetArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 把原始 TIFF 数据写入该文件
+    #[arg(long, conflicts_with = "json")]
+    pub raw: Option<PathBuf>,
+    /// 全局的 `--json`
+    #[arg(from_global)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExifSetArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 原始 TIFF 数据文件，可以带有从 JPEG 中复制的 Exif\0\0 前缀
+    pub exif: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExifRemoveArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum IccCommand {
+    /// 嵌入 ICC 配置，替换已有的 iCCP 并删除 sRGB
+    Embed(IccEmbedArgs),
+    /// 把嵌入的 ICC 配置解压后写入文件
+    Extract(IccExtractArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct IccEmbedArgs {
+    /// PNG 文件路径，是目录时处理其中的所有 PNG 文件
+    pub file_path: PathBuf,
+    /// ICC 配置文件
+    pub profile: PathBuf,
+    /// 配置名称，1 到 79 个 Latin-1 字符
+    #[arg(long, default_value = "ICC profile")]
+    pub name: String,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct IccExtractArgs {
+    /// PNG 文件路径
+    pub file_path: PathBuf,
+    /// 写入 ICC 配置的文件
+    #[arg(short = 'o', long = "out")]
+    pub out: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WatermarkCommand {
+    /// 把标识（1 到 12 字节）连同纠错码字重复写入整张图片的亮度（修改像素），以相同分辨率重新保存后仍能检测
+    Embed(WatermarkEmbedArgs),
+    /// 检测 watermark embed 写入的水印并读出标识
+    Detect(WatermarkDetectArgs),
+    /// 按收件人列表为每个收件人生成一份写入其标识的副本
+    Batch(WatermarkBatchArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatermarkEmbedArgs {
+    /// PNG 文件路径，是目录时给其中的所有 PNG 文件写入同一个标识
+    pub file_path: PathBuf,
+    /// 标识，1 到 12 字节的 UTF-8 文本，例如收件人编号
+    pub id: String,
+    /// 输出文件路径，默认覆盖输入文件
+    #[arg(short = 'o', long = "out")]
+    pub out: Option<PathBuf>,
+    /// 8 位样本上每个像素的亮度改变量：越大越能经受有损的重新保存，也越容易看出
+    #[arg(
+        long,
+        default_value_t = crate::watermark::DEFAULT_STRENGTH,
+        value_parser = clap::value_parser!(u8).range(1..=crate::watermark::MAX_STRENGTH as i64)
+    )]
+    pub strength: u8,
+    /// 从口令派生水印图案，检测时需要同一个口令；不给出时使用公开的默认图案
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+    #[command(flatten)]
+    pub edit: EditArgs,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatermarkDetectArgs {
+    /// 图片路径；启用 convert feature 时也可以是 JPEG、GIF 或 BMP
+    pub file_path: PathBuf,
+    /// 写入时使用的口令
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatermarkBatchArgs {
+    /// 原图路径
+    pub file_path: PathBuf,
+    /// 收件人列表：CSV（每行为 标识[,名称]，可以有 id,name 表头）或 JSON 数组
+    pub recipients: PathBuf,
+    /// 输出目录，默认为原图所在的目录；副本命名为 原图名.标识.png
+    #[arg(short = 'o', long = "out-dir")]
+    pub out_dir: Option<PathBuf>,
+    /// 8 位样本上每个像素的亮度改变量，同 watermark embed
+    #[arg(
+        long,
+        default_value_t = crate::watermark::DEFAULT_STRENGTH,
+        value_parser = clap::value_parser!(u8).range(1..=crate::watermark::MAX_STRENGTH as i64)
+    )]
+    pub strength: u8,
+    /// 从口令派生水印图案，追查时需要同一个口令
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TraceArgs {
+    /// 泄露的图片路径；启用 convert feature 时也可以是 JPEG、GIF 或 BMP
+    pub file_path: PathBuf,
+    /// 生成副本时使用的收件人列表
+    pub recipients: PathBuf,
+    /// 生成副本时使用的口令
+    #[arg(long, env = "PNGME_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatchArgs {
+    /// 要监视的目录
+    pub dir: PathBuf,
+    /// 删除所有辅助 chunk
+    #[arg(
+        long,
+        required_unless_present = "watermark",
+        conflicts_with = "watermark"
+    )]
+    pub strip: bool,
+    /// --strip 时要保留的辅助 chunk 类型，例如 tRNS,gAMA
+    #[arg(long, value_delimiter = ',', requires = "strip")]
+    pub keep: Vec<String>,
+    /// 写入水印 chunk，例如 waTr="exported by CI"；已有相同的 chunk 时跳过
+    #[arg(long, value_name = "TYPE=TEXT")]
+    pub watermark: Option<String>,
+    /// 文件停止变化多少毫秒后才处理，避免处理写了一半的文件
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub debounce: u64,
+    /// 只打印将要修改的文件，不写入；由全局的 --dry-run 设置
+    #[arg(skip)]
+    pub dry_run: bool,
+    /// 同时监视子目录
+    #[arg(short = 'r', long)]
+    pub recursive: bool,
+    /// 只处理文件名匹配该模式的文件，支持 * 和 ?，多个模式用 | 分隔
+    #[arg(long, default_value = crate::batch::DEFAULT_GLOB)]
+    pub glob: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DaemonArgs {
+    /// Unix socket 路径
+    #[arg(long)]
+    pub socket: PathBuf,
+}
@@ -0,0 +1,229 @@
+//! # Assess
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `encode --assess`：比较写入前后的文件，给出这次写入留下的统计痕迹，
+//! 在真正写入之前就可以据此换用更不容易被发现的写入方式或载体：
+//!
+//! - 文件大小的变化；
+//! - 整个文件和像素样本的香农熵（比特/字节）的变化，加密后的信息和被随机化的低位都会抬高熵；
+//! - `detect` 对像素最低位做的卡方检验的统计量和 p 值的变化，p 值接近 1 说明最低位像是被替换过；
+//! - `detect` 给出的风险等级的变化。
+//!
+//! 写入 chunk 或 IEND 之后的方式不改变像素，像素的熵和卡方检验不变，痕迹体现在大小和风险等级上；
+//! 写入像素的方式（`lsb`、`qr`）正好相反。
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::detect::{self, Risk};
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::Result;
+
+/// 一个统计量在写入前后的值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// 一次写入的统计痕迹
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Assessment {
+    /// 文件的字节数
+    pub size: Change<u64>,
+    /// 整个文件的熵（比特/字节）
+    pub file_entropy: Change<f64>,
+    /// 解码后的像素样本的熵（比特/字节），像素无法解码时为 `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixel_entropy: Option<Change<f64>>,
+    /// 像素最低位的卡方统计量，`detect` 不做像素分析的图片为 `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chi_square: Option<Change<f64>>,
+    /// 卡方检验的 p 值，与 `chi_square` 同时存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_value: Option<Change<f64>>,
+    /// `detect` 给出的风险等级
+    pub risk: Change<Risk>,
+}
+
+impl Assessment {
+    /// 文件大小的变化（字节）
+    pub fn size_delta(&self) -> i64 {
+        self.size.after as i64 - self.size.before as i64
+    }
+}
+
+/// 比较写入前后的文件。写入前的文件不是 PNG 或无法解析时返回错误
+pub fn assess(before: &[u8], after: &[u8], limits: &Limits) -> Result<Assessment> {
+    let report_before = detect::detect(before, limits)?;
+    let report_after = detect::detect(after, limits)?;
+    let pixel_entropy = |bytes: &[u8]| {
+        let pixels = Png::from_bytes_with_limits(bytes, limits)
+            .and_then(|png| png.pixels(limits))
+            .ok()?;
+        Some(detect::entropy(&pixels))
+    };
+    let both = |before: Option<f64>, after: Option<f64>| {
+        Some(Change {
+            before: before?,
+            after: after?,
+        })
+    };
+    let lsb_before = report_before.lsb.as_ref();
+    let lsb_after = report_after.lsb.as_ref();
+    Ok(Assessment {
+        size: Change {
+            before: before.len() as u64,
+            after: after.len() as u64,
+        },
+        file_entropy: Change {
+            before: detect::entropy(before),
+            after: detect::entropy(after),
+        },
+        pixel_entropy: both(pixel_entropy(before), pixel_entropy(after)),
+        chi_square: both(
+            lsb_before.map(|lsb| lsb.chi_square),
+            lsb_after.map(|lsb| lsb.chi_square),
+        ),
+        p_value: both(
+            lsb_before.map(|lsb| lsb.p_value),
+            lsb_after.map(|lsb| lsb.p_value),
+        ),
+        risk: Change {
+            before: report_before.risk,
+            after: report_after.risk,
+        },
+    })
+}
+
+impl fmt::Display for Change<f64> {
+    /// `before -> after (+delta)`，保留三位小数
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} -> {:.3} ({:+.3})",
+            self.before,
+            self.after,
+            self.after - self.before
+        )
+    }
+}
+
+impl fmt::Display for Assessment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Size: {} -> {} bytes ({:+})",
+            self.size.before,
+            self.size.after,
+            self.size_delta()
+        )?;
+        write!(f, "\nFile entropy: {} bits/byte", self.file_entropy)?;
+        match &self.pixel_entropy {
+            Some(entropy) => write!(f, "\nPixel entropy: {} bits/byte", entropy)?,
+            None => write!(f, "\nPixel entropy: not available")?,
+        }
+        match (&self.chi_square, &self.p_value) {
+            (Some(chi_square), Some(p_value)) => {
+                write!(f, "\nLSB chi-square: {}", chi_square)?;
+                write!(f, "\nLSB chi-square p-value: {}", p_value)?;
+            }
+            _ => write!(
+                f,
+                "\nLSB chi-square: not applicable (palette image, bit depth other than 8 or too few samples)"
+            )?,
+        }
+        write!(f, "\nRisk: {} -> {}", self.risk.before, self.risk.after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancelToken;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::ihdr::{ColorType, Ihdr};
+    use crate::lsb::{self, LsbAlgorithm, LsbOptions};
+    use crate::png::tests::{testing_image, PNG_FILE};
+
+    /// 64x64 的 8 位灰度图片，样本只取 4 的倍数，最低位全为 0
+    fn grayscale() -> Png {
+        let header = Ihdr::new(64, 64, 8, ColorType::Grayscale).unwrap();
+        let pixels: Vec<u8> = (0..64 * 64).map(|i| (i * 7 % 64 * 4) as u8).collect();
+        testing_image(header, &pixels)
+    }
+
+    #[test]
+    fn test_chunk_embed() {
+        let limits = Limits::default();
+        let before = grayscale();
+        let mut after = before.clone();
+        after.append_chunk(
+            Chunk::new(
+                ChunkType::from_str("ruSt").unwrap(),
+                b"a hidden message longer than 16 bytes".to_vec(),
+            )
+            .unwrap(),
+        );
+        let assessment = assess(&before.as_bytes(), &after.as_bytes(), &limits).unwrap();
+        assert_eq!(assessment.size_delta(), 12 + 37);
+        // 像素没有变化
+        let pixels = assessment.pixel_entropy.unwrap();
+        assert_eq!(pixels.before, pixels.after);
+        let chi_square = assessment.chi_square.unwrap();
+        assert_eq!(chi_square.before, chi_square.after);
+        assert_eq!(assessment.risk.before, Risk::None);
+        assert_eq!(assessment.risk.after, Risk::Medium);
+    }
+
+    #[test]
+    fn test_lsb_embed() {
+        let limits = Limits::default();
+        let before = grayscale();
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..64 * 64 / 8 - lsb::HEADER_LEN)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let mut after = before.clone();
+        let options = LsbOptions {
+            algorithm: LsbAlgorithm::Replace,
+            ..LsbOptions::default()
+        };
+        lsb::embed(&mut after, &data, &options, &limits, &CancelToken::new()).unwrap();
+
+        let assessment = assess(&before.as_bytes(), &after.as_bytes(), &limits).unwrap();
+        // 最低位被随机化：像素的熵升高，卡方统计量下降，p 值接近 1
+        let pixels = assessment.pixel_entropy.unwrap();
+        assert!(pixels.after > pixels.before);
+        let chi_square = assessment.chi_square.unwrap();
+        assert!(chi_square.after < chi_square.before);
+        let p_value = assessment.p_value.unwrap();
+        assert!(p_value.before < 0.5 && p_value.after > 0.95);
+        assert_eq!(assessment.risk.after, Risk::Medium);
+
+        let text = assessment.to_string();
+        assert!(text.starts_with("Size: "));
+        assert!(text.contains("\nPixel entropy: 6.000 -> "));
+        assert!(text.ends_with("Risk: none -> medium"));
+    }
+
+    #[test]
+    fn test_small_image() {
+        let assessment = assess(&PNG_FILE, &PNG_FILE, &Limits::default()).unwrap();
+        assert_eq!(assessment.size_delta(), 0);
+        assert!(assessment.chi_square.is_none());
+        assert!(assessment
+            .to_string()
+            .contains("LSB chi-square: not applicable"));
+        assert!(assess(b"not a png", &PNG_FILE, &Limits::default()).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! # Async IO
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在启用 `tokio` feature 时提供异步读写：`AsyncChunkReader` 从 `AsyncRead` 中逐个读取 chunk，
+//! `Png::from_async_reader` 和 `Png::write_async` 读写整个文件。接收上传文件的 Web 服务可以直接
+//! 处理请求体，不会在等待网络数据时阻塞运行时的工作线程：
+//!
+//! ```text
+//! let mut png = Png::from_async_reader(body).await?;
+//! png.append_chunk(chunk);
+//! png.write_async(&mut response).await?;
+//! ```
+//!
+//! 只有 IO 是异步的。解析单个 chunk 和写入信息很快，但口令派生密钥（Argon2id）和压缩是 CPU 密集的，
+//! 加解密时应放到 `tokio::task::spawn_blocking` 中执行。取消读取只需丢弃 future。
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::chunk::Chunk;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::progress::Progress;
+use crate::sniff;
+use crate::{PngmeError, Result};
+
+/// 从任意 `AsyncRead` 中逐个读取 chunk，`ChunkReader` 的异步版本
+pub struct AsyncChunkReader<R: AsyncRead + Unpin> {
+    reader: R,
+    progress: Option<Arc<dyn Progress>>,
+    limits: Limits,
+    /// 已经读取的 chunk 数量和字节数（含 PNG 签名）
+    chunks_read: usize,
+    bytes_read: u64,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncChunkReader<R> {
+    /// 从 chunk 序列（不含 PNG 签名）创建读取器
+    pub fn new(reader: R) -> Self {
+        AsyncChunkReader {
+            reader,
+            progress: None,
+            limits: Limits::default(),
+            chunks_read: 0,
+            bytes_read: 0,
+            done: false,
+        }
+    }
+
+    /// 设置安全限制，默认为 `Limits::default()`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 设置进度回调，每读取一个 chunk 报告一次
+    pub fn with_progress(mut self, progress: Arc<dyn Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// 从完整的 PNG 数据流创建读取器，会先读取并校验 8 字节签名
+    pub async fn from_png(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 8];
+        let filled = read_full(&mut reader, &mut header).await?;
+        if filled < header.len() || header != Png::STANDARD_HEADER {
+            // 多读几个字节，以便识别魔数较长的格式
+            let mut sniffed = [0u8; sniff::SNIFF_LEN];
+            sniffed[..filled].copy_from_slice(&header[..filled]);
+            let extra = if filled == header.len() {
+                read_full(&mut reader, &mut sniffed[filled..]).await?
+            } else {
+                0
+            };
+            return Err(sniff::signature_error(&sniffed[..filled + extra]));
+        }
+        let mut chunk_reader = Self::new(reader);
+        chunk_reader.bytes_read = header.len() as u64;
+        Ok(chunk_reader)
+    }
+
+    /// 取回内部的读取器
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// 读取下一个 chunk；在 chunk 边界处遇到 EOF 时返回 `Ok(None)`。
+    /// 出错后不再读取，之后总是返回 `Ok(None)`
+    pub async fn next_chunk(&mut self) -> Result<Option<Chunk>> {
+        if self.done {
+            return Ok(None);
+        }
+        let result = self.read_chunk().await;
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result
+    }
+
+    async fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        let mut head = [0u8; 8];
+        let filled = read_full(&mut self.reader, &mut head).await?;
+        if filled == 0 {
+            return Ok(None);
+        }
+        if filled < head.len() {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        let length = u32::from_be_bytes([head[0], head[1], head[2], head[3]]);
+        self.limits.check_chunk_size(length)?;
+        self.limits
+            .check_chunk_type([head[4], head[5], head[6], head[7]])?;
+        self.limits.check_chunk_count(self.chunks_read + 1)?;
+        self.bytes_read += length as u64 + 12;
+        self.limits.check_file_size(self.bytes_read)?;
+        let mut bytes = head.to_vec();
+        // 不按 length 预先分配，避免损坏的长度字段导致巨量内存分配
+        let expected = length as u64 + 4;
+        let read = (&mut self.reader)
+            .take(expected)
+            .read_to_end(&mut bytes)
+            .await?;
+        if (read as u64) < expected {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        self.chunks_read += 1;
+        let chunk = Chunk::try_from(bytes.as_slice())?;
+        if let Some(progress) = &self.progress {
+            progress.chunk(chunk.chunk_type(), length);
+        }
+        Ok(Some(chunk))
+    }
+}
+
+impl Png {
+    /// 从 `AsyncRead` 中读取完整的 PNG 文件
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Png> {
+        Png::from_async_reader_with_limits(reader, &Limits::default()).await
+    }
+
+    /// 与 `from_async_reader` 相同，但使用指定的安全限制
+    pub async fn from_async_reader_with_limits<R: AsyncRead + Unpin>(
+        reader: R,
+        limits: &Limits,
+    ) -> Result<Png> {
+        let mut reader = AsyncChunkReader::from_png(reader)
+            .await?
+            .with_limits(limits.clone());
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk().await? {
+            chunks.push(chunk);
+        }
+        Ok(Png::from_chunks(chunks))
+    }
+
+    /// 把 Png 逐个 chunk 写入 `writer` 并 flush，返回写入的字节数
+    pub async fn write_async<W: AsyncWrite + Unpin>(&self, mut writer: W) -> Result<u64> {
+        writer.write_all(&Png::STANDARD_HEADER).await?;
+        let mut written = Png::STANDARD_HEADER.len() as u64;
+        for chunk in self.chunks() {
+            let bytes = chunk.as_bytes();
+            writer.write_all(&bytes).await?;
+            written += bytes.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
+
+/// 尽可能填满 `buf`，返回实际读取的字节数（只有遇到 EOF 时才会小于 `buf.len()`）
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]).await? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        block_on(async {
+            let mut png = Png::from_async_reader(&PNG_FILE[..]).await.unwrap();
+            assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+
+            png.append_chunk(
+                Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hello".to_vec()).unwrap(),
+            );
+            let mut output = Vec::new();
+            let written = png.write_async(&mut output).await.unwrap();
+            assert_eq!(written, output.len() as u64);
+            assert_eq!(output, png.as_bytes());
+        });
+    }
+
+    #[test]
+    fn test_chunk_stream() {
+        block_on(async {
+            let mut reader = AsyncChunkReader::from_png(&PNG_FILE[..]).await.unwrap();
+            let mut types = Vec::new();
+            while let Some(chunk) = reader.next_chunk().await.unwrap() {
+                types.push(chunk.chunk_type().to_string());
+            }
+            assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+            assert!(reader.next_chunk().await.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_errors() {
+        block_on(async {
+            assert!(matches!(
+                Png::from_async_reader(&b"not a png"[..]).await,
+                Err(PngmeError::InvalidSignature)
+            ));
+
+            let truncated = &PNG_FILE[..PNG_FILE.len() - 2];
+            let mut reader = AsyncChunkReader::from_png(truncated).await.unwrap();
+            let mut results = Vec::new();
+            loop {
+                match reader.next_chunk().await {
+                    Ok(Some(_)) => results.push(true),
+                    Ok(None) => break,
+                    Err(_) => results.push(false),
+                }
+            }
+            assert_eq!(results, [true, true, false]);
+
+            let limits = Limits {
+                max_chunks: 1,
+                ..Limits::default()
+            };
+            assert!(Png::from_async_reader_with_limits(&PNG_FILE[..], &limits)
+                .await
+                .is_err());
+        });
+    }
+}
@@ -0,0 +1,628 @@
+//! # Batch
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了目录的批处理：收集目录中文件名匹配模式（默认 `*.png`）的文件，
+//! 用 `--jobs` 个线程依次交给命令处理，并逐个报告成功或失败。
+//! 单个文件失败不会中断其他文件，全部处理完后才返回错误。
+//! 原地修改时每次覆盖都记入目录下的日志，崩溃后可以用 `pngme recover` 恢复（见 `journal` 模块）。
+//! 收到 SIGINT/SIGTERM 后不再开始新的文件，正在处理的文件照常完成（见 `cancel` 模块）。
+//!
+//! 修改文件的命令默认原地修改；指定 `--output-dir` 时结果写入该目录，子目录结构不变，
+//! 文件名由 `--name-template` 决定，例如 `{stem}.clean.png`。
+//!
+//! 遍历时默认跳过符号链接；`--follow-symlinks` 跟随链接时按解析后的路径记录访问过的目录和文件，
+//! 链接成环或多个链接指向同一位置时只处理一次。`--preserve-hardlinks` 把同一组硬链接视为一个文件，
+//! 并原地覆盖它的内容，而不是替换文件（见 `vfs::PreserveHardlinks`）。
+
+use std::cell::OnceCell;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use sha2::{Digest, Sha256};
+
+use crate::args::BatchArgs;
+use crate::cancel::{self, CancelToken};
+use crate::clock::SystemClock;
+use crate::journal::{Journal, JournalFs, JOURNAL_DIR};
+use crate::metadata::TimeChunk;
+use crate::output::say;
+use crate::preview;
+use crate::progress::Progress;
+use crate::template;
+use crate::vfs::{PreserveHardlinks, Vfs};
+use crate::{PngmeError, Result};
+
+/// `--name-template` 的默认值：保留原文件名
+pub const DEFAULT_NAME_TEMPLATE: &str = "{stem}.{ext}";
+/// `--glob` 的默认值
+pub const DEFAULT_GLOB: &str = "*.png";
+
+/// `encode --convert` 没有给出 `--glob` 时处理的文件：PNG 和可以转换的格式
+pub const CONVERT_GLOB: &str = "*.png|*.jpg|*.jpeg|*.gif|*.bmp";
+
+/// `{hash}` 使用的十六进制位数
+const HASH_LEN: usize = 16;
+
+/// 按 `args` 收集目录中文件名匹配 `--glob` 的文件
+pub fn collect_files(fs: &dyn Vfs, dir: &Path, args: &BatchArgs) -> Result<Vec<PathBuf>> {
+    let mut visited = HashSet::new();
+    visited.insert(fs.canonicalize(dir));
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    walk(fs, dir, args, &mut visited, &mut seen, &mut files)?;
+    Ok(files)
+}
+
+/// 同一个文件的标识：硬链接的 inode，或解析符号链接后的路径
+#[derive(PartialEq, Eq, Hash)]
+enum FileKey {
+    HardLink(u64, u64),
+    Path(PathBuf),
+}
+
+fn walk(
+    fs: &dyn Vfs,
+    dir: &Path,
+    args: &BatchArgs,
+    visited: &mut HashSet<PathBuf>,
+    seen: &mut HashSet<FileKey>,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for path in fs.list(dir)? {
+        if !args.follow_symlinks && fs.is_symlink(&path) {
+            continue;
+        }
+        if path.file_name().is_some_and(|name| name == JOURNAL_DIR) {
+            continue;
+        }
+        if fs.is_dir(&path) {
+            // 指向上级目录的链接会形成环，已经访问过的目录不再进入
+            if args.recursive && visited.insert(fs.canonicalize(&path)) {
+                walk(fs, &path, args, visited, seen, files)?;
+            }
+            continue;
+        }
+        // 对象存储会一次列出前缀下的所有对象，非递归时只保留直接子项
+        if !args.recursive && path.parent() != Some(dir) {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !matches_glob(&args.glob, &name) {
+            continue;
+        }
+        let key = match fs.hard_link_id(&path) {
+            Some((device, inode)) if args.preserve_hardlinks => FileKey::HardLink(device, inode),
+            _ => FileKey::Path(fs.canonicalize(&path)),
+        };
+        if seen.insert(key) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 文件名是否匹配模式，`*` 匹配任意个字符，`?` 匹配一个字符，不区分 ASCII 大小写；
+/// 多个模式用 `|` 分隔，匹配其中任意一个即可
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    pattern
+        .split('|')
+        .any(|pattern| matches_pattern(pattern, name))
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当前匹配到的名字位置
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&name[n]) => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 对目录中的每个文件执行 `f`，报告每个文件的结果；有文件失败时返回错误。
+/// `f` 应当通过传给它的文件系统写入，`--preserve-hardlinks` 才能生效
+pub fn run<F>(fs: &dyn Vfs, dir: &Path, args: &BatchArgs, f: F) -> Result<()>
+where
+    F: Fn(&dyn Vfs, &Path) -> Result<()> + Sync,
+{
+    let files = collect_files(fs, dir, args)?;
+    if files.is_empty() {
+        say!("No files matching {} in {}", args.glob, dir.display());
+        return Ok(());
+    }
+    run_in(fs, dir, &files, args, f)
+}
+
+/// 对 `dir` 中已经收集好的 `files` 执行 `f`：按 `--preserve-hardlinks` 包装文件系统，
+/// 原地修改时（没有 `--output-dir` 和 `--no-journal`）把覆盖记入 `dir` 的日志
+pub fn run_in<F>(fs: &dyn Vfs, dir: &Path, files: &[PathBuf], args: &BatchArgs, f: F) -> Result<()>
+where
+    F: Fn(&dyn Vfs, &Path) -> Result<()> + Sync,
+{
+    let preserve = PreserveHardlinks(fs);
+    let progress = fs.progress();
+    let fs: &dyn Vfs = if args.preserve_hardlinks {
+        &preserve
+    } else {
+        fs
+    };
+    if args.output_dir.is_some() || args.no_journal {
+        return run_files(files, args.jobs, &*progress, cancel::shutdown(), |path| {
+            f(fs, path)
+        });
+    }
+    let journal = Journal::open(fs, dir)?;
+    let journaled = JournalFs::new(fs, &journal);
+    let result = run_files(files, args.jobs, &*progress, cancel::shutdown(), |path| {
+        f(&journaled, path)
+    });
+    journal.close();
+    result
+}
+
+/// 用 `jobs` 个线程对给定的文件执行 `f`，报告每个文件的结果；有文件失败时返回错误。
+/// 每个文件完成后调用 `progress.file_done`。`cancel` 被取消后不再开始新的文件，
+/// 正在处理的文件照常完成，汇总后返回 `PngmeError::Cancelled`
+pub fn run_files<F>(
+    files: &[PathBuf],
+    jobs: usize,
+    progress: &dyn Progress,
+    cancel: &CancelToken,
+    f: F,
+) -> Result<()>
+where
+    F: Fn(&Path) -> Result<()> + Sync,
+{
+    let _graceful = cancel::graceful();
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let failed = Mutex::new(Vec::new());
+    progress.files(files.len() as u64);
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, files.len().max(1)) {
+            scope.spawn(|| {
+                while !cancel.is_cancelled() {
+                    let Some(path) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    tracing::debug!(path = %path.display(), "batch: start");
+                    let result = f(path);
+                    done.fetch_add(1, Ordering::Relaxed);
+                    // 持有锁再输出，避免多个线程的报告交错
+                    let mut failed = failed.lock().unwrap();
+                    progress.suspend(&mut || match &result {
+                        Ok(()) => say!("{}: ok", display(path)),
+                        Err(e) => say!(
+                            "{}: Error: {}",
+                            display(path),
+                            preview::escape_lines(&e.to_string())
+                        ),
+                    });
+                    progress.file_done(path, result.is_ok());
+                    if let Err(e) = result {
+                        failed.push((path, e));
+                    }
+                }
+            });
+        }
+    });
+
+    // 并行处理时失败的文件在输出中很分散，最后按路径排序再列一遍
+    let mut failed = failed.into_inner().unwrap();
+    failed.sort_by_key(|(path, _)| *path);
+    let done = done.into_inner();
+    progress.suspend(&mut || {
+        if done < files.len() {
+            say!(
+                "Interrupted after {} of {} files: {} succeeded, {} failed",
+                done,
+                files.len(),
+                done - failed.len(),
+                failed.len()
+            );
+        } else {
+            say!(
+                "Processed {} files: {} succeeded, {} failed",
+                files.len(),
+                files.len() - failed.len(),
+                failed.len()
+            );
+        }
+        for (path, e) in &failed {
+            say!(
+                "  failed: {}: {}",
+                display(path),
+                preview::escape_lines(&e.to_string())
+            );
+        }
+    });
+    if done < files.len() {
+        return Err(PngmeError::Cancelled);
+    }
+    if !failed.is_empty() {
+        return Err(PngmeError::Batch(format!(
+            "{} of {} files failed",
+            failed.len(),
+            files.len()
+        )));
+    }
+    Ok(())
+}
+
+/// 批处理时不能指定单个输出路径
+pub fn reject_output(option: &str, output: Option<&PathBuf>) -> Result<()> {
+    match output {
+        Some(_) => Err(PngmeError::Usage(format!(
+            "{} cannot be used when processing a directory",
+            option
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// 不修改文件的命令不接受 `--output-dir`
+pub fn reject_output_dir(args: &BatchArgs) -> Result<()> {
+    match args.output_dir {
+        Some(_) => Err(PngmeError::Usage(
+            "--output-dir can only be used with commands that modify files".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 批处理中 `path` 的输出路径：没有 `--output-dir` 时返回 `None`，表示原地修改；
+/// 否则为输出目录下与 `path` 相对 `dir` 相同的子目录中、按 `--name-template` 命名的文件，
+/// 子目录不存在时会被创建
+pub fn output_path(
+    fs: &dyn Vfs,
+    dir: &Path,
+    path: &Path,
+    args: &BatchArgs,
+) -> Result<Option<PathBuf>> {
+    let Some(output_dir) = &args.output_dir else {
+        return Ok(None);
+    };
+    let name = render_name(fs, &args.name_template, path)?;
+    let relative = path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(dir).ok())
+        .unwrap_or(Path::new(""));
+    let out_dir = output_dir.join(relative);
+    fs.create_dir_all(&out_dir)?;
+    Ok(Some(out_dir.join(name)))
+}
+
+/// 按模板为 `path` 生成输出文件名，`{date}` 为当前的 UTC 日期，见 `output_name`
+pub fn render_name(fs: &dyn Vfs, name_template: &str, path: &Path) -> Result<String> {
+    let date = TimeChunk::now(&SystemClock)?;
+    let date = format!("{:04}-{:02}-{:02}", date.year, date.month, date.day);
+    output_name(name_template, path, &date, || fs.read(path).ok())
+}
+
+/// 按模板生成输出文件名。变量：`{stem}` 和 `{ext}` 是原文件名去掉扩展名的部分和扩展名，
+/// `{hash}` 是原文件内容 SHA-256 的前 16 位十六进制（用到时才调用 `read` 读取文件），
+/// `{date}` 是 `date`。结果必须是一个文件名，不能包含路径分隔符
+pub fn output_name(
+    name_template: &str,
+    path: &Path,
+    date: &str,
+    read: impl Fn() -> Option<Vec<u8>>,
+) -> Result<String> {
+    let part = |s: Option<&std::ffi::OsStr>| s.unwrap_or_default().to_string_lossy().into_owned();
+    let hash = OnceCell::new();
+    let name = template::render_with(name_template, "{", "}", |name| match name {
+        "stem" => Some(part(path.file_stem())),
+        "ext" => Some(part(path.extension())),
+        "date" => Some(date.to_string()),
+        "hash" => hash
+            .get_or_init(|| {
+                read().map(|data| {
+                    let digest = Sha256::digest(data);
+                    digest
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<String>()[..HASH_LEN]
+                        .to_string()
+                })
+            })
+            .clone(),
+        _ => None,
+    })?;
+    let components: Vec<Component> = Path::new(&name).components().collect();
+    if !matches!(components[..], [Component::Normal(_)]) || name.contains(['/', '\\']) {
+        return Err(PngmeError::Batch(format!(
+            "the name template must produce a file name, got {:?}",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+/// 目录中的文件名可能包含控制字符，输出前转义
+fn display(path: &Path) -> String {
+    preview::escape(&path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn memory_fs() -> MemoryFs {
+        let fs = MemoryFs::new();
+        for path in [
+            "images/a.png",
+            "images/b.PNG",
+            "images/notes.txt",
+            "images/icons/c.png",
+        ] {
+            fs.insert(path, Vec::new());
+        }
+        fs
+    }
+
+    fn batch_args(recursive: bool, glob: &str, jobs: usize) -> BatchArgs {
+        BatchArgs {
+            recursive,
+            glob: glob.to_string(),
+            jobs,
+            ..BatchArgs::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("*.png", "a.png"));
+        assert!(matches_glob("*.png", "A.PNG"));
+        assert!(matches_glob("*.png", ".png"));
+        assert!(!matches_glob("*.png", "a.png.txt"));
+        assert!(matches_glob("icon-?.png", "icon-1.png"));
+        assert!(!matches_glob("icon-?.png", "icon-10.png"));
+        assert!(matches_glob("*-*.png", "a-b-c.png"));
+        assert!(matches_glob(CONVERT_GLOB, "photo.JPG"));
+        assert!(matches_glob(CONVERT_GLOB, "a.png"));
+        assert!(!matches_glob(CONVERT_GLOB, "notes.txt"));
+        assert!(matches_glob("*", ""));
+        assert!(!matches_glob("a", ""));
+    }
+
+    #[test]
+    fn test_collect_files() {
+        let fs = memory_fs();
+        let dir = Path::new("images");
+        assert_eq!(
+            collect_files(&fs, dir, &batch_args(false, "*.png", 1)).unwrap(),
+            [PathBuf::from("images/a.png"), PathBuf::from("images/b.PNG")]
+        );
+        assert_eq!(
+            collect_files(&fs, dir, &batch_args(true, "*.png", 1)).unwrap(),
+            [
+                PathBuf::from("images/a.png"),
+                PathBuf::from("images/b.PNG"),
+                PathBuf::from("images/icons/c.png")
+            ]
+        );
+        assert_eq!(
+            collect_files(&fs, dir, &batch_args(true, "*", 1))
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_links() {
+        use crate::vfs::StdFs;
+        use std::fs;
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("pngme-links-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.png"), b"a").unwrap();
+        fs::write(dir.join("sub/b.png"), b"b").unwrap();
+        fs::hard_link(dir.join("a.png"), dir.join("c.png")).unwrap();
+        symlink(dir.join("sub/b.png"), dir.join("link.png")).unwrap();
+        // 指向上级目录，跟随时会形成环
+        symlink(&dir, dir.join("sub/loop")).unwrap();
+
+        let names = |args: &BatchArgs| -> Vec<String> {
+            collect_files(&StdFs, &dir, args)
+                .unwrap()
+                .iter()
+                .map(|path| path.strip_prefix(&dir).unwrap().display().to_string())
+                .collect()
+        };
+        let mut args = batch_args(true, "*.png", 1);
+        assert_eq!(names(&args), ["a.png", "c.png", "sub/b.png"]);
+
+        args.follow_symlinks = true;
+        assert_eq!(names(&args), ["a.png", "c.png", "link.png"]);
+
+        args.preserve_hardlinks = true;
+        assert_eq!(names(&args), ["a.png", "link.png"]);
+
+        // 保留硬链接时原地写入，两个路径看到相同的内容
+        run(&StdFs, &dir, &args, |fs, path| {
+            let mut data = fs.read(path)?;
+            data.push(b'!');
+            Ok(fs.write(path, &data)?)
+        })
+        .unwrap();
+        assert_eq!(fs::read(dir.join("c.png")).unwrap(), b"a!");
+        assert_eq!(fs::read(dir.join("sub/b.png")).unwrap(), b"b!");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_reports_progress() {
+        use crate::progress::{tests::Recorder, ProgressFs};
+        use std::sync::Arc;
+
+        let memory = memory_fs();
+        let recorder = Arc::new(Recorder::default());
+        let fs = ProgressFs::new(&memory, recorder.clone());
+        let result = run(
+            &fs,
+            Path::new("images"),
+            &batch_args(true, "*", 2),
+            |fs, path| {
+                fs.read(path)?;
+                match path.extension().is_some_and(|ext| ext == "txt") {
+                    true => Err(PngmeError::InvalidSignature),
+                    false => Ok(()),
+                }
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(recorder.total.load(Ordering::Relaxed), 4);
+        let files = recorder.files.lock().unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files.iter().filter(|(_, ok)| !ok).count(), 1);
+    }
+
+    #[test]
+    fn test_run_reports_failures() {
+        let fs = memory_fs();
+        let seen = Mutex::new(Vec::new());
+        let result = run(
+            &fs,
+            Path::new("images"),
+            &batch_args(true, "*", 3),
+            |_, path| {
+                seen.lock().unwrap().push(path.to_path_buf());
+                if path.extension().is_some_and(|ext| ext == "txt") {
+                    return Err(PngmeError::InvalidSignature);
+                }
+                Ok(())
+            },
+        );
+        assert!(matches!(result, Err(PngmeError::Batch(_))));
+        assert_eq!(seen.into_inner().unwrap().len(), 4);
+
+        assert!(run(
+            &fs,
+            Path::new("images"),
+            &batch_args(false, "*.png", 0),
+            |_, _| Ok(())
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_run_journals_in_place_edits() {
+        let fs = memory_fs();
+        let args = batch_args(true, "*", 2);
+        run(&fs, Path::new("images"), &args, |fs, path| {
+            fs.write(path, b"edited").map_err(PngmeError::from)
+        })
+        .unwrap();
+        assert_eq!(fs.get(Path::new("images/icons/c.png")).unwrap(), b"edited");
+        // 全部完成后日志被删除，日志目录也不会被当作输入
+        assert!(!fs.is_dir(&Path::new("images").join(JOURNAL_DIR)));
+
+        fs.insert(
+            Path::new("images").join(JOURNAL_DIR).join("journal.json"),
+            b"[]".to_vec(),
+        );
+        assert!(run(&fs, Path::new("images"), &args, |_, _| Ok(())).is_err());
+        let files = collect_files(&fs, Path::new("images"), &args).unwrap();
+        assert_eq!(files.len(), 4);
+    }
+
+    #[test]
+    fn test_run_files_stops_when_cancelled() {
+        use crate::progress::NoProgress;
+
+        let files: Vec<PathBuf> = (0..10)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+        let cancel = CancelToken::new();
+        let seen = AtomicUsize::new(0);
+        let result = run_files(&files, 1, &NoProgress, &cancel, |_| {
+            // 处理第三个文件时收到信号，这个文件仍然完成
+            if seen.fetch_add(1, Ordering::Relaxed) == 2 {
+                cancel.cancel();
+            }
+            Ok(())
+        });
+        assert!(matches!(result, Err(PngmeError::Cancelled)));
+        assert_eq!(seen.into_inner(), 3);
+    }
+
+    #[test]
+    fn test_reject_output() {
+        assert!(reject_output("--out", None).is_ok());
+        assert!(reject_output("--out", Some(&PathBuf::from("out.png"))).is_err());
+        let mut args = BatchArgs::default();
+        assert!(reject_output_dir(&args).is_ok());
+        args.output_dir = Some(PathBuf::from("out"));
+        assert!(matches!(
+            reject_output_dir(&args),
+            Err(PngmeError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_output_name() {
+        let path = Path::new("images/photo.final.png");
+        let read = || Some(b"abc".to_vec());
+        let name = |template| output_name(template, path, "2026-10-16", read);
+        assert_eq!(name(DEFAULT_NAME_TEMPLATE).unwrap(), "photo.final.png");
+        assert_eq!(name("{stem}.clean.png").unwrap(), "photo.final.clean.png");
+        assert_eq!(
+            name("{date}-{hash}.{ext}").unwrap(),
+            "2026-10-16-ba7816bf8f01cfea.png"
+        );
+        for template in [
+            "../{stem}.png",
+            "sub/{stem}.png",
+            "..",
+            "{missing}",
+            "{stem",
+        ] {
+            assert!(name(template).is_err(), "{}", template);
+        }
+        assert!(output_name("{hash}", path, "", || None).is_err());
+    }
+
+    #[test]
+    fn test_output_path() {
+        let fs = memory_fs();
+        let mut args = batch_args(true, "*.png", 1);
+        let path = Path::new("images/icons/c.png");
+        assert_eq!(
+            output_path(&fs, Path::new("images"), path, &args).unwrap(),
+            None
+        );
+        args.output_dir = Some(PathBuf::from("out"));
+        args.name_template = "{stem}.clean.png".to_string();
+        assert_eq!(
+            output_path(&fs, Path::new("images"), path, &args).unwrap(),
+            Some(PathBuf::from("out/icons/c.clean.png"))
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! # Bundle
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块用于审查 Android（`.apk`、`.aab`）和 iOS（`.ipa`）安装包中的 PNG 资源。
+//! 这些安装包都是 zip 格式，遍历由 `archive` 模块完成。
+//!
+//! Xcode 会把 IPA 中的 PNG 转换为 Apple 私有的 CgBI 格式：`IHDR` 之前多出一个 `CgBI` chunk，
+//! `IDAT` 是不带 zlib 头的 deflate 数据，像素为预乘 alpha 的 BGRA。chunk 层面的解析不受影响，
+//! 但标准解码器无法显示这些图片，报告中会单独标出。
+
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::archive::{self, ArchiveKind};
+use crate::chunk::Chunk;
+use crate::png::Png;
+use crate::preview;
+use crate::text::{CompressedTextChunk, InternationalTextChunk, TextChunk};
+use crate::Result;
+
+/// 安装包类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleKind {
+    Apk,
+    Ipa,
+}
+
+impl BundleKind {
+    /// 根据扩展名判断安装包类型
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "apk" | "aab" => Some(BundleKind::Apk),
+            "ipa" => Some(BundleKind::Ipa),
+            _ => None,
+        }
+    }
+}
+
+/// 单个 PNG 的审查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PngReport {
+    /// 在安装包中的路径
+    pub name: String,
+    /// 是否为 Apple CgBI 格式
+    pub cgbi: bool,
+    /// 所有辅助 chunk 的类型，按出现顺序排列
+    pub ancillary: Vec<String>,
+    /// tEXt、zTXt、iTXt 中的关键字
+    pub text_keywords: Vec<String>,
+    /// 解析失败时的错误信息
+    pub error: Option<String>,
+}
+
+/// 整个安装包的审查报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleReport {
+    pub kind: BundleKind,
+    pub pngs: Vec<PngReport>,
+}
+
+impl PngReport {
+    fn new(name: &str, png: Result<Png>) -> Self {
+        let mut report = PngReport {
+            name: name.to_string(),
+            cgbi: false,
+            ancillary: Vec::new(),
+            text_keywords: Vec::new(),
+            error: None,
+        };
+        let png = match png {
+            Ok(png) => png,
+            Err(e) => {
+                report.error = Some(e.to_string());
+                return report;
+            }
+        };
+
+        for chunk in png.chunks() {
+            let chunk_type = chunk.chunk_type();
+            if &chunk_type.bytes() == b"CgBI" {
+                report.cgbi = true;
+            } else if chunk_type.is_ancillary() {
+                report.ancillary.push(chunk_type.to_string());
+                report.text_keywords.extend(text_keyword(chunk));
+            }
+        }
+        report
+    }
+
+    /// 是否带有可以去除的元数据
+    pub fn has_metadata(&self) -> bool {
+        !self.ancillary.is_empty()
+    }
+}
+
+impl BundleReport {
+    /// 带有元数据的 PNG 数量
+    pub fn with_metadata(&self) -> usize {
+        self.pngs.iter().filter(|png| png.has_metadata()).count()
+    }
+
+    /// CgBI 格式的 PNG 数量
+    pub fn cgbi(&self) -> usize {
+        self.pngs.iter().filter(|png| png.cgbi).count()
+    }
+
+    /// 解析失败的 PNG 数量
+    pub fn errors(&self) -> usize {
+        self.pngs.iter().filter(|png| png.error.is_some()).count()
+    }
+}
+
+/// 审查安装包中的所有 PNG
+pub fn audit<R: Read>(reader: R, kind: BundleKind) -> Result<BundleReport> {
+    let mut pngs = Vec::new();
+    archive::for_each_png(reader, ArchiveKind::Zip, |name, png| {
+        pngs.push(PngReport::new(name, png));
+        Ok(())
+    })?;
+    Ok(BundleReport { kind, pngs })
+}
+
+/// 解码文本 chunk 的关键字，不是文本 chunk 或格式无效时返回 `None`
+fn text_keyword(chunk: &Chunk) -> Option<String> {
+    match &chunk.chunk_type().bytes() {
+        b"tEXt" => TextChunk::try_from(chunk).ok().map(|text| text.keyword),
+        b"zTXt" => CompressedTextChunk::try_from(chunk)
+            .ok()
+            .map(|text| text.keyword),
+        b"iTXt" => InternationalTextChunk::try_from(chunk)
+            .ok()
+            .map(|text| text.keyword),
+        _ => None,
+    }
+}
+
+impl fmt::Display for BundleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for png in &self.pngs {
+            if let Some(error) = &png.error {
+                writeln!(f, "{}: Error: {}", preview::escape(&png.name), error)?;
+                continue;
+            }
+            if !png.cgbi && !png.has_metadata() {
+                continue;
+            }
+            write!(f, "{}:", preview::escape(&png.name))?;
+            if png.cgbi {
+                write!(f, " CgBI")?;
+            }
+            if png.has_metadata() {
+                write!(f, " {}", png.ancillary.join(" "))?;
+            }
+            if !png.text_keywords.is_empty() {
+                let keywords: Vec<String> = png
+                    .text_keywords
+                    .iter()
+                    .map(|k| preview::escape(k))
+                    .collect();
+                write!(f, " (keywords: {})", keywords.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} PNG files, {} with metadata, {} CgBI, {} unreadable",
+            self.pngs.len(),
+            self.with_metadata(),
+            self.cgbi(),
+            self.errors()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn png_with(types: &[&str]) -> Vec<u8> {
+        let chunks = types
+            .iter()
+            .map(|chunk_type| match *chunk_type {
+                "tEXt" => TextChunk::new("Software", "Photoshop")
+                    .unwrap()
+                    .to_chunk()
+                    .unwrap(),
+                _ => Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new()).unwrap(),
+            })
+            .collect();
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    fn testing_bundle(members: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in members {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_kind_from_path() {
+        let kind = |path: &str| BundleKind::from_path(Path::new(path));
+        assert_eq!(kind("app-release.apk"), Some(BundleKind::Apk));
+        assert_eq!(kind("app.aab"), Some(BundleKind::Apk));
+        assert_eq!(kind("App.IPA"), Some(BundleKind::Ipa));
+        assert_eq!(kind("assets.zip"), None);
+    }
+
+    #[test]
+    fn test_audit_apk() {
+        let bytes = testing_bundle(&[
+            (
+                "res/drawable/clean.png",
+                png_with(&["IHDR", "IDAT", "IEND"]),
+            ),
+            (
+                "res/drawable/tagged.png",
+                png_with(&["IHDR", "tEXt", "IDAT", "IEND"]),
+            ),
+            ("res/drawable/broken.png", b"not a png".to_vec()),
+            ("classes.dex", b"dex".to_vec()),
+        ]);
+        let report = audit(bytes.as_slice(), BundleKind::Apk).unwrap();
+        assert_eq!(report.pngs.len(), 3);
+        assert_eq!(report.with_metadata(), 1);
+        assert_eq!(report.errors(), 1);
+        assert_eq!(report.pngs[1].ancillary, ["tEXt"]);
+        assert_eq!(report.pngs[1].text_keywords, ["Software"]);
+
+        let text = report.to_string();
+        assert!(text.contains("res/drawable/tagged.png: tEXt (keywords: Software)"));
+        assert!(!text.contains("clean.png"));
+        assert!(text.ends_with("3 PNG files, 1 with metadata, 0 CgBI, 1 unreadable"));
+    }
+
+    #[test]
+    fn test_audit_ipa_detects_cgbi() {
+        let bytes = testing_bundle(&[(
+            "Payload/App.app/AppIcon60x60@2x.png",
+            png_with(&["CgBI", "IHDR", "IDAT", "IEND"]),
+        )]);
+        let report = audit(bytes.as_slice(), BundleKind::Ipa).unwrap();
+        assert_eq!(report.cgbi(), 1);
+        assert!(!report.pngs[0].has_metadata());
+        assert!(report
+            .to_string()
+            .contains("Payload/App.app/AppIcon60x60@2x.png: CgBI"));
+    }
+
+    #[test]
+    fn test_report_serializes_to_json() {
+        let bytes = testing_bundle(&[("icon.png", png_with(&["IHDR", "IEND"]))]);
+        let report = audit(bytes.as_slice(), BundleKind::Apk).unwrap();
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.starts_with(r#"{"kind":"apk","pngs":[{"name":"icon.png","cgbi":false"#));
+    }
+}
@@ -0,0 +1,139 @@
+//! # Cancel
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块包含了 `CancelToken` 的实现，宿主程序可以用它中止耗时较长的操作。
+//! `child` 创建的子标记在父标记取消时也视为已取消，可以单独取消一部分工作（例如 daemon 的一个连接）。
+//!
+//! 命令行程序启动时调用 `install_signal_handler`。批处理和 daemon 运行期间持有 `graceful` 返回的标记，
+//! 这时收到 SIGINT/SIGTERM 只会取消 `shutdown()`：它们处理完手头的文件后停止，
+//! 进程以 `EXIT_INTERRUPTED` 退出；再收到一次信号，或者没有这样的操作在运行时，进程立即退出。
+//! 除 `--preserve-hardlinks` 的原地覆盖外，写入都是先写临时文件再改名，立即退出也不会留下写了一半的 PNG。
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Once, OnceLock};
+
+use crate::{PngmeError, Result};
+
+/// 可在线程间共享的取消标记，克隆后的标记共享同一状态
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    parent: Option<CancelToken>,
+}
+
+impl CancelToken {
+    /// 创建一个未取消的标记
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建一个子标记：取消它不影响本标记，本标记取消时它也视为已取消
+    pub fn child(&self) -> Self {
+        CancelToken(Arc::new(Inner {
+            cancelled: AtomicBool::new(false),
+            parent: Some(self.clone()),
+        }))
+    }
+
+    /// 请求取消
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 检查本标记或任何一个父标记是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+            || self
+                .0
+                .parent
+                .as_ref()
+                .is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// 已请求取消时返回 `PngmeError::Cancelled`
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(PngmeError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 被信号中断时的退出码（128 + SIGINT）
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+static SHUTDOWN: OnceLock<CancelToken> = OnceLock::new();
+
+/// 正在运行的可以平稳停止的操作数
+static GRACEFUL: AtomicUsize = AtomicUsize::new(0);
+
+/// 进程级的关闭标记，收到 SIGINT/SIGTERM 时被取消
+pub fn shutdown() -> &'static CancelToken {
+    SHUTDOWN.get_or_init(CancelToken::new)
+}
+
+/// 平稳停止标记，存在期间收到的第一个信号只取消 `shutdown()`
+pub struct Graceful(());
+
+/// 声明一个会定期检查 `shutdown()` 的操作正在运行
+pub fn graceful() -> Graceful {
+    GRACEFUL.fetch_add(1, Ordering::SeqCst);
+    Graceful(())
+}
+
+impl Drop for Graceful {
+    fn drop(&mut self) {
+        GRACEFUL.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 安装 SIGINT/SIGTERM（Windows 上为 Ctrl-C 和关闭控制台）的处理函数，重复调用没有作用。
+/// 宿主程序已经安装了自己的处理函数时不覆盖它
+pub fn install_signal_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = ctrlc::set_handler(|| {
+            let shutdown = shutdown();
+            if GRACEFUL.load(Ordering::SeqCst) == 0 || shutdown.is_cancelled() {
+                std::process::exit(EXIT_INTERRUPTED);
+            }
+            shutdown.cancel();
+            eprintln!("Interrupted: finishing in-flight files, signal again to abort immediately");
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_is_shared_between_clones() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(token.check().is_ok());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(token.check(), Err(PngmeError::Cancelled)));
+    }
+
+    #[test]
+    fn test_child_follows_parent() {
+        let parent = CancelToken::new();
+        let child = parent.child();
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+
+        let child = parent.child();
+        parent.cancel();
+        assert!(child.is_cancelled());
+    }
+}
@@ -0,0 +1,123 @@
+//! # Capabilities
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块描述当前构建支持的功能：启用的 feature、加密和压缩算法、信息的载体和写入位置等。
+//! 封装库、daemon 和 `pngme --version --verbose` 都从 `capabilities()` 获取，
+//! 图形界面可以据此隐藏当前构建不支持的选项，而不是等到调用时报错。
+
+use serde::Serialize;
+
+/// 当前构建支持的功能
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// crate 版本
+    pub version: &'static str,
+    /// 编译时启用的 feature
+    pub features: Vec<&'static str>,
+    /// 加密信息使用的算法
+    pub ciphers: Vec<&'static str>,
+    /// 从口令派生密钥的算法
+    pub key_derivation: Vec<&'static str>,
+    /// 签名算法
+    pub signatures: Vec<&'static str>,
+    /// 信息完整性标签的算法
+    pub integrity: Vec<&'static str>,
+    /// 压缩信息使用的算法
+    pub compressors: Vec<&'static str>,
+    /// 可以承载信息的位置
+    pub carriers: Vec<&'static str>,
+    /// 写入的 chunk 可以放置的位置
+    pub placements: Vec<&'static str>,
+    /// 是否能把信息写入像素（`--method qr` 渲染为 QR 码，`--method lsb` 写入样本的最低位，`watermark` 写入鲁棒水印）
+    pub pixels: bool,
+    /// 是否支持 `pngme daemon`（仅 Unix，需要 `cli` feature）
+    pub daemon: bool,
+}
+
+/// 当前构建支持的功能
+pub fn capabilities() -> Capabilities {
+    let features = [
+        ("chrono", cfg!(feature = "chrono")),
+        ("convert", cfg!(feature = "convert")),
+        ("ffi", cfg!(feature = "ffi")),
+        ("python", cfg!(feature = "python")),
+        ("rayon", cfg!(feature = "rayon")),
+        ("s3", cfg!(feature = "s3")),
+        ("serde", cfg!(feature = "serde")),
+        ("time", cfg!(feature = "time")),
+        ("tokio", cfg!(feature = "tokio")),
+        ("wasm", cfg!(feature = "wasm")),
+    ];
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: features
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name)
+            .collect(),
+        ciphers: vec!["chacha20poly1305"],
+        key_derivation: vec!["argon2id"],
+        signatures: vec!["ed25519"],
+        integrity: vec!["hmac-sha256"],
+        compressors: vec!["zlib"],
+        carriers: vec!["ancillary-chunk", "qr-code", "pixel-lsb", "pixel-watermark"],
+        placements: vec!["before-iend", "before-idat", "apng-frame", "apng-spread"],
+        pixels: true,
+        daemon: cfg!(all(unix, feature = "cli")),
+    }
+}
+
+impl Capabilities {
+    /// 多行的文本描述，用于 `--version --verbose`
+    pub fn describe(&self) -> String {
+        let list = |items: &[&str]| {
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        [
+            format!("pngme {}", self.version),
+            format!("features: {}", list(&self.features)),
+            format!("ciphers: {}", list(&self.ciphers)),
+            format!("key derivation: {}", list(&self.key_derivation)),
+            format!("signatures: {}", list(&self.signatures)),
+            format!("integrity: {}", list(&self.integrity)),
+            format!("compressors: {}", list(&self.compressors)),
+            format!("carriers: {}", list(&self.carriers)),
+            format!("placements: {}", list(&self.placements)),
+            format!("pixels: {}", yes_no(self.pixels)),
+            format!("daemon: {}", yes_no(self.daemon)),
+        ]
+        .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = capabilities();
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(capabilities.features.contains(&"s3"), cfg!(feature = "s3"));
+        assert!(capabilities.ciphers.contains(&"chacha20poly1305"));
+        assert!(capabilities.pixels);
+
+        let json = serde_json::to_value(&capabilities).unwrap();
+        assert_eq!(json["compressors"], serde_json::json!(["zlib"]));
+        assert_eq!(json["daemon"], cfg!(all(unix, feature = "cli")));
+    }
+
+    #[test]
+    fn test_describe() {
+        let description = capabilities().describe();
+        assert!(description.starts_with(&format!("pngme {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(description.contains("ciphers: chacha20poly1305\n"));
+        assert!(description.contains("pixels: yes"));
+    }
+}
@@ -0,0 +1,302 @@
+//! # Caption
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `encode --visible-label`：用内置的 5×7 点阵字体把一行文字画在图片的一角（黑底白字），
+//! 例如 "CONFIDENTIAL" 或时间戳，与隐藏的信息一起作为可见的标记。
+//! 字体覆盖可打印的 ASCII 字符，小写字母画成大写。和 `--method qr` 一样直接覆盖该区域的像素，
+//! 颜色的选择（包括调色板图片）与 QR 码相同，见 `qr::pens`。
+
+use crate::cancel::CancelToken;
+use crate::interlace;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::qr::{self, Corner};
+use crate::{PngmeError, Result};
+
+/// 默认每个点的边长（像素）
+pub const DEFAULT_SCALE: u32 = 2;
+/// 字形的宽和高（点）
+const GLYPH_WIDTH: u64 = 5;
+const GLYPH_HEIGHT: u64 = 7;
+/// 文字四周底色的宽度（点）
+const PADDING: u64 = 2;
+/// `FONT` 中的字形依次对应的字符
+const CHARS: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`{|}~";
+/// 每个字形 7 行，每行的低 5 位从左到右表示各点，1 为文字
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_HEIGHT as usize]; 69] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // 空格
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100], // !
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000], // "
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010], // #
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100], // $
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011], // %
+    [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101], // &
+    [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000], // '
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010], // (
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000], // )
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000], // *
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000], // +
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ,
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // -
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100], // .
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000], // /
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110], // 0
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 1
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111], // 2
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110], // 3
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010], // 4
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110], // 5
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110], // 6
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000], // 7
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110], // 8
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100], // 9
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000], // :
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000], // ;
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010], // <
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000], // =
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000], // >
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100], // ?
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110], // @
+    [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // A
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110], // B
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110], // C
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100], // D
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111], // E
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000], // F
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // G
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // H
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // I
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // J
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001], // K
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111], // L
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001], // M
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001], // N
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // O
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000], // P
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101], // Q
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001], // R
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110], // S
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // T
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // U
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // V
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010], // W
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001], // X
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // Y
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111], // Z
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110], // [
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000], // \
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110], // ]
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000], // ^
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111], // _
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000], // `
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010], // {
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // |
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000], // }
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000], // ~
+];
+
+/// 绘制文字的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptionOptions {
+    /// 文字（连同底色）贴着哪个角
+    pub corner: Corner,
+    /// 每个点的边长（像素）
+    pub scale: u32,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        CaptionOptions {
+            corner: Corner::TopLeft,
+            scale: DEFAULT_SCALE,
+        }
+    }
+}
+
+fn caption_error(message: impl Into<String>) -> PngmeError {
+    PngmeError::InvalidPayload(message.into())
+}
+
+/// 字符的字形，小写字母使用大写字母的字形，不支持的字符返回 `None`
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT as usize]> {
+    let c = c.to_ascii_uppercase();
+    CHARS.find(c).map(|index| &FONT[index])
+}
+
+/// 把一行文字画在图片的一角，替换原来的像素。文字为空、含有不支持的字符或图片放不下时返回错误。
+/// 每画一行和压缩 IDAT 前检查 `cancel`，已取消时返回 `PngmeError::Cancelled`
+pub fn render(
+    png: &mut Png,
+    text: &str,
+    options: &CaptionOptions,
+    limits: &Limits,
+    cancel: &CancelToken,
+) -> Result<()> {
+    if options.scale == 0 {
+        return Err(caption_error(
+            "the caption dot size must be at least 1 pixel",
+        ));
+    }
+    let glyphs = text
+        .chars()
+        .map(|c| {
+            glyph(c).ok_or_else(|| {
+                caption_error(format!(
+                    "the caption can only contain printable ASCII characters, not {:?}",
+                    c
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if glyphs.is_empty() {
+        return Err(caption_error("the caption is empty"));
+    }
+    let header = png.header()?;
+    let scale = u64::from(options.scale);
+    // 每个字符之后留 1 列间隔，最后一个除外
+    let columns = glyphs.len() as u64 * (GLYPH_WIDTH + 1) - 1 + 2 * PADDING;
+    let rows = GLYPH_HEIGHT + 2 * PADDING;
+    let (width, height) = (columns * scale, rows * scale);
+    if width > u64::from(header.width) || height > u64::from(header.height) {
+        return Err(caption_error(format!(
+            "a {}-character caption at {} pixels per dot needs {}x{} pixels, the image is {}x{}",
+            glyphs.len(),
+            options.scale,
+            width,
+            height,
+            header.width,
+            header.height
+        )));
+    }
+    let (width, height) = (width as u32, height as u32);
+    let (left, top) = match options.corner {
+        Corner::TopLeft => (0, 0),
+        Corner::TopRight => (header.width - width, 0),
+        Corner::BottomLeft => (0, header.height - height),
+        Corner::BottomRight => (header.width - width, header.height - height),
+    };
+    let (dark, light) = qr::pens(png, &header)?;
+    // 点是否属于文字
+    let is_lit = |column: u64, row: u64| {
+        let (Some(column), Some(row)) = (column.checked_sub(PADDING), row.checked_sub(PADDING))
+        else {
+            return false;
+        };
+        let (index, x) = (
+            (column / (GLYPH_WIDTH + 1)) as usize,
+            column % (GLYPH_WIDTH + 1),
+        );
+        row < GLYPH_HEIGHT
+            && x < GLYPH_WIDTH
+            && glyphs
+                .get(index)
+                .is_some_and(|glyph| glyph[row as usize] >> (GLYPH_WIDTH - 1 - x) & 1 == 1)
+    };
+
+    let mut image = png.pixels(limits)?;
+    let row_bytes = header.row_bytes() as usize;
+    let channels = dark.len();
+    for y in 0..height {
+        cancel.check()?;
+        let row = &mut image[(top + y) as usize * row_bytes..][..row_bytes];
+        for x in 0..width {
+            let lit = is_lit(u64::from(x) / scale, u64::from(y) / scale);
+            let pen = if lit { &light } else { &dark };
+            for (channel, &value) in pen.iter().enumerate() {
+                let index = (left + x) as usize * channels + channel;
+                qr::set_sample(row, index, header.bit_depth, value);
+            }
+        }
+    }
+    let raw = interlace::filter_adaptive(&header, &interlace::interlace(&header, &image));
+    cancel.check()?;
+    png.set_image_data(&raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::{ColorType, Ihdr};
+    use crate::png::tests::testing_image;
+
+    /// 纯灰色的 8 位灰度图片
+    fn gray(width: u32, height: u32) -> Png {
+        let header = Ihdr::new(width, height, 8, ColorType::Grayscale).unwrap();
+        testing_image(header, &vec![0x80; (width * height) as usize])
+    }
+
+    #[test]
+    fn test_render() {
+        let limits = Limits::default();
+        let mut png = gray(40, 20);
+        let options = CaptionOptions {
+            corner: Corner::BottomRight,
+            scale: 1,
+        };
+        render(&mut png, "Hi", &options, &limits, &CancelToken::new()).unwrap();
+        // 2 个字符：(2 × 6 - 1 + 4) × (7 + 4) 个点，位于右下角
+        let pixels = png.pixels(&limits).unwrap();
+        let (left, top) = (40 - 15, 20 - 11);
+        for (offset, &value) in pixels.iter().enumerate() {
+            let (x, y) = (offset % 40, offset / 40);
+            if x < left || y < top {
+                assert_eq!(value, 0x80, "({}, {})", x, y);
+            }
+        }
+        let text: Vec<String> = (0..7)
+            .map(|row| {
+                let start = (top + 2 + row) * 40 + left + 2;
+                pixels[start..start + 11]
+                    .iter()
+                    .map(|&value| if value == 255 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "#...#..###.",
+                "#...#...#..",
+                "#...#...#..",
+                "#####...#..",
+                "#...#...#..",
+                "#...#...#..",
+                "#...#..###.",
+            ]
+        );
+        assert_eq!(pixels[top * 40 + left], 0);
+    }
+
+    #[test]
+    fn test_font() {
+        assert_eq!(CHARS.len(), FONT.len());
+        assert_eq!(glyph('a'), glyph('A'));
+        assert!(glyph('é').is_none());
+        assert!(glyph('\n').is_none());
+        assert!((' '..='~').all(|c| glyph(c).is_some()));
+        assert!(FONT.iter().flatten().all(|&row| row < 1 << GLYPH_WIDTH));
+    }
+
+    #[test]
+    fn test_errors() {
+        let limits = Limits::default();
+        let cancel = CancelToken::new();
+        let mut png = gray(40, 20);
+        let options = CaptionOptions::default();
+        for text in ["", "CONFIDENTIAL", "tab\there"] {
+            assert!(matches!(
+                render(&mut png, text, &options, &limits, &cancel),
+                Err(PngmeError::InvalidPayload(_))
+            ));
+        }
+        let cancelled = CancelToken::new();
+        cancelled.cancel();
+        let mut png = gray(60, 30);
+        assert!(matches!(
+            render(&mut png, "OK", &options, &limits, &cancelled),
+            Err(PngmeError::Cancelled)
+        ));
+    }
+}
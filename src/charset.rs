@@ -0,0 +1,259 @@
+//! # Charset
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块处理文本 chunk 的字符集：PNG 规范规定 tEXt 和 zTXt 的关键字与文本使用 Latin-1
+//! （ISO/IEC 8859-1），iTXt 的文本和翻译后的关键字使用 UTF-8。
+//!
+//! 转换分为两种模式：`Mode::Strict` 遇到无法表示或无效的字符时报错，
+//! `Mode::Lossy` 用替代字符代替它们，用于只需要显示文本的场合。
+//! Latin-1 的每个字节都对应一个码位，解码不会失败，两种模式只在编码时不同。
+//!
+//! `lint` 不报错，而是列出规范不推荐的内容：控制字符、0 字节、无效的 UTF-8，
+//! 以及常见的错误——把 UTF-8 文本直接写进 tEXt。
+
+use std::fmt;
+
+use crate::chunk_type::ChunkType;
+use crate::{PngmeError, Result};
+
+/// 文本 chunk 使用的字符集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Latin1,
+    Utf8,
+}
+
+/// 遇到无法转换的字符时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// 报错
+    #[default]
+    Strict,
+    /// 编码为 Latin-1 时替换为 `?`，解码 UTF-8 时替换为 U+FFFD
+    Lossy,
+}
+
+/// 文本中不符合规范推荐的内容，`offset` 是在所检查字节中的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingWarning {
+    /// 文本中有 0 字节
+    NulByte { offset: usize },
+    /// 换行符以外的控制字符
+    ControlCharacter { offset: usize, byte: u8 },
+    /// 文本从这里开始不是合法的 UTF-8
+    InvalidUtf8 { offset: usize },
+    /// Latin-1 文本同时是包含非 ASCII 字符的合法 UTF-8，多半是写入方没有转换字符集
+    LooksLikeUtf8,
+}
+
+impl Charset {
+    /// 文本 chunk 类型使用的字符集，其他 chunk 返回 `None`
+    pub fn of(chunk_type: &ChunkType) -> Option<Charset> {
+        match &chunk_type.bytes() {
+            b"tEXt" | b"zTXt" => Some(Charset::Latin1),
+            b"iTXt" => Some(Charset::Utf8),
+            _ => None,
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8], mode: Mode) -> Result<String> {
+        match self {
+            Charset::Latin1 => Ok(latin1_decode(bytes)),
+            Charset::Utf8 => utf8_decode(bytes, mode),
+        }
+    }
+
+    pub fn encode(self, s: &str, mode: Mode) -> Result<Vec<u8>> {
+        match self {
+            Charset::Latin1 => latin1_encode(s, mode),
+            Charset::Utf8 => Ok(s.as_bytes().to_vec()),
+        }
+    }
+
+    /// 检查按该字符集解释的文本
+    pub fn lint(self, bytes: &[u8]) -> Vec<EncodingWarning> {
+        match self {
+            Charset::Latin1 => lint_latin1(bytes),
+            Charset::Utf8 => lint_utf8(bytes),
+        }
+    }
+}
+
+/// Latin-1 的每个字节恰好对应一个 Unicode 码位
+pub fn latin1_decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// 编码为 Latin-1，超出 U+00FF 的字符在严格模式下报错，宽松模式下替换为 `?`
+pub fn latin1_encode(s: &str, mode: Mode) -> Result<Vec<u8>> {
+    s.chars()
+        .map(|c| match (u8::try_from(u32::from(c)), mode) {
+            (Ok(b), _) => Ok(b),
+            (Err(_), Mode::Lossy) => Ok(b'?'),
+            (Err(_), Mode::Strict) => Err(PngmeError::InvalidTextChunk(format!(
+                "{:?} cannot be represented in Latin-1",
+                c
+            ))),
+        })
+        .collect()
+}
+
+/// 文本是否能用 Latin-1 表示
+pub fn is_latin1(s: &str) -> bool {
+    s.chars().all(|c| u32::from(c) <= 0xff)
+}
+
+pub fn utf8_decode(bytes: &[u8], mode: Mode) -> Result<String> {
+    match mode {
+        Mode::Strict => Ok(std::str::from_utf8(bytes)?.to_string()),
+        Mode::Lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+    }
+}
+
+fn lint_latin1(bytes: &[u8]) -> Vec<EncodingWarning> {
+    let mut warnings = lint_bytes(bytes.iter().copied().enumerate());
+    if !bytes.is_ascii() && std::str::from_utf8(bytes).is_ok() {
+        warnings.push(EncodingWarning::LooksLikeUtf8);
+    }
+    warnings
+}
+
+fn lint_utf8(bytes: &[u8]) -> Vec<EncodingWarning> {
+    let (valid, invalid) = match std::str::from_utf8(bytes) {
+        Ok(s) => (s, None),
+        Err(e) => (
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).expect("prefix is valid"),
+            Some(e.valid_up_to()),
+        ),
+    };
+    // C1 控制字符在 UTF-8 中占两个字节，按码位检查
+    let mut warnings = lint_bytes(
+        valid
+            .char_indices()
+            .filter_map(|(offset, c)| u8::try_from(u32::from(c)).ok().map(|b| (offset, b))),
+    );
+    if let Some(offset) = invalid {
+        warnings.push(EncodingWarning::InvalidUtf8 { offset });
+    }
+    warnings
+}
+
+/// 检查 0 字节和换行符以外的 C0、C1 控制字符，每种只报告第一处
+fn lint_bytes(chars: impl Iterator<Item = (usize, u8)>) -> Vec<EncodingWarning> {
+    let mut nul = None;
+    let mut control = None;
+    for (offset, b) in chars {
+        match b {
+            0 => {
+                nul.get_or_insert(EncodingWarning::NulByte { offset });
+            }
+            b'\n' => {}
+            0x01..=0x1f | 0x7f..=0x9f => {
+                control.get_or_insert(EncodingWarning::ControlCharacter { offset, byte: b });
+            }
+            _ => {}
+        }
+    }
+    nul.into_iter().chain(control).collect()
+}
+
+impl fmt::Display for EncodingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingWarning::NulByte { offset } => write!(f, "null byte at offset {}", offset),
+            EncodingWarning::ControlCharacter { offset, byte } => {
+                write!(f, "control character 0x{:02X} at offset {}", byte, offset)
+            }
+            EncodingWarning::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 at offset {}", offset)
+            }
+            EncodingWarning::LooksLikeUtf8 => {
+                write!(
+                    f,
+                    "Latin-1 text looks like UTF-8; use iTXt for Unicode text"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charset_of_chunk_type() {
+        let of = |s: &str| Charset::of(&ChunkType::from_str(s).unwrap());
+        assert_eq!(of("tEXt"), Some(Charset::Latin1));
+        assert_eq!(of("zTXt"), Some(Charset::Latin1));
+        assert_eq!(of("iTXt"), Some(Charset::Utf8));
+        assert_eq!(of("ruSt"), None);
+    }
+
+    #[test]
+    fn test_latin1() {
+        assert_eq!(latin1_decode(b"Caf\xe9"), "Café");
+        assert_eq!(latin1_encode("Café", Mode::Strict).unwrap(), b"Caf\xe9");
+        assert!(matches!(
+            latin1_encode("你好", Mode::Strict),
+            Err(PngmeError::InvalidTextChunk(_))
+        ));
+        assert_eq!(
+            latin1_encode("Café 你好", Mode::Lossy).unwrap(),
+            b"Caf\xe9 ??"
+        );
+        assert!(is_latin1("Café"));
+        assert!(!is_latin1("€"));
+    }
+
+    #[test]
+    fn test_utf8() {
+        let bytes = b"caf\xc3\xa9 \xff";
+        assert!(matches!(
+            Charset::Utf8.decode(bytes, Mode::Strict),
+            Err(PngmeError::InvalidUtf8(_))
+        ));
+        assert_eq!(
+            Charset::Utf8.decode(bytes, Mode::Lossy).unwrap(),
+            "café \u{fffd}"
+        );
+        // 同样的字节按 Latin-1 总能解码
+        assert_eq!(
+            Charset::Latin1.decode(bytes, Mode::Strict).unwrap(),
+            "cafÃ© ÿ"
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        assert!(Charset::Latin1.lint(b"line 1\nline 2 caf\xe9").is_empty());
+        assert_eq!(
+            Charset::Latin1.lint(b"caf\xc3\xa9"),
+            [EncodingWarning::LooksLikeUtf8]
+        );
+        assert_eq!(
+            Charset::Latin1.lint(b"a\0b\r\n\x85"),
+            [
+                EncodingWarning::NulByte { offset: 1 },
+                EncodingWarning::ControlCharacter {
+                    offset: 3,
+                    byte: b'\r'
+                }
+            ]
+        );
+
+        assert!(Charset::Utf8.lint("你好\n".as_bytes()).is_empty());
+        assert_eq!(
+            Charset::Utf8.lint("é\u{85}\u{1b}[31m".as_bytes()),
+            [EncodingWarning::ControlCharacter {
+                offset: 2,
+                byte: 0x85
+            }]
+        );
+        assert_eq!(
+            Charset::Utf8.lint(b"ok \xff"),
+            [EncodingWarning::InvalidUtf8 { offset: 3 }]
+        );
+    }
+}
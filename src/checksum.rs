@@ -0,0 +1,259 @@
+//! # Checksum
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `manifest create` 和 `manifest verify` 使用的校验清单：为一组 PNG 文件记录
+//! 每个文件的 chunk 清单（类型、长度、CRC）和关键 chunk 的 SHA-256 摘要，之后重新计算并与清单比较，
+//! 找出被修改、删除或新增的文件。
+//!
+//! 摘要覆盖按文件顺序排列的每个关键 chunk 的 `长度 | 类型 | 数据`，只要图像不变就不变；
+//! chunk 清单则能发现辅助 chunk（元数据、隐藏的信息）的增删和改动。
+//! 与 `sign`/`verify` 不同，清单不需要密钥，也不改动图片，但清单本身需要保存在可信的地方。
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::chunk::Chunk;
+use crate::png::Png;
+
+/// 当前的清单格式版本
+pub const VERSION: u32 = 1;
+
+/// 一组文件的校验清单
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumManifest {
+    /// 清单格式版本
+    pub version: u32,
+    /// 生成清单时的目录，`manifest verify` 没有给出目录时使用
+    pub root: String,
+    /// 是否包含子目录中的文件
+    pub recursive: bool,
+    /// 文件名模式，与批处理的 `--glob` 相同
+    pub glob: String,
+    /// 按路径排序的文件
+    pub files: Vec<FileRecord>,
+}
+
+/// 清单中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    /// 相对于清单目录的路径，以 `/` 分隔
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 关键 chunk 的 SHA-256 摘要，小写十六进制
+    pub critical_sha256: String,
+    /// 按文件中的顺序排列的 chunk
+    pub chunks: Vec<ChunkRecord>,
+}
+
+/// 文件中的一个 chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    /// chunk 类型
+    #[serde(rename = "type")]
+    pub chunk_type: String,
+    /// 数据长度
+    pub length: u32,
+    /// 文件中存储的 CRC，8 位十六进制
+    pub crc: String,
+}
+
+/// 校验时发现的一处不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// 相对于清单目录的路径
+    pub path: String,
+    /// 不一致的原因
+    pub reason: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+impl ChecksumManifest {
+    /// 由文件记录生成清单，记录按路径排序
+    pub fn new(root: &str, recursive: bool, glob: &str, mut files: Vec<FileRecord>) -> Self {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        ChecksumManifest {
+            version: VERSION,
+            root: root.to_string(),
+            recursive,
+            glob: glob.to_string(),
+            files,
+        }
+    }
+
+    /// 与重新计算的记录比较。`current` 中的每一项是一个当前存在的文件及其记录，
+    /// 无法读取或解析的文件给出错误信息。返回所有不一致，按路径排序
+    pub fn verify(
+        &self,
+        current: impl IntoIterator<Item = (String, Result<FileRecord, String>)>,
+    ) -> Vec<Mismatch> {
+        let mut current: BTreeMap<String, Result<FileRecord, String>> =
+            current.into_iter().collect();
+        let mut mismatches = Vec::new();
+        for expected in &self.files {
+            let reason = match current.remove(&expected.path) {
+                None => Some("missing".to_string()),
+                Some(Err(e)) => Some(e),
+                Some(Ok(actual)) => compare(expected, &actual),
+            };
+            if let Some(reason) = reason {
+                mismatches.push(Mismatch {
+                    path: expected.path.clone(),
+                    reason,
+                });
+            }
+        }
+        mismatches.extend(current.into_keys().map(|path| Mismatch {
+            path,
+            reason: "not in manifest".to_string(),
+        }));
+        mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+        mismatches
+    }
+}
+
+impl FileRecord {
+    /// 为大小为 `size` 的文件 `png` 生成记录
+    pub fn new(path: &str, size: u64, png: &Png) -> Self {
+        let chunks = png
+            .chunks()
+            .iter()
+            .map(|chunk| ChunkRecord {
+                chunk_type: chunk.chunk_type().to_string(),
+                length: chunk.length(),
+                crc: format!("{:08X}", chunk.crc()),
+            })
+            .collect();
+        FileRecord {
+            path: path.to_string(),
+            size,
+            critical_sha256: critical_digest(png.chunks()),
+            chunks,
+        }
+    }
+}
+
+/// 关键 chunk 的 SHA-256 摘要，小写十六进制
+pub fn critical_digest(chunks: &[Chunk]) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in chunks.iter().filter(|c| c.chunk_type().is_critical()) {
+        hasher.update(chunk.length().to_be_bytes());
+        hasher.update(chunk.chunk_type().bytes());
+        hasher.update(chunk.data());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// 比较同一文件的两条记录，一致时返回 `None`。图像的改动优先于 chunk 的改动报告
+fn compare(expected: &FileRecord, actual: &FileRecord) -> Option<String> {
+    if expected.critical_sha256 != actual.critical_sha256 {
+        return Some("image data changed (critical chunk digest differs)".to_string());
+    }
+    if expected.chunks.len() != actual.chunks.len() {
+        return Some(format!(
+            "chunk count changed ({} -> {})",
+            expected.chunks.len(),
+            actual.chunks.len()
+        ));
+    }
+    let changed = expected
+        .chunks
+        .iter()
+        .zip(&actual.chunks)
+        .position(|(a, b)| a != b);
+    if let Some(index) = changed {
+        return Some(format!(
+            "chunk #{} changed ({} -> {})",
+            index, expected.chunks[index].chunk_type, actual.chunks[index].chunk_type
+        ));
+    }
+    if expected.size != actual.size {
+        return Some(format!(
+            "size changed ({} -> {} bytes)",
+            expected.size, actual.size
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+    use std::convert::TryFrom;
+
+    fn record(path: &str, png: &Png) -> (String, Result<FileRecord, String>) {
+        let size = png.as_bytes().len() as u64;
+        (path.to_string(), Ok(FileRecord::new(path, size, png)))
+    }
+
+    #[test]
+    fn test_verify_detects_changes() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let files = ["b.png", "a.png", "c.png"]
+            .iter()
+            .map(|path| record(path, &png).1.unwrap())
+            .collect();
+        let manifest = ChecksumManifest::new("assets", false, "*.png", files);
+        assert_eq!(manifest.files[0].path, "a.png");
+        let unchanged = || ["a.png", "b.png", "c.png"].map(|path| record(path, &png));
+        assert!(manifest.verify(unchanged()).is_empty());
+
+        let mut tagged = png.clone();
+        tagged.append_chunk(
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()).unwrap(),
+        );
+        let mut current = unchanged().to_vec();
+        current[0] = record("a.png", &tagged);
+        current[2] = ("c.png".to_string(), Err("not a PNG".to_string()));
+        current.retain(|(path, _)| path != "b.png");
+        current.push(record("e.png", &png));
+        let reasons: Vec<String> = manifest
+            .verify(current)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                "a.png: chunk count changed (3 -> 4)",
+                "b.png: missing",
+                "c.png: not a PNG",
+                "e.png: not in manifest",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_critical_digest_ignores_ancillary_chunks() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let digest = critical_digest(png.chunks());
+        assert_eq!(digest.len(), 64);
+
+        let mut stripped = png.clone();
+        stripped.remove_chunk("IEND").unwrap();
+        assert_ne!(critical_digest(stripped.chunks()), digest);
+
+        let mut tagged = png.clone();
+        tagged.append_chunk(
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()).unwrap(),
+        );
+        assert_eq!(critical_digest(tagged.chunks()), digest);
+        let expected = FileRecord::new("a.png", 0, &png);
+        let actual = FileRecord::new("a.png", 0, &stripped);
+        assert_eq!(
+            compare(&expected, &actual).unwrap(),
+            "image data changed (critical chunk digest differs)"
+        );
+    }
+}
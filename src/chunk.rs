@@ -4,12 +4,31 @@
 //!
 //! 该模块包含了 `Chunk` 结构体的实现。
 
-use std::convert::{TryFrom, TryInto};
-use std::error::Error;
+use std::convert::TryFrom;
 use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::charset::{Charset, Mode};
 use crate::chunk_type::ChunkType;
+use crate::crypto;
+use crate::encoding::Encoding;
+use crate::parse::{ParseOptions, ParseWarning};
+use crate::payload::{self, Payload};
+use crate::preview;
+use crate::{PngmeError, Result};
 use crc::Crc;
+use rand_core::{CryptoRng, RngCore};
+
+/// PNG 规范允许的最大 chunk 数据长度：2^31 - 1
+pub const MAX_CHUNK_LENGTH: u32 = (1 << 31) - 1;
+
+/// 旧版本 pngme 使用的 CRC 算法，查找表只生成一次
+static LEGACY_CRC: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_CKSUM);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Chunk {
@@ -19,22 +38,56 @@ pub struct Chunk {
     crc: u32,
 }
 
-#[allow(dead_code)]
 impl Chunk {
-    pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Self {
-        let length = (data.len() as u32).to_be();
-        let crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC).checksum(&{
-            let mut bytes = chunk_type.to_string().as_bytes().to_vec();
-            bytes.extend_from_slice(&data[..]);
-            bytes
-        });
-
-        Self {
+    /// 创建 chunk，数据超过 `MAX_CHUNK_LENGTH` 时返回错误
+    pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Result<Self> {
+        let length = u32::try_from(data.len())
+            .ok()
+            .filter(|&length| length <= MAX_CHUNK_LENGTH)
+            .ok_or(PngmeError::ChunkTooLarge(data.len()))?;
+        let crc = Self::calculate_crc(&chunk_type, &data);
+
+        Ok(Self {
             length,
             chunk_type,
             data,
             crc,
-        }
+        })
+    }
+
+    /// 压缩数据后创建 chunk，`decoded_data` 会自动解压
+    pub fn new_compressed(chunk_type: ChunkType, data: &[u8]) -> Result<Self> {
+        Self::new(chunk_type, payload::compress(data)?)
+    }
+
+    /// 获取还原后的信息：带有封装头部（例如被压缩）时自动解包
+    pub fn decoded_data(&self) -> Result<Vec<u8>> {
+        payload::decode(&self.data)
+    }
+
+    /// 获取还原后的完整信息，包括隐藏文件时记录的文件名
+    pub fn decoded_payload(&self) -> Result<Payload> {
+        Payload::from_bytes(&self.data)
+    }
+
+    /// 用口令加密数据后创建 chunk
+    pub fn new_encrypted(chunk_type: ChunkType, data: &[u8], passphrase: &str) -> Result<Self> {
+        Self::new(chunk_type, crypto::encrypt(data, passphrase)?)
+    }
+
+    /// 与 `new_encrypted` 相同，但随机数取自调用方提供的生成器
+    pub fn new_encrypted_with_rng<R: RngCore + CryptoRng>(
+        chunk_type: ChunkType,
+        data: &[u8],
+        passphrase: &str,
+        rng: &mut R,
+    ) -> Result<Self> {
+        Self::new(chunk_type, crypto::encrypt_with_rng(data, passphrase, rng)?)
+    }
+
+    /// 用口令解密由 `new_encrypted` 创建的 chunk 的数据
+    pub fn decrypt_data(&self, passphrase: &str) -> Result<Vec<u8>> {
+        crypto::decrypt(&self.data, passphrase)
     }
 
     pub fn length(&self) -> u32 {
@@ -53,14 +106,183 @@ impl Chunk {
         self.crc
     }
 
-    pub fn data_as_string(&self) -> Result<String, std::str::Utf8Error> {
-        String::from_utf8(self.data.clone()).map_err(|e| e.utf8_error())
+    /// 把数据解码为字符串：tEXt 按 Latin-1 解码（关键字后的分隔符保留为 U+0000），
+    /// 其他 chunk 按 UTF-8 解码，不是合法的 UTF-8 时报错。
+    /// zTXt 和 iTXt 的数据包含压缩数据或多个字段，应该用 `text` 模块解析
+    pub fn data_as_string(&self) -> Result<String> {
+        self.data_charset().decode(&self.data, Mode::Strict)
+    }
+
+    /// 与 `data_as_string` 相同，但无效的 UTF-8 替换为 U+FFFD
+    pub fn data_as_string_lossy(&self) -> String {
+        self.data_charset()
+            .decode(&self.data, Mode::Lossy)
+            .expect("lossy decoding never fails")
+    }
+
+    fn data_charset(&self) -> Charset {
+        match &self.chunk_type.bytes() {
+            b"tEXt" => Charset::Latin1,
+            _ => Charset::Utf8,
+        }
+    }
+
+    /// 按 PNG 规范计算 CRC：CRC-32/ISO-HDLC，覆盖类型码和数据。
+    /// 由 crc32fast 计算，CPU 支持时使用 CRC/PCLMULQDQ 指令
+    pub fn calculate_crc(chunk_type: &ChunkType, data: &[u8]) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&chunk_type.bytes());
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// 旧版本 pngme 写入的 CRC：CRC-32/CKSUM，只覆盖数据
+    pub fn legacy_crc(data: &[u8]) -> u32 {
+        LEGACY_CRC.checksum(data)
+    }
+
+    /// 解析 chunk，同时接受规范 CRC 和旧版本的 CKSUM CRC。
+    /// 返回的 chunk 总是带有规范 CRC，重新写出即可完成迁移。
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self> {
+        let (chunk_type, data, crc) = Self::split(bytes)?;
+        let calculated_crc = Self::calculate_crc(&chunk_type, data);
+        if crc != calculated_crc && crc != Self::legacy_crc(data) {
+            return Err(PngmeError::CrcMismatch {
+                expected: crc,
+                actual: calculated_crc,
+            });
+        }
+        Self::new(chunk_type, data.to_vec())
+    }
+
+    /// 按 `options` 解析 chunk。宽松模式下 CRC 错误不会中止解析，chunk 保留存储的 CRC，
+    /// 问题作为警告返回，警告的偏移为 0；长度超限、数据被截断和无效的类型码总是返回错误
+    pub fn parse_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, Option<ParseWarning>)> {
+        if let Some(length) = bytes.get(..4) {
+            let length = u32::from_be_bytes(length.try_into().unwrap());
+            if length > options.chunk_size_limit() {
+                return Err(PngmeError::ChunkTooLarge(length as usize));
+            }
+        }
+        let chunk = Self::from_bytes_unchecked(bytes)?;
+        if !options.verify_crc || chunk.has_valid_crc() {
+            return Ok((chunk, None));
+        }
+        let actual = Self::calculate_crc(&chunk.chunk_type, &chunk.data);
+        if options.strict {
+            return Err(PngmeError::CrcMismatch {
+                expected: chunk.crc,
+                actual,
+            });
+        }
+        let warning = ParseWarning {
+            offset: 0,
+            chunk_type: chunk.chunk_type.to_string(),
+            message: format!(
+                "CRC mismatch: stored {:08X}, calculated {:08X}",
+                chunk.crc, actual
+            ),
+        };
+        Ok((chunk, Some(warning)))
+    }
+
+    /// 解析 chunk 但不校验 CRC，保留文件中存储的 CRC，用于修复损坏的文件
+    pub fn from_bytes_unchecked(bytes: &[u8]) -> Result<Self> {
+        let (chunk_type, data, crc) = Self::split(bytes)?;
+        Ok(Self {
+            length: data.len() as u32,
+            chunk_type,
+            data: data.to_vec(),
+            crc,
+        })
+    }
+
+    /// 存储的 CRC 是否与按规范计算的结果一致
+    pub fn has_valid_crc(&self) -> bool {
+        self.crc == Self::calculate_crc(&self.chunk_type, &self.data)
+    }
+
+    /// 按规范重新计算 CRC，CRC 发生变化时返回 `true`
+    pub fn recompute_crc(&mut self) -> bool {
+        let crc = Self::calculate_crc(&self.chunk_type, &self.data);
+        let changed = crc != self.crc;
+        self.crc = crc;
+        changed
+    }
+
+    /// 替换数据，同时更新长度并重新计算 CRC（原来存储的 CRC 错误时也会被修正）。
+    /// 数据超过 `MAX_CHUNK_LENGTH` 时返回错误，chunk 保持不变
+    pub fn set_data(&mut self, data: Vec<u8>) -> Result<()> {
+        *self = Self::new(self.chunk_type, data)?;
+        Ok(())
+    }
+
+    /// 用 `f` 由原来的数据生成新数据，其余同 `set_data`
+    pub fn map_data(&mut self, f: impl FnOnce(&[u8]) -> Vec<u8>) -> Result<()> {
+        let data = f(&self.data);
+        self.set_data(data)
+    }
+
+    /// 借用数据的 `ChunkRef` 视图，CRC 为存储的值
+    pub fn as_chunk_ref(&self) -> ChunkRef<'_> {
+        ChunkRef {
+            chunk_type: self.chunk_type,
+            data: &self.data,
+            crc: self.crc,
+        }
+    }
+
+    /// 以 base64（标准字母表，带填充）表示的数据
+    pub fn data_as_base64(&self) -> String {
+        STANDARD.encode(&self.data)
+    }
+
+    /// 用 base64 表示的数据创建 chunk，数据中的空白被忽略
+    pub fn from_base64_payload(chunk_type: ChunkType, encoded: &str) -> Result<Self> {
+        Self::new(chunk_type, Encoding::Base64.decode(encoded.as_bytes())?)
+    }
+
+    /// 以偏移、十六进制和 ASCII 三栏显示数据，最多显示 `max_bytes` 字节（`None` 表示全部）
+    pub fn hexdump(&self, max_bytes: Option<usize>) -> String {
+        preview::hexdump(&self.data, max_bytes)
+    }
+
+    /// 拆分出类型码、数据和 CRC 字段，不做 CRC 校验
+    fn split(bytes: &[u8]) -> Result<(ChunkType, &[u8], u32)> {
+        if bytes.len() < 12 {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        let length = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if length > MAX_CHUNK_LENGTH {
+            return Err(PngmeError::ChunkTooLarge(length as usize));
+        }
+        // 32 位目标（如 wasm32）上 usize 只有 32 位，加法可能溢出
+        let end = (length as usize)
+            .checked_add(12)
+            .ok_or(PngmeError::TruncatedChunk)?;
+        if bytes.len() < end {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        let chunk_type = ChunkType::try_from([bytes[4], bytes[5], bytes[6], bytes[7]])?;
+        let data = &bytes[8..(end - 4)];
+        let crc = u32::from_be_bytes([
+            bytes[end - 4],
+            bytes[end - 3],
+            bytes[end - 2],
+            bytes[end - 1],
+        ]);
+        Ok((chunk_type, data, crc))
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
         result.extend_from_slice(&self.length.to_be_bytes());
-        result.extend_from_slice(self.chunk_type.to_string().as_bytes());
+        result.extend_from_slice(&self.chunk_type.bytes());
         result.extend_from_slice(&self.data);
         result.extend_from_slice(&self.crc.to_be_bytes());
         result
@@ -68,45 +290,161 @@ impl Chunk {
 }
 
 impl TryFrom<&[u8]> for Chunk {
-    type Error = Box<dyn Error>;
+    type Error = PngmeError;
 
-    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() < 12 {
-            return Err("The input slice is not long enough to be a valid chunk".into());
-        }
-
-        let length = u32::from_be_bytes(data[..4].try_into().unwrap());
-        let chunk_type = ChunkType::from_str(std::str::from_utf8(&data[4..8]).unwrap())?;
-        let data = data[8..(length as usize + 8)].to_vec();
-        let crc = u32::from_be_bytes(
-            data[(length as usize)..(length as usize + 4)]  // FIXME: range end index 46 out of range for slice of length 42
-                .try_into()
-                .unwrap(),
-        );
-
-        let calculated_crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC).checksum(&{
-            let mut bytes = chunk_type.to_string().as_bytes().to_vec();
-            bytes.extend_from_slice(&data[..]);
-            bytes
-        });
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        let (chunk_type, data, crc) = Self::split(bytes)?;
+        let calculated_crc = Self::calculate_crc(&chunk_type, data);
         if crc != calculated_crc {
-            return Err("The input slice has an invalid CRC".into());
+            return Err(PngmeError::CrcMismatch {
+                expected: crc,
+                actual: calculated_crc,
+            });
         }
 
         Ok(Self {
-            length,
+            length: data.len() as u32,
+            chunk_type,
+            data: data.to_vec(),
+            crc,
+        })
+    }
+}
+
+/// 借用原始字节的 chunk 视图，访问长度、类型、数据和 CRC 时不分配内存，用于只读的快速扫描
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRef<'a> {
+    chunk_type: ChunkType,
+    data: &'a [u8],
+    crc: u32,
+}
+
+impl<'a> ChunkRef<'a> {
+    /// 解析 `bytes` 开头的一个 chunk，不校验 CRC
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let (chunk_type, data, crc) = Chunk::split(bytes)?;
+        Ok(ChunkRef {
             chunk_type,
             data,
             crc,
         })
     }
+
+    pub fn length(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    /// chunk 在文件中占用的字节数：长度、类型、数据和 CRC
+    pub fn encoded_len(&self) -> usize {
+        self.data.len() + 12
+    }
+
+    /// 存储的 CRC 是否与按规范计算的结果一致
+    pub fn has_valid_crc(&self) -> bool {
+        self.crc == Chunk::calculate_crc(&self.chunk_type, self.data)
+    }
+
+    /// 复制数据，得到保留存储的 CRC 的 `Chunk`
+    pub fn to_chunk(self) -> Chunk {
+        Chunk {
+            length: self.length(),
+            chunk_type: self.chunk_type,
+            data: self.data.to_vec(),
+            crc: self.crc,
+        }
+    }
+}
+
+/// 逐项设置 chunk 的类型、数据以及压缩、加密选项，`build` 时统一校验。
+///
+/// 数据先封装（压缩），再加密，与 `encode` 命令的顺序相同
+#[derive(Debug, Clone, Default)]
+pub struct ChunkBuilder {
+    chunk_type: Option<String>,
+    data: Vec<u8>,
+    compress: bool,
+    passphrase: Option<String>,
+}
+
+impl ChunkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置 chunk 类型，在 `build` 时校验
+    pub fn chunk_type(mut self, chunk_type: &str) -> Self {
+        self.chunk_type = Some(chunk_type.to_string());
+        self
+    }
+
+    pub fn data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    /// 从 `Read` 中读取全部数据
+    pub fn data_from_reader(mut self, mut reader: impl Read) -> Result<Self> {
+        self.data.clear();
+        reader.read_to_end(&mut self.data)?;
+        Ok(self)
+    }
+
+    /// 读取文件的全部内容作为数据
+    pub fn data_from_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        self.data_from_reader(File::open(path)?)
+    }
+
+    /// 压缩数据，`Chunk::decoded_data` 会自动解压
+    pub fn compressed(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// 使用口令加密数据
+    pub fn encrypted(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// 校验类型和数据长度，生成 chunk
+    pub fn build(self) -> Result<Chunk> {
+        let chunk_type = self
+            .chunk_type
+            .ok_or(PngmeError::MissingField("chunk_type"))?;
+        let chunk_type = ChunkType::from_str(&chunk_type)?;
+        let mut data = if self.compress {
+            payload::compress(&self.data)?
+        } else {
+            self.data
+        };
+        if let Some(passphrase) = &self.passphrase {
+            data = crypto::encrypt(&data, passphrase)?;
+        }
+        Chunk::new(chunk_type, data)
+    }
 }
 
 impl fmt::Display for Chunk {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Chunk Type: {}", self.chunk_type)?;
         writeln!(f, "Data Length: {}", self.length)?;
-        writeln!(f, "Data: {}", String::from_utf8_lossy(&self.data))?;
+        writeln!(
+            f,
+            "Data: {}",
+            preview::preview(&self.data_as_string_lossy(), preview::DEFAULT_WIDTH)
+        )?;
         writeln!(f, "CRC: {:X}", self.crc)
     }
 }
@@ -116,6 +454,8 @@ impl fmt::Display for Chunk {
 mod tests {
     use super::*;
     use crate::chunk_type::ChunkType;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
     use std::str::FromStr;
 
     fn testing_chunk() -> Chunk {
@@ -136,13 +476,69 @@ mod tests {
         Chunk::try_from(chunk_data.as_ref()).unwrap()
     }
 
+    #[test]
+    fn test_chunk_ref() {
+        let chunk = testing_chunk();
+        let mut bytes = chunk.as_bytes();
+        bytes.extend_from_slice(b"trailing");
+        let chunk_ref = ChunkRef::parse(&bytes).unwrap();
+        assert_eq!(chunk_ref.length(), 42);
+        assert_eq!(chunk_ref.chunk_type().to_string(), "RuSt");
+        assert_eq!(chunk_ref.data(), chunk.data());
+        assert_eq!(chunk_ref.crc(), 2882656334);
+        assert_eq!(chunk_ref.encoded_len(), 54);
+        assert!(chunk_ref.has_valid_crc());
+        assert_eq!(chunk_ref.to_chunk(), chunk);
+
+        // 不校验 CRC，但能发现 CRC 错误
+        let end = chunk_ref.encoded_len();
+        bytes[end - 1] ^= 1;
+        assert!(!ChunkRef::parse(&bytes).unwrap().has_valid_crc());
+        assert!(matches!(
+            ChunkRef::parse(&bytes[..20]),
+            Err(PngmeError::TruncatedChunk)
+        ));
+    }
+
+    #[test]
+    fn test_chunk_builder() {
+        let chunk = ChunkBuilder::new()
+            .chunk_type("RuSt")
+            .data("This is where your secret message will be!")
+            .build()
+            .unwrap();
+        assert_eq!(chunk, testing_chunk());
+
+        let chunk = ChunkBuilder::new()
+            .chunk_type("ruSt")
+            .data_from_reader(&b"hello hello hello"[..])
+            .unwrap()
+            .compressed(true)
+            .encrypted("secret")
+            .build()
+            .unwrap();
+        let decrypted = chunk.decrypt_data("secret").unwrap();
+        assert_eq!(payload::decode(&decrypted).unwrap(), b"hello hello hello");
+
+        let error = ChunkBuilder::new().data("no type").build().unwrap_err();
+        assert!(matches!(error, PngmeError::MissingField("chunk_type")));
+        assert_eq!(error.to_string(), "Missing required field: chunk_type");
+        assert!(matches!(
+            ChunkBuilder::new().chunk_type("Ru1t").build(),
+            Err(PngmeError::InvalidChunkType(_))
+        ));
+        assert!(ChunkBuilder::new()
+            .data_from_file("/nonexistent/pngme/payload.bin")
+            .is_err());
+    }
+
     #[test]
     fn test_new_chunk() {
         let chunk_type = ChunkType::from_str("RuSt").unwrap();
         let data = "This is where your secret message will be!"
             .as_bytes()
             .to_vec();
-        let chunk = Chunk::new(chunk_type, data);
+        let chunk = Chunk::new(chunk_type, data).unwrap();
         assert_eq!(chunk.length(), 42);
         assert_eq!(chunk.crc(), 2882656334);
     }
@@ -167,6 +563,65 @@ mod tests {
         assert_eq!(chunk_string, expected_chunk_string);
     }
 
+    #[test]
+    fn test_chunk_parse_with() {
+        let mut bytes = testing_chunk().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(Chunk::parse_with(&bytes, &ParseOptions::default()).is_err());
+
+        let (chunk, warning) = Chunk::parse_with(&bytes, &ParseOptions::lenient()).unwrap();
+        assert!(!chunk.has_valid_crc());
+        assert_eq!(chunk.as_bytes(), bytes);
+        assert!(warning.unwrap().message.starts_with("CRC mismatch"));
+
+        let small = ParseOptions {
+            max_chunk_size: Some(8),
+            ..ParseOptions::lenient()
+        };
+        assert!(matches!(
+            Chunk::parse_with(&bytes, &small),
+            Err(PngmeError::ChunkTooLarge(42))
+        ));
+    }
+
+    #[test]
+    fn test_chunk_hexdump() {
+        let chunk = testing_chunk();
+        let dump = chunk.hexdump(Some(16));
+        assert!(dump.starts_with("00000000  54 68 69 73 20 69 73 20"));
+        assert!(dump.contains("|This is where yo|"));
+        assert!(dump.ends_with("... 26 more bytes\n"));
+        assert_eq!(chunk.hexdump(None).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_chunk_base64() {
+        let chunk = testing_chunk();
+        let encoded = chunk.data_as_base64();
+        assert!(encoded.starts_with("VGhpcyBpcyB3aGVyZSB5b3Vy"));
+        let decoded = Chunk::from_base64_payload(*chunk.chunk_type(), &encoded).unwrap();
+        assert_eq!(decoded, chunk);
+        assert!(Chunk::from_base64_payload(*chunk.chunk_type(), "not base64!").is_err());
+    }
+
+    #[test]
+    fn test_chunk_string_charset() {
+        let text = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Title\0Caf\xe9".to_vec(),
+        )
+        .unwrap();
+        assert_eq!(text.data_as_string().unwrap(), "Title\0Café");
+
+        let binary = Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"Caf\xe9".to_vec()).unwrap();
+        assert!(matches!(
+            binary.data_as_string(),
+            Err(PngmeError::InvalidUtf8(_))
+        ));
+        assert_eq!(binary.data_as_string_lossy(), "Caf\u{fffd}");
+    }
+
     #[test]
     fn test_chunk_crc() {
         let chunk = testing_chunk();
@@ -221,6 +676,105 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_invalid_chunk_errors() {
+        let mut chunk_data = testing_chunk().as_bytes();
+        let last = chunk_data.len() - 1;
+        chunk_data[last] ^= 1;
+        assert!(matches!(
+            Chunk::try_from(chunk_data.as_ref()),
+            Err(PngmeError::CrcMismatch {
+                expected: 2882656335,
+                actual: 2882656334
+            })
+        ));
+
+        chunk_data.truncate(20);
+        assert!(matches!(
+            Chunk::try_from(chunk_data.as_ref()),
+            Err(PngmeError::TruncatedChunk)
+        ));
+    }
+
+    #[test]
+    fn test_oversized_length_field() {
+        let mut chunk_data = testing_chunk().as_bytes();
+        for length in [u32::MAX, MAX_CHUNK_LENGTH + 1] {
+            chunk_data[..4].copy_from_slice(&length.to_be_bytes());
+            assert!(matches!(
+                Chunk::try_from(chunk_data.as_ref()),
+                Err(PngmeError::ChunkTooLarge(_))
+            ));
+            assert!(matches!(
+                ChunkRef::parse(&chunk_data),
+                Err(PngmeError::ChunkTooLarge(_))
+            ));
+        }
+        chunk_data[..4].copy_from_slice(&MAX_CHUNK_LENGTH.to_be_bytes());
+        assert!(matches!(
+            Chunk::from_bytes_unchecked(&chunk_data),
+            Err(PngmeError::TruncatedChunk)
+        ));
+    }
+
+    #[test]
+    fn test_compressed_chunk() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let message = "compress me ".repeat(100);
+        let chunk = Chunk::new_compressed(chunk_type, message.as_bytes()).unwrap();
+        assert!((chunk.length() as usize) < message.len());
+        assert_eq!(chunk.decoded_data().unwrap(), message.as_bytes());
+        assert_eq!(
+            testing_chunk().decoded_data().unwrap(),
+            testing_chunk().data()
+        );
+    }
+
+    #[test]
+    fn test_encrypted_chunk() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let chunk = Chunk::new_encrypted(chunk_type, b"top secret", "passphrase").unwrap();
+        assert!(!chunk.data().windows(10).any(|w| w == b"top secret"));
+        assert_eq!(chunk.decrypt_data("passphrase").unwrap(), b"top secret");
+        assert!(chunk.decrypt_data("wrong").is_err());
+    }
+
+    #[test]
+    fn test_crc_covers_type_and_data() {
+        // IEND 的 CRC 是众所周知的 AE426082
+        let iend = Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()).unwrap();
+        assert_eq!(iend.crc(), 0xAE426082);
+    }
+
+    #[test]
+    fn test_legacy_crc_migration() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"This is where your secret message will be!".to_vec();
+        let legacy_crc = Chunk::legacy_crc(&data);
+
+        let legacy_bytes: Vec<u8> = (data.len() as u32)
+            .to_be_bytes()
+            .iter()
+            .chain(chunk_type.bytes().iter())
+            .chain(data.iter())
+            .chain(legacy_crc.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        assert!(Chunk::try_from(legacy_bytes.as_ref()).is_err());
+        let chunk = Chunk::from_legacy_bytes(legacy_bytes.as_ref()).unwrap();
+        assert_eq!(chunk.crc(), 2882656334);
+        assert_eq!(chunk, testing_chunk());
+    }
+
+    #[test]
+    fn test_legacy_parser_still_rejects_bad_crc() {
+        let mut bytes = testing_chunk().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(Chunk::from_legacy_bytes(bytes.as_ref()).is_err());
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -241,4 +795,77 @@ mod tests {
 
         let _chunk_string = format!("{}", chunk);
     }
+
+    #[test]
+    fn test_recompute_crc() {
+        let mut bytes = testing_chunk().as_bytes();
+        let end = bytes.len();
+        bytes[end - 1] ^= 0xff;
+        assert!(Chunk::try_from(bytes.as_ref()).is_err());
+
+        let mut chunk = Chunk::from_bytes_unchecked(&bytes).unwrap();
+        assert!(!chunk.has_valid_crc());
+        assert!(chunk.recompute_crc());
+        assert!(chunk.has_valid_crc());
+        assert_eq!(chunk, testing_chunk());
+        assert!(!chunk.recompute_crc());
+    }
+
+    #[test]
+    fn test_set_data() {
+        let mut chunk = testing_chunk();
+        chunk.set_data(b"replaced".to_vec()).unwrap();
+        assert_eq!(chunk.length(), 8);
+        assert_eq!(chunk.data(), b"replaced");
+        assert!(chunk.has_valid_crc());
+        assert_eq!(chunk.chunk_type().to_string(), "RuSt");
+
+        chunk.map_data(|data| data.to_ascii_uppercase()).unwrap();
+        assert_eq!(
+            chunk,
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"REPLACED".to_vec()).unwrap()
+        );
+
+        // 存储的 CRC 错误的 chunk 在修改后得到正确的 CRC
+        let mut bytes = testing_chunk().as_bytes();
+        let end = bytes.len();
+        bytes[end - 1] ^= 0xff;
+        let mut chunk = Chunk::from_bytes_unchecked(&bytes).unwrap();
+        chunk.map_data(|data| data.to_vec()).unwrap();
+        assert_eq!(chunk, testing_chunk());
+    }
+
+    proptest! {
+        /// crc32fast 的结果与按查找表逐字节计算的 CRC-32/ISO-HDLC 一致
+        #[test]
+        fn prop_crc_matches_reference(
+            chunk_type in crate::chunk_type::tests::any_chunk_type(),
+            data in vec(any::<u8>(), 0..4096),
+        ) {
+            let reference = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+            let mut digest = reference.digest();
+            digest.update(&chunk_type.bytes());
+            digest.update(&data);
+            prop_assert_eq!(Chunk::calculate_crc(&chunk_type, &data), digest.finalize());
+        }
+
+        #[test]
+        fn prop_chunk_roundtrip(
+            chunk_type in crate::chunk_type::tests::any_chunk_type(),
+            data in vec(any::<u8>(), 0..1024),
+        ) {
+            let chunk = Chunk::new(chunk_type, data).unwrap();
+            let bytes = chunk.as_bytes();
+            prop_assert_eq!(bytes.len(), chunk.length() as usize + 12);
+            prop_assert_eq!(Chunk::try_from(bytes.as_slice()).unwrap(), chunk);
+        }
+
+        /// 任意字节都只能返回错误，不能 panic
+        #[test]
+        fn prop_parse_arbitrary_bytes(bytes in vec(any::<u8>(), 0..64)) {
+            let _ = Chunk::try_from(bytes.as_slice());
+            let _ = Chunk::from_bytes_unchecked(&bytes);
+            let _ = Chunk::from_legacy_bytes(&bytes);
+        }
+    }
 }
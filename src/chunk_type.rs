@@ -8,11 +8,74 @@ use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
+use crate::PngmeError;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct ChunkType([u8; 4]);
 
-#[allow(dead_code)]
 impl ChunkType {
+    // 规范中定义的类型，与 `KnownChunk::ALL` 一一对应
+    pub const IHDR: ChunkType = ChunkType(*b"IHDR");
+    pub const PLTE: ChunkType = ChunkType(*b"PLTE");
+    pub const IDAT: ChunkType = ChunkType(*b"IDAT");
+    pub const IEND: ChunkType = ChunkType(*b"IEND");
+    pub const CHRM: ChunkType = ChunkType(*b"cHRM");
+    pub const GAMA: ChunkType = ChunkType(*b"gAMA");
+    pub const ICCP: ChunkType = ChunkType(*b"iCCP");
+    pub const SBIT: ChunkType = ChunkType(*b"sBIT");
+    pub const SRGB: ChunkType = ChunkType(*b"sRGB");
+    pub const CICP: ChunkType = ChunkType(*b"cICP");
+    pub const MDCV: ChunkType = ChunkType(*b"mDCV");
+    pub const CLLI: ChunkType = ChunkType(*b"cLLI");
+    pub const TRNS: ChunkType = ChunkType(*b"tRNS");
+    pub const BKGD: ChunkType = ChunkType(*b"bKGD");
+    pub const HIST: ChunkType = ChunkType(*b"hIST");
+    pub const PHYS: ChunkType = ChunkType(*b"pHYs");
+    pub const SPLT: ChunkType = ChunkType(*b"sPLT");
+    pub const EXIF: ChunkType = ChunkType(*b"eXIf");
+    pub const TIME: ChunkType = ChunkType(*b"tIME");
+    pub const TEXT: ChunkType = ChunkType(*b"tEXt");
+    pub const ZTXT: ChunkType = ChunkType(*b"zTXt");
+    pub const ITXT: ChunkType = ChunkType(*b"iTXt");
+    pub const ACTL: ChunkType = ChunkType(*b"acTL");
+    pub const FCTL: ChunkType = ChunkType(*b"fcTL");
+    pub const FDAT: ChunkType = ChunkType(*b"fdAT");
+
+    /// 根据 1 到 4 个 ASCII 字母的短名称生成私有、可安全复制的辅助类型。
+    /// 名称循环填满 4 个字节后按位置设置大小写，例如 `"xx"` 生成 `xxXx`，`"rust"` 生成 `ruSt`
+    pub fn new_private_ancillary(name: &str) -> Result<Self, PngmeError> {
+        let letters = name.as_bytes();
+        if letters.is_empty() || letters.len() > 4 || !letters.iter().all(u8::is_ascii_alphabetic) {
+            return Err(PngmeError::InvalidChunkType(letters.to_vec()));
+        }
+        let mut bytes = [0u8; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = letters[i % letters.len()].to_ascii_lowercase();
+        }
+        bytes[2] = bytes[2].to_ascii_uppercase();
+        Ok(ChunkType(bytes))
+    }
+
+    /// 设置可安全复制位（第四个字节小写）后的类型
+    pub fn with_safe_to_copy(self, safe_to_copy: bool) -> Self {
+        self.with_case(3, safe_to_copy)
+    }
+
+    /// 设置私有位（第二个字节小写）后的类型
+    pub fn with_private(self, private: bool) -> Self {
+        self.with_case(1, private)
+    }
+
+    /// 第 `index` 个字节的第5位置为 `lowercase`，字节仍是字母，结果总是有效的
+    fn with_case(mut self, index: usize, lowercase: bool) -> Self {
+        if lowercase {
+            self.0[index] |= 32;
+        } else {
+            self.0[index] &= !32;
+        }
+        self
+    }
+
     /// 获取 ChunkType 的字节数组表示
     pub fn bytes(&self) -> [u8; 4] {
         self.0
@@ -57,10 +120,18 @@ impl ChunkType {
         self.0[1] & 32 == 32 // 判断第二个字节的第5位是否为小写
     }
 
+    /// 规范中定义的类型返回对应的 `KnownChunk`，私有或未知的类型返回 `None`
+    pub fn known(&self) -> Option<KnownChunk> {
+        KnownChunk::ALL
+            .into_iter()
+            .find(|known| known.as_str().as_bytes() == self.0)
+    }
+
     /// from_str 方法
-    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, PngmeError> {
         if s.len() != 4 {
-            return Err("Invalid chunk type");
+            return Err(PngmeError::InvalidChunkType(s.as_bytes().to_vec()));
         }
         let mut bytes = [0u8; 4];
         for (i, c) in s.chars().enumerate() {
@@ -71,25 +142,25 @@ impl ChunkType {
 }
 
 impl TryFrom<[u8; 4]> for ChunkType {
-    type Error = &'static str;
+    type Error = PngmeError;
 
     /// 尝试将字节数组转换为 ChunkType 类型
     fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
         if ChunkType::is_valid_type(value) {
             Ok(ChunkType(value))
         } else {
-            Err("Invalid chunk type")
+            Err(PngmeError::InvalidChunkType(value.to_vec()))
         }
     }
 }
 
 impl FromStr for ChunkType {
-    type Err = &'static str;
+    type Err = PngmeError;
 
     /// 将字符串解析为 ChunkType 类型
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.len() != 4 {
-            return Err("Invalid chunk type");
+            return Err(PngmeError::InvalidChunkType(s.as_bytes().to_vec()));
         }
         let mut bytes = [0u8; 4];
         for (i, c) in s.chars().enumerate() {
@@ -107,6 +178,140 @@ impl fmt::Display for ChunkType {
     }
 }
 
+/// PNG 规范（包括 APNG 扩展）中定义的 chunk 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownChunk {
+    Ihdr,
+    Plte,
+    Idat,
+    Iend,
+    Chrm,
+    Gama,
+    Iccp,
+    Sbit,
+    Srgb,
+    Cicp,
+    Mdcv,
+    Clli,
+    Trns,
+    Bkgd,
+    Hist,
+    Phys,
+    Splt,
+    Exif,
+    Time,
+    Text,
+    Ztxt,
+    Itxt,
+    Actl,
+    Fctl,
+    Fdat,
+}
+
+impl KnownChunk {
+    /// 所有已知类型，按规范中的顺序排列
+    pub const ALL: [KnownChunk; 25] = [
+        KnownChunk::Ihdr,
+        KnownChunk::Plte,
+        KnownChunk::Idat,
+        KnownChunk::Iend,
+        KnownChunk::Chrm,
+        KnownChunk::Gama,
+        KnownChunk::Iccp,
+        KnownChunk::Sbit,
+        KnownChunk::Srgb,
+        KnownChunk::Cicp,
+        KnownChunk::Mdcv,
+        KnownChunk::Clli,
+        KnownChunk::Trns,
+        KnownChunk::Bkgd,
+        KnownChunk::Hist,
+        KnownChunk::Phys,
+        KnownChunk::Splt,
+        KnownChunk::Exif,
+        KnownChunk::Time,
+        KnownChunk::Text,
+        KnownChunk::Ztxt,
+        KnownChunk::Itxt,
+        KnownChunk::Actl,
+        KnownChunk::Fctl,
+        KnownChunk::Fdat,
+    ];
+
+    /// 类型码，例如 `"IHDR"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KnownChunk::Ihdr => "IHDR",
+            KnownChunk::Plte => "PLTE",
+            KnownChunk::Idat => "IDAT",
+            KnownChunk::Iend => "IEND",
+            KnownChunk::Chrm => "cHRM",
+            KnownChunk::Gama => "gAMA",
+            KnownChunk::Iccp => "iCCP",
+            KnownChunk::Sbit => "sBIT",
+            KnownChunk::Srgb => "sRGB",
+            KnownChunk::Cicp => "cICP",
+            KnownChunk::Mdcv => "mDCV",
+            KnownChunk::Clli => "cLLI",
+            KnownChunk::Trns => "tRNS",
+            KnownChunk::Bkgd => "bKGD",
+            KnownChunk::Hist => "hIST",
+            KnownChunk::Phys => "pHYs",
+            KnownChunk::Splt => "sPLT",
+            KnownChunk::Exif => "eXIf",
+            KnownChunk::Time => "tIME",
+            KnownChunk::Text => "tEXt",
+            KnownChunk::Ztxt => "zTXt",
+            KnownChunk::Itxt => "iTXt",
+            KnownChunk::Actl => "acTL",
+            KnownChunk::Fctl => "fcTL",
+            KnownChunk::Fdat => "fdAT",
+        }
+    }
+
+    /// 简短的英文名称，用于输出
+    pub fn name(self) -> &'static str {
+        match self {
+            KnownChunk::Ihdr => "image header",
+            KnownChunk::Plte => "palette",
+            KnownChunk::Idat => "image data",
+            KnownChunk::Iend => "image trailer",
+            KnownChunk::Chrm => "primary chromaticities",
+            KnownChunk::Gama => "image gamma",
+            KnownChunk::Iccp => "embedded ICC profile",
+            KnownChunk::Sbit => "significant bits",
+            KnownChunk::Srgb => "standard RGB color space",
+            KnownChunk::Cicp => "coding-independent code points",
+            KnownChunk::Mdcv => "mastering display color volume",
+            KnownChunk::Clli => "content light level",
+            KnownChunk::Trns => "transparency",
+            KnownChunk::Bkgd => "background color",
+            KnownChunk::Hist => "palette histogram",
+            KnownChunk::Phys => "physical pixel dimensions",
+            KnownChunk::Splt => "suggested palette",
+            KnownChunk::Exif => "Exif data",
+            KnownChunk::Time => "last modification time",
+            KnownChunk::Text => "text",
+            KnownChunk::Ztxt => "compressed text",
+            KnownChunk::Itxt => "international text",
+            KnownChunk::Actl => "animation control",
+            KnownChunk::Fctl => "frame control",
+            KnownChunk::Fdat => "frame data",
+        }
+    }
+
+    pub fn chunk_type(self) -> ChunkType {
+        let bytes = self.as_str().as_bytes();
+        ChunkType([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+impl fmt::Display for KnownChunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 impl ChunkType {
     /// 检查是否为有效的 ChunkType 类型
     fn is_valid_type(value: [u8; 4]) -> bool {
@@ -116,8 +321,9 @@ impl ChunkType {
 
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::convert::TryFrom;
     use std::str::FromStr;
 
@@ -212,4 +418,86 @@ mod tests {
         let _chunk_string = format!("{}", chunk_type_1);
         let _are_chunks_equal = chunk_type_1 == chunk_type_2;
     }
+
+    #[test]
+    pub fn test_known_chunks() {
+        let known = |s: &str| ChunkType::from_str(s).unwrap().known();
+        assert_eq!(known("IHDR"), Some(KnownChunk::Ihdr));
+        assert_eq!(known("gAMA"), Some(KnownChunk::Gama));
+        assert_eq!(known("GAMA"), None);
+        assert_eq!(known("ruSt"), None);
+        for chunk in KnownChunk::ALL {
+            assert_eq!(chunk.chunk_type().known(), Some(chunk));
+            assert_eq!(chunk.to_string(), chunk.as_str());
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_constants() {
+        assert_eq!(ChunkType::IHDR, ChunkType::from_str("IHDR").unwrap());
+        assert_eq!(ChunkType::TEXT.to_string(), "tEXt");
+        assert_eq!(ChunkType::MDCV.known(), Some(KnownChunk::Mdcv));
+        assert_eq!(ChunkType::FDAT, KnownChunk::Fdat.chunk_type());
+    }
+
+    #[test]
+    pub fn test_new_private_ancillary() {
+        for (name, expected) in [
+            ("xx", "xxXx"),
+            ("rust", "ruSt"),
+            ("A", "aaAa"),
+            ("PnG", "pnGp"),
+        ] {
+            let chunk = ChunkType::new_private_ancillary(name).unwrap();
+            assert_eq!(chunk.to_string(), expected);
+            assert!(!chunk.is_critical());
+            assert!(chunk.is_private());
+            assert!(chunk.is_reserved_bit_valid());
+            assert!(chunk.is_safe_to_copy());
+            assert!(chunk.is_valid());
+        }
+        for name in ["", "toolong", "x1", "ü"] {
+            assert!(ChunkType::new_private_ancillary(name).is_err());
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_property_setters() {
+        let chunk = ChunkType::from_str("ruSt").unwrap();
+        assert_eq!(chunk.with_safe_to_copy(false).to_string(), "ruST");
+        assert_eq!(chunk.with_safe_to_copy(true), chunk);
+        assert_eq!(chunk.with_private(false).to_string(), "rUSt");
+        let public = chunk.with_private(false).with_safe_to_copy(false);
+        assert!(public.is_public());
+        assert!(!public.is_safe_to_copy());
+    }
+
+    /// 任意合法的 chunk 类型：4 个 ASCII 字母
+    pub(crate) fn any_chunk_type() -> impl Strategy<Value = ChunkType> {
+        proptest::array::uniform4(prop_oneof![b'a'..=b'z', b'A'..=b'Z'])
+            .prop_map(|bytes| ChunkType::try_from(bytes).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_chunk_type_roundtrip(chunk_type in any_chunk_type()) {
+            let parsed: ChunkType = chunk_type.to_string().parse().unwrap();
+            prop_assert_eq!(parsed, chunk_type);
+            prop_assert_eq!(ChunkType::try_from(chunk_type.bytes()).unwrap(), chunk_type);
+            let toggled = chunk_type.with_private(true).with_safe_to_copy(false);
+            prop_assert!(toggled.is_private() && !toggled.is_safe_to_copy());
+            prop_assert_eq!(toggled.is_critical(), chunk_type.is_critical());
+        }
+
+        #[test]
+        fn prop_invalid_bytes_rejected(bytes in any::<[u8; 4]>()) {
+            let valid = bytes.iter().all(u8::is_ascii_alphabetic);
+            prop_assert_eq!(ChunkType::try_from(bytes).is_ok(), valid);
+        }
+
+        #[test]
+        fn prop_from_str_arbitrary(s in ".{0,6}") {
+            let _ = ChunkType::from_str(&s);
+        }
+    }
 }
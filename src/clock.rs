@@ -0,0 +1,153 @@
+//! # Clock
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块把“当前时间”抽象为 `Clock` trait，需要时间戳的功能都应通过它获取时间，
+//! 这样测试和确定性模式可以固定时间，没有系统时钟的平台也可以自行提供。
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 时间来源
+pub trait Clock: Send + Sync {
+    /// 获取当前时间
+    fn now(&self) -> SystemTime;
+}
+
+/// 使用系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// 总是返回同一时间的时钟
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(SystemTime);
+
+impl FixedClock {
+    /// 固定在指定时间
+    pub fn new(time: SystemTime) -> Self {
+        FixedClock(time)
+    }
+
+    /// 固定在 Unix 纪元之后的指定秒数
+    pub fn from_unix_secs(secs: u64) -> Self {
+        FixedClock(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// UTC 日期和时间，精确到秒
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl UtcDateTime {
+    /// 由 `SystemTime` 换算，早于 Unix 纪元的时间按纪元处理
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0) as i64;
+        let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+        // Howard Hinnant 的 civil_from_days 算法
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        UtcDateTime {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u8,
+            minute: (rem % 3600 / 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+
+    /// 换算为 `SystemTime`
+    pub fn to_system_time(self) -> SystemTime {
+        // Howard Hinnant 的 days_from_civil 算法
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        let secs = days * 86_400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second);
+        UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let clock = FixedClock::from_unix_secs(1_700_000_000);
+        assert_eq!(
+            clock.now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            1_700_000_000
+        );
+        assert_eq!(clock.now(), clock.now());
+    }
+
+    #[test]
+    fn test_utc_date_time() {
+        let time = UtcDateTime::from_system_time(FixedClock::from_unix_secs(1_700_000_000).now());
+        assert_eq!(
+            time,
+            UtcDateTime {
+                year: 2023,
+                month: 11,
+                day: 14,
+                hour: 22,
+                minute: 13,
+                second: 20,
+            }
+        );
+        assert_eq!(
+            time.to_system_time(),
+            FixedClock::from_unix_secs(1_700_000_000).now()
+        );
+
+        let leap_day = UtcDateTime::from_system_time(FixedClock::from_unix_secs(951_782_400).now());
+        assert_eq!((leap_day.year, leap_day.month, leap_day.day), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_clock_as_trait_object() {
+        let clocks: Vec<Box<dyn Clock>> =
+            vec![Box::new(SystemClock), Box::new(FixedClock::new(UNIX_EPOCH))];
+        assert!(clocks[0].now() > UNIX_EPOCH);
+        assert_eq!(clocks[1].now(), UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,111 @@
+//! # Compat
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块为旧 API 提供过渡层。最早的 pngme 以 `Box<dyn Error>` 作为错误类型，
+//! 操作都是一次性的 encode、decode、remove 和 print；现在错误统一为 `PngmeError`，
+//! 操作由 `Png`、`Engine` 和 `simple` 提供。这里的函数保持旧的签名并转发到新的实现，
+//! 都标记为 deprecated，下游可以逐个替换调用，而不必一次改完。
+//!
+//! 还在使用 `Box<dyn Error>` 的代码可以用 `into_pngme_error` 把错误转换回 `PngmeError`。
+//!
+//! 有一处改动无法在这里过渡：`Png::header` 现在解析 IHDR 并返回 `Result<Ihdr>`，
+//! 原来返回 8 字节签名的 `header` 改名为 `Png::signature`，取签名的调用需要改用它。
+
+use std::io;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::payload::{self, Payload};
+use crate::png::Png;
+use crate::PngmeError;
+
+/// 旧 API 的错误类型
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+/// 旧 API 的结果类型
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 在 `png` 中写入 `chunk_type` 类型的信息，返回新的 PNG 文件
+#[deprecated(
+    since = "0.1.0",
+    note = "use `Png::append_chunk` or `Engine::process(Request::Encode { .. })`"
+)]
+pub fn encode(png: &[u8], chunk_type: &str, message: &str) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png)?;
+    let chunk_type = ChunkType::from_str(chunk_type)?;
+    png.append_chunk(Chunk::new(chunk_type, message.as_bytes().to_vec())?);
+    Ok(png.as_bytes())
+}
+
+/// 读取 `png` 中 `chunk_type` 类型的信息
+#[deprecated(
+    since = "0.1.0",
+    note = "use `payload::message_data` or `Engine::process(Request::Decode { .. })`"
+)]
+pub fn decode(png: &[u8], chunk_type: &str) -> Result<String> {
+    let png = Png::try_from(png)?;
+    let payload = Payload::from_bytes(&payload::message_data(&png, chunk_type)?)?;
+    Ok(String::from_utf8(payload.data)?)
+}
+
+/// 删除 `png` 中 `chunk_type` 类型的信息，返回新的 PNG 文件
+#[deprecated(
+    since = "0.1.0",
+    note = "use `payload::remove_message` or `Engine::process(Request::Remove { .. })`"
+)]
+pub fn remove(png: &[u8], chunk_type: &str) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png)?;
+    payload::remove_message(&mut png, chunk_type)?;
+    Ok(png.as_bytes())
+}
+
+/// 列出 `png` 中的所有 chunk，格式与 `Png` 的 `Display` 相同
+#[deprecated(since = "0.1.0", note = "use `Png`'s `Display` implementation")]
+pub fn print_chunks(png: &[u8]) -> Result<String> {
+    Ok(Png::try_from(png)?.to_string())
+}
+
+/// 把旧 API 的错误转换为 `PngmeError`：本来就是 `PngmeError` 时原样取回，
+/// `io::Error` 转换为 `PngmeError::Io`，其他错误包装为 `io::ErrorKind::Other`
+pub fn into_pngme_error(error: Error) -> PngmeError {
+    match error.downcast::<PngmeError>() {
+        Ok(error) => *error,
+        Err(error) => match error.downcast::<io::Error>() {
+            Ok(error) => PngmeError::Io(*error),
+            Err(error) => PngmeError::Io(io::Error::other(error)),
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_forwarding_functions() {
+        let output = encode(&PNG_FILE, "ruSt", "hello").unwrap();
+        assert_eq!(decode(&output, "ruSt").unwrap(), "hello");
+        assert!(print_chunks(&output).unwrap().contains("ruSt (5 bytes"));
+        assert_eq!(remove(&output, "ruSt").unwrap(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_into_pngme_error() {
+        let error = decode(&PNG_FILE, "ruSt").unwrap_err();
+        assert!(matches!(
+            into_pngme_error(error),
+            PngmeError::ChunkNotFound(_)
+        ));
+
+        let error: Error = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(
+            into_pngme_error(error),
+            PngmeError::Io(e) if e.kind() == io::ErrorKind::NotFound
+        ));
+
+        let error: Error = "something else".into();
+        assert!(matches!(into_pngme_error(error), PngmeError::Io(_)));
+    }
+}
@@ -0,0 +1,391 @@
+//! # Config
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块读取配置文件 `pngme.toml`，为常用的选项提供默认值，团队可以把配置文件放在仓库中，
+//! 不必在每次调用时重复一长串参数：
+//!
+//! ```toml
+//! chunk_type = "teAm"                  # 命令行中的 chunk 类型为 @default 时使用
+//! compress = true
+//! encrypt = true                       # 口令仍然通过 --passphrase 或 PNGME_PASSPHRASE 提供
+//! chunk_size = 65536
+//! name_template = "{stem}.stego.{ext}" # 与 --output-dir 一起使用
+//! ```
+//!
+//! 依次查找当前目录和 `$XDG_CONFIG_HOME/pngme/`（未设置时为 `~/.config/pngme/`），只使用找到的第一个文件。
+//! 命令行中明确给出的参数总是优先，`--no-compress`、`--no-encrypt`、`--no-decrypt` 用于关闭配置中打开的开关。
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+
+use crate::args::{
+    BatchArgs, ExifCommand, IccCommand, ManifestCommand, MetaCommand, PngMeArgs, WatermarkCommand,
+};
+use crate::chunk_type::ChunkType;
+use crate::commands::DERIVED_CHUNK_TYPE;
+use crate::simple::DEFAULT_CHUNK_TYPE;
+use crate::{PngmeError, Result};
+
+/// 配置文件名
+pub const FILE_NAME: &str = "pngme.toml";
+
+/// 命令行中表示“使用配置文件中的 chunk 类型”的值
+pub const CONFIG_CHUNK_TYPE: &str = "@default";
+
+/// `pngme.toml` 的内容，没有给出的选项使用命令行的默认值
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `@default` 对应的 chunk 类型，未设置时为 `simple::DEFAULT_CHUNK_TYPE`
+    pub chunk_type: Option<String>,
+    /// encode 时压缩信息
+    pub compress: Option<bool>,
+    /// encode 时加密、decode 时解密信息
+    pub encrypt: Option<bool>,
+    /// encode 时单个 chunk 的最大数据长度
+    pub chunk_size: Option<usize>,
+    /// 批处理的输出文件名模板
+    pub name_template: Option<String>,
+}
+
+impl Config {
+    /// 解析配置文件的内容
+    pub fn from_toml(text: &str) -> Result<Config> {
+        let config: Config =
+            toml::from_str(text).map_err(|e| PngmeError::Config(e.message().to_string()))?;
+        if let Some(chunk_type) = &config.chunk_type {
+            if chunk_type != DERIVED_CHUNK_TYPE {
+                ChunkType::from_str(chunk_type)
+                    .map_err(|e| PngmeError::Config(format!("chunk_type: {}", e)))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// 按 `search_paths` 的顺序读取第一个存在的配置文件，都不存在时返回默认配置
+    pub fn load() -> Result<Config> {
+        let cwd = std::env::current_dir()?;
+        for path in search_paths(&cwd, |name| std::env::var_os(name)) {
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            return Config::from_toml(&text).map_err(|e| match e {
+                PngmeError::Config(message) => {
+                    PngmeError::Config(format!("{}: {}", path.display(), message))
+                }
+                e => e,
+            });
+        }
+        Ok(Config::default())
+    }
+
+    /// 把配置中的默认值填入 `command`。`matches` 是解析 `command` 得到的结果，
+    /// 用于区分命令行中明确给出的参数和 clap 的默认值
+    pub fn apply(&self, command: &mut PngMeArgs, matches: &ArgMatches) -> Result<()> {
+        let mut matches = matches;
+        while let Some((_, subcommand)) = matches.subcommand() {
+            matches = subcommand;
+        }
+        let is_default = |id: &str| matches.value_source(id) == Some(ValueSource::DefaultValue);
+
+        match command {
+            PngMeArgs::Encode(args) => {
+                // 使用 --label 时 chunk 类型的位置上是信息
+                if args.label.is_none() {
+                    args.chunk_type = self.chunk_type(&args.chunk_type);
+                }
+                if self.compress == Some(true) && !args.no_compress {
+                    args.compress = true;
+                }
+                if self.encrypt == Some(true) && !args.no_encrypt {
+                    args.encrypt = true;
+                    require_passphrase(args.passphrase.as_deref(), "--no-encrypt")?;
+                }
+                if let (Some(chunk_size), true) = (self.chunk_size, is_default("chunk_size")) {
+                    args.chunk_size = chunk_size;
+                }
+            }
+            PngMeArgs::Decode(args) => {
+                args.chunk_type = self.chunk_type(&args.chunk_type);
+                if self.encrypt == Some(true) && !args.no_decrypt {
+                    args.decrypt = true;
+                    require_passphrase(args.passphrase.as_deref(), "--no-decrypt")?;
+                }
+            }
+            PngMeArgs::Remove(args) => args.chunk_type = self.chunk_type(&args.chunk_type),
+            _ => {}
+        }
+
+        if let (Some(template), Some(batch)) = (&self.name_template, batch_args(command)) {
+            if is_default("name_template") {
+                batch.name_template = template.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// 把命令行中的 `@default` 替换为配置的 chunk 类型，其他值不变
+    fn chunk_type(&self, chunk_type: &str) -> String {
+        if chunk_type != CONFIG_CHUNK_TYPE {
+            return chunk_type.to_string();
+        }
+        self.chunk_type
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CHUNK_TYPE.to_string())
+    }
+}
+
+/// 配置文件的查找顺序：当前目录，然后是 `$XDG_CONFIG_HOME/pngme/`，
+/// 未设置 `XDG_CONFIG_HOME` 时为 `$HOME/.config/pngme/`，Windows 上没有 `HOME` 时为 `%APPDATA%\pngme\`
+pub fn search_paths(cwd: &Path, env: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let env = |name: &str| {
+        env(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let mut paths = vec![cwd.join(FILE_NAME)];
+    let config_home = env("XDG_CONFIG_HOME")
+        .or_else(|| env("HOME").map(|home| home.join(".config")))
+        .or_else(|| env("APPDATA"));
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("pngme").join(FILE_NAME));
+    }
+    paths
+}
+
+/// 配置打开了加密时必须提供口令，否则信息会以明文写入
+fn require_passphrase(passphrase: Option<&str>, opt_out: &str) -> Result<()> {
+    if passphrase.is_some() {
+        return Ok(());
+    }
+    Err(PngmeError::Config(format!(
+        "encrypt = true needs --passphrase or PNGME_PASSPHRASE (use {} to turn it off)",
+        opt_out
+    )))
+}
+
+/// 支持批处理的子命令的批处理参数
+fn batch_args(command: &mut PngMeArgs) -> Option<&mut BatchArgs> {
+    match command {
+        PngMeArgs::Encode(args) => Some(&mut args.batch),
+        PngMeArgs::Decode(args) => Some(&mut args.batch),
+        PngMeArgs::Remove(args) => Some(&mut args.batch),
+        PngMeArgs::Print(args) => Some(&mut args.batch),
+        PngMeArgs::Scan(args) => Some(&mut args.batch),
+        PngMeArgs::Capacity(args) => Some(&mut args.batch),
+        PngMeArgs::Repair(args) => Some(&mut args.batch),
+        PngMeArgs::Strip(args) => Some(&mut args.batch),
+        PngMeArgs::Merge(args) => Some(&mut args.batch),
+        PngMeArgs::Optimize(args) => Some(&mut args.batch),
+        PngMeArgs::Canonicalize(args) => Some(&mut args.batch),
+        PngMeArgs::Profile(args) => Some(&mut args.batch),
+        PngMeArgs::Sign(args) => Some(&mut args.batch),
+        PngMeArgs::Verify(args) => Some(&mut args.batch),
+        PngMeArgs::Manifest(ManifestCommand::Create(args)) => Some(&mut args.batch),
+        PngMeArgs::Stamp(args) => Some(&mut args.batch),
+        PngMeArgs::Metadata(args) => Some(&mut args.batch),
+        PngMeArgs::Meta(MetaCommand::Set(args)) => Some(&mut args.batch),
+        PngMeArgs::Meta(MetaCommand::Delete(args)) => Some(&mut args.batch),
+        PngMeArgs::Touch(args) => Some(&mut args.batch),
+        PngMeArgs::SetDpi(args) => Some(&mut args.batch),
+        PngMeArgs::SetGamma(args) => Some(&mut args.batch),
+        PngMeArgs::Exif(ExifCommand::Set(args)) => Some(&mut args.batch),
+        PngMeArgs::Exif(ExifCommand::Remove(args)) => Some(&mut args.batch),
+        PngMeArgs::Icc(IccCommand::Embed(args)) => Some(&mut args.batch),
+        PngMeArgs::Watermark(WatermarkCommand::Embed(args)) => Some(&mut args.batch),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Cli;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// 解析命令行并应用配置
+    fn parse(config: &Config, args: &[&str]) -> Result<PngMeArgs> {
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("pngme").chain(args.iter().copied()))
+            .unwrap();
+        let mut command = Cli::from_arg_matches(&matches).unwrap().command.unwrap();
+        config.apply(&mut command, &matches)?;
+        Ok(command)
+    }
+
+    fn team_config() -> Config {
+        Config::from_toml(
+            r#"
+            chunk_type = "teAm"
+            compress = true
+            chunk_size = 64
+            name_template = "{stem}.out.{ext}"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = team_config();
+        assert_eq!(config.chunk_type.as_deref(), Some("teAm"));
+        assert_eq!(config.compress, Some(true));
+        assert_eq!(config.encrypt, None);
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+
+        assert!(matches!(
+            Config::from_toml("compres = true"),
+            Err(PngmeError::Config(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("chunk_type = \"bad!\""),
+            Err(PngmeError::Config(_))
+        ));
+        assert!(Config::from_toml("chunk_type = \"@passphrase\"").is_ok());
+    }
+
+    #[test]
+    fn test_apply_encode() {
+        let config = team_config();
+        let PngMeArgs::Encode(args) =
+            parse(&config, &["encode", "a.png", "@default", "hi"]).unwrap()
+        else {
+            panic!("expected encode");
+        };
+        assert_eq!(args.chunk_type, "teAm");
+        assert!(args.compress);
+        assert_eq!(args.chunk_size, 64);
+
+        // 命令行中的参数优先
+        let command = parse(
+            &config,
+            &[
+                "encode",
+                "a.png",
+                "ruSt",
+                "hi",
+                "--no-compress",
+                "--chunk-size",
+                "128",
+            ],
+        );
+        let PngMeArgs::Encode(args) = command.unwrap() else {
+            panic!("expected encode");
+        };
+        assert_eq!(args.chunk_type, "ruSt");
+        assert!(!args.compress);
+        assert_eq!(args.chunk_size, 128);
+
+        let PngMeArgs::Encode(args) =
+            parse(&Config::default(), &["encode", "a.png", "@default", "hi"]).unwrap()
+        else {
+            panic!("expected encode");
+        };
+        assert_eq!(args.chunk_type, DEFAULT_CHUNK_TYPE);
+    }
+
+    #[test]
+    fn test_apply_encryption() {
+        let config = Config {
+            encrypt: Some(true),
+            ..Config::default()
+        };
+        let command = parse(
+            &config,
+            &["encode", "a.png", "ruSt", "hi", "--passphrase", "secret"],
+        );
+        let PngMeArgs::Encode(args) = command.unwrap() else {
+            panic!("expected encode");
+        };
+        assert!(args.encrypt);
+
+        // 没有口令时报错，而不是写入明文
+        assert!(matches!(
+            parse(&config, &["encode", "a.png", "ruSt", "hi"]),
+            Err(PngmeError::Config(_))
+        ));
+        let PngMeArgs::Encode(args) =
+            parse(&config, &["encode", "a.png", "ruSt", "hi", "--no-encrypt"]).unwrap()
+        else {
+            panic!("expected encode");
+        };
+        assert!(!args.encrypt);
+
+        let PngMeArgs::Decode(args) = parse(
+            &config,
+            &["decode", "a.png", "ruSt", "--passphrase", "secret"],
+        )
+        .unwrap() else {
+            panic!("expected decode");
+        };
+        assert!(args.decrypt);
+        assert!(parse(&config, &["decode", "a.png", "ruSt", "--no-decrypt"]).is_ok());
+    }
+
+    #[test]
+    fn test_apply_name_template() {
+        let config = team_config();
+        let PngMeArgs::Strip(args) =
+            parse(&config, &["strip", "dir", "--output-dir", "out"]).unwrap()
+        else {
+            panic!("expected strip");
+        };
+        assert_eq!(args.batch.name_template, "{stem}.out.{ext}");
+
+        let command = parse(
+            &config,
+            &[
+                "exif",
+                "remove",
+                "dir",
+                "--output-dir",
+                "out",
+                "--name-template",
+                "{stem}.{ext}",
+            ],
+        );
+        let PngMeArgs::Exif(ExifCommand::Remove(args)) = command.unwrap() else {
+            panic!("expected exif remove");
+        };
+        assert_eq!(args.batch.name_template, "{stem}.{ext}");
+    }
+
+    #[test]
+    fn test_search_paths() {
+        let cwd = Path::new("/work");
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+        assert_eq!(
+            search_paths(
+                cwd,
+                env(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")])
+            ),
+            [
+                PathBuf::from("/work/pngme.toml"),
+                PathBuf::from("/xdg/pngme/pngme.toml")
+            ]
+        );
+        assert_eq!(
+            search_paths(cwd, env(&[("XDG_CONFIG_HOME", ""), ("HOME", "/home/me")]))[1],
+            PathBuf::from("/home/me/.config/pngme/pngme.toml")
+        );
+        assert_eq!(
+            search_paths(cwd, env(&[])),
+            [PathBuf::from("/work/pngme.toml")]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! # Crypto
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块负责对隐藏的信息进行加密和解密。
+//!
+//! 密钥由口令经 Argon2id 派生，数据使用 ChaCha20-Poly1305 加密。加密后的格式为：
+//!
+//! ```text
+//! | 版本 (1) | salt (16) | nonce (12) | 密文 + tag (n + 16) |
+//! ```
+//!
+//! `derive_chunk_type` 用同样的 Argon2id 从口令派生 chunk 类型，扫描 `ruSt` 等常见类型的人找不到信息，
+//! 知道口令的人可以重新派生出类型。`derive_lsb_key` 以同样的方式派生 `--method lsb --scatter` 打乱写入顺序的密钥，
+//! `derive_watermark_key` 派生决定鲁棒水印图案的密钥。
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, OsRng, RngCore};
+
+use crate::chunk_type::ChunkType;
+use crate::{PngmeError, Result};
+
+/// 当前的加密格式版本
+pub const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 1 + SALT_LEN + NONCE_LEN;
+/// 派生 chunk 类型时使用的固定 salt，同一个口令总是得到同一个类型
+const CHUNK_TYPE_SALT: &[u8] = b"pngme hidden chunk type";
+/// 派生 LSB 写入顺序的密钥时使用的固定 salt
+const LSB_KEY_SALT: &[u8] = b"pngme lsb permutation";
+/// 派生水印图案的密钥时使用的固定 salt
+const WATERMARK_KEY_SALT: &[u8] = b"pngme watermark pattern";
+
+/// 使用口令加密数据，salt 和 nonce 取自操作系统的随机数源
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    encrypt_with_rng(plaintext, passphrase, &mut OsRng)
+}
+
+/// 使用口令加密数据，salt 和 nonce 取自调用方提供的随机数生成器。
+/// 传入固定种子的生成器可以得到可复现的输出，仅应在测试或审计时这样做。
+pub fn encrypt_with_rng<R: RngCore + CryptoRng>(
+    plaintext: &[u8],
+    passphrase: &str,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| PngmeError::Crypto("encryption failed".to_string()))?;
+
+    let mut result = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    result.push(VERSION);
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend(ciphertext);
+    Ok(result)
+}
+
+/// 使用口令解密由 `encrypt` 生成的数据
+pub fn decrypt(payload: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if payload.len() < HEADER_LEN + 16 {
+        return Err(PngmeError::Crypto(
+            "encrypted payload is too short".to_string(),
+        ));
+    }
+    if payload[0] != VERSION {
+        return Err(PngmeError::Crypto(format!(
+            "unsupported encryption version {}",
+            payload[0]
+        )));
+    }
+
+    let salt = &payload[1..1 + SALT_LEN];
+    let nonce = &payload[1 + SALT_LEN..HEADER_LEN];
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), &payload[HEADER_LEN..])
+        .map_err(|_| PngmeError::Crypto("wrong passphrase or corrupted payload".to_string()))
+}
+
+/// 从口令派生一个辅助、私有、可安全复制的 chunk 类型（例如 `qkXe`）。
+/// 第三个字母大写（保留位为 0），其余小写，因此总是合法的类型，且不会与规范中的类型重名
+pub fn derive_chunk_type(passphrase: &str) -> Result<ChunkType> {
+    let mut hash = [0u8; 4];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), CHUNK_TYPE_SALT, &mut hash)
+        .map_err(|e| PngmeError::Crypto(e.to_string()))?;
+    let mut bytes = hash.map(|b| b'a' + b % 26);
+    bytes[2] = bytes[2].to_ascii_uppercase();
+    ChunkType::try_from(bytes)
+}
+
+/// 从口令派生 `--method lsb --scatter` 打乱写入顺序的 256 位密钥，同一个口令总是得到同一个密钥。
+/// 与加密密钥使用不同的 salt，知道写入顺序无助于解密
+pub fn derive_lsb_key(passphrase: &str) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), LSB_KEY_SALT, &mut key)
+        .map_err(|e| PngmeError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+/// 从口令派生决定 `watermark` 水印图案的 256 位密钥，同一个口令总是得到同一个密钥
+pub fn derive_watermark_key(passphrase: &str) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), WATERMARK_KEY_SALT, &mut key)
+        .map_err(|e| PngmeError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+/// 使用 Argon2id 从口令派生 256 位密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| PngmeError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let payload = encrypt(b"secret message", "hunter2").unwrap();
+        assert_eq!(payload[0], VERSION);
+        assert_ne!(&payload[HEADER_LEN..], b"secret message");
+        assert_eq!(decrypt(&payload, "hunter2").unwrap(), b"secret message");
+    }
+
+    #[test]
+    fn test_seeded_rng_is_deterministic() {
+        use rand_chacha::rand_core::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let first = encrypt_with_rng(b"msg", "pw", &mut ChaCha20Rng::seed_from_u64(7)).unwrap();
+        let second = encrypt_with_rng(b"msg", "pw", &mut ChaCha20Rng::seed_from_u64(7)).unwrap();
+        let other = encrypt_with_rng(b"msg", "pw", &mut ChaCha20Rng::seed_from_u64(8)).unwrap();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(decrypt(&first, "pw").unwrap(), b"msg");
+    }
+
+    #[test]
+    fn test_derive_chunk_type() {
+        let chunk_type = derive_chunk_type("hunter2").unwrap();
+        assert_eq!(derive_chunk_type("hunter2").unwrap(), chunk_type);
+        assert_ne!(derive_chunk_type("hunter3").unwrap(), chunk_type);
+        assert!(chunk_type.is_valid());
+        assert!(chunk_type.is_ancillary());
+        assert!(chunk_type.is_private());
+        assert!(chunk_type.is_safe_to_copy());
+        assert!(chunk_type.known().is_none());
+    }
+
+    #[test]
+    fn test_derive_lsb_key() {
+        let key = derive_lsb_key("hunter2").unwrap();
+        assert_eq!(derive_lsb_key("hunter2").unwrap(), key);
+        assert_ne!(derive_lsb_key("hunter3").unwrap(), key);
+    }
+
+    #[test]
+    fn test_derive_watermark_key() {
+        let key = derive_watermark_key("hunter2").unwrap();
+        assert_eq!(derive_watermark_key("hunter2").unwrap(), key);
+        assert_ne!(derive_lsb_key("hunter2").unwrap(), key);
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let payload = encrypt(b"secret message", "hunter2").unwrap();
+        assert!(matches!(
+            decrypt(&payload, "hunter3"),
+            Err(PngmeError::Crypto(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_payload() {
+        let mut payload = encrypt(b"secret message", "hunter2").unwrap();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        assert!(decrypt(&payload, "hunter2").is_err());
+        assert!(decrypt(&payload[..10], "hunter2").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! # Daemon
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块实现了 `pngme daemon`：在 Unix socket 上常驻，按行接收 JSON 命令，
+//! 每条命令对应一行 JSON 响应，省去频繁调用时的进程启动开销。
+//!
+//! 请求示例：
+//!
+//! ```text
+//! {"command":"encode","file":"a.png","chunk_type":"ruSt","message":"hi"}
+//! {"command":"decode","file":"a.png","chunk_type":"ruSt"}
+//! ```
+//!
+//! 响应为 `{"ok":true,"result":...}` 或 `{"ok":false,"error":"...","kind":"...","exit_code":N}`，
+//! 错误的字段与命令行 `--json` 的输出相同（见 `output::error_object`），无法解析的请求的 `kind` 为 `usage`。
+//!
+//! 请求都交给同一个 `Engine` 处理，解析缓存在请求之间保留；同时服务的连接数受 `Limits::max_connections` 限制。
+//!
+//! 收到 SIGINT/SIGTERM 后 daemon 不再接受新的连接和请求，每个连接的取消标记（`shutdown()` 的子标记）随之取消，
+//! 正在执行的请求在下一个检查点返回 `PngmeError::Cancelled`，写完响应后删除 socket 退出。
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::args::DaemonArgs;
+use crate::cancel::{self, CancelToken};
+use crate::engine::{self, Engine, Response};
+use crate::limits::Limits;
+use crate::output;
+use crate::vfs::{StdFs, Vfs};
+use crate::{PngmeError, Result};
+
+/// 请求行中除信息以外的部分（命令、路径等）允许的长度
+const REQUEST_OVERHEAD: u64 = 64 * 1024;
+
+/// daemon 接受的命令
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum Request {
+    Ping,
+    Capabilities,
+    Encode {
+        file: PathBuf,
+        chunk_type: String,
+        message: String,
+        output: Option<PathBuf>,
+    },
+    Decode {
+        file: PathBuf,
+        chunk_type: String,
+    },
+    Remove {
+        file: PathBuf,
+        chunk_type: String,
+    },
+    Print {
+        file: PathBuf,
+    },
+}
+
+/// 两次检查关闭标记之间等待新连接的时间
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// 正在执行的请求数，关闭时等它们完成
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// 正在服务的连接数，达到 `max_connections` 时暂停接受新连接
+static CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// 占用一个连接名额，释放时归还
+struct ConnectionSlot;
+
+impl ConnectionSlot {
+    fn acquire() -> Self {
+        CONNECTIONS.fetch_add(1, Ordering::SeqCst);
+        ConnectionSlot
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 启动 daemon，监听指定的 socket。收到 SIGINT/SIGTERM 后不再接受连接和请求，
+/// 等正在执行的请求完成后删除 socket 并返回 `PngmeError::Cancelled`
+pub fn run(args: DaemonArgs) -> Result<()> {
+    let listener = bind(&args.socket)?;
+    // 非阻塞地接受连接，才能定期检查关闭标记
+    listener.set_nonblocking(true)?;
+    eprintln!("pngme daemon listening on {}", args.socket.display());
+
+    let _graceful = cancel::graceful();
+    let shutdown = cancel::shutdown();
+    // 所有连接共用一个引擎，解析缓存在请求之间保留
+    let engine = Arc::new(Engine::builder().build());
+    while !shutdown.is_cancelled() {
+        // 连接数达到上限时新连接留在 socket 的等待队列中，直到有连接关闭
+        if CONNECTIONS.load(Ordering::SeqCst) >= engine.limits().max_connections {
+            thread::sleep(ACCEPT_POLL);
+            continue;
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let engine = Arc::clone(&engine);
+                let slot = ConnectionSlot::acquire();
+                // 关闭时取消所有连接上正在执行的操作
+                let cancel = shutdown.child();
+                thread::spawn(move || {
+                    let _slot = slot;
+                    let result = stream
+                        .set_nonblocking(false)
+                        .map_err(PngmeError::from)
+                        .and_then(|()| serve_connection(stream, &engine, &cancel));
+                    if let Err(e) = result {
+                        eprintln!("pngme daemon: connection error: {}", e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+            Err(e) => eprintln!("pngme daemon: accept failed: {}", e),
+        }
+    }
+
+    while IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+        thread::sleep(ACCEPT_POLL);
+    }
+    drop(listener);
+    std::fs::remove_file(&args.socket)?;
+    eprintln!("pngme daemon stopped");
+    Err(PngmeError::Cancelled)
+}
+
+/// 监听 `path`。已经存在的 socket 连不上（上一个 daemon 崩溃或被强制结束后留下的）时删除它再监听，
+/// 有 daemon 在监听或者路径不是 socket 时返回错误
+fn bind(path: &Path) -> Result<UnixListener> {
+    if path.symlink_metadata().is_ok() {
+        match UnixStream::connect(path) {
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused && is_socket(path) => {
+                std::fs::remove_file(path)?;
+            }
+            Ok(_) => {
+                let message = format!("Another daemon is listening on {}", path.display());
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, message).into());
+            }
+            Err(_) => {
+                let message = format!("Socket path {} already exists", path.display());
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, message).into());
+            }
+        }
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+fn is_socket(path: &Path) -> bool {
+    path.symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_socket())
+}
+
+/// 处理一个连接上的所有请求。请求行的长度受 `max_payload_size` 限制，超长时回复错误并断开连接；
+/// `cancel` 被取消后到达的请求返回 `PngmeError::Cancelled`
+fn serve_connection(stream: UnixStream, engine: &Engine, cancel: &CancelToken) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let max_line = engine
+        .limits()
+        .max_payload_size
+        .saturating_add(REQUEST_OVERHEAD);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(max_line.saturating_add(1))
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if line.len() as u64 > max_line {
+            let error = PngmeError::LimitExceeded(format!(
+                "request line is longer than {} bytes",
+                max_line
+            ));
+            writeln!(writer, "{}", output::error_object(&error))?;
+            return Err(error);
+        }
+        // 不是 UTF-8 的请求与其他无法解析的请求一样回复用法错误，连接继续可用
+        let line = match std::str::from_utf8(&line) {
+            Ok(line) => line,
+            Err(e) => {
+                let error = output::usage_error_object(&format!("Invalid request: {}", e));
+                writeln!(writer, "{}", error).and_then(|()| writer.flush())?;
+                continue;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        // 计入正在执行的请求后再检查关闭标记，关闭时不会漏等这个请求
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        let response = if cancel.is_cancelled() {
+            output::error_object(&PngmeError::Cancelled).to_string()
+        } else {
+            handle_line(line, engine, cancel)
+        };
+        let written = writeln!(writer, "{}", response).and_then(|()| writer.flush());
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        written?;
+    }
+}
+
+/// 处理一行 JSON 请求，返回一行 JSON 响应
+fn handle_line(line: &str, engine: &Engine, cancel: &CancelToken) -> String {
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(request) => match handle(request, engine, cancel) {
+            Ok(result) => json!({ "ok": true, "result": result }),
+            Err(e) => output::error_object(&e),
+        },
+        Err(e) => output::usage_error_object(&format!("Invalid request: {}", e)),
+    };
+    response.to_string()
+}
+
+/// 按安全限制读取文件，超过 `max_file_size` 时不继续读取
+fn read_file(file: &Path, limits: &Limits) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    StdFs
+        .open(file)?
+        .take(limits.max_file_size.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    limits.check_file_size(bytes.len() as u64)?;
+    Ok(bytes)
+}
+
+/// 执行一条命令。文件的解析和修改都交给 `engine`，daemon 只负责读写文件；
+/// `cancel` 被取消时正在执行的命令返回 `PngmeError::Cancelled`
+fn handle(request: Request, engine: &Engine, cancel: &CancelToken) -> Result<Value> {
+    let limits = engine.limits();
+    cancel.check()?;
+    match request {
+        Request::Ping => Ok(json!("pong")),
+        Request::Capabilities => Ok(json!(crate::capabilities())),
+        Request::Encode {
+            file,
+            chunk_type,
+            message,
+            output,
+        } => {
+            let response = engine.process_with_cancel(
+                engine::Request::Encode {
+                    png: read_file(&file, limits)?,
+                    chunk_type,
+                    message: message.into_bytes(),
+                    compress: false,
+                    passphrase: None,
+                },
+                cancel,
+            )?;
+            StdFs.write(output.as_ref().unwrap_or(&file), &png_bytes(response))?;
+            Ok(Value::Null)
+        }
+        Request::Decode { file, chunk_type } => {
+            let response = engine.process_with_cancel(
+                engine::Request::Decode {
+                    png: read_file(&file, limits)?,
+                    chunk_type,
+                    passphrase: None,
+                },
+                cancel,
+            )?;
+            let Response::Payload(payload) = response else {
+                unreachable!("decode always returns a payload");
+            };
+            Ok(json!(std::str::from_utf8(&payload.data)?))
+        }
+        Request::Remove { file, chunk_type } => {
+            let response = engine.process_with_cancel(
+                engine::Request::Remove {
+                    png: read_file(&file, limits)?,
+                    chunk_type: chunk_type.clone(),
+                },
+                cancel,
+            )?;
+            StdFs.write(&file, &png_bytes(response))?;
+            Ok(json!(chunk_type))
+        }
+        Request::Print { file } => {
+            let response = engine.process_with_cancel(
+                engine::Request::Scan {
+                    png: read_file(&file, limits)?,
+                },
+                cancel,
+            )?;
+            let Response::Chunks(chunks) = response else {
+                unreachable!("scan always returns chunks");
+            };
+            let chunks: Vec<Value> = chunks
+                .iter()
+                .map(|chunk| {
+                    json!({
+                        "type": chunk.chunk_type,
+                        "length": chunk.length,
+                        "crc": chunk.crc,
+                    })
+                })
+                .collect();
+            Ok(Value::Array(chunks))
+        }
+    }
+}
+
+fn png_bytes(response: Response) -> Vec<u8> {
+    match response {
+        Response::Png(bytes) => bytes,
+        _ => unreachable!("edits always return a PNG"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use std::fs;
+
+    fn temp_png(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("pngme-daemon-{}-{}", std::process::id(), name));
+        fs::write(&path, Png::from_chunks(Vec::new()).as_bytes()).unwrap();
+        path
+    }
+
+    fn parse(response: &str) -> Value {
+        serde_json::from_str(response).unwrap()
+    }
+
+    fn engine() -> Engine {
+        Engine::builder().build()
+    }
+
+    fn request(line: &str, engine: &Engine) -> Value {
+        parse(&handle_line(line, engine, &CancelToken::new()))
+    }
+
+    #[test]
+    fn test_bind_replaces_stale_socket() {
+        let path = temp_png("socket");
+        // 普通文件不会被删除
+        assert!(bind(&path).is_err());
+        fs::remove_file(&path).unwrap();
+
+        // 监听后直接关闭，留下没有 daemon 的 socket
+        drop(UnixListener::bind(&path).unwrap());
+        let listener = bind(&path).unwrap();
+        // 正在监听的 socket 不会被替换
+        assert!(bind(&path).is_err());
+        drop(listener);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ping() {
+        let response = request(r#"{"command":"ping"}"#, &engine());
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["result"], "pong");
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let response = request(r#"{"command":"capabilities"}"#, &engine());
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["result"]["daemon"], true);
+        assert_eq!(response["result"]["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_encode_then_decode() {
+        let path = temp_png("roundtrip.png");
+        let encode = json!({
+            "command": "encode",
+            "file": path,
+            "chunk_type": "ruSt",
+            "message": "hello",
+        });
+        assert_eq!(request(&encode.to_string(), &engine())["ok"], true);
+
+        let decode = json!({ "command": "decode", "file": path, "chunk_type": "ruSt" });
+        let response = request(&decode.to_string(), &engine());
+        assert_eq!(response["result"], "hello");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_print_and_remove() {
+        let path = temp_png("remove.png");
+        let engine = engine();
+        let encode = json!({
+            "command": "encode",
+            "file": path,
+            "chunk_type": "ruSt",
+            "message": "hello",
+        });
+        assert_eq!(request(&encode.to_string(), &engine)["ok"], true);
+
+        let print = json!({ "command": "print", "file": path }).to_string();
+        let response = request(&print, &engine);
+        assert_eq!(response["result"][0]["type"], "ruSt");
+
+        let remove = json!({ "command": "remove", "file": path, "chunk_type": "ruSt" });
+        let response = request(&remove.to_string(), &engine);
+        assert_eq!(response["result"], "ruSt");
+        let response = request(&print, &engine);
+        assert_eq!(response["result"], json!([]));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let response = request("not json", &engine());
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["kind"], "usage");
+        assert_eq!(response["exit_code"], 2);
+
+        let response = request(r#"{"command":"explode"}"#, &engine());
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["kind"], "usage");
+    }
+
+    #[test]
+    fn test_cancelled_connection() {
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let decode = json!({ "command": "decode", "file": "a.png", "chunk_type": "ruSt" });
+        let response = parse(&handle_line(&decode.to_string(), &engine(), &cancel));
+        assert_eq!(response["error"], PngmeError::Cancelled.to_string());
+        assert_eq!(response["kind"], "cancelled");
+        assert_eq!(response["exit_code"], PngmeError::Cancelled.exit_code());
+    }
+
+    #[test]
+    fn test_missing_chunk_is_an_error() {
+        let path = temp_png("missing.png");
+        let decode = json!({ "command": "decode", "file": path, "chunk_type": "ruSt" });
+        let response = request(&decode.to_string(), &engine());
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["kind"], "chunk-not-found");
+        assert_eq!(response["exit_code"], 3);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8_request() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let handle =
+            thread::spawn(move || serve_connection(server, &engine(), &CancelToken::new()));
+        client.write_all(b"{\"command\":\"\xff\"}\n").unwrap();
+        client.write_all(b"{\"command\":\"ping\"}\n").unwrap();
+
+        let mut reader = BufReader::new(&client);
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        assert_eq!(parse(&response)["kind"], "usage");
+        assert_eq!(parse(&response)["exit_code"], 2);
+        response.clear();
+        reader.read_line(&mut response).unwrap();
+        assert_eq!(parse(&response)["result"], "pong");
+
+        client.shutdown(std::net::Shutdown::Both).unwrap();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_request_line_limit() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let engine = Engine::builder()
+            .limits(Limits {
+                max_payload_size: 16,
+                ..Limits::default()
+            })
+            .build();
+        let handle = thread::spawn(move || serve_connection(server, &engine, &CancelToken::new()));
+        let line = format!("{}\n", "x".repeat(REQUEST_OVERHEAD as usize + 100));
+        client.write_all(line.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        BufReader::new(&client).read_line(&mut response).unwrap();
+        assert_eq!(parse(&response)["kind"], "limit-exceeded");
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(PngmeError::LimitExceeded(_))
+        ));
+    }
+}
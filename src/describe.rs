@@ -0,0 +1,335 @@
+//! # Describe
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块把规范中定义的 chunk（见 `KnownChunk`）解码为一行人类可读的说明，
+//! 供 `print` 和 `scan` 显示，例如：
+//!
+//! ```text
+//! IHDR  2x2 8-bit RGB
+//! gAMA  gamma 0.45455
+//! pHYs  300 DPI
+//! PLTE  16 colors
+//! tEXt  Title: Café
+//! ```
+//!
+//! tRNS 和 bKGD 的格式取决于 IHDR 中的颜色类型，需要传入文件的 IHDR。
+//! 数据不符合规范时说明是解码错误的描述，不会报错。
+
+use std::convert::TryFrom;
+
+use crate::apng::{AnimationControl, FrameControl};
+use crate::charset::latin1_decode;
+use crate::chunk::Chunk;
+use crate::chunk_type::{ChunkType, KnownChunk};
+use crate::ihdr::{ColorType, Ihdr};
+use crate::limits::Limits;
+use crate::metadata::{
+    Chromaticities, Gamma, IccProfile, PhysicalDimensions, RenderingIntent, TimeChunk,
+};
+use crate::preview;
+use crate::text;
+use crate::{PngmeError, Result};
+
+/// cLLI、mDCV 中亮度的单位（0.0001 cd/m²）
+const LUMINANCE_UNIT: f64 = 10_000.0;
+
+/// 解码 chunk 的主要字段；不是规范中的类型，或者没有值得显示的字段（IDAT、IEND）时返回 `None`
+pub fn describe(chunk_type: &ChunkType, data: &[u8], header: Option<&Ihdr>) -> Option<String> {
+    let known = chunk_type.known()?;
+    let description = match known {
+        KnownChunk::Idat | KnownChunk::Iend => return None,
+        KnownChunk::Text | KnownChunk::Ztxt | KnownChunk::Itxt => {
+            let chunk = Chunk::new(*chunk_type, data.to_vec()).ok()?;
+            return Some(
+                text::preview(&chunk, preview::DEFAULT_WIDTH)
+                    .unwrap_or_else(|| format!("Invalid {} chunk", known)),
+            );
+        }
+        _ => describe_fields(known, chunk_type, data, header),
+    };
+    Some(description.unwrap_or_else(|e| e.to_string()))
+}
+
+fn describe_fields(
+    known: KnownChunk,
+    chunk_type: &ChunkType,
+    data: &[u8],
+    header: Option<&Ihdr>,
+) -> Result<String> {
+    let chunk = || Chunk::new(*chunk_type, data.to_vec());
+    let color_type = header.map(|header| header.color_type);
+    Ok(match known {
+        KnownChunk::Ihdr => Ihdr::try_from(data)?.to_string(),
+        KnownChunk::Plte => {
+            if !data.len().is_multiple_of(3) {
+                return Err(invalid("PLTE length is not a multiple of 3".to_string()));
+            }
+            format!("{} colors", data.len() / 3)
+        }
+        KnownChunk::Trns => match (color_type, data) {
+            (Some(ColorType::Indexed), _) => format!("{} alpha values", data.len()),
+            (Some(ColorType::Grayscale), [a, b]) => {
+                format!("transparent gray {}", u16::from_be_bytes([*a, *b]))
+            }
+            (Some(ColorType::Rgb), _) if data.len() == 6 => {
+                format!("transparent color {}", rgb16(data))
+            }
+            _ => format!("{} bytes", data.len()),
+        },
+        KnownChunk::Gama => format!("gamma {:.5}", Gamma::try_from(&chunk()?)?.value()),
+        KnownChunk::Chrm => {
+            let [white, red, green, blue] = Chromaticities::try_from(&chunk()?)?
+                .points()
+                .map(|(x, y)| format!("({:.4}, {:.4})", x, y));
+            format!(
+                "white {}, red {}, green {}, blue {}",
+                white, red, green, blue
+            )
+        }
+        KnownChunk::Srgb => format!("{} rendering intent", RenderingIntent::try_from(&chunk()?)?),
+        KnownChunk::Iccp => {
+            let profile = IccProfile::from_chunk_with_limits(&chunk()?, &Limits::default())?;
+            format!(
+                "\"{}\" ({} bytes)",
+                preview::escape(&profile.name),
+                profile.profile.len()
+            )
+        }
+        KnownChunk::Sbit => {
+            let bits: Vec<String> = data.iter().map(u8::to_string).collect();
+            format!("{} significant bits", bits.join("/"))
+        }
+        KnownChunk::Cicp => match data {
+            [primaries, transfer, matrix, full_range] => format!(
+                "primaries {}, transfer {}, matrix {}, {} range",
+                primaries,
+                transfer,
+                matrix,
+                if *full_range == 1 { "full" } else { "limited" }
+            ),
+            _ => return Err(invalid("cICP must be 4 bytes".to_string())),
+        },
+        KnownChunk::Mdcv => {
+            expect_len(known, data, 24)?;
+            format!(
+                "luminance {} to {} cd/m²",
+                f64::from(u32_at(data, 20)) / LUMINANCE_UNIT,
+                f64::from(u32_at(data, 16)) / LUMINANCE_UNIT
+            )
+        }
+        KnownChunk::Clli => {
+            expect_len(known, data, 8)?;
+            format!(
+                "max CLL {} cd/m², max FALL {} cd/m²",
+                f64::from(u32_at(data, 0)) / LUMINANCE_UNIT,
+                f64::from(u32_at(data, 4)) / LUMINANCE_UNIT
+            )
+        }
+        KnownChunk::Bkgd => match (color_type, data) {
+            (Some(ColorType::Indexed), [index]) => format!("palette index {}", index),
+            (_, [a, b]) => format!("gray {}", u16::from_be_bytes([*a, *b])),
+            _ if data.len() == 6 => rgb16(data),
+            _ => return Err(invalid(format!("unexpected bKGD length {}", data.len()))),
+        },
+        KnownChunk::Hist => format!("{} entries", data.len() / 2),
+        KnownChunk::Phys => {
+            let phys = PhysicalDimensions::try_from(&chunk()?)?;
+            match phys.dpi() {
+                Some(dpi) if phys.pixels_per_unit_x == phys.pixels_per_unit_y => {
+                    format!("{:.0} DPI", dpi)
+                }
+                Some(_) => format!(
+                    "{}x{} pixels per meter",
+                    phys.pixels_per_unit_x, phys.pixels_per_unit_y
+                ),
+                None => format!(
+                    "aspect ratio {}:{}",
+                    phys.pixels_per_unit_x, phys.pixels_per_unit_y
+                ),
+            }
+        }
+        KnownChunk::Splt => {
+            let name_len = data
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or_else(|| invalid("sPLT name is not null-terminated".to_string()))?;
+            let (depth, entry_len) = match data.get(name_len + 1) {
+                Some(8) => (8, 6),
+                Some(16) => (16, 10),
+                _ => return Err(invalid("sPLT sample depth must be 8 or 16".to_string())),
+            };
+            format!(
+                "\"{}\", {} entries, {}-bit",
+                preview::escape(&latin1_decode(&data[..name_len])),
+                (data.len() - name_len - 2) / entry_len,
+                depth
+            )
+        }
+        KnownChunk::Exif => {
+            let order = match data.get(..2) {
+                Some(b"II") => "little-endian",
+                Some(b"MM") => "big-endian",
+                _ => "unknown byte order",
+            };
+            format!("{} bytes, {}", data.len(), order)
+        }
+        KnownChunk::Time => TimeChunk::try_from(&chunk()?)?.to_string(),
+        KnownChunk::Actl => {
+            let control = AnimationControl::try_from(&chunk()?)?;
+            match control.num_plays {
+                0 => format!("{} frames, loops forever", control.num_frames),
+                plays => format!("{} frames, plays {} times", control.num_frames, plays),
+            }
+        }
+        KnownChunk::Fctl => {
+            let control = FrameControl::try_from(&chunk()?)?;
+            format!(
+                "frame #{}: {}x{} at ({}, {}), delay {} ms",
+                control.sequence_number,
+                control.width,
+                control.height,
+                control.x_offset,
+                control.y_offset,
+                control.delay().as_millis()
+            )
+        }
+        KnownChunk::Fdat => {
+            if data.len() < 4 {
+                return Err(invalid("fdAT is missing its sequence number".to_string()));
+            }
+            format!("sequence #{}, {} bytes", u32_at(data, 0), data.len() - 4)
+        }
+        KnownChunk::Idat
+        | KnownChunk::Iend
+        | KnownChunk::Text
+        | KnownChunk::Ztxt
+        | KnownChunk::Itxt => unreachable!("handled by describe"),
+    })
+}
+
+/// 长度必须正好是 `len`
+fn expect_len(known: KnownChunk, data: &[u8], len: usize) -> Result<()> {
+    if data.len() != len {
+        return Err(invalid(format!(
+            "{} must be {} bytes, found {}",
+            known,
+            len,
+            data.len()
+        )));
+    }
+    Ok(())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// 6 字节的 16 位 RGB 值
+fn rgb16(data: &[u8]) -> String {
+    format!(
+        "({}, {}, {})",
+        u16::from_be_bytes([data[0], data[1]]),
+        u16::from_be_bytes([data[2], data[3]]),
+        u16::from_be_bytes([data[4], data[5]])
+    )
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidMetadata(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use crate::png::Png;
+
+    fn describe_chunk(chunk_type: &str, data: &[u8], color_type: ColorType) -> Option<String> {
+        let header = Ihdr::new(16, 16, 8, color_type).unwrap();
+        describe(
+            &ChunkType::from_str(chunk_type).unwrap(),
+            data,
+            Some(&header),
+        )
+    }
+
+    #[test]
+    fn test_describe_png_file() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let header = png.header().unwrap();
+        let descriptions: Vec<Option<String>> = png
+            .chunks()
+            .iter()
+            .map(|chunk| describe(chunk.chunk_type(), chunk.data(), Some(&header)))
+            .collect();
+        assert_eq!(
+            descriptions,
+            [Some("2x2 8-bit RGB".to_string()), None, None]
+        );
+    }
+
+    #[test]
+    fn test_describe_known_chunks() {
+        let rgb = ColorType::Rgb;
+        let cases: [(&str, &[u8], ColorType, &str); 10] = [
+            ("gAMA", &45455u32.to_be_bytes(), rgb, "gamma 0.45455"),
+            ("PLTE", &[0; 48], ColorType::Indexed, "16 colors"),
+            ("tRNS", &[255; 4], ColorType::Indexed, "4 alpha values"),
+            (
+                "tRNS",
+                &[0, 1, 0, 2, 0, 3],
+                rgb,
+                "transparent color (1, 2, 3)",
+            ),
+            ("bKGD", &[7], ColorType::Indexed, "palette index 7"),
+            ("sRGB", &[0], rgb, "perceptual rendering intent"),
+            ("sBIT", &[5, 6, 5], rgb, "5/6/5 significant bits"),
+            ("eXIf", b"MM\0*", rgb, "4 bytes, big-endian"),
+            (
+                "tIME",
+                &[0x07, 0xea, 10, 16, 8, 30, 5],
+                rgb,
+                "2026-10-16T08:30:05Z",
+            ),
+            ("fdAT", &[0, 0, 0, 3, 1, 2], rgb, "sequence #3, 2 bytes"),
+        ];
+        for (chunk_type, data, color_type, expected) in cases {
+            assert_eq!(
+                describe_chunk(chunk_type, data, color_type).as_deref(),
+                Some(expected),
+                "{}",
+                chunk_type
+            );
+        }
+
+        let phys = PhysicalDimensions::from_dpi(300.0).unwrap().to_chunk();
+        assert_eq!(
+            describe_chunk("pHYs", phys.data(), rgb).as_deref(),
+            Some("300 DPI")
+        );
+        let text = crate::text::text_chunk("Title", "Café").unwrap();
+        assert_eq!(
+            describe_chunk("tEXt", text.data(), rgb).as_deref(),
+            Some("Title: Café")
+        );
+        assert_eq!(describe_chunk("ruSt", b"hidden", rgb), None);
+    }
+
+    #[test]
+    fn test_describe_invalid_data() {
+        let description = describe_chunk("gAMA", &[1, 2], ColorType::Rgb).unwrap();
+        assert!(description.contains("gAMA must be 4 bytes, found 2"));
+        assert!(describe_chunk("PLTE", &[0; 4], ColorType::Indexed)
+            .unwrap()
+            .starts_with("Invalid"));
+        assert!(describe_chunk("tEXt", b"no separator", ColorType::Rgb)
+            .unwrap()
+            .starts_with("Invalid"));
+    }
+}
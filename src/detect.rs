@@ -0,0 +1,594 @@
+//! # Detect
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块用启发式规则检查 PNG 中可能隐藏的数据，生成风险报告：
+//!
+//! - 不认识的私有辅助 chunk（pngme 等工具写入信息的常见位置）；
+//! - 熵很高的文本 chunk（base64、密文、密钥等随机数据）；
+//! - IEND 之后的 chunk 和 IEND 之后多余的字节（包括 `--method trailer` 写入的信息），大多数查看器会忽略它们；
+//! - 像素最低位的卡方检验异常（Westfeld 和 Pfitzmann 的 pairs of values 方法）。
+//!
+//! 这些规则都可能误报：例如相机或编辑器写入的私有 chunk、噪声很大的图片。报告只说明
+//! 哪里值得检查，不能证明文件中一定隐藏了数据。
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::ihdr::ColorType;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::preserve;
+use crate::preview;
+use crate::text::{CompressedTextChunk, InternationalTextChunk, TextChunk};
+use crate::trailer;
+use crate::Result;
+
+/// 私有 chunk 的数据不超过该长度时只算低风险（应用写入的标记之类）
+const SMALL_CHUNK: u32 = 16;
+/// 计算熵的文本的最小长度，太短的文本熵没有意义
+const MIN_TEXT_LEN: usize = 32;
+/// 文本的熵（比特/字节）达到该值时视为随机数据。英文文本通常在 4.5 以下，base64 接近 6
+const ENTROPY_THRESHOLD: f64 = 5.0;
+/// 进行卡方检验的最少样本数
+const MIN_SAMPLES: usize = 1024;
+/// 值对的期望频数低于该值时不参与检验
+const MIN_EXPECTED: f64 = 5.0;
+/// 卡方检验的 p 值达到该值时报告异常
+const LSB_THRESHOLD: f64 = 0.95;
+
+/// 单条发现的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// 整个文件的风险等级，为所有发现中最高的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+/// 发现的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// 不认识的私有辅助 chunk
+    PrivateChunk,
+    /// 熵很高的文本 chunk
+    HighEntropyText,
+    /// IEND 之后的 chunk
+    ChunkAfterIend,
+    /// IEND 之后不能解析为 chunk 的字节
+    TrailingData,
+    /// 像素最低位的分布像是被替换过
+    LsbAnomaly,
+}
+
+/// 一条可疑的发现
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    /// 相关 chunk 或数据在文件中的字节偏移，像素分析没有偏移
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_type: Option<String>,
+    pub message: String,
+}
+
+/// 像素最低位的卡方检验结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LsbAnalysis {
+    /// 参与检验的样本数（不含 alpha）
+    pub samples: usize,
+    pub chi_square: f64,
+    /// 自由度，即参与检验的值对数减一
+    pub degrees_of_freedom: usize,
+    /// 最低位被随机数据替换的概率估计，接近 1 时可疑
+    pub p_value: f64,
+}
+
+/// 风险报告
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectReport {
+    pub risk: Risk,
+    pub findings: Vec<Finding>,
+    /// 调色板图片、位深不是 8 或样本太少时不做像素分析
+    pub lsb: Option<LsbAnalysis>,
+}
+
+impl Finding {
+    fn new(kind: FindingKind, severity: Severity, message: String) -> Self {
+        Finding {
+            kind,
+            severity,
+            offset: None,
+            chunk_type: None,
+            message,
+        }
+    }
+
+    fn at(mut self, offset: u64, chunk_type: Option<String>) -> Self {
+        self.offset = Some(offset);
+        self.chunk_type = chunk_type;
+        self
+    }
+}
+
+impl From<Severity> for Risk {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Low => Risk::Low,
+            Severity::Medium => Risk::Medium,
+            Severity::High => Risk::High,
+        }
+    }
+}
+
+/// 检查 PNG 文件的原始字节。IEND 之前的数据无法解析时返回错误，
+/// IEND 之后的内容不要求是合法的 chunk
+pub fn detect(bytes: &[u8], limits: &Limits) -> Result<DetectReport> {
+    let mut findings = Vec::new();
+    let mut chunks = Vec::new();
+    let mut offset = Png::STANDARD_HEADER.len() as u64;
+    let mut after_iend = false;
+    for chunk in Png::chunk_refs(bytes)? {
+        let chunk = match chunk {
+            // IEND 之后的内容按字节报告，之前的错误说明文件本身已经损坏
+            Err(_) if after_iend => break,
+            Err(e) => return Err(e),
+            Ok(chunk) if after_iend && !chunk.has_valid_crc() => break,
+            Ok(chunk) => chunk,
+        };
+        let chunk_type = chunk.chunk_type().to_string();
+        if after_iend {
+            findings.push(
+                Finding::new(
+                    FindingKind::ChunkAfterIend,
+                    Severity::High,
+                    format!("{} chunk ({} bytes) after IEND", chunk_type, chunk.length()),
+                )
+                .at(offset, Some(chunk_type)),
+            );
+        } else {
+            let owned = chunk.to_chunk();
+            findings.extend(check_chunk(&owned, limits).map(|f| f.at(offset, Some(chunk_type))));
+            after_iend = chunk.chunk_type().bytes() == *b"IEND";
+            chunks.push(owned);
+        }
+        offset += chunk.encoded_len() as u64;
+    }
+    let trailing = (bytes.len() as u64).saturating_sub(offset);
+    if after_iend && trailing > 0 {
+        let rest = &bytes[offset as usize..];
+        let message = match trailer::parse(rest) {
+            Ok(records) => format!(
+                "{} bytes after IEND hold {} pngme trailer record(s)",
+                trailing,
+                records.len()
+            ),
+            Err(_) if trailer::is_trailer(rest) => {
+                format!("{} bytes after IEND hold a damaged pngme trailer", trailing)
+            }
+            Err(_) => format!("{} trailing bytes after IEND", trailing),
+        };
+        findings.push(
+            Finding::new(FindingKind::TrailingData, Severity::High, message).at(offset, None),
+        );
+    }
+
+    let lsb = lsb_analysis(&Png::from_chunks(chunks), limits);
+    if let Some(lsb) = lsb.as_ref().filter(|lsb| lsb.p_value >= LSB_THRESHOLD) {
+        findings.push(Finding::new(
+            FindingKind::LsbAnomaly,
+            Severity::Medium,
+            format!(
+                "least significant bits look randomized (chi-square p = {:.3} over {} samples)",
+                lsb.p_value, lsb.samples
+            ),
+        ));
+    }
+
+    let risk = findings
+        .iter()
+        .map(|finding| Risk::from(finding.severity))
+        .max()
+        .unwrap_or(Risk::None);
+    Ok(DetectReport {
+        risk,
+        findings,
+        lsb,
+    })
+}
+
+/// 检查 IEND 之前的单个 chunk
+fn check_chunk(chunk: &Chunk, limits: &Limits) -> Option<Finding> {
+    let chunk_type = chunk.chunk_type();
+    if chunk_type.is_ancillary() && !chunk_type.is_public() && preserve::is_unknown(chunk) {
+        let severity = match chunk.length() <= SMALL_CHUNK {
+            true => Severity::Low,
+            false => Severity::Medium,
+        };
+        return Some(Finding::new(
+            FindingKind::PrivateChunk,
+            severity,
+            format!(
+                "unknown private ancillary chunk {} ({} bytes)",
+                chunk_type,
+                chunk.length()
+            ),
+        ));
+    }
+
+    let (keyword, text) = decode_text(chunk, limits)?;
+    if text.len() < MIN_TEXT_LEN {
+        return None;
+    }
+    let entropy = entropy(text.as_bytes());
+    (entropy >= ENTROPY_THRESHOLD).then(|| {
+        Finding::new(
+            FindingKind::HighEntropyText,
+            Severity::Medium,
+            format!(
+                "{:?} text looks random ({:.2} bits per byte over {} bytes)",
+                preview::escape(&keyword),
+                entropy,
+                text.len()
+            ),
+        )
+    })
+}
+
+/// 解码 tEXt、zTXt、iTXt 的关键字和文本，不是文本 chunk 或无法解码时返回 `None`
+fn decode_text(chunk: &Chunk, limits: &Limits) -> Option<(String, String)> {
+    match &chunk.chunk_type().bytes() {
+        b"tEXt" => TextChunk::try_from(chunk)
+            .ok()
+            .map(|text| (text.keyword, text.text)),
+        b"zTXt" => CompressedTextChunk::from_chunk_with_limits(chunk, limits)
+            .ok()
+            .map(|text| (text.keyword, text.text)),
+        b"iTXt" => InternationalTextChunk::from_chunk_lossy(chunk, limits)
+            .ok()
+            .map(|text| (text.keyword, text.text)),
+        _ => None,
+    }
+}
+
+/// 字节的香农熵，单位为比特/字节，范围 0 到 8
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// 对 8 位灰度或真彩色图片的样本（不含 alpha）做卡方检验，不适用或像素无法解码时返回 `None`
+fn lsb_analysis(png: &Png, limits: &Limits) -> Option<LsbAnalysis> {
+    let header = png.header().ok()?;
+    if header.bit_depth != 8 || header.color_type == ColorType::Indexed {
+        return None;
+    }
+    let channels = header.color_type.channels() as usize;
+    let color_channels = match header.color_type {
+        ColorType::GrayscaleAlpha | ColorType::Rgba => channels - 1,
+        _ => channels,
+    };
+    let pixels = png.pixels(limits).ok()?;
+    let mut histogram = [0usize; 256];
+    let mut samples = 0;
+    for pixel in pixels.chunks_exact(channels) {
+        for &sample in &pixel[..color_channels] {
+            histogram[sample as usize] += 1;
+        }
+        samples += color_channels;
+    }
+    if samples < MIN_SAMPLES {
+        return None;
+    }
+    chi_square(&histogram).map(|(chi_square, degrees_of_freedom)| LsbAnalysis {
+        samples,
+        chi_square,
+        degrees_of_freedom,
+        p_value: chi_square_survival(chi_square, degrees_of_freedom as f64),
+    })
+}
+
+/// 把每对只有最低位不同的值（2k, 2k+1）的频数与两者的平均值比较。
+/// 最低位被随机数据替换后，每对值的频数趋于相等，卡方统计量很小。
+/// 返回统计量和自由度，参与检验的值对少于两对时返回 `None`
+fn chi_square(histogram: &[usize; 256]) -> Option<(f64, usize)> {
+    let mut chi_square = 0.0;
+    let mut pairs = 0;
+    for pair in histogram.chunks_exact(2) {
+        let expected = (pair[0] + pair[1]) as f64 / 2.0;
+        if expected < MIN_EXPECTED {
+            continue;
+        }
+        let observed = pair[0] as f64;
+        chi_square += (observed - expected).powi(2) / expected;
+        pairs += 1;
+    }
+    (pairs >= 2).then_some((chi_square, pairs - 1))
+}
+
+/// 卡方分布的生存函数 P(X >= x)，即正则化上不完全 gamma 函数 Q(k/2, x/2)
+fn chi_square_survival(x: f64, degrees_of_freedom: f64) -> f64 {
+    let a = degrees_of_freedom / 2.0;
+    let x = x / 2.0;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // 级数展开求 P(a, x)
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut n = a;
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        (1.0 - prefix * sum).clamp(0.0, 1.0)
+    } else {
+        // 连分式求 Q(a, x)（修正的 Lentz 方法）
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (prefix * h).clamp(0.0, 1.0)
+    }
+}
+
+/// ln Γ(x)，x >= 0.5，Lanczos 近似（g = 7）
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Risk::None => write!(f, "none"),
+            Risk::Low => Severity::Low.fmt(f),
+            Risk::Medium => Severity::Medium.fmt(f),
+            Risk::High => Severity::High.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for DetectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Risk: {}", self.risk)?;
+        for finding in &self.findings {
+            write!(f, "\n  [{}] ", finding.severity)?;
+            if let Some(offset) = finding.offset {
+                write!(f, "offset {}: ", offset)?;
+            }
+            write!(f, "{}", finding.message)?;
+        }
+        if let Some(lsb) = &self.lsb {
+            write!(
+                f,
+                "\nLSB chi-square: {:.2} (df {}), p = {:.3} over {} samples",
+                lsb.chi_square, lsb.degrees_of_freedom, lsb.p_value, lsb.samples
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::ihdr::Ihdr;
+    use crate::png::tests::{testing_chunk, testing_image, PNG_FILE};
+
+    /// 64x64 的 8 位灰度图片，`sample` 给出每个像素的值
+    fn grayscale(sample: impl Fn(usize) -> u8) -> Png {
+        let header = Ihdr::new(64, 64, 8, ColorType::Grayscale).unwrap();
+        let pixels: Vec<u8> = (0..64 * 64).map(sample).collect();
+        testing_image(header, &pixels)
+    }
+
+    #[test]
+    fn test_clean() {
+        let report = detect(&PNG_FILE, &Limits::default()).unwrap();
+        assert_eq!(report.risk, Risk::None);
+        assert!(report.findings.is_empty());
+        // 2x2 的图片样本太少，不做像素分析
+        assert!(report.lsb.is_none());
+        assert_eq!(report.to_string(), "Risk: none");
+    }
+
+    #[test]
+    fn test_chunks_and_trailing_data() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(testing_chunk(
+            "ruSt",
+            b"a hidden message longer than 16 bytes",
+        ));
+        png.append_chunk(testing_chunk("flAg", b"1"));
+        png.set_text("Comment", "a perfectly ordinary comment about the picture")
+            .unwrap();
+        let mut bytes = png.as_bytes();
+        bytes.extend(testing_chunk("seCr", b"after").as_bytes());
+        let trailing_offset = bytes.len() as u64;
+        bytes.extend(b"appended data");
+
+        let report = detect(&bytes, &Limits::default()).unwrap();
+        assert_eq!(report.risk, Risk::High);
+        let kinds: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.kind, f.severity))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (FindingKind::PrivateChunk, Severity::Medium),
+                (FindingKind::PrivateChunk, Severity::Low),
+                (FindingKind::ChunkAfterIend, Severity::High),
+                (FindingKind::TrailingData, Severity::High),
+            ]
+        );
+        let ru_st = &report.findings[0];
+        let offset = ru_st.offset.unwrap() as usize;
+        assert_eq!(&bytes[offset + 4..offset + 8], b"ruSt");
+        assert_eq!(report.findings[2].chunk_type.as_deref(), Some("seCr"));
+        assert_eq!(report.findings[3].offset, Some(trailing_offset));
+        assert!(report.findings[3].message.starts_with("13 trailing bytes"));
+
+        // IEND 之前的损坏仍然是错误
+        assert!(detect(&bytes[..40], &Limits::default()).is_err());
+    }
+
+    #[test]
+    fn test_trailer() {
+        let mut records = Vec::new();
+        trailer::append(
+            &mut records,
+            ChunkType::from_str("ruSt").unwrap(),
+            b"after IEND",
+            1024,
+        )
+        .unwrap();
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend(trailer::to_bytes(&records));
+        let report = detect(&bytes, &Limits::default()).unwrap();
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].kind, FindingKind::TrailingData);
+        assert_eq!(report.findings[0].offset, Some(PNG_FILE.len() as u64));
+        assert!(report.findings[0]
+            .message
+            .ends_with("hold 1 pngme trailer record(s)"));
+    }
+
+    #[test]
+    fn test_high_entropy_text() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_text(
+            "Comment",
+            "q8ZK3vR0xW+7TjHbN2mYc1LpA9sUe4/FdGiOoX5kQVw6BrMzDh0tCa7yS=",
+        )
+        .unwrap();
+        let report = detect(&png.as_bytes(), &Limits::default()).unwrap();
+        assert_eq!(report.risk, Risk::Medium);
+        assert_eq!(report.findings[0].kind, FindingKind::HighEntropyText);
+        assert_eq!(report.findings[0].chunk_type.as_deref(), Some("tEXt"));
+
+        assert!(entropy(b"") == 0.0);
+        assert!((entropy(b"abab") - 1.0).abs() < 1e-12);
+        assert!((entropy(&(0..=255).collect::<Vec<u8>>()) - 8.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_lsb() {
+        // 只有偶数值：每对值完全不平衡
+        let clean = grayscale(|i| ((i * 7) % 128 * 2) as u8);
+        let report = detect(&clean.as_bytes(), &Limits::default()).unwrap();
+        let lsb = report.lsb.unwrap();
+        assert_eq!(lsb.samples, 64 * 64);
+        assert!(lsb.p_value < 0.01);
+        assert_eq!(report.risk, Risk::None);
+
+        // 同一图片的最低位被替换为伪随机数据
+        let mut state = 0x2545_f491_u32;
+        let bits: Vec<u8> = (0..64 * 64)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state & 1) as u8
+            })
+            .collect();
+        let stego = grayscale(|i| ((i * 7) % 128 * 2) as u8 | bits[i]);
+        let report = detect(&stego.as_bytes(), &Limits::default()).unwrap();
+        assert!(report.lsb.as_ref().unwrap().p_value > LSB_THRESHOLD);
+        assert_eq!(report.findings[0].kind, FindingKind::LsbAnomaly);
+        assert_eq!(report.risk, Risk::Medium);
+    }
+
+    #[test]
+    fn test_chi_square_survival() {
+        // 与常见的卡方分布表比较
+        assert!((chi_square_survival(3.841, 1.0) - 0.05).abs() < 1e-3);
+        assert!((chi_square_survival(18.307, 10.0) - 0.05).abs() < 1e-3);
+        assert!((chi_square_survival(2.558, 10.0) - 0.99).abs() < 1e-3);
+        assert!((chi_square_survival(0.0, 5.0) - 1.0).abs() < 1e-12);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,235 @@
+//! # Diff
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在 chunk 层面比较两个 PNG，列出新增、删除和修改的 chunk（按类型、长度和 CRC），
+//! 用于确认某个工具改动了什么，或者文件中是否被注入了隐藏数据。
+//!
+//! 类型和 CRC 都相同的 chunk 视为未改动，先按最长公共子序列对齐；
+//! 两个对齐点之间类型相同的 chunk 依次配对为修改，其余为新增或删除。
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::png::Png;
+
+/// 参与比较的 chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkSummary {
+    /// chunk 在所属文件中的序号，从 0 开始
+    pub index: usize,
+    pub chunk_type: String,
+    pub length: u32,
+    pub crc: u32,
+}
+
+/// 一处改动
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum ChunkChange {
+    Added(ChunkSummary),
+    Removed(ChunkSummary),
+    Modified {
+        before: ChunkSummary,
+        after: ChunkSummary,
+    },
+}
+
+/// 两个 PNG 之间的所有改动，按在文件中的位置排序
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PngDiff {
+    pub changes: Vec<ChunkChange>,
+}
+
+impl PngDiff {
+    /// 两个文件的 chunk 是否完全相同
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl ChunkSummary {
+    fn new(index: usize, chunk: &Chunk) -> Self {
+        ChunkSummary {
+            index,
+            chunk_type: chunk.chunk_type().to_string(),
+            length: chunk.length(),
+            crc: chunk.crc(),
+        }
+    }
+}
+
+/// 比较 `before` 和 `after` 的 chunk
+pub fn diff(before: &Png, after: &Png) -> PngDiff {
+    let old = before.chunks();
+    let new = after.chunks();
+    let same = |a: &Chunk, b: &Chunk| a.chunk_type() == b.chunk_type() && a.crc() == b.crc();
+
+    // 去掉相同的开头和结尾，只对中间部分求最长公共子序列
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j]：old_mid[i..] 和 new_mid[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if same(&old_mid[i], &new_mid[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && same(&old_mid[i], &new_mid[j]) {
+            pair_up(&mut changes, &mut removed, &mut added);
+            i += 1;
+            j += 1;
+        } else if j == new_mid.len() || (i < old_mid.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            removed.push(ChunkSummary::new(prefix + i, &old_mid[i]));
+            i += 1;
+        } else {
+            added.push(ChunkSummary::new(prefix + j, &new_mid[j]));
+            j += 1;
+        }
+    }
+    pair_up(&mut changes, &mut removed, &mut added);
+    PngDiff { changes }
+}
+
+/// 把两个对齐点之间删除和新增的 chunk 中类型相同的依次配对为修改
+fn pair_up(
+    changes: &mut Vec<ChunkChange>,
+    removed: &mut Vec<ChunkSummary>,
+    added: &mut Vec<ChunkSummary>,
+) {
+    let mut added: Vec<Option<ChunkSummary>> = added.drain(..).map(Some).collect();
+    for before in removed.drain(..) {
+        let partner = added.iter_mut().find(|after| {
+            after
+                .as_ref()
+                .is_some_and(|after| after.chunk_type == before.chunk_type)
+        });
+        match partner.and_then(Option::take) {
+            Some(after) => changes.push(ChunkChange::Modified { before, after }),
+            None => changes.push(ChunkChange::Removed(before)),
+        }
+    }
+    changes.extend(added.into_iter().flatten().map(ChunkChange::Added));
+}
+
+impl fmt::Display for ChunkSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} #{} ({} bytes, CRC {:08X})",
+            self.chunk_type, self.index, self.length, self.crc
+        )
+    }
+}
+
+impl fmt::Display for PngDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No differences");
+        }
+        for (n, change) in self.changes.iter().enumerate() {
+            if n > 0 {
+                writeln!(f)?;
+            }
+            match change {
+                ChunkChange::Added(chunk) => write!(f, "+ {}", chunk)?,
+                ChunkChange::Removed(chunk) => write!(f, "- {}", chunk)?,
+                ChunkChange::Modified { before, after } => write!(
+                    f,
+                    "~ {} #{} -> #{} ({} -> {} bytes, CRC {:08X} -> {:08X})",
+                    before.chunk_type,
+                    before.index,
+                    after.index,
+                    before.length,
+                    after.length,
+                    before.crc,
+                    after.crc
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::{testing_chunk, PNG_FILE};
+    use std::convert::TryFrom;
+
+    fn summary(change: &ChunkChange) -> (char, &str) {
+        match change {
+            ChunkChange::Added(chunk) => ('+', chunk.chunk_type.as_str()),
+            ChunkChange::Removed(chunk) => ('-', chunk.chunk_type.as_str()),
+            ChunkChange::Modified { before, .. } => ('~', before.chunk_type.as_str()),
+        }
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let diff = diff(&png, &png.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "No differences");
+    }
+
+    #[test]
+    fn test_added_removed_and_modified() {
+        let before = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut after = before.clone();
+        after.append_chunk(testing_chunk("tEXt", b"Title\0old"));
+        let middle = after.clone();
+        after.replace_chunk(testing_chunk("tEXt", b"Title\0new title"));
+        after.append_chunk(testing_chunk("ruSt", b"hidden"));
+
+        let changes = diff(&before, &after).changes;
+        let summaries: Vec<(char, &str)> = changes.iter().map(summary).collect();
+        assert_eq!(summaries, [('+', "tEXt"), ('+', "ruSt")]);
+
+        let changes = diff(&middle, &after).changes;
+        let summaries: Vec<(char, &str)> = changes.iter().map(summary).collect();
+        assert_eq!(summaries, [('~', "tEXt"), ('+', "ruSt")]);
+        let ChunkChange::Modified {
+            before: old,
+            after: new,
+        } = &changes[0]
+        else {
+            unreachable!()
+        };
+        assert_eq!((old.length, new.length), (9, 15));
+
+        let changes = diff(&middle, &before).changes;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(summary(&changes[0]), ('-', "tEXt"));
+    }
+
+    #[test]
+    fn test_display() {
+        let before = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut after = before.clone();
+        after.append_chunk(testing_chunk("ruSt", b"hidden"));
+        let text = diff(&before, &after).to_string();
+        assert!(text.starts_with("+ ruSt #2 (6 bytes, CRC "));
+        assert_eq!(text.lines().count(), 1);
+    }
+}
@@ -0,0 +1,316 @@
+//! # Dry run
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了命令行的 `--dry-run`：`DryRunFs` 包装另一个 `Vfs`，写入和删除只记在内存中，
+//! 之后的读取看到的是修改后的内容，所以命令可以完整地执行一遍而不改动任何文件。
+//! 执行完毕后 `changes` 按路径汇总计划的修改：文件大小的变化，新旧内容都是 PNG 时
+//! 再列出新增、删除和修改的 chunk 及其 CRC（见 `diff` 模块）。
+//!
+//! 同一文件的多次写入合并为一条；先创建后又删除的文件（例如批处理的日志）不会出现在结果中。
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::diff::{self, PngDiff};
+use crate::png::Png;
+use crate::progress::Progress;
+use crate::vfs::{FileAttributes, Preserve, Vfs};
+
+/// 包装另一个文件系统，只读取而不写入，写入和删除记在内存中
+pub struct DryRunFs<'a> {
+    inner: &'a dyn Vfs,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// 第一次修改前的内容，`None` 表示文件不存在
+    original: HashMap<PathBuf, Option<Vec<u8>>>,
+    /// 当前的内容，`None` 表示已被删除
+    current: HashMap<PathBuf, Option<Vec<u8>>>,
+    /// 第一次修改的顺序
+    order: Vec<PathBuf>,
+}
+
+/// 一个文件计划中的修改
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum PlannedChange {
+    Write(PlannedWrite),
+    Remove { path: PathBuf, size: u64 },
+}
+
+/// 计划写入的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedWrite {
+    pub path: PathBuf,
+    /// 原文件的大小，文件原本不存在时为 `None`
+    pub before: Option<u64>,
+    pub after: u64,
+    /// 新内容是 PNG 时与原内容（不存在时视为没有 chunk）的差异
+    pub diff: Option<PngDiff>,
+}
+
+impl<'a> DryRunFs<'a> {
+    pub fn new(inner: &'a dyn Vfs) -> Self {
+        DryRunFs {
+            inner,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// 按第一次修改的顺序列出计划的修改，内容最终没有变化的文件不列出
+    pub fn changes(&self) -> Vec<PlannedChange> {
+        let state = self.state.lock().unwrap();
+        state
+            .order
+            .iter()
+            .filter_map(|path| {
+                let original = state.original[path].as_deref();
+                let current = state.current[path].as_deref();
+                match (original, current) {
+                    (None, None) => None,
+                    (Some(before), None) => Some(PlannedChange::Remove {
+                        path: path.clone(),
+                        size: before.len() as u64,
+                    }),
+                    (Some(before), Some(after)) if before == after => None,
+                    (before, Some(after)) => Some(PlannedChange::Write(PlannedWrite {
+                        path: path.clone(),
+                        before: before.map(|before| before.len() as u64),
+                        after: after.len() as u64,
+                        diff: png_diff(before, after),
+                    })),
+                }
+            })
+            .collect()
+    }
+
+    /// 记录对 `path` 的修改，第一次修改时保存原来的内容
+    fn record(&self, path: &Path, data: Option<Vec<u8>>) {
+        let mut state = self.state.lock().unwrap();
+        if !state.current.contains_key(path) {
+            let original = self.inner.exists(path).then(|| self.inner.read(path).ok());
+            state
+                .original
+                .insert(path.to_path_buf(), original.flatten());
+            state.order.push(path.to_path_buf());
+        }
+        state.current.insert(path.to_path_buf(), data);
+    }
+
+    /// 已修改过的文件的当前内容，没有修改过时返回 `None`
+    fn current(&self, path: &Path) -> Option<Option<Vec<u8>>> {
+        self.state.lock().unwrap().current.get(path).cloned()
+    }
+}
+
+/// 新内容是 PNG 时比较 chunk；原内容不是 PNG 时视为没有 chunk
+fn png_diff(before: Option<&[u8]>, after: &[u8]) -> Option<PngDiff> {
+    let after = Png::try_from(after).ok()?;
+    let before = before
+        .and_then(|before| Png::try_from(before).ok())
+        .unwrap_or_else(|| Png::from_chunks(Vec::new()));
+    Some(diff::diff(&before, &after))
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+impl Vfs for DryRunFs<'_> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        match self.current(path) {
+            Some(Some(data)) => Ok(Box::new(Cursor::new(data))),
+            Some(None) => Err(not_found(path)),
+            None => self.inner.open(path),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        tracing::debug!(path = %path.display(), bytes = data.len(), "dry run: skipped write");
+        self.record(path, Some(data.to_vec()));
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        match self.current(path) {
+            Some(data) => data.is_some(),
+            None => self.inner.exists(path),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path)
+    }
+
+    /// 底层目录的内容，去掉已删除的文件，加上新建的文件
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries = self.inner.list(dir)?;
+        let state = self.state.lock().unwrap();
+        entries.retain(|path| !matches!(state.current.get(path), Some(None)));
+        for path in &state.order {
+            if path.parent() == Some(dir)
+                && state.current[path].is_some()
+                && !entries.contains(path)
+            {
+                entries.push(path.clone());
+            }
+        }
+        Ok(entries)
+    }
+
+    fn create_dir_all(&self, _dir: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if !self.exists(path) {
+            return Err(not_found(path));
+        }
+        tracing::debug!(path = %path.display(), "dry run: skipped remove");
+        self.record(path, None);
+        Ok(())
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.inner.is_symlink(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        self.inner.canonicalize(path)
+    }
+
+    fn hard_link_id(&self, path: &Path) -> Option<(u64, u64)> {
+        self.inner.hard_link_id(path)
+    }
+
+    fn write_in_place(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.write(path, data)
+    }
+
+    fn attributes(&self, path: &Path) -> io::Result<Option<FileAttributes>> {
+        self.inner.attributes(path)
+    }
+
+    fn set_attributes(
+        &self,
+        _path: &Path,
+        _attributes: &FileAttributes,
+        _preserve: Preserve,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn progress(&self) -> Arc<dyn Progress> {
+        self.inner.progress()
+    }
+}
+
+impl fmt::Display for PlannedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedChange::Write(write) => write.fmt(f),
+            PlannedChange::Remove { path, size } => {
+                write!(f, "Would remove {} ({} bytes)", path.display(), size)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PlannedWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.before {
+            Some(before) => write!(
+                f,
+                "Would write {} ({} -> {} bytes, {:+})",
+                self.path.display(),
+                before,
+                self.after,
+                self.after as i64 - before as i64
+            )?,
+            None => write!(
+                f,
+                "Would create {} ({} bytes)",
+                self.path.display(),
+                self.after
+            )?,
+        }
+        if let Some(diff) = &self.diff {
+            for line in diff.to_string().lines() {
+                write!(f, "\n  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_writes_stay_in_memory() {
+        let inner = MemoryFs::new();
+        inner.insert("a.png", PNG_FILE.to_vec());
+        let fs = DryRunFs::new(&inner);
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()).unwrap());
+        fs.write(Path::new("a.png"), &png.as_bytes()).unwrap();
+        fs.write(Path::new("notes.txt"), b"text").unwrap();
+        fs.write(Path::new("journal"), b"pending").unwrap();
+        fs.remove(Path::new("journal")).unwrap();
+
+        assert_eq!(inner.get(Path::new("a.png")).unwrap(), PNG_FILE);
+        assert!(!inner.exists(Path::new("notes.txt")));
+        assert_eq!(fs.read(Path::new("a.png")).unwrap(), png.as_bytes());
+        assert!(!fs.exists(Path::new("journal")));
+
+        let changes = fs.changes();
+        assert_eq!(changes.len(), 2);
+        let PlannedChange::Write(write) = &changes[0] else {
+            unreachable!()
+        };
+        assert_eq!(write.after - write.before.unwrap(), 14);
+        assert_eq!(write.diff.as_ref().unwrap().changes.len(), 1);
+        let text = changes[0].to_string();
+        assert!(text.starts_with("Would write a.png ("));
+        assert!(text.contains("\n  + ruSt #2 (2 bytes, CRC "));
+        assert_eq!(changes[1].to_string(), "Would create notes.txt (4 bytes)");
+    }
+
+    #[test]
+    fn test_remove_and_unchanged() {
+        let inner = MemoryFs::new();
+        inner.insert("a.png", PNG_FILE.to_vec());
+        inner.insert("b.png", PNG_FILE.to_vec());
+        let fs = DryRunFs::new(&inner);
+
+        fs.write(Path::new("a.png"), &PNG_FILE).unwrap();
+        fs.remove(Path::new("b.png")).unwrap();
+        assert!(fs.remove(Path::new("b.png")).is_err());
+        assert!(inner.exists(Path::new("b.png")));
+        assert_eq!(
+            fs.changes(),
+            [PlannedChange::Remove {
+                path: PathBuf::from("b.png"),
+                size: PNG_FILE.len() as u64
+            }]
+        );
+    }
+}
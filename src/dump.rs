@@ -0,0 +1,199 @@
+//! # Dump
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现 chunk 的导出和重组，供取证分析使用：`extract-all` 把每个 chunk 的数据写到
+//! `basename.NNN.TYPE.bin`，并在 `basename.manifest.json` 中记录顺序、类型、长度和存储的 CRC；
+//! `assemble` 按清单把这些文件重新拼成 PNG。
+//!
+//! 导出时不校验 CRC，重组时默认写回清单中存储的 CRC，所以损坏的文件也能逐字节还原；
+//! 修改过数据文件后用 `--recompute-crc` 重新计算。
+
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 导出清单
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 导出时的源文件
+    pub source: String,
+    /// 按文件中的顺序排列的 chunk
+    pub chunks: Vec<ManifestEntry>,
+}
+
+/// 清单中的一个 chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// chunk 在文件中的序号
+    pub index: usize,
+    /// chunk 类型
+    #[serde(rename = "type")]
+    pub chunk_type: String,
+    /// 数据文件，相对于清单所在的目录
+    pub file: String,
+    /// 数据长度
+    pub length: u32,
+    /// 文件中存储的 CRC，8 位十六进制；省略时重组时重新计算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc: Option<String>,
+    /// 存储的 CRC 是否正确
+    #[serde(default)]
+    pub crc_valid: bool,
+}
+
+/// 第 `index` 个 chunk 的数据文件名
+pub fn chunk_file_name(basename: &str, index: usize, chunk_type: &ChunkType) -> String {
+    format!("{}.{:03}.{}.bin", basename, index, chunk_type)
+}
+
+/// 清单的文件名
+pub fn manifest_file_name(basename: &str) -> String {
+    format!("{}.manifest.json", basename)
+}
+
+impl Manifest {
+    /// 为 `png` 中的 chunk 生成清单，数据文件以 `basename` 开头
+    pub fn new(source: &str, basename: &str, png: &Png) -> Self {
+        let chunks = png
+            .chunks()
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| ManifestEntry {
+                index,
+                chunk_type: chunk.chunk_type().to_string(),
+                file: chunk_file_name(basename, index, chunk.chunk_type()),
+                length: chunk.length(),
+                crc: Some(format!("{:08X}", chunk.crc())),
+                crc_valid: chunk.has_valid_crc(),
+            })
+            .collect();
+        Manifest {
+            source: source.to_string(),
+            chunks,
+        }
+    }
+
+    /// 按清单重组 PNG。`read` 根据清单中的相对路径读取数据文件；
+    /// `recompute_crc` 为 `true` 或清单中没有 CRC 时重新计算，否则写回存储的 CRC
+    pub fn assemble<F>(&self, recompute_crc: bool, mut read: F) -> Result<Vec<u8>>
+    where
+        F: FnMut(&Path) -> Result<Vec<u8>>,
+    {
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        for entry in &self.chunks {
+            let path = Path::new(&entry.file);
+            if !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(PngmeError::InvalidPayload(format!(
+                    "chunk {} file {} must be a relative path inside the manifest directory",
+                    entry.index, entry.file
+                )));
+            }
+            let chunk_type: ChunkType = entry.chunk_type.parse()?;
+            let data = read(path)?;
+            let chunk = match entry.crc.as_deref().filter(|_| !recompute_crc) {
+                Some(crc) => {
+                    let crc = u32::from_str_radix(crc, 16).map_err(|_| {
+                        PngmeError::InvalidPayload(format!(
+                            "chunk {} has an invalid CRC {:?}",
+                            entry.index, crc
+                        ))
+                    })?;
+                    let length = u32::try_from(data.len())
+                        .map_err(|_| PngmeError::ChunkTooLarge(data.len()))?;
+                    let mut raw = Vec::with_capacity(data.len() + 12);
+                    raw.extend_from_slice(&length.to_be_bytes());
+                    raw.extend_from_slice(&chunk_type.bytes());
+                    raw.extend_from_slice(&data);
+                    raw.extend_from_slice(&crc.to_be_bytes());
+                    Chunk::from_bytes_unchecked(&raw)?
+                }
+                None => Chunk::new(chunk_type, data)?,
+            };
+            bytes.extend(chunk.as_bytes());
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn dump(png: &Png) -> (Manifest, HashMap<PathBuf, Vec<u8>>) {
+        let manifest = Manifest::new("image.png", "image", png);
+        let files = manifest
+            .chunks
+            .iter()
+            .zip(png.chunks())
+            .map(|(entry, chunk)| (PathBuf::from(&entry.file), chunk.data().to_vec()))
+            .collect();
+        (manifest, files)
+    }
+
+    #[test]
+    fn test_manifest_names() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let manifest = Manifest::new("image.png", "image", &png);
+        let files: Vec<&str> = manifest.chunks.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(
+            files,
+            [
+                "image.000.IHDR.bin",
+                "image.001.IDAT.bin",
+                "image.002.IEND.bin"
+            ]
+        );
+        assert!(manifest.chunks.iter().all(|entry| entry.crc_valid));
+    }
+
+    #[test]
+    fn test_assemble_round_trip() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (manifest, files) = dump(&png);
+        let json = serde_json::to_string(&manifest).unwrap();
+        let manifest: Manifest = serde_json::from_str(&json).unwrap();
+        let bytes = manifest
+            .assemble(false, |path| Ok(files[path].clone()))
+            .unwrap();
+        assert_eq!(bytes, PNG_FILE);
+    }
+
+    #[test]
+    fn test_assemble_keeps_or_recomputes_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        // 破坏 IHDR 的 CRC
+        bytes[29] ^= 0xFF;
+        let png = Png::from_bytes_unchecked(&bytes).unwrap();
+        let (manifest, files) = dump(&png);
+        assert!(!manifest.chunks[0].crc_valid);
+
+        let kept = manifest
+            .assemble(false, |path| Ok(files[path].clone()))
+            .unwrap();
+        assert_eq!(kept, bytes);
+        let fixed = manifest
+            .assemble(true, |path| Ok(files[path].clone()))
+            .unwrap();
+        assert_eq!(fixed, PNG_FILE);
+    }
+
+    #[test]
+    fn test_assemble_rejects_escaping_paths() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (mut manifest, _) = dump(&png);
+        manifest.chunks[0].file = "../secret.bin".to_string();
+        assert!(manifest.assemble(false, |_| Ok(Vec::new())).is_err());
+    }
+}
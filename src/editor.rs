@@ -0,0 +1,249 @@
+//! # Editor
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了事务式的 chunk 编辑器 `PngEditor`：先记录一系列操作（在指定位置插入、按条件删除、
+//! 替换数据、移动位置），`commit` 时在 chunk 列表的副本上依次执行，确认结果的顺序符合规范后
+//! 一次性替换原来的 chunk。任何一步失败或结果违反顺序约束时 `Png` 保持原样，
+//! 不会像连续调用 `append_chunk`/`remove_chunk` 那样停在一半。
+//!
+//! ```text
+//! png.edit()
+//!     .remove_type("tEXt")
+//!     .append(chunk)
+//!     .move_to("pHYs", ChunkPosition::AfterIhdr)
+//!     .commit()?;
+//! ```
+//!
+//! 只拒绝编辑新引入的顺序问题，文件原本就有的问题（例如宽松解析读入的损坏文件）不影响提交。
+
+use crate::chunk::Chunk;
+use crate::ordering::{self, ChunkPosition, OrderingIssue};
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 记录对 `Png` 的一系列修改，`commit` 时一次性应用
+pub struct PngEditor<'a> {
+    png: &'a mut Png,
+    operations: Vec<Operation<'a>>,
+}
+
+enum Operation<'a> {
+    /// 插入 chunk，没有给出位置时按规范为类型选择（见 `ChunkPosition::for_type`）
+    Insert(Chunk, Option<ChunkPosition>),
+    Remove(Box<dyn Fn(&Chunk) -> bool + 'a>),
+    ReplaceData {
+        chunk_type: String,
+        nth: usize,
+        data: Vec<u8>,
+    },
+    Move {
+        chunk_type: String,
+        position: ChunkPosition,
+    },
+}
+
+impl<'a> PngEditor<'a> {
+    pub fn new(png: &'a mut Png) -> Self {
+        PngEditor {
+            png,
+            operations: Vec::new(),
+        }
+    }
+
+    /// 在指定位置插入一个 chunk，位置按执行到这一步时的 chunk 计算
+    pub fn insert(mut self, chunk: Chunk, position: ChunkPosition) -> Self {
+        self.operations
+            .push(Operation::Insert(chunk, Some(position)));
+        self
+    }
+
+    /// 插入一个 chunk，按规范为它的类型选择位置（与 `Png::append_chunk` 相同）
+    pub fn append(mut self, chunk: Chunk) -> Self {
+        self.operations.push(Operation::Insert(chunk, None));
+        self
+    }
+
+    /// 删除所有满足条件的 chunk
+    pub fn remove_where(mut self, predicate: impl Fn(&Chunk) -> bool + 'a) -> Self {
+        self.operations.push(Operation::Remove(Box::new(predicate)));
+        self
+    }
+
+    /// 删除所有指定类型的 chunk，没有时什么也不做
+    pub fn remove_type(self, chunk_type: &str) -> Self {
+        let chunk_type = chunk_type.to_string();
+        self.remove_where(move |chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    /// 替换第 `nth` 个（从 0 开始）该类型 chunk 的数据并重新计算 CRC，位置不变；找不到时提交失败
+    pub fn replace_data(mut self, chunk_type: &str, nth: usize, data: impl Into<Vec<u8>>) -> Self {
+        self.operations.push(Operation::ReplaceData {
+            chunk_type: chunk_type.to_string(),
+            nth,
+            data: data.into(),
+        });
+        self
+    }
+
+    /// 把所有该类型的 chunk 保持相对顺序移动到指定位置；文件中没有该类型时提交失败
+    pub fn move_to(mut self, chunk_type: &str, position: ChunkPosition) -> Self {
+        self.operations.push(Operation::Move {
+            chunk_type: chunk_type.to_string(),
+            position,
+        });
+        self
+    }
+
+    /// 依次执行记录的操作并检查 chunk 顺序，全部成功后替换 `Png` 的 chunk，
+    /// 返回被删除的 chunk。失败时 `Png` 不变
+    pub fn commit(self) -> Result<Vec<Chunk>> {
+        let mut chunks = self.png.chunks().to_vec();
+        let mut removed = Vec::new();
+        for operation in self.operations {
+            match operation {
+                Operation::Insert(chunk, position) => {
+                    let position = position.unwrap_or_else(|| {
+                        ChunkPosition::for_type(
+                            &chunk.chunk_type().to_string(),
+                            is_animated(&chunks),
+                        )
+                    });
+                    chunks.insert(position.index(&chunks), chunk);
+                }
+                Operation::Remove(predicate) => {
+                    let (matched, kept): (Vec<Chunk>, Vec<Chunk>) =
+                        chunks.into_iter().partition(|chunk| predicate(chunk));
+                    chunks = kept;
+                    removed.extend(matched);
+                }
+                Operation::ReplaceData {
+                    chunk_type,
+                    nth,
+                    data,
+                } => {
+                    let index = chunks
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, chunk)| chunk.chunk_type().to_string() == chunk_type)
+                        .nth(nth)
+                        .map(|(index, _)| index)
+                        .ok_or_else(|| {
+                            PngmeError::ChunkNotFound(format!("{} #{}", chunk_type, nth))
+                        })?;
+                    chunks[index] = Chunk::new(*chunks[index].chunk_type(), data)?;
+                }
+                Operation::Move {
+                    chunk_type,
+                    position,
+                } => {
+                    let (moved, kept): (Vec<Chunk>, Vec<Chunk>) = chunks
+                        .into_iter()
+                        .partition(|chunk| chunk.chunk_type().to_string() == chunk_type);
+                    if moved.is_empty() {
+                        return Err(PngmeError::ChunkNotFound(chunk_type));
+                    }
+                    chunks = kept;
+                    let index = position.index(&chunks);
+                    chunks.splice(index..index, moved);
+                }
+            }
+        }
+
+        let existing = ordering::validate(self.png.chunks());
+        let introduced: Vec<OrderingIssue> = ordering::validate(&chunks)
+            .into_iter()
+            .filter(|issue| {
+                !existing
+                    .iter()
+                    .any(|old| old.chunk_type == issue.chunk_type && old.message == issue.message)
+            })
+            .collect();
+        if !introduced.is_empty() {
+            let issues: Vec<String> = introduced.iter().map(ToString::to_string).collect();
+            return Err(PngmeError::InvalidOrdering(issues.join("; ")));
+        }
+        *self.png = Png::from_chunks(chunks);
+        Ok(removed)
+    }
+}
+
+fn is_animated(chunks: &[Chunk]) -> bool {
+    chunks
+        .iter()
+        .any(|chunk| &chunk.chunk_type().bytes() == b"acTL")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::{testing_chunk, PNG_FILE};
+    use std::convert::TryFrom;
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_commit_applies_in_order() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(testing_chunk("teXt", b"old"));
+        let before = types(&png);
+
+        let removed = png
+            .edit()
+            .append(testing_chunk("ruSt", b"first"))
+            .append(testing_chunk("ruSt", b"second"))
+            .insert(testing_chunk("pHYs", &[0; 9]), ChunkPosition::BeforeIdat)
+            .replace_data("ruSt", 1, b"replaced".to_vec())
+            .remove_type("teXt")
+            .move_to("ruSt", ChunkPosition::AfterIhdr)
+            .commit()
+            .unwrap();
+
+        assert_eq!(removed, [testing_chunk("teXt", b"old")]);
+        let after = types(&png);
+        assert_eq!(after[..3], ["IHDR", "ruSt", "ruSt"]);
+        assert_eq!(after.len(), before.len() + 2);
+        let rust: Vec<&[u8]> = png
+            .chunks_by_type("ruSt")
+            .iter()
+            .map(|chunk| chunk.data())
+            .collect();
+        assert_eq!(rust, [&b"first"[..], b"replaced"]);
+        assert!(png.chunk_by_type("pHYs").is_some());
+        assert!(png.validate_ordering().is_empty());
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_png_unchanged() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let original = png.as_bytes();
+
+        let result = png
+            .edit()
+            .append(testing_chunk("ruSt", b"hello"))
+            .replace_data("miSs", 0, b"data".to_vec())
+            .commit();
+        assert!(matches!(result, Err(PngmeError::ChunkNotFound(_))));
+        assert_eq!(png.as_bytes(), original);
+
+        let result = png
+            .edit()
+            .insert(
+                testing_chunk("gAMA", &[0, 0, 0xb1, 0x8f]),
+                ChunkPosition::BeforeIend,
+            )
+            .commit();
+        assert!(matches!(result, Err(PngmeError::InvalidOrdering(_))));
+        let result = png
+            .edit()
+            .move_to("IEND", ChunkPosition::AfterIhdr)
+            .commit();
+        assert!(matches!(result, Err(PngmeError::InvalidOrdering(_))));
+        assert_eq!(png.as_bytes(), original);
+    }
+}
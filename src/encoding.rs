@@ -0,0 +1,121 @@
+//! # Encoding
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `encode --input-encoding` 和 `decode --output-encoding` 使用的文本编码，
+//! 让二进制信息可以安全地经过 shell 参数、管道和脚本：
+//!
+//! - `utf8`：原样传递，输出时数据必须是合法的 UTF-8；
+//! - `base64`：标准字母表，带填充；
+//! - `hex`：每字节两个十六进制数字，解码时不区分大小写，输出小写。
+//!
+//! 解码 base64 和 hex 时忽略空白，所以换行折叠的输出和文件末尾的换行都可以直接使用。
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::{PngmeError, Result};
+
+/// 信息在命令行上的文本编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Encoding {
+    /// 原样传递的文本
+    #[default]
+    Utf8,
+    /// 标准 base64，带填充
+    Base64,
+    /// 十六进制
+    Hex,
+}
+
+impl Encoding {
+    /// 把编码后的输入还原为原始数据；`utf8` 原样返回
+    pub fn decode(self, input: &[u8]) -> Result<Vec<u8>> {
+        let compact = || -> Vec<u8> {
+            input
+                .iter()
+                .copied()
+                .filter(|byte| !byte.is_ascii_whitespace())
+                .collect()
+        };
+        match self {
+            Encoding::Utf8 => Ok(input.to_vec()),
+            Encoding::Base64 => STANDARD
+                .decode(compact())
+                .map_err(|e| PngmeError::InvalidEncoding(format!("base64: {}", e))),
+            Encoding::Hex => decode_hex(&compact()),
+        }
+    }
+
+    /// 把数据编码为文本；`utf8` 要求数据是合法的 UTF-8
+    pub fn encode(self, data: &[u8]) -> Result<String> {
+        match self {
+            Encoding::Utf8 => Ok(std::str::from_utf8(data)?.to_string()),
+            Encoding::Base64 => Ok(STANDARD.encode(data)),
+            Encoding::Hex => Ok(data.iter().map(|byte| format!("{:02x}", byte)).collect()),
+        }
+    }
+}
+
+fn decode_hex(digits: &[u8]) -> Result<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) {
+        return Err(PngmeError::InvalidEncoding(format!(
+            "hex: odd number of digits ({})",
+            digits.len()
+        )));
+    }
+    let digit = |index: usize| {
+        (digits[index] as char).to_digit(16).ok_or_else(|| {
+            PngmeError::InvalidEncoding(format!(
+                "hex: invalid digit {:?} at offset {}",
+                digits[index] as char, index
+            ))
+        })
+    };
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| Ok((digit(i)? << 4 | digit(i + 1)?) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data = [0u8, 1, 0x7f, 0x80, 0xff, b'a'];
+        for encoding in [Encoding::Base64, Encoding::Hex] {
+            let text = encoding.encode(&data).unwrap();
+            assert_eq!(encoding.decode(text.as_bytes()).unwrap(), data);
+        }
+        assert_eq!(Encoding::Hex.encode(&data).unwrap(), "00017f80ff61");
+        assert_eq!(Encoding::Base64.encode(b"pngme").unwrap(), "cG5nbWU=");
+        assert!(Encoding::Utf8.encode(&data).is_err());
+        assert_eq!(Encoding::Utf8.decode(&data).unwrap(), data);
+    }
+
+    #[test]
+    fn test_decode_ignores_whitespace() {
+        assert_eq!(Encoding::Base64.decode(b"cG5n\nbWU=\n").unwrap(), b"pngme");
+        assert_eq!(
+            Encoding::Hex.decode(b"DE ad\tbe ef\n").unwrap(),
+            [0xde, 0xad, 0xbe, 0xef]
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(
+            Encoding::Base64.decode(b"not base64!"),
+            Err(PngmeError::InvalidEncoding(_))
+        ));
+        let error = Encoding::Hex.decode(b"abc").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid encoded input: hex: odd number of digits (3)"
+        );
+        assert!(Encoding::Hex.decode(b"zz").is_err());
+    }
+}
@@ -0,0 +1,734 @@
+//! # Engine
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块提供了嵌入服务时使用的 `Engine`：它持有工作线程数、解析缓存和密钥环，
+//! 所有操作都通过 `Engine::process(Request) -> Result<Response>` 完成，输入输出都是内存中的字节，
+//! 不涉及文件系统和全局状态。`process_all` 用结构化并发（`thread::scope`）并行处理一批请求，
+//! 返回时所有工作线程都已结束。`EngineBuilder::progress` 设置的回调会收到每个请求的字节数、
+//! 耗时的步骤和 `process_all` 中完成的请求，图形界面不需要接触文件系统就能显示进度。
+//! `EngineBuilder::cancel` 设置的 `CancelToken` 被取消后，正在处理的请求在下一个检查点返回
+//! `PngmeError::Cancelled`，`process_all` 中还没开始的请求也直接返回这个错误；
+//! `process_with_cancel` 可以为单个请求指定另外的标记（例如 daemon 的每个连接）。
+//!
+//! ```text
+//! let engine = Engine::builder().jobs(4).signing_key("release", key).build();
+//! let response = engine.process(Request::Sign { png, key: "release".into() })?;
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use crate::cancel::CancelToken;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::crypto;
+use crate::limits::Limits;
+use crate::payload::{self, message_data, remove_message, Payload};
+use crate::png::Png;
+use crate::progress::{NoProgress, Phase, Progress};
+use crate::roundtrip;
+use crate::scan::{self, ChunkInfo};
+use crate::{PngmeError, Result};
+
+/// 解析缓存的键：文件长度和 SHA-256 摘要，缓存中不保留文件字节
+type CacheKey = (usize, [u8; 32]);
+
+/// 解析缓存中的一项：键、按文件大小计的占用和解析结果
+type CacheEntry = (CacheKey, usize, Arc<Png>);
+
+/// 解析缓存默认最多占用的字节数（按缓存文件的大小累计）
+pub const DEFAULT_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+/// 默认只缓存不超过这个大小的文件，更大的文件每次都重新解析
+pub const DEFAULT_CACHE_MAX_ENTRY: usize = 8 * 1024 * 1024;
+
+/// `Engine` 能处理的请求，`png` 都是完整的 PNG 文件字节
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// 写入信息，`passphrase` 不为空时加密
+    Encode {
+        png: Vec<u8>,
+        chunk_type: String,
+        message: Vec<u8>,
+        compress: bool,
+        passphrase: Option<String>,
+    },
+    /// 读取信息
+    Decode {
+        png: Vec<u8>,
+        chunk_type: String,
+        passphrase: Option<String>,
+    },
+    /// 删除一条信息（包括它的所有分片）
+    Remove { png: Vec<u8>, chunk_type: String },
+    /// 列出所有 chunk
+    Scan { png: Vec<u8> },
+    /// 删除辅助 chunk，`keep` 中的类型除外
+    Strip { png: Vec<u8>, keep: Vec<String> },
+    /// 修复错误的 CRC
+    Repair { png: Vec<u8> },
+    /// 用密钥环中名为 `key` 的私钥签名
+    Sign { png: Vec<u8>, key: String },
+    /// 用密钥环中名为 `key` 的公钥校验签名
+    Verify { png: Vec<u8>, key: String },
+    /// 写入一条文本元数据
+    SetText {
+        png: Vec<u8>,
+        keyword: String,
+        text: String,
+    },
+}
+
+/// 请求的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// 修改后的 PNG 文件
+    Png(Vec<u8>),
+    /// 解码出的信息
+    Payload(Payload),
+    /// 扫描结果
+    Chunks(Vec<ChunkInfo>),
+    /// 签名有效
+    Verified,
+}
+
+/// 按名称保存的签名私钥和校验公钥
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    signing: BTreeMap<String, SigningKey>,
+    verifying: BTreeMap<String, VerifyingKey>,
+}
+
+impl Keyring {
+    pub fn insert_signing_key(&mut self, name: impl Into<String>, key: SigningKey) {
+        self.signing.insert(name.into(), key);
+    }
+
+    pub fn insert_verifying_key(&mut self, name: impl Into<String>, key: VerifyingKey) {
+        self.verifying.insert(name.into(), key);
+    }
+
+    pub fn signing_key(&self, name: &str) -> Result<&SigningKey> {
+        self.signing.get(name).ok_or_else(|| missing_key(name))
+    }
+
+    /// 查找公钥；只有同名私钥时使用它对应的公钥
+    pub fn verifying_key(&self, name: &str) -> Result<VerifyingKey> {
+        self.verifying
+            .get(name)
+            .copied()
+            .or_else(|| self.signing.get(name).map(SigningKey::verifying_key))
+            .ok_or_else(|| missing_key(name))
+    }
+}
+
+/// 构造 `Engine`
+#[derive(Debug, Clone)]
+pub struct EngineBuilder {
+    jobs: usize,
+    cache_size: usize,
+    cache_max_entry: usize,
+    keyring: Keyring,
+    limits: Limits,
+    verify_roundtrip: bool,
+    progress: Arc<dyn Progress>,
+    cancel: CancelToken,
+}
+
+impl EngineBuilder {
+    /// `process_all` 使用的工作线程数，默认为 1
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// 解析缓存最多占用的字节数，超出时淘汰最久未使用的文件，0 表示不缓存
+    pub fn cache_size(mut self, bytes: usize) -> Self {
+        self.cache_size = bytes;
+        self
+    }
+
+    /// 超过这个大小的文件不进入解析缓存
+    pub fn cache_max_entry(mut self, bytes: usize) -> Self {
+        self.cache_max_entry = bytes;
+        self
+    }
+
+    pub fn keyring(mut self, keyring: Keyring) -> Self {
+        self.keyring = keyring;
+        self
+    }
+
+    /// 解析文件和信息时使用的安全限制，默认为 `Limits::default()`
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn signing_key(mut self, name: impl Into<String>, key: SigningKey) -> Self {
+        self.keyring.insert_signing_key(name, key);
+        self
+    }
+
+    pub fn verifying_key(mut self, name: impl Into<String>, key: VerifyingKey) -> Self {
+        self.keyring.insert_verifying_key(name, key);
+        self
+    }
+
+    /// 返回修改后的文件前先做往返自检（见 `roundtrip::verify`）
+    pub fn verify_roundtrip(mut self, verify: bool) -> Self {
+        self.verify_roundtrip = verify;
+        self
+    }
+
+    /// 进度回调，默认不报告
+    pub fn progress(mut self, progress: Arc<dyn Progress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// `process` 和 `process_all` 使用的取消标记，默认不会被取消
+    pub fn cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn build(self) -> Engine {
+        Engine {
+            jobs: self.jobs,
+            cache_size: self.cache_size,
+            cache_max_entry: self.cache_max_entry,
+            cache: Mutex::new(VecDeque::new()),
+            keyring: self.keyring,
+            limits: self.limits,
+            verify_roundtrip: self.verify_roundtrip,
+            progress: self.progress,
+            cancel: self.cancel,
+        }
+    }
+}
+
+/// 处理请求的引擎，可以在多个线程间共享
+#[derive(Debug)]
+pub struct Engine {
+    jobs: usize,
+    cache_size: usize,
+    cache_max_entry: usize,
+    /// 最近解析过的文件，最近使用的在队尾
+    cache: Mutex<VecDeque<CacheEntry>>,
+    keyring: Keyring,
+    limits: Limits,
+    verify_roundtrip: bool,
+    progress: Arc<dyn Progress>,
+    cancel: CancelToken,
+}
+
+impl Engine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder {
+            jobs: 1,
+            cache_size: DEFAULT_CACHE_SIZE,
+            cache_max_entry: DEFAULT_CACHE_MAX_ENTRY,
+            keyring: Keyring::default(),
+            limits: Limits::default(),
+            verify_roundtrip: false,
+            progress: Arc::new(NoProgress),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    pub fn keyring(&self) -> &Keyring {
+        &self.keyring
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// 处理一个请求
+    pub fn process(&self, request: Request) -> Result<Response> {
+        self.process_with_cancel(request, &self.cancel)
+    }
+
+    /// 处理一个请求，在开始时、逐个写入 chunk 时、加解密前和序列化前检查 `cancel`，
+    /// 已取消时返回 `PngmeError::Cancelled`
+    pub fn process_with_cancel(&self, request: Request, cancel: &CancelToken) -> Result<Response> {
+        cancel.check()?;
+        match request {
+            Request::Encode {
+                png,
+                chunk_type,
+                message,
+                compress,
+                passphrase,
+            } => {
+                self.limits.check_payload_size(message.len() as u64)?;
+                let mut png = owned(self.parse(&png)?);
+                let chunk_type = ChunkType::from_str(&chunk_type)?;
+                cancel.check()?;
+                if compress {
+                    self.progress.phase(Phase::Compressing);
+                }
+                let mut data = Payload::new(message).to_bytes(compress)?;
+                if let Some(passphrase) = &passphrase {
+                    cancel.check()?;
+                    self.progress.phase(Phase::Encrypting);
+                    data = crypto::encrypt(&data, passphrase)?;
+                }
+                for part in payload::split(&data, payload::DEFAULT_CHUNK_SIZE)? {
+                    cancel.check()?;
+                    png.append_chunk(Chunk::new(chunk_type, part)?);
+                }
+                self.finish(&png, cancel)
+            }
+            Request::Decode {
+                png,
+                chunk_type,
+                passphrase,
+            } => {
+                let mut data = message_data(self.parse(&png)?.as_ref(), &chunk_type)?;
+                if let Some(passphrase) = &passphrase {
+                    cancel.check()?;
+                    self.progress.phase(Phase::Decrypting);
+                    data = crypto::decrypt(&data, passphrase)?;
+                }
+                Ok(Response::Payload(Payload::from_bytes_with_limits(
+                    &data,
+                    &self.limits,
+                )?))
+            }
+            Request::Remove { png, chunk_type } => {
+                let mut png = owned(self.parse(&png)?);
+                remove_message(&mut png, &chunk_type)?;
+                self.finish(&png, cancel)
+            }
+            Request::Scan { png } => Ok(Response::Chunks(scan::scan_bytes_with_limits(
+                &png,
+                &self.limits,
+            )?)),
+            Request::Strip { png, keep } => {
+                let keep = keep
+                    .iter()
+                    .map(|chunk_type| ChunkType::from_str(chunk_type))
+                    .collect::<Result<Vec<_>>>()?;
+                let mut png = owned(self.parse(&png)?);
+                png.strip_ancillary(&keep);
+                self.finish(&png, cancel)
+            }
+            Request::Repair { png } => {
+                let mut png = Png::from_bytes_unchecked_with_limits(&png, &self.limits)?;
+                png.repair_crcs();
+                self.finish(&png, cancel)
+            }
+            Request::Sign { png, key } => {
+                let key = self.keyring.signing_key(&key)?;
+                let mut png = owned(self.parse(&png)?);
+                self.progress.phase(Phase::Signing);
+                png.sign(key);
+                self.finish(&png, cancel)
+            }
+            Request::Verify { png, key } => {
+                let key = self.keyring.verifying_key(&key)?;
+                let png = self.parse(&png)?;
+                self.progress.phase(Phase::Verifying);
+                png.verify(&key)?;
+                Ok(Response::Verified)
+            }
+            Request::SetText { png, keyword, text } => {
+                let mut png = owned(self.parse(&png)?);
+                png.set_text(&keyword, &text)?;
+                self.finish(&png, cancel)
+            }
+        }
+    }
+
+    /// 用 `jobs` 个线程并行处理一批请求，结果与请求一一对应。返回时所有线程都已结束
+    pub fn process_all(&self, requests: Vec<Request>) -> Vec<Result<Response>> {
+        let count = requests.len();
+        let requests: Vec<Mutex<Option<Request>>> =
+            requests.into_iter().map(|r| Mutex::new(Some(r))).collect();
+        let results: Vec<Mutex<Option<Result<Response>>>> =
+            (0..count).map(|_| Mutex::new(None)).collect();
+        let next = AtomicUsize::new(0);
+        self.progress.requests(count as u64);
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(count.max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(request) = requests.get(index) else {
+                        break;
+                    };
+                    let request = request.lock().unwrap().take().expect("taken once");
+                    let result = self.process_with_cancel(request, &self.cancel);
+                    self.progress.request_done(index, result.is_ok());
+                    *results[index].lock().unwrap() = Some(result);
+                });
+            }
+        });
+        results
+            .into_iter()
+            .map(|result| result.into_inner().unwrap().expect("every request ran"))
+            .collect()
+    }
+
+    /// 解析 PNG，命中缓存时直接返回之前的结果。解析完成后把文件大小报告为已处理的字节数
+    fn parse(&self, bytes: &[u8]) -> Result<Arc<Png>> {
+        self.progress.total_bytes(bytes.len() as u64);
+        self.progress.phase(Phase::Parsing);
+        let png = self.parse_cached(bytes)?;
+        self.progress.bytes(bytes.len() as u64);
+        Ok(png)
+    }
+
+    /// 不超过 `cache_max_entry` 的文件按长度和 SHA-256 摘要缓存，
+    /// 加入新文件后从最久未使用的开始淘汰，直到总大小不超过 `cache_size`
+    fn parse_cached(&self, bytes: &[u8]) -> Result<Arc<Png>> {
+        let size = bytes.len();
+        if size > self.cache_size.min(self.cache_max_entry) {
+            return Ok(Arc::new(Png::from_bytes_with_limits(bytes, &self.limits)?));
+        }
+        let key: CacheKey = (size, Sha256::digest(bytes).into());
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(index) = cache.iter().position(|(k, _, _)| *k == key) {
+                let entry = cache.remove(index).expect("index is in range");
+                let png = Arc::clone(&entry.2);
+                cache.push_back(entry);
+                return Ok(png);
+            }
+        }
+        // 解析时不持有锁，其他线程可以同时解析别的文件
+        let png = Arc::new(Png::from_bytes_with_limits(bytes, &self.limits)?);
+        let mut cache = self.cache.lock().unwrap();
+        if !cache.iter().any(|(k, _, _)| *k == key) {
+            cache.push_back((key, size, Arc::clone(&png)));
+            let mut total: usize = cache.iter().map(|(_, size, _)| size).sum();
+            while total > self.cache_size {
+                let (_, size, _) = cache.pop_front().expect("cache is not empty");
+                total -= size;
+            }
+        }
+        Ok(png)
+    }
+
+    /// 序列化修改后的文件，需要时先做往返自检
+    fn finish(&self, png: &Png, cancel: &CancelToken) -> Result<Response> {
+        cancel.check()?;
+        self.progress.phase(Phase::Writing);
+        let bytes = if self.verify_roundtrip {
+            roundtrip::verify(png)?
+        } else {
+            png.as_bytes()
+        };
+        Ok(Response::Png(bytes))
+    }
+}
+
+/// 一次性写入一条信息，返回新的 PNG 文件。供 C ABI、wasm 和 Python 绑定使用：
+/// 每次调用都是独立的文件，所以不使用解析缓存
+#[cfg(any(test, feature = "ffi", feature = "wasm", feature = "python"))]
+pub(crate) fn encode_once(
+    png: &[u8],
+    chunk_type: &str,
+    message: &[u8],
+    compress: bool,
+    passphrase: Option<String>,
+) -> Result<Vec<u8>> {
+    let response = one_shot_engine().process(Request::Encode {
+        png: png.to_vec(),
+        chunk_type: chunk_type.to_string(),
+        message: message.to_vec(),
+        compress,
+        passphrase,
+    })?;
+    match response {
+        Response::Png(png) => Ok(png),
+        _ => unreachable!("encode always returns a PNG"),
+    }
+}
+
+/// 一次性读取 `chunk_type` 类型的信息，返回信息的内容
+#[cfg(any(test, feature = "ffi", feature = "wasm", feature = "python"))]
+pub(crate) fn decode_once(
+    png: &[u8],
+    chunk_type: &str,
+    passphrase: Option<String>,
+) -> Result<Vec<u8>> {
+    let response = one_shot_engine().process(Request::Decode {
+        png: png.to_vec(),
+        chunk_type: chunk_type.to_string(),
+        passphrase,
+    })?;
+    match response {
+        Response::Payload(payload) => Ok(payload.data),
+        _ => unreachable!("decode always returns a payload"),
+    }
+}
+
+#[cfg(any(test, feature = "ffi", feature = "wasm", feature = "python"))]
+fn one_shot_engine() -> Engine {
+    Engine::builder().cache_size(0).build()
+}
+
+/// 取得解析结果的所有权，没有被缓存共享时不复制
+fn owned(png: Arc<Png>) -> Png {
+    Arc::try_unwrap(png).unwrap_or_else(|png| (*png).clone())
+}
+
+fn missing_key(name: &str) -> PngmeError {
+    PngmeError::Crypto(format!("no key named {:?} in the keyring", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    fn engine() -> Engine {
+        Engine::builder()
+            .jobs(4)
+            .signing_key("release", SigningKey::from_bytes(&[7; 32]))
+            .verify_roundtrip(true)
+            .build()
+    }
+
+    fn png_bytes(response: Response) -> Vec<u8> {
+        match response {
+            Response::Png(bytes) => bytes,
+            other => panic!("expected a PNG, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let engine = engine();
+        let png = png_bytes(
+            engine
+                .process(Request::Encode {
+                    png: PNG_FILE.to_vec(),
+                    chunk_type: "ruSt".to_string(),
+                    message: b"hello".to_vec(),
+                    compress: true,
+                    passphrase: Some("secret".to_string()),
+                })
+                .unwrap(),
+        );
+        let decoded = engine
+            .process(Request::Decode {
+                png: png.clone(),
+                chunk_type: "ruSt".to_string(),
+                passphrase: Some("secret".to_string()),
+            })
+            .unwrap();
+        assert_eq!(decoded, Response::Payload(Payload::new(b"hello".to_vec())));
+
+        let removed = png_bytes(
+            engine
+                .process(Request::Remove {
+                    png,
+                    chunk_type: "ruSt".to_string(),
+                })
+                .unwrap(),
+        );
+        assert_eq!(removed, PNG_FILE);
+    }
+
+    #[test]
+    fn test_progress_callbacks() {
+        use crate::progress::tests::Recorder;
+        use std::sync::atomic::Ordering;
+
+        let recorder = Arc::new(Recorder::default());
+        let engine = Engine::builder().jobs(2).progress(recorder.clone()).build();
+        let encode = Request::Encode {
+            png: PNG_FILE.to_vec(),
+            chunk_type: "ruSt".to_string(),
+            message: b"hello".to_vec(),
+            compress: true,
+            passphrase: None,
+        };
+        engine.process(encode.clone()).unwrap();
+        assert_eq!(
+            *recorder.phases.lock().unwrap(),
+            [Phase::Parsing, Phase::Compressing, Phase::Writing]
+        );
+        assert_eq!(
+            recorder.bytes.load(Ordering::Relaxed),
+            PNG_FILE.len() as u64
+        );
+
+        let results = engine.process_all(vec![
+            encode,
+            Request::Remove {
+                png: PNG_FILE.to_vec(),
+                chunk_type: "ruSt".to_string(),
+            },
+        ]);
+        assert_eq!(recorder.total.load(Ordering::Relaxed), 2);
+        let mut done = recorder.requests.lock().unwrap().clone();
+        done.sort();
+        assert_eq!(done, [(0, true), (1, false)]);
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn test_sign_and_verify_with_keyring() {
+        let engine = engine();
+        let signed = png_bytes(
+            engine
+                .process(Request::Sign {
+                    png: PNG_FILE.to_vec(),
+                    key: "release".to_string(),
+                })
+                .unwrap(),
+        );
+        let verify = |key: &str| {
+            engine.process(Request::Verify {
+                png: signed.clone(),
+                key: key.to_string(),
+            })
+        };
+        assert_eq!(verify("release").unwrap(), Response::Verified);
+        assert!(matches!(verify("missing"), Err(PngmeError::Crypto(_))));
+    }
+
+    #[test]
+    fn test_process_all_keeps_order() {
+        let engine = engine();
+        let requests: Vec<Request> = (0..10)
+            .map(|i| Request::SetText {
+                png: PNG_FILE.to_vec(),
+                keyword: "Index".to_string(),
+                text: i.to_string(),
+            })
+            .chain([Request::Scan {
+                png: b"not a png".to_vec(),
+            }])
+            .collect();
+        let results = engine.process_all(requests);
+        assert_eq!(results.len(), 11);
+        for (i, result) in results.iter().take(10).enumerate() {
+            let Ok(Response::Png(bytes)) = result else {
+                panic!("request {} failed", i);
+            };
+            let png = Png::try_from(bytes.as_slice()).unwrap();
+            let text = png.chunk_by_type("tEXt").unwrap();
+            assert_eq!(text.data(), format!("Index\0{}", i).as_bytes());
+        }
+        assert!(results[10].is_err());
+        assert!(engine.process_all(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_encode_and_decode_once() {
+        let output = encode_once(&PNG_FILE, "ruSt", b"hello", true, None).unwrap();
+        assert_eq!(decode_once(&output, "ruSt", None).unwrap(), b"hello");
+
+        let secret = Some("secret".to_string());
+        let output = encode_once(&PNG_FILE, "ruSt", b"hidden", false, secret.clone()).unwrap();
+        assert_eq!(decode_once(&output, "ruSt", secret).unwrap(), b"hidden");
+        assert!(decode_once(&output, "ruSt", Some("wrong".to_string())).is_err());
+
+        assert!(encode_once(b"not a png", "ruSt", b"hello", false, None).is_err());
+        assert!(matches!(
+            decode_once(&PNG_FILE, "ruSt", None),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        // 缓存只放得下一个文件
+        let engine = Engine::builder().cache_size(PNG_FILE.len()).build();
+        let first = engine.parse(&PNG_FILE).unwrap();
+        let second = engine.parse(&PNG_FILE).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let other = Png::from_chunks(Vec::new()).as_bytes();
+        engine.parse(&other).unwrap();
+        assert!(!Arc::ptr_eq(&first, &engine.parse(&PNG_FILE).unwrap()));
+
+        // 超过 cache_max_entry 的文件不缓存
+        let engine = Engine::builder()
+            .cache_max_entry(PNG_FILE.len() - 1)
+            .build();
+        let first = engine.parse(&PNG_FILE).unwrap();
+        assert!(!Arc::ptr_eq(&first, &engine.parse(&PNG_FILE).unwrap()));
+        assert!(engine.cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let engine = Engine::builder()
+            .limits(Limits {
+                max_payload_size: 8,
+                ..Limits::default()
+            })
+            .build();
+        let result = engine.process(Request::Encode {
+            png: PNG_FILE.to_vec(),
+            chunk_type: "ruSt".to_string(),
+            message: b"longer than eight bytes".to_vec(),
+            compress: false,
+            passphrase: None,
+        });
+        assert!(matches!(result, Err(PngmeError::LimitExceeded(_))));
+
+        // Scan 和 Repair 也使用同样的限制
+        let engine = Engine::builder()
+            .limits(Limits {
+                max_file_size: 64,
+                ..Limits::default()
+            })
+            .build();
+        let scan = engine.process(Request::Scan {
+            png: PNG_FILE.to_vec(),
+        });
+        assert!(matches!(scan, Err(PngmeError::LimitExceeded(_))));
+        let repair = engine.process(Request::Repair {
+            png: PNG_FILE.to_vec(),
+        });
+        assert!(matches!(repair, Err(PngmeError::LimitExceeded(_))));
+        let engine = Engine::builder()
+            .limits(Limits {
+                max_chunks: 2,
+                ..Limits::default()
+            })
+            .build();
+        let scan = engine.process(Request::Scan {
+            png: PNG_FILE.to_vec(),
+        });
+        assert!(matches!(scan, Err(PngmeError::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let cancel = CancelToken::new();
+        let engine = Engine::builder().cancel(cancel.clone()).build();
+        let scan = || Request::Scan {
+            png: PNG_FILE.to_vec(),
+        };
+        assert!(engine.process(scan()).is_ok());
+
+        // 单个请求的标记不影响引擎的标记
+        let request = CancelToken::new();
+        request.cancel();
+        assert!(matches!(
+            engine.process_with_cancel(scan(), &request),
+            Err(PngmeError::Cancelled)
+        ));
+        assert!(engine.process(scan()).is_ok());
+
+        cancel.cancel();
+        let results = engine.process_all(vec![scan(), scan()]);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(PngmeError::Cancelled))));
+    }
+}
@@ -0,0 +1,313 @@
+//! # Error
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块定义了 crate 统一使用的错误类型 `PngmeError`。
+//!
+//! 命令行按错误的种类以固定的退出码退出（`PngmeError::exit_code`），脚本可以据此分支：
+//!
+//! | 退出码 | 含义 |
+//! | --- | --- |
+//! | 0 | 成功 |
+//! | 1 | 其他错误 |
+//! | 2 | 命令行参数错误（clap）或选项冲突 |
+//! | 3 | 找不到指定的 chunk |
+//! | 4 | CRC 校验失败 |
+//! | 5 | 输入不是 PNG |
+//! | 6 | I/O 错误（文件不存在、没有权限等） |
+//! | 7 | 解密、完整性标签或签名校验失败 |
+//! | 130 | 被信号中断（见 `cancel`） |
+//!
+//! `PngmeError::kind` 给出稳定的错误种类名，与退出码一起出现在 `--json` 的错误输出中。
+
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+
+use crate::cancel::EXIT_INTERRUPTED;
+use crate::sniff::ImageFormat;
+
+/// 其他错误的退出码
+pub const EXIT_FAILURE: i32 = 1;
+/// 命令行参数错误的退出码，与 clap 相同
+pub const EXIT_USAGE: i32 = 2;
+/// 找不到指定 chunk 的退出码
+pub const EXIT_CHUNK_NOT_FOUND: i32 = 3;
+/// CRC 校验失败的退出码
+pub const EXIT_CRC_MISMATCH: i32 = 4;
+/// 输入不是 PNG 的退出码
+pub const EXIT_NOT_PNG: i32 = 5;
+/// I/O 错误的退出码
+pub const EXIT_IO: i32 = 6;
+/// 解密、完整性标签、签名或校验清单校验失败的退出码
+pub const EXIT_CRYPTO: i32 = 7;
+
+#[derive(Debug)]
+pub enum PngmeError {
+    /// chunk 类型不是 4 个 ASCII 字母
+    InvalidChunkType(Vec<u8>),
+    /// 构建时缺少必需的字段，例如 `ChunkBuilder` 没有设置类型
+    MissingField(&'static str),
+    /// CRC 校验失败
+    CrcMismatch { expected: u32, actual: u32 },
+    /// 输入在 chunk 中途结束
+    TruncatedChunk,
+    /// chunk 数据超过 PNG 规范允许的最大长度
+    ChunkTooLarge(usize),
+    /// 输入超过 `Limits` 中的安全限制
+    LimitExceeded(String),
+    /// 压缩数据解压后超过允许的大小（解压炸弹），解压在超出时立即停止
+    DecompressionBomb { compressed: usize, limit: u64 },
+    /// 输入不是以 PNG 签名开头
+    InvalidSignature,
+    /// 输入是其他格式的图片（按魔数识别）
+    NotPng(ImageFormat),
+    /// 把其他格式的图片转换为 PNG 失败
+    Convert(String),
+    /// 找不到指定类型的 chunk
+    ChunkNotFound(String),
+    /// chunk 数据不是合法的 UTF-8
+    InvalidUtf8(Utf8Error),
+    /// 信息的封装格式无效
+    InvalidPayload(String),
+    /// `--input-encoding` 指定的 base64 或十六进制输入无效
+    InvalidEncoding(String),
+    /// IHDR chunk 缺少字段或字段组合无效
+    InvalidHeader(String),
+    /// IDAT 解压后的图像数据无效（长度不符、未知的过滤类型等）
+    InvalidImageData(String),
+    /// APNG 控制 chunk（acTL、fcTL、fdAT）无效
+    InvalidAnimation(String),
+    /// 编辑后的 chunk 顺序违反规范（见 `ordering`）
+    InvalidOrdering(String),
+    /// tEXt、zTXt、iTXt 等文本 chunk 的内容不符合规范
+    InvalidTextChunk(String),
+    /// pHYs、tIME 等元数据 chunk 或批量编辑的映射表无效
+    InvalidMetadata(String),
+    /// `--strict-preserve` 时不认识的 chunk 没有被原样保留
+    NotPreserved(String),
+    /// `--verify-roundtrip` 时输出无法无损地读回
+    RoundTrip(String),
+    /// `canonicalize --check` 时文件不是规范形式
+    NotCanonical(String),
+    /// `manifest verify` 时文件与校验清单不一致
+    ManifestMismatch(String),
+    /// 加密或解密失败（口令错误、数据被篡改等）
+    Crypto(String),
+    /// 压缩包格式错误
+    Archive(String),
+    /// 模板语法错误或变量未定义
+    Template(String),
+    /// grep 的搜索模式不是合法的正则表达式
+    InvalidPattern(String),
+    /// 批处理参数无效，或有文件处理失败
+    Batch(String),
+    /// 配置文件（pngme.toml）无法解析或设置无效
+    Config(String),
+    /// 无法生成或识读 `--method qr` 的 QR 码（图片太小、找不到 QR 码、错误太多无法纠正等）
+    QrCode(String),
+    /// 无法写入或读取 `--method lsb` 的信息（图片格式不支持、放不下、找不到头部等）
+    Lsb(String),
+    /// 无法写入或检测鲁棒水印（图片格式不支持、图片太小、找不到水印等）
+    Watermark(String),
+    /// 命令行选项互相冲突或取值无效，例如 `--method lsb` 读取标准输入
+    Usage(String),
+    /// 操作被 `CancelToken` 取消
+    Cancelled,
+    /// 底层 I/O 错误
+    Io(io::Error),
+}
+
+impl fmt::Display for PngmeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngmeError::InvalidChunkType(bytes) => {
+                write!(
+                    f,
+                    "Invalid chunk type: {:?}",
+                    String::from_utf8_lossy(bytes)
+                )
+            }
+            PngmeError::CrcMismatch { expected, actual } => write!(
+                f,
+                "CRC mismatch: expected {:08X}, calculated {:08X}",
+                expected, actual
+            ),
+            PngmeError::MissingField(field) => write!(f, "Missing required field: {}", field),
+            PngmeError::TruncatedChunk => write!(f, "The input ended in the middle of a chunk"),
+            PngmeError::ChunkTooLarge(length) => write!(
+                f,
+                "Chunk data is {} bytes, larger than the PNG limit of 2^31 - 1 bytes",
+                length
+            ),
+            PngmeError::LimitExceeded(message) => write!(f, "Limit exceeded: {}", message),
+            PngmeError::DecompressionBomb { compressed, limit } => write!(
+                f,
+                "Refusing to inflate {} bytes of compressed data to more than {} bytes",
+                compressed, limit
+            ),
+            PngmeError::InvalidSignature => {
+                write!(f, "The input does not start with the PNG signature")
+            }
+            PngmeError::NotPng(format) => {
+                write!(f, "Expected a PNG file, found {} image data", format)
+            }
+            PngmeError::Convert(message) => write!(f, "Conversion failed: {}", message),
+            PngmeError::ChunkNotFound(chunk_type) => {
+                write!(f, "Chunk type {} not found", chunk_type)
+            }
+            PngmeError::InvalidUtf8(e) => write!(f, "Chunk data is not valid UTF-8: {}", e),
+            PngmeError::InvalidPayload(message) => write!(f, "Invalid payload: {}", message),
+            PngmeError::InvalidEncoding(message) => {
+                write!(f, "Invalid encoded input: {}", message)
+            }
+            PngmeError::InvalidHeader(message) => write!(f, "Invalid IHDR: {}", message),
+            PngmeError::InvalidImageData(message) => write!(f, "Invalid image data: {}", message),
+            PngmeError::InvalidAnimation(message) => write!(f, "Invalid APNG: {}", message),
+            PngmeError::InvalidOrdering(message) => {
+                write!(f, "Invalid chunk ordering: {}", message)
+            }
+            PngmeError::InvalidTextChunk(message) => write!(f, "Invalid text chunk: {}", message),
+            PngmeError::InvalidMetadata(message) => write!(f, "Invalid metadata: {}", message),
+            PngmeError::NotPreserved(message) => {
+                write!(f, "Unknown chunk not preserved: {}", message)
+            }
+            PngmeError::RoundTrip(message) => write!(f, "Round-trip check failed: {}", message),
+            PngmeError::NotCanonical(path) => write!(f, "{} is not in canonical form", path),
+            PngmeError::ManifestMismatch(message) => {
+                write!(f, "Manifest verification failed: {}", message)
+            }
+            PngmeError::Crypto(message) => write!(f, "Crypto error: {}", message),
+            PngmeError::Archive(message) => write!(f, "Invalid archive: {}", message),
+            PngmeError::Template(message) => write!(f, "Invalid template: {}", message),
+            PngmeError::InvalidPattern(message) => write!(f, "Invalid pattern: {}", message),
+            PngmeError::Batch(message) => write!(f, "Batch failed: {}", message),
+            PngmeError::Config(message) => write!(f, "Invalid config: {}", message),
+            PngmeError::QrCode(message) => write!(f, "QR code error: {}", message),
+            PngmeError::Lsb(message) => write!(f, "LSB error: {}", message),
+            PngmeError::Watermark(message) => write!(f, "Watermark error: {}", message),
+            PngmeError::Usage(message) => write!(f, "Invalid usage: {}", message),
+            PngmeError::Cancelled => write!(f, "The operation was cancelled"),
+            PngmeError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl PngmeError {
+    /// 命令行以该错误结束时的退出码
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PngmeError::ChunkNotFound(_) => EXIT_CHUNK_NOT_FOUND,
+            PngmeError::CrcMismatch { .. } => EXIT_CRC_MISMATCH,
+            PngmeError::InvalidSignature | PngmeError::NotPng(_) => EXIT_NOT_PNG,
+            PngmeError::Io(_) => EXIT_IO,
+            PngmeError::Crypto(_) | PngmeError::ManifestMismatch(_) => EXIT_CRYPTO,
+            PngmeError::Usage(_) => EXIT_USAGE,
+            PngmeError::Cancelled => EXIT_INTERRUPTED,
+            _ => EXIT_FAILURE,
+        }
+    }
+
+    /// 稳定的错误种类名（小写，以 `-` 连接），不随错误信息的措辞变化
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PngmeError::InvalidChunkType(_) => "invalid-chunk-type",
+            PngmeError::MissingField(_) => "missing-field",
+            PngmeError::CrcMismatch { .. } => "crc-mismatch",
+            PngmeError::TruncatedChunk => "truncated-chunk",
+            PngmeError::ChunkTooLarge(_) => "chunk-too-large",
+            PngmeError::LimitExceeded(_) => "limit-exceeded",
+            PngmeError::DecompressionBomb { .. } => "decompression-bomb",
+            PngmeError::InvalidSignature => "invalid-signature",
+            PngmeError::NotPng(_) => "not-png",
+            PngmeError::Convert(_) => "convert",
+            PngmeError::ChunkNotFound(_) => "chunk-not-found",
+            PngmeError::InvalidUtf8(_) => "invalid-utf8",
+            PngmeError::InvalidPayload(_) => "invalid-payload",
+            PngmeError::InvalidEncoding(_) => "invalid-encoding",
+            PngmeError::InvalidHeader(_) => "invalid-header",
+            PngmeError::InvalidImageData(_) => "invalid-image-data",
+            PngmeError::InvalidAnimation(_) => "invalid-animation",
+            PngmeError::InvalidOrdering(_) => "invalid-ordering",
+            PngmeError::InvalidTextChunk(_) => "invalid-text-chunk",
+            PngmeError::InvalidMetadata(_) => "invalid-metadata",
+            PngmeError::NotPreserved(_) => "not-preserved",
+            PngmeError::RoundTrip(_) => "round-trip",
+            PngmeError::NotCanonical(_) => "not-canonical",
+            PngmeError::ManifestMismatch(_) => "manifest-mismatch",
+            PngmeError::Crypto(_) => "crypto",
+            PngmeError::Archive(_) => "archive",
+            PngmeError::Template(_) => "template",
+            PngmeError::InvalidPattern(_) => "invalid-pattern",
+            PngmeError::Batch(_) => "batch",
+            PngmeError::Config(_) => "config",
+            PngmeError::QrCode(_) => "qr-code",
+            PngmeError::Lsb(_) => "lsb",
+            PngmeError::Watermark(_) => "watermark",
+            PngmeError::Usage(_) => "usage",
+            PngmeError::Cancelled => "cancelled",
+            PngmeError::Io(_) => "io",
+        }
+    }
+}
+
+impl std::error::Error for PngmeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngmeError::InvalidUtf8(e) => Some(e),
+            PngmeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PngmeError {
+    fn from(e: io::Error) -> Self {
+        PngmeError::Io(e)
+    }
+}
+
+impl From<Utf8Error> for PngmeError {
+    fn from(e: Utf8Error) -> Self {
+        PngmeError::InvalidUtf8(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let cases = [
+            (
+                PngmeError::ChunkNotFound("ruSt".to_string()),
+                3,
+                "chunk-not-found",
+            ),
+            (
+                PngmeError::CrcMismatch {
+                    expected: 1,
+                    actual: 2,
+                },
+                4,
+                "crc-mismatch",
+            ),
+            (PngmeError::InvalidSignature, 5, "invalid-signature"),
+            (PngmeError::NotPng(ImageFormat::Jpeg), 5, "not-png"),
+            (io::Error::from(io::ErrorKind::NotFound).into(), 6, "io"),
+            (PngmeError::Crypto("bad tag".to_string()), 7, "crypto"),
+            (
+                PngmeError::ManifestMismatch("1 file".to_string()),
+                7,
+                "manifest-mismatch",
+            ),
+            (PngmeError::Usage("--assess".to_string()), 2, "usage"),
+            (PngmeError::Cancelled, 130, "cancelled"),
+            (PngmeError::TruncatedChunk, 1, "truncated-chunk"),
+        ];
+        for (error, code, kind) in cases {
+            assert_eq!((error.exit_code(), error.kind()), (code, kind));
+        }
+    }
+}
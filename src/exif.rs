@@ -0,0 +1,382 @@
+//! # Exif
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 eXIf chunk 的读写。chunk 数据是一个完整的 TIFF 结构，
+//! 不带 JPEG APP1 中的 `Exif\0\0` 前缀：
+//!
+//! ```text
+//! | 字节序 (II 或 MM) | 42 (u16) | IFD0 偏移 (u32) | ... |
+//! IFD: | 项数 (u16) | 项 (标签 u16, 类型 u16, 个数 u32, 值或偏移 u32) ... | 下一个 IFD 偏移 (u32) |
+//! ```
+//!
+//! 数据按原样保存和写回；解析只读取常用的标签：IFD0 中的方向（0x0112）和修改时间（0x0132），
+//! 以及 Exif IFD（IFD0 中 0x8769 指向）中的拍摄时间（0x9003）和数字化时间（0x9004）。
+//! 所有偏移都做边界检查，损坏的数据不会导致越界读取。
+
+use std::convert::TryFrom;
+
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::{Error, PngmeError, Result};
+
+/// JPEG APP1 段中 TIFF 数据之前的前缀，写入 eXIf 时要去掉
+const APP1_PREFIX: &[u8] = b"Exif\0\0";
+/// 方向
+const TAG_ORIENTATION: u16 = 0x0112;
+/// 最后修改时间
+const TAG_DATE_TIME: u16 = 0x0132;
+/// Exif IFD 的偏移
+const TAG_EXIF_IFD: u16 = 0x8769;
+/// 拍摄时间
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+/// 数字化时间
+const TAG_DATE_TIME_DIGITIZED: u16 = 0x9004;
+/// TIFF 字段类型 ASCII、SHORT 和 LONG
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+
+/// TIFF 数据的字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+/// eXIf chunk 中的原始 TIFF 数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exif {
+    data: Vec<u8>,
+}
+
+/// 解析出的常用标签，供 `exif get` 显示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExifSummary {
+    pub length: usize,
+    pub byte_order: ByteOrder,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_time_original: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_time_digitized: Option<String>,
+}
+
+/// IFD 中的一项
+struct Entry<'a> {
+    field_type: u16,
+    count: u32,
+    /// 值的字节，已按类型和个数截取
+    value: &'a [u8],
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+impl Exif {
+    pub const CHUNK_TYPE: &'static str = "eXIf";
+
+    /// 由 TIFF 数据创建，`Exif\0\0` 前缀（从 JPEG 中复制的数据）会被去掉。
+    /// 数据必须以有效的 TIFF 文件头开头
+    pub fn new(data: Vec<u8>) -> Result<Self> {
+        let data = match data.strip_prefix(APP1_PREFIX) {
+            Some(tiff) => tiff.to_vec(),
+            None => data,
+        };
+        let exif = Exif { data };
+        exif.byte_order()?;
+        Ok(exif)
+    }
+
+    /// 原始 TIFF 数据
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// 按文件头确定字节序，并检查 TIFF 标识 42
+    pub fn byte_order(&self) -> Result<ByteOrder> {
+        let order = match self.data.get(..2) {
+            Some(b"II") => ByteOrder::LittleEndian,
+            Some(b"MM") => ByteOrder::BigEndian,
+            _ => {
+                return Err(invalid(
+                    "eXIf data does not start with II or MM".to_string(),
+                ))
+            }
+        };
+        match self.data.get(2..4).map(|bytes| order.u16(bytes)) {
+            Some(42) => Ok(order),
+            _ => Err(invalid(
+                "eXIf data is not a TIFF structure (missing 42)".to_string(),
+            )),
+        }
+    }
+
+    /// 方向（1 到 8），没有该标签时返回 `None`
+    pub fn orientation(&self) -> Result<Option<u16>> {
+        let order = self.byte_order()?;
+        match self.find(self.ifd0()?, TAG_ORIENTATION)? {
+            Some(entry) if entry.field_type == TYPE_SHORT && entry.count >= 1 => {
+                Ok(Some(order.u16(entry.value)))
+            }
+            Some(_) => Err(invalid("Orientation is not a SHORT".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// 最后修改时间（IFD0 的 DateTime），格式为 `YYYY:MM:DD HH:MM:SS`
+    pub fn date_time(&self) -> Result<Option<String>> {
+        self.find(self.ifd0()?, TAG_DATE_TIME)?
+            .map(ascii)
+            .transpose()
+    }
+
+    /// 拍摄时间（Exif IFD 的 DateTimeOriginal）
+    pub fn date_time_original(&self) -> Result<Option<String>> {
+        self.exif_tag(TAG_DATE_TIME_ORIGINAL)
+    }
+
+    /// 数字化时间（Exif IFD 的 DateTimeDigitized）
+    pub fn date_time_digitized(&self) -> Result<Option<String>> {
+        self.exif_tag(TAG_DATE_TIME_DIGITIZED)
+    }
+
+    /// 解析常用标签
+    pub fn summary(&self) -> Result<ExifSummary> {
+        Ok(ExifSummary {
+            length: self.data.len(),
+            byte_order: self.byte_order()?,
+            orientation: self.orientation()?,
+            date_time: self.date_time()?,
+            date_time_original: self.date_time_original()?,
+            date_time_digitized: self.date_time_digitized()?,
+        })
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let chunk_type = ChunkType::from_str(Self::CHUNK_TYPE).expect("eXIf is a valid chunk type");
+        Chunk::new(chunk_type, self.data.clone())
+    }
+
+    /// IFD0 的偏移
+    fn ifd0(&self) -> Result<usize> {
+        let order = self.byte_order()?;
+        self.data
+            .get(4..8)
+            .map(|bytes| order.u32(bytes) as usize)
+            .ok_or_else(|| invalid("eXIf data is too short for a TIFF header".to_string()))
+    }
+
+    /// Exif IFD 中的 ASCII 标签；没有 Exif IFD 或没有该标签时返回 `None`
+    fn exif_tag(&self, tag: u16) -> Result<Option<String>> {
+        let order = self.byte_order()?;
+        let ifd = match self.find(self.ifd0()?, TAG_EXIF_IFD)? {
+            Some(entry) if entry.field_type == TYPE_LONG && entry.count >= 1 => {
+                order.u32(entry.value) as usize
+            }
+            Some(_) => return Err(invalid("ExifIFDPointer is not a LONG".to_string())),
+            None => return Ok(None),
+        };
+        self.find(ifd, tag)?.map(ascii).transpose()
+    }
+
+    /// 在 `offset` 处的 IFD 中查找标签
+    fn find(&self, offset: usize, tag: u16) -> Result<Option<Entry<'_>>> {
+        let order = self.byte_order()?;
+        let truncated = || invalid(format!("eXIf IFD at offset {} is truncated", offset));
+        let count = self
+            .data
+            .get(offset..)
+            .and_then(|rest| rest.get(..2))
+            .map(|bytes| order.u16(bytes) as usize)
+            .ok_or_else(truncated)?;
+        for index in 0..count {
+            let start = offset + 2 + index * 12;
+            let entry = self.data.get(start..start + 12).ok_or_else(truncated)?;
+            if order.u16(&entry[..2]) != tag {
+                continue;
+            }
+            let field_type = order.u16(&entry[2..4]);
+            let count = order.u32(&entry[4..8]);
+            let size = type_size(field_type)
+                .and_then(|size| size.checked_mul(count as usize))
+                .ok_or_else(|| invalid(format!("eXIf tag {:#06x} has an unknown type", tag)))?;
+            // 不超过 4 字节的值直接存放在项中，否则项中是值的偏移
+            let value = if size <= 4 {
+                &entry[8..8 + size]
+            } else {
+                let value_offset = order.u32(&entry[8..12]) as usize;
+                value_offset
+                    .checked_add(size)
+                    .and_then(|end| self.data.get(value_offset..end))
+                    .ok_or_else(|| {
+                        invalid(format!("eXIf tag {:#06x} points outside the data", tag))
+                    })?
+            };
+            return Ok(Some(Entry {
+                field_type,
+                count,
+                value,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+impl TryFrom<&Chunk> for Exif {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        if chunk.chunk_type().to_string() != Self::CHUNK_TYPE {
+            return Err(invalid(format!(
+                "expected a {} chunk, found {}",
+                Self::CHUNK_TYPE,
+                chunk.chunk_type()
+            )));
+        }
+        let exif = Exif {
+            data: chunk.data().to_vec(),
+        };
+        exif.byte_order()?;
+        Ok(exif)
+    }
+}
+
+/// 方向标签的含义
+pub fn orientation_name(orientation: u16) -> Option<&'static str> {
+    Some(match orientation {
+        1 => "normal",
+        2 => "mirrored horizontally",
+        3 => "rotated 180°",
+        4 => "mirrored vertically",
+        5 => "mirrored horizontally and rotated 270° clockwise",
+        6 => "rotated 90° clockwise",
+        7 => "mirrored horizontally and rotated 90° clockwise",
+        8 => "rotated 270° clockwise",
+        _ => return None,
+    })
+}
+
+/// TIFF 字段类型的单个值的字节数
+fn type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// ASCII 字段的值，去掉末尾的 NUL
+fn ascii(entry: Entry<'_>) -> Result<String> {
+    if entry.field_type != TYPE_ASCII {
+        return Err(invalid("expected an ASCII eXIf tag".to_string()));
+    }
+    let value = entry.value.split(|&b| b == 0).next().unwrap_or_default();
+    if !value.is_ascii() {
+        return Err(invalid(
+            "eXIf ASCII tag contains non-ASCII bytes".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(value).into_owned())
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidMetadata(message)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 小端 TIFF：IFD0 含方向 6、修改时间和 Exif IFD 指针，Exif IFD 含拍摄时间
+    pub(crate) fn sample_exif() -> Vec<u8> {
+        let date = b"2026:10:16 08:30:00\0";
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0 在偏移 8，3 项，到 8 + 2 + 36 + 4 = 50 结束；修改时间在 50，Exif IFD 在 70
+        data.extend_from_slice(&3u16.to_le_bytes());
+        for (tag, field_type, count, value) in [
+            (TAG_ORIENTATION, TYPE_SHORT, 1u32, 6u32),
+            (TAG_DATE_TIME, TYPE_ASCII, 20, 50),
+            (TAG_EXIF_IFD, TYPE_LONG, 1, 70),
+        ] {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(date);
+        // Exif IFD 在偏移 70，1 项，到 70 + 2 + 12 + 4 = 88 结束；拍摄时间在 88
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&TAG_DATE_TIME_ORIGINAL.to_le_bytes());
+        data.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+        data.extend_from_slice(&20u32.to_le_bytes());
+        data.extend_from_slice(&88u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(date);
+        data
+    }
+
+    #[test]
+    fn test_exif_tags() {
+        let exif = Exif::new(sample_exif()).unwrap();
+        let summary = exif.summary().unwrap();
+        assert_eq!(summary.byte_order, ByteOrder::LittleEndian);
+        assert_eq!(summary.orientation, Some(6));
+        assert_eq!(summary.date_time.as_deref(), Some("2026:10:16 08:30:00"));
+        assert_eq!(
+            summary.date_time_original.as_deref(),
+            Some("2026:10:16 08:30:00")
+        );
+        assert_eq!(summary.date_time_digitized, None);
+        assert_eq!(orientation_name(6), Some("rotated 90° clockwise"));
+    }
+
+    #[test]
+    fn test_exif_chunk_round_trip() {
+        let mut jpeg_style = APP1_PREFIX.to_vec();
+        jpeg_style.extend(sample_exif());
+        let exif = Exif::new(jpeg_style).unwrap();
+        assert_eq!(exif.data(), sample_exif());
+        let chunk = exif.to_chunk().unwrap();
+        assert_eq!(Exif::try_from(&chunk).unwrap(), exif);
+    }
+
+    #[test]
+    fn test_invalid_exif() {
+        assert!(Exif::new(b"JFIF".to_vec()).is_err());
+        assert!(Exif::new(b"MM\0\x2b\0\0\0\x08".to_vec()).is_err());
+        // IFD 偏移超出数据
+        let exif = Exif::new(b"MM\0*\0\0\x10\0".to_vec()).unwrap();
+        assert!(exif.orientation().is_err());
+        // 值的偏移超出数据
+        let mut data = sample_exif();
+        data[30..34].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(Exif::new(data).unwrap().date_time().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! # Fdat
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `--method fdat`：把信息藏在 APNG 的帧数据里，而不是另加辅助 chunk。
+//! 信息拆分为分片（见 `payload::split_evenly`），每个分片装进一个新的 fdAT chunk，
+//! 追加在各帧原有的 fdAT 之后：
+//!
+//! ```text
+//! fcTL | fdAT (zlib 数据流) ... | fdAT: | 序号 (u32) | 分片 | | 下一帧的 fcTL ...
+//! ```
+//!
+//! 解码器按帧拼接 fdAT 的数据并解压，zlib 数据流结束之后的数据会被忽略，所以动画照常播放。
+//! 插入 fdAT 后，之后所有 fcTL/fdAT 的序号都会重新编号，保持从 0 开始连续递增。
+//! 读取时逐帧解压，找出完全位于数据流结束之后的 fdAT，拼接其中的分片。
+//!
+//! 第 0 帧的数据通常是 IDAT 而不是 fdAT，不会用来存放信息；至少需要一帧以 fdAT 存储。
+//! 再次写入会先删除之前写入的分片。
+
+use flate2::{Decompress, FlushDecompress, Status};
+
+use crate::apng::FrameData;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ordering::{self, ChunkPosition};
+use crate::payload;
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 序号占用的字节数
+const SEQUENCE_LEN: usize = 4;
+
+/// 把信息数据拆分后藏在各帧的 fdAT 中，替换之前写入的信息，返回写入的 fdAT 数量。
+/// 每个 fdAT（含序号）不超过 `max_chunk_size` 字节
+pub fn embed(png: &mut Png, data: &[u8], max_chunk_size: usize) -> Result<usize> {
+    remove(png)?;
+    let frames = fdat_frames(png.chunks());
+    if frames.is_empty() {
+        return Err(PngmeError::InvalidAnimation(
+            "--method fdat needs an animated PNG with at least one frame stored in fdAT"
+                .to_string(),
+        ));
+    }
+    let parts = payload::split_evenly(
+        data,
+        frames.len(),
+        max_chunk_size.saturating_sub(SEQUENCE_LEN),
+    )?;
+    let count = parts.len();
+    let chunk_type = ChunkType::from_str(FrameData::CHUNK_TYPE)?;
+    // 分片多于帧数时相邻的分片放在同一帧中；序号在全部插入后统一编号
+    for (index, part) in parts.into_iter().enumerate() {
+        let frame = frames[index * frames.len() / count];
+        let mut bytes = vec![0; SEQUENCE_LEN];
+        bytes.extend(part);
+        png.insert_chunk(
+            Chunk::new(chunk_type, bytes)?,
+            ChunkPosition::AfterFrame(frame),
+        );
+    }
+    renumber(png)?;
+    Ok(count)
+}
+
+/// 读取藏在 fdAT 中的信息数据
+pub fn message_data(png: &Png) -> Result<Vec<u8>> {
+    let chunks = png.chunks();
+    let parts: Vec<&[u8]> = hidden(chunks)
+        .into_iter()
+        .map(|index| &chunks[index].data()[SEQUENCE_LEN..])
+        .collect();
+    if parts.is_empty() {
+        return Err(PngmeError::ChunkNotFound(format!(
+            "{} with a hidden message",
+            FrameData::CHUNK_TYPE
+        )));
+    }
+    payload::join(&parts)
+}
+
+/// 删除藏有信息的 fdAT 并重新编号，返回被删除的 chunk（可能为空）
+pub fn remove(png: &mut Png) -> Result<Vec<Chunk>> {
+    let indices = hidden(png.chunks());
+    if indices.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut removed = indices
+        .into_iter()
+        .rev()
+        .map(|index| png.remove_chunk_at(index))
+        .collect::<Result<Vec<_>>>()?;
+    removed.reverse();
+    renumber(png)?;
+    Ok(removed)
+}
+
+/// 按文件中的顺序给 fcTL 和 fdAT 重新编号，从 0 开始连续递增
+pub fn renumber(png: &mut Png) -> Result<()> {
+    let mut sequence = 0u32;
+    for chunk in png.chunks_mut() {
+        if !matches!(&chunk.chunk_type().bytes(), b"fcTL" | b"fdAT") || chunk.data().len() < 4 {
+            continue;
+        }
+        chunk.map_data(|data| {
+            let mut data = data.to_vec();
+            data[..SEQUENCE_LEN].copy_from_slice(&sequence.to_be_bytes());
+            data
+        })?;
+        sequence += 1;
+    }
+    Ok(())
+}
+
+/// 以 fdAT 存储数据的帧（按 fcTL 计数，从 0 开始）
+fn fdat_frames(chunks: &[Chunk]) -> Vec<u32> {
+    let frames = chunks
+        .iter()
+        .filter(|chunk| &chunk.chunk_type().bytes() == b"fcTL")
+        .count() as u32;
+    (0..frames)
+        .filter(|&frame| {
+            ordering::frame_range(chunks, frame)
+                .is_some_and(|range| chunks[range].iter().any(is_fdat))
+        })
+        .collect()
+}
+
+/// 藏有信息的 fdAT 的下标：每帧中完全位于 zlib 数据流结束之后的 fdAT。
+/// 数据流没有结束（数据损坏）的帧视为没有信息
+fn hidden(chunks: &[Chunk]) -> Vec<usize> {
+    let mut indices = Vec::new();
+    for frame in fdat_frames(chunks) {
+        let Some(range) = ordering::frame_range(chunks, frame) else {
+            continue;
+        };
+        let fdat: Vec<usize> = range.filter(|&index| is_fdat(&chunks[index])).collect();
+        let data: Vec<u8> = fdat
+            .iter()
+            .flat_map(|&index| chunks[index].data()[SEQUENCE_LEN..].iter().copied())
+            .collect();
+        let Some(end) = stream_len(&data) else {
+            continue;
+        };
+        let mut offset = 0;
+        for index in fdat {
+            if offset >= end {
+                indices.push(index);
+            }
+            offset += chunks[index].data().len() - SEQUENCE_LEN;
+        }
+    }
+    indices
+}
+
+fn is_fdat(chunk: &Chunk) -> bool {
+    &chunk.chunk_type().bytes() == b"fdAT" && chunk.data().len() >= SEQUENCE_LEN
+}
+
+/// `data` 开头的完整 zlib 数据流的长度，数据流不完整或无效时返回 `None`。
+/// 解压出的数据直接丢弃，不占用与图像大小相当的内存
+fn stream_len(data: &[u8]) -> Option<usize> {
+    let mut decompress = Decompress::new(true);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let consumed = decompress.total_in() as usize;
+        let produced = decompress.total_out();
+        let status = decompress
+            .decompress(&data[consumed..], &mut buffer, FlushDecompress::None)
+            .ok()?;
+        if status == Status::StreamEnd {
+            return Some(decompress.total_in() as usize);
+        }
+        if decompress.total_in() as usize == consumed && decompress.total_out() == produced {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::apng::tests::frame_control;
+    use crate::apng::AnimationControl;
+    use crate::png::tests::PNG_FILE;
+    use std::convert::TryFrom;
+
+    /// 三帧的 APNG：第 0 帧是 IDAT，第 1 帧是一个 fdAT，第 2 帧的数据流拆成两个 fdAT
+    pub(crate) fn testing_apng() -> Png {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let idat = png.remove_chunk("IDAT").unwrap();
+        let iend = png.remove_chunk("IEND").unwrap();
+        let stream = idat.data().to_vec();
+        let (head, tail) = stream.split_at(stream.len() / 2);
+        let fdat = |data: &[u8]| {
+            FrameData {
+                sequence_number: 0,
+                data: data.to_vec(),
+            }
+            .to_chunk()
+            .unwrap()
+        };
+        let control = AnimationControl {
+            num_frames: 3,
+            num_plays: 0,
+        };
+        let mut chunks = png.chunks().to_vec();
+        chunks.extend([
+            control.to_chunk(),
+            frame_control(0, 10).to_chunk(),
+            idat,
+            frame_control(0, 10).to_chunk(),
+            fdat(&stream),
+            frame_control(0, 10).to_chunk(),
+            fdat(head),
+            fdat(tail),
+            iend,
+        ]);
+        let mut png = Png::from_chunks(chunks);
+        renumber(&mut png).unwrap();
+        png
+    }
+
+    fn fdat_count(png: &Png) -> usize {
+        png.chunks_by_type("fdAT").len()
+    }
+
+    #[test]
+    fn test_embed_and_extract() {
+        let mut png = testing_apng();
+        let original = png.clone();
+        assert!(png.validate_ordering().is_empty());
+        assert!(hidden(png.chunks()).is_empty());
+
+        let data = b"hidden across animation frames".to_vec();
+        assert_eq!(embed(&mut png, &data, 30).unwrap(), 3);
+        assert_eq!(fdat_count(&png), 3 + 3);
+        assert!(png.validate_ordering().is_empty());
+        assert_eq!(message_data(&png).unwrap(), data);
+        // 分片在各帧原有的 fdAT 之后
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            types[types.len() - 9..],
+            ["fcTL", "fdAT", "fdAT", "fdAT", "fcTL", "fdAT", "fdAT", "fdAT", "IEND"][..]
+        );
+
+        // 再次写入替换之前的信息
+        embed(&mut png, b"second", 1024).unwrap();
+        assert_eq!(fdat_count(&png), 3 + 2);
+        assert_eq!(message_data(&png).unwrap(), b"second");
+
+        assert_eq!(remove(&mut png).unwrap().len(), 2);
+        assert_eq!(png.as_bytes(), original.as_bytes());
+        assert!(matches!(
+            message_data(&png),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_needs_fdat_frame() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(matches!(
+            embed(&mut png, b"data", 1024),
+            Err(PngmeError::InvalidAnimation(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! # FFI
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在启用 `ffi` feature 时导出 C ABI 函数，让 C/C++、Python（ctypes/cffi）、Go（cgo）
+//! 等语言直接调用 pngme，而不需要启动命令行进程。头文件 `include/pngme.h` 由 cbindgen 生成。
+//!
+//! 约定：
+//!
+//! - 所有函数返回 `PngmeStatus`，成功时结果写入调用者提供的 `PngmeBuffer`；
+//! - 返回的缓冲区由 pngme 分配，必须用 `pngme_free` 释放，不能用 `free`；
+//! - 失败时 `pngme_last_error` 返回当前线程最近一次错误的描述；
+//! - Rust 中的 panic 不会跨越 FFI 边界，而是返回 `PNGME_STATUS_PANIC`。
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use crate::engine;
+use crate::PngmeError;
+
+/// 函数的返回值
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PngmeStatus {
+    /// 成功
+    Ok = 0,
+    /// 参数为空指针或者字符串不是 UTF-8
+    InvalidArgument = 1,
+    /// pngme 返回了错误，例如文件不是 PNG 或找不到 chunk
+    Failed = 2,
+    /// 内部错误（panic）
+    Panic = 3,
+}
+
+/// pngme 分配的字节缓冲区，用 `pngme_free` 释放
+#[repr(C)]
+#[derive(Debug)]
+pub struct PngmeBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl PngmeBuffer {
+    fn from_vec(bytes: Vec<u8>) -> PngmeBuffer {
+        let bytes = Box::into_raw(bytes.into_boxed_slice());
+        PngmeBuffer {
+            data: bytes as *mut u8,
+            len: bytes.len(),
+        }
+    }
+}
+
+/// 调用失败的状态码和描述
+struct Failure(PngmeStatus, String);
+
+impl From<PngmeError> for Failure {
+    fn from(e: PngmeError) -> Failure {
+        Failure(PngmeStatus::Failed, e.to_string())
+    }
+}
+
+fn invalid_argument(message: &str) -> Failure {
+    Failure(PngmeStatus::InvalidArgument, message.to_string())
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 在 `png` 中写入 `message`，新的 PNG 文件写入 `out`
+///
+/// # Safety
+///
+/// `png` 和 `message` 必须分别指向 `png_len`、`message_len` 个可读字节（长度为 0 时可以为空），
+/// `chunk_type` 必须是以 0 结尾的字符串，`out` 必须指向可写的 `PngmeBuffer`。
+#[no_mangle]
+pub unsafe extern "C" fn pngme_encode(
+    png: *const u8,
+    png_len: usize,
+    chunk_type: *const c_char,
+    message: *const u8,
+    message_len: usize,
+    out: *mut PngmeBuffer,
+) -> PngmeStatus {
+    call(out, || {
+        let png = bytes(png, png_len)?;
+        let chunk_type = string(chunk_type)?;
+        let message = bytes(message, message_len)?;
+        Ok(engine::encode_once(png, chunk_type, message, false, None)?)
+    })
+}
+
+/// 读取 `png` 中 `chunk_type` 类型的信息，写入 `out`
+///
+/// # Safety
+///
+/// `png` 必须指向 `png_len` 个可读字节，`chunk_type` 必须是以 0 结尾的字符串，
+/// `out` 必须指向可写的 `PngmeBuffer`。
+#[no_mangle]
+pub unsafe extern "C" fn pngme_decode(
+    png: *const u8,
+    png_len: usize,
+    chunk_type: *const c_char,
+    out: *mut PngmeBuffer,
+) -> PngmeStatus {
+    call(out, || {
+        let png = bytes(png, png_len)?;
+        let chunk_type = string(chunk_type)?;
+        Ok(engine::decode_once(png, chunk_type, None)?)
+    })
+}
+
+/// 释放 pngme 返回的缓冲区，`data` 为空时什么也不做
+///
+/// # Safety
+///
+/// `buffer` 必须是 `pngme_encode` 或 `pngme_decode` 写入的缓冲区，并且只能释放一次。
+#[no_mangle]
+pub unsafe extern "C" fn pngme_free(buffer: PngmeBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// 当前线程最近一次错误的描述，没有错误时返回空指针。
+/// 返回的字符串在当前线程下一次调用 pngme 函数之前有效
+#[no_mangle]
+pub extern "C" fn pngme_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// 运行 `f` 并把结果写入 `out`，错误和 panic 都转换为状态码
+unsafe fn call(out: *mut PngmeBuffer, f: impl FnOnce() -> Result<Vec<u8>, Failure>) -> PngmeStatus {
+    set_last_error(None);
+    if out.is_null() {
+        set_last_error(Some("out must not be null".to_string()));
+        return PngmeStatus::InvalidArgument;
+    }
+    out.write(PngmeBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    });
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(bytes)) => {
+            out.write(PngmeBuffer::from_vec(bytes));
+            PngmeStatus::Ok
+        }
+        Ok(Err(Failure(status, message))) => {
+            set_last_error(Some(message));
+            status
+        }
+        Err(_) => {
+            set_last_error(Some("internal error".to_string()));
+            PngmeStatus::Panic
+        }
+    }
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Failure> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(invalid_argument("null buffer"));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn string<'a>(s: *const c_char) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(invalid_argument("null string"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| invalid_argument("string is not valid UTF-8"))
+}
+
+fn set_last_error(message: Option<String>) {
+    // 错误信息中不会有 0，以防万一去掉它们
+    let message = message
+        .map(|message| CString::new(message.replace('\0', "")).expect("nul bytes were removed"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    fn empty() -> PngmeBuffer {
+        PngmeBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(pngme_last_error()) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let message = b"hello from C";
+        let mut encoded = empty();
+        let status = unsafe {
+            pngme_encode(
+                PNG_FILE.as_ptr(),
+                PNG_FILE.len(),
+                c"ruSt".as_ptr(),
+                message.as_ptr(),
+                message.len(),
+                &mut encoded,
+            )
+        };
+        assert_eq!(status, PngmeStatus::Ok);
+        assert!(pngme_last_error().is_null());
+
+        let mut decoded = empty();
+        let status =
+            unsafe { pngme_decode(encoded.data, encoded.len, c"ruSt".as_ptr(), &mut decoded) };
+        assert_eq!(status, PngmeStatus::Ok);
+        assert_eq!(
+            unsafe { slice::from_raw_parts(decoded.data, decoded.len) },
+            message
+        );
+        unsafe {
+            pngme_free(encoded);
+            pngme_free(decoded);
+            pngme_free(empty());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut out = empty();
+        let status = unsafe {
+            pngme_decode(
+                PNG_FILE.as_ptr(),
+                PNG_FILE.len(),
+                c"ruSt".as_ptr(),
+                &mut out,
+            )
+        };
+        assert_eq!(status, PngmeStatus::Failed);
+        assert!(out.data.is_null());
+        assert!(last_error().contains("ruSt"));
+
+        let status =
+            unsafe { pngme_decode(PNG_FILE.as_ptr(), PNG_FILE.len(), ptr::null(), &mut out) };
+        assert_eq!(status, PngmeStatus::InvalidArgument);
+
+        let status = unsafe {
+            pngme_decode(
+                PNG_FILE.as_ptr(),
+                PNG_FILE.len(),
+                c"ruSt".as_ptr(),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, PngmeStatus::InvalidArgument);
+    }
+}
@@ -0,0 +1,201 @@
+//! # Grep
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在所有 chunk 的数据中搜索字符串或正则表达式，报告匹配的 chunk 类型、偏移和上下文片段，
+//! 用于检查图片是否泄露了密钥、内部路径等敏感信息。
+//!
+//! zTXt 和压缩的 iTXt 中的文本是 zlib 压缩的，直接搜索原始数据找不到其中的内容；
+//! 指定 `decompress` 时改为搜索解压后的 `关键字\0文本`。
+
+use std::ops::Range;
+
+use regex::bytes::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::preview;
+use crate::text::{CompressedTextChunk, InternationalTextChunk};
+use crate::{PngmeError, Result};
+
+/// 搜索选项
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// 搜索 zTXt 和压缩的 iTXt 解压后的文本，而不是压缩后的数据
+    pub decompress: bool,
+    /// 片段中匹配前后各保留的字节数
+    pub context: usize,
+    /// 只搜索这些类型的 chunk，为空时搜索全部
+    pub chunk_types: Vec<String>,
+}
+
+/// 一处匹配
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    pub chunk_type: String,
+    /// chunk 在文件中的字节偏移（长度字段的位置）
+    pub offset: u64,
+    /// 匹配在被搜索内容中的起始位置：原始数据中的偏移，或解压后的 `关键字\0文本` 中的偏移
+    pub position: usize,
+    /// 是否在解压后的文本中匹配
+    pub decompressed: bool,
+    /// 匹配及其前后的内容，无效的 UTF-8 被替换，控制字符被转义
+    pub snippet: String,
+}
+
+/// 编译搜索模式。`regex` 为 false 时按字面匹配 `pattern`
+pub fn pattern(pattern: &str, regex: bool, ignore_case: bool) -> Result<Regex> {
+    let pattern = match regex {
+        true => pattern.to_string(),
+        false => regex::escape(pattern),
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| PngmeError::InvalidPattern(e.to_string()))
+}
+
+/// 在 `png` 的每个 chunk 中搜索 `pattern`，按 chunk 顺序返回所有不重叠的匹配。
+/// 偏移按 `Png::iter_with_offsets` 计算，对于用 `Png::from_bytes_unchecked` 解析的文件就是原文件中的位置
+pub fn grep(png: &Png, pattern: &Regex, options: &GrepOptions) -> Vec<GrepMatch> {
+    let mut matches = Vec::new();
+    for ((offset, chunk_ref), chunk) in png.iter_with_offsets().zip(png.chunks()) {
+        let chunk_type = chunk_ref.chunk_type().to_string();
+        if !options.chunk_types.is_empty() && !options.chunk_types.contains(&chunk_type) {
+            continue;
+        }
+        let decompressed = match options.decompress {
+            true => decompressed_text(chunk),
+            false => None,
+        };
+        let haystack = decompressed.as_deref().unwrap_or(chunk_ref.data());
+        for found in pattern.find_iter(haystack) {
+            matches.push(GrepMatch {
+                chunk_type: chunk_type.clone(),
+                offset,
+                position: found.start(),
+                decompressed: decompressed.is_some(),
+                snippet: snippet(haystack, found.range(), options.context),
+            });
+        }
+    }
+    matches
+}
+
+/// zTXt 和压缩的 iTXt 解压后的 `关键字\0文本`，其他 chunk 或无法解析时返回 `None`
+fn decompressed_text(chunk: &Chunk) -> Option<Vec<u8>> {
+    let limits = Limits::default();
+    let (keyword, text) = match &chunk.chunk_type().bytes() {
+        b"zTXt" => CompressedTextChunk::from_chunk_with_limits(chunk, &limits)
+            .map(|t| (t.keyword, t.text))
+            .ok()?,
+        b"iTXt" => {
+            let text = InternationalTextChunk::from_chunk_lossy(chunk, &limits).ok()?;
+            if !text.compressed {
+                return None;
+            }
+            (text.keyword, text.text)
+        }
+        _ => return None,
+    };
+    let mut bytes = keyword.into_bytes();
+    bytes.push(0);
+    bytes.extend_from_slice(text.as_bytes());
+    Some(bytes)
+}
+
+/// 取出 `range` 及其前后各 `context` 字节
+fn snippet(haystack: &[u8], range: Range<usize>, context: usize) -> String {
+    let start = range.start.saturating_sub(context);
+    let end = range.end.saturating_add(context).min(haystack.len());
+    preview::escape(&String::from_utf8_lossy(&haystack[start..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+
+    fn png_with_text() -> Png {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_text("Comment", "token=sk-12345 leaked").unwrap();
+        png.insert_chunk(
+            InternationalTextChunk::new("Note", "internal path /home/build/secret")
+                .unwrap()
+                .with_compression(true)
+                .to_chunk()
+                .unwrap(),
+            crate::ordering::ChunkPosition::BeforeIend,
+        );
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"Token".to_vec()).unwrap(),
+        );
+        png
+    }
+
+    #[test]
+    fn test_literal() {
+        let png = png_with_text();
+        let options = GrepOptions {
+            context: 4,
+            ..GrepOptions::default()
+        };
+        let matches = grep(&png, &pattern("token", false, false).unwrap(), &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].chunk_type, "tEXt");
+        assert_eq!(matches[0].position, 8);
+        assert_eq!(matches[0].snippet, "ent\\x00token=sk-");
+        assert!(!matches[0].decompressed);
+
+        // 偏移是 chunk 在文件中的位置
+        let bytes = png.as_bytes();
+        let offset = matches[0].offset as usize;
+        assert_eq!(&bytes[offset + 4..offset + 8], b"tEXt");
+
+        let matches = grep(&png, &pattern("TOKEN", false, true).unwrap(), &options);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].chunk_type, "ruSt");
+    }
+
+    #[test]
+    fn test_regex_and_types() {
+        let png = png_with_text();
+        let options = GrepOptions {
+            chunk_types: vec!["tEXt".to_string()],
+            ..GrepOptions::default()
+        };
+        let matches = grep(&png, &pattern(r"sk-\d+", true, false).unwrap(), &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].snippet, "sk-12345");
+
+        // 字面模式中的元字符不被解释
+        assert!(grep(&png, &pattern(r"sk-\d+", false, false).unwrap(), &options).is_empty());
+        assert!(matches!(
+            pattern("(", true, false),
+            Err(PngmeError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_decompress() {
+        let png = png_with_text();
+        let secret = pattern("secret", false, false).unwrap();
+        assert!(grep(&png, &secret, &GrepOptions::default()).is_empty());
+
+        let options = GrepOptions {
+            decompress: true,
+            ..GrepOptions::default()
+        };
+        let matches = grep(&png, &secret, &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].chunk_type, "iTXt");
+        assert!(matches[0].decompressed);
+        assert_eq!(
+            matches[0].position,
+            "Note\0internal path /home/build/".len()
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! # Ihdr
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块包含了 `Ihdr` 结构体的实现，用于解析和校验 IHDR chunk：
+//!
+//! ```text
+//! | 宽 (u32) | 高 (u32) | 位深 | 颜色类型 | 压缩方法 | 过滤方法 | 隔行扫描方法 |
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::interlace;
+use crate::{Error, PngmeError, Result};
+
+/// 颜色类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    Grayscale = 0,
+    Rgb = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    Rgba = 6,
+}
+
+/// 隔行扫描方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interlace {
+    None = 0,
+    Adam7 = 1,
+}
+
+/// 解析后的 IHDR chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    /// 压缩方法，规范中只定义了 0（deflate）
+    pub compression_method: u8,
+    /// 过滤方法，规范中只定义了 0（自适应过滤）
+    pub filter_method: u8,
+    pub interlace: Interlace,
+}
+
+impl ColorType {
+    /// 每个像素的样本数
+    pub fn channels(&self) -> u8 {
+        match self {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    /// 该颜色类型允许的位深
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            ColorType::Grayscale => &[1, 2, 4, 8, 16],
+            ColorType::Indexed => &[1, 2, 4, 8],
+            ColorType::Rgb | ColorType::GrayscaleAlpha | ColorType::Rgba => &[8, 16],
+        }
+    }
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Indexed),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::Rgba),
+            _ => Err(invalid(format!("unknown color type {}", value))),
+        }
+    }
+}
+
+impl fmt::Display for ColorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorType::Grayscale => "grayscale",
+            ColorType::Rgb => "RGB",
+            ColorType::Indexed => "indexed",
+            ColorType::GrayscaleAlpha => "grayscale+alpha",
+            ColorType::Rgba => "RGBA",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Ihdr {
+    /// IHDR 数据的固定长度
+    pub const LENGTH: usize = 13;
+
+    /// 创建 IHDR，会校验各字段
+    pub fn new(width: u32, height: u32, bit_depth: u8, color_type: ColorType) -> Result<Ihdr> {
+        let ihdr = Ihdr {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace: Interlace::None,
+        };
+        ihdr.validate()?;
+        Ok(ihdr)
+    }
+
+    /// 校验尺寸、位深与颜色类型的组合，以及压缩和过滤方法
+    pub fn validate(&self) -> Result<()> {
+        const MAX_DIMENSION: u32 = i32::MAX as u32;
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid("width and height must be non-zero".to_string()));
+        }
+        if self.width > MAX_DIMENSION || self.height > MAX_DIMENSION {
+            return Err(invalid(
+                "width and height must not exceed 2^31 - 1".to_string(),
+            ));
+        }
+        if !self
+            .color_type
+            .allowed_bit_depths()
+            .contains(&self.bit_depth)
+        {
+            return Err(invalid(format!(
+                "bit depth {} is not allowed for {} images",
+                self.bit_depth, self.color_type
+            )));
+        }
+        if self.compression_method != 0 {
+            return Err(invalid(format!(
+                "unknown compression method {}",
+                self.compression_method
+            )));
+        }
+        if self.filter_method != 0 {
+            return Err(invalid(format!(
+                "unknown filter method {}",
+                self.filter_method
+            )));
+        }
+        Ok(())
+    }
+
+    /// 每个像素占用的位数
+    pub fn bits_per_pixel(&self) -> u32 {
+        self.color_type.channels() as u32 * self.bit_depth as u32
+    }
+
+    /// 每行未过滤的像素数据字节数（不含过滤类型字节）
+    pub fn row_bytes(&self) -> u64 {
+        (self.width as u64 * self.bits_per_pixel() as u64).div_ceil(8)
+    }
+
+    /// IDAT 解压后的字节数：每行像素数据加一个过滤类型字节，Adam7 隔行扫描时为 7 个子图之和
+    pub fn raw_data_len(&self) -> u64 {
+        interlace::passes(self)
+            .iter()
+            .map(|pass| pass.raw_len(self.bits_per_pixel()))
+            .sum()
+    }
+
+    /// 编码为 13 字节的 IHDR 数据
+    pub fn to_bytes(self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+        bytes[..4].copy_from_slice(&self.width.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.height.to_be_bytes());
+        bytes[8] = self.bit_depth;
+        bytes[9] = self.color_type as u8;
+        bytes[10] = self.compression_method;
+        bytes[11] = self.filter_method;
+        bytes[12] = self.interlace as u8;
+        bytes
+    }
+
+    /// 编码为 IHDR chunk
+    pub fn to_chunk(self) -> Chunk {
+        let chunk_type = ChunkType::from_str("IHDR").expect("IHDR is a valid chunk type");
+        Chunk::new(chunk_type, self.to_bytes().to_vec()).expect("IHDR data is 13 bytes")
+    }
+}
+
+impl TryFrom<&[u8]> for Ihdr {
+    type Error = Error;
+
+    /// 从 13 字节的 IHDR 数据解析
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        let bytes: [u8; Ihdr::LENGTH] = bytes.try_into().map_err(|_| {
+            invalid(format!(
+                "IHDR must be {} bytes, found {}",
+                Ihdr::LENGTH,
+                bytes.len()
+            ))
+        })?;
+        let interlace = match bytes[12] {
+            0 => Interlace::None,
+            1 => Interlace::Adam7,
+            method => return Err(invalid(format!("unknown interlace method {}", method))),
+        };
+        let ihdr = Ihdr {
+            width: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            height: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            bit_depth: bytes[8],
+            color_type: ColorType::try_from(bytes[9])?,
+            compression_method: bytes[10],
+            filter_method: bytes[11],
+            interlace,
+        };
+        ihdr.validate()?;
+        Ok(ihdr)
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        if &chunk.chunk_type().bytes() != b"IHDR" {
+            return Err(invalid(format!(
+                "expected an IHDR chunk, found {}",
+                chunk.chunk_type()
+            )));
+        }
+        Ihdr::try_from(chunk.data())
+    }
+}
+
+impl fmt::Display for Ihdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {}-bit {}",
+            self.width, self.height, self.bit_depth, self.color_type
+        )?;
+        if self.interlace == Interlace::Adam7 {
+            write!(f, ", interlaced")?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidHeader(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const HEADER: [u8; 13] = [
+        0, 0, 1, 0,     // width: 256
+        0, 0, 0, 128,   // height: 128
+        8,              // bit depth
+        6,              // color type: RGBA
+        0, 0, 1,        // compression, filter, interlace
+    ];
+
+    #[test]
+    fn test_parse_header() {
+        let ihdr = Ihdr::try_from(&HEADER[..]).unwrap();
+        assert_eq!(ihdr.width, 256);
+        assert_eq!(ihdr.height, 128);
+        assert_eq!(ihdr.bit_depth, 8);
+        assert_eq!(ihdr.color_type, ColorType::Rgba);
+        assert_eq!(ihdr.interlace, Interlace::Adam7);
+        assert_eq!(ihdr.bits_per_pixel(), 32);
+        assert_eq!(ihdr.row_bytes(), 1024);
+        assert_eq!(ihdr.to_string(), "256x128 8-bit RGBA, interlaced");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ihdr = Ihdr::try_from(&HEADER[..]).unwrap();
+        assert_eq!(ihdr.to_bytes(), HEADER);
+
+        let chunk = ihdr.to_chunk();
+        assert_eq!(Ihdr::try_from(&chunk).unwrap(), ihdr);
+    }
+
+    #[test]
+    fn test_row_bytes_rounds_up() {
+        let ihdr = Ihdr::new(3, 1, 1, ColorType::Grayscale).unwrap();
+        assert_eq!(ihdr.row_bytes(), 1);
+        let ihdr = Ihdr::new(3, 1, 16, ColorType::Rgb).unwrap();
+        assert_eq!(ihdr.row_bytes(), 18);
+    }
+
+    #[test]
+    fn test_raw_data_len() {
+        let mut ihdr = Ihdr::new(8, 8, 8, ColorType::Grayscale).unwrap();
+        assert_eq!(ihdr.raw_data_len(), 8 * 9);
+        // 7 个子图分别为 1x1、1x1、2x1、2x2、4x2、4x4、8x4
+        ihdr.interlace = Interlace::Adam7;
+        assert_eq!(ihdr.raw_data_len(), 2 + 2 + 3 + 6 + 10 + 20 + 36);
+        // 1x1 的图像只有第一个子图
+        let mut ihdr = Ihdr::new(1, 1, 16, ColorType::Rgba).unwrap();
+        ihdr.interlace = Interlace::Adam7;
+        assert_eq!(ihdr.raw_data_len(), 9);
+    }
+
+    #[test]
+    fn test_invalid_bit_depth_combinations() {
+        assert!(Ihdr::new(1, 1, 4, ColorType::Rgb).is_err());
+        assert!(Ihdr::new(1, 1, 16, ColorType::Indexed).is_err());
+        assert!(Ihdr::new(1, 1, 2, ColorType::Rgba).is_err());
+        assert!(Ihdr::new(1, 1, 3, ColorType::Grayscale).is_err());
+        assert!(Ihdr::new(1, 1, 1, ColorType::Grayscale).is_ok());
+        assert!(Ihdr::new(1, 1, 16, ColorType::GrayscaleAlpha).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        let with = |index: usize, value: u8| {
+            let mut bytes = HEADER;
+            bytes[index] = value;
+            Ihdr::try_from(&bytes[..])
+        };
+        assert!(matches!(with(9, 5), Err(PngmeError::InvalidHeader(_))));
+        assert!(with(10, 1).is_err());
+        assert!(with(11, 1).is_err());
+        assert!(with(12, 2).is_err());
+        assert!(with(0, 0x80).is_err());
+        assert!(Ihdr::try_from(&HEADER[..12]).is_err());
+        assert!(Ihdr::new(0, 1, 8, ColorType::Rgb).is_err());
+    }
+
+    #[test]
+    fn test_wrong_chunk_type() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), HEADER.to_vec()).unwrap();
+        assert!(Ihdr::try_from(&chunk).is_err());
+    }
+}
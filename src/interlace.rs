@@ -0,0 +1,420 @@
+//! # Interlace
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块处理 IDAT 解压后的原始图像数据，即每行以过滤类型字节开头的扫描行：
+//!
+//! - `passes` 按 IHDR 把数据划分为子图：逐行扫描时只有一个，Adam7 隔行扫描时有 7 个，
+//!   宽或高为 0 的子图不占数据；
+//! - `unfilter` 撤销每行的过滤（None、Sub、Up、Average、Paeth），每个子图单独过滤，
+//!   第一行的上一行视为全 0；`filter` 反过来用 None 过滤重新生成扫描行，
+//!   `filter_adaptive` 为每行挑选残差最小的过滤类型；
+//! - `deinterlace` 和 `interlace` 在子图和完整图像之间重组像素，位深小于 8 时按位处理。
+//!
+//! 需要逐像素处理的功能（例如在像素中隐藏信息）必须先经过这里，否则会把隔行图片的子图
+//! 当作连续的行。`Png::deinterlace` 用这些函数把隔行图片转换为逐行图片，`Png::optimize` 用它们重新过滤和降低位深。
+
+use crate::ihdr::{ColorType, Ihdr, Interlace};
+use crate::{PngmeError, Result};
+
+/// Adam7 的 7 个子图：起始列、起始行、列间隔、行间隔
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// 一个子图：完整图像中从 (`x`, `y`) 开始、每隔 `dx` 列和 `dy` 行取一个像素组成的图像
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pass {
+    pub x: u32,
+    pub y: u32,
+    pub dx: u32,
+    pub dy: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Pass {
+    /// 子图是否不含像素（不占任何数据，连过滤类型字节也没有）
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// 每行像素数据的字节数（不含过滤类型字节）
+    pub fn row_bytes(&self, bits_per_pixel: u32) -> usize {
+        (self.width as u64 * bits_per_pixel as u64).div_ceil(8) as usize
+    }
+
+    /// 子图带过滤类型字节的原始数据长度
+    pub fn raw_len(&self, bits_per_pixel: u32) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.height as u64 * (1 + self.row_bytes(bits_per_pixel) as u64)
+    }
+}
+
+/// IHDR 描述的图像由哪些子图组成，按数据中的顺序排列
+pub fn passes(header: &Ihdr) -> Vec<Pass> {
+    let full = [(0, 0, 1, 1)];
+    let layout: &[(u32, u32, u32, u32)] = match header.interlace {
+        Interlace::None => &full,
+        Interlace::Adam7 => &ADAM7,
+    };
+    layout
+        .iter()
+        .map(|&(x, y, dx, dy)| Pass {
+            x,
+            y,
+            dx,
+            dy,
+            width: header.width.saturating_sub(x).div_ceil(dx),
+            height: header.height.saturating_sub(y).div_ceil(dy),
+        })
+        .collect()
+}
+
+/// 撤销过滤，返回每个子图的像素数据（各行首尾相接，不含过滤类型字节）
+pub fn unfilter(header: &Ihdr, raw: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let bits = header.bits_per_pixel();
+    // 过滤以字节为单位比较“左边的像素”，不足一个字节的像素按一个字节计
+    let left = (bits as usize).div_ceil(8);
+    let mut rest = raw;
+    let mut result = Vec::new();
+    for pass in passes(header) {
+        let row_bytes = pass.row_bytes(bits);
+        let mut pixels = Vec::with_capacity(pass.height as usize * row_bytes);
+        let mut previous = vec![0u8; row_bytes];
+        for _ in 0..if pass.is_empty() { 0 } else { pass.height } {
+            if rest.len() < 1 + row_bytes {
+                return Err(invalid(format!(
+                    "image data ends early: expected {} bytes",
+                    header.raw_data_len()
+                )));
+            }
+            let (line, tail) = rest.split_at(1 + row_bytes);
+            rest = tail;
+            let mut row = line[1..].to_vec();
+            unfilter_row(line[0], &mut row, &previous, left)?;
+            pixels.extend_from_slice(&row);
+            previous = row;
+        }
+        result.push(pixels);
+    }
+    if !rest.is_empty() {
+        return Err(invalid(format!(
+            "{} bytes of extra image data after the last scanline",
+            rest.len()
+        )));
+    }
+    Ok(result)
+}
+
+fn unfilter_row(filter: u8, row: &mut [u8], previous: &[u8], left: usize) -> Result<()> {
+    for i in 0..row.len() {
+        let a = if i >= left { row[i - left] } else { 0 };
+        let b = previous[i];
+        let c = if i >= left { previous[i - left] } else { 0 };
+        let predictor = predict(filter, a, b, c)
+            .ok_or_else(|| invalid(format!("unknown filter type {}", filter)))?;
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+/// 过滤类型 `filter` 根据左边 `a`、上边 `b`、左上 `c` 三个字节给出的预测值
+fn predict(filter: u8, a: u8, b: u8, c: u8) -> Option<u8> {
+    Some(match filter {
+        0 => 0,
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        4 => paeth(a, b, c),
+        _ => return None,
+    })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// 用 None 过滤把各子图的像素数据重新组成原始图像数据，是 `unfilter` 的逆操作
+pub fn filter(header: &Ihdr, pixels: &[Vec<u8>]) -> Vec<u8> {
+    let bits = header.bits_per_pixel();
+    let mut raw = Vec::with_capacity(header.raw_data_len() as usize);
+    for (pass, data) in passes(header).iter().zip(pixels) {
+        let row_bytes = pass.row_bytes(bits);
+        if pass.is_empty() || row_bytes == 0 {
+            continue;
+        }
+        for row in data.chunks(row_bytes) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+    }
+    raw
+}
+
+/// 与 `filter` 相同，但每行从 5 种过滤类型中选出残差绝对值之和最小的一种，
+/// 通常能压缩得更小。调色板和位深小于 8 的图片按规范建议仍然使用 None 过滤
+pub fn filter_adaptive(header: &Ihdr, pixels: &[Vec<u8>]) -> Vec<u8> {
+    if header.color_type == ColorType::Indexed || header.bit_depth < 8 {
+        return filter(header, pixels);
+    }
+    let bits = header.bits_per_pixel();
+    let left = (bits as usize).div_ceil(8);
+    let mut raw = Vec::with_capacity(header.raw_data_len() as usize);
+    for (pass, data) in passes(header).iter().zip(pixels) {
+        let row_bytes = pass.row_bytes(bits);
+        if pass.is_empty() || row_bytes == 0 {
+            continue;
+        }
+        let mut previous = vec![0u8; row_bytes];
+        for row in data.chunks(row_bytes) {
+            let best = (0..5)
+                .map(|filter| filter_row(filter, row, &previous, left))
+                .min_by_key(|line| {
+                    line[1..]
+                        .iter()
+                        .map(|&b| (b as i8).unsigned_abs() as u64)
+                        .sum::<u64>()
+                })
+                .unwrap_or_default();
+            raw.extend_from_slice(&best);
+            previous = row.to_vec();
+        }
+    }
+    raw
+}
+
+/// 用过滤类型 `filter` 过滤一行，返回带过滤类型字节的扫描行
+fn filter_row(filter: u8, row: &[u8], previous: &[u8], left: usize) -> Vec<u8> {
+    let mut line = Vec::with_capacity(1 + row.len());
+    line.push(filter);
+    for i in 0..row.len() {
+        let a = if i >= left { row[i - left] } else { 0 };
+        let c = if i >= left { previous[i - left] } else { 0 };
+        let predictor = predict(filter, a, previous[i], c).unwrap_or(0);
+        line.push(row[i].wrapping_sub(predictor));
+    }
+    line
+}
+
+/// 把各子图的像素数据重组为完整图像（各行首尾相接，每行 `header.row_bytes()` 字节）
+pub fn deinterlace(header: &Ihdr, pixels: &[Vec<u8>]) -> Vec<u8> {
+    let bits = header.bits_per_pixel();
+    let image_row = header.row_bytes() as usize;
+    let mut image = vec![0u8; image_row * header.height as usize];
+    for (pass, data) in passes(header).iter().zip(pixels) {
+        let row_bytes = pass.row_bytes(bits);
+        for row in 0..pass.height as usize {
+            for col in 0..pass.width as usize {
+                let y = pass.y as usize + row * pass.dy as usize;
+                let x = pass.x as usize + col * pass.dx as usize;
+                copy_pixel(
+                    &data[row * row_bytes..],
+                    col,
+                    &mut image[y * image_row..],
+                    x,
+                    bits,
+                );
+            }
+        }
+    }
+    image
+}
+
+/// 把完整图像拆分为各子图的像素数据，是 `deinterlace` 的逆操作
+pub fn interlace(header: &Ihdr, image: &[u8]) -> Vec<Vec<u8>> {
+    let bits = header.bits_per_pixel();
+    let image_row = header.row_bytes() as usize;
+    passes(header)
+        .iter()
+        .map(|pass| {
+            let row_bytes = pass.row_bytes(bits);
+            let mut data = vec![0u8; row_bytes * pass.height as usize];
+            for row in 0..pass.height as usize {
+                for col in 0..pass.width as usize {
+                    let y = pass.y as usize + row * pass.dy as usize;
+                    let x = pass.x as usize + col * pass.dx as usize;
+                    copy_pixel(
+                        &image[y * image_row..],
+                        x,
+                        &mut data[row * row_bytes..],
+                        col,
+                        bits,
+                    );
+                }
+            }
+            data
+        })
+        .collect()
+}
+
+/// 把 `from` 行中第 `from_x` 个像素复制到 `to` 行中第 `to_x` 个像素
+fn copy_pixel(from: &[u8], from_x: usize, to: &mut [u8], to_x: usize, bits: u32) {
+    let bits = bits as usize;
+    if bits.is_multiple_of(8) {
+        let len = bits / 8;
+        to[to_x * len..(to_x + 1) * len].copy_from_slice(&from[from_x * len..(from_x + 1) * len]);
+        return;
+    }
+    // 位深小于 8 时一个字节里有多个像素，高位在前
+    let mask = (1u8 << bits) - 1;
+    let from_shift = 8 - bits - (from_x * bits) % 8;
+    let value = (from[from_x * bits / 8] >> from_shift) & mask;
+    let to_shift = 8 - bits - (to_x * bits) % 8;
+    let byte = &mut to[to_x * bits / 8];
+    *byte = (*byte & !(mask << to_shift)) | (value << to_shift);
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidImageData(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: u32, height: u32, bit_depth: u8, interlace: Interlace) -> Ihdr {
+        let mut header = Ihdr::new(width, height, bit_depth, ColorType::Grayscale).unwrap();
+        header.interlace = interlace;
+        header
+    }
+
+    /// 每个像素的值是它在图像中的序号（对位深取模）
+    fn numbered_image(header: &Ihdr) -> Vec<u8> {
+        let bits = header.bit_depth as usize;
+        let row_bytes = header.row_bytes() as usize;
+        let mut image = vec![0u8; row_bytes * header.height as usize];
+        for y in 0..header.height as usize {
+            for x in 0..header.width as usize {
+                let value = ((y * header.width as usize + x) % (1 << bits.min(8))) as u8;
+                // 作为第 0 个像素放在行首：位深小于 8 时在高位，16 位时两个字节相同
+                let row = if bits < 8 {
+                    [value << (8 - bits), 0]
+                } else {
+                    [value, value]
+                };
+                copy_pixel(&row, 0, &mut image[y * row_bytes..], x, bits as u32);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_passes() {
+        let passes = passes(&header(8, 8, 8, Interlace::Adam7));
+        let sizes: Vec<(u32, u32)> = passes.iter().map(|p| (p.width, p.height)).collect();
+        assert_eq!(
+            sizes,
+            [(1, 1), (1, 1), (2, 1), (2, 2), (4, 2), (4, 4), (8, 4)]
+        );
+
+        // 1x1 的图像只有第一个子图有像素
+        let tiny = super::passes(&header(1, 1, 8, Interlace::Adam7));
+        assert_eq!(tiny.iter().filter(|pass| !pass.is_empty()).count(), 1);
+
+        for (width, height) in [(1, 1), (3, 5), (8, 8), (13, 7)] {
+            for bit_depth in [1, 4, 8, 16] {
+                let header = header(width, height, bit_depth, Interlace::Adam7);
+                let total: u64 = super::passes(&header)
+                    .iter()
+                    .map(|pass| pass.raw_len(header.bits_per_pixel()))
+                    .sum();
+                assert_eq!(total, header.raw_data_len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_interlace_round_trip() {
+        for (width, height) in [(1, 1), (3, 5), (9, 9), (13, 7)] {
+            for bit_depth in [1, 2, 4, 8, 16] {
+                let header = header(width, height, bit_depth, Interlace::Adam7);
+                let image = numbered_image(&header);
+                let pixels = interlace(&header, &image);
+                assert_eq!(deinterlace(&header, &pixels), image);
+
+                let raw = filter(&header, &pixels);
+                assert_eq!(raw.len() as u64, header.raw_data_len());
+                assert_eq!(unfilter(&header, &raw).unwrap(), pixels);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adam7_pixel_order() {
+        // 8x8 图像中第一个子图只有左上角的像素，第二个子图是第 4 列的像素
+        let header = header(8, 8, 8, Interlace::Adam7);
+        let image: Vec<u8> = (0..64).collect();
+        let pixels = interlace(&header, &image);
+        assert_eq!(pixels[0], [0]);
+        assert_eq!(pixels[1], [4]);
+        assert_eq!(pixels[2], [32, 36]);
+        assert_eq!(pixels[6].len(), 32);
+        assert_eq!(&pixels[6][..8], &image[8..16]);
+    }
+
+    #[test]
+    fn test_unfilter() {
+        // 2x2 的 8 位灰度图像：第一行 Sub 过滤，第二行 Up 过滤
+        let header = header(2, 2, 8, Interlace::None);
+        let raw = [1, 10, 5, 2, 1, 1];
+        assert_eq!(unfilter(&header, &raw).unwrap(), [vec![10, 15, 11, 16]]);
+
+        // Average 和 Paeth
+        let raw = [3, 10, 5, 4, 1, 1];
+        assert_eq!(unfilter(&header, &raw).unwrap(), [vec![10, 10, 11, 12]]);
+
+        assert!(matches!(
+            unfilter(&header, &[5, 0, 0, 0, 0, 0]),
+            Err(PngmeError::InvalidImageData(_))
+        ));
+        assert!(unfilter(&header, &raw[..5]).is_err());
+        assert!(unfilter(&header, &[0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_filter_adaptive() {
+        for (width, height, interlace) in [(13, 7, Interlace::None), (9, 9, Interlace::Adam7)] {
+            for bit_depth in [8, 16] {
+                let header = header(width, height, bit_depth, interlace);
+                let pixels = super::interlace(&header, &numbered_image(&header));
+                let raw = filter_adaptive(&header, &pixels);
+                assert_eq!(raw.len() as u64, header.raw_data_len());
+                assert_eq!(unfilter(&header, &raw).unwrap(), pixels);
+            }
+        }
+
+        // 序号递增的行用 Sub 过滤后残差最小
+        let gray = header(4, 1, 8, Interlace::None);
+        assert_eq!(
+            filter_adaptive(&gray, &[vec![10, 20, 30, 40]]),
+            [1, 10, 10, 10, 10]
+        );
+        // 位深小于 8 时总是 None 过滤
+        let bits = header(8, 1, 1, Interlace::None);
+        assert_eq!(
+            filter_adaptive(&bits, &[vec![0b0101_0101]]),
+            [0, 0b0101_0101]
+        );
+    }
+}
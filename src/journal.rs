@@ -0,0 +1,393 @@
+//! # Journal
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块为批处理中的原地修改记录日志，崩溃或断电后可以用 `pngme recover` 完成或回滚中断的操作。
+//!
+//! 日志位于批处理目录下的 `.pngme-journal/`。覆盖一个文件时，`JournalFs` 依次：
+//!
+//! 1. 把新内容写入日志目录中的临时文件；
+//! 2. 在 `journal.json` 中登记意图：目标路径、原内容和新内容的 SHA-256、临时文件；
+//! 3. 覆盖目标文件；
+//! 4. 删除临时文件，再从 `journal.json` 中删除这条记录。
+//!
+//! `journal.json` 只保存尚未完成的操作，每次都原子地整体重写，所以它最多只有 `--jobs` 条记录。
+//! 恢复时按目标文件的当前内容判断：已是新内容的操作已经完成；临时文件完整时用它完成操作，
+//! 即使目标文件（例如 `--preserve-hardlinks` 原地覆盖时）只写了一半；仍是原内容时视为回滚。
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::progress::Progress;
+use crate::vfs::{FileAttributes, Preserve, PreserveHardlinks, Vfs};
+use crate::{PngmeError, Result};
+
+/// 日志目录的名字，批处理遍历时跳过它
+pub const JOURNAL_DIR: &str = ".pngme-journal";
+
+/// 日志目录中记录未完成操作的文件
+const JOURNAL_FILE: &str = "journal.json";
+
+/// 一次尚未完成的覆盖操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 被覆盖的文件
+    pub path: PathBuf,
+    /// 原内容的 SHA-256；文件原本不存在时为 `None`
+    pub original: Option<String>,
+    /// 新内容的 SHA-256
+    pub new: String,
+    /// 保存新内容的临时文件
+    pub temp: PathBuf,
+}
+
+/// `recover` 对一条记录的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// 目标文件已经是新内容
+    AlreadyApplied,
+    /// 用临时文件完成了覆盖
+    Completed,
+    /// 目标文件仍是原内容，丢弃了临时文件
+    RolledBack,
+    /// 目标文件既不是原内容也不是新内容，临时文件也不完整；记录保留在日志中
+    Unrecoverable,
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Recovery::AlreadyApplied => "already applied",
+            Recovery::Completed => "completed",
+            Recovery::RolledBack => "rolled back",
+            Recovery::Unrecoverable => "unrecoverable, neither the original nor the new content",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// 一个批处理目录的日志
+pub struct Journal<'a> {
+    fs: &'a dyn Vfs,
+    dir: PathBuf,
+    entries: Mutex<Vec<JournalEntry>>,
+    next_temp: AtomicU64,
+}
+
+impl<'a> Journal<'a> {
+    /// 为批处理目录 `dir` 打开日志。上次的操作没有完成时返回错误，提示先运行 `pngme recover`
+    pub fn open(fs: &'a dyn Vfs, dir: &Path) -> Result<Self> {
+        let journal = Journal {
+            fs,
+            dir: fs.canonicalize(dir).join(JOURNAL_DIR),
+            entries: Mutex::new(Vec::new()),
+            next_temp: AtomicU64::new(0),
+        };
+        if fs.exists(&journal.file()) {
+            return Err(PngmeError::Batch(format!(
+                "{} records unfinished operations, run `pngme recover {}` first",
+                journal.file().display(),
+                dir.display()
+            )));
+        }
+        Ok(journal)
+    }
+
+    /// 日志目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn file(&self) -> PathBuf {
+        self.dir.join(JOURNAL_FILE)
+    }
+
+    /// 读取日志中未完成的操作
+    fn load(fs: &dyn Vfs, dir: &Path) -> Result<Vec<JournalEntry>> {
+        let file = dir.join(JOURNAL_DIR).join(JOURNAL_FILE);
+        if !fs.exists(&file) {
+            return Ok(Vec::new());
+        }
+        serde_json::from_slice(&fs.read(&file)?)
+            .map_err(|e| PngmeError::Batch(format!("{} is corrupted: {}", file.display(), e)))
+    }
+
+    /// 整体重写日志；没有未完成的操作时删除日志
+    fn save(fs: &dyn Vfs, journal_dir: &Path, entries: &[JournalEntry]) -> Result<()> {
+        let file = journal_dir.join(JOURNAL_FILE);
+        if entries.is_empty() {
+            if fs.exists(&file) {
+                fs.remove(&file)?;
+            }
+            return Ok(());
+        }
+        let json = serde_json::to_vec_pretty(entries).map_err(|e| PngmeError::Io(e.into()))?;
+        fs.create_dir_all(journal_dir)?;
+        fs.write(&file, &json)?;
+        Ok(())
+    }
+
+    /// 按日志完成或回滚中断的操作，返回每条记录的处理结果。无法恢复的记录保留在日志中
+    pub fn recover(fs: &dyn Vfs, dir: &Path) -> Result<Vec<(JournalEntry, Recovery)>> {
+        let journal_dir = fs.canonicalize(dir).join(JOURNAL_DIR);
+        let entries = Journal::load(fs, dir)?;
+        let mut results = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let recovery = recover_entry(fs, &entry)?;
+            if recovery != Recovery::Unrecoverable && fs.exists(&entry.temp) {
+                fs.remove(&entry.temp)?;
+            }
+            results.push((entry, recovery));
+        }
+        let remaining: Vec<JournalEntry> = results
+            .iter()
+            .filter(|(_, recovery)| *recovery == Recovery::Unrecoverable)
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        Journal::save(fs, &journal_dir, &remaining)?;
+        remove_empty_dir(fs, &journal_dir);
+        Ok(results)
+    }
+
+    /// 批处理结束后删除空的日志目录。运行期间保留它，其他线程可能正要在其中写临时文件
+    pub fn close(self) {
+        remove_empty_dir(self.fs, &self.dir);
+    }
+
+    /// 用日志保护对 `path` 的覆盖
+    fn write(&self, inner: &dyn Vfs, path: &Path, data: &[u8]) -> Result<()> {
+        let original = match inner.exists(path) {
+            true => Some(sha256(&inner.read(path)?)),
+            false => None,
+        };
+        let temp = self.dir.join(format!(
+            "{}.tmp",
+            self.next_temp.fetch_add(1, Ordering::Relaxed)
+        ));
+        let entry = JournalEntry {
+            path: inner.canonicalize(path),
+            original,
+            new: sha256(data),
+            temp,
+        };
+
+        inner.create_dir_all(&self.dir)?;
+        inner.write(&entry.temp, data)?;
+        self.update(|entries| entries.push(entry.clone()))?;
+        inner.write(path, data)?;
+        // 目标文件已是新内容，此后崩溃时恢复会判定为已完成，临时文件可以先删除
+        inner.remove(&entry.temp)?;
+        self.update(|entries| entries.retain(|pending| pending != &entry))
+    }
+
+    /// 修改未完成的操作并立即写回日志；持有锁写入，多个线程的修改不会互相覆盖
+    fn update(&self, change: impl FnOnce(&mut Vec<JournalEntry>)) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        change(&mut entries);
+        Journal::save(self.fs, &self.dir, &entries)
+    }
+}
+
+/// 删除日志目录；目录中还有别的文件（例如无法恢复的临时文件）时保留
+fn remove_empty_dir(fs: &dyn Vfs, journal_dir: &Path) {
+    if fs.is_dir(journal_dir) {
+        let _ = fs.remove(journal_dir);
+    }
+}
+
+/// 根据目标文件和临时文件的当前内容判断如何恢复
+fn recover_entry(fs: &dyn Vfs, entry: &JournalEntry) -> Result<Recovery> {
+    let current = match fs.exists(&entry.path) {
+        true => Some(sha256(&fs.read(&entry.path)?)),
+        false => None,
+    };
+    if current.as_deref() == Some(entry.new.as_str()) {
+        return Ok(Recovery::AlreadyApplied);
+    }
+    if fs.exists(&entry.temp) {
+        let staged = fs.read(&entry.temp)?;
+        if sha256(&staged) == entry.new {
+            // 与批处理一样，有多个硬链接的文件原地覆盖，不断开链接
+            PreserveHardlinks(fs).write(&entry.path, &staged)?;
+            return Ok(Recovery::Completed);
+        }
+    }
+    if current == entry.original {
+        return Ok(Recovery::RolledBack);
+    }
+    Ok(Recovery::Unrecoverable)
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 包装另一个文件系统，每次覆盖文件都记入 `journal`
+pub struct JournalFs<'a> {
+    inner: &'a dyn Vfs,
+    journal: &'a Journal<'a>,
+}
+
+impl<'a> JournalFs<'a> {
+    pub fn new(inner: &'a dyn Vfs, journal: &'a Journal<'a>) -> Self {
+        JournalFs { inner, journal }
+    }
+}
+
+impl Vfs for JournalFs<'_> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read + Send>> {
+        self.inner.open(path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.journal
+            .write(self.inner, path, data)
+            .map_err(|e| match e {
+                PngmeError::Io(e) => e,
+                e => io::Error::other(e.to_string()),
+            })
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path)
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.list(dir)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(dir)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove(path)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.inner.is_symlink(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        self.inner.canonicalize(path)
+    }
+
+    fn hard_link_id(&self, path: &Path) -> Option<(u64, u64)> {
+        self.inner.hard_link_id(path)
+    }
+
+    fn attributes(&self, path: &Path) -> io::Result<Option<FileAttributes>> {
+        self.inner.attributes(path)
+    }
+
+    fn set_attributes(
+        &self,
+        path: &Path,
+        attributes: &FileAttributes,
+        preserve: Preserve,
+    ) -> io::Result<()> {
+        self.inner.set_attributes(path, attributes, preserve)
+    }
+
+    fn progress(&self) -> Arc<dyn Progress> {
+        self.inner.progress()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.inner.read(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn journal_file() -> PathBuf {
+        Path::new("images").join(JOURNAL_DIR).join(JOURNAL_FILE)
+    }
+
+    #[test]
+    fn test_journal_fs_cleans_up() {
+        let fs = MemoryFs::new();
+        fs.insert("images/a.png", b"old".to_vec());
+        let journal = Journal::open(&fs, Path::new("images")).unwrap();
+        let journaled = JournalFs::new(&fs, &journal);
+        journaled.write(Path::new("images/a.png"), b"new").unwrap();
+
+        assert_eq!(fs.get(Path::new("images/a.png")).unwrap(), b"new");
+        assert!(!fs.exists(&journal_file()));
+        assert!(!fs.exists(&journal.dir().join("0.tmp")));
+    }
+
+    /// 模拟在登记意图之后崩溃：写入临时文件和日志，目标文件保持 `target` 的内容
+    fn crash(fs: &MemoryFs, target: &[u8], staged: &[u8]) -> JournalEntry {
+        fs.insert("images/a.png", target.to_vec());
+        let dir = Path::new("images").join(JOURNAL_DIR);
+        let entry = JournalEntry {
+            path: PathBuf::from("images/a.png"),
+            original: Some(sha256(b"old")),
+            new: sha256(b"new"),
+            temp: dir.join("0.tmp"),
+        };
+        fs.insert(entry.temp.clone(), staged.to_vec());
+        Journal::save(fs, &dir, std::slice::from_ref(&entry)).unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_open_refuses_unfinished_journal() {
+        let fs = MemoryFs::new();
+        crash(&fs, b"old", b"new");
+        assert!(matches!(
+            Journal::open(&fs, Path::new("images")),
+            Err(PngmeError::Batch(_))
+        ));
+    }
+
+    #[test]
+    fn test_recover() {
+        // 临时文件完整：无论目标文件是原内容还是写了一半，都用它完成操作
+        for target in [&b"old"[..], b"ne"] {
+            let fs = MemoryFs::new();
+            let entry = crash(&fs, target, b"new");
+            let results = Journal::recover(&fs, Path::new("images")).unwrap();
+            assert_eq!(results, [(entry.clone(), Recovery::Completed)]);
+            assert_eq!(fs.get(Path::new("images/a.png")).unwrap(), b"new");
+            assert!(!fs.exists(&entry.temp));
+            assert!(!fs.exists(&journal_file()));
+        }
+
+        // 已经覆盖完成
+        let fs = MemoryFs::new();
+        crash(&fs, b"new", b"new");
+        let results = Journal::recover(&fs, Path::new("images")).unwrap();
+        assert_eq!(results[0].1, Recovery::AlreadyApplied);
+
+        // 临时文件没写完，目标文件还是原内容
+        let fs = MemoryFs::new();
+        crash(&fs, b"old", b"ne");
+        let results = Journal::recover(&fs, Path::new("images")).unwrap();
+        assert_eq!(results[0].1, Recovery::RolledBack);
+        assert_eq!(fs.get(Path::new("images/a.png")).unwrap(), b"old");
+
+        // 两者都损坏时保留记录
+        let fs = MemoryFs::new();
+        crash(&fs, b"ol", b"ne");
+        let results = Journal::recover(&fs, Path::new("images")).unwrap();
+        assert_eq!(results[0].1, Recovery::Unrecoverable);
+        assert!(fs.exists(&journal_file()));
+    }
+}
@@ -0,0 +1,172 @@
+//! # Label
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了带标签的信息：同一张图片中可以保存多条互不相关的信息，以标签区分，
+//! 例如 `build-info` 和 `license`。所有带标签的信息都写入同一个私有 chunk 类型 `pmLb`，
+//! 每个 chunk 的数据以标签开头：
+//!
+//! ```text
+//! | 标签长度 (u8) | 标签 (UTF-8) | 信息 |
+//! ```
+//!
+//! 信息部分与普通 chunk 中的数据相同（见 `payload`），可以压缩、加密、附加完整性标签；
+//! 过大的信息先拆分为分片，每个分片各自带有标签，所以不同标签的分片可以交错存放。
+//! 标签的作用类似键：写入已存在的标签会替换原来的信息，其他标签的信息不受影响。
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::payload;
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 带标签的信息使用的 chunk 类型：辅助、私有、可以安全复制
+pub const CHUNK_TYPE: &str = "pmLb";
+/// 标签的最大字节数
+pub const MAX_LABEL_LEN: usize = u8::MAX as usize;
+
+/// 检查标签：1 到 255 字节，不含控制字符
+pub fn validate(label: &str) -> Result<()> {
+    if label.is_empty() || label.len() > MAX_LABEL_LEN {
+        return Err(PngmeError::InvalidPayload(format!(
+            "label must be 1 to {} bytes long, got {}",
+            MAX_LABEL_LEN,
+            label.len()
+        )));
+    }
+    if label.chars().any(char::is_control) {
+        return Err(PngmeError::InvalidPayload(format!(
+            "label {:?} contains control characters",
+            label
+        )));
+    }
+    Ok(())
+}
+
+/// 把信息数据封装为带 `label` 的 chunk，超过 `max_chunk_size`（含标签）时拆分为多个 chunk
+pub fn chunks(label: &str, data: &[u8], max_chunk_size: usize) -> Result<Vec<Chunk>> {
+    validate(label)?;
+    let header_len = 1 + label.len();
+    let chunk_type: ChunkType = CHUNK_TYPE.parse()?;
+    payload::split(data, max_chunk_size.saturating_sub(header_len))?
+        .into_iter()
+        .map(|part| {
+            let mut bytes = Vec::with_capacity(header_len + part.len());
+            bytes.push(label.len() as u8);
+            bytes.extend_from_slice(label.as_bytes());
+            bytes.extend(part);
+            Chunk::new(chunk_type, bytes)
+        })
+        .collect()
+}
+
+/// 拆分出 chunk 数据中的标签和信息，格式无效时返回 `None`
+pub fn split(data: &[u8]) -> Option<(&str, &[u8])> {
+    let (&len, rest) = data.split_first()?;
+    let label = rest.get(..len as usize)?;
+    let label = std::str::from_utf8(label).ok().filter(|l| !l.is_empty())?;
+    Some((label, &rest[len as usize..]))
+}
+
+/// 带 `label` 的 chunk 中的信息部分，按出现的顺序排列
+fn parts<'a>(chunks: &'a [Chunk], label: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+    chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type().to_string() == CHUNK_TYPE)
+        .filter_map(|chunk| split(chunk.data()))
+        .filter(move |(found, _)| *found == label)
+        .map(|(_, data)| data)
+}
+
+/// 读取 `label` 对应的信息数据，拆分存储的分片会被重新拼接
+pub fn message_data(chunks: &[Chunk], label: &str) -> Result<Vec<u8>> {
+    let parts: Vec<&[u8]> = parts(chunks, label).collect();
+    if parts.is_empty() {
+        return Err(PngmeError::ChunkNotFound(format!(
+            "{} labelled {:?}",
+            CHUNK_TYPE, label
+        )));
+    }
+    payload::join(&parts)
+}
+
+/// 按第一次出现的顺序列出所有标签及其存储的总字节数（含所有分片及分片头部），忽略格式无效的 chunk
+pub fn labels(chunks: &[Chunk]) -> Vec<(String, usize)> {
+    let mut labels: Vec<(String, usize)> = Vec::new();
+    let labelled = chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type().to_string() == CHUNK_TYPE)
+        .filter_map(|chunk| split(chunk.data()));
+    for (label, data) in labelled {
+        match labels.iter_mut().find(|(found, _)| found == label) {
+            Some((_, size)) => *size += data.len(),
+            None => labels.push((label.to_string(), data.len())),
+        }
+    }
+    labels
+}
+
+/// 删除 `label` 对应的所有 chunk，返回被删除的 chunk（可能为空）
+pub fn remove(png: &mut Png, label: &str) -> Vec<Chunk> {
+    let positions: Vec<usize> = png
+        .chunks()
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| {
+            chunk.chunk_type().to_string() == CHUNK_TYPE
+                && split(chunk.data()).is_some_and(|(found, _)| found == label)
+        })
+        .map(|(index, _)| index)
+        .collect();
+    let mut removed: Vec<Chunk> = positions
+        .into_iter()
+        .rev()
+        .filter_map(|index| png.remove_chunk_at(index).ok())
+        .collect();
+    removed.reverse();
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_labels_coexist() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        for chunk in chunks("build-info", b"commit 1a2b3c on main", 30).unwrap() {
+            png.append_chunk(chunk);
+        }
+        for chunk in chunks("license", b"MIT", 1024).unwrap() {
+            png.append_chunk(chunk);
+        }
+        assert_eq!(
+            labels(png.chunks()),
+            [("build-info".to_string(), 57), ("license".to_string(), 3)]
+        );
+        assert_eq!(
+            message_data(png.chunks(), "build-info").unwrap(),
+            b"commit 1a2b3c on main"
+        );
+        assert_eq!(message_data(png.chunks(), "license").unwrap(), b"MIT");
+        assert!(matches!(
+            message_data(png.chunks(), "other"),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+
+        assert_eq!(remove(&mut png, "build-info").len(), 3);
+        assert_eq!(labels(png.chunks()), [("license".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_invalid_labels() {
+        assert!(chunks("", b"data", 1024).is_err());
+        assert!(chunks("a\nb", b"data", 1024).is_err());
+        assert!(chunks(&"x".repeat(256), b"data", 1024).is_err());
+        assert_eq!(split(&[3, b'a', b'b']), None);
+        assert_eq!(split(&[0, b'a']), None);
+        assert_eq!(split(&[1, b'a', b'!']), Some(("a", &b"!"[..])));
+    }
+}
@@ -0,0 +1,104 @@
+//! # pngme
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! pngme 的库入口：命令行程序（`main.rs`）和 C ABI（`ffi` feature）、
+//! 浏览器绑定（`wasm` feature）共用这里导出的模块。
+//!
+//! 命令行相关的模块（`args`、`config`、`commands`、`batch`、`daemon`、`watch`）和 `TerminalProgress` 只在默认的
+//! `cli` feature 中编译。作为库依赖时可以关闭默认 feature，只使用 `chunk`、`chunk_type`、`png`、
+//! `engine` 等模块，不引入 clap 和 indicatif：
+//!
+//! ```toml
+//! pngme = { version = "0.1", default-features = false }
+//! ```
+
+pub mod apng;
+pub mod archive;
+pub mod assess;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(feature = "cli")]
+pub mod args;
+#[cfg(feature = "cli")]
+pub mod batch;
+pub mod bundle;
+pub mod cancel;
+pub mod capabilities;
+pub mod caption;
+pub mod charset;
+pub mod checksum;
+pub mod chunk;
+pub mod chunk_type;
+pub mod clock;
+#[cfg(feature = "cli")]
+pub mod commands;
+pub mod compat;
+#[cfg(feature = "cli")]
+pub mod config;
+pub mod crypto;
+pub mod describe;
+pub mod detect;
+#[cfg(all(unix, feature = "cli"))]
+pub mod daemon;
+pub mod diff;
+pub mod dry_run;
+pub mod dump;
+pub mod editor;
+pub mod encoding;
+pub mod engine;
+pub mod error;
+pub mod exif;
+pub mod fdat;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod grep;
+pub mod ihdr;
+pub mod interlace;
+pub mod journal;
+pub mod label;
+pub mod limits;
+pub mod lsb;
+pub mod metadata;
+#[cfg(feature = "s3")]
+pub mod object_store;
+pub mod ordering;
+#[cfg(feature = "cli")]
+pub mod output;
+pub mod palette;
+pub mod parse;
+pub mod payload;
+pub mod png;
+pub mod prelude;
+pub mod preserve;
+pub mod preview;
+pub mod profile;
+pub mod progress;
+pub mod provenance;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qr;
+pub mod reader;
+pub mod roundtrip;
+pub mod scan;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod signature;
+pub mod simple;
+pub mod sniff;
+pub mod template;
+pub mod text;
+pub mod trailer;
+pub mod vfs;
+#[cfg(feature = "cli")]
+pub mod watch;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watermark;
+pub mod winpath;
+
+pub use crate::capabilities::capabilities;
+pub use crate::error::PngmeError;
+
+pub type Error = PngmeError;
+pub type Result<T> = std::result::Result<T, Error>;
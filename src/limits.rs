@@ -0,0 +1,203 @@
+//! # Limits
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块集中定义了处理不可信输入时的安全限制：文件大小、单个 chunk 的大小、chunk 数量、
+//! 允许的 chunk 类型、信息大小、解压比例和 daemon 的连接数。解析器（`Png`、`ChunkReader`）、信息封装（`Payload`）以及
+//! daemon 和 `Engine` 都接受 `Limits`，不指定时使用 `Limits::default()`。
+//!
+//! 默认值足以处理正常的图片，又能拒绝用来耗尽内存的恶意文件。确实需要处理超大文件时，
+//! 可以使用 `Limits::permissive()`，它只保留 PNG 规范本身的限制。
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::chunk::MAX_CHUNK_LENGTH;
+use crate::chunk_type::ChunkType;
+use crate::preview;
+use crate::{PngmeError, Result};
+
+const MIB: u64 = 1024 * 1024;
+/// 解压比例只对超过这个大小的输出生效，避免拒绝很小但压缩率很高的信息
+const RATIO_FLOOR: u64 = MIB;
+/// 设置了允许的类型时仍然总是允许的关键 chunk，没有它们就不是一张图片
+const CRITICAL_TYPES: [[u8; 4]; 4] = [*b"IHDR", *b"PLTE", *b"IDAT", *b"IEND"];
+
+/// 处理输入时的安全限制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// 文件的最大字节数
+    pub max_file_size: u64,
+    /// 单个 chunk 数据的最大字节数
+    pub max_chunk_size: u32,
+    /// 文件中 chunk 的最大数量
+    pub max_chunks: usize,
+    /// 允许出现的 chunk 类型，`None` 表示不限制。关键 chunk（IHDR、PLTE、IDAT、IEND）总是允许
+    pub allowed_types: Option<Vec<ChunkType>>,
+    /// 信息（解压后）的最大字节数
+    pub max_payload_size: u64,
+    /// 解压后与解压前大小的最大比例
+    pub max_decompression_ratio: u64,
+    /// daemon 同时服务的最大连接数
+    pub max_connections: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_file_size: 512 * MIB,
+            max_chunk_size: 256 * MIB as u32,
+            max_chunks: 100_000,
+            allowed_types: None,
+            max_payload_size: 256 * MIB,
+            max_decompression_ratio: 1000,
+            max_connections: 64,
+        }
+    }
+}
+
+impl Limits {
+    /// 只保留 PNG 规范本身的限制（chunk 不超过 2^31 - 1 字节），其余都不限制。
+    /// 只应该用于可信的输入：恶意文件可以让进程耗尽内存
+    pub fn permissive() -> Self {
+        Limits {
+            max_file_size: u64::MAX,
+            max_chunk_size: MAX_CHUNK_LENGTH,
+            max_chunks: usize::MAX,
+            allowed_types: None,
+            max_payload_size: u64::MAX,
+            max_decompression_ratio: u64::MAX,
+            max_connections: usize::MAX,
+        }
+    }
+
+    /// 只允许 `types` 中的辅助 chunk（关键 chunk 总是允许），其他类型的 chunk 在解析时被拒绝
+    pub fn with_allowed_types(mut self, types: impl IntoIterator<Item = ChunkType>) -> Self {
+        self.allowed_types = Some(types.into_iter().collect());
+        self
+    }
+
+    pub fn check_file_size(&self, size: u64) -> Result<()> {
+        check("file size", size, self.max_file_size)
+    }
+
+    pub fn check_chunk_size(&self, size: u32) -> Result<()> {
+        check("chunk size", size as u64, self.max_chunk_size as u64)
+    }
+
+    /// `count` 是已经读取的 chunk 数量
+    pub fn check_chunk_count(&self, count: usize) -> Result<()> {
+        check("chunk count", count as u64, self.max_chunks as u64)
+    }
+
+    /// `chunk_type` 是 chunk 头中的类型码，在读取数据之前检查
+    pub fn check_chunk_type(&self, chunk_type: [u8; 4]) -> Result<()> {
+        let Some(allowed) = &self.allowed_types else {
+            return Ok(());
+        };
+        if CRITICAL_TYPES.contains(&chunk_type)
+            || allowed.iter().any(|allowed| allowed.bytes() == chunk_type)
+        {
+            return Ok(());
+        }
+        Err(PngmeError::LimitExceeded(format!(
+            "chunk type {} is not in the list of allowed types",
+            preview::escape(&String::from_utf8_lossy(&chunk_type))
+        )))
+    }
+
+    pub fn check_payload_size(&self, size: u64) -> Result<()> {
+        check("payload size", size, self.max_payload_size)
+    }
+
+    /// `compressed_len` 字节的压缩数据最多能解压出多少字节
+    pub fn max_decompressed_size(&self, compressed_len: usize) -> u64 {
+        let by_ratio = (compressed_len as u64)
+            .saturating_mul(self.max_decompression_ratio)
+            .max(RATIO_FLOOR);
+        by_ratio.min(self.max_payload_size)
+    }
+
+    /// 解压 zlib 数据，输出超过 `max_decompressed_size` 时立即停止并返回
+    /// `PngmeError::DecompressionBomb`，不会先分配全部内存
+    pub fn inflate(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        self.inflate_at_most(compressed, self.max_decompressed_size(compressed.len()))
+    }
+
+    /// 解压 zlib 数据，输出最多 `max` 字节，用于解压后大小已知的数据（例如 IDAT）
+    pub fn inflate_at_most(&self, compressed: &[u8], max: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ZlibDecoder::new(compressed)
+            .take(max.saturating_add(1))
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > max {
+            return Err(PngmeError::DecompressionBomb {
+                compressed: compressed.len(),
+                limit: max,
+            });
+        }
+        Ok(data)
+    }
+}
+
+fn check(what: &str, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        return Err(PngmeError::LimitExceeded(format!(
+            "{} {} exceeds the limit of {}",
+            what, value, max
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload;
+
+    #[test]
+    fn test_checks() {
+        let limits = Limits {
+            max_chunks: 2,
+            ..Limits::default()
+        };
+        limits.check_chunk_count(2).unwrap();
+        assert!(matches!(
+            limits.check_chunk_count(3),
+            Err(PngmeError::LimitExceeded(_))
+        ));
+        assert!(limits.check_chunk_size(MAX_CHUNK_LENGTH).is_err());
+        Limits::permissive()
+            .check_chunk_size(MAX_CHUNK_LENGTH)
+            .unwrap();
+        Limits::permissive().check_file_size(u64::MAX).unwrap();
+
+        let limits = Limits::default().with_allowed_types([ChunkType::try_from(*b"tEXt").unwrap()]);
+        limits.check_chunk_type(*b"tEXt").unwrap();
+        limits.check_chunk_type(*b"IDAT").unwrap();
+        assert!(matches!(
+            limits.check_chunk_type(*b"ruSt"),
+            Err(PngmeError::LimitExceeded(message)) if message.contains("ruSt")
+        ));
+        Limits::default().check_chunk_type(*b"ruSt").unwrap();
+    }
+
+    #[test]
+    fn test_inflate() {
+        let data = vec![0u8; 4 * MIB as usize];
+        let compressed = &payload::compress(&data).unwrap()[4..];
+        assert!(compressed.len() * 1000 < data.len());
+
+        let limits = Limits::default();
+        assert!(matches!(
+            limits.inflate(compressed),
+            Err(PngmeError::DecompressionBomb { .. })
+        ));
+        assert_eq!(Limits::permissive().inflate(compressed).unwrap(), data);
+
+        // 比例限制不影响小数据
+        let small = &payload::compress(&[0; 1000]).unwrap()[4..];
+        assert_eq!(limits.inflate(small).unwrap().len(), 1000);
+    }
+}
@@ -0,0 +1,922 @@
+//! # LSB
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `--method lsb`：把信息写入像素样本的最低位。与 `--method qr` 不同，改动肉眼不可见，
+//! 但信息只在无损保存时存在，重新压缩为 JPEG、缩放或调色都会破坏它。
+//!
+//! 信息前面加上 8 字节的头部，逐位（高位在前）写入载体样本的最低位：
+//!
+//! ```text
+//! | 'p' 'm' 'L' | 标志 | [布局] | 数据长度（4 字节，大端） | 数据 |
+//! ```
+//!
+//! 载体样本按行、列、通道（含 alpha）的顺序排列，16 位图片使用每个样本低字节的最低位。
+//! 给出 `Region` 时只使用矩形内的像素，矩形外的像素保持不变，解码时必须给出同一个矩形。
+//! 调色板图片改动索引会明显失真，低于 8 位的样本改动最低位也很明显，都不支持。
+//!
+//! `Channels` 可以只选用部分通道（例如只用蓝色或只用 alpha），`bits_per_channel` 让每个样本承载 1 到 8 个低位，
+//! 容量随之增减，位数越多越容易被发现。不是全部通道各 1 位时标志中带有 `FLAG_LAYOUT`，其后的布局字节
+//! 低 4 位是选用的通道（第 i 位对应像素中的第 i 个样本），高 4 位是每个样本的位数减 1。头部本身总是只写入
+//! 所选通道的最低位，数据从头部之后的样本开始每个样本写入 `bits_per_channel` 位。读取时依次尝试图片的各种通道组合，
+//! 找到头部后按其中记录的布局读取数据，不需要指定。
+//!
+//! 给出密钥（由口令派生，见 `crypto::derive_lsb_key`）时，各位（包括头部）不按顺序写入，
+//! 而是按以密钥为种子的 ChaCha20 生成的伪随机排列分散到整个载体中：裁剪掉一部分或只分析局部时
+//! 看不到连续的数据，不知道口令也无法找到头部。排列用稀疏的 Fisher-Yates 洗牌逐个生成，
+//! 内存只与写入的位数有关，与图片大小无关。
+//!
+//! 最低位与要写入的位不同时，`LsbAlgorithm::Replace` 直接改写它们，值对 (2k, 2k+1) 的频数随之趋于相等，
+//! 容易被卡方检验（见 `detect`）发现；`LsbAlgorithm::Match`（默认）把样本加上或减去最小的差值
+//! （只写 1 位时随机地加 1 或减 1），读出的位相同，但直方图只是被平滑，不会出现成对相等的特征。
+//! 使用的算法记录在头部的标志中，读取时不需要指定。
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{OsRng, RngCore, SeedableRng};
+
+use crate::cancel::CancelToken;
+use crate::ihdr::{ColorType, Ihdr};
+use crate::interlace;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 头部开头的魔数
+pub const MAGIC: [u8; 3] = *b"pmL";
+/// 头部长度：魔数、标志和数据长度，带有 `FLAG_LAYOUT` 时还要加上 1 字节的布局
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+/// 标志：各位按密钥决定的伪随机顺序写入
+pub const FLAG_SCATTERED: u8 = 1;
+/// 标志：用 LSB matching（±1）写入
+pub const FLAG_MATCHED: u8 = 2;
+/// 标志：标志之后有一个布局字节，记录选用的通道和每个样本的位数
+pub const FLAG_LAYOUT: u8 = 4;
+const KNOWN_FLAGS: u8 = FLAG_SCATTERED | FLAG_MATCHED | FLAG_LAYOUT;
+/// 通道的字母：红、绿、蓝、alpha 和灰度
+const CHANNEL_LETTERS: &str = "rgbay";
+/// 每写入这么多位检查一次是否已取消
+const CHECK_INTERVAL: usize = 1 << 16;
+
+/// 图片中的矩形区域，单位为像素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Region {
+    type Err = PngmeError;
+
+    /// 解析 `x,y,w,h`，宽和高不能为 0
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            lsb_error(format!(
+                "invalid region {:?}, expected X,Y,WIDTH,HEIGHT with a non-zero size",
+                s
+            ))
+        };
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        match values[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(Region {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// 承载信息的通道，由 `r`、`g`、`b`、`a`（alpha）和 `y`（灰度）组成，例如 `rg`、`b`、`a`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channels(u8);
+
+impl Channels {
+    /// 在 `color_type` 的像素中选用的样本：第 i 位对应第 i 个样本。图片没有所选的通道时返回 `PngmeError::Lsb`
+    fn samples(&self, color_type: ColorType) -> Result<u8> {
+        let order: &[char] = match color_type {
+            ColorType::Grayscale => &['y'],
+            ColorType::GrayscaleAlpha => &['y', 'a'],
+            ColorType::Rgb => &['r', 'g', 'b'],
+            ColorType::Rgba => &['r', 'g', 'b', 'a'],
+            ColorType::Indexed => &[],
+        };
+        let mut mask = 0;
+        for (bit, letter) in CHANNEL_LETTERS.chars().enumerate() {
+            if self.0 & 1 << bit == 0 {
+                continue;
+            }
+            let index = order
+                .iter()
+                .position(|&c| c == letter)
+                .ok_or_else(|| lsb_error(format!("the image has no {:?} channel", letter)))?;
+            mask |= 1 << index;
+        }
+        Ok(mask)
+    }
+}
+
+impl FromStr for Channels {
+    type Err = PngmeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut mask = 0;
+        for letter in s.trim().chars() {
+            let bit = CHANNEL_LETTERS
+                .find(letter.to_ascii_lowercase())
+                .ok_or_else(|| {
+                    lsb_error(format!(
+                        "invalid channels {:?}, expected letters from r, g, b, a and y (gray)",
+                        s
+                    ))
+                })?;
+            mask |= 1 << bit;
+        }
+        if mask == 0 {
+            return Err(lsb_error("--channels needs at least one channel"));
+        }
+        Ok(Channels(mask))
+    }
+}
+
+/// 最低位与要写入的位不同时如何修改样本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LsbAlgorithm {
+    /// 直接改写低位（LSB replacement），会使值对的频数趋于相等
+    Replace,
+    /// 加上或减去最小的差值（LSB matching），保持直方图的形状
+    #[default]
+    Match,
+}
+
+/// 写入和读取的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LsbOptions {
+    /// 只使用该矩形内的像素，`None` 时使用整幅图片
+    pub region: Option<Region>,
+    /// 打乱写入顺序的密钥，`None` 时按顺序写入。读取时先按密钥决定的顺序寻找头部，找不到再按顺序寻找
+    pub key: Option<[u8; 32]>,
+    /// 写入使用的算法，读取时忽略
+    pub algorithm: LsbAlgorithm,
+    /// 写入时选用的通道，`None` 时使用所有通道（含 alpha）；读取时忽略
+    pub channels: Option<Channels>,
+    /// 写入时每个样本承载的低位数（1 到 8）；读取时忽略
+    pub bits_per_channel: u8,
+}
+
+impl Default for LsbOptions {
+    fn default() -> Self {
+        LsbOptions {
+            region: None,
+            key: None,
+            algorithm: LsbAlgorithm::default(),
+            channels: None,
+            bits_per_channel: 1,
+        }
+    }
+}
+
+fn lsb_error(message: impl Into<String>) -> PngmeError {
+    PngmeError::Lsb(message.into())
+}
+
+/// 载体样本：把第 k 个载体样本对应到像素数据（每行 `Ihdr::row_bytes` 字节）中的一个字节
+struct Carrier {
+    region: Region,
+    channels: u64,
+    /// 选用的样本在像素中的序号
+    selected: Vec<u64>,
+    /// 选用的样本，第 i 位对应像素中的第 i 个样本
+    mask: u8,
+    /// 每个样本的字节数，1 或 2
+    sample_bytes: usize,
+    row_bytes: usize,
+}
+
+impl Carrier {
+    /// 使用 `mask` 选中的样本，`None` 时使用所有样本
+    fn new(header: &Ihdr, region: Option<Region>, mask: Option<u8>) -> Result<Self> {
+        if header.color_type == ColorType::Indexed {
+            return Err(lsb_error(
+                "palette images are not supported, changing their indices distorts colors visibly",
+            ));
+        }
+        if header.bit_depth < 8 {
+            return Err(lsb_error(format!(
+                "{}-bit samples are not supported, LSB embedding needs 8 or 16 bits per sample",
+                header.bit_depth
+            )));
+        }
+        let region = region.unwrap_or(Region {
+            x: 0,
+            y: 0,
+            width: header.width,
+            height: header.height,
+        });
+        if u64::from(region.x) + u64::from(region.width) > u64::from(header.width)
+            || u64::from(region.y) + u64::from(region.height) > u64::from(header.height)
+        {
+            return Err(lsb_error(format!(
+                "the {}x{} region at ({}, {}) does not fit in the {}x{} image",
+                region.width, region.height, region.x, region.y, header.width, header.height
+            )));
+        }
+        let channels = header.color_type.channels();
+        let mask = mask.unwrap_or((1 << channels) - 1);
+        Ok(Carrier {
+            region,
+            channels: u64::from(channels),
+            selected: (0..u64::from(channels))
+                .filter(|channel| mask & 1 << channel != 0)
+                .collect(),
+            mask,
+            sample_bytes: header.bit_depth as usize / 8,
+            row_bytes: header.row_bytes() as usize,
+        })
+    }
+
+    /// 载体样本数
+    fn len(&self) -> u64 {
+        u64::from(self.region.width) * u64::from(self.region.height) * self.selected.len() as u64
+    }
+
+    /// 是否使用了所有样本
+    fn is_full(&self) -> bool {
+        self.selected.len() as u64 == self.channels
+    }
+
+    /// 第 `index` 个载体样本的最低位所在的字节（16 位样本为低字节）
+    fn offset(&self, index: u64) -> usize {
+        let count = self.selected.len() as u64;
+        let (pixel, channel) = (index / count, self.selected[(index % count) as usize]);
+        let width = u64::from(self.region.width);
+        let x = u64::from(self.region.x) + pixel % width;
+        let y = u64::from(self.region.y) + pixel / width;
+        let sample = (x * self.channels + channel) as usize;
+        y as usize * self.row_bytes + (sample + 1) * self.sample_bytes - 1
+    }
+}
+
+/// 载体位的访问顺序：按顺序，或由密钥决定的伪随机排列
+struct Order {
+    /// 以密钥为种子的随机数，`None` 时按顺序访问
+    rng: Option<ChaCha20Rng>,
+    /// 稀疏的 Fisher-Yates 洗牌中被交换过的位置
+    swapped: HashMap<u64, u64>,
+    next: u64,
+    len: u64,
+}
+
+impl Order {
+    fn new(len: u64, key: Option<&[u8; 32]>) -> Self {
+        Order {
+            rng: key.map(|key| ChaCha20Rng::from_seed(*key)),
+            swapped: HashMap::new(),
+            next: 0,
+            len,
+        }
+    }
+}
+
+impl Iterator for Order {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.next == self.len {
+            return None;
+        }
+        let i = self.next;
+        self.next += 1;
+        let Some(rng) = &mut self.rng else {
+            return Some(i);
+        };
+        // 在 [i, len) 中均匀地取一个位置（乘法取高位，偏差不超过 len / 2^64）
+        let j = i + ((u128::from(rng.next_u64()) * u128::from(self.len - i)) >> 64) as u64;
+        let at_i = self.swapped.remove(&i).unwrap_or(i);
+        if j == i {
+            return Some(at_i);
+        }
+        Some(self.swapped.insert(j, at_i).unwrap_or(j))
+    }
+}
+
+/// 把 `bytes` 按高位在前拆成每组 `width` 位的值和位数，最后一组可能不足 `width` 位
+fn groups(bytes: &[u8], width: u8) -> impl Iterator<Item = (u8, u8)> + '_ {
+    let total = bytes.len() as u64 * 8;
+    (0..total).step_by(width as usize).map(move |start| {
+        let count = (total - start).min(u64::from(width));
+        let value = (start..start + count).fold(0u8, |value, bit| {
+            value << 1 | bytes[(bit / 8) as usize] >> (7 - bit % 8) & 1
+        });
+        (value, count as u8)
+    })
+}
+
+/// 写入头部和数据需要的载体样本数：头部每个样本 1 位，数据每个样本 `bits` 位
+fn samples_needed(header_len: usize, data_len: usize, bits: u8) -> u64 {
+    header_len as u64 * 8 + (data_len as u64 * 8).div_ceil(u64::from(bits))
+}
+
+/// 把 `data`（连同头部）写入像素样本的低位，替换原来的 IDAT。载体放不下时返回 `PngmeError::Lsb`。
+/// 每写入 65536 位和压缩 IDAT 前检查 `cancel`，已取消时返回 `PngmeError::Cancelled`。
+/// LSB matching 加 1 还是减 1 取自操作系统的随机数源
+pub fn embed(
+    png: &mut Png,
+    data: &[u8],
+    options: &LsbOptions,
+    limits: &Limits,
+    cancel: &CancelToken,
+) -> Result<()> {
+    embed_with_rng(png, data, options, limits, cancel, &mut OsRng)
+}
+
+/// 与 `embed` 相同，但 LSB matching 加 1 还是减 1 取自调用方提供的生成器。
+/// 传入固定种子的生成器可以得到可复现的输出，仅应在测试或审计时这样做
+pub fn embed_with_rng<R: RngCore>(
+    png: &mut Png,
+    data: &[u8],
+    options: &LsbOptions,
+    limits: &Limits,
+    cancel: &CancelToken,
+    rng: &mut R,
+) -> Result<()> {
+    let header = png.header()?;
+    let bits = options.bits_per_channel;
+    if !(1..=8).contains(&bits) {
+        return Err(lsb_error(format!(
+            "{} bits per channel is out of range, expected 1 to 8",
+            bits
+        )));
+    }
+    let mask = options
+        .channels
+        .map(|channels| channels.samples(header.color_type))
+        .transpose()?;
+    let carrier = Carrier::new(&header, options.region, mask)?;
+    let len = u32::try_from(data.len())
+        .map_err(|_| lsb_error(format!("{} bytes are too many to embed", data.len())))?;
+    let mut head = Vec::with_capacity(HEADER_LEN + 1);
+    head.extend(MAGIC);
+    let mut flags = 0;
+    if options.key.is_some() {
+        flags |= FLAG_SCATTERED;
+    }
+    if options.algorithm == LsbAlgorithm::Match {
+        flags |= FLAG_MATCHED;
+    }
+    let layout = !carrier.is_full() || bits != 1;
+    if layout {
+        flags |= FLAG_LAYOUT;
+    }
+    head.push(flags);
+    if layout {
+        head.push((bits - 1) << 4 | carrier.mask);
+    }
+    head.extend(len.to_be_bytes());
+    if samples_needed(head.len(), data.len(), bits) > carrier.len() {
+        let capacity = carrier.len().saturating_sub(head.len() as u64 * 8) * u64::from(bits) / 8;
+        return Err(lsb_error(format!(
+            "{} bytes do not fit, the carrier holds {} bytes after the {}-byte header",
+            data.len(),
+            capacity,
+            head.len()
+        )));
+    }
+
+    let mut image = png.pixels(limits)?;
+    let groups = groups(&head, 1).chain(groups(data, bits));
+    let order = Order::new(carrier.len(), options.key.as_ref());
+    for (count, (index, (value, width))) in order.zip(groups).enumerate() {
+        if count % CHECK_INTERVAL == 0 {
+            cancel.check()?;
+        }
+        let offset = carrier.offset(index);
+        let sample = &mut image[offset + 1 - carrier.sample_bytes..=offset];
+        write_bits(sample, value, width, options.algorithm, rng);
+    }
+    let raw = interlace::filter_adaptive(&header, &interlace::interlace(&header, &image));
+    cancel.check()?;
+    png.set_image_data(&raw)
+}
+
+/// 把大端存储的样本的低 `width` 位改为 `value`。`Replace` 直接改写这些位；`Match` 把样本加上或减去
+/// 最小的差值，两个方向一样近时由 `rng` 决定，超出样本的取值范围时只能走另一个方向
+fn write_bits<R: RngCore>(
+    sample: &mut [u8],
+    value: u8,
+    width: u8,
+    algorithm: LsbAlgorithm,
+    rng: &mut R,
+) {
+    let modulus = 1u32 << width;
+    let low = sample.last_mut().expect("samples have at least one byte");
+    let current = u32::from(*low) % modulus;
+    let value = u32::from(value);
+    if current == value {
+        return;
+    }
+    if algorithm == LsbAlgorithm::Replace {
+        *low = (u32::from(*low) - current + value) as u8;
+        return;
+    }
+    let full = sample
+        .iter()
+        .fold(0u32, |full, &byte| full << 8 | u32::from(byte));
+    let max = (1u32 << (8 * sample.len())) - 1;
+    let down = (current + modulus - value) % modulus;
+    let up = (value + modulus - current) % modulus;
+    let go_up = if full < down {
+        true
+    } else if full + up > max {
+        false
+    } else if up != down {
+        up < down
+    } else {
+        rng.next_u32() & 1 == 1
+    };
+    let full = if go_up { full + up } else { full - down };
+    for (shift, byte) in sample.iter_mut().rev().enumerate() {
+        *byte = (full >> (8 * shift)) as u8;
+    }
+}
+
+/// 读取 `embed` 写入的数据。依次尝试图片的所有通道组合（先尝试全部通道），找不到头部或头部无效时返回 `PngmeError::Lsb`
+pub fn extract(png: &Png, options: &LsbOptions, limits: &Limits) -> Result<Vec<u8>> {
+    let header = png.header()?;
+    let full = Carrier::new(&header, options.region, None)?;
+    if full.len() < HEADER_LEN as u64 * 8 {
+        return Err(lsb_error("the carrier is too small to hold any data"));
+    }
+    let image = png.pixels(limits)?;
+    let masks: Vec<u8> = std::iter::once(full.mask).chain(1..full.mask).collect();
+    let keys = match &options.key {
+        Some(key) => vec![Some(key), None],
+        None => vec![None],
+    };
+    let mut first_error = None;
+    for key in keys {
+        for &mask in &masks {
+            let carrier = Carrier::new(&header, options.region, Some(mask))?;
+            match extract_in(&image, &carrier, key) {
+                Ok(data) => return Ok(data),
+                Err(e) => {
+                    // 全部通道、按顺序读取时的错误最有参考价值
+                    if mask == full.mask && key.is_none() {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+        }
+    }
+    Err(first_error.expect("the sequential full carrier is always tried"))
+}
+
+/// 按 `key` 决定的顺序在 `carrier` 中读取头部和数据
+fn extract_in(image: &[u8], carrier: &Carrier, key: Option<&[u8; 32]>) -> Result<Vec<u8>> {
+    let mut order = Order::new(carrier.len(), key);
+    let mut read = |len: usize, width: u8| -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; len];
+        let total = len as u64 * 8;
+        let mut bit = 0;
+        while bit < total {
+            let count = (total - bit).min(u64::from(width));
+            let index = order
+                .next()
+                .ok_or_else(|| lsb_error("the carrier is too small for the header"))?;
+            let value = image[carrier.offset(index)];
+            for shift in (0..count).rev() {
+                bytes[(bit / 8) as usize] |= (value >> shift & 1) << (7 - bit % 8);
+                bit += 1;
+            }
+        }
+        Ok(bytes)
+    };
+
+    let not_found = || {
+        lsb_error(
+            "no pngme data in the least significant bits (was it written with another --region or passphrase?)",
+        )
+    };
+    let head = read(MAGIC.len() + 1, 1)?;
+    let flags = head[MAGIC.len()];
+    let scattered = flags & FLAG_SCATTERED != 0;
+    if head[..MAGIC.len()] != MAGIC || scattered != key.is_some() {
+        return Err(not_found());
+    }
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(lsb_error(format!("unknown header flags {:#04x}", flags)));
+    }
+    let (bits, header_len) = if flags & FLAG_LAYOUT != 0 {
+        let layout = read(1, 1)?[0];
+        // 头部只在记录的通道中才有意义，其他通道组合中碰巧读到的魔数不算
+        if layout & 0x0f != carrier.mask {
+            return Err(not_found());
+        }
+        ((layout >> 4) + 1, HEADER_LEN + 1)
+    } else if carrier.is_full() {
+        (1, HEADER_LEN)
+    } else {
+        return Err(not_found());
+    };
+    let len = u32::from_be_bytes(read(4, 1)?.try_into().unwrap()) as u64;
+    let available = carrier.len().saturating_sub(header_len as u64 * 8) * u64::from(bits) / 8;
+    if len > available {
+        return Err(lsb_error(format!(
+            "the header claims {} bytes but the carrier holds only {} more",
+            len, available
+        )));
+    }
+    let algorithm = match flags & FLAG_MATCHED {
+        0 => LsbAlgorithm::Replace,
+        _ => LsbAlgorithm::Match,
+    };
+    tracing::debug!(
+        bytes = len,
+        scattered,
+        ?algorithm,
+        bits,
+        channels = carrier.mask,
+        "read LSB data"
+    );
+    read(len as usize, bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::testing_image as image;
+
+    /// 带渐变的 8 位 RGB 图片
+    fn rgb(width: u32, height: u32) -> Png {
+        let header = Ihdr::new(width, height, 8, ColorType::Rgb).unwrap();
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| [(i % width * 7) as u8, (i / width * 5) as u8, 0x80])
+            .collect();
+        image(header, &pixels)
+    }
+
+    #[test]
+    fn test_embed_extract() {
+        let limits = Limits::default();
+        for algorithm in [LsbAlgorithm::Replace, LsbAlgorithm::Match] {
+            let mut png = rgb(40, 30);
+            let before = png.pixels(&limits).unwrap();
+            let options = LsbOptions {
+                algorithm,
+                ..LsbOptions::default()
+            };
+            embed(
+                &mut png,
+                b"hidden in plain sight",
+                &options,
+                &limits,
+                &CancelToken::new(),
+            )
+            .unwrap();
+            assert_eq!(
+                extract(&png, &LsbOptions::default(), &limits).unwrap(),
+                b"hidden in plain sight"
+            );
+            let after = png.pixels(&limits).unwrap();
+            assert_ne!(after, before);
+            assert!(before.iter().zip(&after).all(|(&a, &b)| a.abs_diff(b) <= 1));
+            if algorithm == LsbAlgorithm::Replace {
+                assert!(before.iter().zip(&after).all(|(a, b)| a >> 1 == b >> 1));
+            }
+            // 算法记录在头部的标志中（按顺序写入时为第 4 个字节）
+            let flags = after[24..32].iter().fold(0, |flags, &b| flags << 1 | b & 1);
+            let matched = algorithm == LsbAlgorithm::Match;
+            assert_eq!(flags, if matched { FLAG_MATCHED } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_matching_resists_chi_square() {
+        use crate::detect::{self, FindingKind};
+
+        // 样本只取 4 的倍数，写满随机数据后比较两种算法
+        let header = Ihdr::new(64, 64, 8, ColorType::Grayscale).unwrap();
+        let pixels: Vec<u8> = (0..64 * 64).map(|i| (i * 7 % 64 * 4) as u8).collect();
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..64 * 64 / 8 - HEADER_LEN)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let flagged = |algorithm| {
+            let mut png = image(header, &pixels);
+            let options = LsbOptions {
+                algorithm,
+                ..LsbOptions::default()
+            };
+            let limits = Limits::default();
+            embed(&mut png, &data, &options, &limits, &CancelToken::new()).unwrap();
+            assert_eq!(extract(&png, &options, &limits).unwrap(), data);
+            let report = detect::detect(&png.as_bytes(), &limits).unwrap();
+            report
+                .findings
+                .iter()
+                .any(|finding| finding.kind == FindingKind::LsbAnomaly)
+        };
+        assert!(flagged(LsbAlgorithm::Replace));
+        assert!(!flagged(LsbAlgorithm::Match));
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let limits = Limits::default();
+        let embed_seeded = |seed| {
+            let mut png = rgb(40, 30);
+            let data = [0xa5; 100];
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let options = LsbOptions::default();
+            embed_with_rng(
+                &mut png,
+                &data,
+                &options,
+                &limits,
+                &CancelToken::new(),
+                &mut rng,
+            )
+            .unwrap();
+            png.as_bytes()
+        };
+        assert_eq!(embed_seeded(7), embed_seeded(7));
+        assert_ne!(embed_seeded(7), embed_seeded(8));
+    }
+
+    #[test]
+    fn test_write_bits() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let mut write = |sample: &[u8], value, width, algorithm| {
+            let mut sample = sample.to_vec();
+            write_bits(&mut sample, value, width, algorithm, &mut rng);
+            sample
+        };
+        // 只写 1 位时两个方向一样近，随机选择，进位和借位跨越字节
+        let stepped = write(&[0x12, 0xff], 0, 1, LsbAlgorithm::Match);
+        assert!(stepped == [0x13, 0x00] || stepped == [0x12, 0xfe]);
+        // 为 0 时只能加，已是最大值时只能减
+        assert_eq!(write(&[0], 1, 1, LsbAlgorithm::Match), [1]);
+        assert_eq!(write(&[0xff], 0, 1, LsbAlgorithm::Match), [0xfe]);
+        // 多位时取最近的方向
+        assert_eq!(write(&[0x10], 7, 3, LsbAlgorithm::Match), [0x0f]);
+        assert_eq!(
+            write(&[0x01, 0x00], 3, 2, LsbAlgorithm::Match),
+            [0x00, 0xff]
+        );
+        assert_eq!(write(&[0xff], 0, 2, LsbAlgorithm::Replace), [0xfc]);
+        assert_eq!(write(&[0x80], 0, 4, LsbAlgorithm::Match), [0x80]);
+    }
+
+    #[test]
+    fn test_channels_and_bits() {
+        let limits = Limits::default();
+        let header = Ihdr::new(32, 32, 8, ColorType::Rgba).unwrap();
+        let original = vec![0x80; 32 * 32 * 4];
+        for (channels, bits) in [("b", 1), ("a", 4), ("rg", 2), ("rgba", 8), ("rgba", 1)] {
+            let mut png = image(header, &original);
+            let options = LsbOptions {
+                channels: Some(channels.parse().unwrap()),
+                bits_per_channel: bits,
+                ..LsbOptions::default()
+            };
+            let data = vec![0x3c; 100];
+            embed(&mut png, &data, &options, &limits, &CancelToken::new()).unwrap();
+            // 读取时不需要指定通道和位数
+            assert_eq!(
+                extract(&png, &LsbOptions::default(), &limits).unwrap(),
+                data,
+                "{} with {} bits",
+                channels,
+                bits
+            );
+            let selected: Vec<usize> = channels.chars().map(|c| "rgba".find(c).unwrap()).collect();
+            let pixels = png.pixels(&limits).unwrap();
+            for (offset, (&before, &after)) in original.iter().zip(&pixels).enumerate() {
+                if !selected.contains(&(offset % 4)) {
+                    assert_eq!(before, after, "channel {} changed", offset % 4);
+                }
+                assert!(u32::from(before.abs_diff(after)) < 1 << bits);
+            }
+        }
+
+        // 全部通道各 1 位时不写布局字节，头部与以前相同
+        let mut png = image(header, &original);
+        embed(
+            &mut png,
+            b"x",
+            &LsbOptions::default(),
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        let flags = png.pixels(&limits).unwrap()[24..32]
+            .iter()
+            .fold(0, |flags, &b| flags << 1 | b & 1);
+        assert_eq!(flags & FLAG_LAYOUT, 0);
+
+        // 只用蓝色通道、每个样本 1 位：32 × 32 位 = 128 字节，去掉 9 字节的头部
+        let blue = LsbOptions {
+            channels: Some("b".parse().unwrap()),
+            ..LsbOptions::default()
+        };
+        let mut png = image(header, &original);
+        embed(&mut png, &[0; 119], &blue, &limits, &CancelToken::new()).unwrap();
+        assert!(matches!(
+            embed(&mut png, &[0; 120], &blue, &limits, &CancelToken::new()),
+            Err(PngmeError::Lsb(_))
+        ));
+
+        let gray = Ihdr::new(16, 16, 8, ColorType::GrayscaleAlpha).unwrap();
+        let mut png = image(gray, &[0x80; 16 * 16 * 2]);
+        let alpha = LsbOptions {
+            channels: Some("a".parse().unwrap()),
+            bits_per_channel: 3,
+            ..LsbOptions::default()
+        };
+        embed(&mut png, b"alpha", &alpha, &limits, &CancelToken::new()).unwrap();
+        assert_eq!(
+            extract(&png, &LsbOptions::default(), &limits).unwrap(),
+            b"alpha"
+        );
+        let red = LsbOptions {
+            channels: Some("r".parse().unwrap()),
+            ..LsbOptions::default()
+        };
+        assert!(matches!(
+            embed(&mut png, b"x", &red, &limits, &CancelToken::new()),
+            Err(PngmeError::Lsb(_))
+        ));
+        let zero = LsbOptions {
+            bits_per_channel: 0,
+            ..LsbOptions::default()
+        };
+        assert!(embed(&mut png, b"x", &zero, &limits, &CancelToken::new()).is_err());
+        for channels in ["", "rx", "red"] {
+            assert!(channels.parse::<Channels>().is_err(), "{}", channels);
+        }
+    }
+
+    #[test]
+    fn test_region() {
+        let limits = Limits::default();
+        let mut png = rgb(40, 30);
+        let before = png.pixels(&limits).unwrap();
+        let region: Region = "10, 5, 12, 8".parse().unwrap();
+        let options = LsbOptions {
+            region: Some(region),
+            ..LsbOptions::default()
+        };
+        // 12 × 8 × 3 位：36 字节，去掉头部还能放 28 字节
+        let data = [0x5a; 28];
+        embed(&mut png, &data, &options, &limits, &CancelToken::new()).unwrap();
+        assert_eq!(extract(&png, &options, &limits).unwrap(), data);
+        assert!(extract(&png, &LsbOptions::default(), &limits).is_err());
+
+        let after = png.pixels(&limits).unwrap();
+        let row_bytes = 40 * 3;
+        for (offset, (a, b)) in before.iter().zip(&after).enumerate() {
+            let (x, y) = ((offset % row_bytes / 3) as u32, (offset / row_bytes) as u32);
+            let inside = (10..22).contains(&x) && (5..13).contains(&y);
+            assert!(
+                inside || a == b,
+                "pixel ({}, {}) outside the region changed",
+                x,
+                y
+            );
+        }
+
+        assert!(matches!(
+            embed(&mut png, &[0; 29], &options, &limits, &CancelToken::new()),
+            Err(PngmeError::Lsb(_))
+        ));
+    }
+
+    #[test]
+    fn test_scattered() {
+        let limits = Limits::default();
+        let header = Ihdr::new(64, 64, 8, ColorType::Grayscale).unwrap();
+        let mut png = image(header, &[0x80; 64 * 64]);
+        let options = LsbOptions {
+            key: Some([7; 32]),
+            ..LsbOptions::default()
+        };
+        let data = [0xff; 32];
+        embed(&mut png, &data, &options, &limits, &CancelToken::new()).unwrap();
+        assert_eq!(extract(&png, &options, &limits).unwrap(), data);
+        // 没有密钥或密钥不同时找不到头部
+        assert!(extract(&png, &LsbOptions::default(), &limits).is_err());
+        let other = LsbOptions {
+            key: Some([8; 32]),
+            ..options
+        };
+        assert!(extract(&png, &other, &limits).is_err());
+
+        // 改动的样本分散在整幅图片中，而不是集中在开头
+        let changed: Vec<usize> = png
+            .pixels(&limits)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, &sample)| sample != 0x80)
+            .map(|(offset, _)| offset)
+            .collect();
+        assert!(changed.len() > 200);
+        assert!(changed.iter().any(|&offset| offset >= 64 * 60));
+        assert!(changed.windows(2).filter(|w| w[1] == w[0] + 1).count() < changed.len() / 4);
+
+        // 按顺序写入的信息在给出密钥时也能读出
+        let mut png = image(header, &[0x80; 64 * 64]);
+        embed(
+            &mut png,
+            b"plain",
+            &LsbOptions::default(),
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        assert_eq!(extract(&png, &options, &limits).unwrap(), b"plain");
+    }
+
+    #[test]
+    fn test_order_is_a_permutation() {
+        let mut indices: Vec<u64> = Order::new(1000, Some(&[1; 32])).collect();
+        assert_ne!(indices, (0..1000).collect::<Vec<_>>());
+        indices.sort_unstable();
+        assert_eq!(indices, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sixteen_bit() {
+        let limits = Limits::default();
+        let header = Ihdr::new(8, 8, 16, ColorType::GrayscaleAlpha).unwrap();
+        let mut png = image(header, &[0xab; 8 * 8 * 4]);
+        embed(
+            &mut png,
+            b"16",
+            &LsbOptions::default(),
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            extract(&png, &LsbOptions::default(), &limits).unwrap(),
+            b"16"
+        );
+        let pixels = png.pixels(&limits).unwrap();
+        assert!(pixels.iter().step_by(2).all(|&high| high == 0xab));
+    }
+
+    #[test]
+    fn test_errors() {
+        let limits = Limits::default();
+        let cancel = CancelToken::new();
+        for region in ["1,2,3", "1,2,0,4", "a,b,c,d", "1,2,3,4,5"] {
+            assert!(region.parse::<Region>().is_err(), "{}", region);
+        }
+
+        let mut png = rgb(10, 10);
+        let outside = LsbOptions {
+            region: Some("5,5,6,1".parse().unwrap()),
+            ..LsbOptions::default()
+        };
+        assert!(matches!(
+            embed(&mut png, b"x", &outside, &limits, &cancel),
+            Err(PngmeError::Lsb(_))
+        ));
+        assert!(matches!(
+            extract(&png, &LsbOptions::default(), &limits),
+            Err(PngmeError::Lsb(_))
+        ));
+        let cancelled = CancelToken::new();
+        cancelled.cancel();
+        assert!(matches!(
+            embed(&mut png, b"x", &LsbOptions::default(), &limits, &cancelled),
+            Err(PngmeError::Cancelled)
+        ));
+
+        let indexed = image(Ihdr::new(4, 4, 8, ColorType::Indexed).unwrap(), &[0; 16]);
+        let gray = image(Ihdr::new(8, 8, 4, ColorType::Grayscale).unwrap(), &[0; 32]);
+        for mut png in [indexed, gray] {
+            assert!(matches!(
+                embed(&mut png, b"x", &LsbOptions::default(), &limits, &cancel),
+                Err(PngmeError::Lsb(_))
+            ));
+        }
+    }
+}
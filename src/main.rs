@@ -1,12 +1,214 @@
-mod args;
-mod chunk;
-mod chunk_type;
-mod commands;
-mod png;
-
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
-
-fn main() -> Result<()> {
-    todo!()
-}
\ No newline at end of file
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+use clap::error::ErrorKind;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use tracing::Level;
+
+use pngme::args::{Cli, PngMeArgs};
+use pngme::cancel;
+use pngme::clock::SystemClock;
+use pngme::commands;
+use pngme::config::Config;
+#[cfg(unix)]
+use pngme::daemon;
+use pngme::dry_run::DryRunFs;
+use pngme::error::EXIT_USAGE;
+#[cfg(feature = "s3")]
+use pngme::object_store;
+#[cfg(not(feature = "s3"))]
+use pngme::vfs::StdFs;
+use pngme::output;
+use pngme::preview;
+use pngme::progress::{ProgressFs, TerminalProgress};
+use pngme::vfs::Vfs;
+use pngme::watch;
+use pngme::{PngmeError, Result};
+
+fn main() {
+    let matches = Cli::command()
+        .try_get_matches()
+        .unwrap_or_else(|e| exit_usage(e));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| exit_usage(e));
+    cancel::install_signal_handler();
+    let json = cli.json;
+    let command = command_name(&matches);
+    if json {
+        output::enable_json();
+    }
+    match run(cli, &matches) {
+        // --json 时无论成功还是出错都以一个 JSON 对象输出到 stdout，脚本只需解析一处
+        Ok(()) if json => println!("{}", output::success(&command)),
+        Ok(()) => {}
+        Err(e) if json => {
+            println!("{}", output::failure(&command, &e));
+            std::process::exit(e.exit_code());
+        }
+        Err(PngmeError::Cancelled) => {
+            eprintln!("Interrupted");
+            std::process::exit(cancel::EXIT_INTERRUPTED);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", preview::escape_lines(&e.to_string()));
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+fn run(cli: Cli, matches: &ArgMatches) -> Result<()> {
+    #[cfg(feature = "s3")]
+    let fs = object_store::ObjectStoreFs::from_env();
+    #[cfg(not(feature = "s3"))]
+    let fs = StdFs;
+    // 只在 stderr 是终端时显示进度条，重定向到文件或管道时不输出控制字符
+    let progress = std::io::stderr()
+        .is_terminal()
+        .then(|| ProgressFs::new(&fs, Arc::new(TerminalProgress::new())));
+    let fs: &dyn Vfs = match &progress {
+        Some(progress) => progress,
+        None => &fs,
+    };
+
+    if cli.version && cli.command.is_some() {
+        exit_usage(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            "--version cannot be used with a subcommand",
+        ));
+    }
+    let Some(mut command) = cli.command else {
+        if !cli.version {
+            exit_usage(Cli::command().error(
+                ErrorKind::MissingSubcommand,
+                "--verbose and --dry-run require a subcommand or --version",
+            ));
+        }
+        commands::version(cli.verbose);
+        return Ok(());
+    };
+    if cli.verbose {
+        tracing_subscriber::fmt()
+            .with_max_level(Level::DEBUG)
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+    // 命令行中明确给出的参数优先于配置文件
+    Config::load()?.apply(&mut command, matches)?;
+    if !cli.dry_run {
+        return dispatch(fs, command);
+    }
+    match command {
+        // watch 自己打印将要做的修改，不在内存中累积
+        PngMeArgs::Watch(mut args) => {
+            args.dry_run = true;
+            watch::run(fs, args)
+        }
+        #[cfg(unix)]
+        PngMeArgs::Daemon(_) => exit_usage(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            "--dry-run cannot be used with daemon",
+        )),
+        command => {
+            let dry_run = DryRunFs::new(fs);
+            let result = dispatch(&dry_run, command);
+            let changes = dry_run.changes();
+            if output::is_json() {
+                let changes: Vec<String> = changes.iter().map(ToString::to_string).collect();
+                output::result(&serde_json::json!({ "dry_run": changes }))?;
+            } else {
+                if changes.is_empty() {
+                    println!("Dry run: no files would be changed");
+                }
+                for change in &changes {
+                    println!("{}", change);
+                }
+            }
+            result
+        }
+    }
+}
+
+/// 以用法错误退出：命令行中有 `--json` 时把错误作为 `kind` 为 `usage` 的 JSON 对象输出到 stdout 并以
+/// `EXIT_USAGE` 退出，否则由 clap 打印错误和用法说明。参数解析失败时还没有解析结果，所以直接在原始参数中查找
+/// `--json` 和子命令名；`--help` 和 `--version` 不是错误，照常由 clap 输出
+fn exit_usage(error: clap::Error) -> ! {
+    let args: Vec<OsString> = std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .collect();
+    if !error.use_stderr() || !args.iter().any(|arg| arg == "--json") {
+        error.exit();
+    }
+    let mut names = Vec::new();
+    let mut command = Cli::command();
+    for arg in &args {
+        if let Some(subcommand) = command.find_subcommand(arg).cloned() {
+            names.push(subcommand.get_name().to_string());
+            command = subcommand;
+        }
+    }
+    // 只取第一段（到空行为止）合并为一行，去掉 clap 的 `error: ` 前缀和后面的用法说明
+    let rendered = error.render().to_string();
+    let message = rendered
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let message = message.strip_prefix("error: ").unwrap_or(&message);
+    println!("{}", output::usage_failure(&names.join(" "), message));
+    std::process::exit(EXIT_USAGE);
+}
+
+/// 子命令的名称，子命令组中为 `meta list` 这样的形式
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let Some((name, subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    names.join(" ")
+}
+
+fn dispatch(fs: &dyn Vfs, command: PngMeArgs) -> Result<()> {
+    match command {
+        PngMeArgs::Encode(args) => commands::encode(fs, args),
+        PngMeArgs::Decode(args) => commands::decode(fs, args),
+        PngMeArgs::Remove(args) => commands::remove(fs, args),
+        PngMeArgs::ListLabels(args) => commands::list_labels(fs, args),
+        PngMeArgs::Print(args) => commands::print_chunks(fs, args),
+        PngMeArgs::Scan(args) => commands::scan(fs, args),
+        PngMeArgs::Grep(args) => commands::grep(fs, args),
+        PngMeArgs::Diff(args) => commands::diff(fs, args),
+        PngMeArgs::Capacity(args) => commands::capacity(fs, args),
+        PngMeArgs::Detect(args) => commands::detect(fs, args),
+        PngMeArgs::Audit(args) => commands::audit(fs, args),
+        PngMeArgs::Repair(args) => commands::repair(fs, args),
+        PngMeArgs::Strip(args) => commands::strip(fs, args),
+        PngMeArgs::Merge(args) => commands::merge(fs, args),
+        PngMeArgs::Optimize(args) => commands::optimize(fs, args),
+        PngMeArgs::Canonicalize(args) => commands::canonicalize(fs, args),
+        PngMeArgs::ExtractAll(args) => commands::extract_all(fs, args),
+        PngMeArgs::Assemble(args) => commands::assemble(fs, args),
+        PngMeArgs::Recover(args) => commands::recover(fs, args),
+        PngMeArgs::Profile(args) => commands::profile(fs, args),
+        PngMeArgs::Sign(args) => commands::sign(fs, args),
+        PngMeArgs::Verify(args) => commands::verify(fs, args),
+        PngMeArgs::Manifest(command) => commands::manifest(fs, command),
+        PngMeArgs::Stamp(args) => commands::stamp(fs, args),
+        PngMeArgs::Metadata(args) => commands::metadata(fs, args),
+        PngMeArgs::Meta(command) => commands::meta(fs, command),
+        PngMeArgs::Touch(args) => commands::touch(fs, args, &SystemClock),
+        PngMeArgs::SetDpi(args) => commands::set_dpi(fs, args),
+        PngMeArgs::SetGamma(args) => commands::set_gamma(fs, args),
+        PngMeArgs::Exif(command) => commands::exif(fs, command),
+        PngMeArgs::Icc(command) => commands::icc(fs, command),
+        PngMeArgs::Watermark(command) => commands::watermark(fs, command),
+        PngMeArgs::Trace(args) => commands::trace(fs, args),
+        PngMeArgs::Watch(args) => watch::run(fs, args),
+        #[cfg(unix)]
+        PngMeArgs::Daemon(args) => daemon::run(args),
+    }
+}
@@ -0,0 +1,883 @@
+//! # Metadata
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了常用的元数据 chunk：
+//!
+//! ```text
+//! pHYs: | 水平每单位像素数 (u32) | 垂直每单位像素数 (u32) | 单位 (0 未知，1 米) |
+//! tIME: | 年 (u16) | 月 | 日 | 时 | 分 | 秒 |
+//! gAMA: | gamma × 100000 (u32) |
+//! sRGB: | 渲染意图 (0 到 3) |
+//! cHRM: | 白点 x | 白点 y | 红 x | 红 y | 绿 x | 绿 y | 蓝 x | 蓝 y |，每项为 u32，× 100000
+//! ```
+//!
+//! 以及嵌入的 ICC 配置 `iCCP: | 名称 | 0 | 压缩方法 (0) | zlib 压缩的配置 |`，解压时受 `Limits` 限制。
+//!
+//! 并提供从 CSV 映射表读取批量编辑的功能：每行为 `文件名,字段,值`，
+//! 字段 `dpi` 写入 pHYs，`time` 写入 tIME（UTC），其他字段作为文本关键字写入 tEXt/iTXt。
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
+use std::time::SystemTime;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::clock::{Clock, UtcDateTime};
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::{Error, PngmeError, Result};
+
+/// 一英寸的米数
+const METERS_PER_INCH: f64 = 0.0254;
+/// gAMA 和 cHRM 中定点数的单位
+const FIXED_POINT: f64 = 100_000.0;
+
+/// 像素的物理尺寸（pHYs）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    /// 单位是否为米；为假时只表示像素的宽高比
+    pub meters: bool,
+}
+
+/// 最后修改时间（tIME），总是 UTC，字段与 PNG 规范一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeChunk {
+    pub year: u16,
+    /// 1 到 12
+    pub month: u8,
+    /// 1 到 31
+    pub day: u8,
+    /// 0 到 23
+    pub hour: u8,
+    /// 0 到 59
+    pub minute: u8,
+    /// 0 到 60，60 表示闰秒
+    pub second: u8,
+}
+
+/// 图像 gamma（gAMA），即显示时解码指数的倒数，以 100000 为单位存储，例如 1/2.2 存为 45455
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gamma(pub u32);
+
+/// sRGB 色彩空间的渲染意图（sRGB）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderingIntent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+/// 白点和三原色的 CIE 1931 色度坐标 (x, y)（cHRM），以 100000 为单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chromaticities {
+    pub white: (u32, u32),
+    pub red: (u32, u32),
+    pub green: (u32, u32),
+    pub blue: (u32, u32),
+}
+
+/// 嵌入的 ICC 配置（iCCP）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IccProfile {
+    /// 配置名称，1 到 79 字节的 Latin-1 文本
+    pub name: String,
+    /// 解压后的 ICC 配置数据
+    pub profile: Vec<u8>,
+}
+
+/// CSV 映射表中的一行编辑
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataEdit {
+    /// 记录的序号（含表头），从 1 开始；带引号的字段可以跨行，因此不一定等于行号
+    pub row: usize,
+    pub file: String,
+    pub field: String,
+    pub value: String,
+}
+
+impl PhysicalDimensions {
+    pub const CHUNK_TYPE: &'static str = "pHYs";
+
+    /// 按 DPI 创建，水平和垂直方向相同
+    pub fn from_dpi(dpi: f64) -> Result<Self> {
+        let pixels_per_meter = (dpi / METERS_PER_INCH).round();
+        if !(1.0..=u32::MAX as f64).contains(&pixels_per_meter) {
+            return Err(invalid(format!("DPI {} is out of range", dpi)));
+        }
+        Ok(PhysicalDimensions {
+            pixels_per_unit_x: pixels_per_meter as u32,
+            pixels_per_unit_y: pixels_per_meter as u32,
+            meters: true,
+        })
+    }
+
+    /// 水平方向的 DPI，单位未知时返回 `None`
+    pub fn dpi(&self) -> Option<f64> {
+        self.meters
+            .then_some(self.pixels_per_unit_x as f64 * METERS_PER_INCH)
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        let mut data = self.pixels_per_unit_x.to_be_bytes().to_vec();
+        data.extend_from_slice(&self.pixels_per_unit_y.to_be_bytes());
+        data.push(u8::from(self.meters));
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for PhysicalDimensions {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 9)?;
+        let meters = match data[8] {
+            0 => false,
+            1 => true,
+            unit => return Err(invalid(format!("unknown pHYs unit {}", unit))),
+        };
+        Ok(PhysicalDimensions {
+            pixels_per_unit_x: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            pixels_per_unit_y: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            meters,
+        })
+    }
+}
+
+impl Gamma {
+    pub const CHUNK_TYPE: &'static str = "gAMA";
+    /// sRGB 的近似 gamma，1/2.2
+    pub const SRGB: Gamma = Gamma(45455);
+
+    /// 由图像 gamma 创建，例如 0.45455
+    pub fn new(gamma: f64) -> Result<Self> {
+        let scaled = (gamma * FIXED_POINT).round();
+        if !(1.0..=u32::MAX as f64).contains(&scaled) {
+            return Err(invalid(format!("gamma {} is out of range", gamma)));
+        }
+        Ok(Gamma(scaled as u32))
+    }
+
+    /// 由显示时的解码指数创建，例如 2.2 得到 gamma 0.45455
+    pub fn from_exponent(exponent: f64) -> Result<Self> {
+        if exponent.is_nan() || exponent <= 0.0 {
+            return Err(invalid(format!(
+                "gamma exponent {} is out of range",
+                exponent
+            )));
+        }
+        Gamma::new(1.0 / exponent)
+    }
+
+    /// 图像 gamma
+    pub fn value(self) -> f64 {
+        f64::from(self.0) / FIXED_POINT
+    }
+
+    /// 显示时的解码指数，即 gamma 的倒数
+    pub fn exponent(self) -> f64 {
+        FIXED_POINT / f64::from(self.0)
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        new_chunk(Self::CHUNK_TYPE, self.0.to_be_bytes().to_vec())
+    }
+}
+
+impl TryFrom<&Chunk> for Gamma {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 4)?;
+        match u32_at(data, 0) {
+            0 => Err(invalid("gAMA must not be zero".to_string())),
+            gamma => Ok(Gamma(gamma)),
+        }
+    }
+}
+
+impl RenderingIntent {
+    pub const CHUNK_TYPE: &'static str = "sRGB";
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        new_chunk(Self::CHUNK_TYPE, vec![self as u8])
+    }
+}
+
+impl TryFrom<&Chunk> for RenderingIntent {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        match fixed_data(chunk, Self::CHUNK_TYPE, 1)?[0] {
+            0 => Ok(RenderingIntent::Perceptual),
+            1 => Ok(RenderingIntent::RelativeColorimetric),
+            2 => Ok(RenderingIntent::Saturation),
+            3 => Ok(RenderingIntent::AbsoluteColorimetric),
+            intent => Err(invalid(format!("unknown sRGB rendering intent {}", intent))),
+        }
+    }
+}
+
+impl fmt::Display for RenderingIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RenderingIntent::Perceptual => "perceptual",
+            RenderingIntent::RelativeColorimetric => "relative colorimetric",
+            RenderingIntent::Saturation => "saturation",
+            RenderingIntent::AbsoluteColorimetric => "absolute colorimetric",
+        })
+    }
+}
+
+impl Chromaticities {
+    pub const CHUNK_TYPE: &'static str = "cHRM";
+    /// sRGB（ITU-R BT.709 原色，D65 白点），规范建议与 sRGB chunk 一起写入
+    pub const SRGB: Chromaticities = Chromaticities {
+        white: (31270, 32900),
+        red: (64000, 33000),
+        green: (30000, 60000),
+        blue: (15000, 6000),
+    };
+
+    /// 按白点、红、绿、蓝的顺序返回浮点坐标
+    pub fn points(&self) -> [(f64, f64); 4] {
+        [self.white, self.red, self.green, self.blue]
+            .map(|(x, y)| (f64::from(x) / FIXED_POINT, f64::from(y) / FIXED_POINT))
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        let data = [self.white, self.red, self.green, self.blue]
+            .iter()
+            .flat_map(|&(x, y)| [x.to_be_bytes(), y.to_be_bytes()])
+            .flatten()
+            .collect();
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Chromaticities {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 32)?;
+        let point = |i: usize| (u32_at(data, i * 8), u32_at(data, i * 8 + 4));
+        Ok(Chromaticities {
+            white: point(0),
+            red: point(1),
+            green: point(2),
+            blue: point(3),
+        })
+    }
+}
+
+impl TimeChunk {
+    pub const CHUNK_TYPE: &'static str = "tIME";
+
+    /// 检查各字段后创建
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Self> {
+        let time = TimeChunk {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        };
+        time.validate()?;
+        Ok(time)
+    }
+
+    /// `clock` 的当前时间
+    pub fn now(clock: &dyn Clock) -> Result<Self> {
+        TimeChunk::from_system_time(clock.now())
+    }
+
+    /// 解析 `YYYY-MM-DD`、`YYYY-MM-DDTHH:MM:SS` 或 `YYYY-MM-DD HH:MM:SS`，可以带结尾的 `Z`
+    pub fn parse(s: &str) -> Result<Self> {
+        let error = || {
+            invalid(format!(
+                "expected a UTC time like 2026-10-16T08:30:00, found {:?}",
+                s
+            ))
+        };
+        let s = s.trim().trim_end_matches('Z');
+        let (date, time) = match s.split_once(['T', ' ']) {
+            Some((date, time)) => (date, time),
+            None => (s, "00:00:00"),
+        };
+        let numbers = |part: &str, separator: char| -> Option<Vec<u32>> {
+            part.split(separator).map(|n| n.parse().ok()).collect()
+        };
+        let (date, time) = (
+            numbers(date, '-').ok_or_else(error)?,
+            numbers(time, ':').ok_or_else(error)?,
+        );
+        let [year, month, day] = date[..] else {
+            return Err(error());
+        };
+        let [hour, minute, second] = time[..] else {
+            return Err(error());
+        };
+        let field = |n: u32| u8::try_from(n).map_err(|_| error());
+        TimeChunk::new(
+            u16::try_from(year).map_err(|_| error())?,
+            field(month)?,
+            field(day)?,
+            field(hour)?,
+            field(minute)?,
+            field(second)?,
+        )
+    }
+
+    /// 检查各字段的范围（秒允许为 60，表示闰秒）
+    pub fn validate(&self) -> Result<()> {
+        let valid = (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour <= 23
+            && self.minute <= 59
+            && self.second <= 60;
+        if !valid {
+            return Err(invalid(format!("{} is not a valid tIME", self)));
+        }
+        Ok(())
+    }
+
+    /// 由 `UtcDateTime` 转换，年份超出 0 到 65535 时报错
+    pub fn from_utc(time: UtcDateTime) -> Result<Self> {
+        let year = u16::try_from(time.year)
+            .map_err(|_| invalid(format!("year {} does not fit in tIME", time.year)))?;
+        TimeChunk::new(
+            year,
+            time.month,
+            time.day,
+            time.hour,
+            time.minute,
+            time.second,
+        )
+    }
+
+    pub fn to_utc(self) -> UtcDateTime {
+        UtcDateTime {
+            year: i64::from(self.year),
+            month: self.month,
+            day: self.day,
+            hour: self.hour,
+            minute: self.minute,
+            second: self.second,
+        }
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Result<Self> {
+        TimeChunk::from_utc(UtcDateTime::from_system_time(time))
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        self.to_utc().to_system_time()
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(self) -> Chunk {
+        let mut data = self.year.to_be_bytes().to_vec();
+        data.extend_from_slice(&[self.month, self.day, self.hour, self.minute, self.second]);
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for TimeChunk {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let data = fixed_data(chunk, Self::CHUNK_TYPE, 7)?;
+        TimeChunk::new(
+            u16::from_be_bytes([data[0], data[1]]),
+            data[2],
+            data[3],
+            data[4],
+            data[5],
+            data[6],
+        )
+    }
+}
+
+impl fmt::Display for TimeChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<TimeChunk> for chrono::DateTime<chrono::Utc> {
+    type Error = Error;
+
+    /// 日期不存在（例如 2 月 30 日）时报错，闰秒按 chrono 的约定表示为 59 秒加 1 秒的纳秒
+    fn try_from(time: TimeChunk) -> Result<Self> {
+        let (second, nano) = match time.second {
+            60 => (59, 1_000_000_000),
+            second => (u32::from(second), 0),
+        };
+        chrono::NaiveDate::from_ymd_opt(
+            i32::from(time.year),
+            u32::from(time.month),
+            u32::from(time.day),
+        )
+        .and_then(|date| {
+            date.and_hms_nano_opt(u32::from(time.hour), u32::from(time.minute), second, nano)
+        })
+        .map(|time| time.and_utc())
+        .ok_or_else(|| invalid(format!("{} is not a valid date", time)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for TimeChunk {
+    type Error = Error;
+
+    /// 舍去秒以下的部分，年份超出 0 到 65535 时报错
+    fn try_from(time: chrono::DateTime<chrono::Utc>) -> Result<Self> {
+        use chrono::{Datelike, Timelike};
+
+        let year = u16::try_from(time.year())
+            .map_err(|_| invalid(format!("year {} does not fit in tIME", time.year())))?;
+        let leap = u8::from(time.nanosecond() >= 1_000_000_000);
+        TimeChunk::new(
+            year,
+            time.month() as u8,
+            time.day() as u8,
+            time.hour() as u8,
+            time.minute() as u8,
+            time.second() as u8 + leap,
+        )
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<TimeChunk> for time::OffsetDateTime {
+    type Error = Error;
+
+    /// 日期不存在时报错；`time` 不支持闰秒，60 秒按 59 秒处理
+    fn try_from(chunk: TimeChunk) -> Result<Self> {
+        let error = |_| invalid(format!("{} is not a valid date", chunk));
+        let month = time::Month::try_from(chunk.month).map_err(error)?;
+        let date = time::Date::from_calendar_date(i32::from(chunk.year), month, chunk.day)
+            .map_err(error)?;
+        let clock =
+            time::Time::from_hms(chunk.hour, chunk.minute, chunk.second.min(59)).map_err(error)?;
+        Ok(time::PrimitiveDateTime::new(date, clock).assume_utc())
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for TimeChunk {
+    type Error = Error;
+
+    /// 先换算到 UTC 再舍去秒以下的部分，年份超出 0 到 65535 时报错
+    fn try_from(time: time::OffsetDateTime) -> Result<Self> {
+        let time = time.to_offset(time::UtcOffset::UTC);
+        let year = u16::try_from(time.year())
+            .map_err(|_| invalid(format!("year {} does not fit in tIME", time.year())))?;
+        TimeChunk::new(
+            year,
+            u8::from(time.month()),
+            time.day(),
+            time.hour(),
+            time.minute(),
+            time.second(),
+        )
+    }
+}
+
+impl IccProfile {
+    pub const CHUNK_TYPE: &'static str = "iCCP";
+
+    /// 编码为 chunk，配置数据会被压缩
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let name: Vec<u8> = self
+            .name
+            .chars()
+            .map(|c| u8::try_from(c as u32).ok().filter(|&b| b != 0))
+            .collect::<Option<_>>()
+            .filter(|name: &Vec<u8>| (1..=79).contains(&name.len()))
+            .ok_or_else(|| invalid(format!("invalid iCCP profile name {:?}", self.name)))?;
+        let mut encoder = ZlibEncoder::new(name, Compression::default());
+        encoder.get_mut().extend_from_slice(&[0, 0]);
+        encoder.write_all(&self.profile)?;
+        let chunk_type = ChunkType::from_str(Self::CHUNK_TYPE).expect("iCCP is a valid chunk type");
+        Chunk::new(chunk_type, encoder.finish()?)
+    }
+
+    /// 与 `TryFrom<&Chunk>` 相同，但按指定的安全限制解压
+    pub fn from_chunk_with_limits(chunk: &Chunk, limits: &Limits) -> Result<Self> {
+        if chunk.chunk_type().to_string() != Self::CHUNK_TYPE {
+            return Err(invalid(format!(
+                "expected a {} chunk, found {}",
+                Self::CHUNK_TYPE,
+                chunk.chunk_type()
+            )));
+        }
+        let data = chunk.data();
+        let separator = data
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid("iCCP profile name is not terminated".to_string()))?;
+        let (name, rest) = data.split_at(separator);
+        match rest.get(1) {
+            Some(0) => {}
+            Some(method) => {
+                return Err(invalid(format!(
+                    "unknown iCCP compression method {}",
+                    method
+                )))
+            }
+            None => return Err(invalid("iCCP compression method is missing".to_string())),
+        }
+        let profile = limits.inflate(&rest[2..]).map_err(|e| match e {
+            PngmeError::Io(e) => invalid(format!("failed to decompress iCCP profile: {}", e)),
+            e => e,
+        })?;
+        Ok(IccProfile {
+            name: name.iter().map(|&b| b as char).collect(),
+            profile,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for IccProfile {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        IccProfile::from_chunk_with_limits(chunk, &Limits::default())
+    }
+}
+
+/// 解析 CSV 映射表。第一行是 `filename,field,value` 表头时会被跳过，空行会被忽略
+pub fn parse_edits(csv: &str) -> Result<Vec<MetadataEdit>> {
+    let mut edits = Vec::new();
+    for (index, record) in parse_csv(csv)?.into_iter().enumerate() {
+        let row = index + 1;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let [file, field, value] = &record[..] else {
+            return Err(invalid(format!(
+                "row {}: expected 3 columns (filename, field, value), found {}",
+                row,
+                record.len()
+            )));
+        };
+        if row == 1 && file.eq_ignore_ascii_case("filename") && field.eq_ignore_ascii_case("field")
+        {
+            continue;
+        }
+        edits.push(MetadataEdit {
+            row,
+            file: file.trim().to_string(),
+            field: field.trim().to_string(),
+            value: value.clone(),
+        });
+    }
+    Ok(edits)
+}
+
+/// 对 PNG 应用一条编辑
+pub fn apply_edit(png: &mut Png, field: &str, value: &str) -> Result<()> {
+    match field.to_ascii_lowercase().as_str() {
+        "dpi" => {
+            let dpi: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid(format!("DPI must be a number, found {:?}", value)))?;
+            png.replace_chunk(PhysicalDimensions::from_dpi(dpi)?.to_chunk());
+        }
+        "time" => png.replace_chunk(TimeChunk::parse(value)?.to_chunk()),
+        _ => png.set_text(field, value)?,
+    }
+    Ok(())
+}
+
+/// 按 RFC 4180 解析 CSV：字段可以用双引号包围，引号内的 `""` 表示一个双引号
+pub(crate) fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid("CSV ends inside a quoted field".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn fixed_data<'a>(chunk: &'a Chunk, chunk_type: &str, length: usize) -> Result<&'a [u8]> {
+    if chunk.chunk_type().to_string() != chunk_type {
+        return Err(invalid(format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        )));
+    }
+    if chunk.data().len() != length {
+        return Err(invalid(format!(
+            "{} must be {} bytes, found {}",
+            chunk_type,
+            length,
+            chunk.data().len()
+        )));
+    }
+    Ok(chunk.data())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+    let chunk_type = ChunkType::from_str(chunk_type).expect("standard chunk types are valid");
+    Chunk::new(chunk_type, data).expect("fixed-size metadata is small")
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidMetadata(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_physical_dimensions() {
+        let phys = PhysicalDimensions::from_dpi(300.0).unwrap();
+        assert_eq!(phys.pixels_per_unit_x, 11811);
+        assert!((phys.dpi().unwrap() - 300.0).abs() < 0.01);
+
+        let chunk = phys.to_chunk();
+        assert_eq!(chunk.data(), &[0, 0, 0x2e, 0x23, 0, 0, 0x2e, 0x23, 1]);
+        assert_eq!(PhysicalDimensions::try_from(&chunk).unwrap(), phys);
+        assert!(PhysicalDimensions::from_dpi(0.0).is_err());
+        assert!(PhysicalDimensions::from_dpi(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_gamma() {
+        let gamma = Gamma::from_exponent(2.2).unwrap();
+        assert_eq!(gamma, Gamma::SRGB);
+        assert!((gamma.exponent() - 2.2).abs() < 0.001);
+        assert_eq!(Gamma::new(0.45455).unwrap(), gamma);
+
+        let chunk = gamma.to_chunk();
+        assert_eq!(chunk.data(), &[0, 0, 0xb1, 0x8f]);
+        assert_eq!(Gamma::try_from(&chunk).unwrap(), gamma);
+        for bad in [0.0, -1.0, f64::NAN] {
+            assert!(Gamma::from_exponent(bad).is_err(), "{}", bad);
+        }
+        assert!(Gamma::try_from(&new_chunk("gAMA", vec![0; 4])).is_err());
+    }
+
+    #[test]
+    fn test_srgb_and_chromaticities() {
+        let intent = RenderingIntent::RelativeColorimetric;
+        assert_eq!(intent.to_chunk().data(), &[1]);
+        assert_eq!(
+            RenderingIntent::try_from(&intent.to_chunk()).unwrap(),
+            intent
+        );
+        assert!(RenderingIntent::try_from(&new_chunk("sRGB", vec![4])).is_err());
+
+        let chunk = Chromaticities::SRGB.to_chunk();
+        assert_eq!(chunk.data().len(), 32);
+        assert_eq!(&chunk.data()[..8], &[0, 0, 0x7a, 0x26, 0, 0, 0x80, 0x84]);
+        let chrm = Chromaticities::try_from(&chunk).unwrap();
+        assert_eq!(chrm, Chromaticities::SRGB);
+        assert_eq!(chrm.points()[1], (0.64, 0.33));
+        assert!(Chromaticities::try_from(&Gamma::SRGB.to_chunk()).is_err());
+    }
+
+    #[test]
+    fn test_icc_profile() {
+        let icc = IccProfile {
+            name: "sRGB IEC61966-2.1".to_string(),
+            profile: (0..=255).cycle().take(3000).collect(),
+        };
+        let chunk = icc.to_chunk().unwrap();
+        assert!(chunk.data().starts_with(b"sRGB IEC61966-2.1\0\0"));
+        assert_eq!(IccProfile::try_from(&chunk).unwrap(), icc);
+
+        let bomb = IccProfile {
+            name: "bomb".to_string(),
+            profile: vec![0; 8 << 20],
+        };
+        assert!(matches!(
+            IccProfile::try_from(&bomb.to_chunk().unwrap()),
+            Err(PngmeError::DecompressionBomb { .. })
+        ));
+        let invalid_name = IccProfile {
+            name: String::new(),
+            profile: Vec::new(),
+        };
+        assert!(invalid_name.to_chunk().is_err());
+    }
+
+    #[test]
+    fn test_time_chunk() {
+        let modified = TimeChunk::parse("2026-10-16T08:30:05Z").unwrap();
+        assert_eq!(modified.to_string(), "2026-10-16T08:30:05Z");
+        let chunk = modified.to_chunk();
+        assert_eq!(chunk.data(), &[0x07, 0xea, 10, 16, 8, 30, 5]);
+        assert_eq!(TimeChunk::try_from(&chunk).unwrap(), modified);
+
+        assert_eq!(
+            TimeChunk::parse("2026-10-16").unwrap().to_string(),
+            "2026-10-16T00:00:00Z"
+        );
+        assert!(TimeChunk::parse("2026-10-16 08:30:05").is_ok());
+        for bad in ["2026-13-01", "2026-10-16T24:00:00", "yesterday", "2026-10"] {
+            assert!(
+                matches!(TimeChunk::parse(bad), Err(PngmeError::InvalidMetadata(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_chunk_conversions() {
+        use crate::clock::{Clock, FixedClock};
+
+        let clock = FixedClock::from_unix_secs(1_700_000_000);
+        let time = TimeChunk::now(&clock).unwrap();
+        assert_eq!(time, TimeChunk::new(2023, 11, 14, 22, 13, 20).unwrap());
+        assert_eq!(time.to_system_time(), clock.now());
+        assert_eq!(TimeChunk::from_utc(time.to_utc()).unwrap(), time);
+        assert!(TimeChunk::new(2026, 0, 1, 0, 0, 0).is_err());
+
+        let too_late = UtcDateTime {
+            year: 70_000,
+            ..time.to_utc()
+        };
+        assert!(TimeChunk::from_utc(too_late).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_time_chunk_chrono() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let time = TimeChunk::new(2026, 10, 16, 8, 30, 5).unwrap();
+        let date_time = DateTime::<Utc>::try_from(time).unwrap();
+        assert_eq!(
+            date_time,
+            Utc.with_ymd_and_hms(2026, 10, 16, 8, 30, 5).unwrap()
+        );
+        assert_eq!(TimeChunk::try_from(date_time).unwrap(), time);
+
+        let leap_second = TimeChunk::new(2016, 12, 31, 23, 59, 60).unwrap();
+        let date_time = DateTime::<Utc>::try_from(leap_second).unwrap();
+        assert_eq!(TimeChunk::try_from(date_time).unwrap(), leap_second);
+
+        let february_30 = TimeChunk::new(2026, 2, 30, 0, 0, 0).unwrap();
+        assert!(DateTime::<Utc>::try_from(february_30).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_chunk_time() {
+        use time::{Date, Month, OffsetDateTime, Time, UtcOffset};
+
+        let chunk = TimeChunk::new(2026, 10, 16, 8, 30, 5).unwrap();
+        let date_time = OffsetDateTime::try_from(chunk).unwrap();
+        assert_eq!(
+            date_time.date(),
+            Date::from_calendar_date(2026, Month::October, 16).unwrap()
+        );
+        assert_eq!(date_time.time(), Time::from_hms(8, 30, 5).unwrap());
+        assert_eq!(TimeChunk::try_from(date_time).unwrap(), chunk);
+
+        // 非 UTC 的时间先换算到 UTC
+        let beijing = date_time.to_offset(UtcOffset::from_hms(8, 0, 0).unwrap());
+        assert_eq!(TimeChunk::try_from(beijing).unwrap(), chunk);
+
+        let february_30 = TimeChunk::new(2026, 2, 30, 0, 0, 0).unwrap();
+        assert!(OffsetDateTime::try_from(february_30).is_err());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "filename,field,value\r\na.png,Title,\"Hello, \"\"world\"\"\"\n\nb.png,dpi,300";
+        let edits = parse_edits(csv).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].row, 2);
+        assert_eq!(edits[0].value, "Hello, \"world\"");
+        assert_eq!(
+            (edits[1].file.as_str(), edits[1].field.as_str()),
+            ("b.png", "dpi")
+        );
+
+        let multiline = parse_edits("a.png,Description,\"line 1\nline 2\"\n").unwrap();
+        assert_eq!(multiline[0].value, "line 1\nline 2");
+
+        assert!(parse_edits("a.png,Title\n").is_err());
+        assert!(parse_edits("a.png,Title,\"unterminated\n").is_err());
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        apply_edit(&mut png, "DPI", "72").unwrap();
+        apply_edit(&mut png, "dpi", "300").unwrap();
+        apply_edit(&mut png, "time", "2026-10-16").unwrap();
+        apply_edit(&mut png, "Title", "Dice").unwrap();
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "pHYs", "IDAT", "tIME", "tEXt", "IEND"]);
+        let phys = PhysicalDimensions::try_from(png.chunk_by_type("pHYs").unwrap()).unwrap();
+        assert_eq!(phys.pixels_per_unit_x, 11811);
+
+        assert!(apply_edit(&mut png, "dpi", "high").is_err());
+        assert!(apply_edit(&mut png, "time", "soon").is_err());
+    }
+}
@@ -0,0 +1,461 @@
+//! # Ordering
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块描述了 PNG 规范对 chunk 顺序的约束：
+//!
+//! - `IHDR` 必须是第一个 chunk，`IEND` 必须是最后一个；
+//! - `IDAT` 必须连续出现；`PLTE` 必须在第一个 `IDAT` 之前；
+//! - `cHRM`、`gAMA`、`iCCP`、`sBIT`、`sRGB`、`cICP` 必须在 `PLTE` 和 `IDAT` 之前；
+//! - `tRNS`、`bKGD`、`hIST` 必须在 `PLTE` 之后、`IDAT` 之前；
+//! - `pHYs`、`sPLT`、`eXIf`、`acTL` 必须在 `IDAT` 之前；
+//! - 其余 chunk（包括文本 chunk 和私有 chunk）可以出现在 `IHDR` 与 `IEND` 之间的任意位置。
+//!
+//! APNG 的 `fcTL`/`fdAT` 序号必须从 0 开始连续递增。
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::chunk::Chunk;
+
+/// 插入 chunk 的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkPosition {
+    /// 紧跟在 `IHDR` 之后
+    AfterIhdr,
+    /// 在 `PLTE` 和图像数据之前
+    BeforePlte,
+    /// 在图像数据（第一个 `IDAT`，APNG 中为第一个 `fcTL`）之前
+    BeforeIdat,
+    /// 在 `IEND` 之前
+    BeforeIend,
+    /// 在 APNG 第 n 帧（从 0 开始）的数据之后、下一个 `fcTL` 之前
+    AfterFrame(u32),
+}
+
+/// 违反顺序约束的 chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderingIssue {
+    /// chunk 的下标；缺少 chunk 时为 `None`
+    pub index: Option<usize>,
+    pub chunk_type: String,
+    pub message: String,
+}
+
+/// 必须在 `PLTE` 和 `IDAT` 之前的 chunk
+const BEFORE_PLTE: [&str; 6] = ["cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP"];
+/// 必须在 `PLTE` 之后、`IDAT` 之前的 chunk
+const AFTER_PLTE: [&str; 3] = ["tRNS", "bKGD", "hIST"];
+/// 必须在 `IDAT` 之前的 chunk
+const BEFORE_IDAT: [&str; 4] = ["pHYs", "sPLT", "eXIf", "acTL"];
+/// 最多出现一次的 chunk
+const SINGLE: [&str; 16] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP", "tRNS", "bKGD", "hIST",
+    "pHYs", "tIME", "eXIf", "acTL",
+];
+
+/// 该类型的 chunk 是否最多只能出现一次
+pub fn is_single(chunk_type: &str) -> bool {
+    SINGLE.contains(&chunk_type)
+}
+
+/// 规范形式（见 `Png::canonicalize`）中 chunk 所在的分组，按分组从小到大排列：
+/// `IHDR`、`PLTE` 之前的 chunk、`PLTE`、`PLTE` 之后的 chunk、`IDAT` 之前的 chunk、`IDAT`、
+/// 其余 chunk、`IEND`
+pub fn canonical_rank(chunk_type: &str) -> u8 {
+    match chunk_type {
+        "IHDR" => 0,
+        _ if BEFORE_PLTE.contains(&chunk_type) => 1,
+        "PLTE" => 2,
+        _ if AFTER_PLTE.contains(&chunk_type) => 3,
+        _ if BEFORE_IDAT.contains(&chunk_type) => 4,
+        "IDAT" => 5,
+        "IEND" => 7,
+        _ => 6,
+    }
+}
+
+impl ChunkPosition {
+    /// 按规范为某种 chunk 选择插入位置；`animated` 表示文件是 APNG
+    pub fn for_type(chunk_type: &str, animated: bool) -> ChunkPosition {
+        if BEFORE_PLTE.contains(&chunk_type) {
+            ChunkPosition::BeforePlte
+        } else if AFTER_PLTE.contains(&chunk_type) || BEFORE_IDAT.contains(&chunk_type) {
+            ChunkPosition::BeforeIdat
+        } else if animated {
+            // APNG 中不要把 chunk 放在帧序列中间
+            ChunkPosition::BeforeIdat
+        } else {
+            ChunkPosition::BeforeIend
+        }
+    }
+
+    /// 计算插入下标；找不到参照的 chunk 时依次退回到更靠后的位置
+    pub fn index(self, chunks: &[Chunk]) -> usize {
+        let find = |types: &[&[u8; 4]]| {
+            chunks
+                .iter()
+                .position(|chunk| types.contains(&&chunk.chunk_type().bytes()))
+        };
+        let before_iend = || match chunks.last() {
+            Some(last) if &last.chunk_type().bytes() == b"IEND" => chunks.len() - 1,
+            _ => chunks.len(),
+        };
+        let before_idat = || find(&[b"fcTL", b"IDAT"]).unwrap_or_else(before_iend);
+        match self {
+            ChunkPosition::AfterIhdr => find(&[b"IHDR"]).map_or_else(before_idat, |i| i + 1),
+            ChunkPosition::BeforePlte => {
+                find(&[b"PLTE"]).map_or_else(before_idat, |i| i.min(before_idat()))
+            }
+            ChunkPosition::BeforeIdat => before_idat(),
+            ChunkPosition::BeforeIend => before_iend(),
+            ChunkPosition::AfterFrame(frame) => {
+                frame_range(chunks, frame).map_or_else(before_iend, |range| range.end)
+            }
+        }
+    }
+}
+
+/// APNG 第 `frame` 帧（按 `fcTL` 计数，从 0 开始）占用的 chunk 下标范围：
+/// 从它的 `fcTL` 到下一个 `fcTL`，最后一帧到 `IEND` 之前
+pub fn frame_range(chunks: &[Chunk], frame: u32) -> Option<Range<usize>> {
+    let mut controls = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| &chunk.chunk_type().bytes() == b"fcTL")
+        .map(|(index, _)| index)
+        .skip(frame as usize);
+    let start = controls.next()?;
+    let end = controls.next().unwrap_or_else(|| match chunks.last() {
+        Some(last) if &last.chunk_type().bytes() == b"IEND" => chunks.len() - 1,
+        _ => chunks.len(),
+    });
+    Some(start..end)
+}
+
+impl fmt::Display for OrderingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "chunk {} ({}): {}", index, self.chunk_type, self.message),
+            None => write!(f, "{}: {}", self.chunk_type, self.message),
+        }
+    }
+}
+
+/// 检查 chunk 顺序，返回所有违反规范的地方
+pub fn validate(chunks: &[Chunk]) -> Vec<OrderingIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |index: Option<usize>, chunk_type: &str, message: &str| {
+        issues.push(OrderingIssue {
+            index,
+            chunk_type: chunk_type.to_string(),
+            message: message.to_string(),
+        })
+    };
+
+    let types: Vec<String> = chunks
+        .iter()
+        .map(|chunk| chunk.chunk_type().to_string())
+        .collect();
+    let first = |name: &str| types.iter().position(|t| t == name);
+    let first_idat = first("IDAT");
+    let plte = first("PLTE");
+
+    // Apple 的 CgBI 格式在 IHDR 之前多一个 CgBI chunk
+    let header_index = usize::from(types.first().is_some_and(|t| t == "CgBI"));
+    match types.get(header_index) {
+        Some(t) if t == "IHDR" => {}
+        _ => issue(None, "IHDR", "the first chunk must be IHDR"),
+    }
+    if types.last().is_none_or(|t| t != "IEND") {
+        issue(None, "IEND", "the last chunk must be IEND");
+    }
+    if first_idat.is_none() {
+        issue(None, "IDAT", "there must be at least one IDAT chunk");
+    }
+
+    let mut seen: Vec<&str> = Vec::new();
+    let mut idat_ended = false;
+    for (index, t) in types.iter().enumerate() {
+        let t = t.as_str();
+        if SINGLE.contains(&t) && seen.contains(&t) {
+            issue(Some(index), t, "must not appear more than once");
+        }
+        seen.push(t);
+
+        if t == "IDAT" {
+            if idat_ended {
+                issue(Some(index), t, "IDAT chunks must be consecutive");
+            }
+        } else if first_idat.is_some_and(|i| i < index) && !idat_ended {
+            idat_ended = true;
+        }
+
+        let after_idat = first_idat.is_some_and(|i| index > i);
+        let after_plte = plte.is_some_and(|i| index > i);
+        if (t == "PLTE" || BEFORE_IDAT.contains(&t)) && after_idat {
+            issue(Some(index), t, "must appear before the first IDAT");
+        }
+        if BEFORE_PLTE.contains(&t) && (after_idat || after_plte) {
+            issue(Some(index), t, "must appear before PLTE and IDAT");
+        }
+        if AFTER_PLTE.contains(&t) {
+            if after_idat {
+                issue(Some(index), t, "must appear before the first IDAT");
+            }
+            if plte.is_some_and(|i| index < i) {
+                issue(Some(index), t, "must appear after PLTE");
+            }
+        }
+        if index + 1 < types.len() && t == "IEND" {
+            issue(Some(index), t, "nothing may follow IEND");
+        }
+    }
+    if seen.contains(&"iCCP") && seen.contains(&"sRGB") {
+        issue(None, "iCCP", "iCCP and sRGB must not both be present");
+    }
+
+    let mut expected = 0u32;
+    for (index, chunk) in chunks.iter().enumerate() {
+        if let Some(sequence) = sequence_number(chunk) {
+            if sequence != expected {
+                issues.push(OrderingIssue {
+                    index: Some(index),
+                    chunk_type: chunk.chunk_type().to_string(),
+                    message: format!("sequence number {} should be {}", sequence, expected),
+                });
+            }
+            expected = sequence.wrapping_add(1);
+        }
+    }
+    issues
+}
+
+/// 把违反顺序约束的 chunk 移到规范为其类型选择的位置，其余 chunk 保持原来的相对顺序，
+/// 已经符合规范的 chunk 列表不会改变。返回是否移动了 chunk。会移动的只有：不在最前面的 `IHDR`
+/// （Apple 的 `CgBI` 之后）、不在最后的 `IEND`、第一段之后的 `IDAT`（接到第一段末尾），
+/// 以及出现在 `PLTE` 或 `IDAT` 之后等不允许位置的受限 chunk（见 `ChunkPosition::for_type`）。
+/// 重复出现的 chunk、APNG 的序号等不能靠移动解决的问题保持原样
+pub fn normalize(chunks: &mut Vec<Chunk>) -> bool {
+    let original = chunks.clone();
+    let is = |chunk: &Chunk, name: &[u8; 4]| &chunk.chunk_type().bytes() == name;
+    let find = |chunks: &[Chunk], name: &[u8; 4]| chunks.iter().position(|chunk| is(chunk, name));
+
+    let header_index = usize::from(chunks.first().is_some_and(|chunk| is(chunk, b"CgBI")));
+    if let Some(index) = find(chunks, b"IHDR").filter(|&index| index != header_index) {
+        let ihdr = chunks.remove(index);
+        chunks.insert(header_index.min(chunks.len()), ihdr);
+    }
+    if let Some(index) = find(chunks, b"IEND") {
+        let iend = chunks.remove(index);
+        chunks.push(iend);
+    }
+    if let Some(first) = find(chunks, b"IDAT") {
+        let (idat, rest): (Vec<Chunk>, Vec<Chunk>) =
+            chunks.drain(first..).partition(|chunk| is(chunk, b"IDAT"));
+        chunks.extend(idat);
+        chunks.extend(rest);
+    }
+
+    let animated = find(chunks, b"acTL").is_some();
+    for _ in 0..chunks.len() {
+        // 先移动 PLTE，其他 chunk 的合法位置取决于它
+        let misplaced = |plte_only: bool| {
+            (0..chunks.len()).find(|&index| {
+                (!plte_only || is(&chunks[index], b"PLTE")) && is_misplaced(chunks, index)
+            })
+        };
+        let Some(index) = misplaced(true).or_else(|| misplaced(false)) else {
+            break;
+        };
+        let chunk = chunks.remove(index);
+        let chunk_type = chunk.chunk_type().to_string();
+        let position = match chunk_type.as_str() {
+            "PLTE" => ChunkPosition::BeforeIdat,
+            _ => ChunkPosition::for_type(&chunk_type, animated),
+        };
+        chunks.insert(position.index(chunks), chunk);
+    }
+    *chunks != original
+}
+
+/// 第 `index` 个 chunk 是否出现在其类型不允许的位置
+fn is_misplaced(chunks: &[Chunk], index: usize) -> bool {
+    let chunk_type = chunks[index].chunk_type().to_string();
+    let t = chunk_type.as_str();
+    let any = |chunks: &[Chunk], name: &[u8; 4]| {
+        chunks
+            .iter()
+            .any(|chunk| &chunk.chunk_type().bytes() == name)
+    };
+    let after_idat = any(&chunks[..index], b"IDAT");
+    let restricted = t == "PLTE"
+        || BEFORE_PLTE.contains(&t)
+        || AFTER_PLTE.contains(&t)
+        || BEFORE_IDAT.contains(&t);
+    (restricted && after_idat)
+        || (BEFORE_PLTE.contains(&t) && any(&chunks[..index], b"PLTE"))
+        || (AFTER_PLTE.contains(&t) && any(&chunks[index + 1..], b"PLTE"))
+}
+
+/// 读取 fcTL 或 fdAT 的序号
+fn sequence_number(chunk: &Chunk) -> Option<u32> {
+    match &chunk.chunk_type().bytes() {
+        b"fcTL" | b"fdAT" if chunk.data().len() >= 4 => {
+            let data = chunk.data();
+            Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]]))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+
+    fn chunks(types: &[&str]) -> Vec<Chunk> {
+        types
+            .iter()
+            .map(|t| Chunk::new(ChunkType::from_str(t).unwrap(), Vec::new()).unwrap())
+            .collect()
+    }
+
+    fn messages(types: &[&str]) -> Vec<String> {
+        validate(&chunks(types))
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_ordering() {
+        assert!(
+            messages(&["IHDR", "gAMA", "PLTE", "tRNS", "IDAT", "IDAT", "tEXt", "IEND"]).is_empty()
+        );
+        assert!(messages(&["CgBI", "IHDR", "IDAT", "IEND"]).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_ordering() {
+        assert_eq!(
+            messages(&["IDAT", "IHDR", "IEND"]),
+            ["IHDR: the first chunk must be IHDR"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "IDAT", "tEXt", "IDAT", "IEND"]),
+            ["chunk 3 (IDAT): IDAT chunks must be consecutive"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "PLTE", "gAMA", "IDAT", "IEND"]),
+            ["chunk 2 (gAMA): must appear before PLTE and IDAT"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "tRNS", "PLTE", "IDAT", "IEND"]),
+            ["chunk 1 (tRNS): must appear after PLTE"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "IDAT", "pHYs", "IEND"]),
+            ["chunk 2 (pHYs): must appear before the first IDAT"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "IDAT", "IEND", "tEXt"]),
+            [
+                "IEND: the last chunk must be IEND",
+                "chunk 2 (IEND): nothing may follow IEND"
+            ]
+        );
+        assert_eq!(
+            messages(&["IHDR", "gAMA", "gAMA", "IDAT", "IEND"]),
+            ["chunk 2 (gAMA): must not appear more than once"]
+        );
+        assert_eq!(
+            messages(&["IHDR", "IEND"]),
+            ["IDAT: there must be at least one IDAT chunk"]
+        );
+    }
+
+    #[test]
+    fn test_apng_sequence_numbers() {
+        use crate::apng::tests::frame_control;
+
+        let mut list = chunks(&["IHDR", "acTL"]);
+        list.push(frame_control(0, 1).to_chunk());
+        list.extend(chunks(&["IDAT"]));
+        list.push(frame_control(2, 1).to_chunk());
+        list.extend(chunks(&["IEND"]));
+        let issues = validate(&list);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "chunk 4 (fcTL): sequence number 2 should be 1"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalized = |types: &[&str]| {
+            let mut list = chunks(types);
+            let moved = normalize(&mut list);
+            assert!(validate(&list).is_empty(), "{:?}", validate(&list));
+            let types: Vec<String> = list.iter().map(|c| c.chunk_type().to_string()).collect();
+            (moved, types)
+        };
+        // 符合规范的顺序原样保留，包括可以任意放置的 chunk
+        let valid = [
+            "IHDR", "pHYs", "ruSt", "gAMA", "PLTE", "IDAT", "tEXt", "IEND",
+        ];
+        assert_eq!(
+            normalized(&valid),
+            (false, valid.map(String::from).to_vec())
+        );
+
+        let (moved, types) = normalized(&[
+            "IDAT", "IHDR", "tRNS", "PLTE", "IDAT", "tEXt", "IDAT", "IEND", "gAMA", "zzZz",
+        ]);
+        assert!(moved);
+        assert_eq!(
+            types,
+            ["IHDR", "gAMA", "PLTE", "tRNS", "IDAT", "IDAT", "IDAT", "tEXt", "zzZz", "IEND"]
+        );
+    }
+
+    #[test]
+    fn test_position_for_type() {
+        use ChunkPosition::*;
+        assert_eq!(ChunkPosition::for_type("gAMA", false), BeforePlte);
+        assert_eq!(ChunkPosition::for_type("tRNS", false), BeforeIdat);
+        assert_eq!(ChunkPosition::for_type("pHYs", false), BeforeIdat);
+        assert_eq!(ChunkPosition::for_type("ruSt", false), BeforeIend);
+        assert_eq!(ChunkPosition::for_type("ruSt", true), BeforeIdat);
+    }
+
+    #[test]
+    fn test_position_index() {
+        let list = chunks(&["IHDR", "PLTE", "IDAT", "IEND"]);
+        assert_eq!(ChunkPosition::AfterIhdr.index(&list), 1);
+        assert_eq!(ChunkPosition::BeforePlte.index(&list), 1);
+        assert_eq!(ChunkPosition::BeforeIdat.index(&list), 2);
+        assert_eq!(ChunkPosition::BeforeIend.index(&list), 3);
+
+        let list = chunks(&["IHDR", "IDAT", "IEND"]);
+        assert_eq!(ChunkPosition::BeforePlte.index(&list), 1);
+
+        let list = chunks(&["IHDR"]);
+        assert_eq!(ChunkPosition::BeforeIdat.index(&list), 1);
+        assert_eq!(ChunkPosition::BeforeIend.index(&[]), 0);
+    }
+
+    #[test]
+    fn test_frame_range() {
+        let list = chunks(&[
+            "IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fdAT", "IEND",
+        ]);
+        assert_eq!(frame_range(&list, 0), Some(2..4));
+        assert_eq!(frame_range(&list, 1), Some(4..7));
+        assert_eq!(frame_range(&list, 2), None);
+        assert_eq!(ChunkPosition::AfterFrame(0).index(&list), 4);
+        assert_eq!(ChunkPosition::AfterFrame(1).index(&list), 7);
+        assert_eq!(ChunkPosition::AfterFrame(5).index(&list), 7);
+    }
+}
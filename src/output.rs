@@ -0,0 +1,153 @@
+//! # Output
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块管理命令行程序的输出方式。默认情况下命令把结果和提示都打印到 stdout。
+//! 全局的 `--json` 打开 JSON 模式后，命令通过 `result` 记录的结果被收集起来，命令结束时与 `"ok":true`
+//! 一起作为一个 JSON 对象输出（见 `success`），出错时输出 `failure` 给出的对象，参数无法解析时输出
+//! `usage_failure` 给出的对象；给人看的提示（`say!`）改为输出到 stderr，stdout 上只有这一个对象，
+//! 脚本不需要区分命令就能解析。daemon 的错误响应使用同样的 `error`、`kind` 和 `exit_code` 字段。
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::EXIT_USAGE;
+use crate::{PngmeError, Result};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// JSON 模式下收集的结果，批处理中按完成顺序排列
+static RESULTS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// 打开 JSON 模式，之后的结果被收集起来，提示输出到 stderr
+pub fn enable_json() {
+    JSON.store(true, Ordering::SeqCst);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::SeqCst)
+}
+
+/// 输出一行提示：JSON 模式下写到 stderr，否则写到 stdout
+pub fn print_line(line: fmt::Arguments) {
+    if is_json() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// 用法与 `println!` 相同，见 `print_line`
+macro_rules! say {
+    ($($arg:tt)*) => {
+        $crate::output::print_line(format_args!($($arg)*))
+    };
+}
+pub(crate) use say;
+
+/// 记录命令的结果：JSON 模式下留到命令结束时输出，否则立即以缩进的 JSON 打印到 stdout
+pub fn result<T: Serialize>(value: &T) -> Result<()> {
+    if is_json() {
+        let value = serde_json::to_value(value).map_err(|e| PngmeError::Io(e.into()))?;
+        RESULTS.lock().unwrap().push(value);
+    } else {
+        let json = serde_json::to_string_pretty(value).map_err(|e| PngmeError::Io(e.into()))?;
+        println!("{}", json);
+    }
+    Ok(())
+}
+
+/// 命令成功时在 JSON 模式下输出的对象，取出之前收集的结果
+pub fn success(command: &str) -> Value {
+    envelope(command, std::mem::take(&mut *RESULTS.lock().unwrap()))
+}
+
+/// 命令出错时在 JSON 模式下输出的对象，`kind` 和 `exit_code` 见 `PngmeError::kind` 和 `PngmeError::exit_code`
+pub fn failure(command: &str, error: &PngmeError) -> Value {
+    with_command(command, error_object(error))
+}
+
+/// 参数无法解析时在 JSON 模式下输出的对象，`kind` 为 `usage`，`exit_code` 为 `EXIT_USAGE`
+pub fn usage_failure(command: &str, message: &str) -> Value {
+    with_command(command, usage_error_object(message))
+}
+
+/// 不带命令名的错误对象：`{"ok":false,"error":...,"kind":...,"exit_code":...}`
+pub fn error_object(error: &PngmeError) -> Value {
+    json!({
+        "ok": false,
+        "error": error.to_string(),
+        "kind": error.kind(),
+        "exit_code": error.exit_code(),
+    })
+}
+
+/// 不带命令名的用法错误对象，字段与 `error_object` 相同
+pub fn usage_error_object(message: &str) -> Value {
+    json!({
+        "ok": false,
+        "error": message,
+        "kind": "usage",
+        "exit_code": EXIT_USAGE,
+    })
+}
+
+fn with_command(command: &str, mut object: Value) -> Value {
+    object["command"] = json!(command);
+    object
+}
+
+/// 没有结果时省略 `result`，只有一个结果时直接使用它，批处理中有多个结果时组成数组
+fn envelope(command: &str, mut results: Vec<Value>) -> Value {
+    let mut object = json!({ "ok": true, "command": command });
+    let result = match results.len() {
+        0 => return object,
+        1 => results.remove(0),
+        _ => Value::Array(results),
+    };
+    object["result"] = result;
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope() {
+        assert_eq!(
+            envelope("encode", Vec::new()),
+            json!({ "ok": true, "command": "encode" })
+        );
+        assert_eq!(
+            envelope("decode", vec![json!("hello")]),
+            json!({ "ok": true, "command": "decode", "result": "hello" })
+        );
+        assert_eq!(
+            envelope("scan", vec![json!([1]), json!([2])])["result"],
+            json!([[1], [2]])
+        );
+
+        let failure = failure("remove", &PngmeError::ChunkNotFound("ruSt".to_string()));
+        assert_eq!(failure["ok"], false);
+        assert_eq!(failure["command"], "remove");
+        assert_eq!(failure["exit_code"], 3);
+        assert_eq!(failure["kind"], "chunk-not-found");
+
+        let usage = usage_failure("decode", "missing <CHUNK_TYPE>");
+        assert_eq!(
+            usage,
+            json!({
+                "ok": false,
+                "command": "decode",
+                "error": "missing <CHUNK_TYPE>",
+                "kind": "usage",
+                "exit_code": 2,
+            })
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! # Palette
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了调色板（PLTE）和透明度（tRNS）chunk 的读写：
+//!
+//! ```text
+//! PLTE: | 红 | 绿 | 蓝 | ... 1 到 256 项
+//! tRNS: 调色板图片 | alpha | ... 不超过调色板的项数，缺少的项为 255
+//!       灰度图片   | 灰度 (u16) |
+//!       RGB 图片   | 红 (u16) | 绿 (u16) | 蓝 (u16) |
+//! ```
+//!
+//! tRNS 的格式取决于 IHDR 中的颜色类型。写回文件见 `Png::set_palette` 和 `Png::set_transparency`，
+//! 它们原地替换已有的 chunk，新插入时满足 PLTE 在 tRNS 和 IDAT 之前的顺序约束。
+
+use std::convert::TryFrom;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::{ColorType, Ihdr};
+use crate::{Error, PngmeError, Result};
+
+/// 调色板最多的项数
+pub const MAX_PALETTE_ENTRIES: usize = 256;
+
+/// 调色板（PLTE）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    entries: Vec<[u8; 3]>,
+}
+
+/// 透明度（tRNS），格式取决于颜色类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transparency {
+    /// 调色板图片：每个调色板项的 alpha，按下标排列
+    Indexed(Vec<u8>),
+    /// 灰度图片：该灰度值的像素完全透明
+    Gray(u16),
+    /// RGB 图片：该颜色的像素完全透明
+    Rgb(u16, u16, u16),
+}
+
+impl Palette {
+    pub const CHUNK_TYPE: &'static str = "PLTE";
+
+    /// 由 RGB 项创建，项数必须在 1 到 256 之间
+    pub fn new(entries: Vec<[u8; 3]>) -> Result<Self> {
+        if !(1..=MAX_PALETTE_ENTRIES).contains(&entries.len()) {
+            return Err(invalid(format!(
+                "PLTE must have 1 to {} entries, found {}",
+                MAX_PALETTE_ENTRIES,
+                entries.len()
+            )));
+        }
+        Ok(Palette { entries })
+    }
+
+    /// 所有项
+    pub fn entries(&self) -> &[[u8; 3]] {
+        &self.entries
+    }
+
+    /// 项数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 调色板总是至少有一项
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 第 `index` 项
+    pub fn get(&self, index: usize) -> Option<[u8; 3]> {
+        self.entries.get(index).copied()
+    }
+
+    /// 修改第 `index` 项
+    pub fn set(&mut self, index: usize, color: [u8; 3]) -> Result<()> {
+        let len = self.entries.len();
+        let entry = self.entries.get_mut(index).ok_or_else(|| {
+            invalid(format!(
+                "palette index {} is out of range, the palette has {} entries",
+                index, len
+            ))
+        })?;
+        *entry = color;
+        Ok(())
+    }
+
+    /// 在末尾添加一项，返回它的下标
+    pub fn push(&mut self, color: [u8; 3]) -> Result<usize> {
+        if self.entries.len() >= MAX_PALETTE_ENTRIES {
+            return Err(invalid(format!(
+                "PLTE cannot have more than {} entries",
+                MAX_PALETTE_ENTRIES
+            )));
+        }
+        self.entries.push(color);
+        Ok(self.entries.len() - 1)
+    }
+
+    /// 检查调色板能否用于 `header` 描述的图片：灰度图片不能有调色板，
+    /// 调色板图片的项数不能超过位深能表示的下标数
+    pub fn check(&self, header: &Ihdr) -> Result<()> {
+        match header.color_type {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => Err(invalid(
+                "grayscale images must not have a PLTE chunk".to_string(),
+            )),
+            ColorType::Indexed if self.len() > 1 << header.bit_depth => Err(invalid(format!(
+                "a {}-bit indexed image can use at most {} palette entries, found {}",
+                header.bit_depth,
+                1 << header.bit_depth,
+                self.len()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk(Self::CHUNK_TYPE, self.entries.concat())
+    }
+}
+
+impl TryFrom<&Chunk> for Palette {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        expect_type(chunk, Self::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if !data.len().is_multiple_of(3) {
+            return Err(invalid(format!(
+                "PLTE length {} is not a multiple of 3",
+                data.len()
+            )));
+        }
+        Palette::new(
+            data.chunks_exact(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                .collect(),
+        )
+    }
+}
+
+impl Transparency {
+    pub const CHUNK_TYPE: &'static str = "tRNS";
+
+    /// 按 `color_type` 解析 tRNS chunk；带 alpha 通道的图片不能有 tRNS
+    pub fn from_chunk(chunk: &Chunk, color_type: ColorType) -> Result<Self> {
+        expect_type(chunk, Self::CHUNK_TYPE)?;
+        let data = chunk.data();
+        let sample = |i: usize| u16::from_be_bytes([data[2 * i], data[2 * i + 1]]);
+        match (color_type, data.len()) {
+            (ColorType::Indexed, len) if len <= MAX_PALETTE_ENTRIES => {
+                Ok(Transparency::Indexed(data.to_vec()))
+            }
+            (ColorType::Grayscale, 2) => Ok(Transparency::Gray(sample(0))),
+            (ColorType::Rgb, 6) => Ok(Transparency::Rgb(sample(0), sample(1), sample(2))),
+            (ColorType::GrayscaleAlpha | ColorType::Rgba, _) => Err(invalid(
+                "images with an alpha channel must not have a tRNS chunk".to_string(),
+            )),
+            (color_type, len) => Err(invalid(format!(
+                "tRNS length {} does not match color type {:?}",
+                len, color_type
+            ))),
+        }
+    }
+
+    /// 调色板第 `index` 项的 alpha，没有记录的项为 255（不透明）；不是调色板格式时返回 `None`
+    pub fn alpha(&self, index: usize) -> Option<u8> {
+        match self {
+            Transparency::Indexed(alphas) => Some(alphas.get(index).copied().unwrap_or(255)),
+            _ => None,
+        }
+    }
+
+    /// 修改调色板第 `index` 项的 alpha，中间缺少的项补为 255
+    pub fn set_alpha(&mut self, index: usize, alpha: u8) -> Result<()> {
+        let Transparency::Indexed(alphas) = self else {
+            return Err(invalid(
+                "only indexed tRNS chunks have per-entry alpha values".to_string(),
+            ));
+        };
+        if index >= MAX_PALETTE_ENTRIES {
+            return Err(invalid(format!("palette index {} is out of range", index)));
+        }
+        if alphas.len() <= index {
+            alphas.resize(index + 1, 255);
+        }
+        alphas[index] = alpha;
+        Ok(())
+    }
+
+    /// 检查能否用于 `header` 描述的图片：格式与颜色类型一致，样本值不超过位深，
+    /// 调色板格式的项数不超过 `palette_len`
+    pub fn check(&self, header: &Ihdr, palette_len: usize) -> Result<()> {
+        let max = u16::MAX >> (16 - header.bit_depth);
+        match (self, header.color_type) {
+            (Transparency::Indexed(alphas), ColorType::Indexed) => {
+                if alphas.len() > palette_len {
+                    return Err(invalid(format!(
+                        "tRNS has {} entries but the palette has only {}",
+                        alphas.len(),
+                        palette_len
+                    )));
+                }
+            }
+            (Transparency::Gray(gray), ColorType::Grayscale) => {
+                if *gray > max {
+                    return Err(out_of_range(header.bit_depth));
+                }
+            }
+            (Transparency::Rgb(r, g, b), ColorType::Rgb) => {
+                if [r, g, b].iter().any(|&&sample| sample > max) {
+                    return Err(out_of_range(header.bit_depth));
+                }
+            }
+            (_, color_type) => {
+                return Err(invalid(format!(
+                    "this tRNS format cannot be used with color type {:?}",
+                    color_type
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Chunk {
+        let data = match self {
+            Transparency::Indexed(alphas) => alphas.clone(),
+            Transparency::Gray(gray) => gray.to_be_bytes().to_vec(),
+            Transparency::Rgb(r, g, b) => [r, g, b].iter().flat_map(|s| s.to_be_bytes()).collect(),
+        };
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+fn out_of_range(bit_depth: u8) -> PngmeError {
+    invalid(format!(
+        "tRNS sample value does not fit in {} bits",
+        bit_depth
+    ))
+}
+
+fn expect_type(chunk: &Chunk, chunk_type: &str) -> Result<()> {
+    if chunk.chunk_type().to_string() != chunk_type {
+        return Err(invalid(format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        )));
+    }
+    Ok(())
+}
+
+fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+    let chunk_type = ChunkType::from_str(chunk_type).expect("standard chunk types are valid");
+    Chunk::new(chunk_type, data).expect("PLTE and tRNS data are small")
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidMetadata(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        let mut palette = Palette::new(vec![[0, 0, 0], [255, 255, 255]]).unwrap();
+        palette.set(1, [255, 0, 0]).unwrap();
+        assert_eq!(palette.push([0, 0, 255]).unwrap(), 2);
+        assert!(palette.set(3, [0, 0, 0]).is_err());
+
+        let chunk = palette.to_chunk();
+        assert_eq!(chunk.data(), [0, 0, 0, 255, 0, 0, 0, 0, 255]);
+        assert_eq!(Palette::try_from(&chunk).unwrap(), palette);
+
+        assert!(Palette::new(Vec::new()).is_err());
+        assert!(Palette::new(vec![[0; 3]; 257]).is_err());
+        let mut full = Palette::new(vec![[0; 3]; 256]).unwrap();
+        assert!(full.push([0; 3]).is_err());
+    }
+
+    #[test]
+    fn test_palette_check() {
+        let palette = Palette::new(vec![[0; 3]; 3]).unwrap();
+        assert!(palette
+            .check(&Ihdr::new(1, 1, 2, ColorType::Indexed).unwrap())
+            .is_ok());
+        assert!(palette
+            .check(&Ihdr::new(1, 1, 1, ColorType::Indexed).unwrap())
+            .is_err());
+        assert!(palette
+            .check(&Ihdr::new(1, 1, 8, ColorType::Grayscale).unwrap())
+            .is_err());
+        // RGB 图片的调色板只是建议的量化颜色
+        assert!(palette
+            .check(&Ihdr::new(1, 1, 8, ColorType::Rgb).unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_transparency() {
+        let mut indexed = Transparency::Indexed(vec![0]);
+        indexed.set_alpha(2, 128).unwrap();
+        assert_eq!(indexed, Transparency::Indexed(vec![0, 255, 128]));
+        assert_eq!(indexed.alpha(2), Some(128));
+        assert_eq!(indexed.alpha(10), Some(255));
+        let chunk = indexed.to_chunk();
+        assert_eq!(
+            Transparency::from_chunk(&chunk, ColorType::Indexed).unwrap(),
+            indexed
+        );
+
+        let rgb = Transparency::Rgb(1, 2, 3);
+        let chunk = rgb.to_chunk();
+        assert_eq!(chunk.data(), [0, 1, 0, 2, 0, 3]);
+        assert_eq!(
+            Transparency::from_chunk(&chunk, ColorType::Rgb).unwrap(),
+            rgb
+        );
+        assert!(Transparency::from_chunk(&chunk, ColorType::Grayscale).is_err());
+        assert!(Transparency::from_chunk(&chunk, ColorType::Rgba).is_err());
+        assert!(Transparency::Gray(0).set_alpha(0, 0).is_err());
+    }
+
+    #[test]
+    fn test_transparency_check() {
+        let indexed = Ihdr::new(1, 1, 8, ColorType::Indexed).unwrap();
+        assert!(Transparency::Indexed(vec![0; 2]).check(&indexed, 2).is_ok());
+        assert!(Transparency::Indexed(vec![0; 3])
+            .check(&indexed, 2)
+            .is_err());
+        let gray = Ihdr::new(1, 1, 4, ColorType::Grayscale).unwrap();
+        assert!(Transparency::Gray(15).check(&gray, 0).is_ok());
+        assert!(Transparency::Gray(16).check(&gray, 0).is_err());
+        assert!(Transparency::Rgb(0, 0, 0).check(&gray, 0).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! # Parse
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块定义了 `Png::from_bytes_with` 和 `Chunk::parse_with` 使用的解析选项。
+//!
+//! 严格模式与 `Png::try_from` 一致，遇到任何问题都返回错误；宽松模式用于分析来源不明的畸形文件，
+//! CRC 错误、签名错误和无效的类型码都记为 `ParseWarning` 后继续解析，
+//! 长度字段损坏时用 `find_next_chunk` 向后搜索下一个合理的 chunk 边界（类型码有效且 CRC 匹配），
+//! 从那里继续解析；找不到时停止并保留已经解析出的 chunk。
+
+use std::fmt;
+
+use crate::chunk::{Chunk, MAX_CHUNK_LENGTH};
+use crate::chunk_type::ChunkType;
+
+/// 解析选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// 遇到问题时返回错误，而不是记录警告后继续
+    pub strict: bool,
+    /// 校验每个 chunk 的 CRC；不校验时保留存储的 CRC
+    pub verify_crc: bool,
+    /// 单个 chunk 数据的最大字节数，`None` 表示只受规范的 2^31 - 1 限制
+    pub max_chunk_size: Option<u32>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: true,
+            verify_crc: true,
+            max_chunk_size: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// 宽松模式：校验 CRC，但出错时只记录警告
+    pub fn lenient() -> Self {
+        ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        }
+    }
+
+    /// 实际生效的 chunk 大小上限
+    pub fn chunk_size_limit(&self) -> u32 {
+        self.max_chunk_size.unwrap_or(MAX_CHUNK_LENGTH)
+    }
+}
+
+/// 宽松模式下被跳过或容忍的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// 问题所在 chunk 的偏移（从文件开头算起；`Chunk::parse_with` 中从传入的字节算起）
+    pub offset: usize,
+    /// chunk 类型，类型码无效时为转义后的原始字节
+    pub chunk_type: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "offset {} ({}): {}",
+            self.offset, self.chunk_type, self.message
+        )
+    }
+}
+
+/// 从 `from` 开始向后搜索第一个完整的 chunk：类型码的四个字节都是字母，
+/// 数据没有超出 `bytes`，并且 CRC 匹配。返回它在 `bytes` 中的偏移
+pub fn find_next_chunk(bytes: &[u8], from: usize) -> Option<usize> {
+    (from..bytes.len().saturating_sub(11)).find(|&offset| is_chunk_at(bytes, offset))
+}
+
+/// `offset` 处是否是一个完整且 CRC 正确的 chunk
+pub fn is_chunk_at(bytes: &[u8], offset: usize) -> bool {
+    let Some(rest) = bytes.get(offset..).filter(|rest| rest.len() >= 12) else {
+        return false;
+    };
+    let length = u32::from_be_bytes(rest[..4].try_into().unwrap());
+    if length > MAX_CHUNK_LENGTH || rest.len() - 12 < length as usize {
+        return false;
+    }
+    let Ok(chunk_type) = ChunkType::try_from([rest[4], rest[5], rest[6], rest[7]]) else {
+        return false;
+    };
+    let end = 8 + length as usize;
+    let crc = u32::from_be_bytes(rest[end..end + 4].try_into().unwrap());
+    Chunk::calculate_crc(&chunk_type, &rest[8..end]) == crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_find_next_chunk() {
+        assert!(is_chunk_at(&PNG_FILE, 8));
+        assert!(!is_chunk_at(&PNG_FILE, 9));
+        // IHDR 从偏移 8 开始，占 25 字节，其后是 IDAT
+        assert_eq!(find_next_chunk(&PNG_FILE, 0), Some(8));
+        assert_eq!(find_next_chunk(&PNG_FILE, 9), Some(33));
+        assert_eq!(find_next_chunk(&PNG_FILE, PNG_FILE.len() - 11), None);
+        assert_eq!(find_next_chunk(&PNG_FILE[..20], 0), None);
+    }
+}
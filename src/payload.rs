@@ -0,0 +1,655 @@
+//! # Payload
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块定义了隐藏信息的封装格式。需要额外信息（压缩、文件名等）的数据以一个小的头部开头：
+//!
+//! ```text
+//! | 0x00 'P' 'M' | flags (1) | [文件信息] | 数据 |
+//! ```
+//!
+//! 设置了 `FLAG_FILE` 时，文件信息为 `文件名长度 (u16) | 文件名 (UTF-8) | 原始长度 (u64)`，
+//! 均为大端序；设置了 `FLAG_COMPRESSED` 时，数据部分经过 zlib 压缩。
+//! 设置了 `FLAG_MAC` 时，末尾还有 32 字节的 HMAC-SHA256 完整性标签，覆盖它之前的全部字节（包括头部）。
+//! CRC 只能发现意外的损坏，修改信息的人可以重新计算 CRC；没有密钥就无法伪造标签，
+//! `Payload::from_bytes_verified` 据此发现被篡改的信息。
+//!
+//! 过大的数据可以用 `split` 拆分到多个同类型的 chunk 中，每个分片的格式为：
+//!
+//! ```text
+//! | 0x00 'P' 'M' | FLAG_PART | 序号 (u32) | 总数 (u32) | 分片数据 |
+//! ```
+//!
+//! 分片位于最外层：数据先封装（和加密），再拆分；读取时先用 `join` 拼接。
+//!
+//! 普通文本原样存储，没有头部，因此旧文件和其他工具写入的文本仍能直接读取。
+//!
+//! 文件名统一规范化为 NFC，同一个名字在 macOS（NFD）和其他系统上写入的字节相同。
+//! 文件名来自不可信的文件，提取前必须经过 `safe_filename`：它只保留最后一个路径分量，
+//! 去掉 `..`、控制字符以及 Windows 上的保留名和非法字符，结果不会逃出输出目录。
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::chunk::Chunk;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::winpath;
+use crate::{PngmeError, Result};
+
+/// 封装头部的魔数
+pub const MAGIC: [u8; 3] = [0, b'P', b'M'];
+/// 数据经过 zlib 压缩
+pub const FLAG_COMPRESSED: u8 = 0b0000_0001;
+/// 数据是一个文件，头部带有文件名和原始长度
+pub const FLAG_FILE: u8 = 0b0000_0010;
+/// 数据是拆分后的一个分片
+pub const FLAG_PART: u8 = 0b0000_0100;
+/// 末尾带有 HMAC-SHA256 完整性标签
+pub const FLAG_MAC: u8 = 0b0000_1000;
+const KNOWN_FLAGS: u8 = FLAG_COMPRESSED | FLAG_FILE | FLAG_MAC;
+/// 完整性标签的长度
+pub const MAC_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 1;
+const PART_HEADER_LEN: usize = HEADER_LEN + 8;
+/// 默认的单个 chunk 最大数据长度：1 MiB
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+/// 信息没有文件名或文件名清理后为空时，提取使用的文件名
+pub const DEFAULT_FILENAME: &str = "payload.bin";
+/// 提取时文件名的最大字节数，大多数文件系统的限制
+const MAX_FILENAME_LEN: usize = 255;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 隐藏的信息：数据以及可选的原始文件名
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Payload {
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Payload {
+    /// 普通信息
+    pub fn new(data: Vec<u8>) -> Self {
+        Payload {
+            filename: None,
+            data,
+        }
+    }
+
+    /// 带文件名的文件，文件名规范化为 NFC
+    pub fn file(filename: impl Into<String>, data: Vec<u8>) -> Self {
+        Payload {
+            filename: Some(filename.into().nfc().collect()),
+            data,
+        }
+    }
+
+    /// 编码为 chunk 数据。没有需要记录的信息时原样返回数据
+    pub fn to_bytes(&self, compress: bool) -> Result<Vec<u8>> {
+        self.encode(compress, None)
+    }
+
+    /// 编码为 chunk 数据，并在末尾附加用 `key` 计算的完整性标签
+    pub fn to_bytes_with_mac(&self, compress: bool, key: &[u8]) -> Result<Vec<u8>> {
+        self.encode(compress, Some(key))
+    }
+
+    fn encode(&self, compress: bool, mac_key: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut flags = 0;
+        if compress {
+            flags |= FLAG_COMPRESSED;
+        }
+        if self.filename.is_some() {
+            flags |= FLAG_FILE;
+        }
+        if mac_key.is_some() {
+            flags |= FLAG_MAC;
+        }
+        // 恰好以魔数开头的普通数据也要加上头部，否则读取时会被误认为封装数据
+        if flags == 0 && !is_enveloped(&self.data) {
+            return Ok(self.data.clone());
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(flags);
+        if let Some(filename) = &self.filename {
+            let name = filename.as_bytes();
+            let name_len = u16::try_from(name.len()).map_err(|_| {
+                PngmeError::InvalidPayload("file name is longer than 65535 bytes".to_string())
+            })?;
+            bytes.extend_from_slice(&name_len.to_be_bytes());
+            bytes.extend_from_slice(name);
+            bytes.extend_from_slice(&(self.data.len() as u64).to_be_bytes());
+        }
+
+        if compress {
+            let mut encoder = ZlibEncoder::new(bytes, Compression::best());
+            encoder.write_all(&self.data)?;
+            bytes = encoder.finish()?;
+        } else {
+            bytes.extend_from_slice(&self.data);
+        }
+        if let Some(key) = mac_key {
+            let tag = mac(key, &bytes);
+            bytes.extend_from_slice(&tag);
+        }
+        Ok(bytes)
+    }
+
+    /// 从 chunk 数据解码，带有封装头部时按 flags 解析，否则整个数据即为信息
+    pub fn from_bytes(bytes: &[u8]) -> Result<Payload> {
+        Payload::from_bytes_with_limits(bytes, &Limits::default())
+    }
+
+    /// 校验完整性标签后解码。数据没有标签、标签与 `key` 计算的不一致（信息被修改或密钥错误）时
+    /// 返回 `PngmeError::Crypto`
+    pub fn from_bytes_verified(bytes: &[u8], key: &[u8], limits: &Limits) -> Result<Payload> {
+        if !has_mac(bytes) {
+            return Err(PngmeError::Crypto(
+                "the message has no integrity tag".to_string(),
+            ));
+        }
+        let (body, tag) = bytes.split_at(bytes.len() - MAC_LEN);
+        let mut verifier =
+            HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        verifier.update(body);
+        verifier.verify_slice(tag).map_err(|_| {
+            PngmeError::Crypto(
+                "integrity tag does not match: the message was modified or the key is wrong"
+                    .to_string(),
+            )
+        })?;
+        Payload::from_bytes_with_limits(bytes, limits)
+    }
+
+    /// 与 `from_bytes` 相同，但使用指定的安全限制检查信息大小和解压比例
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Payload> {
+        limits.check_payload_size(bytes.len() as u64)?;
+        if !is_enveloped(bytes) {
+            return Ok(Payload::new(bytes.to_vec()));
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(invalid("payload header is truncated"));
+        }
+
+        let flags = bytes[MAGIC.len()];
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(PngmeError::InvalidPayload(format!(
+                "unknown payload flags {:#04x}",
+                flags
+            )));
+        }
+
+        let mut rest = &bytes[HEADER_LEN..];
+        // 标签不参与解码；需要校验时用 `from_bytes_verified`
+        if flags & FLAG_MAC != 0 {
+            if rest.len() < MAC_LEN {
+                return Err(invalid("integrity tag is truncated"));
+            }
+            rest = &rest[..rest.len() - MAC_LEN];
+        }
+        let mut file = None;
+        if flags & FLAG_FILE != 0 {
+            let name_len = u16::from_be_bytes(take(&mut rest, 2)?.try_into().unwrap()) as usize;
+            let name = std::str::from_utf8(take(&mut rest, name_len)?)?.to_string();
+            let length = u64::from_be_bytes(take(&mut rest, 8)?.try_into().unwrap());
+            limits.check_payload_size(length)?;
+            file = Some((name, length));
+        }
+
+        let data = if flags & FLAG_COMPRESSED != 0 {
+            limits.inflate(rest).map_err(|e| match e {
+                PngmeError::Io(e) => {
+                    PngmeError::InvalidPayload(format!("cannot decompress payload: {}", e))
+                }
+                e => e,
+            })?
+        } else {
+            rest.to_vec()
+        };
+
+        match file {
+            Some((_, length)) if length != data.len() as u64 => {
+                Err(PngmeError::InvalidPayload(format!(
+                    "expected {} bytes of file data, found {}",
+                    length,
+                    data.len()
+                )))
+            }
+            Some((name, _)) => Ok(Payload::file(name, data)),
+            None => Ok(Payload::new(data)),
+        }
+    }
+}
+
+/// 把信息中的文件名转换为可以安全写入输出目录的文件名：
+/// 只保留最后一个路径分量（`/` 和 `\\` 都视为分隔符），控制字符替换为 `_`，
+/// 在 Windows 上还会替换 `<>:"|?*`、去掉结尾的点和空格并给保留设备名加上 `_` 前缀。
+/// 结果为空、`.` 或 `..` 时使用 `DEFAULT_FILENAME`
+pub fn safe_filename(name: &str) -> String {
+    sanitize_filename(name, cfg!(windows))
+}
+
+fn sanitize_filename(name: &str, windows: bool) -> String {
+    let last = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let mut safe: String = last
+        .nfc()
+        .map(|c| {
+            let invalid_on_windows =
+                windows && matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*');
+            if c.is_control() || invalid_on_windows {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if windows {
+        safe.truncate(safe.trim_end_matches(['.', ' ']).len());
+        if winpath::is_reserved_name(&safe) {
+            safe.insert(0, '_');
+        }
+    }
+    if safe.len() > MAX_FILENAME_LEN {
+        let mut end = MAX_FILENAME_LEN;
+        while !safe.is_char_boundary(end) {
+            end -= 1;
+        }
+        safe.truncate(end);
+    }
+    if safe.is_empty() || safe == "." || safe == ".." {
+        return DEFAULT_FILENAME.to_string();
+    }
+    safe
+}
+
+/// 检查数据是否带有封装头部
+pub fn is_enveloped(payload: &[u8]) -> bool {
+    payload.starts_with(&MAGIC)
+}
+
+/// 检查数据是否带有完整性标签
+pub fn has_mac(payload: &[u8]) -> bool {
+    is_enveloped(payload)
+        && !is_part(payload)
+        && payload.len() >= HEADER_LEN + MAC_LEN
+        && payload[MAGIC.len()] & FLAG_MAC != 0
+}
+
+/// 用 `key` 计算 `data` 的 HMAC-SHA256
+fn mac(key: &[u8], data: &[u8]) -> [u8; MAC_LEN] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// 压缩数据并加上封装头部
+pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    Payload::new(data.to_vec()).to_bytes(true)
+}
+
+/// 还原信息数据：带有封装头部时按 flags 解包，否则原样返回
+pub fn decode(payload: &[u8]) -> Result<Vec<u8>> {
+    Ok(Payload::from_bytes(payload)?.data)
+}
+
+/// 检查数据是否为 `split` 生成的分片
+pub fn is_part(bytes: &[u8]) -> bool {
+    is_enveloped(bytes) && bytes.get(MAGIC.len()) == Some(&FLAG_PART)
+}
+
+/// 把数据拆分为若干段，每段（含分片头部）不超过 `max_chunk_size` 字节。
+/// 数据本身不超过上限时原样返回一段，不加分片头部
+pub fn split(data: &[u8], max_chunk_size: usize) -> Result<Vec<Vec<u8>>> {
+    if data.len() <= max_chunk_size {
+        return Ok(vec![data.to_vec()]);
+    }
+    parts(data, max_piece_size(max_chunk_size)?)
+}
+
+/// 把数据尽量均匀地拆分为 `count` 个分片（数据太短时分片会更少），
+/// 每段（含分片头部）同样不超过 `max_chunk_size` 字节，超出时分片会更多。
+/// 与 `split` 不同，即使只有一段也会加上分片头部
+pub fn split_evenly(data: &[u8], count: usize, max_chunk_size: usize) -> Result<Vec<Vec<u8>>> {
+    let piece_size = data
+        .len()
+        .div_ceil(count.max(1))
+        .clamp(1, max_piece_size(max_chunk_size)?);
+    parts(data, piece_size)
+}
+
+/// 分片中数据部分的最大长度
+fn max_piece_size(max_chunk_size: usize) -> Result<usize> {
+    if max_chunk_size <= PART_HEADER_LEN {
+        return Err(PngmeError::InvalidPayload(format!(
+            "chunk size must be larger than {} bytes to split a payload",
+            PART_HEADER_LEN
+        )));
+    }
+    Ok(max_chunk_size - PART_HEADER_LEN)
+}
+
+/// 按 `piece_size` 切分数据，并给每段加上分片头部
+fn parts(data: &[u8], piece_size: usize) -> Result<Vec<Vec<u8>>> {
+    let mut pieces: Vec<&[u8]> = data.chunks(piece_size).collect();
+    if pieces.is_empty() {
+        pieces.push(&[]);
+    }
+    let count = u32::try_from(pieces.len())
+        .map_err(|_| invalid("payload needs more than u32::MAX parts"))?;
+    Ok(pieces
+        .into_iter()
+        .enumerate()
+        .map(|(index, piece)| {
+            let mut part = MAGIC.to_vec();
+            part.push(FLAG_PART);
+            part.extend_from_slice(&(index as u32).to_be_bytes());
+            part.extend_from_slice(&count.to_be_bytes());
+            part.extend_from_slice(piece);
+            part
+        })
+        .collect())
+}
+
+/// 拼接同一类型的 chunk 数据（按文件中的顺序传入）。
+/// 第一段不是分片时直接返回第一段；否则按序号重组全部分片，并检查分片是否完整
+pub fn join(chunks: &[&[u8]]) -> Result<Vec<u8>> {
+    let first = match chunks.first() {
+        Some(first) => *first,
+        None => return Err(invalid("no data to join")),
+    };
+    if !is_part(first) {
+        return Ok(first.to_vec());
+    }
+
+    let mut parts: Vec<(u32, u32, &[u8])> = Vec::new();
+    for chunk in chunks.iter().filter(|chunk| is_part(chunk)) {
+        let mut rest = &chunk[HEADER_LEN..];
+        let index = u32::from_be_bytes(take(&mut rest, 4)?.try_into().unwrap());
+        let count = u32::from_be_bytes(take(&mut rest, 4)?.try_into().unwrap());
+        parts.push((index, count, rest));
+    }
+    parts.sort_by_key(|(index, _, _)| *index);
+
+    let count = parts[0].1;
+    let complete = parts.len() == count as usize
+        && parts
+            .iter()
+            .enumerate()
+            .all(|(i, (index, part_count, _))| *index == i as u32 && *part_count == count);
+    if !complete {
+        return Err(PngmeError::InvalidPayload(format!(
+            "expected {} parts, found {} (missing or duplicate parts)",
+            count,
+            parts.len()
+        )));
+    }
+    Ok(parts
+        .into_iter()
+        .flat_map(|(_, _, data)| data.to_vec())
+        .collect())
+}
+
+/// 读取指定类型的信息数据，拆分存储的分片会被重新拼接
+pub fn message_data(png: &Png, chunk_type: &str) -> Result<Vec<u8>> {
+    chunks_message_data(png.chunks(), chunk_type)
+}
+
+/// 从一组 chunk 中读取指定类型的信息数据
+pub fn chunks_message_data(chunks: &[Chunk], chunk_type: &str) -> Result<Vec<u8>> {
+    let parts: Vec<&[u8]> = chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type().to_string() == chunk_type)
+        .map(Chunk::data)
+        .collect();
+    if parts.is_empty() {
+        return Err(PngmeError::ChunkNotFound(chunk_type.to_string()));
+    }
+    join(&parts)
+}
+
+/// 删除第一个指定类型的 chunk，返回它和删除的 chunk 数量
+pub fn remove_message(png: &mut Png, chunk_type: &str) -> Result<(Chunk, usize)> {
+    let chunk = png.remove_chunk(chunk_type)?;
+    let mut removed = 1;
+    // 拆分存储的信息要把所有分片一起删除
+    if is_part(chunk.data()) {
+        while png
+            .chunk_by_type(chunk_type)
+            .is_some_and(|chunk| is_part(chunk.data()))
+        {
+            png.remove_chunk(chunk_type)?;
+            removed += 1;
+        }
+    }
+    Ok((chunk, removed))
+}
+
+/// 从 `rest` 开头取出 `n` 个字节
+fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if rest.len() < n {
+        return Err(invalid("payload header is truncated"));
+    }
+    let (head, tail) = rest.split_at(n);
+    *rest = tail;
+    Ok(head)
+}
+
+fn invalid(message: &str) -> PngmeError {
+    PngmeError::InvalidPayload(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_round_trip() {
+        let data = "repeat ".repeat(1000).into_bytes();
+        let payload = compress(&data).unwrap();
+        assert!(is_enveloped(&payload));
+        assert!(payload.len() < data.len() / 10);
+        assert_eq!(decode(&payload).unwrap(), data);
+    }
+
+    #[test]
+    fn test_plain_payload_passes_through() {
+        assert_eq!(decode(b"plain message").unwrap(), b"plain message");
+        assert_eq!(decode(b"").unwrap(), b"");
+        assert_eq!(
+            Payload::new(b"plain".to_vec()).to_bytes(false).unwrap(),
+            b"plain"
+        );
+    }
+
+    #[test]
+    fn test_data_starting_with_magic_is_escaped() {
+        let data = [0, b'P', b'M', 7, 8, 9].to_vec();
+        let bytes = Payload::new(data.clone()).to_bytes(false).unwrap();
+        assert_eq!(bytes.len(), data.len() + HEADER_LEN);
+        assert_eq!(decode(&bytes).unwrap(), data);
+    }
+
+    #[test]
+    fn test_mac_round_trip() {
+        let limits = Limits::default();
+        for compress in [false, true] {
+            for payload in [
+                Payload::new(b"hello".to_vec()),
+                Payload::file("notes.txt", b"secret notes".to_vec()),
+            ] {
+                let bytes = payload.to_bytes_with_mac(compress, b"key").unwrap();
+                assert!(has_mac(&bytes));
+                assert_eq!(
+                    Payload::from_bytes_verified(&bytes, b"key", &limits).unwrap(),
+                    payload
+                );
+                // 不校验时忽略标签
+                assert_eq!(Payload::from_bytes(&bytes).unwrap(), payload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mac_detects_tampering() {
+        let limits = Limits::default();
+        let bytes = Payload::new(b"pay alice 10".to_vec())
+            .to_bytes_with_mac(false, b"key")
+            .unwrap();
+        let invalid_mac =
+            |bytes: &[u8], key: &[u8]| match Payload::from_bytes_verified(bytes, key, &limits) {
+                Err(PngmeError::Crypto(message)) => message,
+                other => panic!("expected an integrity error, got {:?}", other),
+            };
+        assert!(invalid_mac(&bytes, b"wrong key").contains("does not match"));
+
+        let mut tampered = bytes.clone();
+        tampered[HEADER_LEN + 10] = b'9';
+        assert!(invalid_mac(&tampered, b"key").contains("does not match"));
+
+        // 去掉标签和标志位的信息不能冒充带标签的信息
+        let mut stripped = bytes[..bytes.len() - MAC_LEN].to_vec();
+        stripped[MAGIC.len()] &= !FLAG_MAC;
+        assert!(!has_mac(&stripped));
+        assert!(invalid_mac(&stripped, b"key").contains("no integrity tag"));
+        assert!(invalid_mac(b"plain", b"key").contains("no integrity tag"));
+
+        let truncated = [&MAGIC[..], &[FLAG_MAC], b"short"].concat();
+        assert!(Payload::from_bytes(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        for compress in [false, true] {
+            let payload = Payload::file("payload.zip", data.clone());
+            let bytes = payload.to_bytes(compress).unwrap();
+            assert_eq!(Payload::from_bytes(&bytes).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_filename_is_nfc() {
+        let decomposed = "cafe\u{301}.txt";
+        let payload = Payload::file(decomposed, b"data".to_vec());
+        assert_eq!(payload.filename.as_deref(), Some("caf\u{e9}.txt"));
+        let decoded = Payload::from_bytes(&payload.to_bytes(false).unwrap()).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_safe_filename() {
+        for windows in [false, true] {
+            let safe = |name| sanitize_filename(name, windows);
+            assert_eq!(safe("report.pdf"), "report.pdf");
+            assert_eq!(safe("../../etc/passwd"), "passwd");
+            assert_eq!(safe("..\\..\\Windows\\win.ini"), "win.ini");
+            assert_eq!(safe("/abs/path/"), DEFAULT_FILENAME);
+            assert_eq!(safe(".."), DEFAULT_FILENAME);
+            assert_eq!(safe(""), DEFAULT_FILENAME);
+            assert_eq!(safe("a\nb\x1b.txt"), "a_b_.txt");
+            assert_eq!(safe("\u{4f60}\u{597d}.txt"), "\u{4f60}\u{597d}.txt");
+            assert_eq!(safe(&"x".repeat(300)).len(), MAX_FILENAME_LEN);
+        }
+        assert_eq!(sanitize_filename("con.txt", false), "con.txt");
+        assert_eq!(sanitize_filename("con.txt", true), "_con.txt");
+        assert_eq!(sanitize_filename("LPT1", true), "_LPT1");
+        assert_eq!(sanitize_filename("a<b>:c?.txt. ", true), "a_b__c_.txt");
+        assert_eq!(sanitize_filename("a<b>.txt", false), "a<b>.txt");
+    }
+
+    #[test]
+    fn test_file_length_mismatch() {
+        let mut bytes = Payload::file("a.bin", vec![1, 2, 3])
+            .to_bytes(false)
+            .unwrap();
+        bytes.pop();
+        assert!(Payload::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_payload_limits() {
+        let limits = Limits {
+            max_payload_size: 100,
+            ..Limits::default()
+        };
+        let small = Payload::new(vec![1; 50]).to_bytes(true).unwrap();
+        assert_eq!(
+            Payload::from_bytes_with_limits(&small, &limits)
+                .unwrap()
+                .data,
+            vec![1; 50]
+        );
+        let large = Payload::new(vec![1; 500]).to_bytes(true).unwrap();
+        assert!(large.len() < 100);
+        assert!(matches!(
+            Payload::from_bytes_with_limits(&large, &limits),
+            Err(PngmeError::DecompressionBomb { .. })
+        ));
+        let file = Payload::file("a.bin", vec![2; 500])
+            .to_bytes(false)
+            .unwrap();
+        assert!(Payload::from_bytes_with_limits(&file, &limits).is_err());
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let parts = split(&data, 100).unwrap();
+        assert_eq!(parts.len(), 12);
+        assert!(parts.iter().all(|part| part.len() <= 100 && is_part(part)));
+
+        let mut refs: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+        refs.reverse();
+        assert_eq!(join(&refs).unwrap(), data);
+    }
+
+    #[test]
+    fn test_small_data_is_not_split() {
+        let parts = split(b"short", 100).unwrap();
+        assert_eq!(parts, [b"short".to_vec()]);
+        assert_eq!(join(&[b"short", b"other"]).unwrap(), b"short");
+        assert!(split(&[0; 100], 10).is_err());
+    }
+
+    #[test]
+    fn test_split_evenly() {
+        let parts = split_evenly(b"hello", 3, 100).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|part| is_part(part)));
+        let refs: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+        assert_eq!(join(&refs).unwrap(), b"hello");
+
+        assert_eq!(split_evenly(b"hi", 5, 100).unwrap().len(), 2);
+        assert_eq!(split_evenly(&[0; 100], 2, 30).unwrap().len(), 6);
+        let empty = split_evenly(b"", 3, 100).unwrap();
+        assert_eq!(empty.len(), 1);
+        assert_eq!(join(&[&empty[0]]).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_join_missing_part() {
+        let data = vec![7u8; 1000];
+        let parts = split(&data, 100).unwrap();
+        let refs: Vec<&[u8]> = parts.iter().skip(1).map(Vec::as_slice).collect();
+        assert!(join(&refs).is_err());
+
+        let mut refs: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+        refs.push(&parts[3]);
+        assert!(join(&refs).is_err());
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        assert!(decode(&MAGIC).is_err());
+        assert!(decode(&[0, b'P', b'M', 0x80]).is_err());
+        assert!(decode(&[0, b'P', b'M', FLAG_COMPRESSED, 1, 2, 3]).is_err());
+        assert!(decode(&[0, b'P', b'M', FLAG_FILE, 0, 9, b'a']).is_err());
+    }
+}
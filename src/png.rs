@@ -0,0 +1,2197 @@
+//! # Png
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块包含了 `Png` 结构体的实现。
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::time::Duration;
+
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::apng::{AnimationControl, FrameControl};
+use crate::cancel::CancelToken;
+use crate::chunk::{Chunk, ChunkRef, MAX_CHUNK_LENGTH};
+use crate::chunk_type::ChunkType;
+use crate::describe;
+use crate::diff::{self, PngDiff};
+use crate::editor::PngEditor;
+use crate::exif::Exif;
+use crate::ihdr::{ColorType, Ihdr, Interlace};
+use crate::interlace;
+use crate::limits::Limits;
+use crate::metadata::{IccProfile, RenderingIntent};
+use crate::ordering::{self, ChunkPosition, OrderingIssue};
+use crate::palette::{Palette, Transparency};
+use crate::parse::{self, ParseOptions, ParseWarning};
+use crate::payload::DEFAULT_CHUNK_SIZE;
+use crate::preview;
+use crate::reader::ChunkReader;
+use crate::signature;
+use crate::sniff;
+use crate::text;
+use crate::{Error, PngmeError, Result};
+
+/// 隐藏信息的方式，用于估算容量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapacityMethod {
+    /// 写入辅助 chunk（pngme 使用的方式）
+    Chunks,
+    /// 替换每个像素样本的最低位
+    Lsb,
+}
+
+/// `Png::optimize` 的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptimizeOptions {
+    /// 重新压缩 IDAT 使用的 zlib 压缩级别（0-9）
+    pub level: u32,
+    /// 删除多余的 chunk：只能出现一次却重复出现的，以及与之前某个 chunk 完全相同的已知辅助 chunk
+    pub remove_redundant: bool,
+    /// 把 16 位图片转换为 8 位，每个样本四舍五入；这会丢失精度
+    pub reduce_bit_depth: bool,
+}
+
+/// 写出 PNG 的选项（见 `Png::write_with`），默认与 `as_bytes` 相同，按原样写出
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// 把违反顺序约束的 chunk 移到规范为其类型选择的位置（见 `ordering::normalize`），
+    /// 其余 chunk 保持原来的相对顺序，不做顺带的重排
+    pub normalize_order: bool,
+    /// 按类型码和数据重新计算 CRC，而不是写出读入时保留的 CRC（宽松解析的文件中可能是错的）
+    pub recompute_crc: bool,
+    /// 不写出易变的 chunk（`tIME`，以及关键字在 `VOLATILE_KEYWORDS` 中的文本 chunk）
+    pub strip_volatile: bool,
+}
+
+impl WriteOptions {
+    /// `Png::write_canonical` 使用的选项：整理顺序并重新计算 CRC，不删除任何 chunk
+    pub fn canonical() -> Self {
+        WriteOptions {
+            normalize_order: true,
+            recompute_crc: true,
+            strip_volatile: false,
+        }
+    }
+}
+
+/// `Png::merge_from` 复制哪些辅助 chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeFilter {
+    /// 指定类型的 chunk
+    Types(Vec<ChunkType>),
+    /// 所有可以安全复制的 chunk（类型码第 4 个字母小写）：规范允许编辑器在修改图像后原样保留它们
+    SafeToCopy,
+}
+
+/// `Png::verify_all_crcs` 对单个 chunk 的校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcCheck {
+    /// chunk 在 Png 中的下标
+    pub index: usize,
+    pub chunk_type: ChunkType,
+    /// chunk 中记录的 CRC
+    pub expected: u32,
+    /// 按类型码和数据重新计算的 CRC
+    pub actual: u32,
+}
+
+impl CrcCheck {
+    fn of(index: usize, chunk: &Chunk) -> CrcCheck {
+        CrcCheck {
+            index,
+            chunk_type: *chunk.chunk_type(),
+            expected: chunk.crc(),
+            actual: Chunk::calculate_crc(chunk.chunk_type(), chunk.data()),
+        }
+    }
+
+    /// 记录的 CRC 与计算结果一致
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        OptimizeOptions {
+            level: 9,
+            remove_redundant: true,
+            reduce_bit_depth: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Png {
+    chunks: Vec<Chunk>,
+}
+
+impl Png {
+    /// PNG 文件的 8 字节签名
+    pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+    /// 由 chunk 列表构造 Png
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Png { chunks }
+    }
+
+    /// 从数据流中读取 Png，chunk 会被逐个解析
+    pub fn from_reader<R: Read>(reader: R) -> Result<Png> {
+        Png::from_reader_with_limits(reader, &Limits::default())
+    }
+
+    /// 与 `from_reader` 相同，但使用指定的安全限制
+    pub fn from_reader_with_limits<R: Read>(reader: R, limits: &Limits) -> Result<Png> {
+        let chunks = ChunkReader::from_png(reader)?
+            .with_limits(limits.clone())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Png { chunks })
+    }
+
+    /// 与 `TryFrom<&[u8]>` 相同，但使用指定的安全限制
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Png> {
+        limits.check_file_size(bytes.len() as u64)?;
+        if bytes.len() < 8 || bytes[..8] != Self::STANDARD_HEADER {
+            return Err(sniff::signature_error(bytes));
+        }
+        Png::parse_chunks(&bytes[8..], limits, |rest| Chunk::try_from(rest))
+    }
+
+    /// 按 `options` 解析 Png，同时返回宽松模式下记录的警告（严格模式下总是为空）。
+    /// 宽松模式下签名错误、CRC 错误和无效类型码的 chunk 被记录后跳过（CRC 错误的 chunk 会保留），
+    /// 长度字段损坏（超限、超出文件或指向不合理的位置）时搜索下一个完整的 chunk 继续解析，
+    /// 找不到时停止，返回已经解析出的 chunk
+    pub fn from_bytes_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Png, Vec<ParseWarning>)> {
+        let mut warnings = Vec::new();
+        if bytes.len() < 8 || bytes[..8] != Self::STANDARD_HEADER {
+            if options.strict {
+                return Err(sniff::signature_error(bytes));
+            }
+            warnings.push(ParseWarning {
+                offset: 0,
+                chunk_type: "signature".to_string(),
+                message: sniff::signature_error(bytes).to_string(),
+            });
+        }
+
+        let mut chunks = Vec::new();
+        let mut offset = 8;
+        while offset < bytes.len() {
+            let rest = &bytes[offset..];
+            let length = rest
+                .get(..4)
+                .map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize);
+            match Chunk::parse_with(rest, options) {
+                Ok((chunk, warning)) => {
+                    let next = offset + chunk.length() as usize + 12;
+                    // CRC 错误的 chunk 之后不是合理的边界，而更早的位置有一个完整的 chunk 时，
+                    // 说明长度字段已经损坏，丢弃这个 chunk 并从那里继续
+                    let resync = match warning {
+                        Some(_) if next < bytes.len() && !parse::is_chunk_at(bytes, next) => {
+                            parse::find_next_chunk(bytes, offset + 1).filter(|&found| found < next)
+                        }
+                        _ => None,
+                    };
+                    if let Some(found) = resync {
+                        warnings.push(ParseWarning {
+                            offset,
+                            chunk_type: chunk.chunk_type().to_string(),
+                            message: format!(
+                                "corrupted length field, skipped {} bytes to the next valid chunk",
+                                found - offset
+                            ),
+                        });
+                        offset = found;
+                        continue;
+                    }
+                    warnings.extend(warning.map(|warning| ParseWarning { offset, ..warning }));
+                    offset = next;
+                    chunks.push(chunk);
+                }
+                Err(e) if options.strict => return Err(e),
+                // 类型码无效但长度可信时跳过这个 chunk
+                Err(PngmeError::InvalidChunkType(raw))
+                    if length.is_some_and(|length| length + 12 <= rest.len()) =>
+                {
+                    warnings.push(ParseWarning {
+                        offset,
+                        chunk_type: preview::escape(&String::from_utf8_lossy(&raw)),
+                        message: "invalid chunk type, chunk skipped".to_string(),
+                    });
+                    offset += length.unwrap() + 12;
+                }
+                Err(e) => {
+                    let chunk_type = rest
+                        .get(4..8)
+                        .map(|bytes| preview::escape(&String::from_utf8_lossy(bytes)))
+                        .unwrap_or_default();
+                    match parse::find_next_chunk(bytes, offset + 1) {
+                        Some(found) => {
+                            warnings.push(ParseWarning {
+                                offset,
+                                chunk_type,
+                                message: format!(
+                                    "{}, skipped {} bytes to the next valid chunk",
+                                    e,
+                                    found - offset
+                                ),
+                            });
+                            offset = found;
+                        }
+                        None => {
+                            warnings.push(ParseWarning {
+                                offset,
+                                chunk_type,
+                                message: format!("{}, parsing stopped", e),
+                            });
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Ok((Png { chunks }, warnings))
+    }
+
+    /// 解析旧版本 pngme 写入的文件，接受 CKSUM 形式的 CRC，
+    /// 解析结果带有规范 CRC，调用 `as_bytes` 写回即可完成迁移
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Png> {
+        if bytes.len() < 8 || bytes[..8] != Self::STANDARD_HEADER {
+            return Err(sniff::signature_error(bytes));
+        }
+        Png::parse_chunks(&bytes[8..], &Limits::default(), Chunk::from_legacy_bytes)
+    }
+
+    /// 解析 Png 但不校验 CRC，保留每个 chunk 中存储的 CRC，配合 `repair_crcs` 使用
+    pub fn from_bytes_unchecked(bytes: &[u8]) -> Result<Png> {
+        Png::from_bytes_unchecked_with_limits(bytes, &Limits::default())
+    }
+
+    /// 与 `from_bytes_unchecked` 相同，但使用指定的安全限制
+    pub fn from_bytes_unchecked_with_limits(bytes: &[u8], limits: &Limits) -> Result<Png> {
+        limits.check_file_size(bytes.len() as u64)?;
+        if bytes.len() < 8 || bytes[..8] != Self::STANDARD_HEADER {
+            return Err(sniff::signature_error(bytes));
+        }
+        Png::parse_chunks(&bytes[8..], limits, Chunk::from_bytes_unchecked)
+    }
+
+    /// 按顺序返回每个 chunk 及其在 `as_bytes()` 输出中的偏移（长度字段所在的位置）。
+    /// 第一个 chunk 从签名之后的偏移 8 开始，数据从 `offset + 8` 开始，CRC 位于 `offset + 8 + length`。
+    /// 严格解析是无损的，因此对于从文件解析出、尚未修改的 Png，偏移就是 chunk 在原文件中的位置，
+    /// 可以据此只改写文件中的几个字节
+    pub fn iter_with_offsets(&self) -> ChunkOffsets<'_> {
+        ChunkOffsets {
+            chunks: self.chunks.iter(),
+            offset: Self::STANDARD_HEADER.len() as u64,
+        }
+    }
+
+    /// 校验签名后返回逐个借用 chunk 的迭代器，不复制数据也不校验 CRC，用于大文件的只读扫描
+    pub fn chunk_refs(bytes: &[u8]) -> Result<ChunkRefs<'_>> {
+        if bytes.len() < 8 || bytes[..8] != Self::STANDARD_HEADER {
+            return Err(sniff::signature_error(bytes));
+        }
+        Ok(ChunkRefs {
+            rest: &bytes[8..],
+            done: false,
+        })
+    }
+
+    /// 在 chunk 层面与 `other` 比较，`self` 为修改前的文件
+    pub fn diff(&self, other: &Png) -> PngDiff {
+        diff::diff(self, other)
+    }
+
+    /// 拼接所有 IDAT 并解压，得到带过滤类型字节的原始图像数据。
+    /// 输出不能超过 IHDR 声明的大小，也不能超过 `limits.max_decompressed_size`，超出时返回
+    /// `PngmeError::DecompressionBomb`
+    pub fn image_data(&self, limits: &Limits) -> Result<Vec<u8>> {
+        let expected = self.header()?.raw_data_len();
+        let compressed: Vec<u8> = self
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().bytes() == *b"IDAT")
+            .flat_map(|chunk| chunk.data().iter().copied())
+            .collect();
+        let max = expected.min(limits.max_decompressed_size(compressed.len()));
+        limits.inflate_at_most(&compressed, max)
+    }
+
+    /// 解压并撤销过滤，得到逐行排列的像素数据（每行 `Ihdr::row_bytes` 字节），
+    /// 隔行图片会先重组为完整图像
+    pub fn pixels(&self, limits: &Limits) -> Result<Vec<u8>> {
+        let header = self.header()?;
+        let passes = interlace::unfilter(&header, &self.image_data(limits)?)?;
+        Ok(interlace::deinterlace(&header, &passes))
+    }
+
+    /// 用压缩后的 `raw`（带过滤类型字节的原始图像数据）替换所有 IDAT，
+    /// 新的 IDAT 放在原来第一个 IDAT 的位置，每个不超过 1 MiB
+    pub fn set_image_data(&mut self, raw: &[u8]) -> Result<()> {
+        self.replace_image_data(&compress(raw, Compression::best())?)
+    }
+
+    /// 用已压缩的数据替换所有 IDAT
+    fn replace_image_data(&mut self, compressed: &[u8]) -> Result<()> {
+        self.replace_image_data_in(compressed, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// 用已压缩的数据替换所有 IDAT，每个 IDAT 不超过 `part_size` 字节
+    fn replace_image_data_in(&mut self, compressed: &[u8], part_size: usize) -> Result<()> {
+        let is_idat = |chunk: &Chunk| chunk.chunk_type().bytes() == *b"IDAT";
+        let index = self
+            .chunks
+            .iter()
+            .position(is_idat)
+            .ok_or_else(|| PngmeError::ChunkNotFound("IDAT".to_string()))?;
+        self.chunks.retain(|chunk| !is_idat(chunk));
+        let idat = ChunkType::try_from(*b"IDAT")?;
+        let parts = compressed
+            .chunks(part_size)
+            .map(|part| Chunk::new(idat, part.to_vec()))
+            .collect::<Result<Vec<_>>>()?;
+        self.chunks.splice(index..index, parts);
+        Ok(())
+    }
+
+    /// 在不改变（`reduce_bit_depth` 时只降低精度）图像的前提下减小文件：按 `options` 删除多余的 chunk，
+    /// 再用原来的过滤和逐行挑选的过滤分别以 `options.level` 重新压缩 IDAT，只有更小时才替换。
+    /// 返回被删除的 chunk。APNG 的其余帧不会被重新压缩，也不能降低位深。
+    /// 每次压缩前检查 `cancel`，已取消时返回 `PngmeError::Cancelled`
+    pub fn optimize(
+        &mut self,
+        options: &OptimizeOptions,
+        limits: &Limits,
+        cancel: &CancelToken,
+    ) -> Result<Vec<Chunk>> {
+        cancel.check()?;
+        let removed = if options.remove_redundant {
+            self.remove_redundant_chunks()
+        } else {
+            Vec::new()
+        };
+        let level = Compression::new(options.level.min(9));
+        let mut header = self.header()?;
+
+        if options.reduce_bit_depth && header.bit_depth == 16 {
+            if self.is_animated() {
+                return Err(PngmeError::InvalidAnimation(
+                    "cannot reduce the bit depth of an animated PNG".to_string(),
+                ));
+            }
+            let image: Vec<u8> = self
+                .pixels(limits)?
+                .chunks_exact(2)
+                .map(|sample| scale_to_8_bits(u16::from_be_bytes([sample[0], sample[1]])) as u8)
+                .collect();
+            header.bit_depth = 8;
+            let raw = interlace::filter_adaptive(&header, &interlace::interlace(&header, &image));
+            cancel.check()?;
+            self.replace_image_data(&compress(&raw, level)?)?;
+            self.replace_header(header);
+            self.reduce_ancillary_bit_depth()?;
+            return Ok(removed);
+        }
+
+        let raw = self.image_data(limits)?;
+        let refiltered = interlace::filter_adaptive(&header, &interlace::unfilter(&header, &raw)?);
+        let current: usize = self
+            .chunks_by_type("IDAT")
+            .iter()
+            .map(|chunk| chunk.data().len())
+            .sum();
+        let mut best = Vec::new();
+        for raw in [&raw, &refiltered] {
+            cancel.check()?;
+            let compressed = compress(raw, level)?;
+            if best.is_empty() || compressed.len() < best.len() {
+                best = compressed;
+            }
+        }
+        if best.len() < current {
+            self.replace_image_data(&best)?;
+        }
+        Ok(removed)
+    }
+
+    /// 改写为确定的规范形式，使像素和有意义的元数据相同的图片得到逐字节相同的文件，
+    /// 适合放进版本控制。返回被删除的 chunk。规范形式为：
+    ///
+    /// - 删除多余的 chunk（同 `OptimizeOptions::remove_redundant`）和易变的 chunk
+    ///   （`tIME`，以及关键字在 `VOLATILE_KEYWORDS` 中的文本 chunk）；
+    /// - 撤销原来的过滤，用 `interlace::filter_adaptive` 重新过滤，以压缩级别 9 压缩成一个 IDAT；
+    /// - chunk 按 `ordering::canonical_rank` 分组排列，组内按类型码的字节排序，
+    ///   同类型的 chunk 保持原来的相对顺序。
+    ///
+    /// APNG 的帧顺序有意义，不能重排，因此返回错误。重新过滤和压缩前检查 `cancel`，
+    /// 已取消时返回 `PngmeError::Cancelled`
+    pub fn canonicalize(&mut self, limits: &Limits, cancel: &CancelToken) -> Result<Vec<Chunk>> {
+        if self.is_animated() {
+            return Err(PngmeError::InvalidAnimation(
+                "cannot canonicalize an animated PNG".to_string(),
+            ));
+        }
+        let mut removed = self.remove_redundant_chunks();
+        let (volatile, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(is_volatile);
+        self.chunks = kept;
+        removed.extend(volatile);
+
+        let header = self.header()?;
+        cancel.check()?;
+        let raw = interlace::filter_adaptive(
+            &header,
+            &interlace::unfilter(&header, &self.image_data(limits)?)?,
+        );
+        cancel.check()?;
+        self.replace_image_data_in(
+            &compress(&raw, Compression::best())?,
+            MAX_CHUNK_LENGTH as usize,
+        )?;
+        self.chunks.sort_by_cached_key(|chunk| {
+            let chunk_type = chunk.chunk_type();
+            (
+                ordering::canonical_rank(&chunk_type.to_string()),
+                chunk_type.bytes(),
+            )
+        });
+        Ok(removed)
+    }
+
+    /// 删除多余的 chunk，返回被删除的 chunk
+    fn remove_redundant_chunks(&mut self) -> Vec<Chunk> {
+        let mut kept: Vec<Chunk> = Vec::with_capacity(self.chunks.len());
+        let mut removed = Vec::new();
+        for chunk in std::mem::take(&mut self.chunks) {
+            let chunk_type = chunk.chunk_type();
+            let redundant = kept.iter().any(|existing| {
+                existing.chunk_type() == chunk_type
+                    && (ordering::is_single(&chunk_type.to_string())
+                        || (!chunk_type.is_critical()
+                            && chunk_type.known().is_some()
+                            && existing.data() == chunk.data()))
+            });
+            if redundant {
+                removed.push(chunk);
+            } else {
+                kept.push(chunk);
+            }
+        }
+        self.chunks = kept;
+        removed
+    }
+
+    /// 图片从 16 位转换为 8 位后，同样转换 tRNS、bKGD 中的颜色值和 sBIT 中的有效位数
+    fn reduce_ancillary_bit_depth(&mut self) -> Result<()> {
+        for chunk in &mut self.chunks {
+            let data = match &chunk.chunk_type().bytes() {
+                b"tRNS" | b"bKGD" => chunk
+                    .data()
+                    .chunks(2)
+                    .flat_map(|value| match value {
+                        [high, low] => {
+                            scale_to_8_bits(u16::from_be_bytes([*high, *low])).to_be_bytes()
+                        }
+                        _ => [0, value[0]],
+                    })
+                    .collect(),
+                b"sBIT" => chunk.data().iter().map(|&bits| bits.min(8)).collect(),
+                _ => continue,
+            };
+            *chunk = Chunk::new(*chunk.chunk_type(), data)?;
+        }
+        Ok(())
+    }
+
+    /// 把 Adam7 隔行图片转换为逐行图片：重组像素、用 None 过滤重新压缩 IDAT 并修改 IHDR。
+    /// 像素不变，不是隔行图片时什么也不做并返回 `false`。APNG 的每一帧都按 IHDR 隔行，
+    /// 只转换 IDAT 会破坏其余帧，因此返回错误
+    pub fn deinterlace(&mut self, limits: &Limits) -> Result<bool> {
+        let mut header = self.header()?;
+        if header.interlace == Interlace::None {
+            return Ok(false);
+        }
+        if self.is_animated() {
+            return Err(PngmeError::InvalidAnimation(
+                "cannot deinterlace an animated PNG".to_string(),
+            ));
+        }
+        let image = self.pixels(limits)?;
+        header.interlace = Interlace::None;
+        let raw = interlace::filter(&header, &interlace::interlace(&header, &image));
+        self.set_image_data(&raw)?;
+        self.replace_header(header);
+        Ok(true)
+    }
+
+    /// 用 `header` 替换 IHDR chunk，位置不变
+    fn replace_header(&mut self, header: Ihdr) {
+        let chunk = header.to_chunk();
+        match self
+            .chunks
+            .iter_mut()
+            .find(|existing| existing.chunk_type() == chunk.chunk_type())
+        {
+            Some(existing) => *existing = chunk,
+            None => self.chunks.insert(0, chunk),
+        }
+    }
+
+    /// 重新计算所有 chunk 的 CRC，返回被修复的 chunk 的下标
+    pub fn repair_crcs(&mut self) -> Vec<usize> {
+        self.chunks
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, chunk)| chunk.recompute_crc().then_some(index))
+            .collect()
+    }
+
+    /// 校验所有 chunk 的 CRC，按 chunk 顺序返回每个 chunk 的结果。
+    /// 启用 `rayon` feature 时在多个线程上并行计算
+    pub fn verify_all_crcs(&self) -> Vec<CrcCheck> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            self.chunks
+                .par_iter()
+                .enumerate()
+                .map(|(index, chunk)| CrcCheck::of(index, chunk))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            self.chunks
+                .iter()
+                .enumerate()
+                .map(|(index, chunk)| CrcCheck::of(index, chunk))
+                .collect()
+        }
+    }
+
+    /// 依次解析签名之后的所有 chunk
+    fn parse_chunks(
+        mut rest: &[u8],
+        limits: &Limits,
+        parse: impl Fn(&[u8]) -> Result<Chunk>,
+    ) -> Result<Png> {
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            // 在复制数据之前检查长度字段和类型码
+            if let Some(length) = rest.get(..4) {
+                limits.check_chunk_size(u32::from_be_bytes(length.try_into().unwrap()))?;
+            }
+            if let Some(chunk_type) = rest.get(4..8) {
+                limits.check_chunk_type(chunk_type.try_into().unwrap())?;
+            }
+            limits.check_chunk_count(chunks.len() + 1)?;
+            let chunk = parse(rest)?;
+            rest = &rest[(chunk.length() as usize + 12)..];
+            chunks.push(chunk);
+        }
+        Ok(Png { chunks })
+    }
+
+    /// 追加一个 chunk，按规范为它的类型选择位置（见 `ChunkPosition::for_type`）：
+    /// 一般的 chunk 插入到 IEND 之前；APNG 中插入到第一个 fcTL 或 IDAT 之前，
+    /// 避免夹在帧序列中间
+    pub fn append_chunk(&mut self, chunk: Chunk) {
+        let position = ChunkPosition::for_type(&chunk.chunk_type().to_string(), self.is_animated());
+        self.insert_chunk(chunk, position);
+    }
+
+    /// 在指定位置插入一个 chunk
+    pub fn insert_chunk(&mut self, chunk: Chunk, position: ChunkPosition) {
+        let index = position.index(&self.chunks);
+        self.chunks.insert(index, chunk);
+    }
+
+    /// 开始一次事务式编辑：记录的操作在 `commit` 时一次性应用，顺序违反规范时不做任何修改（见 `editor`）
+    pub fn edit(&mut self) -> PngEditor<'_> {
+        PngEditor::new(self)
+    }
+
+    /// 检查 chunk 顺序是否符合规范，返回所有问题
+    pub fn validate_ordering(&self) -> Vec<OrderingIssue> {
+        ordering::validate(&self.chunks)
+    }
+
+    /// 删除第一个指定类型的 chunk 并返回它
+    pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngmeError::ChunkNotFound(chunk_type.to_string()))?;
+        Ok(self.chunks.remove(index))
+    }
+
+    /// 删除所有指定类型的 chunk，返回被删除的 chunk（可能为空）
+    pub fn remove_all_chunks(&mut self, chunk_type: &str) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|chunk: &Chunk| chunk.chunk_type().to_string() == chunk_type);
+        self.chunks = kept;
+        removed
+    }
+
+    /// 删除 `chunks()` 中第 `index` 个 chunk 并返回它
+    pub fn remove_chunk_at(&mut self, index: usize) -> Result<Chunk> {
+        if index >= self.chunks.len() {
+            return Err(PngmeError::ChunkNotFound(format!(
+                "chunk #{} (the file has {} chunks)",
+                index,
+                self.chunks.len()
+            )));
+        }
+        Ok(self.chunks.remove(index))
+    }
+
+    /// 从 `source` 复制 `filter` 选中的辅助 chunk，返回复制的 chunk。
+    /// 关键 chunk 和 APNG 的帧控制 chunk（acTL、fcTL、fdAT）从不复制。每个 chunk 按规范为其类型选择的位置插入
+    /// （见 `append_chunk`），同类型的 chunk 保持在 `source` 中的相对顺序；只能出现一次的类型替换已有的同类型 chunk，
+    /// 与已有 chunk 完全相同的不会重复复制
+    pub fn merge_from(&mut self, source: &Png, filter: &MergeFilter) -> Vec<Chunk> {
+        let selected: Vec<&Chunk> = source
+            .chunks
+            .iter()
+            .filter(|chunk| {
+                let chunk_type = chunk.chunk_type();
+                if chunk_type.is_critical()
+                    || matches!(&chunk_type.bytes(), b"acTL" | b"fcTL" | b"fdAT")
+                {
+                    return false;
+                }
+                match filter {
+                    MergeFilter::Types(types) => types.contains(chunk_type),
+                    MergeFilter::SafeToCopy => chunk_type.is_safe_to_copy(),
+                }
+            })
+            .collect();
+        let mut copied = Vec::new();
+        for chunk in selected {
+            if self.chunks.contains(chunk) {
+                continue;
+            }
+            let chunk_type = chunk.chunk_type().to_string();
+            if ordering::is_single(&chunk_type) {
+                self.remove_all_chunks(&chunk_type);
+            }
+            self.append_chunk(chunk.clone());
+            copied.push(chunk.clone());
+        }
+        copied
+    }
+
+    /// 删除所有辅助 chunk（`keep` 中的类型除外），返回被删除的 chunk
+    pub fn strip_ancillary(&mut self, keep: &[ChunkType]) -> Vec<Chunk> {
+        let (kept, removed) =
+            std::mem::take(&mut self.chunks)
+                .into_iter()
+                .partition(|chunk: &Chunk| {
+                    chunk.chunk_type().is_critical() || keep.contains(chunk.chunk_type())
+                });
+        self.chunks = kept;
+        removed
+    }
+
+    /// 用 Ed25519 私钥对关键 chunk 签名，签名保存在 `siGN` chunk 中，已有的签名会被替换
+    pub fn sign(&mut self, key: &SigningKey) {
+        self.remove_all_chunks(signature::CHUNK_TYPE);
+        let chunk = signature::sign(&self.chunks, key);
+        self.append_chunk(chunk);
+    }
+
+    /// 用 Ed25519 公钥校验 `siGN` chunk 中的签名
+    pub fn verify(&self, key: &VerifyingKey) -> Result<()> {
+        signature::verify(&self.chunks, key)
+    }
+
+    /// 删除所有同类型的 chunk，再追加新的 chunk，用于 pHYs、tIME 等只能出现一次的 chunk
+    pub fn replace_chunk(&mut self, chunk: Chunk) {
+        self.chunks
+            .retain(|existing| existing.chunk_type() != chunk.chunk_type());
+        self.append_chunk(chunk);
+    }
+
+    /// 写入一条文本元数据，替换同一关键字已有的 tEXt、zTXt 和 iTXt。
+    /// 文本能用 Latin-1 表示时写入 tEXt，否则写入 iTXt
+    pub fn set_text(&mut self, keyword: &str, text: &str) -> Result<()> {
+        let chunk = text::text_chunk(keyword, text)?;
+        self.chunks
+            .retain(|chunk| text::keyword(chunk).as_deref() != Some(keyword));
+        self.append_chunk(chunk);
+        Ok(())
+    }
+
+    /// 读取关键字对应的文本，同一关键字有多个文本 chunk 时返回最后一个
+    pub fn get_text(&self, keyword: &str) -> Option<String> {
+        self.chunks
+            .iter()
+            .rev()
+            .filter_map(text::entry)
+            .find(|(key, _)| key == keyword)
+            .map(|(_, text)| text)
+    }
+
+    /// 删除关键字对应的所有 tEXt、zTXt 和 iTXt，返回被删除的 chunk
+    pub fn remove_text(&mut self, keyword: &str) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|chunk| text::keyword(chunk).as_deref() == Some(keyword));
+        self.chunks = kept;
+        removed
+    }
+
+    /// 所有文本元数据（关键字 → 文本），同一关键字出现多次时保留最后一个，无法解析的 chunk 被跳过
+    pub fn text_metadata(&self) -> HashMap<String, String> {
+        self.chunks.iter().filter_map(text::entry).collect()
+    }
+
+    /// 是否为 APNG（带有 acTL chunk）
+    pub fn is_animated(&self) -> bool {
+        self.chunk_by_type(AnimationControl::CHUNK_TYPE).is_some()
+    }
+
+    /// 解析 acTL chunk，不是 APNG 时返回 `None`
+    pub fn animation_control(&self) -> Result<Option<AnimationControl>> {
+        self.chunk_by_type(AnimationControl::CHUNK_TYPE)
+            .map(AnimationControl::try_from)
+            .transpose()
+    }
+
+    /// 按顺序解析所有 fcTL chunk
+    pub fn frame_controls(&self) -> Result<Vec<FrameControl>> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == FrameControl::CHUNK_TYPE)
+            .map(FrameControl::try_from)
+            .collect()
+    }
+
+    /// 帧数，静态图片为 1
+    pub fn frame_count(&self) -> Result<u32> {
+        Ok(self
+            .animation_control()?
+            .map_or(1, |control| control.num_frames))
+    }
+
+    /// 第 `frame` 帧（从 0 开始）的 fcTL 及其数据在 `chunks()` 中的下标范围，
+    /// 帧中插入的其他 chunk 也在范围内；没有该帧时返回 `None`
+    pub fn frame_range(&self, frame: u32) -> Option<Range<usize>> {
+        ordering::frame_range(&self.chunks, frame)
+    }
+
+    /// 每一帧的显示时长，静态图片返回空列表
+    pub fn frame_delays(&self) -> Result<Vec<Duration>> {
+        Ok(self
+            .frame_controls()?
+            .iter()
+            .map(FrameControl::delay)
+            .collect())
+    }
+
+    /// 获取 PNG 文件签名
+    pub fn signature(&self) -> &[u8; 8] {
+        &Self::STANDARD_HEADER
+    }
+
+    /// 估算可以隐藏的字节数。
+    ///
+    /// - `Chunks`：单个 chunk 最多容纳的数据量，拆分到多个 chunk 时没有上限；
+    /// - `Lsb`：每个样本 1 位时的容量（含 `--method lsb` 的头部）；调色板图片改动索引会明显失真，
+    ///   低于 8 位的样本改动最低位也很明显，容量为 0
+    pub fn capacity(&self, method: CapacityMethod) -> Result<u64> {
+        match method {
+            CapacityMethod::Chunks => Ok(MAX_CHUNK_LENGTH as u64),
+            CapacityMethod::Lsb => {
+                let header = self.header()?;
+                if header.color_type == ColorType::Indexed || header.bit_depth < 8 {
+                    return Ok(0);
+                }
+                let samples = header.width as u64
+                    * header.height as u64
+                    * header.color_type.channels() as u64;
+                Ok(samples / 8)
+            }
+        }
+    }
+
+    /// 解析并校验 IHDR chunk
+    pub fn header(&self) -> Result<Ihdr> {
+        let chunk = self
+            .chunk_by_type("IHDR")
+            .ok_or_else(|| PngmeError::ChunkNotFound("IHDR".to_string()))?;
+        Ihdr::try_from(chunk)
+    }
+
+    /// 解析调色板（PLTE），没有时返回 `None`
+    pub fn palette(&self) -> Result<Option<Palette>> {
+        self.chunk_by_type(Palette::CHUNK_TYPE)
+            .map(Palette::try_from)
+            .transpose()
+    }
+
+    /// 按 IHDR 中的颜色类型解析透明度（tRNS），没有时返回 `None`
+    pub fn transparency(&self) -> Result<Option<Transparency>> {
+        match self.chunk_by_type(Transparency::CHUNK_TYPE) {
+            Some(chunk) => Transparency::from_chunk(chunk, self.header()?.color_type).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 写回调色板：原地替换已有的 PLTE，其余 chunk 不动；没有 PLTE 时插入到 tRNS、bKGD、hIST
+    /// 和图像数据之前。调色板必须与 IHDR 相符，已有的 tRNS 项数不能超过新的调色板
+    pub fn set_palette(&mut self, palette: &Palette) -> Result<()> {
+        let header = self.header()?;
+        palette.check(&header)?;
+        if let Some(transparency) = self.transparency()? {
+            transparency.check(&header, palette.len())?;
+        }
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| {
+                matches!(
+                    &chunk.chunk_type().bytes(),
+                    b"tRNS" | b"bKGD" | b"hIST" | b"IDAT" | b"fcTL"
+                )
+            })
+            .unwrap_or_else(|| ChunkPosition::BeforeIend.index(&self.chunks));
+        self.put_chunk(palette.to_chunk(), index);
+        Ok(())
+    }
+
+    /// 写回透明度：原地替换已有的 tRNS，其余 chunk 不动；没有 tRNS 时插入到 PLTE 之后、图像数据之前。
+    /// 格式必须与 IHDR 的颜色类型相符，调色板图片需要先有 PLTE
+    pub fn set_transparency(&mut self, transparency: &Transparency) -> Result<()> {
+        let palette_len = self.palette()?.map_or(0, |palette| palette.len());
+        transparency.check(&self.header()?, palette_len)?;
+        let index = ChunkPosition::BeforeIdat.index(&self.chunks);
+        self.put_chunk(transparency.to_chunk(), index);
+        Ok(())
+    }
+
+    /// 解析 eXIf chunk，没有时返回 `None`
+    pub fn exif(&self) -> Result<Option<Exif>> {
+        self.chunk_by_type(Exif::CHUNK_TYPE)
+            .map(Exif::try_from)
+            .transpose()
+    }
+
+    /// 写入 eXIf：原地替换已有的 eXIf，没有时插入到图像数据之前
+    pub fn set_exif(&mut self, exif: &Exif) -> Result<()> {
+        let index = ChunkPosition::BeforeIdat.index(&self.chunks);
+        self.put_chunk(exif.to_chunk()?, index);
+        Ok(())
+    }
+
+    /// 解析 iCCP chunk 并按默认的安全限制解压配置，没有时返回 `None`
+    pub fn icc_profile(&self) -> Result<Option<IccProfile>> {
+        self.chunk_by_type(IccProfile::CHUNK_TYPE)
+            .map(IccProfile::try_from)
+            .transpose()
+    }
+
+    /// 写入 ICC 配置：原地替换已有的 iCCP，没有时插入到 PLTE 和图像数据之前。
+    /// `profile` 必须以 ICC 文件头开始；规范不允许 iCCP 与 sRGB 同时存在，已有的 sRGB 会被删除
+    pub fn set_icc_profile(&mut self, name: &str, profile: &[u8]) -> Result<()> {
+        if profile.get(36..40) != Some(b"acsp") {
+            return Err(PngmeError::InvalidMetadata(
+                "not an ICC profile: missing the acsp signature".to_string(),
+            ));
+        }
+        let icc = IccProfile {
+            name: name.to_string(),
+            profile: profile.to_vec(),
+        };
+        let chunk = icc.to_chunk()?;
+        self.remove_all_chunks(RenderingIntent::CHUNK_TYPE);
+        let index = ChunkPosition::BeforePlte.index(&self.chunks);
+        self.put_chunk(chunk, index);
+        Ok(())
+    }
+
+    /// 替换第一个同类型的 chunk；没有时插入到 `index`
+    fn put_chunk(&mut self, chunk: Chunk, index: usize) {
+        match self
+            .chunks
+            .iter_mut()
+            .find(|existing| existing.chunk_type() == chunk.chunk_type())
+        {
+            Some(existing) => *existing = chunk,
+            None => self.chunks.insert(index, chunk),
+        }
+    }
+
+    /// 获取所有 chunk
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// 获取第一个指定类型的 chunk
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    /// 获取所有 chunk 的可变引用，用 `Chunk::set_data` 等方法修改数据时长度和 CRC 会自动更新
+    pub fn chunks_mut(&mut self) -> &mut [Chunk] {
+        &mut self.chunks
+    }
+
+    /// 获取第一个指定类型的 chunk 的可变引用
+    pub fn chunk_by_type_mut(&mut self, chunk_type: &str) -> Option<&mut Chunk> {
+        self.chunks
+            .iter_mut()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    /// 按在文件中的顺序获取所有指定类型的 chunk
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .collect()
+    }
+
+    /// 第 `nth` 个（从 0 开始）指定类型的 chunk 在 `chunks()` 中的序号
+    pub fn position_of(&self, chunk_type: &str, nth: usize) -> Option<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.chunk_type().to_string() == chunk_type)
+            .nth(nth)
+            .map(|(index, _)| index)
+    }
+
+    /// 将 Png 编码为字节序列
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut result = Self::STANDARD_HEADER.to_vec();
+        for chunk in &self.chunks {
+            result.extend(chunk.as_bytes());
+        }
+        result
+    }
+
+    /// 按 `options` 序列化，不修改 `self`
+    pub fn to_bytes_with(&self, options: &WriteOptions) -> Vec<u8> {
+        let mut chunks: Vec<Chunk> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !(options.strip_volatile && is_volatile(chunk)))
+            .cloned()
+            .collect();
+        if options.normalize_order {
+            ordering::normalize(&mut chunks);
+        }
+        if options.recompute_crc {
+            chunks.iter_mut().for_each(|chunk| {
+                chunk.recompute_crc();
+            });
+        }
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    /// 按 `options` 写出到 `writer`
+    pub fn write_with<W: Write>(&self, mut writer: W, options: &WriteOptions) -> Result<()> {
+        writer.write_all(&self.to_bytes_with(options))?;
+        Ok(())
+    }
+
+    /// 以确定的字节布局写出（`WriteOptions::canonical`）：相同的 chunk 总是得到相同的字节，
+    /// 与 chunk 是按什么顺序加入的、读入时 CRC 是否正确无关。向同一个文件写入同样的（未加密的）信息
+    /// 总是得到逐字节相同的结果，适合可复现构建和按内容寻址的存储。
+    /// 与 `canonicalize` 不同，这里不重新压缩图像数据，也不删除任何 chunk
+    pub fn write_canonical<W: Write>(&self, writer: W) -> Result<()> {
+        self.write_with(writer, &WriteOptions::canonical())
+    }
+}
+
+/// 规范形式中删除的文本 chunk 关键字：编辑器和转换工具写入的创建、修改时间
+pub const VOLATILE_KEYWORDS: [&str; 5] = [
+    "Creation Time",
+    "date:create",
+    "date:modify",
+    "date:timestamp",
+    "modify-date",
+];
+
+/// 是否为规范形式中删除的易变 chunk：`tIME` 和记录时间的文本 chunk
+fn is_volatile(chunk: &Chunk) -> bool {
+    match &chunk.chunk_type().bytes() {
+        b"tIME" => true,
+        b"tEXt" | b"zTXt" | b"iTXt" => {
+            let keyword = chunk.data().split(|&byte| byte == 0).next().unwrap_or(&[]);
+            VOLATILE_KEYWORDS
+                .iter()
+                .any(|volatile| volatile.as_bytes() == keyword)
+        }
+        _ => false,
+    }
+}
+
+/// 以 `level` 压缩原始图像数据
+fn compress(raw: &[u8], level: Compression) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), level);
+    encoder.write_all(raw)?;
+    Ok(encoder.finish()?)
+}
+
+/// 把 16 位样本四舍五入为 8 位
+fn scale_to_8_bits(value: u16) -> u16 {
+    ((value as u32 * 255 + 32767) / 65535) as u16
+}
+
+/// `Png::iter_with_offsets` 返回的迭代器，产生 `(偏移, ChunkRef)`
+pub struct ChunkOffsets<'a> {
+    chunks: std::slice::Iter<'a, Chunk>,
+    offset: u64,
+}
+
+impl<'a> Iterator for ChunkOffsets<'a> {
+    type Item = (u64, ChunkRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?.as_chunk_ref();
+        let offset = self.offset;
+        self.offset += chunk.encoded_len() as u64;
+        Some((offset, chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ChunkOffsets<'_> {}
+
+/// `Png::chunk_refs` 返回的迭代器，遇到错误后停止
+pub struct ChunkRefs<'a> {
+    rest: &'a [u8],
+    done: bool,
+}
+
+impl<'a> Iterator for ChunkRefs<'a> {
+    type Item = Result<ChunkRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.rest.is_empty() {
+            return None;
+        }
+        match ChunkRef::parse(self.rest) {
+            Ok(chunk) => {
+                self.rest = &self.rest[chunk.encoded_len()..];
+                Some(Ok(chunk))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = Error;
+
+    /// 从字节序列解析 Png，使用默认的安全限制
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Png::from_bytes_with_limits(bytes, &Limits::default())
+    }
+}
+
+impl fmt::Display for Png {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = self.header().ok();
+        writeln!(f, "Png {{")?;
+        for chunk in &self.chunks {
+            write!(
+                f,
+                "  {} ({} bytes, CRC {:08X})",
+                chunk.chunk_type(),
+                chunk.length(),
+                chunk.crc()
+            )?;
+            if let Some(description) =
+                describe::describe(chunk.chunk_type(), chunk.data(), header.as_ref())
+            {
+                write!(f, " {}", description)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn testing_chunks() -> Vec<Chunk> {
+        vec![
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+            chunk_from_strings("miDl", "I am another chunk").unwrap(),
+            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
+        ]
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(testing_chunks())
+    }
+
+    /// 由逐行的像素数据（不含过滤类型字节）构造只有 IHDR、IDAT 和 IEND 的图片
+    pub(crate) fn testing_image(header: Ihdr, pixels: &[u8]) -> Png {
+        let chunk = |chunk_type: &str| chunk_from_strings(chunk_type, "").unwrap();
+        let mut png = Png::from_chunks(vec![header.to_chunk(), chunk("IDAT"), chunk("IEND")]);
+        let raw = interlace::filter(&header, &interlace::interlace(&header, pixels));
+        png.set_image_data(&raw).unwrap();
+        png
+    }
+
+    /// 由类型和数据构造 chunk
+    pub(crate) fn testing_chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec()).unwrap()
+    }
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk> {
+        let chunk_type = ChunkType::from_str(chunk_type)?;
+        let data: Vec<u8> = data.bytes().collect();
+
+        Ok(Chunk::new(chunk_type, data).unwrap())
+    }
+
+    #[test]
+    fn test_from_chunks() {
+        let chunks = testing_chunks();
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_valid_from_bytes() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_ok());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = [13, 80, 78, 71, 13, 10, 26, 10]
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        #[rustfmt::skip]
+        let mut bad_chunk = vec![
+            0, 0, 0, 5,         // length
+            32, 117, 83, 116,   // Chunk Type (bad)
+            65, 64, 65, 66, 67, // Data
+            1, 2, 3, 4, 5       // CRC (bad)
+        ];
+
+        chunk_bytes.append(&mut bad_chunk);
+
+        let png = Png::try_from(chunk_bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_truncated_chunk() {
+        let mut bytes = testing_png().as_bytes();
+        bytes.truncate(bytes.len() - 3);
+
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let limits = Limits {
+            max_chunks: 2,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            Png::from_bytes_with_limits(&PNG_FILE, &limits),
+            Err(PngmeError::LimitExceeded(_))
+        ));
+        let limits = Limits {
+            max_file_size: 16,
+            ..Limits::default()
+        };
+        assert!(Png::from_bytes_with_limits(&PNG_FILE, &limits).is_err());
+        Png::from_bytes_with_limits(&PNG_FILE, &Limits::permissive()).unwrap();
+
+        // 长度字段声称 4 GiB 的 chunk 在分配内存之前就被拒绝
+        let mut crafted = Png::STANDARD_HEADER.to_vec();
+        crafted.extend([0xff, 0xff, 0xff, 0xff]);
+        crafted.extend(b"IDAT");
+        assert!(matches!(
+            Png::from_bytes_with_limits(&crafted, &Limits::default()),
+            Err(PngmeError::LimitExceeded(_))
+        ));
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec()).unwrap(),
+        );
+        let bytes = png.as_bytes();
+        let text_only =
+            Limits::default().with_allowed_types([ChunkType::from_str("tEXt").unwrap()]);
+        assert_eq!(
+            Png::from_bytes_with_limits(&bytes, &text_only)
+                .unwrap()
+                .chunks()
+                .len(),
+            4
+        );
+        let critical_only = Limits::default().with_allowed_types([]);
+        Png::from_bytes_with_limits(&PNG_FILE, &critical_only).unwrap();
+        assert!(matches!(
+            Png::from_bytes_with_limits(&bytes, &critical_only),
+            Err(PngmeError::LimitExceeded(message)) if message.contains("tEXt")
+        ));
+        assert!(Png::from_reader_with_limits(&bytes[..], &critical_only).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_with_options() {
+        let strict = ParseOptions::default();
+        let lenient = ParseOptions::lenient();
+        let (png, warnings) = Png::from_bytes_with(&PNG_FILE, &strict).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert!(warnings.is_empty());
+
+        // IHDR 的 CRC 错误：严格模式报错，宽松模式保留 chunk 并记录警告
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[29] ^= 0xFF;
+        assert!(matches!(
+            Png::from_bytes_with(&bytes, &strict),
+            Err(PngmeError::CrcMismatch { .. })
+        ));
+        let (png, warnings) = Png::from_bytes_with(&bytes, &lenient).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].offset, 8);
+        assert_eq!(warnings[0].chunk_type, "IHDR");
+        let unverified = ParseOptions {
+            verify_crc: false,
+            ..strict
+        };
+        assert!(Png::from_bytes_with(&bytes, &unverified)
+            .unwrap()
+            .1
+            .is_empty());
+    }
+
+    #[test]
+    fn test_from_bytes_with_skips_invalid_types() {
+        // 在 IEND 之前插入一个类型码含数字的 chunk
+        let mut bytes = PNG_FILE[..PNG_FILE.len() - 12].to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, 2, b'r', b'1', b'S', b't', 7, 7, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&PNG_FILE[PNG_FILE.len() - 12..]);
+        assert!(matches!(
+            Png::from_bytes_with(&bytes, &ParseOptions::default()),
+            Err(PngmeError::InvalidChunkType(_))
+        ));
+        let (png, warnings) = Png::from_bytes_with(&bytes, &ParseOptions::lenient()).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].chunk_type, "r1St");
+        assert_eq!(warnings[0].offset, PNG_FILE.len() - 12);
+    }
+
+    #[test]
+    fn test_from_bytes_with_stops_at_broken_chunks() {
+        let truncated = &PNG_FILE[..PNG_FILE.len() - 3];
+        assert!(Png::from_bytes_with(truncated, &ParseOptions::default()).is_err());
+        let (png, warnings) = Png::from_bytes_with(truncated, &ParseOptions::lenient()).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT"]);
+        assert_eq!(warnings.len(), 1);
+
+        // 超出上限的 chunk 被跳过，从下一个完整的 chunk 继续
+        let small = ParseOptions {
+            max_chunk_size: Some(12),
+            ..ParseOptions::lenient()
+        };
+        let (png, warnings) = Png::from_bytes_with(&PNG_FILE, &small).unwrap();
+        assert_eq!(chunk_types(&png), ["IEND"]);
+        assert!(warnings[0].message.contains("skipped"));
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[0] = 0;
+        let (png, warnings) = Png::from_bytes_with(&bytes, &ParseOptions::lenient()).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(warnings[0].chunk_type, "signature");
+    }
+
+    #[test]
+    fn test_from_bytes_with_resyncs_after_corrupted_length() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()).unwrap(),
+        );
+        let clean = png.as_bytes();
+
+        // IHDR 的长度字段被改成了超出文件的值，或者仍在文件内但指向错误的位置
+        for length in [0xFFFF_0000u32, 20] {
+            let mut bytes = clean.clone();
+            bytes[8..12].copy_from_slice(&length.to_be_bytes());
+            assert!(Png::from_bytes_with(&bytes, &ParseOptions::default()).is_err());
+
+            let (png, warnings) = Png::from_bytes_with(&bytes, &ParseOptions::lenient()).unwrap();
+            assert_eq!(chunk_types(&png), ["IDAT", "ruSt", "IEND"]);
+            assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"hidden");
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].offset, 8);
+            assert!(warnings[0].message.contains("skipped 25 bytes"));
+        }
+    }
+
+    #[test]
+    fn test_list_chunks() {
+        let png = testing_png();
+        let chunks = png.chunks();
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_chunk_by_type() {
+        let png = testing_png();
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    }
+
+    #[test]
+    fn test_chunk_by_type_mut() {
+        let mut png = testing_png();
+        png.chunk_by_type_mut("FrSt")
+            .unwrap()
+            .set_data(b"I was edited".to_vec())
+            .unwrap();
+        assert!(png.chunk_by_type_mut("NoNe").is_none());
+
+        let reparsed = Png::try_from(&png.as_bytes()[..]).unwrap();
+        assert_eq!(
+            reparsed.chunk_by_type("FrSt").unwrap().data(),
+            b"I was edited"
+        );
+        for chunk in png.chunks_mut() {
+            chunk.map_data(|_| Vec::new()).unwrap();
+        }
+        assert!(png.chunks().iter().all(|chunk| chunk.length() == 0));
+    }
+
+    #[test]
+    fn test_append_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let chunk = png.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+    }
+
+    #[test]
+    fn test_append_chunk_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("ruSt", "Message").unwrap());
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "ruSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.remove_chunk("TeSt").unwrap();
+        let chunk = png.chunk_by_type("TeSt");
+        assert!(chunk.is_none());
+    }
+
+    #[test]
+    fn test_chunks_by_type_and_indexed_removal() {
+        let mut png = testing_png();
+        for text in ["first", "second", "third"] {
+            png.append_chunk(chunk_from_strings("teXt", text).unwrap());
+        }
+        let texts: Vec<String> = png
+            .chunks_by_type("teXt")
+            .iter()
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect();
+        assert_eq!(texts, ["first", "second", "third"]);
+        assert!(png.chunks_by_type("miSs").is_empty());
+
+        let count = png.chunks().len();
+        let index = png.position_of("teXt", 1).unwrap();
+        let removed = png.remove_chunk_at(index).unwrap();
+        assert_eq!(removed.data_as_string().unwrap(), "second");
+        assert_eq!(png.chunks().len(), count - 1);
+        assert_eq!(png.position_of("teXt", 2), None);
+        assert!(matches!(
+            png.remove_chunk_at(count),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+
+        let removed = png.remove_all_chunks("teXt");
+        assert_eq!(removed.len(), 2);
+        assert!(png.chunk_by_type("teXt").is_none());
+        assert!(png.remove_all_chunks("teXt").is_empty());
+    }
+
+    #[test]
+    fn test_png_from_image_file() {
+        let png = Png::try_from(&PNG_FILE[..]);
+        assert!(png.is_ok());
+    }
+
+    #[test]
+    fn test_png_from_reader() {
+        let png = Png::from_reader(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_png_from_legacy_bytes() {
+        let chunk = chunk_from_strings("ruSt", "legacy message").unwrap();
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend_from_slice(&chunk.length().to_be_bytes());
+        bytes.extend_from_slice(&chunk.chunk_type().bytes());
+        bytes.extend_from_slice(chunk.data());
+        bytes.extend_from_slice(&Chunk::legacy_crc(chunk.data()).to_be_bytes());
+
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+        let png = Png::from_legacy_bytes(&bytes).unwrap();
+        assert_eq!(png.chunks(), &[chunk]);
+    }
+
+    #[test]
+    fn test_chunk_refs() {
+        let png = testing_png();
+        let bytes = png.as_bytes();
+        let refs = Png::chunk_refs(&bytes)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(refs.len(), png.chunks().len());
+        for (chunk_ref, chunk) in refs.into_iter().zip(png.chunks()) {
+            assert_eq!(chunk_ref.data(), chunk.data());
+            assert_eq!(&chunk_ref.to_chunk(), chunk);
+        }
+
+        assert!(matches!(
+            Png::chunk_refs(&bytes[1..]),
+            Err(PngmeError::InvalidSignature)
+        ));
+        let mut refs = Png::chunk_refs(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(refs
+            .by_ref()
+            .take(png.chunks().len() - 1)
+            .all(|r| r.is_ok()));
+        assert!(matches!(refs.next(), Some(Err(PngmeError::TruncatedChunk))));
+        assert!(refs.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_with_offsets() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let offsets: Vec<_> = png
+            .iter_with_offsets()
+            .map(|(offset, chunk)| (offset, chunk.chunk_type().to_string()))
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                (8, "IHDR".to_string()),
+                (33, "IDAT".to_string()),
+                (62, "IEND".to_string())
+            ]
+        );
+        assert_eq!(png.iter_with_offsets().len(), 3);
+
+        // 按偏移原地改写 IHDR 中的宽度并修正 CRC，与重新序列化的结果相同
+        let mut bytes = PNG_FILE.to_vec();
+        let (offset, ihdr) = png.iter_with_offsets().next().unwrap();
+        let data_start = offset as usize + 8;
+        bytes[data_start..data_start + 4].copy_from_slice(&3u32.to_be_bytes());
+        let mut data = ihdr.data().to_vec();
+        data[..4].copy_from_slice(&3u32.to_be_bytes());
+        let crc = Chunk::calculate_crc(ihdr.chunk_type(), &data);
+        let crc_start = data_start + ihdr.length() as usize;
+        bytes[crc_start..crc_start + 4].copy_from_slice(&crc.to_be_bytes());
+        let patched = Png::try_from(&bytes[..]).unwrap();
+        assert_eq!(patched.header().unwrap().width, 3);
+    }
+
+    #[test]
+    fn test_repair_crcs() {
+        let mut bytes = PNG_FILE.to_vec();
+        // 破坏 IDAT 的 CRC
+        bytes[60] ^= 0xff;
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+
+        let mut png = Png::from_bytes_unchecked(&bytes).unwrap();
+        assert_eq!(png.repair_crcs(), [1]);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+        assert!(png.repair_crcs().is_empty());
+    }
+
+    #[test]
+    fn test_verify_all_crcs() {
+        let mut bytes = PNG_FILE.to_vec();
+        // 破坏 IDAT 的 CRC
+        bytes[60] ^= 0xff;
+        let png = Png::from_bytes_unchecked(&bytes).unwrap();
+
+        let report = png.verify_all_crcs();
+        assert_eq!(report.len(), png.chunks().len());
+        assert!(report
+            .iter()
+            .enumerate()
+            .all(|(index, check)| check.index == index));
+        let invalid: Vec<_> = report.iter().filter(|check| !check.is_valid()).collect();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].index, 1);
+        assert_eq!(invalid[0].chunk_type.to_string(), "IDAT");
+        assert_eq!(invalid[0].expected ^ 0xff00, invalid[0].actual);
+    }
+
+    fn testing_apng() -> Png {
+        use crate::apng::tests::frame_control;
+        use crate::apng::FrameData;
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let idat = png.remove_chunk("IDAT").unwrap();
+        let control = AnimationControl {
+            num_frames: 2,
+            num_plays: 0,
+        };
+        let frame_data = FrameData {
+            sequence_number: 2,
+            data: idat.data().to_vec(),
+        };
+        let iend = png.chunks.pop().unwrap();
+        png.chunks.extend([
+            control.to_chunk(),
+            frame_control(0, 10).to_chunk(),
+            idat,
+            frame_control(1, 20).to_chunk(),
+            frame_data.to_chunk().unwrap(),
+            iend,
+        ]);
+        png
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_animation_info() {
+        let png = testing_apng();
+        assert!(png.is_animated());
+        assert_eq!(png.frame_count().unwrap(), 2);
+        assert_eq!(
+            png.frame_delays().unwrap(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+
+        let still = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(!still.is_animated());
+        assert_eq!(still.frame_count().unwrap(), 1);
+        assert!(still.frame_delays().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_append_chunk_in_apng() {
+        let mut png = testing_apng();
+        png.append_chunk(chunk_from_strings("ruSt", "first").unwrap());
+        png.append_chunk(chunk_from_strings("ruSt", "second").unwrap());
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "acTL", "ruSt", "ruSt", "fcTL", "IDAT", "fcTL", "fdAT", "IEND"]
+        );
+        assert_eq!(png.chunks()[3].data_as_string().unwrap(), "second");
+    }
+
+    #[test]
+    fn test_insert_after_frame() {
+        let mut png = testing_apng();
+        png.insert_chunk(
+            chunk_from_strings("ruSt", "0").unwrap(),
+            ChunkPosition::AfterFrame(0),
+        );
+        png.insert_chunk(
+            chunk_from_strings("ruSt", "1").unwrap(),
+            ChunkPosition::AfterFrame(1),
+        );
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "acTL", "fcTL", "IDAT", "ruSt", "fcTL", "fdAT", "ruSt", "IEND"]
+        );
+        assert_eq!(png.frame_range(1), Some(5..8));
+        assert_eq!(png.frame_range(2), None);
+        assert!(png.validate_ordering().is_empty());
+    }
+
+    #[test]
+    fn test_append_chunk_by_type() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("pHYs", "").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "").unwrap());
+        png.insert_chunk(
+            chunk_from_strings("ruSt", "").unwrap(),
+            ChunkPosition::AfterIhdr,
+        );
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "ruSt", "pHYs", "IDAT", "tEXt", "IEND"]
+        );
+        assert!(png.validate_ordering().is_empty());
+    }
+
+    #[test]
+    fn test_validate_ordering() {
+        let png = testing_png();
+        let issues = png.validate_ordering();
+        assert!(!issues.is_empty());
+        assert!(Png::try_from(&PNG_FILE[..])
+            .unwrap()
+            .validate_ordering()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_strip_ancillary() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("gAMA", "").unwrap());
+        png.append_chunk(chunk_from_strings("ruSt", "hidden").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "").unwrap());
+
+        let keep = [ChunkType::from_str("gAMA").unwrap()];
+        let removed = png.strip_ancillary(&keep);
+        let removed: Vec<String> = removed.iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(removed, ["ruSt", "tEXt"]);
+        assert_eq!(chunk_types(&png), ["IHDR", "gAMA", "IDAT", "IEND"]);
+
+        assert_eq!(png.strip_ancillary(&[]).len(), 1);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let mut source = Png::try_from(&PNG_FILE[..]).unwrap();
+        source.append_chunk(chunk_from_strings("pHYs", "source dpi").unwrap());
+        source.append_chunk(chunk_from_strings("gAMA", "").unwrap());
+        source.append_chunk(chunk_from_strings("tEXt", "first").unwrap());
+        source.append_chunk(chunk_from_strings("ruSt", "hidden").unwrap());
+        source.append_chunk(chunk_from_strings("tEXt", "second").unwrap());
+        let mut target = Png::try_from(&PNG_FILE[..]).unwrap();
+        target.append_chunk(chunk_from_strings("pHYs", "target dpi").unwrap());
+
+        // gAMA 不能安全复制
+        let copied = target.merge_from(&source, &MergeFilter::SafeToCopy);
+        assert_eq!(
+            chunk_types(&Png::from_chunks(copied)),
+            ["pHYs", "tEXt", "ruSt", "tEXt"]
+        );
+        assert_eq!(
+            chunk_types(&target),
+            ["IHDR", "pHYs", "IDAT", "tEXt", "ruSt", "tEXt", "IEND"]
+        );
+        assert_eq!(target.chunk_by_type("pHYs").unwrap().data(), b"source dpi");
+        assert!(target
+            .merge_from(&source, &MergeFilter::SafeToCopy)
+            .is_empty());
+
+        let mut target = Png::try_from(&PNG_FILE[..]).unwrap();
+        let types = ["gAMA", "IDAT"].map(|t| ChunkType::from_str(t).unwrap());
+        let copied = target.merge_from(&source, &MergeFilter::Types(types.to_vec()));
+        assert_eq!(chunk_types(&Png::from_chunks(copied)), ["gAMA"]);
+        assert_eq!(chunk_types(&target), ["IHDR", "gAMA", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = SigningKey::from_bytes(&[1; 32]);
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.sign(&key);
+        png.sign(&key);
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "siGN", "IEND"]);
+        png.verify(&key.verifying_key()).unwrap();
+
+        png.append_chunk(chunk_from_strings("ruSt", "hidden").unwrap());
+        png.verify(&key.verifying_key()).unwrap();
+        png.remove_chunk("IDAT").unwrap();
+        assert!(png.verify(&key.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn test_set_text() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_text("Title", "first").unwrap();
+        png.set_text("Author", "Ferris").unwrap();
+        png.set_text("Title", "标题").unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "tEXt", "iTXt", "IEND"]);
+        assert!(png.set_text("", "text").is_err());
+    }
+
+    #[test]
+    fn test_text_metadata() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_text("Title", "标题").unwrap();
+        png.set_text("Author", "Ferris").unwrap();
+        png.append_chunk(chunk_from_strings("tEXt", "broken").unwrap());
+        assert_eq!(png.get_text("Title").as_deref(), Some("标题"));
+        assert_eq!(png.get_text("Comment"), None);
+
+        let metadata = png.text_metadata();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["Author"], "Ferris");
+
+        assert_eq!(
+            chunk_types(&Png::from_chunks(png.remove_text("Title"))),
+            ["iTXt"]
+        );
+        assert!(png.remove_text("Title").is_empty());
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "tEXt", "tEXt", "IEND"]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(
+            png.capacity(CapacityMethod::Chunks).unwrap(),
+            MAX_CHUNK_LENGTH as u64
+        );
+        // 2x2 RGB：12 个样本
+        assert_eq!(png.capacity(CapacityMethod::Lsb).unwrap(), 1);
+
+        let indexed = Ihdr::new(100, 100, 8, ColorType::Indexed).unwrap();
+        let png = Png::from_chunks(vec![indexed.to_chunk()]);
+        assert_eq!(png.capacity(CapacityMethod::Lsb).unwrap(), 0);
+        let gray = Ihdr::new(100, 100, 4, ColorType::Grayscale).unwrap();
+        let png = Png::from_chunks(vec![gray.to_chunk()]);
+        assert_eq!(png.capacity(CapacityMethod::Lsb).unwrap(), 0);
+
+        let rgba = Ihdr::new(100, 100, 16, ColorType::Rgba).unwrap();
+        let png = Png::from_chunks(vec![rgba.to_chunk()]);
+        assert_eq!(png.capacity(CapacityMethod::Lsb).unwrap(), 5000);
+
+        assert!(testing_png().capacity(CapacityMethod::Lsb).is_err());
+    }
+
+    #[test]
+    fn test_image_data() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.image_data(&Limits::default()).unwrap().len(), 2 * 7);
+
+        // 声明 2x2 的图像，IDAT 却解压出 1 MiB
+        let bomb = crate::payload::compress(&vec![0; 1 << 20]).unwrap();
+        let mut chunks = png.chunks().to_vec();
+        let idat = chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().bytes() == *b"IDAT")
+            .unwrap();
+        chunks[idat] =
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), bomb[4..].to_vec()).unwrap();
+        assert!(matches!(
+            Png::from_chunks(chunks).image_data(&Limits::permissive()),
+            Err(PngmeError::DecompressionBomb { limit: 14, .. })
+        ));
+    }
+
+    #[test]
+    fn test_pixels_and_deinterlace() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.pixels(&Limits::default()).unwrap().len(), 2 * 6);
+
+        let mut header = Ihdr::new(5, 3, 8, ColorType::Grayscale).unwrap();
+        header.interlace = Interlace::Adam7;
+        let image: Vec<u8> = (0..15).collect();
+        let mut png = testing_image(header, &image);
+        assert_eq!(png.pixels(&Limits::default()).unwrap(), image);
+
+        assert!(png.deinterlace(&Limits::default()).unwrap());
+        assert_eq!(png.header().unwrap().interlace, Interlace::None);
+        assert_eq!(png.pixels(&Limits::default()).unwrap(), image);
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        assert!(!png.deinterlace(&Limits::default()).unwrap());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let limits = Limits::default();
+        let text = chunk_from_strings("tEXt", "Title\0dice").unwrap();
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.insert_chunk(text.clone(), ChunkPosition::BeforeIend);
+        png.insert_chunk(text.clone(), ChunkPosition::BeforeIend);
+        png.insert_chunk(
+            chunk_from_strings("tEXt", "Title\0other").unwrap(),
+            ChunkPosition::BeforeIend,
+        );
+        png.insert_chunk(
+            chunk_from_strings("ruSt", "same").unwrap(),
+            ChunkPosition::BeforeIend,
+        );
+        png.insert_chunk(
+            chunk_from_strings("ruSt", "same").unwrap(),
+            ChunkPosition::BeforeIend,
+        );
+        let pixels = png.pixels(&limits).unwrap();
+        let idat_length = png.chunk_by_type("IDAT").unwrap().length();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            png.clone()
+                .optimize(&OptimizeOptions::default(), &limits, &cancel),
+            Err(PngmeError::Cancelled)
+        ));
+
+        // 只删除完全相同的已知辅助 chunk，私有 chunk 不动
+        let removed = png
+            .optimize(&OptimizeOptions::default(), &limits, &CancelToken::new())
+            .unwrap();
+        assert_eq!(removed, [text]);
+        assert_eq!(png.chunks_by_type("tEXt").len(), 2);
+        assert_eq!(png.chunks_by_type("ruSt").len(), 2);
+        assert_eq!(png.pixels(&limits).unwrap(), pixels);
+        assert!(png.chunk_by_type("IDAT").unwrap().length() <= idat_length);
+
+        // 16 位灰度图片转换为 8 位，tRNS 和 sBIT 同时转换
+        let header = Ihdr::new(2, 1, 16, ColorType::Grayscale).unwrap();
+        let idat = Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()).unwrap();
+        let iend = Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()).unwrap();
+        let trns = Chunk::new(ChunkType::from_str("tRNS").unwrap(), vec![0xff, 0xff]).unwrap();
+        let sbit = Chunk::new(ChunkType::from_str("sBIT").unwrap(), vec![16]).unwrap();
+        let mut png = Png::from_chunks(vec![header.to_chunk(), sbit, trns, idat, iend]);
+        png.set_image_data(&[0, 0x12, 0x34, 0xff, 0xff]).unwrap();
+        let options = OptimizeOptions {
+            reduce_bit_depth: true,
+            ..OptimizeOptions::default()
+        };
+        png.optimize(&options, &limits, &CancelToken::new())
+            .unwrap();
+        assert_eq!(png.header().unwrap().bit_depth, 8);
+        assert_eq!(png.pixels(&limits).unwrap(), [0x12, 0xff]);
+        assert_eq!(png.chunk_by_type("tRNS").unwrap().data(), [0, 0xff]);
+        assert_eq!(png.chunk_by_type("sBIT").unwrap().data(), [8]);
+    }
+
+    #[test]
+    fn test_palette_editing() {
+        let header = Ihdr::new(1, 1, 8, ColorType::Indexed).unwrap();
+        let idat = Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()).unwrap();
+        let iend = Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()).unwrap();
+        let message = chunk_from_strings("ruSt", "hidden").unwrap();
+        let mut png = Png::from_chunks(vec![header.to_chunk(), idat, message.clone(), iend]);
+        assert_eq!(png.palette().unwrap(), None);
+        // 没有 PLTE 时不能写入调色板格式的 tRNS
+        assert!(png
+            .set_transparency(&Transparency::Indexed(vec![0]))
+            .is_err());
+
+        let mut palette = Palette::new(vec![[0, 0, 0], [255, 255, 255]]).unwrap();
+        png.set_palette(&palette).unwrap();
+        png.set_transparency(&Transparency::Indexed(vec![0, 255]))
+            .unwrap();
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "PLTE", "tRNS", "IDAT", "ruSt", "IEND"]
+        );
+
+        palette.set(1, [255, 0, 0]).unwrap();
+        png.set_palette(&palette).unwrap();
+        let mut transparency = png.transparency().unwrap().unwrap();
+        transparency.set_alpha(1, 128).unwrap();
+        png.set_transparency(&transparency).unwrap();
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "PLTE", "tRNS", "IDAT", "ruSt", "IEND"]
+        );
+        assert_eq!(png.palette().unwrap().unwrap().get(1), Some([255, 0, 0]));
+        assert_eq!(png.transparency().unwrap().unwrap().alpha(1), Some(128));
+        assert_eq!(png.chunk_by_type("ruSt"), Some(&message));
+        assert!(png.validate_ordering().is_empty());
+
+        // tRNS 有两项时调色板不能缩小到一项
+        assert!(png
+            .set_palette(&Palette::new(vec![[0, 0, 0]]).unwrap())
+            .is_err());
+    }
+
+    /// 只有文件头的 ICC 配置
+    pub(crate) fn icc_profile_bytes() -> Vec<u8> {
+        let mut profile = vec![0; 128];
+        profile[..4].copy_from_slice(&128u32.to_be_bytes());
+        profile[36..40].copy_from_slice(b"acsp");
+        profile
+    }
+
+    #[test]
+    fn test_icc_profile() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.icc_profile().unwrap(), None);
+        png.append_chunk(RenderingIntent::Perceptual.to_chunk());
+
+        let profile = icc_profile_bytes();
+        png.set_icc_profile("Display P3", &profile).unwrap();
+        png.set_icc_profile("Display P3", &profile).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "iCCP", "IDAT", "IEND"]);
+        let icc = png.icc_profile().unwrap().unwrap();
+        assert_eq!(icc.name, "Display P3");
+        assert_eq!(icc.profile, profile);
+        assert!(png.validate_ordering().is_empty());
+
+        assert!(matches!(
+            png.set_icc_profile("Display P3", b"not a profile"),
+            Err(PngmeError::InvalidMetadata(_))
+        ));
+        assert!(png.set_icc_profile("", &profile).is_err());
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let limits = Limits::default();
+        let gama =
+            Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 0xb1, 0x8f]).unwrap();
+        let title = chunk_from_strings("tEXt", "Title\0dice").unwrap();
+        let message = chunk_from_strings("ruSt", "hello").unwrap();
+
+        let mut first = Png::try_from(&PNG_FILE[..]).unwrap();
+        first.insert_chunk(gama.clone(), ChunkPosition::AfterIhdr);
+        first.insert_chunk(title.clone(), ChunkPosition::BeforeIend);
+        first.insert_chunk(message.clone(), ChunkPosition::BeforeIend);
+
+        // 同样的图片：不同的压缩和 IDAT 拆分、不同的 chunk 位置，外加易变的时间
+        let mut second = Png::try_from(&PNG_FILE[..]).unwrap();
+        let raw = second.image_data(&limits).unwrap();
+        second
+            .replace_image_data_in(&compress(&raw, Compression::fast()).unwrap(), 5)
+            .unwrap();
+        assert!(second.chunks_by_type("IDAT").len() > 1);
+        second.insert_chunk(message, ChunkPosition::AfterIhdr);
+        second.insert_chunk(title.clone(), ChunkPosition::BeforeIdat);
+        second.insert_chunk(gama, ChunkPosition::BeforeIend);
+        let time = chunk_from_strings("tEXt", "date:modify\0modified").unwrap();
+        second.insert_chunk(time.clone(), ChunkPosition::BeforeIend);
+        second.insert_chunk(title, ChunkPosition::BeforeIend);
+
+        first.canonicalize(&limits, &CancelToken::new()).unwrap();
+        let removed = second.canonicalize(&limits, &CancelToken::new()).unwrap();
+        assert_eq!(chunk_types(&Png::from_chunks(removed)), ["tEXt", "tEXt"]);
+        assert!(!second.chunks_by_type("tEXt").contains(&&time));
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert_eq!(
+            chunk_types(&first),
+            ["IHDR", "gAMA", "IDAT", "ruSt", "tEXt", "IEND"]
+        );
+        assert!(first.validate_ordering().is_empty());
+        assert_eq!(
+            first.pixels(&limits).unwrap(),
+            Png::try_from(&PNG_FILE[..])
+                .unwrap()
+                .pixels(&limits)
+                .unwrap()
+        );
+
+        // 规范形式是幂等的
+        let bytes = first.as_bytes();
+        assert!(first
+            .canonicalize(&limits, &CancelToken::new())
+            .unwrap()
+            .is_empty());
+        assert_eq!(first.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_write_canonical() {
+        let gama =
+            Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 0xb1, 0x8f]).unwrap();
+        let message = chunk_from_strings("ruSt", "hello").unwrap();
+        let canonical = |png: &Png| {
+            let mut bytes = Vec::new();
+            png.write_canonical(&mut bytes).unwrap();
+            bytes
+        };
+
+        // 符合规范的文件原样写出
+        let mut first = Png::try_from(&PNG_FILE[..]).unwrap();
+        first.append_chunk(gama.clone());
+        first.append_chunk(message.clone());
+        assert_eq!(canonical(&first), first.as_bytes());
+
+        // gAMA 错放在 IDAT 之后、CRC 损坏的同一组 chunk 得到相同的字节
+        let mut second = Png::try_from(&PNG_FILE[..]).unwrap();
+        second.insert_chunk(message, ChunkPosition::BeforeIend);
+        second.insert_chunk(gama, ChunkPosition::BeforeIend);
+        let mut bytes = second.as_bytes();
+        let crc = bytes.len() - 12 - 4;
+        bytes[crc] ^= 0xff;
+        let second = Png::from_bytes_unchecked(&bytes).unwrap();
+        assert!(!second.validate_ordering().is_empty());
+        assert_ne!(second.as_bytes(), first.as_bytes());
+        assert_eq!(canonical(&second), first.as_bytes());
+        assert_eq!(second.to_bytes_with(&WriteOptions::default()), bytes);
+
+        let mut third = first.clone();
+        let time = vec![0x07, 0xea, 10, 16, 0, 0, 0];
+        third.append_chunk(Chunk::new(ChunkType::from_str("tIME").unwrap(), time).unwrap());
+        let options = WriteOptions {
+            strip_volatile: true,
+            ..WriteOptions::canonical()
+        };
+        assert_eq!(third.to_bytes_with(&options), first.as_bytes());
+    }
+
+    #[test]
+    fn test_header() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let header = png.header().unwrap();
+        assert_eq!((header.width, header.height), (2, 2));
+        assert_eq!(header.color_type, crate::ihdr::ColorType::Rgb);
+        assert_eq!(png.signature(), &Png::STANDARD_HEADER);
+
+        assert!(matches!(
+            testing_png().header(),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let actual = png.as_bytes();
+        let expected: Vec<u8> = PNG_FILE.to_vec();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_png_trait_impls() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png: Png = TryFrom::try_from(bytes.as_ref()).unwrap();
+
+        let _png_string = format!("{}", png);
+    }
+
+    fn any_chunk() -> impl Strategy<Value = Chunk> {
+        (
+            crate::chunk_type::tests::any_chunk_type(),
+            vec(any::<u8>(), 0..64),
+        )
+            .prop_map(|(chunk_type, data)| Chunk::new(chunk_type, data).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_png_roundtrip(chunks in vec(any_chunk(), 0..8)) {
+            let png = Png::from_chunks(chunks);
+            let bytes = png.as_bytes();
+            prop_assert_eq!(Png::try_from(bytes.as_slice()).unwrap().as_bytes(), bytes.clone());
+            let (lenient, warnings) = Png::from_bytes_with(&bytes, &ParseOptions::lenient()).unwrap();
+            prop_assert!(warnings.is_empty());
+            prop_assert_eq!(lenient.chunks(), png.chunks());
+        }
+
+        #[test]
+        fn prop_offsets_match_bytes(chunks in vec(any_chunk(), 0..8)) {
+            let png = Png::from_chunks(chunks);
+            let bytes = png.as_bytes();
+            let mut end = Png::STANDARD_HEADER.len();
+            for ((offset, chunk_ref), chunk) in png.iter_with_offsets().zip(png.chunks()) {
+                let offset = offset as usize;
+                prop_assert_eq!(offset, end);
+                end = offset + chunk_ref.encoded_len();
+                prop_assert_eq!(bytes[offset..end].to_vec(), chunk.as_bytes());
+            }
+            prop_assert_eq!(end, bytes.len());
+        }
+
+        /// 带有正确签名的任意字节：所有解析入口都只能返回错误，不能 panic
+        #[test]
+        fn prop_parse_arbitrary_bytes(body in vec(any::<u8>(), 0..256)) {
+            let mut bytes = Png::STANDARD_HEADER.to_vec();
+            bytes.extend_from_slice(&body);
+            let _ = Png::try_from(bytes.as_slice());
+            let _ = Png::from_bytes_unchecked(&bytes);
+            let _ = Png::from_reader(bytes.as_slice());
+            let _ = Png::from_bytes_with(&bytes, &ParseOptions::lenient());
+            let _ = crate::scan::scan_bytes(&bytes);
+        }
+    }
+
+    // 一张 2x2 的 RGB 图片：IHDR、IDAT、IEND
+    pub(crate) const PNG_FILE: [u8; 74] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 2, 8, 2,
+        0, 0, 0, 253, 212, 154, 115, 0, 0, 0, 17, 73, 68, 65, 84, 120, 156, 99, 248, 207, 192, 0,
+        68, 12, 96, 242, 63, 0, 27, 242, 3, 253, 243, 231, 75, 177, 0, 0, 0, 0, 73, 69, 78, 68,
+        174, 66, 96, 130,
+    ];
+}
@@ -0,0 +1,61 @@
+//! # Prelude
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块重新导出库的常用类型，`use pngme::prelude::*;` 即可使用：
+//!
+//! ```text
+//! use pngme::prelude::*;
+//!
+//! let mut png = Png::try_from(bytes.as_slice())?;
+//! png.append_chunk(Chunk::new(ChunkType::from_str("ruSt")?, b"hello".to_vec())?);
+//! ```
+//!
+//! 这里的名字受语义化版本保护：只会增加，删除或改变含义需要升级主版本号。
+//! 不在 prelude 中的模块仍可能在次版本中调整。
+
+pub use crate::chunk::Chunk;
+pub use crate::chunk_type::{ChunkType, KnownChunk};
+pub use crate::editor::PngEditor;
+pub use crate::engine::{Engine, EngineBuilder, Request, Response};
+pub use crate::error::PngmeError;
+pub use crate::limits::Limits;
+pub use crate::ordering::ChunkPosition;
+pub use crate::payload::Payload;
+pub use crate::png::Png;
+pub use crate::progress::{NoProgress, Phase, Progress};
+pub use crate::Result;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use std::sync::Arc;
+
+    /// prelude 中的名字必须一直可用，删除任何一个都会让这个测试编译失败
+    #[test]
+    fn test_prelude_names() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hello".to_vec()).unwrap();
+        png.insert_chunk(chunk, ChunkPosition::BeforeIend);
+        let editor: PngEditor = png.edit();
+        assert!(editor.commit().unwrap().is_empty());
+        assert_eq!(ChunkType::IHDR.known(), Some(KnownChunk::Ihdr));
+
+        let builder: EngineBuilder = Engine::builder().progress(Arc::new(NoProgress));
+        let engine = builder.limits(Limits::default()).build();
+        let response = engine.process(Request::Decode {
+            png: png.as_bytes(),
+            chunk_type: "ruSt".to_string(),
+            passphrase: None,
+        });
+        assert_eq!(
+            response.unwrap(),
+            Response::Payload(Payload::new(b"hello".to_vec()))
+        );
+        let error: Result<()> = Err(PngmeError::Cancelled);
+        assert!(error.is_err());
+        assert_eq!(Phase::Parsing.name(), "parsing");
+        let _: Option<&dyn Progress> = None;
+    }
+}
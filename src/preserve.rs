@@ -0,0 +1,130 @@
+//! # Preserve
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块检查编辑操作是否原样保留了 pngme 不认识的 chunk（例如厂商私有 chunk）。
+//!
+//! 所有非破坏性的操作（`encode`、`remove`、`sign`、`stamp`、`metadata`）只插入或删除
+//! 自己负责的 chunk，其余 chunk 逐字节保留，相对顺序以及位于 `IDAT` 之前还是之后都不变。
+//! `--strict-preserve` 会在写入前用 `check` 确认这一点，不满足时拒绝写入。
+//! `strip` 和 `repair` 本身就会删除或改写 chunk，不在此保证之内。
+
+use crate::chunk::Chunk;
+use crate::{PngmeError, Result};
+
+/// 除规范中的类型（`KnownChunk`）以外，pngme 会主动改写的私有 chunk 类型
+const PNGME_CHUNKS: [&str; 2] = ["siGN", "pmPv"];
+
+/// chunk 相对于图像数据的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    BeforeIdat,
+    AfterIdat,
+}
+
+/// chunk 类型是否是 pngme 不认识的类型
+pub fn is_unknown(chunk: &Chunk) -> bool {
+    chunk.chunk_type().known().is_none()
+        && !PNGME_CHUNKS.contains(&chunk.chunk_type().to_string().as_str())
+}
+
+/// 确认 `before` 中每个不认识的 chunk 都原样出现在 `after` 中，顺序和相对 `IDAT` 的位置不变。
+/// 允许新增 chunk；`removed` 中的类型是操作有意删除的，不做检查
+pub fn check(before: &[Chunk], after: &[Chunk], removed: &[&str]) -> Result<()> {
+    let mut candidates = unknown_chunks(after).into_iter();
+    for (chunk, side) in unknown_chunks(before) {
+        let chunk_type = chunk.chunk_type().to_string();
+        if removed.contains(&chunk_type.as_str()) {
+            continue;
+        }
+        // `after` 中的不认识的 chunk 只能比 `before` 多，按顺序逐个匹配
+        let found = candidates.any(|(candidate, candidate_side)| {
+            candidate_side == side && candidate.as_bytes() == chunk.as_bytes()
+        });
+        if !found {
+            return Err(PngmeError::NotPreserved(format!(
+                "{} chunk ({} bytes) was lost, modified or moved across IDAT",
+                chunk_type,
+                chunk.length()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 所有不认识的 chunk 以及它们相对于 `IDAT` 的位置
+fn unknown_chunks(chunks: &[Chunk]) -> Vec<(&Chunk, Side)> {
+    let mut side = Side::BeforeIdat;
+    let mut unknown = Vec::new();
+    for chunk in chunks {
+        if chunk.chunk_type().bytes() == *b"IDAT" {
+            side = Side::AfterIdat;
+        } else if is_unknown(chunk) {
+            unknown.push((chunk, side));
+        }
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::testing_chunk;
+
+    fn testing_chunks() -> Vec<Chunk> {
+        vec![
+            testing_chunk("IHDR", &[0; 13]),
+            testing_chunk("vpAg", b"vendor"),
+            testing_chunk("IDAT", b"pixels"),
+            testing_chunk("prVt", b"private"),
+            testing_chunk("IEND", b""),
+        ]
+    }
+
+    #[test]
+    fn test_is_unknown() {
+        assert!(is_unknown(&testing_chunk("vpAg", b"")));
+        assert!(!is_unknown(&testing_chunk("tEXt", b"")));
+        assert!(!is_unknown(&testing_chunk("siGN", b"")));
+    }
+
+    #[test]
+    fn test_additions_are_allowed() {
+        let before = testing_chunks();
+        let mut after = before.clone();
+        after.insert(1, testing_chunk("tEXt", b"Title\0pngme"));
+        after.insert(4, testing_chunk("ruSt", b"hidden"));
+        check(&before, &after, &[]).unwrap();
+    }
+
+    #[test]
+    fn test_lost_or_modified_chunk() {
+        let before = testing_chunks();
+        let mut after = before.clone();
+        after.remove(1);
+        assert!(matches!(
+            check(&before, &after, &[]),
+            Err(PngmeError::NotPreserved(_))
+        ));
+        check(&before, &after, &["vpAg"]).unwrap();
+
+        let mut after = before.clone();
+        after[3] = testing_chunk("prVt", b"changed");
+        assert!(check(&before, &after, &[]).is_err());
+    }
+
+    #[test]
+    fn test_moved_chunk() {
+        let before = testing_chunks();
+        // 移到 IDAT 之后
+        let mut after = before.clone();
+        let vendor = after.remove(1);
+        after.insert(2, vendor);
+        assert!(check(&before, &after, &[]).is_err());
+
+        // 交换两个不认识的 chunk 的顺序
+        let mut after = before.clone();
+        after.swap(1, 3);
+        assert!(check(&before, &after, &[]).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! # Preview
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块把来自文件的文本（文本 chunk、压缩包中的文件名等）转换为适合在终端显示的预览：
+//!
+//! - 控制字符和 Unicode 双向控制字符转义为 `\n`、`\x1B`、`\u{202E}` 等形式，
+//!   恶意文件无法通过 ANSI 转义序列清屏、伪造输出或改变文字方向；
+//! - 超过指定宽度时在字素簇（用户看到的一个字符）边界截断并加上 `…`，
+//!   不会把组合字符、emoji 序列或多字节字符切成两半。
+//!
+//! 宽度按转义后的字素簇个数计算，不考虑全角字符占两列。
+//!
+//! 二进制数据用 `hexdump` 显示为偏移、十六进制和 ASCII 三栏，不可打印的字节显示为 `.`。
+//!
+//! 输出到终端的字符串只要有一部分来自文件（关键字、文本、压缩包和信息中的文件名、
+//! 登记表中的工具名，以及包含这些内容的错误信息），都应该先经过 `escape` 或 `escape_lines`。
+
+use std::fmt::Write;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// 截断时追加的省略号
+pub const ELLIPSIS: char = '…';
+
+/// `print` 等列表中文本预览的默认宽度
+pub const DEFAULT_WIDTH: usize = 60;
+
+/// 转义控制字符，其他字符保持不变
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        push_escaped(&mut escaped, c);
+    }
+    escaped
+}
+
+/// 转义控制字符但保留换行，用于输出多行文本（例如解码出的信息）
+pub fn escape_lines(s: &str) -> String {
+    s.split('\n').map(escape).collect::<Vec<_>>().join("\n")
+}
+
+/// 在字素簇边界截断到最多 `width` 个字素簇（包括省略号），不转义
+pub fn truncate(s: &str, width: usize) -> String {
+    let graphemes: Vec<&str> = s.graphemes(true).collect();
+    if graphemes.len() <= width {
+        return s.to_string();
+    }
+    let mut truncated: String = graphemes[..width.saturating_sub(1)].concat();
+    if width > 0 {
+        truncated.push(ELLIPSIS);
+    }
+    truncated
+}
+
+/// 转义并截断：转义后的每个字符都计入宽度，截断不会切开转义序列
+pub fn preview(s: &str, width: usize) -> String {
+    let rendered: Vec<(String, usize)> = s.graphemes(true).map(render).collect();
+    let total: usize = rendered.iter().map(|(_, columns)| columns).sum();
+    if total <= width {
+        return rendered.into_iter().map(|(grapheme, _)| grapheme).collect();
+    }
+    let mut preview = String::new();
+    let mut used = 0;
+    for (grapheme, columns) in &rendered {
+        // 给省略号留出一列
+        if used + columns + 1 > width {
+            break;
+        }
+        used += columns;
+        preview.push_str(grapheme);
+    }
+    if width > 0 {
+        preview.push(ELLIPSIS);
+    }
+    preview
+}
+
+/// 十六进制视图每行的字节数
+pub const HEXDUMP_WIDTH: usize = 16;
+
+/// 以 `偏移  十六进制  |ASCII|` 的格式显示数据，每行 16 字节，中间以空格分为两组。
+/// 最多显示 `max_bytes` 字节（`None` 表示全部），其余的字节数写在最后一行
+pub fn hexdump(data: &[u8], max_bytes: Option<usize>) -> String {
+    let shown = max_bytes.map_or(data.len(), |max| max.min(data.len()));
+    let mut out = String::new();
+    for (row, line) in data[..shown].chunks(HEXDUMP_WIDTH).enumerate() {
+        let _ = write!(out, "{:08x} ", row * HEXDUMP_WIDTH);
+        for column in 0..HEXDUMP_WIDTH {
+            if column % 8 == 0 {
+                out.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => {
+                    let _ = write!(out, "{:02x} ", byte);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    if shown < data.len() {
+        let _ = writeln!(out, "... {} more bytes", data.len() - shown);
+    }
+    out
+}
+
+/// 转义一个字素簇并计算宽度：没有转义时算一列，否则转义后的每个字符算一列
+fn render(grapheme: &str) -> (String, usize) {
+    let escaped = escape(grapheme);
+    let columns = if escaped == grapheme {
+        1
+    } else {
+        escaped.chars().count()
+    };
+    (escaped, columns)
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        '\\' => out.push_str("\\\\"),
+        c if c.is_ascii_control() => {
+            let _ = write!(out, "\\x{:02X}", c as u32);
+        }
+        c if is_unsafe(c) => {
+            let _ = write!(out, "\\u{{{:04X}}}", c as u32);
+        }
+        c => out.push(c),
+    }
+}
+
+/// C1 控制字符、双向控制字符和零宽空格：它们不可见，却能改变终端或文字的显示。
+/// emoji 序列使用的零宽连接符不在其中
+fn is_unsafe(c: char) -> bool {
+    matches!(
+        c,
+        '\u{80}'..='\u{9f}'
+            | '\u{200b}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain Café 你好"), "plain Café 你好");
+        assert_eq!(escape("a\nb\tc\\"), "a\\nb\\tc\\\\");
+        assert_eq!(escape("\x1b[2J\x07"), "\\x1B[2J\\x07");
+        assert_eq!(escape("abc\u{202e}txt.exe"), "abc\\u{202E}txt.exe");
+        assert_eq!(escape("\u{85}"), "\\u{0085}");
+    }
+
+    #[test]
+    fn test_escape_lines() {
+        assert_eq!(escape_lines("line 1\nline 2\n"), "line 1\nline 2\n");
+        assert_eq!(
+            escape_lines("\x1b]0;title\x07\r\nok"),
+            "\\x1B]0;title\\x07\\r\nok"
+        );
+    }
+
+    #[test]
+    fn test_truncate_on_grapheme_boundaries() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("hello world", 6), "hello…");
+        // é 由 e 和组合重音符组成，不能只保留 e
+        assert_eq!(truncate("cafe\u{301}s!", 5), "cafe\u{301}…");
+        // 家庭 emoji 是由零宽连接符组成的一个字素簇
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(
+            truncate(&format!("{}{}x", family, family), 2),
+            format!("{}…", family)
+        );
+        assert_eq!(truncate("你好世界", 3), "你好…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let data: Vec<u8> = (0x40..0x52).chain([0, b' ', 0x7f]).collect();
+        assert_eq!(
+            hexdump(&data, None),
+            "00000000  40 41 42 43 44 45 46 47  48 49 4a 4b 4c 4d 4e 4f  |@ABCDEFGHIJKLMNO|\n\
+             00000010  50 51 00 20 7f                                    |PQ. .|\n"
+        );
+        assert_eq!(
+            hexdump(&data, Some(4)),
+            "00000000  40 41 42 43                                       |@ABC|\n\
+             ... 17 more bytes\n"
+        );
+        assert_eq!(hexdump(&[], None), "");
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("line 1\nline 2", 20), "line 1\\nline 2");
+        assert_eq!(preview("abcdef", 4), "abc…");
+        // 转义序列要么完整保留，要么整个省略
+        assert_eq!(preview("ab\x1b[31m", 5), "ab…");
+        assert_eq!(preview("ab\x1b[31m", 9), "ab\\x1B[3…");
+        assert_eq!(preview("ab\x1b[31m", 10), "ab\\x1B[31m");
+        assert!(!preview("\u{1b}]0;evil\u{7}", 60).contains('\u{1b}'));
+    }
+}
@@ -0,0 +1,226 @@
+//! # Profile
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块统计一批 PNG 中 chunk 类型、长度和顺序的分布，生成语料画像（profile）。
+//!
+//! 画像以 JSON 保存，可以交给 `encode --profile`：写入的 chunk 会放在语料中
+//! 可以任意放置的辅助 chunk 最常出现的位置（IDAT 之前或 IEND 之前），让嵌入信息的文件更接近典型文件。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::Chunk;
+use crate::ordering::{self, ChunkPosition};
+use crate::png::Png;
+
+/// 画像中保留的最常见 chunk 顺序的数量
+pub const MAX_ORDERINGS: usize = 20;
+
+/// 语料画像
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// 统计的文件数
+    pub files: usize,
+    /// 无法解析而跳过的文件数
+    pub skipped: usize,
+    /// 每种 chunk 类型的统计
+    pub chunk_types: BTreeMap<String, ChunkTypeStats>,
+    /// 最常见的 chunk 顺序，按出现的文件数从多到少排列，最多 `MAX_ORDERINGS` 个
+    pub orderings: Vec<OrderingStats>,
+}
+
+/// 一种 chunk 类型的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkTypeStats {
+    /// 包含该类型的文件数
+    pub files: usize,
+    /// 出现的总次数
+    pub count: usize,
+    pub min_length: u32,
+    pub max_length: u32,
+    pub total_length: u64,
+    /// 出现在第一个 IDAT 之前的次数
+    pub before_idat: usize,
+    /// 出现在最后一个 IDAT 之后的次数
+    pub after_idat: usize,
+}
+
+/// 一种 chunk 顺序：类型序列，连续的同类型 chunk 合并为一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderingStats {
+    pub sequence: Vec<String>,
+    /// 以该顺序排列的文件数
+    pub files: usize,
+}
+
+/// 生成画像时的累加器，顺序要统计完所有文件后才能选出最常见的
+#[derive(Debug, Default)]
+pub struct ProfileBuilder {
+    profile: Profile,
+    orderings: HashMap<Vec<String>, usize>,
+}
+
+impl ChunkTypeStats {
+    /// 平均数据长度
+    pub fn mean_length(&self) -> u64 {
+        self.total_length
+            .checked_div(self.count as u64)
+            .unwrap_or(0)
+    }
+}
+
+impl ProfileBuilder {
+    pub fn new() -> Self {
+        ProfileBuilder::default()
+    }
+
+    /// 统计一个文件
+    pub fn add(&mut self, png: &Png) {
+        let chunks = png.chunks();
+        let is_idat = |chunk: &Chunk| chunk.chunk_type().bytes() == *b"IDAT";
+        let first_idat = chunks.iter().position(is_idat);
+        let last_idat = chunks.iter().rposition(is_idat);
+
+        let mut seen = Vec::new();
+        let mut sequence: Vec<String> = Vec::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let chunk_type = chunk.chunk_type().to_string();
+            let stats = self
+                .profile
+                .chunk_types
+                .entry(chunk_type.clone())
+                .or_default();
+            if stats.count == 0 {
+                stats.min_length = chunk.length();
+            }
+            stats.count += 1;
+            stats.min_length = stats.min_length.min(chunk.length());
+            stats.max_length = stats.max_length.max(chunk.length());
+            stats.total_length += chunk.length() as u64;
+            if first_idat.is_some_and(|first| index < first) {
+                stats.before_idat += 1;
+            }
+            if last_idat.is_some_and(|last| index > last) {
+                stats.after_idat += 1;
+            }
+            if !seen.contains(&chunk_type) {
+                stats.files += 1;
+                seen.push(chunk_type.clone());
+            }
+            if sequence.last() != Some(&chunk_type) {
+                sequence.push(chunk_type);
+            }
+        }
+        *self.orderings.entry(sequence).or_default() += 1;
+        self.profile.files += 1;
+    }
+
+    /// 记录一个无法解析的文件
+    pub fn skip(&mut self) {
+        self.profile.skipped += 1;
+    }
+
+    /// 结束统计，选出最常见的顺序；文件数相同的顺序按序列排序，保证结果确定
+    pub fn finish(self) -> Profile {
+        let mut orderings: Vec<OrderingStats> = self
+            .orderings
+            .into_iter()
+            .map(|(sequence, files)| OrderingStats { sequence, files })
+            .collect();
+        orderings.sort_by(|a, b| {
+            b.files
+                .cmp(&a.files)
+                .then_with(|| a.sequence.cmp(&b.sequence))
+        });
+        orderings.truncate(MAX_ORDERINGS);
+        Profile {
+            orderings,
+            ..self.profile
+        }
+    }
+}
+
+impl Profile {
+    /// 新写入的辅助 chunk 的位置：语料中可以任意放置的 chunk（不受顺序约束的辅助 chunk，
+    /// 见 `ordering::canonical_rank`）更多出现在 IDAT 之前时为 `BeforeIdat`，否则为 `BeforeIend`
+    pub fn placement(&self) -> ChunkPosition {
+        let (before, after) = self
+            .chunk_types
+            .iter()
+            .filter(|(chunk_type, _)| {
+                ordering::canonical_rank(chunk_type) == 6
+                    && chunk_type
+                        .as_bytes()
+                        .first()
+                        .is_some_and(u8::is_ascii_lowercase)
+            })
+            .fold((0, 0), |(before, after), (_, stats)| {
+                (before + stats.before_idat, after + stats.after_idat)
+            });
+        if before > after {
+            ChunkPosition::BeforeIdat
+        } else {
+            ChunkPosition::BeforeIend
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::png::tests::PNG_FILE;
+
+    fn png_with_text(position: ChunkPosition) -> Png {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let text = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Software\0pngme".to_vec(),
+        )
+        .unwrap();
+        png.insert_chunk(text, position);
+        png
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut builder = ProfileBuilder::new();
+        builder.add(&png_with_text(ChunkPosition::BeforeIdat));
+        builder.add(&png_with_text(ChunkPosition::BeforeIdat));
+        builder.add(&png_with_text(ChunkPosition::BeforeIend));
+        builder.skip();
+        let profile = builder.finish();
+
+        assert_eq!(profile.files, 3);
+        assert_eq!(profile.skipped, 1);
+        let text = &profile.chunk_types["tEXt"];
+        assert_eq!((text.files, text.count), (3, 3));
+        assert_eq!((text.before_idat, text.after_idat), (2, 1));
+        assert_eq!(
+            (text.min_length, text.max_length, text.mean_length()),
+            (14, 14, 14)
+        );
+        assert_eq!(profile.chunk_types["IDAT"].files, 3);
+
+        assert_eq!(profile.orderings.len(), 2);
+        assert_eq!(profile.orderings[0].files, 2);
+        assert_eq!(
+            profile.orderings[0].sequence,
+            ["IHDR", "tEXt", "IDAT", "IEND"]
+        );
+        assert_eq!(profile.placement(), ChunkPosition::BeforeIdat);
+
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(serde_json::from_str::<Profile>(&json).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_placement_defaults_to_before_iend() {
+        assert_eq!(Profile::default().placement(), ChunkPosition::BeforeIend);
+        let mut builder = ProfileBuilder::new();
+        builder.add(&png_with_text(ChunkPosition::BeforeIend));
+        assert_eq!(builder.finish().placement(), ChunkPosition::BeforeIend);
+    }
+}
@@ -0,0 +1,472 @@
+//! # Progress
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块定义了进度回调 `Progress`。库在读取数据、解析 chunk 和完成批处理中的文件时调用它，
+//! 宿主程序据此显示进度；命令行在 stderr 是终端时用 `TerminalProgress` 显示 indicatif 进度条。
+//!
+//! 进度通过文件系统传递：`ProgressFs` 包装另一个 `Vfs`，统计打开的文件被读取的字节数，
+//! 并通过 `Vfs::progress` 把回调交给 `ChunkReader` 和批处理；不经过文件系统的 `Engine`
+//! 通过 `EngineBuilder::progress` 接收回调。耗时的步骤（密钥派生和加解密、压缩、签名等）开始时
+//! 调用 `Progress::phase`，图形界面可以用 `Throughput` 根据已处理的字节数估算速度和剩余时间。
+
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "cli")]
+use std::sync::atomic::{AtomicBool, AtomicU8};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "cli")]
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+
+use crate::chunk_type::ChunkType;
+use crate::vfs::{FileAttributes, Preserve, Vfs};
+
+/// 耗时操作当前所处的步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// 解析 PNG
+    Parsing = 1,
+    /// 压缩信息或图像数据
+    Compressing,
+    /// 解压信息
+    Decompressing,
+    /// 从口令派生密钥并加密
+    Encrypting,
+    /// 从口令派生密钥并解密
+    Decrypting,
+    /// 签名
+    Signing,
+    /// 校验签名
+    Verifying,
+    /// 序列化修改后的文件
+    Writing,
+}
+
+/// 进度回调，所有方法默认什么也不做；可能从多个线程同时调用
+pub trait Progress: Send + Sync {
+    /// 预计要处理 `total` 字节，用于估算剩余时间；不知道大小时不会调用
+    fn total_bytes(&self, _total: u64) {}
+
+    /// 又读取了 `bytes` 字节
+    fn bytes(&self, _bytes: u64) {}
+
+    /// 开始一个耗时的步骤
+    fn phase(&self, _phase: Phase) {}
+
+    /// 解析了一个 chunk，`length` 是数据长度
+    fn chunk(&self, _chunk_type: &ChunkType, _length: u32) {}
+
+    /// 批处理开始，共有 `total` 个文件
+    fn files(&self, _total: u64) {}
+
+    /// 批处理中的一个文件处理完毕，`ok` 表示是否成功
+    fn file_done(&self, _path: &Path, _ok: bool) {}
+
+    /// `Engine::process_all` 开始，共有 `total` 个请求
+    fn requests(&self, _total: u64) {}
+
+    /// `Engine::process_all` 中第 `index` 个请求处理完毕
+    fn request_done(&self, _index: usize, _ok: bool) {}
+
+    /// 在输出文字时调用 `f`，进度条可以借此暂时隐藏，避免与输出交错
+    fn suspend(&self, f: &mut dyn FnMut()) {
+        f()
+    }
+}
+
+impl fmt::Debug for dyn Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("dyn Progress")
+    }
+}
+
+impl Phase {
+    /// 小写的步骤名，用于显示
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Parsing => "parsing",
+            Phase::Compressing => "compressing",
+            Phase::Decompressing => "decompressing",
+            Phase::Encrypting => "encrypting",
+            Phase::Decrypting => "decrypting",
+            Phase::Signing => "signing",
+            Phase::Verifying => "verifying",
+            Phase::Writing => "writing",
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    fn from_u8(value: u8) -> Option<Phase> {
+        [
+            Phase::Parsing,
+            Phase::Compressing,
+            Phase::Decompressing,
+            Phase::Encrypting,
+            Phase::Decrypting,
+            Phase::Signing,
+            Phase::Verifying,
+            Phase::Writing,
+        ]
+        .into_iter()
+        .find(|phase| *phase as u8 == value)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 根据已处理的字节数估算速度和剩余时间，供实现 `Progress` 的宿主程序使用：
+/// 在 `total_bytes` 中调用 `set_total`，在 `bytes` 中调用 `add`
+#[derive(Debug)]
+pub struct Throughput {
+    start: Instant,
+    total: AtomicU64,
+    processed: AtomicU64,
+}
+
+impl Throughput {
+    /// 从现在开始计时
+    pub fn new() -> Self {
+        Throughput {
+            start: Instant::now(),
+            total: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+        }
+    }
+
+    /// 累加预计要处理的字节数；批处理时每个文件调用一次
+    pub fn set_total(&self, total: u64) {
+        self.total.fetch_add(total, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, bytes: u64) {
+        self.processed.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// 已处理的字节数
+    pub fn processed(&self) -> u64 {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// 预计要处理的字节数，不知道时为 0
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// 开始以来的平均速度（字节每秒）
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes_per_second_at(self.start.elapsed())
+    }
+
+    /// 估算的剩余时间；不知道总量或还没有处理任何字节时为 `None`
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_at(self.start.elapsed())
+    }
+
+    /// 已经过 `elapsed` 时的平均速度，便于使用自己的时钟
+    pub fn bytes_per_second_at(&self, elapsed: Duration) -> f64 {
+        match elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => self.processed() as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    /// 已经过 `elapsed` 时估算的剩余时间
+    pub fn eta_at(&self, elapsed: Duration) -> Option<Duration> {
+        let rate = self.bytes_per_second_at(elapsed);
+        if self.total() == 0 || rate <= 0.0 {
+            return None;
+        }
+        let remaining = self.total().saturating_sub(self.processed());
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 不报告进度
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
+
+/// 读取时把字节数报告给 `Progress` 的读取器
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Arc<dyn Progress>,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R, progress: Arc<dyn Progress>) -> Self {
+        ProgressReader { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.bytes(read as u64);
+        Ok(read)
+    }
+}
+
+/// 包装另一个文件系统，向 `progress` 报告读取的字节数，并通过 `Vfs::progress` 提供回调
+pub struct ProgressFs<'a> {
+    inner: &'a dyn Vfs,
+    progress: Arc<dyn Progress>,
+}
+
+impl<'a> ProgressFs<'a> {
+    pub fn new(inner: &'a dyn Vfs, progress: Arc<dyn Progress>) -> Self {
+        ProgressFs { inner, progress }
+    }
+}
+
+impl Vfs for ProgressFs<'_> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let reader = self.inner.open(path)?;
+        Ok(Box::new(ProgressReader::new(reader, self.progress.clone())))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.write(path, data)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path)
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.list(dir)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(dir)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove(path)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.inner.is_symlink(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        self.inner.canonicalize(path)
+    }
+
+    fn hard_link_id(&self, path: &Path) -> Option<(u64, u64)> {
+        self.inner.hard_link_id(path)
+    }
+
+    fn write_in_place(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.write_in_place(path, data)
+    }
+
+    fn attributes(&self, path: &Path) -> io::Result<Option<FileAttributes>> {
+        self.inner.attributes(path)
+    }
+
+    fn set_attributes(
+        &self,
+        path: &Path,
+        attributes: &FileAttributes,
+        preserve: Preserve,
+    ) -> io::Result<()> {
+        self.inner.set_attributes(path, attributes, preserve)
+    }
+
+    fn progress(&self) -> Arc<dyn Progress> {
+        self.progress.clone()
+    }
+}
+
+/// 在终端上显示的进度条：处理单个文件时显示已读取的字节数和 chunk 数，读到 IEND 后清除，
+/// 以免与命令随后的输出交错；批处理时显示已完成的文件数，直到被丢弃
+#[cfg(feature = "cli")]
+pub struct TerminalProgress {
+    bar: ProgressBar,
+    bytes: AtomicU64,
+    chunks: AtomicU64,
+    batch: AtomicBool,
+    /// 当前步骤（`Phase as u8`），0 表示没有
+    phase: AtomicU8,
+}
+
+#[cfg(feature = "cli")]
+impl TerminalProgress {
+    /// 创建绘制到 stderr 的进度条
+    pub fn new() -> Self {
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(ProgressStyle::with_template("{spinner} {msg}").unwrap());
+        TerminalProgress {
+            bar,
+            bytes: AtomicU64::new(0),
+            chunks: AtomicU64::new(0),
+            batch: AtomicBool::new(false),
+            phase: AtomicU8::new(0),
+        }
+    }
+
+    fn update(&self) {
+        if self.bar.is_finished() {
+            return;
+        }
+        let mut message = format!(
+            "{} read, {} chunks",
+            HumanBytes(self.bytes.load(Ordering::Relaxed)),
+            self.chunks.load(Ordering::Relaxed)
+        );
+        if let Some(phase) = Phase::from_u8(self.phase.load(Ordering::Relaxed)) {
+            message = format!("{}, {}", message, phase);
+        }
+        self.bar.set_message(message);
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Default for TerminalProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Progress for TerminalProgress {
+    fn bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.update();
+    }
+
+    fn phase(&self, phase: Phase) {
+        self.phase.store(phase as u8, Ordering::Relaxed);
+        self.update();
+    }
+
+    fn chunk(&self, chunk_type: &ChunkType, _length: u32) {
+        self.chunks.fetch_add(1, Ordering::Relaxed);
+        self.update();
+        if chunk_type.bytes() == *b"IEND" && !self.batch.load(Ordering::Relaxed) {
+            self.bar.finish_and_clear();
+        }
+    }
+
+    fn files(&self, total: u64) {
+        self.batch.store(true, Ordering::Relaxed);
+        self.bar.set_length(total);
+        self.bar.set_style(
+            ProgressStyle::with_template("{spinner} [{bar:30}] {pos}/{len} files, {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+    }
+
+    fn file_done(&self, _path: &Path, _ok: bool) {
+        self.bar.inc(1);
+    }
+
+    fn suspend(&self, f: &mut dyn FnMut()) {
+        self.bar.suspend(f)
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Drop for TerminalProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    use std::sync::Mutex;
+
+    /// 记录收到的回调
+    #[derive(Default)]
+    pub(crate) struct Recorder {
+        pub bytes: AtomicU64,
+        pub chunks: Mutex<Vec<String>>,
+        pub files: Mutex<Vec<(PathBuf, bool)>>,
+        pub total: AtomicU64,
+        pub phases: Mutex<Vec<Phase>>,
+        pub requests: Mutex<Vec<(usize, bool)>>,
+    }
+
+    impl Progress for Recorder {
+        fn phase(&self, phase: Phase) {
+            self.phases.lock().unwrap().push(phase);
+        }
+
+        fn requests(&self, total: u64) {
+            self.total.store(total, Ordering::Relaxed);
+        }
+
+        fn request_done(&self, index: usize, ok: bool) {
+            self.requests.lock().unwrap().push((index, ok));
+        }
+
+        fn bytes(&self, bytes: u64) {
+            self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+
+        fn chunk(&self, chunk_type: &ChunkType, _length: u32) {
+            self.chunks.lock().unwrap().push(chunk_type.to_string());
+        }
+
+        fn files(&self, total: u64) {
+            self.total.store(total, Ordering::Relaxed);
+        }
+
+        fn file_done(&self, path: &Path, ok: bool) {
+            self.files.lock().unwrap().push((path.to_path_buf(), ok));
+        }
+    }
+
+    #[test]
+    fn test_progress_fs_counts_bytes() {
+        let memory = MemoryFs::new();
+        memory.insert("a.png", vec![0; 100]);
+        let recorder = Arc::new(Recorder::default());
+        let fs = ProgressFs::new(&memory, recorder.clone());
+
+        assert_eq!(fs.read(Path::new("a.png")).unwrap().len(), 100);
+        assert_eq!(recorder.bytes.load(Ordering::Relaxed), 100);
+        // 写入不计入读取的字节数
+        fs.write(Path::new("b.png"), &[1, 2, 3]).unwrap();
+        assert_eq!(recorder.bytes.load(Ordering::Relaxed), 100);
+        assert_eq!(memory.get(Path::new("b.png")).unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_throughput() {
+        let throughput = Throughput::new();
+        assert_eq!(throughput.eta_at(Duration::from_secs(1)), None);
+        throughput.set_total(1000);
+        throughput.add(250);
+        assert_eq!(throughput.bytes_per_second_at(Duration::from_secs(5)), 50.0);
+        assert_eq!(
+            throughput.eta_at(Duration::from_secs(5)),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(throughput.bytes_per_second_at(Duration::ZERO), 0.0);
+        assert_eq!(Phase::Encrypting.to_string(), "encrypting");
+    }
+}
@@ -0,0 +1,307 @@
+//! # Provenance
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在私有的 `pmPv` chunk 中登记 pngme 新增或修改过的 chunk，
+//! 让审查时能区分哪些 chunk 是我们写入的，哪些是文件原有的。
+//!
+//! `pmPv` 的数据是 UTF-8 文本，每行登记一个 chunk，字段之间用制表符分隔：
+//!
+//! ```text
+//! 类型 \t CRC（8 位十六进制） \t 工具版本 \t 操作 \t 操作 id
+//! ```
+//!
+//! chunk 由类型和 CRC 识别，因此移动位置不影响登记；内容被其他工具改动后登记自然失效。
+
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::clock::{Clock, SystemClock};
+use crate::describe;
+use crate::png::Png;
+use crate::preview;
+use crate::{PngmeError, Result};
+
+/// 保存登记表的 chunk 类型
+pub const CHUNK_TYPE: &str = "pmPv";
+/// 写入登记表的工具版本
+pub const TOOL: &str = concat!("pngme/", env!("CARGO_PKG_VERSION"));
+
+/// 一条登记：由 pngme 写入的一个 chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceEntry {
+    pub chunk_type: ChunkType,
+    pub crc: u32,
+    /// 写入时的工具版本，例如 `pngme/0.1.0`
+    pub tool: String,
+    /// 写入它的操作，例如 `encode`
+    pub operation: String,
+    /// 同一次运行的所有写入共享的 id
+    pub operation_id: String,
+}
+
+impl ProvenanceEntry {
+    /// 是否登记的是这个 chunk
+    pub fn matches(&self, chunk_type: &ChunkType, crc: u32) -> bool {
+        self.chunk_type == *chunk_type && self.crc == crc
+    }
+}
+
+impl fmt::Display for ProvenanceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            preview::escape(&self.tool),
+            preview::escape(&self.operation),
+            preview::escape(&self.operation_id)
+        )
+    }
+}
+
+/// `pmPv` chunk 中的登记表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    pub entries: Vec<ProvenanceEntry>,
+}
+
+impl Provenance {
+    /// 读取 chunk 列表中的登记表，没有 `pmPv` 时返回空表
+    pub fn from_chunks(chunks: &[Chunk]) -> Result<Provenance> {
+        match chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type().to_string() == CHUNK_TYPE)
+        {
+            Some(chunk) => Provenance::parse(chunk.data()),
+            None => Ok(Provenance::default()),
+        }
+    }
+
+    /// 解析 `pmPv` chunk 的数据
+    pub fn parse(data: &[u8]) -> Result<Provenance> {
+        let text = std::str::from_utf8(data)?;
+        let entries = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [chunk_type, crc, tool, operation, operation_id] = fields[..] else {
+                    return Err(invalid(format!("expected 5 fields in {:?}", line)));
+                };
+                Ok(ProvenanceEntry {
+                    chunk_type: ChunkType::from_str(chunk_type)?,
+                    crc: u32::from_str_radix(crc, 16)
+                        .map_err(|_| invalid(format!("invalid CRC {:?}", crc)))?,
+                    tool: tool.to_string(),
+                    operation: operation.to_string(),
+                    operation_id: operation_id.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Provenance { entries })
+    }
+
+    /// 查找某个 chunk 的登记
+    pub fn lookup(&self, chunk_type: &ChunkType, crc: u32) -> Option<&ProvenanceEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(chunk_type, crc))
+    }
+
+    /// 编码为 `pmPv` chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{}\t{:08X}\t{}\t{}\t{}\n",
+                entry.chunk_type, entry.crc, entry.tool, entry.operation, entry.operation_id
+            ));
+        }
+        let chunk_type = ChunkType::from_str(CHUNK_TYPE).expect("pmPv is a valid chunk type");
+        Chunk::new(chunk_type, text.into_bytes())
+    }
+}
+
+/// 登记 `png` 中相对 `before` 新增或修改的 chunk，并删除已经不存在的 chunk 的登记
+pub fn tag(png: &mut Png, before: &[Chunk], operation: &str, operation_id: &str) -> Result<()> {
+    let mut provenance = Provenance::from_chunks(png.chunks())?;
+    let chunks: Vec<&Chunk> = png
+        .chunks()
+        .iter()
+        .filter(|chunk| chunk.chunk_type().to_string() != CHUNK_TYPE)
+        .collect();
+    provenance.entries.retain(|entry| {
+        chunks
+            .iter()
+            .any(|chunk| entry.matches(chunk.chunk_type(), chunk.crc()))
+    });
+    for chunk in chunks {
+        let added = !before.contains(chunk);
+        if added && provenance.lookup(chunk.chunk_type(), chunk.crc()).is_none() {
+            provenance.entries.push(ProvenanceEntry {
+                chunk_type: *chunk.chunk_type(),
+                crc: chunk.crc(),
+                tool: TOOL.to_string(),
+                operation: operation.to_string(),
+                operation_id: operation_id.to_string(),
+            });
+        }
+    }
+
+    if provenance.entries.is_empty() {
+        let _ = png.remove_chunk(CHUNK_TYPE);
+    } else {
+        png.replace_chunk(provenance.to_chunk()?);
+    }
+    Ok(())
+}
+
+/// 与 `Png` 的 `Display` 格式相同（已知的 chunk 后面显示解码后的字段），
+/// 但在 pngme 写入的 chunk 后面标出登记信息
+pub struct AnnotatedPng<'a>(pub &'a Png);
+
+impl fmt::Display for AnnotatedPng<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 登记表无法解析时按没有登记处理
+        let provenance = Provenance::from_chunks(self.0.chunks()).unwrap_or_default();
+        let header = self.0.header().ok();
+        writeln!(f, "Png {{")?;
+        for chunk in self.0.chunks() {
+            write!(
+                f,
+                "  {} ({} bytes, CRC {:08X})",
+                chunk.chunk_type(),
+                chunk.length(),
+                chunk.crc()
+            )?;
+            if let Some(entry) = provenance.lookup(chunk.chunk_type(), chunk.crc()) {
+                write!(f, " added by {}", entry)?;
+            }
+            if let Some(description) =
+                describe::describe(chunk.chunk_type(), chunk.data(), header.as_ref())
+            {
+                write!(f, " {}", description)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// 本次运行的操作 id：启动时间（Unix 纳秒）和进程号，均为十六进制
+pub fn operation_id() -> &'static str {
+    static OPERATION_ID: OnceLock<String> = OnceLock::new();
+    OPERATION_ID.get_or_init(|| {
+        let nanos = SystemClock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        format!("{:x}-{:x}", nanos, std::process::id())
+    })
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::InvalidMetadata(format!("pmPv: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::{testing_chunk, PNG_FILE};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_round_trip() {
+        let provenance = Provenance {
+            entries: vec![ProvenanceEntry {
+                chunk_type: ChunkType::from_str("ruSt").unwrap(),
+                crc: 0x0123ABCD,
+                tool: TOOL.to_string(),
+                operation: "encode".to_string(),
+                operation_id: "abc-1".to_string(),
+            }],
+        };
+        let chunk = provenance.to_chunk().unwrap();
+        assert_eq!(chunk.chunk_type().to_string(), CHUNK_TYPE);
+        assert!(chunk
+            .data_as_string()
+            .unwrap()
+            .starts_with("ruSt\t0123ABCD\t"));
+        assert_eq!(Provenance::parse(chunk.data()).unwrap(), provenance);
+    }
+
+    #[test]
+    fn test_invalid_registry() {
+        assert!(matches!(
+            Provenance::parse(b"ruSt\t0123ABCD\n"),
+            Err(PngmeError::InvalidMetadata(_))
+        ));
+        assert!(Provenance::parse(b"ruSt\tnothex\ta\tb\tc\n").is_err());
+        assert!(Provenance::parse(b"").unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn test_display_escapes_registry_fields() {
+        let provenance =
+            Provenance::parse(b"ruSt\t0123ABCD\t\x1b]0;evil\x07\tencode\top-1\n").unwrap();
+        let entry = provenance.entries[0].to_string();
+        assert_eq!(entry, "\\x1B]0;evil\\x07 encode (op-1)");
+    }
+
+    #[test]
+    fn test_tag() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(testing_chunk("vpAg", b"shipped with the file"));
+        let before = png.chunks().to_vec();
+
+        png.append_chunk(testing_chunk("ruSt", b"hidden"));
+        tag(&mut png, &before, "encode", "op-1").unwrap();
+        let provenance = Provenance::from_chunks(png.chunks()).unwrap();
+        assert_eq!(provenance.entries.len(), 1);
+        let hidden = png.chunk_by_type("ruSt").unwrap();
+        let entry = provenance
+            .lookup(hidden.chunk_type(), hidden.crc())
+            .unwrap();
+        assert_eq!(entry.operation, "encode");
+        let vendor = png.chunk_by_type("vpAg").unwrap();
+        assert!(provenance
+            .lookup(vendor.chunk_type(), vendor.crc())
+            .is_none());
+
+        // 被删除的 chunk 的登记随下一次登记一起清除
+        let before = png.chunks().to_vec();
+        png.remove_chunk("ruSt").unwrap();
+        tag(&mut png, &before, "remove", "op-2").unwrap();
+        assert!(png.chunk_by_type(CHUNK_TYPE).is_none());
+    }
+
+    #[test]
+    fn test_annotated_png() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(AnnotatedPng(&png).to_string(), png.to_string());
+
+        let before = png.chunks().to_vec();
+        png.append_chunk(testing_chunk("ruSt", b"hidden"));
+        tag(&mut png, &before, "encode", "op-1").unwrap();
+        let listing = AnnotatedPng(&png).to_string();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert!(lines[3].starts_with("  ruSt (6 bytes"));
+        assert!(lines[3].ends_with(&format!("added by {} encode (op-1)", TOOL)));
+        assert!(!lines[1].contains("added by"));
+
+        png.set_text("Title", "two\nlines").unwrap();
+        let listing = AnnotatedPng(&png).to_string();
+        let text = listing.lines().find(|line| line.contains("tEXt")).unwrap();
+        assert!(text.ends_with(" Title: two\\nlines"));
+    }
+
+    #[test]
+    fn test_operation_id_is_stable() {
+        assert_eq!(operation_id(), operation_id());
+        assert!(operation_id().contains('-'));
+    }
+}
@@ -0,0 +1,235 @@
+//! # Python
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在启用 `python` feature 时通过 PyO3 导出 Python 模块 `pngme`，脚本可以直接处理 chunk，
+//! 而不需要启动命令行进程。用 maturin 构建（配置见 `pyproject.toml`）：
+//!
+//! ```text
+//! import pngme
+//! output = pngme.encode(open("dice.png", "rb").read(), "ruSt", "hello")
+//! pngme.decode(output, "ruSt")  # "hello"
+//!
+//! png = pngme.Png(output)
+//! png.append_chunk(pngme.Chunk("teXt", b"data"))
+//! [str(chunk.chunk_type) for chunk in png.chunks()]
+//! ```
+//!
+//! `Chunk`、`ChunkType` 和 `Png` 包装同名的 Rust 类型，所有数据都以 `bytes` 传递；
+//! pngme 的错误转换为 Python 异常 `pngme.PngmeError`。
+
+use std::str;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::engine;
+use crate::png::Png;
+
+create_exception!(
+    pngme,
+    PngmeError,
+    PyException,
+    "pngme 返回的错误，例如输入不是 PNG 或找不到 chunk"
+);
+
+impl From<crate::Error> for PyErr {
+    fn from(error: crate::Error) -> PyErr {
+        PngmeError::new_err(error.to_string())
+    }
+}
+
+/// chunk 类型，例如 `ChunkType("ruSt")`
+#[pyclass(name = "ChunkType", module = "pngme", frozen, eq, skip_from_py_object)]
+#[derive(Clone, PartialEq, Eq)]
+pub struct PyChunkType(ChunkType);
+
+#[pymethods]
+impl PyChunkType {
+    #[new]
+    fn new(chunk_type: &str) -> PyResult<Self> {
+        Ok(PyChunkType(ChunkType::from_str(chunk_type)?))
+    }
+
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.bytes())
+    }
+
+    fn is_critical(&self) -> bool {
+        self.0.is_critical()
+    }
+
+    fn is_public(&self) -> bool {
+        self.0.is_public()
+    }
+
+    fn is_reserved_bit_valid(&self) -> bool {
+        self.0.is_reserved_bit_valid()
+    }
+
+    fn is_safe_to_copy(&self) -> bool {
+        self.0.is_safe_to_copy()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ChunkType('{}')", self.0)
+    }
+}
+
+/// 一个 chunk，例如 `Chunk("ruSt", b"hello")`，CRC 在创建时计算
+#[pyclass(name = "Chunk", module = "pngme", frozen, eq, from_py_object)]
+#[derive(Clone, PartialEq)]
+pub struct PyChunk(Chunk);
+
+#[pymethods]
+impl PyChunk {
+    #[new]
+    fn new(chunk_type: &str, data: &[u8]) -> PyResult<Self> {
+        Ok(PyChunk(Chunk::new(
+            ChunkType::from_str(chunk_type)?,
+            data.to_vec(),
+        )?))
+    }
+
+    #[getter]
+    fn chunk_type(&self) -> PyChunkType {
+        PyChunkType(*self.0.chunk_type())
+    }
+
+    #[getter]
+    fn length(&self) -> u32 {
+        self.0.length()
+    }
+
+    #[getter]
+    fn crc(&self) -> u32 {
+        self.0.crc()
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.0.data())
+    }
+
+    /// 把数据按 UTF-8 解码为字符串
+    fn data_as_string(&self) -> PyResult<String> {
+        Ok(self.0.data_as_string()?)
+    }
+
+    /// 编码后的 chunk：长度、类型、数据和 CRC
+    fn as_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.as_bytes())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Chunk('{}', length={}, crc=0x{:08X})",
+            self.0.chunk_type(),
+            self.0.length(),
+            self.0.crc()
+        )
+    }
+}
+
+/// PNG 文件，`Png(data)` 从字节解析并校验 CRC
+#[pyclass(name = "Png", module = "pngme", skip_from_py_object)]
+#[derive(Clone)]
+pub struct PyPng(Png);
+
+#[pymethods]
+impl PyPng {
+    #[new]
+    fn new(data: &[u8]) -> PyResult<Self> {
+        Ok(PyPng(Png::try_from(data)?))
+    }
+
+    /// 由 chunk 列表组成 PNG
+    #[staticmethod]
+    fn from_chunks(chunks: Vec<PyChunk>) -> Self {
+        PyPng(Png::from_chunks(
+            chunks.into_iter().map(|chunk| chunk.0).collect(),
+        ))
+    }
+
+    /// 按文件中的顺序列出所有 chunk
+    fn chunks(&self) -> Vec<PyChunk> {
+        self.0.chunks().iter().cloned().map(PyChunk).collect()
+    }
+
+    /// 第一个该类型的 chunk，没有时返回 `None`
+    fn chunk_by_type(&self, chunk_type: &str) -> Option<PyChunk> {
+        self.0.chunk_by_type(chunk_type).cloned().map(PyChunk)
+    }
+
+    /// 按规范为 chunk 的类型选择位置插入
+    fn append_chunk(&mut self, chunk: PyChunk) {
+        self.0.append_chunk(chunk.0);
+    }
+
+    /// 删除并返回第一个该类型的 chunk，没有时抛出 `PngmeError`
+    fn remove_chunk(&mut self, chunk_type: &str) -> PyResult<PyChunk> {
+        Ok(PyChunk(self.0.remove_chunk(chunk_type)?))
+    }
+
+    /// 编码后的 PNG 文件
+    fn as_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.as_bytes())
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.chunks().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Png({} chunks)", self.0.chunks().len())
+    }
+}
+
+/// 在 `png` 中写入 `message`，返回新的 PNG 文件；`passphrase` 不为空时加密
+#[pyfunction]
+#[pyo3(signature = (png, chunk_type, message, passphrase=None, compress=false))]
+fn encode<'py>(
+    py: Python<'py>,
+    png: &[u8],
+    chunk_type: &str,
+    message: &str,
+    passphrase: Option<String>,
+    compress: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let output = engine::encode_once(png, chunk_type, message.as_bytes(), compress, passphrase)?;
+    Ok(PyBytes::new(py, &output))
+}
+
+/// 读取 `png` 中 `chunk_type` 类型的信息
+#[pyfunction]
+#[pyo3(signature = (png, chunk_type, passphrase=None))]
+fn decode(png: &[u8], chunk_type: &str, passphrase: Option<String>) -> PyResult<String> {
+    let data = engine::decode_once(png, chunk_type, passphrase)?;
+    Ok(str::from_utf8(&data)
+        .map_err(crate::Error::from)?
+        .to_string())
+}
+
+/// Python 模块 `pngme`
+#[pymodule]
+fn pngme(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyChunkType>()?;
+    m.add_class::<PyChunk>()?;
+    m.add_class::<PyPng>()?;
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add("PngmeError", m.py().get_type::<PngmeError>())?;
+    Ok(())
+}
@@ -0,0 +1,1222 @@
+//! # QR
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `--method qr`：把信息渲染为 QR 码画在图片的一角，解码时从像素中找到并识读它。
+//! 与写入 chunk 不同，信息在截图、转发等只保留像素的场景中仍然存在（只要 QR 码清晰可见），
+//! 代价是修改了图片的内容，容量也只有 QR 码的最大容量（纠错等级 M 时 2331 字节）。
+//!
+//! 编码由 `qrcode` crate 完成。识读由本模块实现：二值化后按 1:1:3:1:1 的比例寻找三个定位图案，
+//! 按它们的位置仿射采样模块，再读取格式信息、去除掩码、逐块进行 Reed-Solomon 纠错并解析数据段。
+//! 它针对的是 pngme 画出的 QR 码，可以处理缩放和旋转过的截图，不处理透视变形的照片。
+
+use qrcode::bits::Bits;
+use qrcode::canvas::{Canvas, Module};
+use qrcode::types::Mode;
+use qrcode::{Color, EcLevel, QrCode, Version};
+
+use crate::cancel::CancelToken;
+use crate::ihdr::{ColorType, Ihdr};
+use crate::interlace;
+use crate::limits::Limits;
+use crate::palette::{Palette, Transparency};
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 纠错等级 M 时 QR 码（版本 40，字节模式）最多能容纳的字节数
+pub const MAX_DATA_LEN: usize = 2331;
+/// 默认每个模块的边长（像素）
+pub const DEFAULT_SCALE: u32 = 4;
+/// QR 码四周留白的宽度（模块），规范要求至少 4 个模块
+pub const QUIET_ZONE: u32 = 4;
+/// 写入时使用的纠错等级，可以恢复约 15% 的码字
+const EC_LEVEL: EcLevel = EcLevel::M;
+/// 识读时最多尝试的定位图案候选数
+const MAX_CANDIDATES: usize = 12;
+/// 格式信息与最接近的合法值最多相差的位数
+const MAX_FORMAT_ERRORS: u32 = 3;
+/// 格式信息的两份拷贝（左上角，以及左下角和右上角）中各位的位置，高位在前；负数表示从右侧或底部数起
+const FORMAT_MAIN: [(i16, i16); 15] = [
+    (0, 8),
+    (1, 8),
+    (2, 8),
+    (3, 8),
+    (4, 8),
+    (5, 8),
+    (7, 8),
+    (8, 8),
+    (8, 7),
+    (8, 5),
+    (8, 4),
+    (8, 3),
+    (8, 2),
+    (8, 1),
+    (8, 0),
+];
+const FORMAT_SIDE: [(i16, i16); 15] = [
+    (8, -1),
+    (8, -2),
+    (8, -3),
+    (8, -4),
+    (8, -5),
+    (8, -6),
+    (8, -7),
+    (-8, 8),
+    (-7, 8),
+    (-6, 8),
+    (-5, 8),
+    (-4, 8),
+    (-3, 8),
+    (-2, 8),
+    (-1, 8),
+];
+/// 字母数字模式的字符表
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// QR 码在图片中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Corner {
+    /// 左上角
+    TopLeft,
+    /// 右上角
+    TopRight,
+    /// 左下角
+    BottomLeft,
+    /// 右下角
+    #[default]
+    BottomRight,
+}
+
+/// 渲染 QR 码的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QrOptions {
+    /// QR 码（连同四周的留白）贴着哪个角
+    pub corner: Corner,
+    /// 每个模块的边长（像素）
+    pub scale: u32,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        QrOptions {
+            corner: Corner::default(),
+            scale: DEFAULT_SCALE,
+        }
+    }
+}
+
+fn qr_error(message: impl Into<String>) -> PngmeError {
+    PngmeError::QrCode(message.into())
+}
+
+/// 把 `data` 编码为 QR 码，画在图片的一角（含四周 4 个模块的留白），替换原来的像素。
+/// 深色模块为黑色、浅色模块为白色（均不透明）；调色板图片使用已有的接近黑白的项，
+/// 没有时在调色板末尾添加。图片放不下时返回错误。每画一行和压缩 IDAT 前检查 `cancel`，
+/// 已取消时返回 `PngmeError::Cancelled`
+pub fn render(
+    png: &mut Png,
+    data: &[u8],
+    options: &QrOptions,
+    limits: &Limits,
+    cancel: &CancelToken,
+) -> Result<()> {
+    if options.scale == 0 {
+        return Err(qr_error("the module size must be at least 1 pixel"));
+    }
+    let code = encode(data)?;
+    let width = code.width() as u32;
+    let header = png.header()?;
+    let side = u64::from(width + 2 * QUIET_ZONE) * u64::from(options.scale);
+    if side > u64::from(header.width) || side > u64::from(header.height) {
+        return Err(qr_error(format!(
+            "a {0}x{0} QR code at {1} pixels per module needs {2}x{2} pixels, the image is {3}x{4}",
+            width, options.scale, side, header.width, header.height
+        )));
+    }
+    let side = side as u32;
+    let (left, top) = match options.corner {
+        Corner::TopLeft => (0, 0),
+        Corner::TopRight => (header.width - side, 0),
+        Corner::BottomLeft => (0, header.height - side),
+        Corner::BottomRight => (header.width - side, header.height - side),
+    };
+    let (dark, light) = pens(png, &header)?;
+    let colors = code.to_colors();
+    // 像素所在的模块，落在留白中时为 `None`
+    let module = |offset: u32| {
+        (offset / options.scale)
+            .checked_sub(QUIET_ZONE)
+            .filter(|&module| module < width)
+    };
+
+    let mut image = png.pixels(limits)?;
+    let row_bytes = header.row_bytes() as usize;
+    let channels = dark.len();
+    for y in 0..side {
+        cancel.check()?;
+        let row = &mut image[(top + y) as usize * row_bytes..][..row_bytes];
+        for x in 0..side {
+            let is_dark = match (module(x), module(y)) {
+                (Some(mx), Some(my)) => colors[(my * width + mx) as usize] == Color::Dark,
+                _ => false,
+            };
+            let pen = if is_dark { &dark } else { &light };
+            for (channel, &value) in pen.iter().enumerate() {
+                let index = (left + x) as usize * channels + channel;
+                set_sample(row, index, header.bit_depth, value);
+            }
+        }
+    }
+    let raw = interlace::filter_adaptive(&header, &interlace::interlace(&header, &image));
+    cancel.check()?;
+    png.set_image_data(&raw)
+}
+
+/// 在图片中寻找 QR 码并返回其中的数据。找不到或无法识读时返回 `PngmeError::QrCode`
+pub fn read(png: &Png, limits: &Limits) -> Result<Vec<u8>> {
+    let bitmap = Bitmap::from_png(png, limits)?;
+    let mut error = qr_error("no QR code found in the image");
+    for finders in finder_triples(&find_finders(&bitmap)) {
+        match read_code(&bitmap, &finders) {
+            Ok(data) => return Ok(data),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// 用能容纳 `data` 的最小版本编码。整段数据使用字节模式，解码结果与写入的字节完全相同
+fn encode(data: &[u8]) -> Result<QrCode> {
+    for version in 1..=40 {
+        let mut bits = Bits::new(Version::Normal(version));
+        if bits.push_byte_data(data).is_ok() && bits.push_terminator(EC_LEVEL).is_ok() {
+            return QrCode::with_bits(bits, EC_LEVEL).map_err(|e| qr_error(e.to_string()));
+        }
+    }
+    Err(qr_error(format!(
+        "{} bytes do not fit in a QR code, the limit is {} bytes",
+        data.len(),
+        MAX_DATA_LEN
+    )))
+}
+
+/// 深色和浅色模块的样本值，每个通道一个；`caption` 也用它选择文字和底色
+pub(crate) fn pens(png: &mut Png, header: &Ihdr) -> Result<(Vec<u16>, Vec<u16>)> {
+    let max = ((1u32 << header.bit_depth) - 1) as u16;
+    Ok(match header.color_type {
+        ColorType::Grayscale => (vec![0], vec![max]),
+        ColorType::GrayscaleAlpha => (vec![0, max], vec![max, max]),
+        ColorType::Rgb => (vec![0; 3], vec![max; 3]),
+        ColorType::Rgba => (vec![0, 0, 0, max], vec![max; 4]),
+        ColorType::Indexed => {
+            let mut palette = png
+                .palette()?
+                .ok_or_else(|| PngmeError::ChunkNotFound(Palette::CHUNK_TYPE.to_string()))?;
+            let alpha = match png.transparency()? {
+                Some(Transparency::Indexed(alpha)) => alpha,
+                _ => Vec::new(),
+            };
+            let len = palette.len();
+            let dark = palette_entry(&mut palette, &alpha, header, true)?;
+            let light = palette_entry(&mut palette, &alpha, header, false)?;
+            if palette.len() != len {
+                png.set_palette(&palette)?;
+            }
+            (vec![dark], vec![light])
+        }
+    })
+}
+
+/// 调色板中可以用作深色（或浅色）模块的不透明项，没有时在末尾添加纯黑（或纯白）
+fn palette_entry(palette: &mut Palette, alpha: &[u8], header: &Ihdr, dark: bool) -> Result<u16> {
+    let suitable = |index: usize, color: &[u8; 3]| {
+        let luma = luma(color.map(u32::from));
+        alpha.get(index).is_none_or(|&alpha| alpha == 255)
+            && if dark { luma < 64 } else { luma > 192 }
+    };
+    if let Some(index) = palette
+        .entries()
+        .iter()
+        .enumerate()
+        .position(|(index, color)| suitable(index, color))
+    {
+        return Ok(index as u16);
+    }
+    if palette.len() >= 1 << header.bit_depth {
+        return Err(qr_error(format!(
+            "the {}-bit palette is full and has no {} entry",
+            header.bit_depth,
+            if dark { "black" } else { "white" }
+        )));
+    }
+    Ok(palette.push(if dark { [0; 3] } else { [255; 3] })? as u16)
+}
+
+/// 亮度（0 到 255）
+fn luma([r, g, b]: [u32; 3]) -> u32 {
+    (299 * r + 587 * g + 114 * b) / 1000
+}
+
+/// 行内第 `index` 个样本的值
+pub(crate) fn sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => u16::from(row[index]),
+        depth => {
+            let bit = index * depth as usize;
+            let shift = 8 - depth as usize - bit % 8;
+            u16::from(row[bit / 8] >> shift) & ((1 << depth) - 1)
+        }
+    }
+}
+
+/// 修改行内第 `index` 个样本
+pub(crate) fn set_sample(row: &mut [u8], index: usize, bit_depth: u8, value: u16) {
+    match bit_depth {
+        16 => row[2 * index..2 * index + 2].copy_from_slice(&value.to_be_bytes()),
+        8 => row[index] = value as u8,
+        depth => {
+            let bit = index * depth as usize;
+            let shift = 8 - depth as usize - bit % 8;
+            let mask = ((1u16 << depth) - 1) as u8;
+            row[bit / 8] = row[bit / 8] & !(mask << shift) | (value as u8 & mask) << shift;
+        }
+    }
+}
+
+/// 二值化后的图片
+struct Bitmap {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    /// 把像素合成到白色背景上，亮度低于一半的为深色
+    fn from_png(png: &Png, limits: &Limits) -> Result<Self> {
+        let header = png.header()?;
+        let image = png.pixels(limits)?;
+        let (width, height) = (header.width as usize, header.height as usize);
+        let channels = header.color_type.channels() as usize;
+        let max = (1u32 << header.bit_depth) - 1;
+        let palette = png
+            .palette()?
+            .map(|palette| palette.entries().to_vec())
+            .unwrap_or_default();
+        let palette_alpha = match png.transparency()? {
+            Some(Transparency::Indexed(alpha)) => alpha,
+            _ => Vec::new(),
+        };
+
+        let mut dark = Vec::with_capacity(width * height);
+        for row in image.chunks_exact(header.row_bytes() as usize) {
+            for x in 0..width {
+                let value = |channel: usize| {
+                    u32::from(sample(row, x * channels + channel, header.bit_depth)) * 255 / max
+                };
+                let (color, alpha) = match header.color_type {
+                    ColorType::Grayscale => ([value(0); 3], 255),
+                    ColorType::GrayscaleAlpha => ([value(0); 3], value(1)),
+                    ColorType::Rgb => ([value(0), value(1), value(2)], 255),
+                    ColorType::Rgba => ([value(0), value(1), value(2)], value(3)),
+                    ColorType::Indexed => {
+                        let index = sample(row, x, header.bit_depth) as usize;
+                        let color = palette.get(index).copied().unwrap_or_default();
+                        let alpha = palette_alpha.get(index).copied().unwrap_or(255);
+                        (color.map(u32::from), u32::from(alpha))
+                    }
+                };
+                let luma = (luma(color) * alpha + 255 * (255 - alpha)) / 255;
+                dark.push(luma < 128);
+            }
+        }
+        Ok(Bitmap {
+            width,
+            height,
+            dark,
+        })
+    }
+
+    /// 图片之外的位置视为浅色
+    fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.dark[y * self.width + x]
+    }
+}
+
+/// 定位图案的候选：中心坐标（像素）、估计的模块边长和被几次扫描命中
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f64,
+    y: f64,
+    module: f64,
+    hits: u32,
+}
+
+impl Finder {
+    fn distance(&self, other: &Finder) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// 深、浅、深、浅、深五段的长度是否接近 1:1:3:1:1
+fn is_finder_ratio(runs: &[usize; 5]) -> bool {
+    let total: usize = runs.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    runs.iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&run, expected)| (run as f64 - expected * module).abs() < expected * module / 2.0)
+}
+
+/// 沿一条线从 `center` 向两侧数出五段，比例符合定位图案且总长接近 `expected` 时
+/// 返回中间一段的中心位置和总长
+fn cross_check(
+    len: usize,
+    dark: impl Fn(usize) -> bool,
+    center: usize,
+    expected: usize,
+) -> Option<(f64, usize)> {
+    if !dark(center) {
+        return None;
+    }
+    let mut runs = [0usize; 5];
+    let mut before = center as isize;
+    for (run, color) in [(2, true), (1, false), (0, true)] {
+        while before >= 0 && dark(before as usize) == color && runs[run] <= expected {
+            runs[run] += 1;
+            before -= 1;
+        }
+    }
+    let mut after = center + 1;
+    for (run, color) in [(2, true), (3, false), (4, true)] {
+        while after < len && dark(after) == color && runs[run] <= expected {
+            runs[run] += 1;
+            after += 1;
+        }
+    }
+    let total: usize = runs.iter().sum();
+    if runs.contains(&0) || total.abs_diff(expected) * 5 >= expected * 2 || !is_finder_ratio(&runs)
+    {
+        return None;
+    }
+    let end = after - runs[4] - runs[3];
+    Some((end as f64 - runs[2] as f64 / 2.0, total))
+}
+
+/// 逐行扫描符合 1:1:3:1:1 的片段，在竖直和水平方向上复核后合并为定位图案的候选
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut finders: Vec<Finder> = Vec::new();
+    for y in 0..bitmap.height {
+        // 行内颜色相同的连续段：(起点, 长度)，相邻的段颜色交替
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for x in 1..=bitmap.width {
+            if x == bitmap.width || bitmap.get(x, y) != bitmap.get(start, y) {
+                runs.push((start, x - start));
+                start = x;
+            }
+        }
+        let first_dark = bitmap.get(0, y);
+        for i in 0..runs.len().saturating_sub(4) {
+            if (i % 2 == 0) != first_dark {
+                continue;
+            }
+            let window: [usize; 5] = std::array::from_fn(|k| runs[i + k].1);
+            if !is_finder_ratio(&window) {
+                continue;
+            }
+            let total: usize = window.iter().sum();
+            let column = runs[i + 2].0 + runs[i + 2].1 / 2;
+            let Some((cy, vertical)) =
+                cross_check(bitmap.height, |row| bitmap.get(column, row), y, total)
+            else {
+                continue;
+            };
+            let row = cy as usize;
+            let Some((cx, horizontal)) = cross_check(
+                bitmap.width,
+                |column| bitmap.get(column, row),
+                column,
+                total,
+            ) else {
+                continue;
+            };
+            let module = (total + vertical + horizontal) as f64 / 21.0;
+            let same = |finder: &&mut Finder| {
+                (finder.x - cx).abs() <= finder.module * 2.0
+                    && (finder.y - cy).abs() <= finder.module * 2.0
+                    && (finder.module - module).abs() <= finder.module.max(module) / 2.0 + 0.5
+            };
+            match finders.iter_mut().find(same) {
+                Some(finder) => {
+                    let hits = f64::from(finder.hits);
+                    finder.x = (finder.x * hits + cx) / (hits + 1.0);
+                    finder.y = (finder.y * hits + cy) / (hits + 1.0);
+                    finder.module = (finder.module * hits + module) / (hits + 1.0);
+                    finder.hits += 1;
+                }
+                None => finders.push(Finder {
+                    x: cx,
+                    y: cy,
+                    module,
+                    hits: 1,
+                }),
+            }
+        }
+    }
+    finders
+}
+
+/// 从候选中选出可能属于同一个 QR 码的三个定位图案，按左上、右上、左下排列，
+/// 最接近等腰直角三角形的在前。只考虑命中次数最多的几个候选
+fn finder_triples(finders: &[Finder]) -> Vec<[Finder; 3]> {
+    let mut candidates = finders.to_vec();
+    candidates.sort_by_key(|finder| std::cmp::Reverse(finder.hits));
+    candidates.truncate(MAX_CANDIDATES);
+
+    let mut triples = Vec::new();
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let [a, b, c] = [candidates[i], candidates[j], candidates[k]];
+                let modules = [a.module, b.module, c.module];
+                let smallest = modules.iter().copied().fold(f64::INFINITY, f64::min);
+                let largest = modules.iter().copied().fold(0.0, f64::max);
+                if largest > smallest * 1.5 {
+                    continue;
+                }
+                // 直角顶点是最长边所对的点
+                let (ab, ac, bc) = (a.distance(&b), a.distance(&c), b.distance(&c));
+                let (corner, p, q) = if bc >= ab && bc >= ac {
+                    (a, b, c)
+                } else if ac >= ab {
+                    (b, a, c)
+                } else {
+                    (c, a, b)
+                };
+                let u = (p.x - corner.x, p.y - corner.y);
+                let v = (q.x - corner.x, q.y - corner.y);
+                let (lu, lv) = (u.0.hypot(u.1), v.0.hypot(v.1));
+                let module = (a.module + b.module + c.module) / 3.0;
+                // 版本 1 的定位图案中心相距 14 个模块
+                if lu.min(lv) < 10.0 * module {
+                    continue;
+                }
+                let skew = (lu - lv).abs() / lu.max(lv);
+                let cos = (u.0 * v.0 + u.1 * v.1) / (lu * lv);
+                if skew > 0.2 || cos.abs() > 0.2 {
+                    continue;
+                }
+                // 图像的 y 轴向下，叉积为正时 p 在右上、q 在左下
+                let (top_right, bottom_left) = if u.0 * v.1 - u.1 * v.0 > 0.0 {
+                    (p, q)
+                } else {
+                    (q, p)
+                };
+                triples.push((skew + cos.abs(), [corner, top_right, bottom_left]));
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    triples.into_iter().map(|(_, finders)| finders).collect()
+}
+
+/// 按三个定位图案的间距估计版本，依次尝试估计值附近的版本
+fn read_code(bitmap: &Bitmap, finders: &[Finder; 3]) -> Result<Vec<u8>> {
+    let [top_left, top_right, bottom_left] = finders;
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let legs = (top_left.distance(top_right) + top_left.distance(bottom_left)) / 2.0;
+    let estimate = ((legs / module + 7.0 - 17.0) / 4.0).round() as i16;
+    let mut error = qr_error("cannot determine the QR code version");
+    for version in [0, -1, 1, -2, 2].map(|offset| estimate + offset) {
+        if !(1..=40).contains(&version) {
+            continue;
+        }
+        let grid = Grid::new(bitmap, finders, 17 + 4 * version);
+        match read_version(&grid, Version::Normal(version)) {
+            Ok(data) => return Ok(data),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// 模块网格到像素的仿射映射，由三个定位图案的中心（各距边缘 3.5 个模块）确定
+struct Grid<'a> {
+    bitmap: &'a Bitmap,
+    origin: (f64, f64),
+    right: (f64, f64),
+    down: (f64, f64),
+    width: i16,
+}
+
+impl<'a> Grid<'a> {
+    fn new(
+        bitmap: &'a Bitmap,
+        [top_left, top_right, bottom_left]: &[Finder; 3],
+        width: i16,
+    ) -> Self {
+        let span = f64::from(width - 7);
+        Grid {
+            bitmap,
+            origin: (top_left.x, top_left.y),
+            right: (
+                (top_right.x - top_left.x) / span,
+                (top_right.y - top_left.y) / span,
+            ),
+            down: (
+                (bottom_left.x - top_left.x) / span,
+                (bottom_left.y - top_left.y) / span,
+            ),
+            width,
+        }
+    }
+
+    /// 第 `y` 行第 `x` 列的模块是否为深色，负数表示从右侧或底部数起
+    fn dark(&self, x: i16, y: i16) -> bool {
+        let wrap = |value: i16| if value < 0 { value + self.width } else { value };
+        let (a, b) = (f64::from(wrap(x)) - 3.0, f64::from(wrap(y)) - 3.0);
+        let px = self.origin.0 + a * self.right.0 + b * self.down.0;
+        let py = self.origin.1 + a * self.right.1 + b * self.down.1;
+        px >= 0.0 && py >= 0.0 && self.bitmap.get(px as usize, py as usize)
+    }
+}
+
+/// 按指定的版本识读：格式信息、数据码字、纠错和数据段
+fn read_version(grid: &Grid, version: Version) -> Result<Vec<u8>> {
+    let (ec_level, mask) =
+        read_format(grid).ok_or_else(|| qr_error("cannot read the format information"))?;
+    let mut canvas = Canvas::new(version, ec_level);
+    canvas.draw_all_functional_patterns();
+    let codewords = read_codewords(grid, &canvas, mask);
+    parse_segments(&correct(&codewords, version, ec_level)?, version)
+}
+
+/// 格式信息：5 位数据（纠错等级和掩码）加上 BCH(15, 5) 校验位，再与 0x5412 异或
+fn format_code(info: u16) -> u16 {
+    let mut remainder = info << 10;
+    for bit in (10..15).rev() {
+        if remainder >> bit & 1 == 1 {
+            remainder ^= 0x537 << (bit - 10);
+        }
+    }
+    (info << 10 | remainder) ^ 0x5412
+}
+
+/// 读取两份格式信息，取与之最接近的合法值，返回纠错等级和掩码编号
+fn read_format(grid: &Grid) -> Option<(EcLevel, u8)> {
+    let read = |coords: &[(i16, i16)]| {
+        coords
+            .iter()
+            .fold(0u16, |acc, &(x, y)| acc << 1 | u16::from(grid.dark(x, y)))
+    };
+    let (main, side) = (read(&FORMAT_MAIN), read(&FORMAT_SIDE));
+    let (errors, info) = (0..32u16)
+        .map(|info| {
+            let code = format_code(info);
+            (
+                (code ^ main).count_ones().min((code ^ side).count_ones()),
+                info,
+            )
+        })
+        .min()?;
+    if errors > MAX_FORMAT_ERRORS {
+        return None;
+    }
+    let ec_level = match info >> 3 {
+        0b01 => EcLevel::L,
+        0b00 => EcLevel::M,
+        0b11 => EcLevel::Q,
+        _ => EcLevel::H,
+    };
+    Some((ec_level, (info & 0b111) as u8))
+}
+
+/// 掩码 `mask` 是否翻转第 `y` 行第 `x` 列的模块
+fn is_masked(mask: u8, x: i16, y: i16) -> bool {
+    let (i, j) = (i32::from(y), i32::from(x));
+    match mask {
+        0 => (i + j) % 2 == 0,
+        1 => i % 2 == 0,
+        2 => j % 3 == 0,
+        3 => (i + j) % 3 == 0,
+        4 => (i / 2 + j / 3) % 2 == 0,
+        5 => (i * j) % 2 + (i * j) % 3 == 0,
+        6 => ((i * j) % 2 + (i * j) % 3) % 2 == 0,
+        _ => ((i + j) % 2 + (i * j) % 3) % 2 == 0,
+    }
+}
+
+/// 从右下角开始，每两列为一组上下交替地读取数据模块（跳过第 6 列的定时图案），去除掩码后组成码字
+fn read_codewords(grid: &Grid, canvas: &Canvas, mask: u8) -> Vec<u8> {
+    let width = grid.width;
+    let mut bits = Vec::new();
+    let mut column = width - 1;
+    let mut upward = true;
+    while column > 0 {
+        if column == 6 {
+            column -= 1;
+        }
+        for i in 0..width {
+            let y = if upward { width - 1 - i } else { i };
+            for x in [column, column - 1] {
+                if matches!(canvas.get(x, y), Module::Empty) {
+                    bits.push(grid.dark(x, y) != is_masked(mask, x, y));
+                }
+            }
+        }
+        upward = !upward;
+        column -= 2;
+    }
+    bits.chunks_exact(8)
+        .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | u8::from(bit)))
+        .collect()
+}
+
+/// 按版本和纠错等级拆开交错排列的码字，逐块纠错后拼接各块的数据码字
+fn correct(codewords: &[u8], version: Version, ec_level: EcLevel) -> Result<Vec<u8>> {
+    let (blocks, ec_len) = block_layout(version, ec_level, codewords.len())?;
+    let mut words = codewords.iter().copied();
+    let mut received: Vec<Vec<u8>> = blocks
+        .iter()
+        .map(|&len| Vec::with_capacity(len + ec_len))
+        .collect();
+    let longest = blocks.iter().copied().max().unwrap_or(0);
+    for i in 0..longest + ec_len {
+        for (block, &len) in received.iter_mut().zip(&blocks) {
+            if i < len || i >= longest {
+                block.extend(words.next());
+            }
+        }
+    }
+    let mut data = Vec::with_capacity(blocks.iter().sum());
+    for (block, &len) in received.iter_mut().zip(&blocks) {
+        reed_solomon::correct(block, ec_len)?;
+        data.extend_from_slice(&block[..len]);
+    }
+    Ok(data)
+}
+
+/// 每块的数据码字数和每块的纠错码字数。规范中的分块总是若干个长度相同的块后面跟着
+/// 若干个长一个码字的块，`qrcode` 没有公开块数，这里用它交错排列数据码字的结果验证候选的块数
+fn block_layout(version: Version, ec_level: EcLevel, total: usize) -> Result<(Vec<usize>, usize)> {
+    let invalid = |e: qrcode::types::QrError| qr_error(e.to_string());
+    let data_len = Bits::new(version).max_len(ec_level).map_err(invalid)? / 8;
+    let ec_total = total.saturating_sub(data_len);
+    // 两组探测数据合起来给每个码字一个不同的编号，交错排列的结果相同说明分块相同
+    let probes: Vec<Vec<u8>> = [0, 8]
+        .iter()
+        .map(|shift| (0..data_len).map(|i| (i >> shift) as u8).collect())
+        .collect();
+    let expected = probes
+        .iter()
+        .map(|probe| {
+            Ok(qrcode::ec::construct_codewords(probe, version, ec_level)
+                .map_err(invalid)?
+                .0)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for count in 1..=data_len {
+        if ec_total % count != 0 {
+            continue;
+        }
+        let blocks: Vec<usize> = (0..count)
+            .map(|i| data_len / count + usize::from(i >= count - data_len % count))
+            .collect();
+        let matches = probes.iter().zip(&expected).all(|(probe, expected)| {
+            let mut offset = 0;
+            let parts: Vec<&[u8]> = blocks
+                .iter()
+                .map(|&len| {
+                    offset += len;
+                    &probe[offset - len..offset]
+                })
+                .collect();
+            interleave(&parts) == *expected
+        });
+        if matches {
+            return Ok((blocks, ec_total / count));
+        }
+    }
+    Err(qr_error(format!(
+        "{} codewords do not match the block structure of version {:?}",
+        total, version
+    )))
+}
+
+/// 依次取每块的第 1 个码字、第 2 个码字……，较短的块取完后跳过
+fn interleave(blocks: &[&[u8]]) -> Vec<u8> {
+    let longest = blocks.iter().map(|block| block.len()).max().unwrap_or(0);
+    (0..longest)
+        .flat_map(|i| blocks.iter().filter_map(move |block| block.get(i).copied()))
+        .collect()
+}
+
+/// 按位读取数据码字，高位在前
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Result<u32> {
+        if count > self.remaining() {
+            return Err(qr_error(
+                "the data segments run past the end of the QR code",
+            ));
+        }
+        let value = (self.position..self.position + count).fold(0, |acc, bit| {
+            acc << 1 | u32::from(self.bytes[bit / 8] >> (7 - bit % 8) & 1)
+        });
+        self.position += count;
+        Ok(value)
+    }
+}
+
+/// 解析数据段：支持数字、字母数字和字节模式，跳过 ECI 标记，遇到结束符或数据用完时停止
+fn parse_segments(data: &[u8], version: Version) -> Result<Vec<u8>> {
+    let mut reader = BitReader {
+        bytes: data,
+        position: 0,
+    };
+    let mut out = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            0b0100 => {
+                let count = reader.read(Mode::Byte.length_bits_count(version))?;
+                for _ in 0..count {
+                    out.push(reader.read(8)? as u8);
+                }
+            }
+            0b0001 => {
+                let mut count = reader.read(Mode::Numeric.length_bits_count(version))? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([4, 7, 10][digits - 1])?;
+                    if value >= 10u32.pow(digits as u32) {
+                        return Err(qr_error("invalid numeric segment"));
+                    }
+                    out.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count =
+                    reader.read(Mode::Alphanumeric.length_bits_count(version))? as usize;
+                while count > 0 {
+                    let (bits, chars) = if count >= 2 { (11, 2) } else { (6, 1) };
+                    let value = reader.read(bits)? as usize;
+                    let indices = if chars == 2 {
+                        vec![value / 45, value % 45]
+                    } else {
+                        vec![value]
+                    };
+                    for index in indices {
+                        let &byte = ALPHANUMERIC
+                            .get(index)
+                            .ok_or_else(|| qr_error("invalid alphanumeric segment"))?;
+                        out.push(byte);
+                    }
+                    count -= chars;
+                }
+            }
+            0b0111 => {
+                let first = reader.read(8)?;
+                if first & 0x80 != 0 {
+                    reader.read(if first & 0x40 != 0 { 16 } else { 8 })?;
+                }
+            }
+            mode => {
+                return Err(qr_error(format!(
+                    "unsupported QR segment mode {:04b}",
+                    mode
+                )))
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// QR 码使用的 Reed-Solomon 纠错：GF(256) 的本原多项式为 0x11d，生成多项式的根为 α^0 到 α^(n-1)。
+/// 与 `qrcode::ec::create_error_correction_code` 生成的纠错码字配套，`watermark` 也用它纠正读出的码字
+pub(crate) mod reed_solomon {
+    use super::qr_error;
+    use crate::Result;
+
+    /// α 的幂，长度为两个周期，乘法时不需要取模
+    const EXP: [u8; 512] = exp_table();
+    /// 非零元素的离散对数
+    const LOG: [u8; 256] = log_table();
+
+    const fn exp_table() -> [u8; 512] {
+        let mut table = [0; 512];
+        let mut value: u16 = 1;
+        let mut i = 0;
+        while i < 512 {
+            table[i] = value as u8;
+            value <<= 1;
+            if value & 0x100 != 0 {
+                value ^= 0x11d;
+            }
+            i += 1;
+        }
+        table
+    }
+
+    const fn log_table() -> [u8; 256] {
+        let exp = exp_table();
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 255 {
+            table[exp[i] as usize] = i as u8;
+            i += 1;
+        }
+        table
+    }
+
+    fn mul(a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+    }
+
+    /// `b` 不能为 0
+    fn div(a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        EXP[LOG[a as usize] as usize + 255 - LOG[b as usize] as usize]
+    }
+
+    /// α^n
+    fn pow(n: usize) -> u8 {
+        EXP[n % 255]
+    }
+
+    /// 多项式（低次项在前）在 `x` 处的值
+    fn eval(poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| mul(acc, x) ^ c)
+    }
+
+    /// 纠正 `block`（数据码字后跟 `ec_len` 个纠错码字，首个码字为最高次项）中的错误，
+    /// 最多纠正 `ec_len / 2` 个码字，超出时返回错误
+    pub(crate) fn correct(block: &mut [u8], ec_len: usize) -> Result<()> {
+        let syndromes = |block: &[u8]| -> Vec<u8> {
+            (0..ec_len)
+                .map(|j| block.iter().fold(0, |acc, &c| mul(acc, pow(j)) ^ c))
+                .collect()
+        };
+        let syndrome = syndromes(block);
+        if syndrome.iter().all(|&s| s == 0) {
+            return Ok(());
+        }
+        let uncorrectable = || qr_error("too many errors in the QR code to correct");
+
+        // Berlekamp-Massey：求错误位置多项式
+        let mut locator = vec![1u8];
+        let mut previous = vec![1u8];
+        let mut errors = 0;
+        let mut shift = 1;
+        let mut last = 1u8;
+        for k in 0..ec_len {
+            let discrepancy = (1..=errors).fold(syndrome[k], |d, i| {
+                d ^ mul(locator.get(i).copied().unwrap_or(0), syndrome[k - i])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let before = locator.clone();
+            let coefficient = div(discrepancy, last);
+            if locator.len() < previous.len() + shift {
+                locator.resize(previous.len() + shift, 0);
+            }
+            for (i, &c) in previous.iter().enumerate() {
+                locator[i + shift] ^= mul(coefficient, c);
+            }
+            if 2 * errors <= k {
+                errors = k + 1 - errors;
+                previous = before;
+                last = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+        }
+        if 2 * errors > ec_len {
+            return Err(uncorrectable());
+        }
+
+        // Chien 搜索：第 i 个码字的位置值为 α^(n-1-i)，其倒数是错误位置多项式的根
+        let n = block.len();
+        let positions: Vec<usize> = (0..n)
+            .filter(|&i| eval(&locator, div(1, pow(n - 1 - i))) == 0)
+            .collect();
+        if positions.len() != errors {
+            return Err(uncorrectable());
+        }
+
+        // Forney：错误值 = X·Ω(X⁻¹) / Λ'(X⁻¹)，其中 Ω = S·Λ mod x^ec_len
+        let mut evaluator = vec![0u8; ec_len];
+        for (i, &s) in syndrome.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate().take(ec_len.saturating_sub(i)) {
+                evaluator[i + j] ^= mul(s, l);
+            }
+        }
+        // 形式导数：特征为 2 时只剩奇数次项
+        let derivative: Vec<u8> = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(j, &c)| if j % 2 == 1 { c } else { 0 })
+            .collect();
+        for i in positions {
+            let x = pow(n - 1 - i);
+            let x_inverse = div(1, x);
+            let denominator = eval(&derivative, x_inverse);
+            if denominator == 0 {
+                return Err(uncorrectable());
+            }
+            block[i] ^= mul(x, div(eval(&evaluator, x_inverse), denominator));
+        }
+        if syndromes(block).iter().any(|&s| s != 0) {
+            return Err(uncorrectable());
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_correct() {
+            let data = b"pngme reed-solomon".to_vec();
+            let ec = qrcode::ec::create_error_correction_code(&data, 10);
+            let block = [data.clone(), ec].concat();
+
+            let mut corrupted = block.clone();
+            for (i, value) in [(0, 0xff), (7, 0x00), (20, 0x55), (27, 0x01), (12, 0xaa)] {
+                corrupted[i] ^= value | 1;
+            }
+            correct(&mut corrupted, 10).unwrap();
+            assert_eq!(corrupted, block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::testing_image;
+
+    /// 带渐变背景的 8 位 RGB 图片
+    fn rgb(width: u32, height: u32) -> Png {
+        let header = Ihdr::new(width, height, 8, ColorType::Rgb).unwrap();
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [(x * 255 / width) as u8, (y * 255 / height) as u8, 0x80]
+            })
+            .collect();
+        testing_image(header, &pixels)
+    }
+
+    /// 按最近邻把 8 位 RGB 图片缩放 `factor` 倍
+    fn scaled(png: &Png, factor: f64) -> Png {
+        let header = png.header().unwrap();
+        let pixels = png.pixels(&Limits::default()).unwrap();
+        let width = (f64::from(header.width) * factor) as u32;
+        let height = (f64::from(header.height) * factor) as u32;
+        let scaled: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let x = (f64::from(i % width) / factor) as usize;
+                let y = (f64::from(i / width) / factor) as usize;
+                let offset = (y * header.width as usize + x) * 3;
+                pixels[offset..offset + 3].to_vec()
+            })
+            .collect();
+        testing_image(
+            Ihdr::new(width, height, 8, ColorType::Rgb).unwrap(),
+            &scaled,
+        )
+    }
+
+    #[test]
+    fn test_render_and_read() {
+        let limits = Limits::default();
+        let mut png = rgb(200, 150);
+        render(
+            &mut png,
+            b"meet me at midnight",
+            &QrOptions::default(),
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        assert_eq!(read(&png, &limits).unwrap(), b"meet me at midnight");
+        // 只修改了右下角
+        let pixels = png.pixels(&limits).unwrap();
+        assert_eq!(&pixels[..3], &[0, 0, 0x80]);
+        let last = pixels.len() - 3;
+        assert_eq!(&pixels[last..], &[0xff, 0xff, 0xff]);
+
+        for corner in [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft] {
+            let mut png = rgb(300, 300);
+            let data: Vec<u8> = (0..=255).collect();
+            let options = QrOptions { corner, scale: 3 };
+            render(&mut png, &data, &options, &limits, &CancelToken::new()).unwrap();
+            assert_eq!(read(&png, &limits).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_read_screenshot() {
+        let limits = Limits::default();
+        let mut png = rgb(160, 160);
+        render(
+            &mut png,
+            b"scaled",
+            &QrOptions::default(),
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        assert_eq!(read(&scaled(&png, 2.0), &limits).unwrap(), b"scaled");
+        assert_eq!(read(&scaled(&png, 1.5), &limits).unwrap(), b"scaled");
+        assert_eq!(read(&scaled(&png, 0.75), &limits).unwrap(), b"scaled");
+    }
+
+    #[test]
+    fn test_color_types() {
+        let limits = Limits::default();
+        let options = QrOptions {
+            corner: Corner::TopLeft,
+            scale: 2,
+        };
+
+        let gray = Ihdr::new(80, 80, 16, ColorType::Grayscale).unwrap();
+        let mut png = testing_image(gray, &vec![0x80; 80 * 80 * 2]);
+        render(&mut png, b"gray", &options, &limits, &CancelToken::new()).unwrap();
+        assert_eq!(read(&png, &limits).unwrap(), b"gray");
+
+        // 调色板中没有黑白两色时添加到末尾
+        let indexed = Ihdr::new(80, 80, 4, ColorType::Indexed).unwrap();
+        let palette = Palette::new(vec![[0xff, 0, 0], [0, 0xff, 0]]).unwrap();
+        let mut png = testing_image(indexed, &vec![0x01; 40 * 80]);
+        png.set_palette(&palette).unwrap();
+        render(&mut png, b"indexed", &options, &limits, &CancelToken::new()).unwrap();
+        assert_eq!(png.palette().unwrap().unwrap().len(), 4);
+        assert_eq!(read(&png, &limits).unwrap(), b"indexed");
+
+        // 1 位的调色板已满，又没有黑色
+        let full = Ihdr::new(80, 80, 1, ColorType::Indexed).unwrap();
+        let palette = Palette::new(vec![[0xff, 0, 0], [0xff, 0xff, 0xff]]).unwrap();
+        let mut png = testing_image(full, &vec![0; 10 * 80]);
+        png.set_palette(&palette).unwrap();
+        assert!(matches!(
+            render(&mut png, b"full", &options, &limits, &CancelToken::new()),
+            Err(PngmeError::QrCode(_))
+        ));
+    }
+
+    #[test]
+    fn test_damaged_modules() {
+        let limits = Limits::default();
+        let options = QrOptions {
+            corner: Corner::TopLeft,
+            scale: 1,
+        };
+        let mut png = rgb(60, 60);
+        render(
+            &mut png,
+            b"still readable",
+            &options,
+            &limits,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        // 在数据区域（右下角附近）涂掉几个模块
+        let header = png.header().unwrap();
+        let mut pixels = png.pixels(&limits).unwrap();
+        for (x, y) in [(22, 22), (23, 22), (20, 24), (16, 20), (22, 14)] {
+            let offset = (y * header.width as usize + x) * 3;
+            for sample in &mut pixels[offset..offset + 3] {
+                *sample = 0xff - *sample;
+            }
+        }
+        let raw = interlace::filter(&header, &interlace::interlace(&header, &pixels));
+        png.set_image_data(&raw).unwrap();
+        assert_eq!(read(&png, &limits).unwrap(), b"still readable");
+    }
+
+    #[test]
+    fn test_errors() {
+        let limits = Limits::default();
+        let mut png = rgb(40, 40);
+        // 版本 1 加上留白需要 29 个模块
+        let options = QrOptions {
+            corner: Corner::TopLeft,
+            scale: 2,
+        };
+        assert!(matches!(
+            render(&mut png, b"x", &options, &limits, &CancelToken::new()),
+            Err(PngmeError::QrCode(_))
+        ));
+        assert!(render(
+            &mut png,
+            b"x",
+            &QrOptions {
+                scale: 0,
+                ..options
+            },
+            &limits,
+            &CancelToken::new()
+        )
+        .is_err());
+        assert!(render(
+            &mut png,
+            &[0; MAX_DATA_LEN + 1],
+            &options,
+            &limits,
+            &CancelToken::new()
+        )
+        .is_err());
+        assert!(matches!(read(&png, &limits), Err(PngmeError::QrCode(_))));
+
+        let mut png = rgb(60, 60);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let options = QrOptions {
+            corner: Corner::TopLeft,
+            scale: 1,
+        };
+        assert!(matches!(
+            render(&mut png, b"x", &options, &limits, &cancel),
+            Err(PngmeError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        // 数字 "01234567" 和字母数字 "AC-42"，版本 1
+        let mut bits = Bits::new(Version::Normal(1));
+        bits.push_numeric_data(b"01234567").unwrap();
+        bits.push_alphanumeric_data(b"AC-42").unwrap();
+        bits.push_terminator(EcLevel::M).unwrap();
+        let data = bits.into_bytes();
+        assert_eq!(
+            parse_segments(&data, Version::Normal(1)).unwrap(),
+            b"01234567AC-42"
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! # Reader
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块包含了 `ChunkReader` 的实现，用于从 `std::io::Read` 中逐个读取 chunk，
+//! 无需先把整个文件读入内存，以及 `inject`，边读边写地在 IEND 之前插入 chunk。
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::cancel::CancelToken;
+use crate::chunk::Chunk;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::progress::Progress;
+use crate::sniff;
+use crate::{PngmeError, Result};
+
+/// 从任意 `Read` 中逐个读取 chunk 的迭代器
+pub struct ChunkReader<R: Read> {
+    reader: R,
+    cancel: Option<CancelToken>,
+    progress: Option<Arc<dyn Progress>>,
+    limits: Limits,
+    /// 已经读取的 chunk 数量和字节数（含 PNG 签名）
+    chunks_read: usize,
+    bytes_read: u64,
+    done: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    /// 从 chunk 序列（不含 PNG 签名）创建读取器
+    pub fn new(reader: R) -> Self {
+        ChunkReader {
+            reader,
+            cancel: None,
+            progress: None,
+            limits: Limits::default(),
+            chunks_read: 0,
+            bytes_read: 0,
+            done: false,
+        }
+    }
+
+    /// 设置安全限制，默认为 `Limits::default()`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 设置取消标记，每读取一个 chunk 前都会检查一次
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// 设置进度回调，每读取一个 chunk 报告一次
+    pub fn with_progress(mut self, progress: Arc<dyn Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// 从完整的 PNG 数据流创建读取器，会先读取并校验 8 字节签名
+    pub fn from_png(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 8];
+        let filled = read_full(&mut reader, &mut header)?;
+        if filled < header.len() || header != Png::STANDARD_HEADER {
+            // 多读几个字节，以便识别魔数较长的格式
+            let mut sniffed = [0u8; sniff::SNIFF_LEN];
+            sniffed[..filled].copy_from_slice(&header[..filled]);
+            let extra = if filled == header.len() {
+                read_full(&mut reader, &mut sniffed[filled..])?
+            } else {
+                0
+            };
+            return Err(sniff::signature_error(&sniffed[..filled + extra]));
+        }
+        let mut chunk_reader = Self::new(reader);
+        chunk_reader.bytes_read = header.len() as u64;
+        Ok(chunk_reader)
+    }
+
+    /// 取回内部的读取器
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// 读取下一个 chunk；在 chunk 边界处遇到 EOF 时返回 `Ok(None)`
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        if let Some(cancel) = &self.cancel {
+            cancel.check()?;
+        }
+
+        let mut head = [0u8; 8];
+        let filled = read_full(&mut self.reader, &mut head)?;
+        if filled == 0 {
+            return Ok(None);
+        }
+        if filled < head.len() {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        let length = u32::from_be_bytes([head[0], head[1], head[2], head[3]]);
+        self.limits.check_chunk_size(length)?;
+        self.limits
+            .check_chunk_type([head[4], head[5], head[6], head[7]])?;
+        self.limits.check_chunk_count(self.chunks_read + 1)?;
+        self.bytes_read += length as u64 + 12;
+        self.limits.check_file_size(self.bytes_read)?;
+        let mut bytes = head.to_vec();
+        // 不按 length 预先分配，避免损坏的长度字段导致巨量内存分配
+        let expected = length as u64 + 4;
+        let read = (&mut self.reader).take(expected).read_to_end(&mut bytes)?;
+        if (read as u64) < expected {
+            return Err(PngmeError::TruncatedChunk);
+        }
+
+        self.chunks_read += 1;
+        let chunk = Chunk::try_from(bytes.as_slice())?;
+        if let Some(progress) = &self.progress {
+            progress.chunk(chunk.chunk_type(), length);
+        }
+        Ok(Some(chunk))
+    }
+}
+
+impl<R: Read> Iterator for ChunkReader<R> {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_chunk() {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// 从 `reader` 读取 PNG 并逐个 chunk 写入 `writer`，在 IEND 之前插入 `chunks`，
+/// 返回写入的字节数。内存中最多只有一个 chunk，适合在管道中处理大文件。
+/// 输入没有 IEND 时返回 `PngmeError::ChunkNotFound`，此时已经写出的数据不完整
+pub fn inject<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    chunks: &[Chunk],
+    limits: Limits,
+) -> Result<u64> {
+    writer.write_all(&Png::STANDARD_HEADER)?;
+    let mut written = Png::STANDARD_HEADER.len() as u64;
+    let mut injected = false;
+    for chunk in ChunkReader::from_png(reader)?.with_limits(limits) {
+        let chunk = chunk?;
+        if !injected && chunk.chunk_type().bytes() == *b"IEND" {
+            for new_chunk in chunks {
+                written += write_chunk(&mut writer, new_chunk)?;
+            }
+            injected = true;
+        }
+        written += write_chunk(&mut writer, &chunk)?;
+    }
+    if !injected {
+        return Err(PngmeError::ChunkNotFound("IEND".to_string()));
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+fn write_chunk<W: Write>(writer: &mut W, chunk: &Chunk) -> Result<u64> {
+    let bytes = chunk.as_bytes();
+    writer.write_all(&bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+/// 尽可能填满 `buf`，返回实际读取的字节数（只有遇到 EOF 时才会小于 `buf.len()`）
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::testing_chunk;
+
+    /// 中间的 chunk 没有数据
+    fn testing_chunks() -> Vec<Chunk> {
+        vec![
+            testing_chunk("FrSt", b"first"),
+            testing_chunk("miDl", b""),
+            testing_chunk("LASt", b"last"),
+        ]
+    }
+
+    fn testing_bytes() -> Vec<u8> {
+        testing_chunks()
+            .iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_all_chunks() {
+        let bytes = testing_bytes();
+        let chunks: Vec<Chunk> = ChunkReader::new(bytes.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks, testing_chunks());
+    }
+
+    #[test]
+    fn test_read_from_png() {
+        let png = Png::from_chunks(testing_chunks());
+        let bytes = png.as_bytes();
+        let chunks: Vec<Chunk> = ChunkReader::from_png(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks, testing_chunks());
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let bytes = testing_bytes();
+        assert!(ChunkReader::from_png(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_truncated_chunk() {
+        let mut bytes = testing_bytes();
+        bytes.truncate(bytes.len() - 2);
+        let results: Vec<Result<Chunk>> = ChunkReader::new(bytes.as_slice()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_stops_after_error() {
+        let mut bytes = testing_bytes();
+        bytes[4] = b'1';
+        let mut reader = ChunkReader::new(bytes.as_slice());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_reader_reports_chunks() {
+        let bytes = testing_bytes();
+        let recorder = Arc::new(crate::progress::tests::Recorder::default());
+        let count = ChunkReader::new(bytes.as_slice())
+            .with_progress(recorder.clone())
+            .count();
+        assert_eq!(recorder.chunks.lock().unwrap().len(), count);
+    }
+
+    #[test]
+    fn test_cancelled_reader() {
+        let bytes = testing_bytes();
+        let cancel = CancelToken::new();
+        let mut reader = ChunkReader::new(bytes.as_slice()).with_cancel(cancel.clone());
+        assert!(reader.next().unwrap().is_ok());
+
+        cancel.cancel();
+        assert!(matches!(reader.next(), Some(Err(PngmeError::Cancelled))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_huge_length_does_not_allocate() {
+        let bytes = [0xff, 0xff, 0xff, 0xff, b'r', b'u', b'S', b't', 1, 2, 3];
+        let mut reader = ChunkReader::new(&bytes[..]);
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        let bytes = testing_bytes();
+        let limits = Limits {
+            max_chunks: 2,
+            ..Limits::default()
+        };
+        let mut reader = ChunkReader::new(bytes.as_slice()).with_limits(limits);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(PngmeError::LimitExceeded(_)))
+        ));
+
+        let limits = Limits {
+            max_chunk_size: 4,
+            ..Limits::default()
+        };
+        let result: Result<Vec<Chunk>> = ChunkReader::new(bytes.as_slice())
+            .with_limits(limits)
+            .collect();
+        assert!(matches!(result, Err(PngmeError::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_inject_before_iend() {
+        let mut png = Png::try_from(&crate::png::tests::PNG_FILE[..]).unwrap();
+        let hidden = testing_chunk("ruSt", b"hidden");
+        let mut out = Vec::new();
+        let written = inject(
+            &crate::png::tests::PNG_FILE[..],
+            &mut out,
+            std::slice::from_ref(&hidden),
+            Limits::default(),
+        )
+        .unwrap();
+        png.append_chunk(hidden);
+        assert_eq!(out, png.as_bytes());
+        assert_eq!(written, out.len() as u64);
+    }
+
+    #[test]
+    fn test_inject_without_iend() {
+        let bytes = Png::from_chunks(testing_chunks()).as_bytes();
+        assert!(matches!(
+            inject(bytes.as_slice(), io::sink(), &[], Limits::default()),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! # Round trip
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了写入前的自检（`--verify-roundtrip`）：把编辑后的 PNG 序列化，
+//! 重新解析（校验 CRC）后再序列化一次，两次结果必须逐字节一致；
+//! 文件中所有带封装头部的信息（包括拆分的分片）都必须能被完整提取。
+//! 加密的信息没有口令无法解密，只检查分片能否拼接。
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::payload::{self, Payload};
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 检查 `png` 能否无损地写出再读回，返回序列化后的字节
+pub fn verify(png: &Png) -> Result<Vec<u8>> {
+    let bytes = png.as_bytes();
+    let reparsed = Png::try_from(bytes.as_slice())
+        .map_err(|e| failed(format!("the output cannot be parsed: {}", e)))?;
+    if reparsed.chunks() != png.chunks() {
+        return Err(failed(
+            "the chunks read back differ from the chunks written",
+        ));
+    }
+    let rewritten = reparsed.as_bytes();
+    if rewritten != bytes {
+        let offset = rewritten
+            .iter()
+            .zip(&bytes)
+            .position(|(a, b)| a != b)
+            .unwrap_or(rewritten.len().min(bytes.len()));
+        return Err(failed(format!(
+            "re-serialized output differs at byte {}",
+            offset
+        )));
+    }
+
+    // 分片按类型拼接，未拆分的封装信息逐个解码
+    let mut parts: BTreeMap<String, Vec<&[u8]>> = BTreeMap::new();
+    for chunk in reparsed.chunks() {
+        let data = chunk.data();
+        if payload::is_part(data) {
+            parts
+                .entry(chunk.chunk_type().to_string())
+                .or_default()
+                .push(data);
+        } else if payload::is_enveloped(data) {
+            extract(&chunk.chunk_type().to_string(), data)?;
+        }
+    }
+    for (chunk_type, parts) in parts {
+        let data = payload::join(&parts)
+            .map_err(|e| failed(format!("{} parts cannot be joined: {}", chunk_type, e)))?;
+        // 先封装后加密的信息拼接后没有封装头部
+        if payload::is_enveloped(&data) {
+            extract(&chunk_type, &data)?;
+        }
+    }
+    Ok(bytes)
+}
+
+fn extract(chunk_type: &str, data: &[u8]) -> Result<Payload> {
+    Payload::from_bytes(data)
+        .map_err(|e| failed(format!("{} payload cannot be extracted: {}", chunk_type, e)))
+}
+
+fn failed(message: impl Into<String>) -> PngmeError {
+    PngmeError::RoundTrip(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::{ColorType, Ihdr};
+    use crate::png::tests::{testing_chunk, testing_image, PNG_FILE};
+
+    fn testing_png() -> Png {
+        let header = Ihdr::new(4, 4, 8, ColorType::Grayscale).unwrap();
+        testing_image(header, &[0x80; 16])
+    }
+
+    #[test]
+    fn test_verify_round_trip() {
+        let mut png = testing_png();
+        assert_eq!(verify(&png).unwrap(), png.as_bytes());
+
+        let data = Payload::file("notes.txt", b"hello".repeat(20))
+            .to_bytes(true)
+            .unwrap();
+        for part in payload::split(&data, 32).unwrap() {
+            png.append_chunk(testing_chunk("ruSt", &part));
+        }
+        png.append_chunk(testing_chunk("ruSt", b"plain text"));
+        verify(&png).unwrap();
+    }
+
+    #[test]
+    fn test_broken_payload() {
+        let mut png = testing_png();
+        let mut data = Payload::new(b"hello".to_vec()).to_bytes(true).unwrap();
+        data.truncate(data.len() - 2);
+        png.append_chunk(testing_chunk("ruSt", &data));
+        assert!(matches!(verify(&png), Err(PngmeError::RoundTrip(_))));
+    }
+
+    #[test]
+    fn test_missing_part() {
+        let mut png = testing_png();
+        let parts = payload::split(&[7; 100], 32).unwrap();
+        for part in parts.into_iter().skip(1) {
+            png.append_chunk(testing_chunk("ruSt", &part));
+        }
+        assert!(matches!(verify(&png), Err(PngmeError::RoundTrip(_))));
+    }
+
+    #[test]
+    fn test_bad_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[60] ^= 0xff;
+        let png = Png::from_bytes_unchecked(&bytes).unwrap();
+        assert!(matches!(verify(&png), Err(PngmeError::RoundTrip(_))));
+    }
+}
@@ -0,0 +1,286 @@
+//! # Scan
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块遍历整个 PNG 文件，列出每个 chunk 的偏移、长度、CRC 状态和属性位。
+//! 解析时不校验 CRC，CRC 错误的 chunk 也会被列出并标记为 `bad`。
+//! 在 `pmPv` 中登记过的 chunk 会标出是由哪个版本的 pngme、哪个操作写入的。
+//! 文本 chunk 中不符合规范的字符集（例如写进 tEXt 的 UTF-8）和关键字会作为警告列出。
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::describe;
+use crate::ihdr::Ihdr;
+use crate::limits::Limits;
+use crate::png::Png;
+use crate::provenance::{self, Provenance};
+use crate::text;
+use crate::Result;
+
+/// 单个 chunk 的详细信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkInfo {
+    /// chunk 在文件中的字节偏移（长度字段的位置）
+    pub offset: u64,
+    pub chunk_type: String,
+    pub length: u32,
+    pub crc: u32,
+    pub crc_ok: bool,
+    pub critical: bool,
+    pub public: bool,
+    pub safe_to_copy: bool,
+    /// 规范中定义的 chunk 解码后的主要字段，见 `describe::describe`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 由 pngme 写入时，写入它的工具版本、操作和操作 id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_by: Option<String>,
+    /// 文本 chunk 中字符集或关键字的问题，见 `text::lint`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// 压缩包中单个文件的扫描结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileScan {
+    pub name: String,
+    pub chunks: Vec<ChunkInfo>,
+    /// 文件无法解析时的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FileScan {
+    /// 扫描一个文件，解析失败时记录错误而不是返回错误
+    pub fn new(name: &str, bytes: &[u8]) -> Self {
+        let (chunks, error) = match scan_bytes(bytes) {
+            Ok(chunks) => (chunks, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        FileScan {
+            name: name.to_string(),
+            chunks,
+            error,
+        }
+    }
+}
+
+/// 扫描 PNG 数据中的所有 chunk，只借用数据，不复制 chunk 的内容。使用默认的安全限制
+pub fn scan_bytes(bytes: &[u8]) -> Result<Vec<ChunkInfo>> {
+    scan_bytes_with_limits(bytes, &Limits::default())
+}
+
+/// 按 `limits` 扫描 PNG 数据：文件大小、每个 chunk 的长度、类型和 chunk 数量超限时返回 `PngmeError::LimitExceeded`
+pub fn scan_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Vec<ChunkInfo>> {
+    limits.check_file_size(bytes.len() as u64)?;
+    // 登记表无法解析时只是不做标记，不影响扫描
+    let provenance = Png::chunk_refs(bytes)?
+        .map_while(|chunk| chunk.ok())
+        .find(|chunk| chunk.chunk_type().to_string() == provenance::CHUNK_TYPE)
+        .and_then(|chunk| Provenance::parse(chunk.data()).ok())
+        .unwrap_or_default();
+    let header = Png::chunk_refs(bytes)?
+        .next()
+        .and_then(|chunk| chunk.ok())
+        .and_then(|chunk| Ihdr::try_from(chunk.data()).ok());
+    let mut offset = Png::STANDARD_HEADER.len() as u64;
+    Png::chunk_refs(bytes)?
+        .enumerate()
+        .map(|(index, chunk)| {
+            let chunk = chunk?;
+            let chunk_type = chunk.chunk_type();
+            limits.check_chunk_size(chunk.length())?;
+            limits.check_chunk_type(chunk_type.bytes())?;
+            limits.check_chunk_count(index + 1)?;
+            let info = ChunkInfo {
+                offset,
+                chunk_type: chunk_type.to_string(),
+                length: chunk.length(),
+                crc: chunk.crc(),
+                crc_ok: chunk.has_valid_crc(),
+                critical: chunk_type.is_critical(),
+                public: chunk_type.is_public(),
+                safe_to_copy: chunk_type.is_safe_to_copy(),
+                description: describe::describe(chunk_type, chunk.data(), header.as_ref()),
+                added_by: provenance
+                    .lookup(chunk_type, chunk.crc())
+                    .map(|entry| entry.to_string()),
+                warnings: text::lint(chunk_type, chunk.data(), limits),
+            };
+            offset += chunk.encoded_len() as u64;
+            Ok(info)
+        })
+        .collect()
+}
+
+/// 以表格形式显示扫描结果
+pub struct ScanTable<'a>(pub &'a [ChunkInfo]);
+
+impl fmt::Display for ScanTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        write!(
+            f,
+            "{:>10}  {:<4}  {:>10}  {:<8}  {:<6}  {:<8}  {:<6}  SAFE-TO-COPY  DETAILS",
+            "OFFSET", "TYPE", "LENGTH", "CRC", "STATUS", "CRITICAL", "PUBLIC"
+        )?;
+        for info in self.0 {
+            let mut line = format!(
+                "{:>10}  {:<4}  {:>10}  {:08X}  {:<6}  {:<8}  {:<6}  {:<12}",
+                info.offset,
+                info.chunk_type,
+                info.length,
+                info.crc,
+                if info.crc_ok { "ok" } else { "bad" },
+                yes_no(info.critical),
+                yes_no(info.public),
+                yes_no(info.safe_to_copy)
+            );
+            if let Some(description) = &info.description {
+                line += &format!("  {}", description);
+            }
+            if let Some(added_by) = &info.added_by {
+                line += &format!("  added by {}", added_by);
+            }
+            for warning in &info.warnings {
+                line += &format!("  warning: {}", warning);
+            }
+            // 没有说明时不留下 SAFE-TO-COPY 列的填充空格
+            write!(f, "\n{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_scan_offsets_and_properties() {
+        let infos = scan_bytes(&PNG_FILE).unwrap();
+        let summary: Vec<(u64, &str, u32)> = infos
+            .iter()
+            .map(|info| (info.offset, info.chunk_type.as_str(), info.length))
+            .collect();
+        assert_eq!(
+            summary,
+            [(8, "IHDR", 13), (33, "IDAT", 17), (62, "IEND", 0)]
+        );
+        assert!(infos.iter().all(|info| info.crc_ok && info.critical));
+        assert!(infos.iter().all(|info| info.public && !info.safe_to_copy));
+    }
+
+    #[test]
+    fn test_scan_reports_bad_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[60] ^= 0xff;
+        let infos = scan_bytes(&bytes).unwrap();
+        let status: Vec<bool> = infos.iter().map(|info| info.crc_ok).collect();
+        assert_eq!(status, [true, false, true]);
+    }
+
+    #[test]
+    fn test_scan_table() {
+        let infos = scan_bytes(&PNG_FILE).unwrap();
+        let table = ScanTable(&infos).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].trim_start().starts_with("OFFSET  TYPE"));
+        assert_eq!(
+            lines[1],
+            "         8  IHDR          13  FDD49A73  ok      yes       yes     no            2x2 8-bit RGB"
+        );
+        assert!(lines[2].ends_with("yes     no"));
+    }
+
+    #[test]
+    fn test_scan_marks_pngme_chunks() {
+        use crate::chunk::Chunk;
+        use crate::chunk_type::ChunkType;
+        use std::convert::TryFrom;
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let before = png.chunks().to_vec();
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()).unwrap(),
+        );
+        provenance::tag(&mut png, &before, "encode", "op-1").unwrap();
+
+        let infos = scan_bytes(&png.as_bytes()).unwrap();
+        let added: Vec<(&str, bool)> = infos
+            .iter()
+            .map(|info| (info.chunk_type.as_str(), info.added_by.is_some()))
+            .collect();
+        assert_eq!(
+            added,
+            [
+                ("IHDR", false),
+                ("IDAT", false),
+                ("ruSt", true),
+                ("pmPv", false),
+                ("IEND", false)
+            ]
+        );
+        let table = ScanTable(&infos).to_string();
+        assert!(table.lines().nth(3).unwrap().ends_with("encode (op-1)"));
+    }
+
+    #[test]
+    fn test_scan_reports_text_warnings() {
+        use crate::chunk::Chunk;
+        use crate::chunk_type::ChunkType;
+        use std::convert::TryFrom;
+
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(
+            Chunk::new(
+                ChunkType::from_str("tEXt").unwrap(),
+                "Title\0你好".as_bytes().to_vec(),
+            )
+            .unwrap(),
+        );
+        let infos = scan_bytes(&png.as_bytes()).unwrap();
+        assert!(infos[..2].iter().all(|info| info.warnings.is_empty()));
+        assert_eq!(infos[2].warnings.len(), 1);
+        let table = ScanTable(&infos).to_string();
+        assert!(table
+            .lines()
+            .nth(3)
+            .unwrap()
+            .contains("warning: text: Latin-1 text looks like UTF-8"));
+    }
+
+    #[test]
+    fn test_scan_limits() {
+        let limits = Limits {
+            max_chunks: 2,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            scan_bytes_with_limits(&PNG_FILE, &limits),
+            Err(crate::PngmeError::LimitExceeded(_))
+        ));
+        let limits = Limits {
+            max_file_size: 73,
+            ..Limits::default()
+        };
+        assert!(scan_bytes_with_limits(&PNG_FILE, &limits).is_err());
+        assert_eq!(
+            scan_bytes_with_limits(&PNG_FILE, &Limits::default()).unwrap(),
+            scan_bytes(&PNG_FILE).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scan_truncated_file() {
+        assert!(scan_bytes(&PNG_FILE[..40]).is_err());
+
+        let scan = FileScan::new("broken.png", &PNG_FILE[..40]);
+        assert!(scan.chunks.is_empty());
+        assert!(scan.error.is_some());
+    }
+}
@@ -0,0 +1,147 @@
+//! # Serialize
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块（`serde` feature）为 `ChunkType`、`Chunk` 和 `Png` 实现了 serde 序列化：
+//!
+//! ```json
+//! {"chunks": [{"chunk_type": "ruSt", "length": 2, "data": "aGk=", "crc": 1234}]}
+//! ```
+//!
+//! chunk 类型序列化为字符串，数据序列化为 base64。`length` 和 `crc` 只用于查看，
+//! 反序列化时会根据类型和数据重新计算，因此外部工具修改数据后无需自己更新它们。
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+
+impl Serialize for ChunkType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChunkType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        ChunkType::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct ChunkRef<'a> {
+    chunk_type: &'a ChunkType,
+    length: u32,
+    data: String,
+    crc: u32,
+}
+
+#[derive(Deserialize)]
+struct ChunkOwned {
+    chunk_type: ChunkType,
+    data: String,
+}
+
+impl Serialize for Chunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ChunkRef {
+            chunk_type: self.chunk_type(),
+            length: self.length(),
+            data: STANDARD.encode(self.data()),
+            crc: self.crc(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chunk {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let chunk = ChunkOwned::deserialize(deserializer)?;
+        let data = STANDARD
+            .decode(chunk.data)
+            .map_err(|e| de::Error::custom(format!("invalid base64 chunk data: {}", e)))?;
+        Chunk::new(chunk.chunk_type, data).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct PngRef<'a> {
+    chunks: &'a [Chunk],
+}
+
+#[derive(Deserialize)]
+struct PngOwned {
+    chunks: Vec<Chunk>,
+}
+
+impl Serialize for Png {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PngRef {
+            chunks: self.chunks(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Png {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Png::from_chunks(
+            PngOwned::deserialize(deserializer)?.chunks,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use std::convert::TryFrom;
+
+    fn testing_chunk() -> Chunk {
+        Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_chunk_to_json() {
+        let json = serde_json::to_string(&testing_chunk()).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"chunk_type":"ruSt","length":2,"data":"aGk=","crc":{}}}"#,
+                testing_chunk().crc()
+            )
+        );
+    }
+
+    #[test]
+    fn test_chunk_from_edited_json() {
+        // 修改数据后不需要更新 length 和 crc
+        let json = r#"{"chunk_type":"ruSt","length":2,"data":"aGVsbG8=","crc":0}"#;
+        let chunk: Chunk = serde_json::from_str(json).unwrap();
+        assert_eq!(chunk.data(), b"hello");
+        assert_eq!(chunk.length(), 5);
+        assert!(chunk.has_valid_crc());
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let bad_type = r#"{"chunk_type":"ru1t","data":""}"#;
+        assert!(serde_json::from_str::<Chunk>(bad_type).is_err());
+        let bad_data = r#"{"chunk_type":"ruSt","data":"not base64!"}"#;
+        assert!(serde_json::from_str::<Chunk>(bad_data).is_err());
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let json = serde_json::to_string(&png).unwrap();
+        let decoded: Png = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.as_bytes(), PNG_FILE.to_vec());
+    }
+}
@@ -0,0 +1,173 @@
+//! # Simple
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块为应用开发者提供一次调用即可完成的简化接口，不需要了解 chunk 模型：
+//!
+//! ```text
+//! let output = pngme::simple::hide_text(&png, "hello", Some("secret"))?;
+//! assert_eq!(pngme::simple::reveal_text(&output, Some("secret"))?, "hello");
+//! ```
+//!
+//! 所有函数都使用固定的默认值：信息写入 `DEFAULT_CHUNK_TYPE` 类型的 chunk，放在 IEND 之前；
+//! 压缩后更小时才压缩；超过 `payload::DEFAULT_CHUNK_SIZE` 的数据拆分到多个 chunk。
+//! 需要其他选项时使用 `Engine` 或 `Png`。
+
+use std::str;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::crypto;
+use crate::payload::{self, message_data, remove_message, Payload};
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// 写入信息使用的 chunk 类型
+pub const DEFAULT_CHUNK_TYPE: &str = "ruSt";
+
+/// `clean_metadata` 保留的辅助 chunk：影响颜色、透明度、像素比例和动画的 chunk
+pub const RENDERING_CHUNKS: [&str; 14] = [
+    "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP", "mDCV", "cLLI", "tRNS", "bKGD", "pHYs", "acTL",
+    "fcTL", "fdAT",
+];
+
+/// 在 `png` 中写入文本 `text`，`passphrase` 不为空时加密，返回新的 PNG 文件
+pub fn hide_text(png: &[u8], text: &str, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    hide(png, Payload::new(text.as_bytes().to_vec()), passphrase)
+}
+
+/// 读取 `hide_text` 写入的文本
+pub fn reveal_text(png: &[u8], passphrase: Option<&str>) -> Result<String> {
+    let payload = reveal(png, passphrase)?;
+    Ok(str::from_utf8(&payload.data)?.to_string())
+}
+
+/// 在 `png` 中写入文件 `name` 的内容 `data`，`passphrase` 不为空时加密，返回新的 PNG 文件
+pub fn hide_file(png: &[u8], name: &str, data: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>> {
+    hide(png, Payload::file(name, data.to_vec()), passphrase)
+}
+
+/// 读取 `hide_file` 写入的文件，返回文件名和内容。
+/// 文件名来自不可信的文件，已经过 `payload::safe_filename` 清理，可以直接用于输出目录
+pub fn reveal_file(png: &[u8], passphrase: Option<&str>) -> Result<(String, Vec<u8>)> {
+    let payload = reveal(png, passphrase)?;
+    let name = payload::safe_filename(
+        payload
+            .filename
+            .as_deref()
+            .unwrap_or(payload::DEFAULT_FILENAME),
+    );
+    Ok((name, payload.data))
+}
+
+/// 删除所有元数据（文本、时间、EXIF、写入的信息等），只保留关键 chunk 和 `RENDERING_CHUNKS`，
+/// 图片的显示效果不变
+pub fn clean_metadata(png: &[u8]) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png)?;
+    let keep = RENDERING_CHUNKS
+        .iter()
+        .map(|chunk_type| ChunkType::from_str(chunk_type))
+        .collect::<Result<Vec<_>>>()?;
+    png.strip_ancillary(&keep);
+    Ok(png.as_bytes())
+}
+
+/// 替换已有的信息后写入 `payload`
+fn hide(png: &[u8], payload: Payload, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png)?;
+    match remove_message(&mut png, DEFAULT_CHUNK_TYPE) {
+        Ok(_) | Err(PngmeError::ChunkNotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    let plain = payload.to_bytes(false)?;
+    let compressed = payload.to_bytes(true)?;
+    let mut data = if compressed.len() < plain.len() {
+        compressed
+    } else {
+        plain
+    };
+    if let Some(passphrase) = passphrase {
+        data = crypto::encrypt(&data, passphrase)?;
+    }
+
+    let chunk_type = ChunkType::from_str(DEFAULT_CHUNK_TYPE)?;
+    for part in payload::split(&data, payload::DEFAULT_CHUNK_SIZE)? {
+        png.append_chunk(Chunk::new(chunk_type, part)?);
+    }
+    Ok(png.as_bytes())
+}
+
+fn reveal(png: &[u8], passphrase: Option<&str>) -> Result<Payload> {
+    let png = Png::try_from(png)?;
+    let mut data = message_data(&png, DEFAULT_CHUNK_TYPE)?;
+    if let Some(passphrase) = passphrase {
+        data = crypto::decrypt(&data, passphrase)?;
+    }
+    Payload::from_bytes(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_hide_and_reveal_text() {
+        let output = hide_text(&PNG_FILE, "hello", None).unwrap();
+        assert_eq!(reveal_text(&output, None).unwrap(), "hello");
+
+        // 再次写入时替换旧的信息
+        let output = hide_text(&output, "hello again", Some("secret")).unwrap();
+        let png = Png::try_from(&output[..]).unwrap();
+        assert_eq!(png.chunks_by_type(DEFAULT_CHUNK_TYPE).len(), 1);
+        assert_eq!(reveal_text(&output, Some("secret")).unwrap(), "hello again");
+        assert!(reveal_text(&output, Some("wrong")).is_err());
+    }
+
+    #[test]
+    fn test_hide_and_reveal_file() {
+        let data = vec![b'a'; 4096];
+        let output = hide_file(&PNG_FILE, "../notes.txt", &data, None).unwrap();
+        // 重复的数据压缩后更小
+        assert!(output.len() < PNG_FILE.len() + data.len());
+        let (name, revealed) = reveal_file(&output, None).unwrap();
+        assert_eq!(name, "notes.txt");
+        assert_eq!(revealed, data);
+
+        let output = hide_text(&PNG_FILE, "hello", None).unwrap();
+        assert_eq!(
+            reveal_file(&output, None).unwrap(),
+            (payload::DEFAULT_FILENAME.to_string(), b"hello".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_clean_metadata() {
+        let output = hide_text(&PNG_FILE, "hello", None).unwrap();
+        let mut png = Png::try_from(&output[..]).unwrap();
+        png.set_text("Author", "someone").unwrap();
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 0xb1, 0x8f]).unwrap(),
+        );
+
+        let cleaned = clean_metadata(&png.as_bytes()).unwrap();
+        let cleaned = Png::try_from(&cleaned[..]).unwrap();
+        assert!(cleaned.chunk_by_type("gAMA").is_some());
+        assert!(cleaned.chunk_by_type(DEFAULT_CHUNK_TYPE).is_none());
+        assert!(cleaned.chunk_by_type("tEXt").is_none());
+        assert!(matches!(
+            reveal_text(&cleaned.as_bytes(), None),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(hide_text(b"not a png", "hello", None).is_err());
+        assert!(matches!(
+            reveal_text(&PNG_FILE, None),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+//! # Sniff
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块按文件开头的魔数识别常见的图片格式。输入不是 PNG 时，解析函数返回
+//! `PngmeError::NotPng` 并说明实际的格式，而不只是“签名错误”。
+//!
+//! 启用 `convert` feature 时，`to_png` 通过 `image` crate 把 JPEG、GIF、BMP 转换为 PNG。
+//! 转换只保留像素：EXIF、颜色配置等元数据会丢失，GIF 只保留第一帧。
+
+use std::fmt;
+
+use crate::PngmeError;
+
+/// 识别格式最多需要的字节数
+pub const SNIFF_LEN: usize = 12;
+
+/// 按魔数识别出的图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    WebP,
+    Tiff,
+    Avif,
+    Heic,
+    Qoi,
+}
+
+impl ImageFormat {
+    /// 格式的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Gif => "GIF",
+            ImageFormat::Bmp => "BMP",
+            ImageFormat::WebP => "WebP",
+            ImageFormat::Tiff => "TIFF",
+            ImageFormat::Avif => "AVIF",
+            ImageFormat::Heic => "HEIC",
+            ImageFormat::Qoi => "QOI",
+        }
+    }
+
+    /// `to_png` 能否转换该格式（与是否启用 `convert` feature 无关）
+    pub fn is_convertible(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Bmp
+        )
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// 按开头的字节识别格式，`bytes` 至少应包含 `SNIFF_LEN` 个字节（不足时只能识别魔数较短的格式）
+pub fn sniff(bytes: &[u8]) -> Option<ImageFormat> {
+    let format = match bytes {
+        [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => ImageFormat::Png,
+        [0xff, 0xd8, 0xff, ..] => ImageFormat::Jpeg,
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => ImageFormat::Gif,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => ImageFormat::WebP,
+        [b'I', b'I', 0x2a, 0x00, ..] | [b'M', b'M', 0x00, 0x2a, ..] => ImageFormat::Tiff,
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b's' | b'f', ..] => {
+            ImageFormat::Avif
+        }
+        [_, _, _, _, b'f', b't', b'y', b'p', b'h', b'e', b'i' | b'v', b'c' | b'x', ..] => {
+            ImageFormat::Heic
+        }
+        [b'q', b'o', b'i', b'f', ..] => ImageFormat::Qoi,
+        // BMP 的魔数只有两个字节，再检查保留字段为 0，减少误判
+        [b'B', b'M', _, _, _, _, 0, 0, 0, 0, ..] => ImageFormat::Bmp,
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// 签名不是 PNG 时返回的错误：能识别出其他格式时为 `NotPng`，否则为 `InvalidSignature`
+pub fn signature_error(header: &[u8]) -> PngmeError {
+    match sniff(header) {
+        Some(format) if format != ImageFormat::Png => PngmeError::NotPng(format),
+        _ => PngmeError::InvalidSignature,
+    }
+}
+
+/// 把 JPEG、GIF 或 BMP 图片转换为 PNG，输入已经是 PNG 时原样返回
+#[cfg(feature = "convert")]
+pub fn to_png(bytes: &[u8]) -> crate::Result<Vec<u8>> {
+    let format = match sniff(bytes) {
+        Some(ImageFormat::Png) => return Ok(bytes.to_vec()),
+        Some(format) if format.is_convertible() => format,
+        Some(format) => return Err(PngmeError::NotPng(format)),
+        None => return Err(PngmeError::InvalidSignature),
+    };
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| PngmeError::Convert(format!("cannot decode the {} image: {}", format, e)))?;
+    let mut output = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, image::ImageFormat::Png)
+        .map_err(|e| PngmeError::Convert(e.to_string()))?;
+    Ok(output.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(&PNG_FILE), Some(ImageFormat::Png));
+        assert_eq!(
+            sniff(&[0xff, 0xd8, 0xff, 0xe0, 0, 0x10]),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(sniff(b"GIF89a\x01\x00"), Some(ImageFormat::Gif));
+        assert_eq!(
+            sniff(b"BM\x3a\x00\x00\x00\x00\x00\x00\x00\x36\x00"),
+            Some(ImageFormat::Bmp)
+        );
+        assert_eq!(
+            sniff(b"RIFF\x24\x00\x00\x00WEBPVP8 "),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(sniff(b"II*\x00\x08\x00\x00\x00"), Some(ImageFormat::Tiff));
+        assert_eq!(sniff(b"\x00\x00\x00\x1cftypavif"), Some(ImageFormat::Avif));
+        assert_eq!(sniff(b"\x00\x00\x00\x18ftypheic"), Some(ImageFormat::Heic));
+        assert_eq!(sniff(b"qoif\x00\x00\x00\x01"), Some(ImageFormat::Qoi));
+
+        assert_eq!(sniff(b"BMW is not a bitmap"), None);
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WAVEfmt "), None);
+        assert_eq!(sniff(b"not a png"), None);
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[test]
+    fn test_signature_error() {
+        assert!(matches!(
+            signature_error(&[0xff, 0xd8, 0xff, 0xdb]),
+            PngmeError::NotPng(ImageFormat::Jpeg)
+        ));
+        assert!(matches!(
+            signature_error(b"not a png"),
+            PngmeError::InvalidSignature
+        ));
+        assert_eq!(
+            PngmeError::NotPng(ImageFormat::Gif).to_string(),
+            "Expected a PNG file, found GIF image data"
+        );
+    }
+
+    #[cfg(feature = "convert")]
+    #[test]
+    fn test_to_png() {
+        use crate::png::Png;
+
+        let mut bmp = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(3, 2, image::Rgb([255, 0, 0]))
+            .write_to(&mut bmp, image::ImageFormat::Bmp)
+            .unwrap();
+        let bmp = bmp.into_inner();
+        assert_eq!(sniff(&bmp), Some(ImageFormat::Bmp));
+
+        let png = Png::try_from(&to_png(&bmp).unwrap()[..]).unwrap();
+        let ihdr = png.header().unwrap();
+        assert_eq!((ihdr.width, ihdr.height), (3, 2));
+        assert_eq!(to_png(&PNG_FILE).unwrap(), PNG_FILE.to_vec());
+        assert!(matches!(
+            to_png(b"RIFF\x24\x00\x00\x00WEBPVP8 "),
+            Err(PngmeError::NotPng(ImageFormat::WebP))
+        ));
+        assert!(matches!(
+            to_png(&[0xff, 0xd8, 0xff, 0xe0, 0, 0]),
+            Err(PngmeError::Convert(_))
+        ));
+    }
+}
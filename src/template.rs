@@ -0,0 +1,145 @@
+//! # Template
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了文本 chunk 使用的简单模板：`{{name}}` 会被替换为变量的值，
+//! 花括号内两侧的空格会被忽略。变量不存在或花括号没有闭合时返回错误，
+//! 避免把未替换的模板写进图片。
+//!
+//! 批处理的输出文件名模板使用单层花括号 `{name}`，见 `render_with`。
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{PngmeError, Result};
+
+/// 用 `lookup` 查找变量，替换模板中所有的 `{{name}}`
+pub fn render(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    render_with(template, "{{", "}}", lookup)
+}
+
+/// 与 `render` 相同，但变量的起止符号为 `open` 和 `close`
+pub fn render_with(
+    template: &str,
+    open: &str,
+    close: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(open) {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + open.len()..];
+        let end = after
+            .find(close)
+            .ok_or_else(|| invalid(format!("unclosed {} in {:?}", open, template)))?;
+        let name = after[..end].trim();
+        if name.is_empty() {
+            return Err(invalid(format!("empty variable name in {:?}", template)));
+        }
+        let value = lookup(name).ok_or_else(|| invalid(format!("undefined variable {}", name)))?;
+        output.push_str(&value);
+        rest = &after[end + close.len()..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// 解析 `name=value` 形式的赋值
+pub fn parse_assignment(assignment: &str) -> Result<(String, String)> {
+    match assignment.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(invalid(format!(
+            "expected NAME=VALUE, found {:?}",
+            assignment
+        ))),
+    }
+}
+
+/// 从 JSON 对象读取变量，值可以是字符串、数字或布尔值
+pub fn variables_from_json(json: &str) -> Result<BTreeMap<String, String>> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| invalid(format!("invalid variables file: {}", e)))?;
+    let Value::Object(object) = value else {
+        return Err(invalid(
+            "variables file must contain a JSON object".to_string(),
+        ));
+    };
+    object
+        .into_iter()
+        .map(|(name, value)| match value {
+            Value::String(s) => Ok((name, s)),
+            Value::Number(_) | Value::Bool(_) => Ok((name, value.to_string())),
+            _ => Err(invalid(format!(
+                "variable {} must be a string, number or boolean",
+                name
+            ))),
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> PngmeError {
+    PngmeError::Template(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "project" => Some("pngme".to_string()),
+            "build_id" => Some("42".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("{{project}} build {{ build_id }}", lookup).unwrap(),
+            "pngme build 42"
+        );
+        assert_eq!(render("no variables", lookup).unwrap(), "no variables");
+        assert_eq!(render("{{project}}}", lookup).unwrap(), "pngme}");
+        assert_eq!(
+            render_with("{project}-{build_id}.png", "{", "}", lookup).unwrap(),
+            "pngme-42.png"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(matches!(
+            render("{{missing}}", lookup),
+            Err(PngmeError::Template(_))
+        ));
+        assert!(render("{{project", lookup).is_err());
+        assert!(render("{{ }}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("Title={{project}} = x").unwrap(),
+            ("Title".to_string(), "{{project}} = x".to_string())
+        );
+        assert_eq!(parse_assignment("a=").unwrap().1, "");
+        assert!(parse_assignment("no equals").is_err());
+        assert!(parse_assignment("=value").is_err());
+    }
+
+    #[test]
+    fn test_variables_from_json() {
+        let vars = variables_from_json(r#"{"project": "pngme", "build": 7, "ok": true}"#).unwrap();
+        assert_eq!(vars["project"], "pngme");
+        assert_eq!(vars["build"], "7");
+        assert_eq!(vars["ok"], "true");
+        assert!(variables_from_json(r#"{"list": [1]}"#).is_err());
+        assert!(variables_from_json("[]").is_err());
+        assert!(variables_from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,692 @@
+//! # Text
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 PNG 规范中的三种标准文本 chunk：
+//!
+//! ```text
+//! tEXt: | 关键字 | 0 | 文本 (Latin-1) |
+//! zTXt: | 关键字 | 0 | 压缩方法 (0) | zlib 压缩的文本 (Latin-1) |
+//! iTXt: | 关键字 | 0 | 压缩标志 | 压缩方法 (0) | 语言标签 | 0 | 翻译后的关键字 (UTF-8) | 0 | 文本 (UTF-8) |
+//! ```
+//!
+//! 关键字为 1 到 79 字节的可打印 Latin-1 字符，首尾不能是空格，也不能包含连续的空格。
+//! 字符集的转换见 `charset` 模块：解析默认使用严格模式，`from_chunk_lossy` 用替代字符
+//! 代替无效的 UTF-8，`new_lossy` 把无法用 Latin-1 表示的字符替换为 `?`；
+//! `lint` 列出文本 chunk 中规范不推荐的内容。
+
+use std::convert::TryFrom;
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::charset::{self, latin1_decode, Charset, Mode};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::limits::Limits;
+use crate::preview;
+use crate::{Error, PngmeError, Result};
+
+/// 关键字的最大长度
+pub const MAX_KEYWORD_LEN: usize = 79;
+
+/// 未压缩的 Latin-1 文本（tEXt）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    pub keyword: String,
+    pub text: String,
+}
+
+/// zlib 压缩的 Latin-1 文本（zTXt）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedTextChunk {
+    pub keyword: String,
+    pub text: String,
+}
+
+/// 国际化的 UTF-8 文本（iTXt），文本可以选择压缩
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternationalTextChunk {
+    pub keyword: String,
+    pub compressed: bool,
+    /// RFC 3066 语言标签，例如 `zh-cn`，可以为空
+    pub language_tag: String,
+    /// 翻译成 `language_tag` 语言的关键字，可以为空
+    pub translated_keyword: String,
+    pub text: String,
+}
+
+impl TextChunk {
+    pub const CHUNK_TYPE: &'static str = "tEXt";
+
+    /// 创建 tEXt，关键字和文本都必须能用 Latin-1 表示
+    pub fn new(keyword: impl Into<String>, text: impl Into<String>) -> Result<Self> {
+        let text_chunk = TextChunk {
+            keyword: keyword.into(),
+            text: text.into(),
+        };
+        check_keyword(&text_chunk.keyword)?;
+        latin1_encode(&text_chunk.text)?;
+        Ok(text_chunk)
+    }
+
+    /// 创建 tEXt，文本中无法用 Latin-1 表示的字符和 0 字节替换为 `?`
+    pub fn new_lossy(keyword: impl Into<String>, text: &str) -> Result<Self> {
+        TextChunk::new(keyword, latin1_lossy(text))
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = keyword_bytes(&self.keyword)?;
+        data.extend(latin1_encode(&self.text)?);
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for TextChunk {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let (keyword, rest) = split_keyword(chunk, Self::CHUNK_TYPE)?;
+        Ok(TextChunk {
+            keyword,
+            text: latin1_decode(rest),
+        })
+    }
+}
+
+impl CompressedTextChunk {
+    pub const CHUNK_TYPE: &'static str = "zTXt";
+
+    /// 创建 zTXt，关键字和文本都必须能用 Latin-1 表示
+    pub fn new(keyword: impl Into<String>, text: impl Into<String>) -> Result<Self> {
+        let text_chunk = CompressedTextChunk {
+            keyword: keyword.into(),
+            text: text.into(),
+        };
+        check_keyword(&text_chunk.keyword)?;
+        latin1_encode(&text_chunk.text)?;
+        Ok(text_chunk)
+    }
+
+    /// 创建 zTXt，文本中无法用 Latin-1 表示的字符和 0 字节替换为 `?`
+    pub fn new_lossy(keyword: impl Into<String>, text: &str) -> Result<Self> {
+        CompressedTextChunk::new(keyword, latin1_lossy(text))
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let mut data = keyword_bytes(&self.keyword)?;
+        data.push(0);
+        data.extend(deflate(&latin1_encode(&self.text)?)?);
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+
+    /// 与 `TryFrom<&Chunk>` 相同，但按指定的安全限制解压
+    pub fn from_chunk_with_limits(chunk: &Chunk, limits: &Limits) -> Result<Self> {
+        let (keyword, rest) = split_keyword(chunk, Self::CHUNK_TYPE)?;
+        let (&method, compressed) = rest
+            .split_first()
+            .ok_or_else(|| invalid("missing compression method"))?;
+        check_method(method)?;
+        Ok(CompressedTextChunk {
+            keyword,
+            text: latin1_decode(&inflate(compressed, limits)?),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for CompressedTextChunk {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        CompressedTextChunk::from_chunk_with_limits(chunk, &Limits::default())
+    }
+}
+
+impl InternationalTextChunk {
+    pub const CHUNK_TYPE: &'static str = "iTXt";
+
+    /// 创建不压缩、没有语言信息的 iTXt
+    pub fn new(keyword: impl Into<String>, text: impl Into<String>) -> Result<Self> {
+        let text_chunk = InternationalTextChunk {
+            keyword: keyword.into(),
+            compressed: false,
+            language_tag: String::new(),
+            translated_keyword: String::new(),
+            text: text.into(),
+        };
+        check_keyword(&text_chunk.keyword)?;
+        Ok(text_chunk)
+    }
+
+    /// 设置语言标签和翻译后的关键字
+    pub fn with_language(
+        mut self,
+        language_tag: impl Into<String>,
+        translated_keyword: impl Into<String>,
+    ) -> Self {
+        self.language_tag = language_tag.into();
+        self.translated_keyword = translated_keyword.into();
+        self
+    }
+
+    /// 设置是否压缩文本
+    pub fn with_compression(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// 编码为 chunk
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        if !self.language_tag.is_ascii() || self.language_tag.contains('\0') {
+            return Err(invalid("language tag must be ASCII without null bytes"));
+        }
+        if self.translated_keyword.contains('\0') {
+            return Err(invalid("translated keyword must not contain null bytes"));
+        }
+
+        let mut data = keyword_bytes(&self.keyword)?;
+        data.push(self.compressed as u8);
+        data.push(0);
+        data.extend_from_slice(self.language_tag.as_bytes());
+        data.push(0);
+        data.extend_from_slice(self.translated_keyword.as_bytes());
+        data.push(0);
+        if self.compressed {
+            data.extend(deflate(self.text.as_bytes())?);
+        } else {
+            data.extend_from_slice(self.text.as_bytes());
+        }
+        new_chunk(Self::CHUNK_TYPE, data)
+    }
+
+    /// 与 `TryFrom<&Chunk>` 相同，但按指定的安全限制解压
+    pub fn from_chunk_with_limits(chunk: &Chunk, limits: &Limits) -> Result<Self> {
+        InternationalTextChunk::parse(chunk, limits, Mode::Strict)
+    }
+
+    /// 解析 iTXt，翻译后的关键字和文本中无效的 UTF-8 替换为 U+FFFD 而不是报错
+    pub fn from_chunk_lossy(chunk: &Chunk, limits: &Limits) -> Result<Self> {
+        InternationalTextChunk::parse(chunk, limits, Mode::Lossy)
+    }
+
+    fn parse(chunk: &Chunk, limits: &Limits, mode: Mode) -> Result<Self> {
+        let (keyword, rest) = split_keyword(chunk, Self::CHUNK_TYPE)?;
+        let (flag, method, rest) = match rest {
+            [flag, method, rest @ ..] => (*flag, *method, rest),
+            _ => return Err(invalid("missing compression flag or method")),
+        };
+        let compressed = match flag {
+            0 => false,
+            1 => true,
+            _ => return Err(invalid("invalid compression flag")),
+        };
+        check_method(method)?;
+
+        let (language_tag, rest) = split_null(rest, "language tag")?;
+        let (translated_keyword, text) = split_null(rest, "translated keyword")?;
+        let text = if compressed {
+            inflate(text, limits)?
+        } else {
+            text.to_vec()
+        };
+
+        Ok(InternationalTextChunk {
+            keyword,
+            compressed,
+            language_tag: charset::utf8_decode(language_tag, Mode::Strict)?,
+            translated_keyword: charset::utf8_decode(translated_keyword, mode)?,
+            text: charset::utf8_decode(&text, mode)?,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for InternationalTextChunk {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        InternationalTextChunk::from_chunk_with_limits(chunk, &Limits::default())
+    }
+}
+
+/// 为文本选择合适的 chunk：能用 Latin-1 表示时使用 tEXt，否则使用 iTXt
+pub fn text_chunk(keyword: &str, text: &str) -> Result<Chunk> {
+    match TextChunk::new(keyword, text) {
+        Ok(text_chunk) => text_chunk.to_chunk(),
+        Err(_) => InternationalTextChunk::new(keyword, text)?.to_chunk(),
+    }
+}
+
+/// 读取 tEXt、zTXt 或 iTXt 的关键字，其他 chunk 返回 `None`
+pub fn keyword(chunk: &Chunk) -> Option<String> {
+    let chunk_type = chunk.chunk_type().to_string();
+    if ![
+        TextChunk::CHUNK_TYPE,
+        CompressedTextChunk::CHUNK_TYPE,
+        InternationalTextChunk::CHUNK_TYPE,
+    ]
+    .contains(&chunk_type.as_str())
+    {
+        return None;
+    }
+    split_null(chunk.data(), "keyword")
+        .ok()
+        .map(|(keyword, _)| latin1_decode(keyword))
+}
+
+/// 解析 tEXt、zTXt 或 iTXt 的 `(关键字, 文本)`，iTXt 中无效的 UTF-8 被替换，
+/// 其他 chunk 或无法解析时返回 `None`
+pub fn entry(chunk: &Chunk) -> Option<(String, String)> {
+    match &chunk.chunk_type().bytes() {
+        b"tEXt" => TextChunk::try_from(chunk).map(|t| (t.keyword, t.text)),
+        b"zTXt" => CompressedTextChunk::try_from(chunk).map(|t| (t.keyword, t.text)),
+        b"iTXt" => InternationalTextChunk::from_chunk_lossy(chunk, &Limits::default())
+            .map(|t| (t.keyword, t.text)),
+        _ => return None,
+    }
+    .ok()
+}
+
+/// 文本 chunk 的单行预览 `关键字: 文本`，控制字符被转义，文本最多 `width` 列。
+/// 无效的 UTF-8 被替换，其他 chunk 或无法解析时返回 `None`
+pub fn preview(chunk: &Chunk, width: usize) -> Option<String> {
+    let (keyword, text) = entry(chunk)?;
+    Some(format!(
+        "{}: {}",
+        preview::escape(&keyword),
+        preview::preview(&text, width)
+    ))
+}
+
+/// 检查文本 chunk 的字符集和关键字，返回 `字段: 问题` 形式的警告，其他 chunk 返回空列表。
+/// 结构无法解析时只返回解析错误
+pub fn lint(chunk_type: &ChunkType, data: &[u8], limits: &Limits) -> Vec<String> {
+    let Some(charset) = Charset::of(chunk_type) else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    match lint_fields(chunk_type, data, limits) {
+        Ok((keyword, fields)) => {
+            if let Err(e) = check_keyword(&latin1_decode(keyword)) {
+                warnings.push(format!("keyword: {}", e));
+            }
+            for (field, bytes) in fields {
+                warnings.extend(
+                    charset
+                        .lint(&bytes)
+                        .into_iter()
+                        .map(|warning| format!("{}: {}", field, warning)),
+                );
+            }
+        }
+        Err(e) => warnings.push(e.to_string()),
+    }
+    warnings
+}
+
+/// 字段名和字段内容
+type LintField = (&'static str, Vec<u8>);
+
+/// 拆分出关键字和需要按字符集检查的字段（压缩的文本先解压）
+fn lint_fields<'a>(
+    chunk_type: &ChunkType,
+    data: &'a [u8],
+    limits: &Limits,
+) -> Result<(&'a [u8], Vec<LintField>)> {
+    let (keyword, rest) = split_null(data, "keyword")?;
+    let fields = match &chunk_type.bytes() {
+        b"zTXt" => {
+            let (&method, compressed) = rest
+                .split_first()
+                .ok_or_else(|| invalid("missing compression method"))?;
+            check_method(method)?;
+            vec![("text", inflate(compressed, limits)?)]
+        }
+        b"iTXt" => {
+            let (flag, rest) = match rest {
+                [flag, method, rest @ ..] => {
+                    check_method(*method)?;
+                    (*flag, rest)
+                }
+                _ => return Err(invalid("missing compression flag or method")),
+            };
+            let (_, rest) = split_null(rest, "language tag")?;
+            let (translated_keyword, text) = split_null(rest, "translated keyword")?;
+            let text = match flag {
+                0 => text.to_vec(),
+                _ => inflate(text, limits)?,
+            };
+            vec![
+                ("translated keyword", translated_keyword.to_vec()),
+                ("text", text),
+            ]
+        }
+        _ => vec![("text", rest.to_vec())],
+    };
+    Ok((keyword, fields))
+}
+
+/// 检查关键字是否符合规范
+fn check_keyword(keyword: &str) -> Result<()> {
+    let bytes = latin1_encode(keyword)?;
+    if bytes.is_empty() || bytes.len() > MAX_KEYWORD_LEN {
+        return Err(invalid("keyword must be 1 to 79 bytes long"));
+    }
+    if !bytes.iter().all(|&b| (32..=126).contains(&b) || b >= 161) {
+        return Err(invalid("keyword contains non-printable characters"));
+    }
+    if keyword.starts_with(' ') || keyword.ends_with(' ') || keyword.contains("  ") {
+        return Err(invalid(
+            "keyword must not have leading, trailing or consecutive spaces",
+        ));
+    }
+    Ok(())
+}
+
+/// 关键字加上结尾的 0
+fn keyword_bytes(keyword: &str) -> Result<Vec<u8>> {
+    check_keyword(keyword)?;
+    let mut bytes = latin1_encode(keyword)?;
+    bytes.push(0);
+    Ok(bytes)
+}
+
+/// 检查 chunk 类型，并拆分出关键字和剩余数据
+fn split_keyword<'a>(chunk: &'a Chunk, chunk_type: &str) -> Result<(String, &'a [u8])> {
+    if chunk.chunk_type().to_string() != chunk_type {
+        return Err(invalid(&format!(
+            "expected a {} chunk, found {}",
+            chunk_type,
+            chunk.chunk_type()
+        )));
+    }
+    let (keyword, rest) = split_null(chunk.data(), "keyword")?;
+    let keyword = latin1_decode(keyword);
+    check_keyword(&keyword)?;
+    Ok((keyword, rest))
+}
+
+/// 在第一个 0 处拆分
+fn split_null<'a>(bytes: &'a [u8], field: &str) -> Result<(&'a [u8], &'a [u8])> {
+    let index = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| invalid(&format!("{} is not null-terminated", field)))?;
+    Ok((&bytes[..index], &bytes[index + 1..]))
+}
+
+fn check_method(method: u8) -> Result<()> {
+    if method != 0 {
+        return Err(invalid(&format!("unknown compression method {}", method)));
+    }
+    Ok(())
+}
+
+fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Result<Chunk> {
+    let chunk_type = ChunkType::from_str(chunk_type).expect("standard chunk types are valid");
+    Chunk::new(chunk_type, data)
+}
+
+/// 编码为 Latin-1，文本 chunk 中不能有 0 字节
+fn latin1_encode(s: &str) -> Result<Vec<u8>> {
+    let bytes = charset::latin1_encode(s, Mode::Strict)
+        .map_err(|_| invalid("text must be Latin-1 without null bytes"))?;
+    if bytes.contains(&0) {
+        return Err(invalid("text must be Latin-1 without null bytes"));
+    }
+    Ok(bytes)
+}
+
+/// 把无法写入 tEXt/zTXt 的字符替换为 `?`
+fn latin1_lossy(s: &str) -> String {
+    s.chars()
+        .map(|c| match u32::from(c) {
+            1..=0xff => c,
+            _ => '?',
+        })
+        .collect()
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn inflate(data: &[u8], limits: &Limits) -> Result<Vec<u8>> {
+    limits.inflate(data).map_err(|e| match e {
+        PngmeError::Io(e) => invalid(&format!("failed to decompress text: {}", e)),
+        e => e,
+    })
+}
+
+fn invalid(message: &str) -> PngmeError {
+    PngmeError::InvalidTextChunk(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_chunk_layout() {
+        let chunk = TextChunk::new("Title", "Café").unwrap().to_chunk().unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "tEXt");
+        assert_eq!(chunk.data(), b"Title\0Caf\xe9");
+    }
+
+    #[test]
+    fn test_text_chunk_round_trip() {
+        let text = TextChunk::new("Author", "Norfloxaciner").unwrap();
+        let chunk = text.to_chunk().unwrap();
+        assert_eq!(TextChunk::try_from(&chunk).unwrap(), text);
+    }
+
+    #[test]
+    fn test_compressed_text_chunk_round_trip() {
+        let text = CompressedTextChunk::new("Comment", "a".repeat(1000)).unwrap();
+        let chunk = text.to_chunk().unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "zTXt");
+        assert!(chunk.data().len() < 100);
+        assert_eq!(&chunk.data()[..9], b"Comment\0\0");
+        assert_eq!(CompressedTextChunk::try_from(&chunk).unwrap(), text);
+    }
+
+    #[test]
+    fn test_international_text_chunk_round_trip() {
+        for compressed in [false, true] {
+            let text = InternationalTextChunk::new("Title", "你好，世界")
+                .unwrap()
+                .with_language("zh-cn", "标题")
+                .with_compression(compressed);
+            let chunk = text.to_chunk().unwrap();
+            assert_eq!(&chunk.chunk_type().to_string(), "iTXt");
+            assert_eq!(InternationalTextChunk::try_from(&chunk).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_international_text_chunk_layout() {
+        let chunk = InternationalTextChunk::new("Title", "hi")
+            .unwrap()
+            .with_language("en", "Title")
+            .to_chunk()
+            .unwrap();
+        assert_eq!(chunk.data(), b"Title\0\0\0en\0Title\0hi");
+    }
+
+    #[test]
+    fn test_invalid_keywords() {
+        assert!(TextChunk::new("", "text").is_err());
+        assert!(TextChunk::new("k".repeat(80), "text").is_err());
+        assert!(TextChunk::new(" Title", "text").is_err());
+        assert!(TextChunk::new("Title ", "text").is_err());
+        assert!(TextChunk::new("Two  spaces", "text").is_err());
+        assert!(TextChunk::new("标题", "text").is_err());
+        assert!(TextChunk::new("Tab\t", "text").is_err());
+        assert!(TextChunk::new("k".repeat(79), "text").is_ok());
+    }
+
+    #[test]
+    fn test_text_must_be_latin1() {
+        assert!(TextChunk::new("Title", "你好").is_err());
+        assert!(CompressedTextChunk::new("Title", "你好").is_err());
+        assert!(TextChunk::new("Title", "nul\0").is_err());
+        assert!(InternationalTextChunk::new("Title", "你好").is_ok());
+    }
+
+    #[test]
+    fn test_text_chunk_choice_and_keyword() {
+        let latin1 = text_chunk("Title", "Café").unwrap();
+        assert_eq!(&latin1.chunk_type().to_string(), "tEXt");
+        let unicode = text_chunk("Title", "标题").unwrap();
+        assert_eq!(&unicode.chunk_type().to_string(), "iTXt");
+        assert!(text_chunk(" bad", "text").is_err());
+
+        let compressed = CompressedTextChunk::new("Comment", "x").unwrap();
+        for chunk in [latin1, unicode] {
+            assert_eq!(keyword(&chunk).as_deref(), Some("Title"));
+        }
+        assert_eq!(
+            keyword(&compressed.to_chunk().unwrap()).as_deref(),
+            Some("Comment")
+        );
+        assert_eq!(
+            keyword(&new_chunk("ruSt", b"Title\0x".to_vec()).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wrong_chunk_type() {
+        let chunk = TextChunk::new("Title", "text").unwrap().to_chunk().unwrap();
+        assert!(CompressedTextChunk::try_from(&chunk).is_err());
+        assert!(InternationalTextChunk::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_malformed_chunks() {
+        let missing_null = new_chunk("tEXt", b"Title".to_vec()).unwrap();
+        assert!(matches!(
+            TextChunk::try_from(&missing_null),
+            Err(PngmeError::InvalidTextChunk(_))
+        ));
+
+        let bad_method = new_chunk("zTXt", b"Title\0\x01data".to_vec()).unwrap();
+        assert!(CompressedTextChunk::try_from(&bad_method).is_err());
+
+        let bad_flag = new_chunk("iTXt", b"Title\0\x02\0\0\0hi".to_vec()).unwrap();
+        assert!(InternationalTextChunk::try_from(&bad_flag).is_err());
+
+        let truncated = new_chunk("iTXt", b"Title\0\0\0en".to_vec()).unwrap();
+        assert!(InternationalTextChunk::try_from(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_lossy_conversion() {
+        let text = TextChunk::new_lossy("Title", "你好 Café\0").unwrap();
+        assert_eq!(text.text, "?? Café?");
+        assert_eq!(
+            CompressedTextChunk::new_lossy("Title", "€5").unwrap().text,
+            "?5"
+        );
+
+        let chunk = new_chunk("iTXt", b"Title\0\0\0\0\0caf\xe9".to_vec()).unwrap();
+        assert!(matches!(
+            InternationalTextChunk::try_from(&chunk),
+            Err(PngmeError::InvalidUtf8(_))
+        ));
+        let text = InternationalTextChunk::from_chunk_lossy(&chunk, &Limits::default()).unwrap();
+        assert_eq!(text.text, "caf\u{fffd}");
+    }
+
+    #[test]
+    fn test_preview() {
+        let chunk = TextChunk::new("Title", "Café").unwrap().to_chunk().unwrap();
+        assert_eq!(preview(&chunk, 60).as_deref(), Some("Title: Café"));
+
+        let hostile = InternationalTextChunk::new("Comment", "\x1b[2Jrm -rf ~\n".repeat(10))
+            .unwrap()
+            .with_compression(true)
+            .to_chunk()
+            .unwrap();
+        let text = preview(&hostile, 20).unwrap();
+        assert!(text.starts_with("Comment: \\x1B[2Jrm -rf"));
+        assert!(text.ends_with('…'));
+        assert!(!text.contains('\x1b') && !text.contains('\n'));
+
+        assert_eq!(
+            preview(&new_chunk("ruSt", b"x".to_vec()).unwrap(), 60),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let lint = |chunk: &Chunk| lint(chunk.chunk_type(), chunk.data(), &Limits::default());
+
+        let clean = [
+            TextChunk::new("Title", "Café").unwrap().to_chunk().unwrap(),
+            CompressedTextChunk::new("Title", "line 1\nline 2")
+                .unwrap()
+                .to_chunk()
+                .unwrap(),
+            InternationalTextChunk::new("Title", "你好")
+                .unwrap()
+                .to_chunk()
+                .unwrap(),
+            new_chunk("ruSt", b"\xff\0".to_vec()).unwrap(),
+        ];
+        for chunk in &clean {
+            assert!(lint(chunk).is_empty(), "{}", chunk.chunk_type());
+        }
+
+        let utf8_in_text = new_chunk("tEXt", "Title\0你好".as_bytes().to_vec()).unwrap();
+        assert_eq!(
+            lint(&utf8_in_text),
+            ["text: Latin-1 text looks like UTF-8; use iTXt for Unicode text"]
+        );
+
+        let escape = new_chunk("iTXt", b"Title\0\0\0\0\0\x1b[2J\xff".to_vec()).unwrap();
+        assert_eq!(
+            lint(&escape),
+            [
+                "text: control character 0x1B at offset 0",
+                "text: invalid UTF-8 at offset 4"
+            ]
+        );
+
+        let bad_keyword = new_chunk("tEXt", b" Title\0text".to_vec()).unwrap();
+        assert_eq!(lint(&bad_keyword).len(), 1);
+        assert!(lint(&bad_keyword)[0].starts_with("keyword: "));
+
+        let truncated = new_chunk("zTXt", b"Title".to_vec()).unwrap();
+        assert_eq!(lint(&truncated).len(), 1);
+    }
+
+    #[test]
+    fn test_decompression_bomb() {
+        // 8 MiB 的空格压缩后不到 10 KiB
+        let bomb = deflate(&[b' '; 8 << 20]).unwrap();
+        let mut data = b"Comment\0\0".to_vec();
+        data.extend_from_slice(&bomb);
+        let chunk = new_chunk("zTXt", data).unwrap();
+        assert!(matches!(
+            CompressedTextChunk::try_from(&chunk),
+            Err(PngmeError::DecompressionBomb { .. })
+        ));
+        let text =
+            CompressedTextChunk::from_chunk_with_limits(&chunk, &Limits::permissive()).unwrap();
+        assert_eq!(text.text.len(), 8 << 20);
+
+        let mut data = b"Comment\0\x01\0\0\0".to_vec();
+        data.extend_from_slice(&bomb);
+        let chunk = new_chunk("iTXt", data).unwrap();
+        assert!(matches!(
+            InternationalTextChunk::try_from(&chunk),
+            Err(PngmeError::DecompressionBomb { .. })
+        ));
+    }
+}
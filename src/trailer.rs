@@ -0,0 +1,189 @@
+//! # Trailer
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `--method trailer`：把信息写在 IEND 之后。大多数查看器读到 IEND 就停止，
+//! 会忽略之后的字节；但 pngme 的其他命令和很多工具会拒绝或丢弃这样的文件，`strip` 会删除它们。
+//!
+//! IEND 之后的格式为魔数加上若干条记录，每条记录的编码与 chunk 相同，
+//! 因此同样带有类型和 CRC，拆分（`payload::split`）和按类型读取都与普通信息一致：
+//!
+//! ```text
+//! | "pngmeTRL" | 长度 (u32) | 类型 (4) | 数据 | CRC (u32) | ... |
+//! ```
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::payload;
+use crate::png::Png;
+use crate::{PngmeError, Result};
+
+/// IEND 之后的数据以该魔数开头时为 pngme 写入的信息
+pub const MAGIC: [u8; 8] = *b"pngmeTRL";
+
+/// 信息的写入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Method {
+    /// 写入 IEND 之前的辅助 chunk
+    #[default]
+    Chunk,
+    /// 写在 IEND 之后
+    Trailer,
+    /// 渲染为 QR 码画在图片的一角（修改像素，见 `qr`），截图后仍能读出
+    Qr,
+    /// 拆分后藏在 APNG 各帧的 fdAT 中，位于 zlib 数据流结束之后（见 `fdat`）
+    Fdat,
+    /// 写入像素样本的最低位（修改像素，见 `lsb`），肉眼不可见，但不能经受有损的重新编码
+    Lsb,
+}
+
+/// 把文件分为到 IEND 为止的 PNG 数据和 IEND 之后的字节。
+/// IEND 之前的 chunk 必须完整（不校验 CRC），找不到 IEND 时返回 `PngmeError::ChunkNotFound`
+pub fn split(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    let mut end = Png::STANDARD_HEADER.len();
+    for chunk in Png::chunk_refs(bytes)? {
+        let chunk = chunk?;
+        end += chunk.encoded_len();
+        if chunk.chunk_type().bytes() == *b"IEND" {
+            return Ok(bytes.split_at(end));
+        }
+    }
+    Err(PngmeError::ChunkNotFound("IEND".to_string()))
+}
+
+/// IEND 之后的字节是否是 pngme 写入的信息
+pub fn is_trailer(trailing: &[u8]) -> bool {
+    trailing.starts_with(&MAGIC)
+}
+
+/// 解析 IEND 之后的记录，没有数据时返回空列表；不是 pngme 的格式或记录损坏时返回错误
+pub fn parse(trailing: &[u8]) -> Result<Vec<Chunk>> {
+    if trailing.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(mut rest) = trailing.strip_prefix(&MAGIC) else {
+        return Err(PngmeError::InvalidPayload(format!(
+            "{} bytes after IEND were not written by pngme",
+            trailing.len()
+        )));
+    };
+    let mut records = Vec::new();
+    while !rest.is_empty() {
+        let record = Chunk::try_from(rest)?;
+        rest = &rest[record.length() as usize + 12..];
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// 编码记录，没有记录时返回空数据
+pub fn to_bytes(records: &[Chunk]) -> Vec<u8> {
+    if records.is_empty() {
+        return Vec::new();
+    }
+    let mut bytes = MAGIC.to_vec();
+    for record in records {
+        bytes.extend(record.as_bytes());
+    }
+    bytes
+}
+
+/// 把 `data` 按 `max_chunk_size` 拆分为类型为 `chunk_type` 的记录，追加到已有的记录之后
+pub fn append(
+    records: &mut Vec<Chunk>,
+    chunk_type: ChunkType,
+    data: &[u8],
+    max_chunk_size: usize,
+) -> Result<()> {
+    for part in payload::split(data, max_chunk_size)? {
+        records.push(Chunk::new(chunk_type, part)?);
+    }
+    Ok(())
+}
+
+/// 读取 IEND 之后类型为 `chunk_type` 的信息，拆分的信息会被拼接
+pub fn message_data(bytes: &[u8], chunk_type: &str) -> Result<Vec<u8>> {
+    let (_, trailing) = split(bytes)?;
+    if trailing.is_empty() {
+        return Err(PngmeError::ChunkNotFound(format!(
+            "{} (no data after IEND)",
+            chunk_type
+        )));
+    }
+    payload::chunks_message_data(&parse(trailing)?, chunk_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    fn with_trailer(chunk_type: &str, data: &[u8], max_chunk_size: usize) -> Vec<u8> {
+        let mut records = Vec::new();
+        append(
+            &mut records,
+            ChunkType::from_str(chunk_type).unwrap(),
+            data,
+            max_chunk_size,
+        )
+        .unwrap();
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend(to_bytes(&records));
+        bytes
+    }
+
+    #[test]
+    fn test_split() {
+        let bytes = with_trailer("ruSt", b"hello", 1024);
+        let (png, trailing) = split(&bytes).unwrap();
+        assert_eq!(png, &PNG_FILE[..]);
+        assert!(is_trailer(trailing));
+        assert_eq!(split(&PNG_FILE).unwrap().1, b"");
+
+        // 没有 IEND
+        assert!(matches!(
+            split(&PNG_FILE[..PNG_FILE.len() - 12]),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_message_data() {
+        let bytes = with_trailer("ruSt", b"hidden after IEND", 1024);
+        assert_eq!(message_data(&bytes, "ruSt").unwrap(), b"hidden after IEND");
+        assert!(matches!(
+            message_data(&bytes, "abCD"),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+        assert!(matches!(
+            message_data(&PNG_FILE, "ruSt"),
+            Err(PngmeError::ChunkNotFound(_))
+        ));
+
+        // 拆分成多条记录
+        let data: Vec<u8> = (0..100).collect();
+        let bytes = with_trailer("ruSt", &data, 30);
+        let (_, trailing) = split(&bytes).unwrap();
+        assert!(parse(trailing).unwrap().len() > 1);
+        assert_eq!(message_data(&bytes, "ruSt").unwrap(), data);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(b"").unwrap().is_empty());
+        assert!(matches!(
+            parse(b"garbage"),
+            Err(PngmeError::InvalidPayload(_))
+        ));
+
+        let mut bytes = with_trailer("ruSt", b"hello", 1024);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let (_, trailing) = split(&bytes).unwrap();
+        assert!(matches!(
+            parse(trailing),
+            Err(PngmeError::CrcMismatch { .. })
+        ));
+    }
+}
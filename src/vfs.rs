@@ -0,0 +1,827 @@
+//! # Vfs
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/15
+//!
+//! 该模块定义了最小的文件系统抽象 `Vfs`。命令只通过它读写文件，
+//! 因此输入既可以来自本地磁盘，也可以来自内存、归档或对象存储。
+
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::progress::{NoProgress, Progress};
+use crate::winpath::{self, long_path};
+
+/// 文件系统抽象
+pub trait Vfs: Send + Sync {
+    /// 以流的方式打开文件
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// 写入整个文件，已存在时覆盖
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// 文件是否存在
+    fn exists(&self, path: &Path) -> bool;
+
+    /// 路径是否为目录
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// 列出目录下的直接子项
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// 创建目录及其上级目录；没有目录概念的实现（内存、对象存储）什么也不做
+    fn create_dir_all(&self, _dir: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// 删除文件或空目录；不支持删除的实现返回 `Unsupported`
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("cannot remove {}", path.display()),
+        ))
+    }
+
+    /// 路径本身是否为符号链接（不跟随链接）；没有链接概念的实现总是返回 false
+    fn is_symlink(&self, _path: &Path) -> bool {
+        false
+    }
+
+    /// 解析符号链接后的绝对路径，用于识别指向同一位置的不同路径；无法解析时返回原路径
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+
+    /// 文件有多个硬链接时返回其标识（设备号和 inode），否则返回 `None`
+    fn hard_link_id(&self, _path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// 覆盖已有文件的内容而不替换文件本身，保留 inode 和硬链接；不保证原子性
+    fn write_in_place(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.write(path, data)
+    }
+
+    /// 读取文件的时间戳、权限、所有者和扩展属性，供 `set_attributes` 恢复；
+    /// 没有这些概念的实现返回 `None`
+    fn attributes(&self, _path: &Path) -> io::Result<Option<FileAttributes>> {
+        Ok(None)
+    }
+
+    /// 把 `attributes` 中 `preserve` 选中的部分恢复到文件上
+    fn set_attributes(
+        &self,
+        _path: &Path,
+        _attributes: &FileAttributes,
+        _preserve: Preserve,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// 报告进度的回调，默认不报告
+    fn progress(&self) -> Arc<dyn Progress> {
+        Arc::new(NoProgress)
+    }
+
+    /// 读取整个文件
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.open(path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// `--preserve` 恢复的文件属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Preserve {
+    All,
+    Times,
+    Perms,
+}
+
+impl Preserve {
+    /// 是否恢复修改和访问时间
+    pub fn times(self) -> bool {
+        matches!(self, Preserve::All | Preserve::Times)
+    }
+
+    /// 是否恢复权限位、所有者和扩展属性
+    pub fn perms(self) -> bool {
+        matches!(self, Preserve::All | Preserve::Perms)
+    }
+}
+
+/// `Vfs::attributes` 读取的文件属性
+#[derive(Debug, Clone)]
+pub struct FileAttributes {
+    metadata: fs::Metadata,
+    /// 扩展属性的名字和值
+    #[cfg_attr(not(unix), allow(dead_code))]
+    xattrs: Vec<(OsString, Vec<u8>)>,
+}
+
+/// 文件被其他进程锁定时的最大重试次数
+const LOCK_RETRIES: u32 = 5;
+/// 第一次重试前的等待时间，之后每次加倍
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// 本地文件系统
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFs;
+
+impl Vfs for StdFs {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let path = long_path(path);
+        let file = retry_locked(&path, LOCK_RETRIES, LOCK_RETRY_DELAY, || File::open(&path))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    /// 原子地写入：见 `write_atomic`
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        write_atomic(path, data)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        long_path(path).exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        long_path(path).is_dir()
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        // 用原来的目录拼接文件名，返回的路径不带扩展长度前缀
+        let mut entries = fs::read_dir(long_path(dir))?
+            .map(|entry| entry.map(|entry| dir.join(entry.file_name())))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(long_path(dir))
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let path = long_path(path);
+        match path.is_dir() {
+            true => fs::remove_dir(&path),
+            false => retry_locked(&path, LOCK_RETRIES, LOCK_RETRY_DELAY, || {
+                fs::remove_file(&path)
+            }),
+        }
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        long_path(path).is_symlink()
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        fs::canonicalize(long_path(path)).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    #[cfg(unix)]
+    fn hard_link_id(&self, path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = fs::metadata(long_path(path)).ok()?;
+        (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+    }
+
+    fn attributes(&self, path: &Path) -> io::Result<Option<FileAttributes>> {
+        let path = &*long_path(path);
+        let metadata = fs::metadata(path)?;
+        #[cfg(unix)]
+        let xattrs = match xattr::list_deref(path) {
+            Ok(names) => names
+                .filter_map(|name| {
+                    let value = xattr::get_deref(path, &name).ok()??;
+                    Some((name, value))
+                })
+                .collect(),
+            // 文件系统不支持扩展属性
+            Err(_) => Vec::new(),
+        };
+        #[cfg(not(unix))]
+        let xattrs = Vec::new();
+        Ok(Some(FileAttributes { metadata, xattrs }))
+    }
+
+    fn set_attributes(
+        &self,
+        path: &Path,
+        attributes: &FileAttributes,
+        preserve: Preserve,
+    ) -> io::Result<()> {
+        let path = &*long_path(path);
+        let metadata = &attributes.metadata;
+        if preserve.perms() {
+            // 修改所有者会清除 setuid 位，所以先于权限位；没有权限时跳过所有者和扩展属性
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                permitted(std::os::unix::fs::chown(
+                    path,
+                    Some(metadata.uid()),
+                    Some(metadata.gid()),
+                ))?;
+                for (name, value) in &attributes.xattrs {
+                    permitted(xattr::set_deref(path, name, value))?;
+                }
+            }
+            fs::set_permissions(path, metadata.permissions())?;
+        }
+        if preserve.times() {
+            let mut times = fs::FileTimes::new().set_modified(metadata.modified()?);
+            if let Ok(accessed) = metadata.accessed() {
+                times = times.set_accessed(accessed);
+            }
+            File::open(path)?.set_times(times)?;
+        }
+        Ok(())
+    }
+
+    fn write_in_place(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let path = long_path(path);
+        check_writable(&path)?;
+        let mut file = retry_locked(&path, LOCK_RETRIES, LOCK_RETRY_DELAY, || {
+            fs::OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(&path)
+        })?;
+        file.write_all(data)?;
+        file.sync_all()
+    }
+}
+
+/// 包装另一个文件系统：覆盖有多个硬链接的文件时原地写入，
+/// 所有链接都能看到新内容；其他文件照常写入
+pub struct PreserveHardlinks<'a>(pub &'a dyn Vfs);
+
+impl Vfs for PreserveHardlinks<'_> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        self.0.open(path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        match self.0.hard_link_id(path) {
+            Some(_) => self.0.write_in_place(path, data),
+            None => self.0.write(path, data),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.0.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.0.is_dir(path)
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        self.0.list(dir)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        self.0.create_dir_all(dir)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.0.remove(path)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.0.is_symlink(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        self.0.canonicalize(path)
+    }
+
+    fn hard_link_id(&self, path: &Path) -> Option<(u64, u64)> {
+        self.0.hard_link_id(path)
+    }
+
+    fn write_in_place(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.0.write_in_place(path, data)
+    }
+
+    fn attributes(&self, path: &Path) -> io::Result<Option<FileAttributes>> {
+        self.0.attributes(path)
+    }
+
+    fn set_attributes(
+        &self,
+        path: &Path,
+        attributes: &FileAttributes,
+        preserve: Preserve,
+    ) -> io::Result<()> {
+        self.0.set_attributes(path, attributes, preserve)
+    }
+
+    fn progress(&self) -> Arc<dyn Progress> {
+        self.0.progress()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.0.read(path)
+    }
+}
+
+/// 内存中的文件系统，主要用于测试和嵌入式场景
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemoryFs {
+    /// 创建空的内存文件系统
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个文件
+    pub fn insert(&self, path: impl Into<PathBuf>, data: Vec<u8>) {
+        self.files.lock().unwrap().insert(path.into(), data);
+    }
+
+    /// 获取文件内容的副本
+    pub fn get(&self, path: &Path) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(path).cloned()
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let data = self.get(path).ok_or_else(|| not_found(path))?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.insert(path, data.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.lock().unwrap().contains_key(path)
+    }
+
+    /// 没有真正的目录，路径之下还有文件时视为非空目录
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if self.files.lock().unwrap().remove(path).is_some() {
+            return Ok(());
+        }
+        match self.is_dir(path) {
+            true => Err(io::Error::new(
+                io::ErrorKind::DirectoryNotEmpty,
+                format!("{} is not empty", path.display()),
+            )),
+            false => Err(not_found(path)),
+        }
+    }
+
+    /// 没有真正的目录，某个文件位于该路径之下时视为目录
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .lock()
+            .unwrap()
+            .keys()
+            .any(|file| file != path && file.starts_with(path))
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries: Vec<PathBuf> = self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|path| {
+                let first = path.strip_prefix(dir).ok()?.components().next()?;
+                Some(dir.join(first))
+            })
+            .collect();
+        // 键按路径分量排序，同一子目录下的文件是相邻的
+        entries.dedup();
+        Ok(entries)
+    }
+}
+
+/// 先写入同一目录下的临时文件并 fsync，再重命名覆盖目标文件。
+/// 同一文件系统内的重命名是原子的，写到一半崩溃也不会损坏原文件。
+/// 目标是符号链接时替换它指向的文件，已有文件的权限会被保留。
+/// 临时文件名中的序号，同一进程中每次写入都不同
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+/// 临时文件名已存在时最多换多少次序号
+const TEMP_ATTEMPTS: usize = 100;
+
+/// 在 `dir` 中为 `name` 新建一个临时文件。用 `create_new` 打开，已存在的文件（包括别人放在那里的符号链接）
+/// 不会被截断或跟随，名称已存在时换一个序号重试
+fn create_temp(dir: &Path, name: &OsStr) -> io::Result<(File, PathBuf)> {
+    let mut attempts = 0;
+    loop {
+        let temp = dir.join(format!(
+            ".{}.{}.{}.tmp",
+            name.to_string_lossy(),
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((file, temp)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                attempts += 1;
+                if attempts == TEMP_ATTEMPTS {
+                    return Err(error);
+                }
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// 在 Windows 上过长的路径会转换为扩展长度路径，保留设备名（例如 `nul.png`）会被拒绝
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    // 扩展长度路径不会被解释为设备，所以要在转换之前检查
+    let reserved = path
+        .file_name()
+        .is_some_and(|name| winpath::is_reserved_name(&name.to_string_lossy()));
+    if cfg!(windows) && reserved {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is a reserved device name on Windows", path.display()),
+        ));
+    }
+    let path = fs::canonicalize(long_path(path)).unwrap_or_else(|_| long_path(path).into_owned());
+    // 目录可写时重命名可以替换只读文件，这里显式拒绝
+    check_writable(&path)?;
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (mut file, temp) = create_temp(dir, name)?;
+
+    let result = (|| {
+        file.write_all(data)?;
+        if let Ok(metadata) = fs::metadata(&path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        retry_locked(&path, LOCK_RETRIES, LOCK_RETRY_DELAY, || {
+            fs::rename(&temp, &path)
+        })
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+
+    // 重命名本身也要落盘；不是所有平台都能打开目录，失败时忽略
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// 已存在的文件是只读的时返回错误
+fn check_writable(path: &Path) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().readonly() => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is read-only", path.display()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 执行 `op`，文件被其他进程锁定时等待后重试，最多重试 `retries` 次，每次等待的时间加倍；
+/// 重试用尽后返回说明文件被锁定的错误
+fn retry_locked<T>(
+    path: &Path,
+    retries: u32,
+    delay: Duration,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut delay = delay;
+    for _ in 0..retries {
+        match op() {
+            Err(e) if is_locked(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    op().map_err(|e| match is_locked(&e) {
+        true => io::Error::new(
+            e.kind(),
+            format!("{} is locked by another process: {}", path.display(), e),
+        ),
+        false => e,
+    })
+}
+
+/// 错误是否表示文件正被其他进程使用
+fn is_locked(e: &io::Error) -> bool {
+    // Windows 的 ERROR_SHARING_VIOLATION 和 ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33)) {
+        return true;
+    }
+    matches!(
+        e.kind(),
+        io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy
+    )
+}
+
+/// 没有权限或文件系统不支持时忽略错误
+#[cfg(unix)]
+fn permitted(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::PermissionDenied | io::ErrorKind::Unsupported
+            ) =>
+        {
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fs() {
+        let fs = MemoryFs::new();
+        assert!(!fs.exists(Path::new("a/1.png")));
+        assert!(fs.read(Path::new("a/1.png")).is_err());
+
+        fs.write(Path::new("a/1.png"), b"one").unwrap();
+        fs.insert("a/2.png", b"two".to_vec());
+        fs.insert("b/3.png", b"three".to_vec());
+        fs.insert("a/c/4.png", b"four".to_vec());
+
+        assert!(fs.exists(Path::new("a/1.png")));
+        assert_eq!(fs.read(Path::new("a/2.png")).unwrap(), b"two");
+        assert_eq!(
+            fs.list(Path::new("a")).unwrap(),
+            [
+                PathBuf::from("a/1.png"),
+                PathBuf::from("a/2.png"),
+                PathBuf::from("a/c")
+            ]
+        );
+        assert!(fs.is_dir(Path::new("a/c")));
+        assert!(!fs.is_dir(Path::new("a/1.png")));
+
+        fs.remove(Path::new("a/1.png")).unwrap();
+        assert!(!fs.exists(Path::new("a/1.png")));
+        assert!(fs.remove(Path::new("a/1.png")).is_err());
+        assert!(fs.remove(Path::new("a/c")).is_err());
+    }
+
+    #[test]
+    fn test_std_fs() {
+        let dir = std::env::temp_dir().join(format!("pngme-vfs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file.bin");
+
+        StdFs.write(&path, b"data").unwrap();
+        assert!(StdFs.exists(&path));
+        assert_eq!(StdFs.read(&path).unwrap(), b"data");
+        assert_eq!(StdFs.list(&dir).unwrap(), std::slice::from_ref(&path));
+        assert!(StdFs.is_dir(&dir));
+        assert!(!StdFs.is_dir(&path));
+        StdFs.remove(&path).unwrap();
+        StdFs.remove(&dir).unwrap();
+        assert!(!StdFs.exists(&dir));
+    }
+
+    #[test]
+    fn test_std_fs_atomic_write() {
+        let dir = std::env::temp_dir().join(format!("pngme-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("image.png");
+        fs::write(&path, b"old").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        StdFs.write(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        // 临时文件已经被重命名，目录中只剩目标文件
+        assert_eq!(StdFs.list(&dir).unwrap(), std::slice::from_ref(&path));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        assert!(StdFs.write(&dir.join("missing/image.png"), b"x").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_std_fs_concurrent_writes() {
+        let dir = std::env::temp_dir().join(format!("pngme-concurrent-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("image.png");
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64 * 1024]).collect();
+        thread::scope(|scope| {
+            for data in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        StdFs.write(path, data).unwrap();
+                    }
+                });
+            }
+        });
+        // 每次写入都用自己的临时文件，结果是某一次完整的写入，没有遗留的临时文件
+        assert!(contents.contains(&fs::read(&path).unwrap()));
+        assert_eq!(StdFs.list(&dir).unwrap(), std::slice::from_ref(&path));
+
+        // 临时文件名上已有的文件（例如符号链接）不会被打开，而是换一个名称
+        let (_, first) = create_temp(&dir, OsStr::new("image.png")).unwrap();
+        let taken = dir.join(format!(
+            ".image.png.{}.{}.tmp",
+            std::process::id(),
+            NEXT_TEMP.load(Ordering::Relaxed)
+        ));
+        fs::write(&taken, b"not ours").unwrap();
+        let (_, second) = create_temp(&dir, OsStr::new("image.png")).unwrap();
+        assert_ne!(second, first);
+        assert_eq!(fs::read(&taken).unwrap(), b"not ours");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserve_hardlinks() {
+        let dir = std::env::temp_dir().join(format!("pngme-hardlink-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.png"), dir.join("b.png"));
+        fs::write(&a, b"old").unwrap();
+        fs::hard_link(&a, &b).unwrap();
+        assert!(StdFs.hard_link_id(&a).is_some());
+        assert_eq!(StdFs.hard_link_id(&a), StdFs.hard_link_id(&b));
+
+        // 默认的原子写入会替换文件，断开硬链接
+        StdFs.write(&a, b"new").unwrap();
+        assert_eq!(fs::read(&b).unwrap(), b"old");
+        assert_eq!(StdFs.hard_link_id(&a), None);
+
+        fs::remove_file(&a).unwrap();
+        fs::hard_link(&b, &a).unwrap();
+        PreserveHardlinks(&StdFs).write(&a, b"shared").unwrap();
+        assert_eq!(fs::read(&b).unwrap(), b"shared");
+        assert_eq!(StdFs.hard_link_id(&a), StdFs.hard_link_id(&b));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_std_fs_preserve_attributes() {
+        use std::time::{Duration, SystemTime};
+
+        let dir = std::env::temp_dir().join(format!("pngme-attrs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (input, out) = (dir.join("in.png"), dir.join("out.png"));
+        fs::write(&input, b"old").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        File::open(&input)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_modified(modified))
+            .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&input, fs::Permissions::from_mode(0o604)).unwrap();
+        }
+        let attributes = StdFs.attributes(&input).unwrap().unwrap();
+        let mode = |path: &Path| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::metadata(path).unwrap().permissions().mode() & 0o777
+            }
+            #[cfg(not(unix))]
+            {
+                u32::from(fs::metadata(path).unwrap().permissions().readonly())
+            }
+        };
+
+        StdFs.write(&out, b"new").unwrap();
+        StdFs
+            .set_attributes(&out, &attributes, Preserve::Times)
+            .unwrap();
+        assert_eq!(fs::metadata(&out).unwrap().modified().unwrap(), modified);
+        assert_ne!(mode(&out), mode(&input));
+
+        StdFs.write(&out, b"newer").unwrap();
+        StdFs
+            .set_attributes(&out, &attributes, Preserve::Perms)
+            .unwrap();
+        assert_ne!(fs::metadata(&out).unwrap().modified().unwrap(), modified);
+        assert_eq!(mode(&out), mode(&input));
+
+        assert!(MemoryFs::new().attributes(&input).unwrap().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_std_fs_rejects_read_only() {
+        let dir = std::env::temp_dir().join(format!("pngme-readonly-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("image.png");
+        fs::write(&path, b"old").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions.clone()).unwrap();
+
+        let error = StdFs.write(&path, b"new").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("read-only"));
+        assert!(StdFs.write_in_place(&path, b"new").is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_retry_locked() {
+        let busy = || io::Error::from(io::ErrorKind::ResourceBusy);
+        let mut calls = 0;
+        let result = retry_locked(Path::new("a.png"), 3, Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err(busy())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let error = retry_locked(Path::new("a.png"), 2, Duration::ZERO, || {
+            Err::<(), _>(busy())
+        })
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("a.png is locked by another process"));
+
+        // 其他错误不重试
+        let mut calls = 0;
+        let result = retry_locked(Path::new("a.png"), 3, Duration::ZERO, || {
+            calls += 1;
+            Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_std_fs_long_paths() {
+        let root = std::env::temp_dir().join(format!("pngme-long-{}", std::process::id()));
+        let mut dir = root.clone();
+        while dir.as_os_str().len() < 300 {
+            dir.push("a-fairly-long-directory-name");
+        }
+        StdFs.create_dir_all(&dir).unwrap();
+        let path = dir.join("image.png");
+        StdFs.write(&path, b"deep").unwrap();
+        assert!(StdFs.exists(&path));
+        assert_eq!(StdFs.read(&path).unwrap(), b"deep");
+        assert_eq!(StdFs.list(&dir).unwrap(), std::slice::from_ref(&path));
+
+        assert!(StdFs.write(&root.join("nul.png"), b"x").is_err());
+        fs::remove_dir_all(long_path(&root)).unwrap();
+    }
+}
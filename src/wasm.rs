@@ -0,0 +1,47 @@
+//! # Wasm
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块在启用 `wasm` feature 时为浏览器导出 `encode` 和 `decode`，
+//! 通过 `wasm-bindgen` 生成 JS 绑定，所有处理都在客户端内存中完成：
+//!
+//! ```text
+//! import init, { encode, decode } from "./pkg/pngme.js";
+//! await init();
+//! const output = encode(new Uint8Array(buffer), "ruSt", "hello"); // Uint8Array
+//! decode(output, "ruSt"); // "hello"
+//! ```
+
+use std::str;
+
+use wasm_bindgen::prelude::*;
+
+use crate::engine;
+use crate::Result;
+
+/// 在 `bytes` 表示的 PNG 中写入 `message`，返回新的 PNG 文件
+#[wasm_bindgen]
+pub fn encode(
+    bytes: &[u8],
+    chunk_type: &str,
+    message: &str,
+) -> std::result::Result<Vec<u8>, JsError> {
+    js(engine::encode_once(
+        bytes,
+        chunk_type,
+        message.as_bytes(),
+        false,
+        None,
+    ))
+}
+
+/// 读取 `bytes` 表示的 PNG 中 `chunk_type` 类型的信息
+#[wasm_bindgen]
+pub fn decode(bytes: &[u8], chunk_type: &str) -> std::result::Result<String, JsError> {
+    js(engine::decode_once(bytes, chunk_type, None)
+        .and_then(|data| Ok(str::from_utf8(&data)?.to_string())))
+}
+
+fn js<T>(result: Result<T>) -> std::result::Result<T, JsError> {
+    result.map_err(|e| JsError::new(&e.to_string()))
+}
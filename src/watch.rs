@@ -0,0 +1,330 @@
+//! # Watch
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了 `pngme watch`：监视一个目录，对新建或修改的 PNG 文件执行固定的操作
+//! （删除元数据或写入水印 chunk），用于导出目录和 CI 产物目录。
+//!
+//! 文件在停止变化 `--debounce` 毫秒后才处理，避免读到写了一半的文件。操作是幂等的：
+//! 文件已经满足要求时不会写入，因此 pngme 自己写入文件产生的事件不会导致循环处理。
+//!
+//! 收到 SIGINT/SIGTERM 后处理完当前文件即退出。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::args::WatchArgs;
+use crate::batch;
+use crate::cancel;
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::commands::read_png;
+use crate::output::say;
+use crate::vfs::Vfs;
+use crate::{PngmeError, Result};
+
+/// 检查关闭标记和到期文件的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 对每个文件执行的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchOperation {
+    /// 删除辅助 chunk，保留列出的类型
+    Strip(Vec<ChunkType>),
+    /// 在 IEND 之前写入水印 chunk
+    Watermark(Chunk),
+}
+
+impl WatchOperation {
+    /// 从命令行参数解析操作，水印的格式为 `TYPE=TEXT`
+    pub fn from_args(args: &WatchArgs) -> Result<Self> {
+        if let Some(watermark) = &args.watermark {
+            let (chunk_type, text) = watermark.split_once('=').ok_or_else(|| {
+                PngmeError::Batch(format!("watermark must be TYPE=TEXT, got {:?}", watermark))
+            })?;
+            let chunk = Chunk::new(ChunkType::from_str(chunk_type)?, text.as_bytes().to_vec())?;
+            return Ok(WatchOperation::Watermark(chunk));
+        }
+        let keep = args
+            .keep
+            .iter()
+            .map(|chunk_type| ChunkType::from_str(chunk_type))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(WatchOperation::Strip(keep))
+    }
+}
+
+/// 对 `path` 执行 `operation`，返回描述修改的文本；文件已满足要求时返回 `None` 且不写入。
+/// `dry_run` 为 true 时只返回描述
+pub fn apply(
+    fs: &dyn Vfs,
+    path: &Path,
+    operation: &WatchOperation,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    let mut png = read_png(fs, path)?;
+    let verb = |done: &str, planned: &str| if dry_run { planned } else { done }.to_string();
+    let description = match operation {
+        WatchOperation::Strip(keep) => {
+            let removed = png.strip_ancillary(keep);
+            if removed.is_empty() {
+                return Ok(None);
+            }
+            format!(
+                "{} {} chunk(s) from {}",
+                verb("Stripped", "Would strip"),
+                removed.len(),
+                path.display()
+            )
+        }
+        WatchOperation::Watermark(chunk) => {
+            let chunk_type = chunk.chunk_type().to_string();
+            if png.chunks_by_type(&chunk_type).contains(&chunk) {
+                return Ok(None);
+            }
+            png.append_chunk(chunk.clone());
+            format!(
+                "{} {} watermark to {}",
+                verb("Added", "Would add"),
+                chunk_type,
+                path.display()
+            )
+        }
+    };
+    if !dry_run {
+        fs.write(path, &png.as_bytes())?;
+    }
+    Ok(Some(description))
+}
+
+/// 记录每个文件最后一次变化的时间，文件安静 `delay` 之后才交给调用者处理
+#[derive(Debug)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Debouncer {
+            delay,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// 记录 `path` 在 `now` 发生了变化，之前的等待重新计时
+    pub fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// 取出在 `now` 已经安静了 `delay` 的文件，按路径排序
+    pub fn ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let delay = self.delay;
+        let mut ready = Vec::new();
+        self.pending.retain(|path, changed| {
+            if now.saturating_duration_since(*changed) >= delay {
+                ready.push(path.clone());
+                false
+            } else {
+                true
+            }
+        });
+        ready.sort();
+        ready
+    }
+
+    /// 是否还有等待中的文件
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// 监视 `args.dir` 直到收到关闭信号，之后返回 `PngmeError::Cancelled`。
+/// 单个文件处理失败时输出到 stderr 并继续监视
+pub fn run(fs: &dyn Vfs, args: WatchArgs) -> Result<()> {
+    if !fs.is_dir(&args.dir) {
+        return Err(PngmeError::Batch(format!(
+            "{} is not a directory",
+            args.dir.display()
+        )));
+    }
+    let operation = WatchOperation::from_args(&args)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+    let mode = if args.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(&args.dir, mode).map_err(watch_error)?;
+    eprintln!("Watching {} (Ctrl-C to stop)", args.dir.display());
+
+    let _graceful = cancel::graceful();
+    let shutdown = cancel::shutdown();
+    let mut debouncer = Debouncer::new(Duration::from_millis(args.debounce));
+    while !shutdown.is_cancelled() {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    let now = Instant::now();
+                    for path in event.paths {
+                        if is_candidate(&path, &args.glob) {
+                            debouncer.touch(path, now);
+                        }
+                    }
+                }
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        for path in debouncer.ready(Instant::now()) {
+            // 原子写入的临时文件在重命名后就不存在了
+            if !fs.exists(&path) || fs.is_dir(&path) {
+                continue;
+            }
+            match apply(fs, &path, &operation, args.dry_run) {
+                Ok(Some(description)) => say!("{}", description),
+                Ok(None) => {}
+                Err(e) => eprintln!("Error: {}: {}", path.display(), e),
+            }
+        }
+    }
+    Err(PngmeError::Cancelled)
+}
+
+/// 文件名是否匹配 `glob`，跳过隐藏文件（包括原子写入使用的临时文件）
+fn is_candidate(path: &Path, glob: &str) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    !name.starts_with('.') && batch::matches_glob(glob, name)
+}
+
+fn watch_error(e: notify::Error) -> PngmeError {
+    PngmeError::Batch(format!("cannot watch directory: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+    use crate::png::Png;
+    use crate::vfs::MemoryFs;
+
+    fn watch_args(strip: bool, keep: &[&str], watermark: Option<&str>) -> WatchArgs {
+        WatchArgs {
+            dir: PathBuf::from("."),
+            strip,
+            keep: keep.iter().map(|s| s.to_string()).collect(),
+            watermark: watermark.map(str::to_string),
+            debounce: 500,
+            dry_run: false,
+            recursive: false,
+            glob: "*.png".to_string(),
+        }
+    }
+
+    fn memory_fs_with_text() -> MemoryFs {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.set_text("Author", "someone").unwrap();
+        png.append_chunk(
+            Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 0xb1, 0x8f]).unwrap(),
+        );
+        let fs = MemoryFs::new();
+        fs.insert("a.png", png.as_bytes());
+        fs
+    }
+
+    #[test]
+    fn test_operation_from_args() {
+        let operation =
+            WatchOperation::from_args(&watch_args(false, &[], Some("waTr=built by CI=1"))).unwrap();
+        let WatchOperation::Watermark(chunk) = operation else {
+            panic!("expected a watermark");
+        };
+        assert_eq!(chunk.chunk_type().to_string(), "waTr");
+        assert_eq!(chunk.data(), b"built by CI=1");
+
+        assert_eq!(
+            WatchOperation::from_args(&watch_args(true, &["gAMA"], None)).unwrap(),
+            WatchOperation::Strip(vec![ChunkType::from_str("gAMA").unwrap()])
+        );
+        assert!(WatchOperation::from_args(&watch_args(false, &[], Some("waTr"))).is_err());
+        assert!(WatchOperation::from_args(&watch_args(false, &[], Some("bad!=x"))).is_err());
+    }
+
+    #[test]
+    fn test_apply_strip() {
+        let fs = memory_fs_with_text();
+        let path = Path::new("a.png");
+        let operation = WatchOperation::Strip(vec![ChunkType::from_str("gAMA").unwrap()]);
+
+        let before = fs.get(path).unwrap();
+        let description = apply(&fs, path, &operation, true).unwrap().unwrap();
+        assert!(description.starts_with("Would strip 1 chunk(s)"));
+        assert_eq!(fs.get(path).unwrap(), before);
+
+        assert!(apply(&fs, path, &operation, false).unwrap().is_some());
+        let png = Png::try_from(&fs.get(path).unwrap()[..]).unwrap();
+        assert!(png.chunk_by_type("tEXt").is_none());
+        assert!(png.chunk_by_type("gAMA").is_some());
+
+        // 再次处理时不写入
+        assert_eq!(apply(&fs, path, &operation, false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_apply_watermark() {
+        let fs = memory_fs_with_text();
+        let path = Path::new("a.png");
+        let operation =
+            WatchOperation::from_args(&watch_args(false, &[], Some("waTr=exported"))).unwrap();
+
+        let description = apply(&fs, path, &operation, false).unwrap().unwrap();
+        assert_eq!(description, "Added waTr watermark to a.png");
+        assert_eq!(apply(&fs, path, &operation, false).unwrap(), None);
+        let png = Png::try_from(&fs.get(path).unwrap()[..]).unwrap();
+        assert_eq!(png.chunks_by_type("waTr").len(), 1);
+
+        assert!(apply(&fs, Path::new("missing.png"), &operation, false).is_err());
+    }
+
+    #[test]
+    fn test_debouncer() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(500));
+        debouncer.touch(PathBuf::from("b.png"), start);
+        debouncer.touch(PathBuf::from("a.png"), start);
+        assert!(debouncer
+            .ready(start + Duration::from_millis(100))
+            .is_empty());
+
+        // 再次变化后重新计时
+        debouncer.touch(PathBuf::from("b.png"), start + Duration::from_millis(300));
+        assert_eq!(
+            debouncer.ready(start + Duration::from_millis(500)),
+            [PathBuf::from("a.png")]
+        );
+        assert!(!debouncer.is_empty());
+        assert_eq!(
+            debouncer.ready(start + Duration::from_millis(800)),
+            [PathBuf::from("b.png")]
+        );
+        assert!(debouncer.is_empty());
+    }
+
+    #[test]
+    fn test_is_candidate() {
+        assert!(is_candidate(Path::new("dir/a.png"), "*.png"));
+        assert!(!is_candidate(Path::new("dir/a.jpg"), "*.png"));
+        assert!(!is_candidate(Path::new("dir/.a.png.1.tmp"), "*"));
+        assert!(!is_candidate(Path::new("dir/.a.png"), "*.png"));
+    }
+}
@@ -0,0 +1,801 @@
+//! # Watermark
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块实现了鲁棒水印（`pngme watermark`）：把一个短标识（1 到 12 字节，例如收件人编号）写入整张图片的亮度，
+//! 用于追查泄露的副本。与 `--method lsb` 不同，它不依赖样本的最低位，图片被常见工具以相同的分辨率重新保存
+//! （换用其他压缩参数、转为灰度、RGBA 或 16 位、减少颜色、调整亮度，以及质量不太低的 JPEG）之后通常仍能读出。
+//! 代价是容量只有一个标识，并且改动是可以测量的噪声（默认每个像素的亮度改变 ±3）。
+//!
+//! 标识前面加上长度字节并补零到 12 字节，再加上 12 个 Reed-Solomon 纠错码字（与 QR 码相同的编码，
+//! 最多纠正 6 个码字），共 25 字节、200 位。图片按 2×2 像素划分为单元，以密钥为种子的 ChaCha20
+//! 把所有单元均匀地分给这 200 位并打乱到整张图片，再为每个单元取一个伪随机的符号 ±1：
+//! 单元内每个像素的颜色通道（不含 alpha）都加上 `强度 × 符号 × (位为 1 ? 1 : -1)`。
+//! 每一位重复写入数十到数千个相距很远的单元，局部的涂改或失真只影响其中很少一部分。
+//!
+//! 检测时计算每个单元的亮度，减去上下左右相邻单元的平均值以去掉图片本身的低频内容，再与该单元的符号相乘，
+//! 按位累加后归一化为 z 值（没有水印时近似服从标准正态分布）。按符号判决出 200 位后进行纠错，
+//! 纠错失败或长度无效时认为没有找到水印。纠错后的码字与软判决值的相关性作为 `score` 报告。
+//!
+//! 水印图案由图片尺寸和密钥决定：不给出口令时使用公开的默认密钥，任何人都能读出标识；
+//! 给出口令时由它派生密钥（见 `crypto::derive_watermark_key`），不知道口令无法读出或有针对性地去除。
+//! 裁剪、缩放和旋转会改变单元的位置，不在支持的范围内。
+//!
+//! 为多个收件人分发同一张图片时，给每份副本写入不同的标识（`parse_recipients` 读取收件人列表）。
+//! 找到泄露的副本后，`trace` 计算它与每个收件人的码字的相关性，`source` 在其中认定来源：
+//! 相关性最高、不低于 `MATCH_SCORE` 并且明显高于其他收件人的就是来源。
+//! 已知候选的标识时不需要纠错成功，比 `detect` 能经受更严重的失真。
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::CancelToken;
+use crate::ihdr::{ColorType, Ihdr};
+use crate::interlace;
+use crate::limits::Limits;
+use crate::metadata;
+use crate::png::Png;
+use crate::qr::{self, reed_solomon};
+use crate::{PngmeError, Result};
+
+/// 标识的最大字节数
+pub const MAX_ID_LEN: usize = 12;
+/// 纠错码字数，最多纠正一半个码字的错误
+const EC_LEN: usize = 12;
+/// 码字的字节数：长度字节、补零后的标识和纠错码字
+const CODEWORD_LEN: usize = 1 + MAX_ID_LEN + EC_LEN;
+/// 码字的位数，即水印图案中不同的位的个数
+pub const CODEWORD_BITS: usize = CODEWORD_LEN * 8;
+/// 单元的边长（像素）
+const CELL: usize = 2;
+/// 每一位至少分到的单元数，决定了图片的最小尺寸
+const MIN_CELLS_PER_BIT: usize = 16;
+/// 默认的强度：8 位样本上每个像素的改变量
+pub const DEFAULT_STRENGTH: u8 = 3;
+/// 最大的强度
+pub const MAX_STRENGTH: u8 = 32;
+/// `trace` 认定图片来自某个收件人的最低相关性：没有水印时相关性近似服从标准正态分布，
+/// 超过 6 的概率约为十亿分之一
+pub const MATCH_SCORE: f64 = 6.0;
+/// `source` 认定来源时最高的相关性至少要比第二高的高出的值：两个收件人的相关性之差的标准差约为 1
+pub const MATCH_MARGIN: f64 = 3.0;
+/// 不给出口令时使用的公开密钥
+const DEFAULT_KEY: [u8; 32] = *b"pngme robust watermark pattern!!";
+
+// 单元承载的位以 u8 保存
+const _: () = assert!(CODEWORD_BITS <= 256);
+
+/// 写入和检测水印的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatermarkOptions {
+    /// 8 位样本上每个像素的改变量（1 到 `MAX_STRENGTH`，16 位样本按比例放大），只用于写入
+    pub strength: u8,
+    /// 决定水印图案的密钥，`None` 时使用公开的默认密钥
+    pub key: Option<[u8; 32]>,
+}
+
+impl Default for WatermarkOptions {
+    fn default() -> Self {
+        WatermarkOptions {
+            strength: DEFAULT_STRENGTH,
+            key: None,
+        }
+    }
+}
+
+/// 检测到的水印
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Detection {
+    /// 读出的标识
+    pub id: String,
+    /// 按符号判决出的 200 位中被纠错改正的位数
+    pub corrected_bits: usize,
+    /// 软判决值与纠错后的码字的相关性（z 值）：没有水印时在 0 附近，越大越可靠
+    pub score: f64,
+}
+
+/// 收件人列表中的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recipient {
+    /// 写入该收件人的副本的标识
+    pub id: String,
+    /// 便于识别的名称，可以省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// `trace` 中一个收件人与图片中的水印的相关性
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceMatch {
+    pub recipient: Recipient,
+    /// 与该收件人的码字的相关性（z 值，见 `Detection::score`）
+    pub score: f64,
+}
+
+fn watermark_error(message: impl Into<String>) -> PngmeError {
+    PngmeError::Watermark(message.into())
+}
+
+impl fmt::Display for Recipient {
+    /// 标识，有名称时在括号中附上名称
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", self.id, name),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// 由密钥和图片尺寸决定的水印图案：每个单元承载码字中的哪一位，以及它的符号
+struct Pattern {
+    columns: usize,
+    rows: usize,
+    /// 第 i 个单元（按行排列）承载的位
+    bits: Vec<u8>,
+    /// 第 i 个单元的符号，`true` 为 +1
+    chips: Vec<bool>,
+}
+
+impl Pattern {
+    /// 图片太小、每一位分不到 `MIN_CELLS_PER_BIT` 个单元时返回 `PngmeError::Watermark`
+    fn new(width: u32, height: u32, key: &[u8; 32]) -> Result<Self> {
+        let columns = width as usize / CELL;
+        let rows = height as usize / CELL;
+        let cells = columns * rows;
+        if cells < CODEWORD_BITS * MIN_CELLS_PER_BIT {
+            return Err(watermark_error(format!(
+                "the {}x{} image is too small, a watermark needs at least {} pixels",
+                width,
+                height,
+                CODEWORD_BITS * MIN_CELLS_PER_BIT * CELL * CELL
+            )));
+        }
+        let mut rng = ChaCha20Rng::from_seed(*key);
+        // 每一位分到同样多（最多差一个）的单元，再用 Fisher-Yates 洗牌打乱到整张图片
+        let mut bits: Vec<u8> = (0..cells).map(|i| (i % CODEWORD_BITS) as u8).collect();
+        for i in (1..cells).rev() {
+            let j = ((u128::from(rng.next_u64()) * (i as u128 + 1)) >> 64) as usize;
+            bits.swap(i, j);
+        }
+        let mut chips = Vec::with_capacity(cells);
+        while chips.len() < cells {
+            let word = rng.next_u64();
+            let count = (cells - chips.len()).min(64);
+            chips.extend((0..count).map(|bit| word >> bit & 1 == 1));
+        }
+        Ok(Pattern {
+            columns,
+            rows,
+            bits,
+            chips,
+        })
+    }
+
+    /// 像素 (x, y) 所在的单元，图片右边和下边不足一个单元的像素不属于任何单元
+    fn cell(&self, x: usize, y: usize) -> Option<usize> {
+        let (column, row) = (x / CELL, y / CELL);
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+}
+
+/// 标识的码字：长度字节、补零到 `MAX_ID_LEN` 字节的标识和纠错码字
+fn codeword(id: &str) -> Result<Vec<u8>> {
+    if id.is_empty() || id.len() > MAX_ID_LEN {
+        return Err(watermark_error(format!(
+            "the identifier must be 1 to {} bytes, got {}",
+            MAX_ID_LEN,
+            id.len()
+        )));
+    }
+    let mut data = vec![id.len() as u8];
+    data.extend(id.as_bytes());
+    data.resize(1 + MAX_ID_LEN, 0);
+    let ec = qrcode::ec::create_error_correction_code(&data, EC_LEN);
+    data.extend(ec);
+    Ok(data)
+}
+
+/// 码字的第 `index` 位（高位在前）
+fn bit(codeword: &[u8], index: usize) -> bool {
+    codeword[index / 8] >> (7 - index % 8) & 1 == 1
+}
+
+/// 把标识写入像素，替换原来的 IDAT。调色板图片和低于 8 位的样本不支持，图片太小、标识为空或超过
+/// `MAX_ID_LEN` 字节时返回 `PngmeError::Watermark`。每处理一行检查 `cancel`，已取消时返回 `PngmeError::Cancelled`
+pub fn embed(
+    png: &mut Png,
+    id: &str,
+    options: &WatermarkOptions,
+    limits: &Limits,
+    cancel: &CancelToken,
+) -> Result<()> {
+    let header = png.header()?;
+    if header.color_type == ColorType::Indexed {
+        return Err(watermark_error(
+            "palette images are not supported, convert them to RGB first",
+        ));
+    }
+    if header.bit_depth < 8 {
+        return Err(watermark_error(format!(
+            "{}-bit samples are not supported, the watermark needs 8 or 16 bits per sample",
+            header.bit_depth
+        )));
+    }
+    if !(1..=MAX_STRENGTH).contains(&options.strength) {
+        return Err(watermark_error(format!(
+            "strength {} is out of range, expected 1 to {}",
+            options.strength, MAX_STRENGTH
+        )));
+    }
+    let codeword = codeword(id)?;
+    let pattern = Pattern::new(
+        header.width,
+        header.height,
+        options.key.as_ref().unwrap_or(&DEFAULT_KEY),
+    )?;
+
+    let mut image = png.pixels(limits)?;
+    let channels = header.color_type.channels() as usize;
+    let colors = match header.color_type {
+        ColorType::Grayscale | ColorType::GrayscaleAlpha => 1,
+        _ => 3,
+    };
+    let max = (1i32 << header.bit_depth) - 1;
+    let strength = i32::from(options.strength) * (max / 255);
+    for (y, row) in image
+        .chunks_exact_mut(header.row_bytes() as usize)
+        .enumerate()
+    {
+        cancel.check()?;
+        for x in 0..header.width as usize {
+            let Some(cell) = pattern.cell(x, y) else {
+                continue;
+            };
+            let positive = pattern.chips[cell] == bit(&codeword, pattern.bits[cell] as usize);
+            let delta = if positive { strength } else { -strength };
+            for channel in 0..colors {
+                let index = x * channels + channel;
+                let value = i32::from(qr::sample(row, index, header.bit_depth)) + delta;
+                qr::set_sample(row, index, header.bit_depth, value.clamp(0, max) as u16);
+            }
+        }
+    }
+    let raw = interlace::filter_adaptive(&header, &interlace::interlace(&header, &image));
+    cancel.check()?;
+    png.set_image_data(&raw)
+}
+
+/// 检测 `embed` 写入的水印并读出标识。支持所有颜色类型和位深（包括重新保存时被转换为调色板的图片），
+/// 找不到水印或错误太多无法纠正时返回 `PngmeError::Watermark`
+pub fn detect(png: &Png, options: &WatermarkOptions, limits: &Limits) -> Result<Detection> {
+    decode(&soft_bits(png, options, limits)?)
+}
+
+/// 对软判决值进行判决和纠错，读出标识
+fn decode(soft: &[f64]) -> Result<Detection> {
+    let received: Vec<u8> = soft
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &z| acc << 1 | u8::from(z > 0.0)))
+        .collect();
+    let not_found = || watermark_error("no watermark found, or too many errors to correct");
+    let mut corrected = received.clone();
+    reed_solomon::correct(&mut corrected, EC_LEN).map_err(|_| not_found())?;
+    let len = corrected[0] as usize;
+    if !(1..=MAX_ID_LEN).contains(&len) || corrected[1 + len..=MAX_ID_LEN].iter().any(|&b| b != 0) {
+        return Err(not_found());
+    }
+    let id = String::from_utf8(corrected[1..=len].to_vec()).map_err(|_| not_found())?;
+    let corrected_bits = received
+        .iter()
+        .zip(&corrected)
+        .map(|(a, b)| (a ^ b).count_ones() as usize)
+        .sum();
+    Ok(Detection {
+        id,
+        corrected_bits,
+        score: score(soft, &corrected),
+    })
+}
+
+/// 依次计算图片中的水印与每个收件人的标识的相关性，按从高到低排列。与 `detect` 不同，
+/// 不要求纠错成功：失真严重到读不出标识时，真正的来源的相关性通常仍明显高于其他收件人。
+/// 不同标识的码字有相同的部分（长度字节、补零和部分纠错码字），水印与某个标识相近时，
+/// 该标识的相关性也会随之升高，所以能直接读出的标识不在列表中时返回错误，而不是给出一个相近的收件人
+pub fn trace(
+    png: &Png,
+    recipients: &[Recipient],
+    options: &WatermarkOptions,
+    limits: &Limits,
+) -> Result<Vec<TraceMatch>> {
+    let soft = soft_bits(png, options, limits)?;
+    if let Ok(detection) = decode(&soft) {
+        if !recipients
+            .iter()
+            .any(|recipient| recipient.id == detection.id)
+        {
+            return Err(watermark_error(format!(
+                "the image carries watermark {:?}, which is not in the recipients list",
+                detection.id
+            )));
+        }
+    }
+    let mut matches = recipients
+        .iter()
+        .map(|recipient| {
+            Ok(TraceMatch {
+                recipient: recipient.clone(),
+                score: score(&soft, &codeword(&recipient.id)?),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(matches)
+}
+
+/// 在 `trace` 的结果中认定来源：最高的相关性不低于 `MATCH_SCORE`，并且比第二高的至少高出 `MATCH_MARGIN`
+pub fn source(matches: &[TraceMatch]) -> Result<&TraceMatch> {
+    let best = matches
+        .first()
+        .ok_or_else(|| watermark_error("the recipients list is empty"))?;
+    if best.score < MATCH_SCORE {
+        return Err(watermark_error(format!(
+            "no recipient's watermark found (best score {:.1} for {}, at least {:.1} required)",
+            best.score, best.recipient, MATCH_SCORE
+        )));
+    }
+    if let Some(runner_up) = matches.get(1) {
+        if best.score - runner_up.score < MATCH_MARGIN {
+            return Err(watermark_error(format!(
+                "cannot tell {} (score {:.1}) and {} (score {:.1}) apart",
+                best.recipient, best.score, runner_up.recipient, runner_up.score
+            )));
+        }
+    }
+    Ok(best)
+}
+
+/// 解析收件人列表：以 `[` 开头时为 JSON 数组，元素是标识字符串或 `{"id": ..., "name": ...}` 对象；
+/// 否则为 CSV，每行为 `标识[,名称]`，第一行是 `id,name` 表头时会被跳过，空行会被忽略。
+/// 标识必须是 1 到 `MAX_ID_LEN` 字节且互不相同，列表不能为空
+pub fn parse_recipients(text: &str) -> Result<Vec<Recipient>> {
+    let recipients = if text.trim_start().starts_with('[') {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Id(String),
+            Recipient(Recipient),
+        }
+        serde_json::from_str::<Vec<Entry>>(text)
+            .map_err(|e| watermark_error(format!("invalid recipients JSON: {}", e)))?
+            .into_iter()
+            .map(|entry| match entry {
+                Entry::Id(id) => Recipient { id, name: None },
+                Entry::Recipient(recipient) => recipient,
+            })
+            .collect()
+    } else {
+        let mut recipients = Vec::new();
+        for (index, record) in metadata::parse_csv(text)?.into_iter().enumerate() {
+            let row = index + 1;
+            if record.iter().all(|field| field.trim().is_empty()) {
+                continue;
+            }
+            let (id, name) = match &record[..] {
+                [id] => (id.trim(), None),
+                [id, name] => (id.trim(), Some(name.trim())),
+                _ => {
+                    return Err(watermark_error(format!(
+                        "recipients row {}: expected 1 or 2 columns (id, name), found {}",
+                        row,
+                        record.len()
+                    )))
+                }
+            };
+            if row == 1 && id.eq_ignore_ascii_case("id") {
+                continue;
+            }
+            recipients.push(Recipient {
+                id: id.to_string(),
+                name: name.filter(|name| !name.is_empty()).map(str::to_string),
+            });
+        }
+        recipients
+    };
+    if recipients.is_empty() {
+        return Err(watermark_error("the recipients list is empty"));
+    }
+    let mut seen = HashSet::new();
+    for recipient in &recipients {
+        codeword(&recipient.id)?;
+        if !seen.insert(&recipient.id) {
+            return Err(watermark_error(format!(
+                "recipient id {:?} appears more than once",
+                recipient.id
+            )));
+        }
+    }
+    Ok(recipients)
+}
+
+/// 软判决值与码字的相关性，按位数归一化：没有水印或码字不符时近似服从标准正态分布
+fn score(soft: &[f64], codeword: &[u8]) -> f64 {
+    let sum: f64 = soft
+        .iter()
+        .enumerate()
+        .map(|(i, &z)| if bit(codeword, i) { z } else { -z })
+        .sum();
+    sum / (soft.len() as f64).sqrt()
+}
+
+/// 每一位的软判决值：该位的所有单元上去掉低频内容的亮度与符号的相关性，按能量归一化为 z 值
+fn soft_bits(png: &Png, options: &WatermarkOptions, limits: &Limits) -> Result<Vec<f64>> {
+    let header = png.header()?;
+    let pattern = Pattern::new(
+        header.width,
+        header.height,
+        options.key.as_ref().unwrap_or(&DEFAULT_KEY),
+    )?;
+    let luma = cell_luma(png, &header, &pattern, limits)?;
+    let (columns, rows) = (pattern.columns, pattern.rows);
+    let mut correlation = vec![0.0; CODEWORD_BITS];
+    let mut energy = vec![0.0; CODEWORD_BITS];
+    for row in 0..rows {
+        for column in 0..columns {
+            let cell = row * columns + column;
+            let neighbours = [
+                (column > 0).then(|| cell - 1),
+                (column + 1 < columns).then(|| cell + 1),
+                (row > 0).then(|| cell - columns),
+                (row + 1 < rows).then(|| cell + columns),
+            ];
+            let (sum, count) = neighbours
+                .iter()
+                .flatten()
+                .fold((0.0, 0.0), |(sum, count), &i| (sum + luma[i], count + 1.0));
+            let residual = luma[cell] - sum / count;
+            let bit = pattern.bits[cell] as usize;
+            correlation[bit] += if pattern.chips[cell] {
+                residual
+            } else {
+                -residual
+            };
+            energy[bit] += residual * residual;
+        }
+    }
+    Ok(correlation
+        .iter()
+        .zip(&energy)
+        .map(|(&c, &e)| if e > 0.0 { c / e.sqrt() } else { 0.0 })
+        .collect())
+}
+
+/// 每个单元中像素亮度（0 到 255）的和，不考虑 alpha
+fn cell_luma(png: &Png, header: &Ihdr, pattern: &Pattern, limits: &Limits) -> Result<Vec<f64>> {
+    let image = png.pixels(limits)?;
+    let channels = header.color_type.channels() as usize;
+    let scale = 255.0 / f64::from((1u32 << header.bit_depth) - 1);
+    let palette = png
+        .palette()?
+        .map(|palette| palette.entries().to_vec())
+        .unwrap_or_default();
+    let mut luma = vec![0.0; pattern.columns * pattern.rows];
+    for (y, row) in image.chunks_exact(header.row_bytes() as usize).enumerate() {
+        for x in 0..header.width as usize {
+            let Some(cell) = pattern.cell(x, y) else {
+                continue;
+            };
+            let value = |channel: usize| {
+                f64::from(qr::sample(row, x * channels + channel, header.bit_depth)) * scale
+            };
+            luma[cell] += match header.color_type {
+                ColorType::Grayscale | ColorType::GrayscaleAlpha => value(0),
+                ColorType::Rgb | ColorType::Rgba => {
+                    0.299 * value(0) + 0.587 * value(1) + 0.114 * value(2)
+                }
+                ColorType::Indexed => {
+                    let index = qr::sample(row, x, header.bit_depth) as usize;
+                    let [r, g, b] = palette.get(index).copied().unwrap_or_default();
+                    0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b)
+                }
+            };
+        }
+    }
+    Ok(luma)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::palette::Palette;
+    use crate::png::tests::testing_image;
+
+    /// 模拟照片的 8 位 RGB 图片：平滑的渐变、纹理和随机噪声
+    pub(crate) fn photo(width: u32, height: u32) -> Png {
+        let header = Ihdr::new(width, height, 8, ColorType::Rgb).unwrap();
+        let mut state = 0x9e37_79b9_u32;
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (f64::from(i % width), f64::from(i / width));
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = f64::from(state % 17) - 8.0;
+                let texture = 30.0 * (x / 7.0).sin() * (y / 11.0).cos();
+                [
+                    40.0 + x * 0.6 + texture + noise,
+                    90.0 + y * 0.4 - texture + noise,
+                    160.0 - x * 0.2 + noise,
+                ]
+                .map(|value| value.clamp(0.0, 255.0) as u8)
+            })
+            .collect();
+        testing_image(header, &pixels)
+    }
+
+    /// 对 8 位 RGB 图片的每个样本应用 `f`，得到同样尺寸的 8 位 RGB 图片
+    fn map_rgb(png: &Png, mut f: impl FnMut(usize, u8) -> u8) -> Png {
+        let pixels = png.pixels(&Limits::default()).unwrap();
+        let pixels: Vec<u8> = pixels.iter().enumerate().map(|(i, &v)| f(i, v)).collect();
+        testing_image(png.header().unwrap(), &pixels)
+    }
+
+    /// 把 8 位 RGB 图片转换为其他颜色类型和位深，模拟其他工具重新保存
+    fn convert(png: &Png, color_type: ColorType, bit_depth: u8) -> Png {
+        let header = png.header().unwrap();
+        let rgb = png.pixels(&Limits::default()).unwrap();
+        let mut pixels = Vec::new();
+        let mut palette = Vec::new();
+        for rgb in rgb.chunks_exact(3) {
+            let luma =
+                ((299 * u32::from(rgb[0]) + 587 * u32::from(rgb[1]) + 114 * u32::from(rgb[2]))
+                    / 1000) as u8;
+            let samples: Vec<u8> = match color_type {
+                ColorType::Grayscale => vec![luma],
+                ColorType::GrayscaleAlpha => vec![luma, 255],
+                ColorType::Rgb => rgb.to_vec(),
+                ColorType::Rgba => vec![rgb[0], rgb[1], rgb[2], 255],
+                ColorType::Indexed => {
+                    // 每个通道量化为 6 级（web 安全色），共 216 色
+                    let level = |v: u8| (u32::from(v) * 5 + 127) / 255;
+                    let index = level(rgb[0]) * 36 + level(rgb[1]) * 6 + level(rgb[2]);
+                    vec![index as u8]
+                }
+            };
+            for sample in samples {
+                match bit_depth {
+                    16 => pixels.extend([sample, sample]),
+                    _ => pixels.push(sample),
+                }
+            }
+        }
+        if color_type == ColorType::Indexed {
+            for index in 0..216u32 {
+                let level = |l: u32| (l * 51) as u8;
+                palette.push([level(index / 36), level(index / 6 % 6), level(index % 6)]);
+            }
+        }
+        let header = Ihdr::new(header.width, header.height, bit_depth, color_type).unwrap();
+        let mut png = testing_image(header, &pixels);
+        if !palette.is_empty() {
+            png.set_palette(&Palette::new(palette).unwrap()).unwrap();
+        }
+        png
+    }
+
+    fn marked(id: &str, options: &WatermarkOptions) -> Png {
+        let mut png = photo(256, 192);
+        embed(
+            &mut png,
+            id,
+            options,
+            &Limits::default(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_embed_and_detect() {
+        let limits = Limits::default();
+        let original = photo(256, 192);
+        let png = marked("leak-0042", &WatermarkOptions::default());
+        let detection = detect(&png, &WatermarkOptions::default(), &limits).unwrap();
+        assert_eq!(detection.id, "leak-0042");
+        assert!(detection.score > 20.0, "score {}", detection.score);
+
+        // 改动只是每个像素 ±3 的噪声（在 0 和 255 处截断）
+        let before = original.pixels(&limits).unwrap();
+        let after = png.pixels(&limits).unwrap();
+        assert!(before
+            .iter()
+            .zip(&after)
+            .all(|(&a, &b)| (i32::from(a) - i32::from(b)).abs() <= 3));
+
+        assert!(matches!(
+            detect(&original, &WatermarkOptions::default(), &limits),
+            Err(PngmeError::Watermark(_))
+        ));
+    }
+
+    #[test]
+    fn test_resave() {
+        let limits = Limits::default();
+        let options = WatermarkOptions::default();
+        let png = marked("r7", &options);
+        let mut state = 1u32;
+        let copies = [
+            convert(&png, ColorType::Grayscale, 8),
+            convert(&png, ColorType::GrayscaleAlpha, 16),
+            convert(&png, ColorType::Rgba, 8),
+            convert(&png, ColorType::Indexed, 8),
+            // 调亮
+            map_rgb(&png, |_, v| v.saturating_add(20)),
+            // 每个通道只保留 5 位
+            map_rgb(&png, |_, v| v & 0xf8),
+            // ±4 的随机噪声，近似轻度的有损压缩
+            map_rgb(&png, |_, v| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (i32::from(v) + (state >> 16) as i32 % 9 - 4).clamp(0, 255) as u8
+            }),
+        ];
+        for (i, copy) in copies.iter().enumerate() {
+            let detection =
+                detect(copy, &options, &limits).unwrap_or_else(|e| panic!("copy {}: {}", i, e));
+            assert_eq!(detection.id, "r7", "copy {}", i);
+        }
+    }
+
+    #[cfg(feature = "convert")]
+    #[test]
+    fn test_jpeg() {
+        use image::codecs::jpeg::JpegEncoder;
+
+        let png = marked("jpeg", &WatermarkOptions::default());
+        let header = png.header().unwrap();
+        let pixels = png.pixels(&Limits::default()).unwrap();
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, 85)
+            .encode(
+                &pixels,
+                header.width,
+                header.height,
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+        let resaved = Png::try_from(&crate::sniff::to_png(&jpeg).unwrap()[..]).unwrap();
+        let detection = detect(&resaved, &WatermarkOptions::default(), &Limits::default()).unwrap();
+        assert_eq!(detection.id, "jpeg");
+    }
+
+    #[test]
+    fn test_key() {
+        let limits = Limits::default();
+        let keyed = WatermarkOptions {
+            key: Some([7; 32]),
+            ..WatermarkOptions::default()
+        };
+        let png = marked("secret", &keyed);
+        assert_eq!(detect(&png, &keyed, &limits).unwrap().id, "secret");
+        assert!(matches!(
+            detect(&png, &WatermarkOptions::default(), &limits),
+            Err(PngmeError::Watermark(_))
+        ));
+    }
+
+    #[test]
+    fn test_trace() {
+        let limits = Limits::default();
+        let options = WatermarkOptions::default();
+        let recipients = parse_recipients("id,name\nr0001,Alice\nr0002,Bob\nr0003,\n").unwrap();
+        assert_eq!(recipients[2].to_string(), "r0003");
+        let png = marked("r0002", &options);
+        let matches = trace(&png, &recipients, &options, &limits).unwrap();
+        assert_eq!(matches[0].recipient.to_string(), "r0002 (Bob)");
+        assert!(matches[0].score >= MATCH_SCORE);
+        assert!(matches[0].score > 1.5 * matches[1].score);
+
+        assert_eq!(source(&matches).unwrap().recipient.id, "r0002");
+
+        // 噪声大到读不出标识时仍能找到来源
+        let weak = WatermarkOptions {
+            strength: 1,
+            ..options
+        };
+        let mut state = 7u32;
+        let noisy = map_rgb(&marked("r0002", &weak), |_, v| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (i32::from(v) + (state >> 16) as i32 % 121 - 60).clamp(0, 255) as u8
+        });
+        assert!(detect(&noisy, &options, &limits).is_err());
+        let matches = trace(&noisy, &recipients, &options, &limits).unwrap();
+        assert_eq!(source(&matches).unwrap().recipient.id, "r0002");
+
+        // 来源不在列表中时不会认定为标识相近的收件人
+        let outsider = marked("r0004", &options);
+        let error = trace(&outsider, &recipients, &options, &limits).unwrap_err();
+        assert!(error.to_string().contains("\"r0004\""));
+
+        let unmarked = photo(256, 192);
+        let matches = trace(&unmarked, &recipients, &options, &limits).unwrap();
+        assert!(matches[0].score < MATCH_SCORE);
+        assert!(source(&matches).is_err());
+        assert!(source(&[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_recipients() {
+        let json = r#"["a1", {"id": "b2", "name": "Bob"}]"#;
+        assert_eq!(
+            parse_recipients(json).unwrap(),
+            [
+                Recipient {
+                    id: "a1".to_string(),
+                    name: None
+                },
+                Recipient {
+                    id: "b2".to_string(),
+                    name: Some("Bob".to_string())
+                },
+            ]
+        );
+        assert_eq!(
+            parse_recipients("\"c3\",\"Carol, Jr.\"\n").unwrap()[0]
+                .name
+                .as_deref(),
+            Some("Carol, Jr.")
+        );
+        for invalid in [
+            "",
+            "id,name\n",
+            "a1\na1\n",
+            "a1,Alice,extra\n",
+            "much-too-long-id\n",
+            "[1, 2]",
+        ] {
+            assert!(
+                matches!(parse_recipients(invalid), Err(PngmeError::Watermark(_))),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let limits = Limits::default();
+        let cancel = CancelToken::new();
+        let options = WatermarkOptions::default();
+        let mut png = photo(256, 192);
+        for id in ["", "thirteen byte"] {
+            assert!(matches!(
+                embed(&mut png, id, &options, &limits, &cancel),
+                Err(PngmeError::Watermark(_))
+            ));
+        }
+        let weak = WatermarkOptions {
+            strength: 0,
+            ..options
+        };
+        assert!(embed(&mut png, "a", &weak, &limits, &cancel).is_err());
+
+        let mut small = photo(100, 100);
+        let error = embed(&mut small, "a", &options, &limits, &cancel).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Watermark error: the 100x100 image is too small, a watermark needs at least 12800 pixels"
+        );
+        let mut indexed = convert(&png, ColorType::Indexed, 8);
+        assert!(embed(&mut indexed, "a", &options, &limits, &cancel).is_err());
+
+        cancel.cancel();
+        assert!(matches!(
+            embed(&mut png, "a", &options, &limits, &cancel),
+            Err(PngmeError::Cancelled)
+        ));
+    }
+}
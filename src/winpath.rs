@@ -0,0 +1,133 @@
+//! # Winpath
+//! 作者：Norfloxaciner <1762161822@qq.com>
+//! 创建/修改日期：2026/10/16
+//!
+//! 该模块处理 Windows 路径的两个限制：
+//!
+//! - 普通路径最长约 260 个字符，更长的路径必须写成 `\\?\C:\...` 或 `\\?\UNC\server\share\...`
+//!   形式的扩展长度路径。这种路径不经过 Win32 的规范化，必须是绝对路径，
+//!   分隔符只能是 `\`，也不能含有 `.` 和 `..`，所以转换时要先做这些处理；
+//! - `CON`、`NUL`、`COM1` 等设备名即使带扩展名（`nul.png`）也指向设备而不是文件。
+//!
+//! 转换本身是纯字符串操作，在所有平台上都可以测试；`long_path` 只在 Windows 上生效。
+
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Windows 保留的设备名，不区分大小写
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 超过这个长度的路径转换为扩展长度路径。`MAX_PATH` 是 260，但创建目录时还要给文件名
+/// 留出 8.3 格式的 12 个字符，所以取 248
+#[cfg(windows)]
+const MAX_PATH: usize = 248;
+
+/// 文件名（第一个 `.` 之前的部分，忽略结尾的空格）是否为 Windows 保留的设备名
+pub fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+/// 把绝对 Windows 路径转换为扩展长度路径：`/` 换成 `\`，去掉空分量和 `.`，按字面处理 `..`。
+/// 已经是 `\\?\` 或 `\\.\` 形式，或者不是绝对路径（包括 `C:foo` 这样相对于驱动器的路径）时返回 `None`
+pub fn extended_length(path: &str) -> Option<String> {
+    let path = path.replace('/', "\\");
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let (prefix, rest, root_len) = if let Some(unc) = path.strip_prefix(r"\\") {
+        // UNC 路径的前两个分量（服务器和共享名）是根，`..` 不能越过它们
+        (r"\\?\UNC".to_string(), unc.to_string(), 2)
+    } else {
+        let bytes = path.as_bytes();
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != b":\\" {
+            return None;
+        }
+        (format!(r"\\?\{}", &path[..2]), path[3..].to_string(), 0)
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.len() > root_len {
+                    components.pop();
+                }
+            }
+            _ => components.push(component),
+        }
+    }
+    if components.len() < root_len {
+        return None;
+    }
+    Some(format!("{}\\{}", prefix, components.join("\\")))
+}
+
+/// 在 Windows 上把过长的路径转换为扩展长度路径（相对路径先转换为绝对路径），
+/// 其他情况原样返回
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        if path.as_os_str().len() >= MAX_PATH {
+            let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+            if let Some(extended) = absolute.to_str().and_then(extended_length) {
+                return Cow::Owned(std::path::PathBuf::from(extended));
+            }
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_reserved_name() {
+        assert!(is_reserved_name("CON"));
+        assert!(is_reserved_name("nul.png"));
+        assert!(is_reserved_name("com1.tar.gz"));
+        assert!(is_reserved_name("Aux .txt"));
+        assert!(!is_reserved_name("console.png"));
+        assert!(!is_reserved_name("com10"));
+        assert!(!is_reserved_name(""));
+    }
+
+    #[test]
+    fn test_extended_length() {
+        assert_eq!(
+            extended_length(r"C:\art\..\assets\.\icons/a.png").as_deref(),
+            Some(r"\\?\C:\assets\icons\a.png")
+        );
+        assert_eq!(
+            extended_length(r"c:\..\a.png").as_deref(),
+            Some(r"\\?\c:\a.png")
+        );
+        assert_eq!(
+            extended_length(r"\\server\share\..\dir\a.png").as_deref(),
+            Some(r"\\?\UNC\server\share\dir\a.png")
+        );
+        assert_eq!(extended_length(r"\\server"), None);
+        assert_eq!(extended_length(r"\\?\C:\a.png"), None);
+        assert_eq!(extended_length(r"\\.\pipe\name"), None);
+        assert_eq!(extended_length(r"C:relative\a.png"), None);
+        assert_eq!(extended_length(r"assets\a.png"), None);
+    }
+
+    #[test]
+    fn test_long_path() {
+        let short = Path::new("assets/a.png");
+        assert_eq!(long_path(short), short);
+        #[cfg(not(windows))]
+        {
+            let deep = "d/".repeat(200) + "a.png";
+            assert_eq!(long_path(Path::new(&deep)), Path::new(&deep));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! 命令行程序的端到端测试：运行编译好的 `pngme`，检查全局 `--json` 的输出和退出码
+
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use clap::CommandFactory;
+use pngme::args::Cli;
+use pngme::png::Png;
+use serde_json::Value;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pngme-cli-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pngme(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// stdout 上应该只有一个 JSON 对象
+fn json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_json_error_for_mutating_command() {
+    let dir = temp_dir("error");
+    fs::write(dir.join("notes.txt"), "not a png").unwrap();
+
+    for (command, args) in [
+        ("encode", ["--json", "encode", "notes.txt", "ruSt", "hello"]),
+        (
+            "remove",
+            ["remove", "notes.txt", "ruSt", "--json", "--dry-run"],
+        ),
+    ] {
+        let output = pngme(&dir, &args);
+        assert_eq!(output.status.code(), Some(5));
+        let error = json(&output);
+        assert_eq!(error["ok"], false);
+        assert_eq!(error["command"], command);
+        assert_eq!(error["kind"], "invalid-signature");
+        assert_eq!(error["exit_code"], 5);
+    }
+    assert_eq!(fs::read(dir.join("notes.txt")).unwrap(), b"not a png");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json_usage_error() {
+    let dir = temp_dir("usage");
+    for (command, args) in [
+        ("decode", &["--json", "decode", "a.png"][..]),
+        ("", &["--json", "explode"][..]),
+        ("meta list", &["meta", "list", "--json", "--bogus"][..]),
+    ] {
+        let output = pngme(&dir, args);
+        assert_eq!(output.status.code(), Some(2));
+        let error = json(&output);
+        assert_eq!(error["ok"], false);
+        assert_eq!(error["command"], command);
+        assert_eq!(error["kind"], "usage");
+        assert_eq!(error["exit_code"], 2);
+        assert!(!error["error"].as_str().unwrap().is_empty());
+    }
+
+    // 没有 --json 时仍由 clap 打印用法说明
+    let output = pngme(&dir, &["decode", "a.png"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json_success() {
+    let dir = temp_dir("success");
+    fs::write(dir.join("a.png"), Png::from_chunks(Vec::new()).as_bytes()).unwrap();
+
+    let output = pngme(&dir, &["--json", "encode", "a.png", "ruSt", "hello"]);
+    assert!(output.status.success());
+    assert_eq!(
+        json(&output),
+        serde_json::json!({ "ok": true, "command": "encode" })
+    );
+
+    let output = pngme(&dir, &["decode", "a.png", "ruSt", "--json"]);
+    assert_eq!(json(&output)["result"], "hello");
+    let output = pngme(&dir, &["--json", "meta", "list", "a.png"]);
+    assert_eq!(json(&output)["command"], "meta list");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_command_definitions() {
+    // 参数之间的引用（conflicts_with、requires 等）都指向存在的参数，否则 clap 在 debug 构建中解析到该子命令时 panic
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn test_json_option_conflict() {
+    let dir = temp_dir("conflict");
+    fs::write(dir.join("a.png"), Png::from_chunks(Vec::new()).as_bytes()).unwrap();
+
+    for (command, args) in [
+        (
+            "decode",
+            &["--json", "decode", "a.png", "ruSt", "--region", "0,0,1,1"][..],
+        ),
+        (
+            "encode",
+            &[
+                "--json", "encode", "a.png", "ruSt", "hi", "-", "--method", "trailer",
+            ][..],
+        ),
+        (
+            "encode",
+            &[
+                "--json", "encode", "-", "ruSt", "hi", "out.png", "--method", "lsb",
+            ][..],
+        ),
+        (
+            "watermark embed",
+            &["--json", "watermark", "embed", "a.png", "id", "--out", "-"][..],
+        ),
+        (
+            "encode",
+            &[
+                "--json",
+                "encode",
+                "a.png",
+                "ruSt",
+                "hi",
+                "-",
+                "--visible-label",
+                "A",
+            ][..],
+        ),
+        (
+            "encode",
+            &["--json", "encode", "-", "ruSt", "hi", "out.png", "--assess"][..],
+        ),
+    ] {
+        let output = pngme(&dir, args);
+        assert_eq!(output.status.code(), Some(2));
+        let error = json(&output);
+        assert_eq!(error["command"], command);
+        assert_eq!(error["kind"], "usage");
+        assert_eq!(error["exit_code"], 2);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// 没有任何 chunk 的 PNG，写入一条 Title 文本
+fn png_with_title(name: &str) -> PathBuf {
+    let dir = temp_dir(name);
+    fs::write(dir.join("a.png"), Png::from_chunks(Vec::new()).as_bytes()).unwrap();
+    let output = pngme(&dir, &["meta", "set", "a.png", "Title", "Dice"]);
+    assert!(output.status.success());
+    dir
+}
+
+#[test]
+fn test_json_meta_get() {
+    let dir = png_with_title("meta-get");
+    let output = pngme(&dir, &["--json", "meta", "get", "a.png", "Title"]);
+    assert_eq!(json(&output)["result"], "Dice");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json_capacity() {
+    let dir = png_with_title("capacity");
+    let output = pngme(&dir, &["--json", "capacity", "a.png"]);
+    let result = &json(&output)["result"];
+    assert_eq!(result["chunks"], 2147483647);
+    // 没有 IHDR 时无法估算 LSB 容量
+    assert!(result["lsb"].is_null());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json_print() {
+    let dir = png_with_title("print");
+    let output = pngme(&dir, &["--json", "print", "a.png"]);
+    assert_eq!(json(&output)["result"][0]["chunk_type"], "tEXt");
+
+    // 十六进制视图也在同一个 JSON 对象中
+    let output = pngme(&dir, &["--json", "print", "a.png", "--hex", "tEXt"]);
+    let result = &json(&output)["result"];
+    assert_eq!(result[0][0]["chunk_type"], "tEXt");
+    assert_eq!(result[1][0]["index"], 0);
+    assert!(result[1][0]["hexdump"]
+        .as_str()
+        .unwrap()
+        .starts_with("00000000"));
+    let output = pngme(&dir, &["--json", "scan", "a.png", "--hex", "tEXt"]);
+    assert_eq!(json(&output)["result"][1][0]["chunk_type"], "tEXt");
+    fs::remove_dir_all(dir).unwrap();
+}